@@ -0,0 +1,80 @@
+//! Compares day 18 b's two ways of finding the droplet's outside surface on the puzzle input in
+//! `./input/`: flood filling the vacuum around it, and walking over its faces.
+//!
+//! Run with `cargo bench --bench day18`. Nothing is timed if there's no input file for day 18 b.
+//! Before timing them, how much each one has to keep track of is printed: every cell of vacuum for
+//! the flood fill, and every face it walks over for the surface walk.
+
+use std::{mem::size_of, path::Path};
+
+use aoc2022::{
+    challenge::{self, ChallengeNumber, InputChecks, Subchallenge},
+    solver::{Solver, SolverConfig},
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const CHALLENGE: ChallengeNumber = 18;
+
+/// Each algorithm, with the metadata that says how much it kept track of and how big each of
+/// those things is: a cell's position for the flood fill, and a face (its cube and which way it
+/// faces) along with the surface it's part of for the surface walk.
+const ALGORITHMS: [(&str, &str, usize); 2] = [
+    ("flood", "cells_filled", size_of::<[i32; 3]>()),
+    ("surface", "faces_walked", size_of::<([i32; 6], usize)>()),
+];
+
+fn exterior_surface(c: &mut Criterion) {
+    let open = || {
+        challenge::get_challenge_input(
+            Path::new(challenge::INPUT_DIR),
+            CHALLENGE,
+            Subchallenge::B,
+            &None,
+            InputChecks::default(),
+        )
+    };
+    if let Err(e) = open() {
+        eprintln!("Skipping day {CHALLENGE} b, no input file: {e}");
+        return;
+    }
+
+    let mut solver = Solver::new();
+    let configs = ALGORITHMS.map(|(algorithm, _, _)| {
+        let mut config = SolverConfig::default();
+        config.params.insert("algorithm", algorithm);
+        config
+    });
+
+    println!("{:<10}{:>10}{:>14}", "algorithm", "kept", "about");
+    for ((algorithm, metadata, size), config) in ALGORITHMS.iter().zip(&configs) {
+        let answer = {
+            let _quiet = gag::Gag::stdout().ok();
+            solver
+                .solve(CHALLENGE, Subchallenge::B, open().unwrap(), config)
+                .unwrap()
+        };
+        let kept = answer.metadata()[*metadata].as_u64().unwrap() as usize;
+        println!("{algorithm:<10}{kept:>10}{:>12} B", kept * size);
+    }
+
+    let mut group = c.benchmark_group(format!("day {CHALLENGE} b"));
+    for ((algorithm, _, _), config) in ALGORITHMS.iter().zip(&configs) {
+        group.bench_function(BenchmarkId::from_parameter(algorithm), |b| {
+            b.iter_batched(
+                || open().unwrap(),
+                |input| {
+                    // The solver prints its answer every time, which would drown out criterion.
+                    let _quiet = gag::Gag::stdout().ok();
+                    solver
+                        .solve(CHALLENGE, Subchallenge::B, input, config)
+                        .unwrap()
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, exterior_surface);
+criterion_main!(benches);
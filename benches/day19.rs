@@ -0,0 +1,53 @@
+//! Times the day 19 geode search on the puzzle input in `./input/`.
+//!
+//! Run with `cargo bench --bench day19`. Nothing is timed if there's no input file for day 19. To
+//! compare two versions of the search, run `cargo bench --bench day19 -- --save-baseline before`
+//! on the first, then `cargo bench --bench day19 -- --baseline before` on the second.
+
+use std::path::Path;
+
+use aoc2022::{
+    challenge::{self, ChallengeNumber, InputChecks, Subchallenge},
+    solver::{Solver, SolverConfig},
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const CHALLENGE: ChallengeNumber = 19;
+
+fn geode_search(c: &mut Criterion) {
+    for subchallenge in [Subchallenge::A, Subchallenge::B] {
+        let open = || {
+            challenge::get_challenge_input(
+                Path::new(challenge::INPUT_DIR),
+                CHALLENGE,
+                subchallenge,
+                &None,
+                InputChecks::default(),
+            )
+        };
+        if let Err(e) = open() {
+            eprintln!("Skipping day {CHALLENGE} {subchallenge}, no input file: {e}");
+            continue;
+        }
+
+        let mut solver = Solver::new();
+        let config = SolverConfig::default();
+
+        c.bench_function(&format!("day {CHALLENGE} {subchallenge}"), |b| {
+            b.iter_batched(
+                || open().unwrap(),
+                |input| {
+                    // The solver prints its answer every time, which would drown out criterion.
+                    let _quiet = gag::Gag::stdout().ok();
+                    solver
+                        .solve(CHALLENGE, subchallenge, input, &config)
+                        .unwrap()
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, geode_search);
+criterion_main!(benches);
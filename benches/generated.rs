@@ -0,0 +1,58 @@
+//! Times the solvers that can make up their own inputs on bigger and bigger ones, to see how they
+//! scale past the size of the real puzzle inputs.
+//!
+//! Run with `cargo bench --bench generated`. Throughput is reported in moves, numbers or
+//! characters per second, so a solver that scales linearly keeps the same throughput at every
+//! size. The inputs are made up with a fixed seed, so every run times the same ones.
+
+use std::io::Cursor;
+
+use aoc2022::{
+    challenge::{ChallengeNumber, Subchallenge},
+    solver::{Solver, SolverConfig},
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+const SEED: u64 = 2022;
+
+/// Each challenge, with the sizes of input to time it on.
+const SIZES: [(ChallengeNumber, &[usize]); 3] = [
+    (6, &[10_000, 100_000, 1_000_000]),
+    (9, &[2_000, 20_000, 200_000]),
+    // Mixing takes more than linear time, so this stops sooner.
+    (20, &[1_000, 5_000, 25_000]),
+];
+
+fn generated(c: &mut Criterion) {
+    let mut solver = Solver::new();
+    let config = SolverConfig::default();
+
+    for (challenge, sizes) in SIZES {
+        for subchallenge in [Subchallenge::A, Subchallenge::B] {
+            let mut group = c.benchmark_group(format!("day {challenge} {subchallenge}"));
+            group.sample_size(10);
+            for &size in sizes {
+                let (input, _) = solver.generate(challenge, size, Some(SEED)).unwrap();
+                group.throughput(Throughput::Elements(size as u64));
+                group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+                    b.iter_batched(
+                        || Box::new(Cursor::new(input.clone())),
+                        |input| {
+                            // The solvers print their answers every time, which would drown out
+                            // criterion.
+                            let _quiet = gag::Gag::stdout().ok();
+                            solver
+                                .solve(challenge, subchallenge, input, &config)
+                                .unwrap()
+                        },
+                        BatchSize::LargeInput,
+                    )
+                });
+            }
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, generated);
+criterion_main!(benches);
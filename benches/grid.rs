@@ -0,0 +1,90 @@
+//! Compares [`BitGrid`] against a plain `Grid<bool>`.
+//!
+//! Run with `cargo bench --bench grid`. The memory used by each grid is printed before the timing
+//! benchmarks start.
+
+use aoc2022::grid::{BitGrid, Grid, GridCoord};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Grid sizes to compare. 1000x1000 is about the size of day 14's grid with a floor.
+const SIZES: [usize; 3] = [64, 400, 1000];
+
+/// A deterministic, roughly one-in-three scattering of cells to mark.
+fn marked(size: usize) -> impl Iterator<Item = GridCoord> {
+    (0..size * size)
+        .filter(|i| (i * 2_654_435_761) % 3 == 0)
+        .map(move |i| GridCoord::from((i % size, i / size)))
+}
+
+fn report_memory() {
+    println!("{:>6} {:>14} {:>14}", "size", "Grid<bool>", "BitGrid");
+    for size in SIZES {
+        let bools = Grid::<bool>::new(size, size);
+        let bits = BitGrid::new(size, size);
+        println!(
+            "{:>6} {:>12} B {:>12} B",
+            format!("{size}²"),
+            bools.heap_size(),
+            bits.heap_size()
+        );
+    }
+}
+
+fn fill(c: &mut Criterion) {
+    report_memory();
+
+    let mut group = c.benchmark_group("fill");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::new("Grid<bool>", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut grid = Grid::<bool>::new(size, size);
+                for coord in marked(size) {
+                    *grid.cell_mut(coord).unwrap() = true;
+                }
+                grid
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("BitGrid", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut grid = BitGrid::new(size, size);
+                for coord in marked(size) {
+                    grid.set(coord, true);
+                }
+                grid
+            })
+        });
+    }
+    group.finish();
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    for size in SIZES {
+        let mut bools = Grid::<bool>::new(size, size);
+        for coord in marked(size) {
+            *bools.cell_mut(coord).unwrap() = true;
+        }
+        let bits = BitGrid::from(&bools);
+
+        group.bench_with_input(BenchmarkId::new("Grid<bool>", size), &bools, |b, grid| {
+            b.iter(|| {
+                (0..size)
+                    .flat_map(|y| (0..size).map(move |x| GridCoord::from((x, y))))
+                    .filter(|&coord| *black_box(grid).cell(coord).unwrap())
+                    .count()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("BitGrid", size), &bits, |b, grid| {
+            b.iter(|| {
+                (0..size)
+                    .flat_map(|y| (0..size).map(move |x| GridCoord::from((x, y))))
+                    .filter(|&coord| black_box(grid).cell(coord).unwrap())
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fill, scan);
+criterion_main!(benches);
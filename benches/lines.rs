@@ -0,0 +1,153 @@
+//! Compares reading inputs line by line with [`BufRead::lines`] against
+//! [`input_iter::for_each_line`], and times the solvers that use the latter (days 2, 3, 4, 6, 18
+//! and 20) on big synthetic inputs.
+//!
+//! Run with `cargo bench --bench lines`. Throughput is reported in bytes of input per second.
+
+use std::io::{BufRead, Cursor};
+
+use aoc2022::{
+    challenge::Subchallenge,
+    input_iter,
+    solver::{Solver, SolverConfig},
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+/// How many lines to put in the synthetic inputs. A multiple of three, for day 3's groups.
+const LINES: usize = 300_000;
+
+/// A deterministic pseudo-random letter for each index.
+fn letter(i: usize) -> char {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    char::from(LETTERS[(i * 2_654_435_761) % LETTERS.len()])
+}
+
+/// Rucksacks for day 3, between 16 and 48 items each.
+fn rucksacks() -> String {
+    let mut input = String::new();
+    for line in 0..LINES {
+        let len = 16 + (line * 7) % 17 * 2;
+        input.extend((0..len).map(|i| letter(line * 64 + i)));
+        input.push('\n');
+    }
+    input
+}
+
+/// A signal for day 6 that repeats three characters until the very end, where both markers are.
+fn signal() -> String {
+    let mut input = "abc".repeat(LINES * 10);
+    input.push_str("defghijklmnopq\n");
+    input
+}
+
+/// A strategy guide for day 2, going through every pair of columns.
+fn guide() -> String {
+    (0..LINES)
+        .map(|i| {
+            format!(
+                "{} {}\n",
+                ["A", "B", "C"][i % 3],
+                ["X", "Y", "Z"][i / 3 % 3]
+            )
+        })
+        .collect()
+}
+
+/// Pairs of section assignments for day 4, some of them overlapping and some not.
+fn assignments() -> String {
+    (0..LINES)
+        .map(|i| {
+            let (a, b) = (i % 89, i % 97);
+            format!("{a}-{},{b}-{}\n", a + i % 7, b + i % 11)
+        })
+        .collect()
+}
+
+/// Cubes for day 18, scattered around a 40 cube wide space. Only a tenth as many as the other
+/// days get, since the space fills up long before that.
+fn cubes() -> String {
+    (0..LINES / 10)
+        .map(|i| {
+            let i = i * 2_654_435_761;
+            format!("{},{},{}\n", i % 40, i / 40 % 40, i / 1600 % 40)
+        })
+        .collect()
+}
+
+/// An encrypted file for day 20, with a zero in the middle. Mixing takes a lot longer per number
+/// than the other days take per line, so it only gets a hundredth as many.
+fn numbers() -> String {
+    (0..LINES / 100)
+        .map(|i| {
+            let n = (i * 2_654_435_761 % 20_001) as i64 - 10_000;
+            let n = if i == LINES / 200 { 0 } else { n };
+            format!("{n}\n")
+        })
+        .collect()
+}
+
+fn reading(c: &mut Criterion) {
+    let input = rucksacks();
+    let mut group = c.benchmark_group("read lines");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+
+    group.bench_function("BufRead::lines", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            for line in Cursor::new(&input).lines() {
+                total += black_box(line.unwrap()).len();
+            }
+            total
+        })
+    });
+    group.bench_function("for_each_line", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            input_iter::for_each_line(&mut Cursor::new(&input), |line| {
+                total += black_box(line).len();
+                Ok::<_, std::io::Error>(())
+            })
+            .unwrap();
+            total
+        })
+    });
+    group.finish();
+}
+
+fn solving(c: &mut Criterion) {
+    let mut solver = Solver::new();
+    let config = SolverConfig::default();
+
+    let inputs = [
+        (2, guide()),
+        (3, rucksacks()),
+        (4, assignments()),
+        (6, signal()),
+        (18, cubes()),
+        (20, numbers()),
+    ];
+    for (challenge, input) in inputs {
+        let mut group = c.benchmark_group(format!("day {challenge}"));
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        for subchallenge in [Subchallenge::A, Subchallenge::B] {
+            group.bench_function(subchallenge.as_str(), |b| {
+                b.iter_batched(
+                    || Box::new(Cursor::new(input.clone())),
+                    |input| {
+                        // The solvers print their answers every time, which would drown out
+                        // criterion.
+                        let _quiet = gag::Gag::stdout().ok();
+                        solver
+                            .solve(challenge, subchallenge, input, &config)
+                            .unwrap()
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, reading, solving);
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+//! A plugin with another solver for challenge 1, which sorts every elf's calories instead of
+//! keeping track of the top three.
+//!
+//! Plugins don't need this crate at all, just the handful of declarations below, copied from
+//! `aoc2022::solver::PluginSolver` and friends. Build it and use it with:
+//!
+//! ```text
+//! cargo build --example plugin
+//! cargo run --features plugins -- --plugin target/debug/examples/libplugin.so --solver plugin 1 b
+//! ```
+
+use std::ffi::{c_char, c_int, c_void, CString};
+
+const PLUGIN_ABI_VERSION: u32 = 2;
+
+pub type RegisterFn = unsafe extern "C" fn(registrar: *mut c_void, solver: PluginSolver);
+
+#[repr(C)]
+pub struct PluginSolver {
+    pub abi_version: u32,
+    pub challenge: u8,
+    pub solve: unsafe extern "C" fn(u8, *const u8, usize, *mut *mut c_char) -> c_int,
+    pub free: unsafe extern "C" fn(*mut c_char),
+}
+
+/// Which version of the plugin interface this was built against, checked before anything else.
+#[no_mangle]
+pub extern "C" fn plugin_abi_version() -> u32 {
+    PLUGIN_ABI_VERSION
+}
+
+/// # Safety
+///
+/// Only to be called by `aoc2022 --plugin`.
+#[no_mangle]
+pub unsafe extern "C" fn register_solvers(registrar: *mut c_void, register: RegisterFn) {
+    let solver = PluginSolver {
+        abi_version: PLUGIN_ABI_VERSION,
+        challenge: 1,
+        solve,
+        free,
+    };
+    unsafe { register(registrar, solver) };
+}
+
+unsafe extern "C" fn solve(
+    subchallenge: u8,
+    input: *const u8,
+    input_len: usize,
+    output: *mut *mut c_char,
+) -> c_int {
+    let input = unsafe { std::slice::from_raw_parts(input, input_len) };
+    let top = if subchallenge == b'a' { 1 } else { 3 };
+    let (status, text) = match std::str::from_utf8(input).map_err(|e| e.to_string()) {
+        Ok(input) => match most_calories(input, top) {
+            Ok(calories) => (0, calories.to_string()),
+            Err(e) => (1, e),
+        },
+        Err(e) => (1, e),
+    };
+    // Neither the answer nor an error message ever has a NUL in it.
+    unsafe { *output = CString::new(text).unwrap_or_default().into_raw() };
+    status
+}
+
+unsafe extern "C" fn free(string: *mut c_char) {
+    drop(unsafe { CString::from_raw(string) });
+}
+
+/// How many calories the `top` elves carrying the most have between them.
+fn most_calories(input: &str, top: usize) -> Result<u64, String> {
+    let mut elves = input
+        .split("\n\n")
+        .map(|elf| {
+            elf.lines()
+                .map(|line| {
+                    line.trim()
+                        .parse::<u64>()
+                        .map_err(|e| format!("`{line}`: {e}"))
+                })
+                .sum::<Result<u64, String>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    elves.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(elves.iter().take(top).sum())
+}
@@ -0,0 +1,9 @@
+#![no_main]
+
+use aoc2022::solver::Solver;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    // Malformed input should always come back as an error, never a panic.
+    let _ = Solver::new().validate_str(11, input);
+});
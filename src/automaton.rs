@@ -0,0 +1,456 @@
+//! A sparse cellular automaton engine.
+//!
+//! Live cells are stored in a [`HashSet`], so the world can grow in any direction without
+//! having to size a grid up front. Each round, every live cell looks at which of its eight
+//! neighbors are occupied (a [`NeighborMask`]) and its [`Rule`] decides whether it stays, moves,
+//! or dies. Moves that collide are sorted out by the rule's conflict resolution, and rules can
+//! optionally bring empty cells to life, Game-of-Life style.
+//!
+//! Built with day 23's elf diffusion in mind.
+
+// Groundwork for day 23: nothing outside of the tests drives an automaton yet.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::json;
+
+use crate::simulation::{Simulation, StepResult};
+
+/// A cell's coordinates, as `(x, y)`.
+///
+/// `x` grows eastwards and `y` grows southwards, matching how puzzle inputs are laid out.
+pub(crate) type Cell = (i64, i64);
+
+/// The offset to each neighbor, indexed by its bit in a [`NeighborMask`].
+const NEIGHBOR_OFFSETS: [Cell; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A set of a cell's eight neighbors, one bit each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) struct NeighborMask(u8);
+
+impl NeighborMask {
+    pub(crate) const NONE: Self = Self(0);
+    pub(crate) const ALL: Self = Self(u8::MAX);
+
+    pub(crate) const NW: Self = Self(1 << 0);
+    pub(crate) const N: Self = Self(1 << 1);
+    pub(crate) const NE: Self = Self(1 << 2);
+    pub(crate) const W: Self = Self(1 << 3);
+    pub(crate) const E: Self = Self(1 << 4);
+    pub(crate) const SW: Self = Self(1 << 5);
+    pub(crate) const S: Self = Self(1 << 6);
+    pub(crate) const SE: Self = Self(1 << 7);
+
+    /// The three neighbors on the northern side.
+    pub(crate) const NORTH_SIDE: Self = Self::NW.union(Self::N).union(Self::NE);
+    /// The three neighbors on the southern side.
+    pub(crate) const SOUTH_SIDE: Self = Self::SW.union(Self::S).union(Self::SE);
+    /// The three neighbors on the western side.
+    pub(crate) const WEST_SIDE: Self = Self::NW.union(Self::W).union(Self::SW);
+    /// The three neighbors on the eastern side.
+    pub(crate) const EAST_SIDE: Self = Self::NE.union(Self::E).union(Self::SE);
+
+    /// Every neighbor that's in either mask.
+    pub(crate) const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Whether the two masks have any neighbors in common.
+    pub(crate) const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Whether every neighbor in `other` is also in this mask.
+    pub(crate) const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub(crate) const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// How many neighbors are in the mask.
+    pub(crate) const fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// The cell that a single-neighbor mask (like [`NeighborMask::N`]) points to from `cell`.
+    ///
+    /// Returns `None` if the mask doesn't contain exactly one neighbor.
+    pub(crate) fn offset_from(self, (x, y): Cell) -> Option<Cell> {
+        if self.count() != 1 {
+            return None;
+        }
+        let (dx, dy) = NEIGHBOR_OFFSETS[self.0.trailing_zeros() as usize];
+        Some((x + dx, y + dy))
+    }
+
+    /// Find which of `cell`'s neighbors are live.
+    fn of(cells: &HashSet<Cell>, (x, y): Cell) -> Self {
+        NEIGHBOR_OFFSETS
+            .iter()
+            .enumerate()
+            .filter(|(_, (dx, dy))| cells.contains(&(x + dx, y + dy)))
+            .fold(Self::NONE, |mask, (bit, _)| Self(mask.0 | 1 << bit))
+    }
+}
+
+/// What a live cell wants to do this round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Proposal {
+    /// Stay where it is.
+    Stay,
+    /// Move to another cell, if [`Rule::resolve`] lets it.
+    Move(Cell),
+    /// Stop being live.
+    Die,
+}
+
+/// The rules that drive an [`Automaton`].
+pub(crate) trait Rule {
+    /// Whether [`Rule::born`] should be consulted at all. Working out the neighbors of every
+    /// empty cell is wasted effort for rules that only ever move cells around.
+    const HAS_BIRTHS: bool = false;
+
+    /// Decide what the live cell at `cell` does during round `round` (counting from 0).
+    fn propose(&self, cell: Cell, neighbors: NeighborMask, round: usize) -> Proposal;
+
+    /// Pick which of the `contenders` (if any) gets to move into `target`. Everybody else stays
+    /// put. The winner is still blocked if `target` ends up occupied by a cell that didn't move.
+    ///
+    /// By default, a move only happens if nobody else wanted the same cell.
+    fn resolve(&self, _target: Cell, contenders: &[Cell]) -> Option<Cell> {
+        match contenders {
+            [only] => Some(*only),
+            _ => None,
+        }
+    }
+
+    /// Whether the empty cell at `cell` comes to life during round `round`.
+    ///
+    /// Only called when [`Rule::HAS_BIRTHS`] is `true`.
+    fn born(&self, _cell: Cell, _neighbors: NeighborMask, _round: usize) -> bool {
+        false
+    }
+}
+
+/// What changed during a single round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RoundStats {
+    /// Which round this was, counting from 1.
+    pub(crate) round: usize,
+    pub(crate) moved: usize,
+    pub(crate) born: usize,
+    pub(crate) died: usize,
+}
+
+impl RoundStats {
+    /// Whether nothing at all changed this round.
+    pub(crate) fn is_stable(&self) -> bool {
+        self.moved == 0 && self.born == 0 && self.died == 0
+    }
+}
+
+/// A sparse cellular automaton, made up of a set of live cells and the [`Rule`] that moves them.
+#[derive(Debug, Clone)]
+pub(crate) struct Automaton<R> {
+    rule: R,
+    initial: HashSet<Cell>,
+    cells: HashSet<Cell>,
+    round: usize,
+}
+
+impl<R: Rule> Automaton<R> {
+    pub(crate) fn new(cells: impl IntoIterator<Item = Cell>, rule: R) -> Self {
+        let cells: HashSet<Cell> = cells.into_iter().collect();
+        Self {
+            rule,
+            initial: cells.clone(),
+            cells,
+            round: 0,
+        }
+    }
+
+    /// Parse live cells out of a picture like a puzzle input, where `live` marks a live cell.
+    ///
+    /// The top-left character is at `(0, 0)`.
+    pub(crate) fn from_picture(picture: &str, live: char, rule: R) -> Self {
+        let cells = picture.lines().enumerate().flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(move |&(_, c)| c == live)
+                .map(move |(x, _)| (x as i64, y as i64))
+        });
+        Self::new(cells, rule)
+    }
+
+    pub(crate) fn cells(&self) -> &HashSet<Cell> {
+        &self.cells
+    }
+
+    /// How many rounds have been run so far.
+    pub(crate) fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Which of `cell`'s neighbors are currently live.
+    pub(crate) fn neighbors(&self, cell: Cell) -> NeighborMask {
+        NeighborMask::of(&self.cells, cell)
+    }
+
+    /// Run a single round.
+    pub(crate) fn step_round(&mut self) -> RoundStats {
+        let round = self.round;
+        let mut stats = RoundStats {
+            round: round + 1,
+            moved: 0,
+            born: 0,
+            died: 0,
+        };
+
+        let mut next = HashSet::with_capacity(self.cells.len());
+        let mut wanted: HashMap<Cell, Vec<Cell>> = HashMap::new();
+
+        for &cell in &self.cells {
+            match self.rule.propose(cell, self.neighbors(cell), round) {
+                Proposal::Stay => {
+                    next.insert(cell);
+                }
+                Proposal::Move(target) => wanted.entry(target).or_default().push(cell),
+                Proposal::Die => stats.died += 1,
+            }
+        }
+
+        // Where each winning mover is headed, keyed by the cell it's leaving.
+        let mut moves: HashMap<Cell, Cell> = HashMap::with_capacity(wanted.len());
+        for (target, mut contenders) in wanted {
+            // Sort so that rules see contenders in the same order no matter how the set hashes.
+            contenders.sort_unstable();
+            let winner = self.rule.resolve(target, &contenders);
+            for cell in contenders {
+                if Some(cell) == winner {
+                    moves.insert(cell, target);
+                } else {
+                    next.insert(cell);
+                }
+            }
+        }
+
+        // A move into a cell that's still occupied (by a cell that stayed, or by a mover that
+        // was sent back) is blocked, which can in turn block whoever wanted the blocked cell's
+        // spot. Keep going until nothing else gets blocked, so cells never merge.
+        loop {
+            let blocked: Vec<Cell> = moves
+                .iter()
+                .filter(|(_, target)| next.contains(*target))
+                .map(|(&cell, _)| cell)
+                .collect();
+            if blocked.is_empty() {
+                break;
+            }
+            for cell in blocked {
+                moves.remove(&cell);
+                next.insert(cell);
+            }
+        }
+        stats.moved = moves.len();
+        next.extend(moves.into_values());
+
+        if R::HAS_BIRTHS {
+            let candidates: HashSet<Cell> = self
+                .cells
+                .iter()
+                .flat_map(|&(x, y)| {
+                    NEIGHBOR_OFFSETS
+                        .iter()
+                        .map(move |(dx, dy)| (x + dx, y + dy))
+                })
+                .filter(|cell| !self.cells.contains(cell))
+                .collect();
+            for cell in candidates {
+                if self.rule.born(cell, self.neighbors(cell), round) && next.insert(cell) {
+                    stats.born += 1;
+                }
+            }
+        }
+
+        self.cells = next;
+        self.round += 1;
+        stats
+    }
+
+    /// An endless iterator that runs one round per item.
+    ///
+    /// Use something like [`Iterator::take`] or [`Iterator::find`] to stop it.
+    pub(crate) fn rounds(&mut self) -> impl Iterator<Item = RoundStats> + '_ {
+        std::iter::repeat_with(move || self.step_round())
+    }
+
+    /// The smallest rectangle containing every live cell, as `(min, max)` corners.
+    ///
+    /// Returns `None` if there aren't any live cells.
+    pub(crate) fn bounds(&self) -> Option<(Cell, Cell)> {
+        let mut cells = self.cells.iter().copied();
+        let first = cells.next()?;
+        Some(cells.fold((first, first), |((x0, y0), (x1, y1)), (x, y)| {
+            ((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y)))
+        }))
+    }
+
+    /// How many dead cells are inside of [`Automaton::bounds`].
+    pub(crate) fn empty_tiles(&self) -> usize {
+        self.bounds().map_or(0, |((x0, y0), (x1, y1))| {
+            ((x1 - x0 + 1) * (y1 - y0 + 1)) as usize - self.cells.len()
+        })
+    }
+}
+
+impl<R: Rule> Simulation for Automaton<R> {
+    fn step(&mut self) -> StepResult {
+        if self.step_round().is_stable() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn reset(&mut self) {
+        self.cells = self.initial.clone();
+        self.round = 0;
+    }
+
+    fn metrics(&self) -> serde_json::Value {
+        json!({
+            "round": self.round,
+            "cells": self.cells.len(),
+            "bounds": self.bounds(),
+            "empty_tiles": self.empty_tiles(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The elves from day 23: look north, south, west, then east, rotating each round.
+    struct Diffusion;
+
+    impl Rule for Diffusion {
+        fn propose(&self, cell: Cell, neighbors: NeighborMask, round: usize) -> Proposal {
+            const CHECKS: [(NeighborMask, NeighborMask); 4] = [
+                (NeighborMask::NORTH_SIDE, NeighborMask::N),
+                (NeighborMask::SOUTH_SIDE, NeighborMask::S),
+                (NeighborMask::WEST_SIDE, NeighborMask::W),
+                (NeighborMask::EAST_SIDE, NeighborMask::E),
+            ];
+
+            if neighbors.is_empty() {
+                return Proposal::Stay;
+            }
+            (0..4)
+                .map(|i| CHECKS[(round + i) % 4])
+                .find(|(side, _)| !neighbors.intersects(*side))
+                .and_then(|(_, dir)| dir.offset_from(cell))
+                .map_or(Proposal::Stay, Proposal::Move)
+        }
+    }
+
+    /// Conway's Game of Life.
+    struct Life;
+
+    impl Rule for Life {
+        const HAS_BIRTHS: bool = true;
+
+        fn propose(&self, _cell: Cell, neighbors: NeighborMask, _round: usize) -> Proposal {
+            match neighbors.count() {
+                2 | 3 => Proposal::Stay,
+                _ => Proposal::Die,
+            }
+        }
+
+        fn born(&self, _cell: Cell, neighbors: NeighborMask, _round: usize) -> bool {
+            neighbors.count() == 3
+        }
+    }
+
+    #[test]
+    fn small_diffusion() {
+        let mut elves =
+            Automaton::from_picture(".....\n..##.\n..#..\n.....\n..##.\n.....\n", '#', Diffusion);
+        elves.rounds().take(3).for_each(drop);
+
+        let expected =
+            Automaton::from_picture("..#..\n....#\n#....\n....#\n.....\n..#..\n", '#', Diffusion);
+        assert_eq!(elves.cells(), expected.cells());
+        assert!(elves.step_round().is_stable());
+    }
+
+    #[test]
+    fn larger_diffusion() {
+        let picture = "....#..\n\
+                       ..###.#\n\
+                       #...#.#\n\
+                       .#...##\n\
+                       #.###..\n\
+                       ##.#.##\n\
+                       .#..#..\n";
+        let mut elves = Automaton::from_picture(picture, '#', Diffusion);
+        elves.rounds().take(10).for_each(drop);
+        assert_eq!(elves.empty_tiles(), 110);
+
+        elves.reset();
+        let first_stable = elves.rounds().find(RoundStats::is_stable).unwrap();
+        assert_eq!(first_stable.round, 20);
+    }
+
+    /// Every cell tries to move east, except for the ones in `walls`.
+    struct EastUnless(HashSet<Cell>);
+
+    impl Rule for EastUnless {
+        fn propose(&self, (x, y): Cell, _neighbors: NeighborMask, _round: usize) -> Proposal {
+            if self.0.contains(&(x, y)) {
+                Proposal::Stay
+            } else {
+                Proposal::Move((x + 1, y))
+            }
+        }
+    }
+
+    #[test]
+    fn moves_into_occupied_cells_are_blocked() {
+        // The cell at (0, 0) is blocked by the wall at (1, 0), so (-1, 0) is blocked in turn.
+        // The cell at (0, 1) has nothing in its way.
+        let walls = HashSet::from([(1, 0)]);
+        let mut world = Automaton::new([(-1, 0), (0, 0), (1, 0), (0, 1)], EastUnless(walls));
+        let stats = world.step_round();
+        assert_eq!(stats.moved, 1);
+        assert_eq!(world.cells().len(), 4);
+        assert_eq!(
+            world.cells(),
+            &HashSet::from([(-1, 0), (0, 0), (1, 0), (1, 1)])
+        );
+    }
+
+    #[test]
+    fn blinker() {
+        let mut life = Automaton::new([(0, 1), (1, 1), (2, 1)], Life);
+        let stats = life.step_round();
+        assert_eq!((stats.born, stats.died), (2, 2));
+        assert_eq!(life.cells(), &HashSet::from([(1, 0), (1, 1), (1, 2)]));
+
+        life.step_round();
+        assert_eq!(life.cells(), &HashSet::from([(0, 1), (1, 1), (2, 1)]));
+        assert_eq!(life.bounds(), Some(((0, 1), (2, 1))));
+    }
+}
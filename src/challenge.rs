@@ -1,14 +1,37 @@
 use std::fmt;
 use std::fs;
-use std::io;
+use std::io::{self, BufRead};
+use std::ops::{Range, RangeInclusive};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use miette::{Diagnostic, LabeledSpan, SourceCode};
 use regex::Regex;
 use thiserror::Error;
 
+pub mod crypt;
+
 pub type ChallengeNumber = u8;
 
+/// Every challenge in Advent of Code, one for each day up to Christmas.
+pub const CHALLENGES: RangeInclusive<ChallengeNumber> = 1..=25;
+
+/// Where puzzle inputs are looked for, unless `input_dir` is set in
+/// [`SETTINGS_FILE`](crate::settings::SETTINGS_FILE) or another file is given.
+pub const INPUT_DIR: &str = "./input";
+
+/// How big an input can get before it's probably not a puzzle input, in bytes. The biggest real
+/// inputs are a few tens of kilobytes.
+pub const DEFAULT_MAX_INPUT_SIZE: u64 = 1024 * 1024;
+
+/// How much of the start of an input is looked at for signs that it's a web page.
+const HTML_SNIFF_LEN: usize = 1024;
+
+/// A challenge's puzzle input, ready to be read.
+///
+/// Usually this is the input file itself, but an encrypted input is decrypted into memory first.
+pub type ChallengeInput = Box<dyn io::BufRead + Send>;
+
 #[derive(Copy, Clone, Debug)]
 pub enum Subchallenge {
     A,
@@ -55,45 +78,281 @@ impl FromStr for Subchallenge {
 )]
 pub struct SubchallengeFromStrError(String);
 
+/// What to do about inputs that don't look like puzzle inputs.
+///
+/// An input that starts like a web page is always refused, since it's what gets saved by
+/// mistake when the puzzle's page is saved instead of its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputChecks {
+    /// How big an input can be, in bytes, before it's warned about.
+    pub max_size: u64,
+    /// Refuse inputs bigger than `max_size`, instead of only warning about them.
+    pub strict: bool,
+}
+
+impl InputChecks {
+    /// Warn about an input at `path` that's `size` bytes long if it's too big, or refuse it if
+    /// the checks are strict.
+    fn check_size(&self, path: &Path, size: u64) -> Result<(), GetChallengeInputError> {
+        if size <= self.max_size {
+            return Ok(());
+        }
+        let err = GetChallengeInputError::TooBig {
+            path: path.to_path_buf(),
+            size,
+            max_size: self.max_size,
+        };
+        if self.strict {
+            return Err(err);
+        }
+        eprintln!("Warning: {err}");
+        Ok(())
+    }
+}
+
+impl Default for InputChecks {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_INPUT_SIZE,
+            strict: false,
+        }
+    }
+}
+
+/// Open the input for a challenge from `input_dir`, or from `path_override` if it's given,
+/// decrypting it if it was saved with `aoc2022 input encrypt`.
+///
+/// The key for an encrypted input is taken from the environment, as described in [`crypt`].
 pub fn get_challenge_input(
+    input_dir: &Path,
     challenge: ChallengeNumber,
     subchallenge: Subchallenge,
     path_override: &Option<PathBuf>,
-) -> Result<io::BufReader<fs::File>, GetChallengeInputError> {
+    checks: InputChecks,
+) -> Result<ChallengeInput, GetChallengeInputError> {
     let path = if let Some(path) = path_override {
         path.clone()
     } else {
-        find_default_challenge_input_file(challenge, subchallenge)?
+        find_default_challenge_input_file(input_dir, challenge, subchallenge)?
     };
+    open_input(&path, challenge, checks)
+}
+
+/// Open one of the other inputs saved for a challenge in `input_dir`, like
+/// `./input/17b.friend1.txt` for the label `friend1`, decrypting it if need be.
+pub fn get_labeled_challenge_input(
+    input_dir: &Path,
+    challenge: ChallengeNumber,
+    subchallenge: Subchallenge,
+    label: &str,
+    checks: InputChecks,
+) -> Result<ChallengeInput, GetChallengeInputError> {
+    let path =
+        find_input_file(input_dir, challenge, subchallenge, Some(label))?.ok_or_else(|| {
+            GetChallengeInputError::LabeledInputFileLocationError {
+                challenge,
+                subchallenge,
+                label: label.to_string(),
+            }
+        })?;
+    open_input(&path, challenge, checks)
+}
+
+/// Open the input at `path`, checking that it looks like an input for `challenge`.
+///
+/// Only the start of a plain text input is read here, so the rest is still streamed to the
+/// solver however big it is.
+fn open_input(
+    path: &Path,
+    challenge: ChallengeNumber,
+    checks: InputChecks,
+) -> Result<ChallengeInput, GetChallengeInputError> {
+    if crypt::is_encrypted(path) {
+        let secret = crypt::Secret::from_env()?.ok_or(crypt::CryptError::NoKey)?;
+        let input = crypt::decrypt(&fs::read(path)?, &secret)?;
+        HtmlInputError::check(path, challenge, &input)?;
+        checks.check_size(path, input.len() as u64)?;
+        return Ok(Box::new(io::Cursor::new(input)));
+    }
 
     let f = fs::File::open(path)?;
+    let size = f.metadata()?.len();
+    let mut reader = io::BufReader::new(f);
+    HtmlInputError::check(path, challenge, reader.fill_buf()?)?;
+    checks.check_size(path, size)?;
+
+    Ok(Box::new(reader))
+}
+
+/// The bytes of the tag that `input` starts with, if it starts like a web page.
+///
+/// Only `<!DOCTYPE html` and `<html` count, since some puzzle inputs (like challenge 17's) start
+/// with a `<` of their own.
+pub fn html_tag(input: &[u8]) -> Option<Range<usize>> {
+    let start = input.iter().position(|byte| !byte.is_ascii_whitespace())?;
+    // A byte order mark is left by some editors when they save a page as text.
+    let start = if input[start..].starts_with(b"\xef\xbb\xbf") {
+        start + 3
+    } else {
+        start
+    };
+    let rest = &input[start..];
+    let is_html = [&b"<!doctype html"[..], b"<html"]
+        .iter()
+        .any(|tag| rest.len() >= tag.len() && rest[..tag.len()].eq_ignore_ascii_case(tag));
+    if !is_html {
+        return None;
+    }
+    let end = rest
+        .iter()
+        .position(|&byte| byte == b'>')
+        .map_or(rest.len(), |end| end + 1);
+    Some(start..start + end)
+}
+
+/// An input that starts like a web page, which is what gets saved when the puzzle's page is saved
+/// instead of its input. Caught before a parser gets to it and fails on the first `<`.
+#[derive(Debug, Error)]
+#[error("{} looks like a web page, not a puzzle input", path.display())]
+pub struct HtmlInputError {
+    path: PathBuf,
+    challenge: ChallengeNumber,
+    /// The start of the input, as far as it was looked at.
+    head: String,
+    /// The bytes of `head` that make it look like a web page.
+    tag: Range<usize>,
+}
+
+impl HtmlInputError {
+    /// Refuse `input` from `path` if it starts like a web page.
+    fn check(path: &Path, challenge: ChallengeNumber, input: &[u8]) -> Result<(), Self> {
+        let head = &input[..input.len().min(HTML_SNIFF_LEN)];
+        let Some(tag) = html_tag(head) else {
+            return Ok(());
+        };
+        Err(Self {
+            path: path.to_path_buf(),
+            challenge,
+            head: String::from_utf8_lossy(head).into_owned(),
+            tag,
+        })
+    }
+
+    /// Render the error with fancy formatting, pointing out the tag that gave the page away.
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        crate::color::report_handler()
+            .render_report(&mut s, self)
+            .expect("writing to a String can't fail");
+        s
+    }
+}
+
+impl Diagnostic for HtmlInputError {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.head)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(LabeledSpan::new(
+            Some("this is HTML".to_string()),
+            self.tag.start,
+            self.tag.len(),
+        ))))
+    }
 
-    Ok(io::BufReader::new(f))
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(format!(
+            "Open https://adventofcode.com/2022/day/{}/input while logged in, and save what it \
+             shows as plain text",
+            self.challenge
+        )))
+    }
 }
 
-fn find_default_challenge_input_file(
+/// Find the input for a challenge in `input_dir`, named like `16a.txt`, `16A.txt` or `016a.txt`.
+pub(crate) fn find_default_challenge_input_file(
+    input_dir: &Path,
     challenge: ChallengeNumber,
     subchallenge: Subchallenge,
 ) -> Result<PathBuf, GetChallengeInputError> {
-    let default_input_file_re =
-        Regex::new(format!("(?i)^0*{challenge}{subchallenge}.txt$").as_str()).unwrap();
+    find_input_file(input_dir, challenge, subchallenge, None)?.ok_or(
+        GetChallengeInputError::DefaultInputFileLocationError {
+            challenge,
+            subchallenge,
+        },
+    )
+}
 
-    let input_dir = Path::new("./input");
+/// Find the input for a challenge in `input_dir` with the given `label`, or without one if it's
+/// `None`.
+fn find_input_file(
+    input_dir: &Path,
+    challenge: ChallengeNumber,
+    subchallenge: Subchallenge,
+    label: Option<&str>,
+) -> io::Result<Option<PathBuf>> {
+    let label = label.map_or(String::new(), |label| {
+        format!(r"\.{}", regex::escape(label))
+    });
+    let input_file_re = Regex::new(
+        format!(
+            r"(?i)^0*{challenge}{subchallenge}{label}\.txt(\.{})?$",
+            crypt::ENCRYPTED_EXTENSION
+        )
+        .as_str(),
+    )
+    .unwrap();
 
+    // A plain text input wins over an encrypted one, in case both are lying around.
+    let mut encrypted = None;
     for entry in fs::read_dir(input_dir)? {
         let entry = entry?;
 
         if let Some(file_name) = entry.file_name().to_str() {
-            if default_input_file_re.is_match(file_name) {
-                return Ok(input_dir.join(file_name));
+            if input_file_re.is_match(file_name) {
+                let path = input_dir.join(file_name);
+                if !crypt::is_encrypted(&path) {
+                    return Ok(Some(path));
+                }
+                encrypted = Some(path);
             }
         }
     }
 
-    Err(GetChallengeInputError::DefaultInputFileLocationError {
-        challenge,
-        subchallenge,
-    })
+    Ok(encrypted)
+}
+
+/// The labels of every other input saved for a challenge in `input_dir`, in order. An input
+/// saved as `17b.friend1.txt` (or `17b.friend1.txt.enc`) has the label `friend1`.
+pub fn find_input_labels(
+    input_dir: &Path,
+    challenge: ChallengeNumber,
+    subchallenge: Subchallenge,
+) -> io::Result<Vec<String>> {
+    let labeled_input_file_re = Regex::new(
+        format!(
+            r"(?i)^0*{challenge}{subchallenge}\.(.+)\.txt(\.{})?$",
+            crypt::ENCRYPTED_EXTENSION
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    let mut labels = Vec::new();
+    for entry in fs::read_dir(input_dir)? {
+        let entry = entry?;
+
+        if let Some(file_name) = entry.file_name().to_str() {
+            if let Some(captures) = labeled_input_file_re.captures(file_name) {
+                labels.push(captures[1].to_string());
+            }
+        }
+    }
+
+    labels.sort_unstable();
+    labels.dedup();
+    Ok(labels)
 }
 
 #[derive(Debug, Error)]
@@ -106,6 +365,126 @@ pub enum GetChallengeInputError {
         subchallenge: Subchallenge,
     },
 
+    #[error(
+        "Could not find input file labeled `{label}` for challenge {challenge}, subchallenge \
+         {subchallenge}."
+    )]
+    LabeledInputFileLocationError {
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        label: String,
+    },
+
+    #[error(
+        "{} is {} KiB, which is much bigger than any puzzle input (the limit is {} KiB).",
+        path.display(),
+        size.div_ceil(1024),
+        max_size / 1024
+    )]
+    TooBig {
+        path: PathBuf,
+        size: u64,
+        max_size: u64,
+    },
+
+    #[error(transparent)]
+    Html(#[from] HtmlInputError),
+
+    #[error(transparent)]
+    CryptError(#[from] crypt::CryptError),
+
     #[error(transparent)]
     IoError(#[from] io::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn finds_labeled_inputs() {
+        let dir = std::env::temp_dir().join(format!("aoc2022-labels-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "17b.txt",
+            "17b.friend1.txt",
+            "017B.friend2.txt.enc",
+            "17b.friend2.txt",
+            "17a.other.txt",
+            "117b.nope.txt",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let labels = find_input_labels(&dir, 17, Subchallenge::B);
+        let default = find_default_challenge_input_file(&dir, 17, Subchallenge::B);
+        let friend2 = find_input_file(&dir, 17, Subchallenge::B, Some("friend2"));
+        let missing = find_input_file(&dir, 17, Subchallenge::B, Some("friend3"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(labels.unwrap(), ["friend1", "friend2"]);
+        assert_eq!(default.unwrap(), dir.join("17b.txt"));
+        // The plain text input wins over the encrypted one.
+        assert_eq!(friend2.unwrap(), Some(dir.join("17b.friend2.txt")));
+        assert_eq!(missing.unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_web_pages() {
+        assert_eq!(
+            html_tag(b"<!DOCTYPE html>\n<html lang=\"en-us\">"),
+            Some(0..15)
+        );
+        assert_eq!(html_tag(b"\n  <html lang=\"en-us\">\n"), Some(3..22));
+        assert_eq!(html_tag(b"\xef\xbb\xbf<!doctype html>"), Some(3..18));
+        // Challenge 17's input is all `<` and `>`.
+        assert_eq!(html_tag(b"<<>><>>>\n"), None);
+        assert_eq!(html_tag(b"1000\n2000\n"), None);
+        assert_eq!(html_tag(b""), None);
+
+        let dir = std::env::temp_dir().join(format!("aoc2022-html-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("17a.txt");
+        fs::write(&path, "<!DOCTYPE html>\n<html lang=\"en-us\">\n").unwrap();
+        let page = open_input(&path, 17, InputChecks::default()).map(|_| ());
+        fs::write(&path, "<<>><>>>\n").unwrap();
+        let mut input = String::new();
+        let jets = open_input(&path, 17, InputChecks::default())
+            .and_then(|mut input_file| Ok(input_file.read_to_string(&mut input)?));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let Err(GetChallengeInputError::Html(err)) = page else {
+            panic!("expected a web page to be refused, got {page:?}");
+        };
+        assert!(err.render().contains("this is HTML"), "{}", err.render());
+        assert!(err.render().contains("day/17/input"), "{}", err.render());
+        jets.unwrap();
+        // Peeking at the start of the input doesn't take it away from the solver.
+        assert_eq!(input, "<<>><>>>\n");
+    }
+
+    #[test]
+    fn warns_about_big_inputs_unless_strict() {
+        let path = Path::new("input/01a.txt");
+        let checks = InputChecks {
+            max_size: 2048,
+            strict: false,
+        };
+        assert!(checks.check_size(path, 2048).is_ok());
+        assert!(checks.check_size(path, 4096).is_ok());
+
+        let strict = InputChecks {
+            strict: true,
+            ..checks
+        };
+        assert!(strict.check_size(path, 2048).is_ok());
+        let err = strict.check_size(path, 4097).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input/01a.txt is 5 KiB, which is much bigger than any puzzle input (the limit is 2 \
+             KiB)."
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Encrypting puzzle inputs at rest, since Advent of Code asks for them not to be published.
+//!
+//! `aoc2022 input encrypt` replaces every `./input/*.txt` with an encrypted `*.txt.enc`, which
+//! [`get_challenge_input`](super::get_challenge_input) decrypts on the fly whenever the key is
+//! available. The key is derived from a passphrase or the contents of a keyfile with Argon2, and
+//! each file is sealed with ChaCha20-Poly1305 under its own salt and nonce.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use thiserror::Error;
+
+/// The extension added to an input file once it's encrypted, as in `16a.txt.enc`.
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+/// The environment variable to read a passphrase from.
+pub const PASSPHRASE_VAR: &str = "AOC2022_INPUT_PASSPHRASE";
+
+/// The environment variable to read the path to a keyfile from, if there's no passphrase.
+pub const KEYFILE_VAR: &str = "AOC2022_INPUT_KEYFILE";
+
+/// Starts every encrypted file, so that a file that was never encrypted isn't mistaken for one
+/// with the wrong key.
+const MAGIC: &[u8] = b"aoc2022-input-v1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// The passphrase or keyfile contents that every input's key is derived from.
+pub struct Secret(Vec<u8>);
+
+impl Secret {
+    pub fn passphrase(passphrase: &str) -> Self {
+        Self(passphrase.as_bytes().to_vec())
+    }
+
+    pub fn from_keyfile(path: &Path) -> Result<Self, CryptError> {
+        let contents = fs::read(path).map_err(|source| CryptError::Keyfile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self(contents))
+    }
+
+    /// The secret given by [`PASSPHRASE_VAR`] or [`KEYFILE_VAR`], in that order, if either is set.
+    pub fn from_env() -> Result<Option<Self>, CryptError> {
+        if let Ok(passphrase) = env::var(PASSPHRASE_VAR) {
+            return Ok(Some(Self::passphrase(&passphrase)));
+        }
+        env::var_os(KEYFILE_VAR)
+            .map(|path| Self::from_keyfile(Path::new(&path)))
+            .transpose()
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<ChaCha20Poly1305, CryptError> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(&self.0, salt, &mut key)
+            .map_err(|e| CryptError::KeyDerivation(e.to_string()))?;
+        Ok(ChaCha20Poly1305::new(&key))
+    }
+}
+
+/// Encrypt `plaintext` under a fresh salt and nonce.
+pub fn encrypt(plaintext: &[u8], secret: &Secret) -> Result<Vec<u8>, CryptError> {
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = secret
+        .cipher(&salt)?
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .expect("inputs are far smaller than what ChaCha20-Poly1305 can encrypt");
+
+    Ok([MAGIC, &salt, &nonce, &ciphertext].concat())
+}
+
+/// Decrypt something made by [`encrypt`].
+pub fn decrypt(data: &[u8], secret: &Secret) -> Result<Vec<u8>, CryptError> {
+    let rest = data.strip_prefix(MAGIC).ok_or(CryptError::NotEncrypted)?;
+    if rest.len() < SALT_LEN + NONCE_LEN {
+        return Err(CryptError::NotEncrypted);
+    }
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    secret
+        .cipher(salt)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| CryptError::WrongKey)
+}
+
+/// Whether a file looks like it was encrypted, going by its name.
+pub fn is_encrypted(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == ENCRYPTED_EXTENSION)
+}
+
+/// Encrypt every `.txt` file in `dir`, replacing it with a `.txt.enc`. Returns the files that were
+/// written.
+pub fn encrypt_dir(dir: &Path, secret: &Secret) -> Result<Vec<PathBuf>, CryptError> {
+    convert_dir(
+        dir,
+        |path| {
+            if path.extension()? != "txt" {
+                return None;
+            }
+            let mut encrypted = path.as_os_str().to_owned();
+            encrypted.push(".");
+            encrypted.push(ENCRYPTED_EXTENSION);
+            Some((PathBuf::from(encrypted), encrypt as Convert))
+        },
+        secret,
+    )
+}
+
+/// Decrypt every `.txt.enc` file in `dir`, replacing it with the original `.txt`. Returns the files
+/// that were written.
+pub fn decrypt_dir(dir: &Path, secret: &Secret) -> Result<Vec<PathBuf>, CryptError> {
+    convert_dir(
+        dir,
+        |path| is_encrypted(path).then(|| (path.with_extension(""), decrypt as Convert)),
+        secret,
+    )
+}
+
+type Convert = fn(&[u8], &Secret) -> Result<Vec<u8>, CryptError>;
+
+/// Convert every file in `dir` that `pick` gives a new path for, and remove the original once the
+/// new one is written.
+fn convert_dir(
+    dir: &Path,
+    pick: impl Fn(&Path) -> Option<(PathBuf, Convert)>,
+    secret: &Secret,
+) -> Result<Vec<PathBuf>, CryptError> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    let mut written = Vec::new();
+    for path in entries {
+        let Some((to, convert)) = pick(&path) else {
+            continue;
+        };
+        let converted = convert(&fs::read(&path)?, secret)?;
+        fs::write(&to, converted)?;
+        fs::remove_file(&path)?;
+        written.push(to);
+    }
+    Ok(written)
+}
+
+#[derive(Debug, Error)]
+pub enum CryptError {
+    #[error(
+        "The input is encrypted, but no key was given. Set `{PASSPHRASE_VAR}` to the passphrase, \
+         or `{KEYFILE_VAR}` to the path of the keyfile."
+    )]
+    NoKey,
+
+    #[error("The input wasn't encrypted by `aoc2022 input encrypt`.")]
+    NotEncrypted,
+
+    #[error("Could not decrypt the input. Either the key is wrong, or the file is corrupted.")]
+    WrongKey,
+
+    #[error("Could not derive a key: {0}")]
+    KeyDerivation(String),
+
+    #[error("Could not read keyfile {}", path.display())]
+    Keyfile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let secret = Secret::passphrase("correct horse battery staple");
+        let input = b"498,4 -> 498,6 -> 496,6\n";
+
+        let encrypted = encrypt(input, &secret).unwrap();
+        assert!(!encrypted.windows(input.len()).any(|window| window == input));
+        assert_eq!(decrypt(&encrypted, &secret).unwrap(), input);
+
+        // Every encryption gets its own salt and nonce.
+        assert_ne!(encrypt(input, &secret).unwrap(), encrypted);
+
+        assert!(matches!(
+            decrypt(&encrypted, &Secret::passphrase("wrong")),
+            Err(CryptError::WrongKey)
+        ));
+        assert!(matches!(
+            decrypt(input, &secret),
+            Err(CryptError::NotEncrypted)
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+//! Sets of ASCII letters, packed into the bits of a single `u64`.
+//!
+//! Puzzles like day 3's rucksacks treat a line of letters as a set of items, and only ever ask
+//! which letters two or three lines have in common. With only 52 letters, a [`CharSet`] fits them
+//! all into one integer, so unions and intersections are a single instruction and nothing is
+//! allocated.
+//!
+//! Each letter has a priority, the way day 3 numbers its items: `a` through `z` are 1 through 26,
+//! and `A` through `Z` are 27 through 52. A set's letters are stored at the bits of their
+//! priorities, and iterate in that order.
+
+use std::{
+    fmt,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign},
+};
+
+/// A set of the letters `a-z` and `A-Z`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CharSet(u64);
+
+/// A character that isn't one of the letters a [`CharSet`] can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("`{0}` isn't a letter from a to z or A to Z")]
+pub struct NotALetter(pub char);
+
+impl CharSet {
+    /// The set with no letters in it.
+    pub const EMPTY: Self = Self(0);
+
+    /// The set with every letter in it.
+    pub const ALL: Self = Self(((1 << 52) - 1) << 1);
+
+    /// The letter's priority, from 1 for `a` to 52 for `Z`, or `None` if it isn't a letter.
+    pub const fn priority(letter: char) -> Option<u32> {
+        match letter {
+            'a'..='z' => Some(letter as u32 - 'a' as u32 + 1),
+            'A'..='Z' => Some(letter as u32 - 'A' as u32 + 27),
+            _ => None,
+        }
+    }
+
+    /// The letter with `priority`, or `None` if it isn't from 1 to 52.
+    pub const fn letter(priority: u32) -> Option<char> {
+        match priority {
+            1..=26 => Some((b'a' + (priority - 1) as u8) as char),
+            27..=52 => Some((b'A' + (priority - 27) as u8) as char),
+            _ => None,
+        }
+    }
+
+    /// The set of every letter in `letters`, or the first character that isn't a letter.
+    pub fn from_letters(letters: &str) -> Result<Self, NotALetter> {
+        let mut set = Self::EMPTY;
+        for letter in letters.chars() {
+            set.insert(letter)?;
+        }
+        Ok(set)
+    }
+
+    /// Add `letter` to the set. Returns whether it wasn't already there.
+    pub fn insert(&mut self, letter: char) -> Result<bool, NotALetter> {
+        let bit = Self::bit(letter).ok_or(NotALetter(letter))?;
+        let added = self.0 & bit == 0;
+        self.0 |= bit;
+        Ok(added)
+    }
+
+    /// Whether `letter` is in the set. Characters that aren't letters never are.
+    pub fn contains(&self, letter: char) -> bool {
+        Self::bit(letter).is_some_and(|bit| self.0 & bit != 0)
+    }
+
+    pub const fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The letters in both sets.
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// The letters in either set.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// The sum of the priorities of every letter in the set.
+    pub fn priority_sum(&self) -> u32 {
+        self.priorities().sum()
+    }
+
+    /// Every letter in the set, from the lowest priority to the highest.
+    pub fn iter(&self) -> impl Iterator<Item = char> {
+        self.priorities().filter_map(Self::letter)
+    }
+
+    /// The priority of every letter in the set, from lowest to highest.
+    pub fn priorities(&self) -> impl Iterator<Item = u32> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            let priority = bits.trailing_zeros();
+            // Clears the lowest bit that's set, leaving no bits once they've all been seen.
+            bits &= bits.checked_sub(1)?;
+            Some(priority)
+        })
+    }
+
+    const fn bit(letter: char) -> Option<u64> {
+        match Self::priority(letter) {
+            Some(priority) => Some(1 << priority),
+            None => None,
+        }
+    }
+}
+
+impl BitAnd for CharSet {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        self.intersection(rhs)
+    }
+}
+
+impl BitAndAssign for CharSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = self.intersection(rhs);
+    }
+}
+
+impl BitOr for CharSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for CharSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+/// Shows the letters like a set, e.g. `{'a', 'p', 'L'}`.
+impl fmt::Debug for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every letter, in order of priority.
+    const LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    #[test]
+    fn every_letter_has_its_own_priority() {
+        for (i, letter) in LETTERS.chars().enumerate() {
+            let priority = i as u32 + 1;
+            assert_eq!(CharSet::priority(letter), Some(priority), "{letter}");
+            assert_eq!(CharSet::letter(priority), Some(letter));
+        }
+        assert_eq!(CharSet::priority('t'), Some(20));
+        assert_eq!(CharSet::priority('D'), Some(30));
+
+        for not_a_letter in [' ', '0', '`', '{', '@', '[', 'é', '😅'] {
+            assert_eq!(CharSet::priority(not_a_letter), None, "{not_a_letter}");
+        }
+        assert_eq!(CharSet::letter(0), None);
+        assert_eq!(CharSet::letter(53), None);
+    }
+
+    #[test]
+    fn holds_every_letter_on_its_own_and_together() {
+        for letter in LETTERS.chars() {
+            let mut set = CharSet::EMPTY;
+            assert_eq!(set.insert(letter), Ok(true));
+            assert_eq!(set.insert(letter), Ok(false));
+            assert_eq!(set.len(), 1);
+            assert_eq!(set.iter().collect::<String>(), letter.to_string());
+            assert_eq!(set.priority_sum(), CharSet::priority(letter).unwrap());
+
+            for other in LETTERS.chars() {
+                assert_eq!(set.contains(other), other == letter, "{letter} {other}");
+            }
+            assert!((set & CharSet::ALL) == set);
+            assert!((set | CharSet::ALL) == CharSet::ALL);
+        }
+
+        let all = CharSet::from_letters(LETTERS).unwrap();
+        assert_eq!(all, CharSet::ALL);
+        assert_eq!(all.len(), 52);
+        assert_eq!(all.iter().collect::<String>(), LETTERS);
+        assert_eq!(all.priority_sum(), (1..=52).sum::<u32>());
+    }
+
+    #[test]
+    fn combines_sets() {
+        let a = CharSet::from_letters("vJrwpWtwJgWr").unwrap();
+        let b = CharSet::from_letters("hcsFMMfFFhFp").unwrap();
+        assert_eq!((a & b).iter().collect::<String>(), "p");
+        assert_eq!(format!("{:?}", a & b), "{'p'}");
+
+        let mut union = a;
+        union |= b;
+        assert_eq!(union.len(), a.len() + b.len() - 1);
+        let mut both = union;
+        both &= a;
+        assert_eq!(both, a);
+
+        assert!(CharSet::EMPTY.is_empty());
+        assert_eq!(CharSet::EMPTY.iter().count(), 0);
+        assert_eq!(CharSet::from_letters("ab1"), Err(NotALetter('1')));
+        assert!(!a.contains('1'));
+    }
+}
@@ -0,0 +1,194 @@
+//! Telling the time, in a way that tests can fake.
+//!
+//! Everything that times or paces something, like `bench`, the checkpoints and the terminal UIs'
+//! ticks, asks a [`SharedClock`] instead of calling [`Instant::now`] itself. It's the real
+//! [`SystemClock`] everywhere but in tests, which hand out a [`FakeClock`] that only moves when
+//! it's told to, so that waiting for a tick or a timeout takes no real time at all.
+//!
+//! Waiting on a channel with a deadline goes through the clock too, with
+//! [`SharedClock::recv_until`], so that solve timeouts run out on fake time as well.
+
+use std::{
+    fmt,
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Something that tells the time, and can wait for some of it to pass.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// What time it is now.
+    fn now(&self) -> Instant;
+
+    /// Wait for `duration` to pass.
+    fn sleep(&self, duration: Duration);
+
+    /// The longest to block for at once while waiting for a deadline, or `None` to block right
+    /// up until it. Time that can jump ahead while something's blocked has to be checked on
+    /// every so often.
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// The real time, from the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock that stands still until it's [advanced](FakeClock::advance), or something sleeps on
+/// it. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct FakeClock(Arc<Mutex<Instant>>);
+
+impl FakeClock {
+    /// A clock stopped at the real time right now.
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    /// Move the time on by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.time() += duration;
+    }
+
+    fn time(&self) -> std::sync::MutexGuard<'_, Instant> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        *self.time()
+    }
+
+    /// Returns right away, with the time moved on by `duration`.
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+
+    /// Often enough that whatever's waiting notices the time being moved on straight away.
+    fn poll_interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(1))
+    }
+}
+
+/// A cheap-to-clone handle to a [`Clock`], which is the [`SystemClock`] by default.
+#[derive(Debug, Clone)]
+pub struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self(Arc::new(clock))
+    }
+
+    pub fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    pub fn sleep(&self, duration: Duration) {
+        self.0.sleep(duration);
+    }
+
+    /// How long it's been since `earlier`, which was from this clock.
+    pub fn since(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+
+    /// Wait for a message on `rx` until this clock reaches `deadline`, or forever if there isn't
+    /// one.
+    ///
+    /// Like [`Receiver::recv_timeout`], but the deadline can pass on fake time.
+    pub fn recv_until<T>(
+        &self,
+        rx: &Receiver<T>,
+        deadline: Option<Instant>,
+    ) -> Result<T, RecvTimeoutError> {
+        let Some(deadline) = deadline else {
+            return rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
+        };
+        loop {
+            let left = deadline.saturating_duration_since(self.now());
+            if left.is_zero() {
+                return rx.try_recv().map_err(|e| match e {
+                    TryRecvError::Empty => RecvTimeoutError::Timeout,
+                    TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+                });
+            }
+            let wait = self.0.poll_interval().map_or(left, |poll| poll.min(left));
+            match rx.recv_timeout(wait) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                received => return received,
+            }
+        }
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn fake_time_only_moves_when_told() {
+        let fake = FakeClock::new();
+        let clock = SharedClock::new(fake.clone());
+        let start = clock.now();
+
+        assert_eq!(clock.since(start), Duration::ZERO);
+        fake.advance(Duration::from_secs(2));
+        clock.sleep(Duration::from_secs(60));
+        assert_eq!(clock.since(start), Duration::from_secs(62));
+        assert_eq!(fake.now(), clock.now());
+    }
+
+    #[test]
+    fn deadlines_pass_on_fake_time() {
+        let fake = FakeClock::new();
+        let clock = SharedClock::new(fake.clone());
+        let deadline = clock.now() + Duration::from_secs(60);
+        let (tx, rx) = mpsc::channel::<()>();
+
+        let mover = thread::spawn(move || fake.sleep(Duration::from_secs(61)));
+        assert_eq!(
+            clock.recv_until(&rx, Some(deadline)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        mover.join().unwrap();
+
+        tx.send(()).unwrap();
+        assert_eq!(clock.recv_until(&rx, Some(deadline)), Ok(()));
+        drop(tx);
+        assert_eq!(
+            clock.recv_until(&rx, None),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Whether to color what gets printed to the terminal, as given to `--color`.
+//!
+//! Solvers draw pictures with colors from [`owo_colors`], like day 17's tower. Those colors are
+//! ANSI escape codes, which clutter up logs and files that stdout gets redirected to, so anything
+//! that colors its output checks [`enabled`] first. Which choice is in effect is set for the whole
+//! crate by [`set`], since [`Display`](std::fmt::Display) impls have nowhere else to get it from.
+//!
+//! Errors go to stderr, which can be redirected on its own, so whether they're colored is decided
+//! separately, by [`stderr_enabled`]. Diagnostics that point into an input, like parse errors, are
+//! drawn with the [`report_handler`] that follows it.
+
+use std::{
+    env, fmt,
+    io::{self, IsTerminal},
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use miette::{GraphicalReportHandler, GraphicalTheme};
+
+/// Whether to color output, as given to `--color`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color output when it goes to a terminal, unless the `NO_COLOR` environment variable is set.
+    #[default]
+    Auto,
+    /// Always color output, even when it's piped somewhere.
+    Always,
+    /// Never color output.
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether this choice colors what's printed to stdout, right now.
+    pub fn colors(self) -> bool {
+        self.colors_on(&io::stdout())
+    }
+
+    /// Whether this choice colors what's printed to `stream`, right now.
+    pub fn colors_on(self, stream: &impl IsTerminal) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            // Any value but an empty one counts, as https://no-color.org says.
+            Self::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && stream.is_terminal()
+            }
+        }
+    }
+}
+
+/// What [`enabled`] and [`stderr_enabled`] say: whether to color stdout and stderr, or that it
+/// hasn't been worked out yet.
+static ENABLED: AtomicU8 = AtomicU8::new(UNDECIDED);
+static STDERR_ENABLED: AtomicU8 = AtomicU8::new(UNDECIDED);
+
+const UNDECIDED: u8 = 0;
+const OFF: u8 = 1;
+const ON: u8 = 2;
+
+/// Color output from now on according to `choice`.
+pub fn set(choice: ColorChoice) {
+    let flag = |colors| if colors { ON } else { OFF };
+    ENABLED.store(flag(choice.colors()), Ordering::Relaxed);
+    STDERR_ENABLED.store(flag(choice.colors_on(&io::stderr())), Ordering::Relaxed);
+}
+
+/// Whether to color output, as last [`set`], or as [`ColorChoice::Auto`] would if it's never been
+/// set.
+pub fn enabled() -> bool {
+    decided(&ENABLED)
+}
+
+/// Like [`enabled`], but for errors and diagnostics printed to stderr.
+pub fn stderr_enabled() -> bool {
+    decided(&STDERR_ENABLED)
+}
+
+fn decided(enabled: &AtomicU8) -> bool {
+    match enabled.load(Ordering::Relaxed) {
+        UNDECIDED => {
+            set(ColorChoice::Auto);
+            decided(enabled)
+        }
+        enabled => enabled == ON,
+    }
+}
+
+/// What to draw diagnostics with, like a parse error pointing into the input, so that they're
+/// only colored if [`stderr_enabled`] says so.
+pub fn report_handler() -> GraphicalReportHandler {
+    report_handler_for(stderr_enabled(), io::stderr().is_terminal())
+}
+
+/// [`report_handler`], for a stderr that's colored or not, and is a terminal or not.
+fn report_handler_for(colors: bool, terminal: bool) -> GraphicalReportHandler {
+    let theme = match (colors, terminal) {
+        (true, true) => GraphicalTheme::unicode(),
+        // Logs and files only get plain ASCII, like miette does by itself.
+        (true, false) => GraphicalTheme::ascii(),
+        (false, true) => GraphicalTheme::unicode_nocolor(),
+        (false, false) => GraphicalTheme::none(),
+    };
+    GraphicalReportHandler::new_themed(theme)
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err("expected `auto`, `always` or `never`".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Always => write!(f, "always"),
+            Self::Never => write!(f, "never"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_choice() {
+        for choice in [ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never] {
+            assert_eq!(choice.to_string().parse(), Ok(choice));
+        }
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+
+        assert!(ColorChoice::Always.colors());
+        assert!(!ColorChoice::Never.colors());
+        assert!(ColorChoice::Always.colors_on(&io::stderr()));
+    }
+
+    #[test]
+    fn diagnostics_follow_the_choice() {
+        let error =
+            crate::parse::parse_lines_all("1\nx\n", nom::character::complete::u32).unwrap_err();
+        let render = |colors, terminal| {
+            let mut s = String::new();
+            report_handler_for(colors, terminal)
+                .render_report(&mut s, &error)
+                .unwrap();
+            s
+        };
+
+        for terminal in [false, true] {
+            assert!(!render(false, terminal).contains('\x1b'));
+            assert!(render(true, terminal).contains('\x1b'));
+        }
+        assert!(render(false, false).is_ascii());
+    }
+}
@@ -0,0 +1,279 @@
+//! Finding where an iterated system starts repeating itself, so that long runs of it can skip
+//! over the repeats.
+//!
+//! A system that's stepped from one state to the next by a function, and only has so many states,
+//! has to come back around to one it's been in before. From then on it goes around the same loop
+//! forever. [`brent`] and [`floyd`] find where that loop starts and how long it is, for systems
+//! whose whole state can be compared. [`FastForward`] is for systems where it can't, like day 17's
+//! ever-growing tower: it watches a key made from the part of the state that decides what happens
+//! next, and once the key has come around often enough to trust, extrapolates a metric that grows
+//! by the same amount every time around, like the tower's height.
+//!
+//! Built for day 17's tower, and meant for day 23-style simulations that run for far longer than
+//! they take to settle into a loop.
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::math::{MathError, MathMode};
+
+/// Where the states of an iterated system start repeating, counted in steps from the first state.
+// Only the tests find whole-state cycles so far: day 17 fast-forwards, and day 23 isn't solved yet.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Cycle {
+    /// How many steps it takes to get to the first state that's part of the loop.
+    pub(crate) start: usize,
+    /// How many steps it takes to go around the loop once.
+    pub(crate) period: usize,
+}
+
+#[allow(dead_code)] // See `Cycle`.
+impl Cycle {
+    /// How many steps it takes to get to the same state as after `steps` steps, without going
+    /// around the loop even once.
+    pub(crate) const fn equivalent_step(&self, steps: usize) -> usize {
+        if steps < self.start {
+            steps
+        } else {
+            self.start + (steps - self.start) % self.period
+        }
+    }
+}
+
+/// Find the cycle that stepping from `start` with `step` ends up in, with Brent's algorithm.
+///
+/// This takes fewer steps than [`floyd`], since the hare never has to be stepped twice over the
+/// same states. Never returns if the states don't repeat.
+#[allow(dead_code)] // See `Cycle`.
+pub(crate) fn brent<T, F>(start: T, step: F) -> Cycle
+where
+    T: Clone + PartialEq,
+    F: Fn(&T) -> T,
+{
+    // Find the period, by leaving the tortoise at every power of two and waiting for the hare to
+    // come back around to it.
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    // With the hare a whole period ahead, the two meet at the start of the loop.
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut mu = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+
+    Cycle { start: mu, period }
+}
+
+/// Find the cycle that stepping from `start` with `step` ends up in, with Floyd's tortoise and
+/// hare.
+///
+/// Never returns if the states don't repeat.
+#[allow(dead_code)] // See `Cycle`.
+pub(crate) fn floyd<T, F>(start: T, step: F) -> Cycle
+where
+    T: Clone + PartialEq,
+    F: Fn(&T) -> T,
+{
+    // The hare goes twice as fast, so the two meet somewhere in the loop.
+    let mut tortoise = step(&start);
+    let mut hare = step(&step(&start));
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    // Where they met is as far from the start of the loop as the start of the loop is from the
+    // first state, so stepping one from each end meets there.
+    let mut mu = 0;
+    tortoise = start;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+
+    let mut period = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        period += 1;
+    }
+
+    Cycle { start: mu, period }
+}
+
+/// When a [`FastForward`] key was last seen, and how often it's been seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Sighting {
+    /// How many times the key has been seen.
+    pub(crate) count: usize,
+    /// The step the key was last seen at.
+    pub(crate) step: usize,
+    /// The metric the last time the key was seen.
+    pub(crate) metric: usize,
+}
+
+/// How far a [`FastForward`] says to skip ahead, going around a loop as many times as fits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Skip {
+    /// When the key that closed the loop was last seen.
+    pub(crate) last: Sighting,
+    /// How many steps it takes to go around the loop once.
+    pub(crate) period: usize,
+    /// How much the metric grows every time around the loop.
+    pub(crate) gain: usize,
+    /// How many times around the loop to skip.
+    pub(crate) repeats: usize,
+    /// How many steps to skip, which is `repeats` periods.
+    pub(crate) steps: usize,
+    /// How much to add to the metric, which is `repeats` gains.
+    pub(crate) metric: usize,
+}
+
+/// Watches an iterated system for a key that comes back around, to skip most of the way to a
+/// target step in one go.
+///
+/// Keys are made from whatever part of the state decides what happens next, like which piece and
+/// which jet come next on day 17. A key coming back around once isn't always enough to trust, since
+/// the system might still be settling in, so the loop is only skipped once a key has been seen
+/// `confirmations` times before. Only one skip is ever made.
+#[derive(Debug)]
+pub(crate) struct FastForward<K> {
+    seen: HashMap<K, Sighting>,
+    confirmations: usize,
+    skipped: bool,
+}
+
+impl<K: Hash + Eq> FastForward<K> {
+    pub(crate) fn new(confirmations: usize) -> Self {
+        Self {
+            seen: HashMap::new(),
+            confirmations,
+            skipped: false,
+        }
+    }
+
+    /// Whether a skip has been made already, after which keys aren't even looked at.
+    #[cfg(test)]
+    pub(crate) const fn skipped(&self) -> bool {
+        self.skipped
+    }
+
+    /// Note that the system is in a state with `key` at `step`, with its metric at `metric`.
+    ///
+    /// Returns how far to skip ahead without going past `target`, if `key` closes a loop that's
+    /// been confirmed often enough. The skip is worked out with `math`, since long runs can have
+    /// metrics big enough to overflow.
+    pub(crate) fn observe(
+        &mut self,
+        key: K,
+        step: usize,
+        metric: usize,
+        target: usize,
+        math: MathMode,
+    ) -> Result<Option<Skip>, MathError> {
+        if self.skipped {
+            return Ok(None);
+        }
+
+        let mut skip = None;
+        if let Some(&last) = self.seen.get(&key) {
+            if last.count == self.confirmations {
+                let period = math.sub(step, last.step)?;
+                let gain = math.sub(metric, last.metric)?;
+                let repeats = math.div(math.sub(target, step)?, period)?;
+                skip = Some(Skip {
+                    last,
+                    period,
+                    gain,
+                    repeats,
+                    steps: math.mul(repeats, period)?,
+                    metric: math.mul(repeats, gain)?,
+                });
+                self.skipped = true;
+            }
+        }
+
+        let sighting = self.seen.entry(key).or_insert(Sighting {
+            count: 0,
+            step,
+            metric,
+        });
+        sighting.count += 1;
+        sighting.step = step;
+        sighting.metric = metric;
+
+        Ok(skip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brent_and_floyd_find_the_same_cycle() {
+        // 0, 1, 2, 3, then 4..=10 forever.
+        let step = |&x: &u32| if x == 10 { 4 } else { x + 1 };
+        let expected = Cycle {
+            start: 4,
+            period: 7,
+        };
+        assert_eq!(brent(0, step), expected);
+        assert_eq!(floyd(0, step), expected);
+        assert_eq!(expected.equivalent_step(3), 3);
+        assert_eq!(expected.equivalent_step(4 + 7 * 1000 + 2), 6);
+
+        let step = |&x: &u64| (x * x + 1) % 255;
+        for start in 0..255 {
+            assert_eq!(brent(start, step), floyd(start, step), "from {start}");
+        }
+    }
+
+    #[test]
+    fn fast_forwards_a_growing_metric() {
+        // Every step adds the key to the metric, and the keys go 0, 1, 2 around and around, so the
+        // metric grows by 3 every 3 steps.
+        let mut fast_forward = FastForward::new(1);
+        let (mut step, mut metric) = (0, 0);
+        let target = 1000;
+        let mut skips = Vec::new();
+        while step < target {
+            let key = step % 3;
+            metric += key;
+            if let Some(skip) = fast_forward
+                .observe(key, step, metric, target, MathMode::Checked)
+                .unwrap()
+            {
+                step += skip.steps;
+                metric += skip.metric;
+                skips.push(skip);
+            }
+            step += 1;
+        }
+
+        assert_eq!(step, target);
+        assert_eq!(metric, (0..target).map(|step| step % 3).sum::<usize>());
+        assert!(fast_forward.skipped());
+        assert_eq!(skips.len(), 1);
+        assert_eq!((skips[0].period, skips[0].gain), (3, 3));
+    }
+}
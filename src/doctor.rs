@@ -0,0 +1,496 @@
+//! Checking that everything is in place to solve challenges, for `aoc2022 doctor`.
+//!
+//! The report covers the puzzle inputs, whether an Advent of Code session token is configured, and
+//! whether the terminal can show the `--visualize` UIs and the characters that pictures are drawn
+//! with. Every check that doesn't pass comes with a suggestion for fixing it.
+
+#[cfg(feature = "tui")]
+use std::io::{self, IsTerminal};
+use std::{env, fmt, fs, path::Path};
+
+use crate::{
+    challenge::{
+        crypt::{self, Secret},
+        find_default_challenge_input_file, html_tag, ChallengeNumber, GetChallengeInputError,
+        Subchallenge,
+    },
+    glyphs,
+};
+
+/// The environment variable that tools for downloading puzzle inputs read the session token from.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+
+/// How many hex digits are in an Advent of Code session cookie.
+const SESSION_LEN: usize = 128;
+
+/// The fewest colors that the visualizations are drawn properly with.
+#[cfg(feature = "tui")]
+const MIN_COLORS: u16 = 8;
+
+/// What `aoc2022 doctor` found.
+#[derive(Debug)]
+pub struct DoctorReport {
+    /// The input files for every challenge with a solver, for subchallenges A and B.
+    pub inputs: Vec<(ChallengeNumber, [InputStatus; 2])>,
+    pub checks: Vec<Check>,
+}
+
+/// The state of one challenge's input file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputStatus {
+    /// There's no input file, which is fine for challenges that haven't been tried yet.
+    Missing,
+    /// The input can be read.
+    Ok,
+    /// The input is encrypted, and can be decrypted with the key that's available.
+    Encrypted,
+    /// The input is encrypted, but there's no key to decrypt it with.
+    NoKey,
+    /// The input can't be used, for the given reason.
+    Bad(String),
+}
+
+/// How a check came out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ok,
+    /// Something that only gets in the way of some of the features.
+    Warning,
+    /// Something that stops challenges from being solved.
+    Problem,
+}
+
+/// One thing that was checked.
+#[derive(Debug)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about it, unless the check passed.
+    pub suggestion: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Ok,
+            detail: detail.into(),
+            suggestion: None,
+        }
+    }
+
+    fn failed(
+        status: Status,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        suggestion: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            suggestion: Some(suggestion.into()),
+        }
+    }
+}
+
+impl DoctorReport {
+    /// Check the inputs for `challenges` in `input_dir`, the session token (from the environment
+    /// or `session_file`), and the terminal.
+    pub fn run(
+        input_dir: &Path,
+        challenges: &[ChallengeNumber],
+        session_file: Option<&Path>,
+    ) -> Self {
+        let (secret, key_check) = match Secret::from_env() {
+            Ok(secret) => (secret, None),
+            Err(e) => {
+                let check = Check::failed(
+                    Status::Problem,
+                    "input key",
+                    e.to_string(),
+                    format!("Check that `{}` names a readable file.", crypt::KEYFILE_VAR),
+                );
+                (None, Some(check))
+            }
+        };
+
+        let mut report = Self::check_inputs(input_dir, challenges, secret.as_ref());
+        report.checks.extend(key_check);
+        report.checks.push(check_session(session_file));
+        report.checks.extend(check_terminal());
+        report.checks.push(check_glyphs());
+        report
+    }
+
+    /// Check the inputs for `challenges` in `input_dir`, decrypting any encrypted ones with
+    /// `secret`.
+    pub fn check_inputs(
+        input_dir: &Path,
+        challenges: &[ChallengeNumber],
+        secret: Option<&Secret>,
+    ) -> Self {
+        let mut report = Self {
+            inputs: Vec::new(),
+            checks: Vec::new(),
+        };
+
+        if !input_dir.is_dir() {
+            report.checks.push(Check::failed(
+                Status::Problem,
+                "input directory",
+                format!("{} doesn't exist", input_dir.display()),
+                format!(
+                    "Create `{}` and save each puzzle input in it, named like `01a.txt`.",
+                    input_dir.display()
+                ),
+            ));
+            return report;
+        }
+
+        for &challenge in challenges {
+            let statuses = [Subchallenge::A, Subchallenge::B].map(|subchallenge| {
+                check_input(
+                    input_dir,
+                    challenge,
+                    subchallenge,
+                    secret,
+                    &mut report.checks,
+                )
+            });
+            report.inputs.push((challenge, statuses));
+        }
+
+        let found = report
+            .inputs
+            .iter()
+            .flat_map(|(_, statuses)| statuses)
+            .filter(|status| **status != InputStatus::Missing)
+            .count();
+        report.checks.insert(
+            0,
+            Check::ok(
+                "input directory",
+                format!("{} has {found} puzzle inputs", input_dir.display()),
+            ),
+        );
+        report
+    }
+
+    /// The worst way that any check came out.
+    pub fn status(&self) -> Status {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(Status::Ok)
+    }
+
+    /// Whether anything stops challenges from being solved.
+    pub fn is_err(&self) -> bool {
+        self.status() == Status::Problem
+    }
+
+    /// The checks that found a problem.
+    pub fn problems(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == Status::Problem)
+    }
+}
+
+/// Check one challenge's input file, adding a check for it if anything's wrong.
+fn check_input(
+    input_dir: &Path,
+    challenge: ChallengeNumber,
+    subchallenge: Subchallenge,
+    secret: Option<&Secret>,
+    checks: &mut Vec<Check>,
+) -> InputStatus {
+    let path = match find_default_challenge_input_file(input_dir, challenge, subchallenge) {
+        Ok(path) => path,
+        Err(GetChallengeInputError::DefaultInputFileLocationError { .. }) => {
+            return InputStatus::Missing
+        }
+        Err(e) => {
+            checks.push(Check::failed(
+                Status::Problem,
+                input_dir.display().to_string(),
+                format!("can't be searched: {e}"),
+                "Check the directory's permissions.",
+            ));
+            return InputStatus::Bad(e.to_string());
+        }
+    };
+    let name = path.display().to_string();
+
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            checks.push(Check::failed(
+                Status::Problem,
+                &name,
+                format!("can't be read: {e}"),
+                "Check the file's permissions.",
+            ));
+            return InputStatus::Bad(e.to_string());
+        }
+    };
+
+    let (contents, status) = if crypt::is_encrypted(&path) {
+        let Some(secret) = secret else {
+            checks.push(Check::failed(
+                Status::Warning,
+                &name,
+                "is encrypted, but no key was given",
+                format!(
+                    "Set `{}` to the passphrase, or `{}` to the path of the keyfile.",
+                    crypt::PASSPHRASE_VAR,
+                    crypt::KEYFILE_VAR
+                ),
+            ));
+            return InputStatus::NoKey;
+        };
+        match crypt::decrypt(&contents, secret) {
+            Ok(contents) => (contents, InputStatus::Encrypted),
+            Err(e) => {
+                checks.push(Check::failed(
+                    Status::Problem,
+                    &name,
+                    e.to_string(),
+                    "Check that the key is the one the inputs were encrypted with.",
+                ));
+                return InputStatus::Bad(e.to_string());
+            }
+        }
+    } else {
+        (contents, InputStatus::Ok)
+    };
+
+    if let Err(e) = std::str::from_utf8(&contents) {
+        let detail = format!("isn't valid UTF-8: {e}");
+        checks.push(Check::failed(
+            Status::Problem,
+            &name,
+            &detail,
+            "Download the input again, and save it as plain text.",
+        ));
+        return InputStatus::Bad(detail);
+    }
+    if html_tag(&contents).is_some() {
+        let detail = "looks like a web page, not a puzzle input";
+        checks.push(Check::failed(
+            Status::Problem,
+            &name,
+            detail,
+            format!(
+                "Open https://adventofcode.com/2022/day/{challenge}/input while logged in, and \
+                 save what it shows as plain text."
+            ),
+        ));
+        return InputStatus::Bad(detail.to_string());
+    }
+    status
+}
+
+/// Check whether a session token for downloading inputs is set, or saved in `session_file`, and
+/// looks like one.
+fn check_session(session_file: Option<&Path>) -> Check {
+    let name = "session token";
+    let get_one = "Log in to adventofcode.com, and copy the value of the `session` cookie.";
+    // Where the token came from, and how to say that it's there.
+    let (token, source, found) = match (env::var(SESSION_VAR), session_file) {
+        (Ok(token), _) => (
+            token,
+            format!("`{SESSION_VAR}`"),
+            format!("`{SESSION_VAR}` is set"),
+        ),
+        (Err(_), Some(file)) => match fs::read_to_string(file) {
+            Ok(token) => (
+                token.trim().to_string(),
+                file.display().to_string(),
+                format!("saved in {}", file.display()),
+            ),
+            Err(e) => {
+                return Check::failed(
+                    Status::Warning,
+                    name,
+                    format!("can't read {}: {e}", file.display()),
+                    format!("{get_one} Then save it to {}.", file.display()),
+                )
+            }
+        },
+        (Err(_), None) => {
+            return Check::failed(
+                Status::Warning,
+                name,
+                format!("`{SESSION_VAR}` isn't set, so inputs have to be downloaded by hand"),
+                format!("{get_one} Then set `{SESSION_VAR}` to it."),
+            )
+        }
+    };
+    if token.len() == SESSION_LEN && token.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Check::ok(name, found)
+    } else {
+        Check::failed(
+            Status::Warning,
+            name,
+            format!("{source} doesn't look like a session token"),
+            format!("{get_one} It should be {SESSION_LEN} hex digits long."),
+        )
+    }
+}
+
+/// Check whether the terminal can show the `--visualize` UIs.
+#[cfg(feature = "tui")]
+fn check_terminal() -> Vec<Check> {
+    if !io::stdout().is_terminal() {
+        return vec![Check::failed(
+            Status::Warning,
+            "terminal",
+            "stdout isn't a terminal, so `--visualize` won't work",
+            "Run `aoc2022` straight from a terminal, without redirecting its output.",
+        )];
+    }
+
+    let raw_mode = match crossterm::terminal::enable_raw_mode()
+        .and_then(|()| crossterm::terminal::disable_raw_mode())
+    {
+        Ok(()) => Check::ok("terminal", "supports raw mode"),
+        Err(e) => Check::failed(
+            Status::Warning,
+            "terminal",
+            format!("can't switch to raw mode, so `--visualize` won't work: {e}"),
+            "Try another terminal emulator.",
+        ),
+    };
+
+    let colors = crossterm::style::available_color_count();
+    let colors = if colors >= MIN_COLORS {
+        Check::ok("colors", format!("{colors} colors"))
+    } else {
+        Check::failed(
+            Status::Warning,
+            "colors",
+            format!("only {colors} colors, so visualizations will be hard to make out"),
+            "Set `TERM` to one that supports colors, like `xterm-256color`.",
+        )
+    };
+
+    vec![raw_mode, colors]
+}
+
+/// Check whether the terminal seems to be able to show the Unicode glyphs that pictures are drawn
+/// with, or whether they'll be drawn with ASCII instead.
+fn check_glyphs() -> Check {
+    if glyphs::unicode_supported() {
+        return Check::ok("glyphs", "Unicode blocks, braille and box lines");
+    }
+    let suggestion = if cfg!(windows) {
+        "Run `aoc2022` in Windows Terminal to see the pictures in full."
+    } else {
+        "Set a UTF-8 locale, like `LANG=en_US.UTF-8`, to see the pictures in full."
+    };
+    Check::failed(
+        Status::Warning,
+        "glyphs",
+        "the terminal may not show Unicode blocks or braille, so pictures are drawn with ASCII",
+        suggestion,
+    )
+}
+
+/// There's no terminal UI to check on without the `tui` feature.
+#[cfg(not(feature = "tui"))]
+fn check_terminal() -> Vec<Check> {
+    vec![Check::failed(
+        Status::Warning,
+        "terminal",
+        "built without the `tui` feature, so `--visualize` won't work",
+        "Rebuild with `cargo build --features tui`.",
+    )]
+}
+
+impl fmt::Display for InputStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let word = match self {
+            Self::Missing => "-",
+            Self::Ok => "ok",
+            Self::Encrypted => "enc",
+            Self::NoKey => "NOKEY",
+            Self::Bad(_) => "BAD",
+        };
+        // Pass the formatter along, so that padding works.
+        word.fmt(f)
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.inputs.is_empty() {
+            writeln!(f, "{:<11}{:<7}b", "challenge", "a")?;
+            for (challenge, [a, b]) in &self.inputs {
+                writeln!(f, "{:<11}{a:<7}{b}", format!("{challenge:02}"))?;
+            }
+            writeln!(f)?;
+        }
+
+        for check in &self.checks {
+            let status = match check.status {
+                Status::Ok => "ok",
+                Status::Warning => "WARN",
+                Status::Problem => "PROBLEM",
+            };
+            writeln!(f, "{status:<9}{}: {}", check.name, check.detail)?;
+            // Problems come back as an error, with their suggestions attached.
+            if let (Status::Warning, Some(suggestion)) = (check.status, &check.suggestion) {
+                writeln!(f, "{:<9}{suggestion}", "")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_every_input() {
+        let dir = env::temp_dir().join(format!("aoc2022-doctor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let secret = Secret::passphrase("hunter2");
+        fs::write(dir.join("01a.txt"), "1000\n2000\n").unwrap();
+        fs::write(dir.join("1b.txt"), b"\xff\xfe").unwrap();
+        fs::write(
+            dir.join("02a.txt.enc"),
+            crypt::encrypt(b"A Y\n", &secret).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("03a.txt"),
+            "<!DOCTYPE html>\n<html lang=\"en-us\">\n",
+        )
+        .unwrap();
+
+        let report = DoctorReport::check_inputs(&dir, &[1, 2, 3], Some(&secret));
+        assert_eq!(report.inputs[0].1[0], InputStatus::Ok);
+        assert!(matches!(report.inputs[0].1[1], InputStatus::Bad(_)));
+        assert_eq!(
+            report.inputs[1].1,
+            [InputStatus::Encrypted, InputStatus::Missing]
+        );
+        assert!(matches!(report.inputs[2].1[0], InputStatus::Bad(_)));
+        assert!(report.is_err());
+        assert_eq!(report.problems().count(), 2);
+
+        let report = DoctorReport::check_inputs(&dir, &[2], None);
+        assert_eq!(report.inputs[0].1[0], InputStatus::NoKey);
+        assert_eq!(report.status(), Status::Warning);
+
+        fs::remove_dir_all(&dir).unwrap();
+        let report = DoctorReport::check_inputs(&dir, &[1], None);
+        assert!(report.inputs.is_empty());
+        assert!(report.is_err());
+    }
+}
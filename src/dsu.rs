@@ -0,0 +1,202 @@
+//! Disjoint sets (union-find), for splitting things up into connected components.
+//!
+//! Elements are numbered `0..len`, so anything that can be flattened into an index can be grouped:
+//! a [`Grid`]'s cells by [`label_grid`], or the voxels of a [`Bounds`](crate::voxel::Bounds) in the
+//! order of its `iter`. Finding an element's set compresses the path to its root, and merging two
+//! sets hangs the smaller one under the bigger one, so both take close to constant time.
+//!
+//! Built for day 18's pockets of vacuum, and meant for measuring regions on day 23 as well.
+
+use crate::grid::{Grid, GridCoord};
+
+/// A partition of the elements `0..len` into disjoint sets.
+///
+/// Every element starts off in a set of its own. Each set is represented by one of its elements,
+/// its root, which [`DisjointSets::find`] returns for any element in the set.
+#[derive(Debug, Clone)]
+pub(crate) struct DisjointSets {
+    /// The element that each element hangs under, or itself if it's a root.
+    parent: Vec<usize>,
+    /// How many elements are in each root's set. Only kept up to date for roots.
+    size: Vec<usize>,
+    /// How many sets there are.
+    count: usize,
+}
+
+impl DisjointSets {
+    /// Put each of `len` elements in a set of its own.
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            count: len,
+        }
+    }
+
+    /// How many elements there are, in all sets together.
+    pub(crate) fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// The root of the set that `element` is in.
+    ///
+    /// Every element on the way to the root gets hung directly under it, so that finding any of
+    /// them again takes a single step.
+    pub(crate) fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut current = element;
+        while current != root {
+            current = std::mem::replace(&mut self.parent[current], root);
+        }
+        root
+    }
+
+    /// Merge the sets that `a` and `b` are in. Returns `false` if they were already in the same
+    /// set.
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        let (big, small) = if self.size[a] >= self.size[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[small] = big;
+        self.size[big] += self.size[small];
+        self.count -= 1;
+        true
+    }
+
+    /// The elements of every set, in increasing order.
+    ///
+    /// The sets are ordered by their smallest element, so the set holding element `0` (if there
+    /// is one) always comes first.
+    pub(crate) fn components(&mut self) -> impl Iterator<Item = Vec<usize>> {
+        // Where each root's set goes in the output, once it's come up.
+        let mut slots = vec![usize::MAX; self.len()];
+        let mut components: Vec<Vec<usize>> = Vec::with_capacity(self.count);
+
+        for element in 0..self.len() {
+            let root = self.find(element);
+            if slots[root] == usize::MAX {
+                slots[root] = components.len();
+                components.push(Vec::with_capacity(self.size[root]));
+            }
+            components[slots[root]].push(element);
+        }
+        components.into_iter()
+    }
+}
+
+// Only the tests ask about single sets so far. These are for measuring day 23's regions.
+#[allow(dead_code)]
+impl DisjointSets {
+    /// How many sets the elements are split up into.
+    pub(crate) const fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Whether `a` and `b` are in the same set.
+    pub(crate) fn same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// How many elements are in the same set as `element`, including itself.
+    pub(crate) fn size_of(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    /// The root of every set, along with how many elements are in it, in order of the roots.
+    pub(crate) fn sizes(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.parent
+            .iter()
+            .enumerate()
+            .filter(|&(element, &parent)| element == parent)
+            .map(|(root, _)| (root, self.size[root]))
+    }
+}
+
+/// Group the cells of `grid` that are connected to each other through `joined` neighbors.
+///
+/// Cells are numbered like [`Grid::data`], one row after another, and two cells that share an
+/// edge end up in the same set if `joined` says so for them. To leave some cells out, like walls,
+/// have `joined` turn them away; they'll then be in sets of their own.
+#[allow(dead_code)] // Only the tests label a grid until day 23 is solved.
+pub(crate) fn label_grid<T>(grid: &Grid<T>, joined: impl Fn(&T, &T) -> bool) -> DisjointSets {
+    let mut sets = DisjointSets::new(grid.num_cells());
+    for (index, cell) in grid.data.iter().enumerate() {
+        let coord = GridCoord {
+            x: index % grid.width,
+            y: index / grid.width,
+        };
+        let right = (coord.x + 1 < grid.width).then_some(index + 1);
+        let down = (coord.y + 1 < grid.height).then_some(index + grid.width);
+
+        for neighbor in right.into_iter().chain(down) {
+            if joined(cell, &grid.data[neighbor]) {
+                sets.union(index, neighbor);
+            }
+        }
+    }
+    sets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_sets() {
+        let mut sets = DisjointSets::new(6);
+        assert_eq!(sets.count(), 6);
+
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(
+            !sets.union(0, 2),
+            "0 and 2 were already joined through 1 and 3"
+        );
+
+        assert_eq!(sets.count(), 3);
+        assert!(sets.same(0, 3));
+        assert!(!sets.same(0, 4));
+        assert_eq!(sets.size_of(2), 4);
+        assert_eq!(sets.size_of(5), 1);
+
+        let mut sizes = sets.sizes().map(|(_, size)| size).collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, [1, 1, 4]);
+        assert_eq!(
+            sets.components().collect::<Vec<_>>(),
+            [vec![0, 1, 2, 3], vec![4], vec![5]]
+        );
+    }
+
+    #[test]
+    fn labels_grid_regions() {
+        let picture = "##..\n\
+                       #..#\n\
+                       ..##\n";
+        let grid = Grid {
+            width: 4,
+            height: 3,
+            data: picture.bytes().filter(|&b| b != b'\n').collect(),
+        };
+
+        let mut sets = label_grid(&grid, |a, b| a == b);
+        let components = sets.components().collect::<Vec<_>>();
+        assert_eq!(
+            components,
+            [vec![0, 1, 4], vec![2, 3, 5, 6, 8, 9], vec![7, 10, 11]]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! The error type returned by the library's solvers.
+//!
+//! The binary turns these into pretty reports with `color_eyre`, but nothing in the library
+//! depends on it, so callers that embed the solvers can match on what went wrong.
+
+use std::{fmt, io, num::ParseIntError};
+
+use crate::{math::MathError, parse::BadInputError, sync::Cancelled};
+
+/// Everything that can go wrong while a solver is running.
+#[derive(thiserror::Error, miette::Diagnostic, Debug)]
+pub enum SolverError {
+    /// The input didn't match the format described in the puzzle.
+    #[error("{context}")]
+    Parse {
+        context: String,
+        #[source]
+        #[diagnostic_source]
+        source: BadInputError,
+    },
+
+    /// Reading the input, or talking to the terminal, failed.
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// The input is well-formed, but doesn't make sense for the puzzle.
+    #[error("{0}")]
+    InvalidInput(String),
+
+    /// The solver doesn't handle this input, or this way of running it.
+    #[error("{0}")]
+    Unsupported(String),
+
+    /// The solver was asked to stop before it finished.
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+
+    /// Arithmetic overflowed under [`MathMode::Checked`](crate::math::MathMode::Checked), or
+    /// divided by zero.
+    #[error(transparent)]
+    Math(#[from] MathError),
+}
+
+impl From<io::Error> for SolverError {
+    fn from(source: io::Error) -> Self {
+        Self::Io {
+            context: "Could not read challenge input".to_string(),
+            source,
+        }
+    }
+}
+
+impl From<BadInputError> for SolverError {
+    fn from(source: BadInputError) -> Self {
+        Self::Parse {
+            context: "Could not parse challenge input".to_string(),
+            source,
+        }
+    }
+}
+
+impl From<ParseIntError> for SolverError {
+    fn from(err: ParseIntError) -> Self {
+        Self::InvalidInput(format!("Could not parse a number: {err}"))
+    }
+}
+
+impl SolverError {
+    /// Shorthand for [`SolverError::InvalidInput`].
+    pub fn invalid_input(message: impl fmt::Display) -> Self {
+        Self::InvalidInput(message.to_string())
+    }
+
+    /// Put `context` in front of the error's message.
+    fn prefixed(self, context: &str) -> Self {
+        match self {
+            Self::Parse {
+                context: inner,
+                source,
+            } => Self::Parse {
+                context: format!("{context}: {inner}"),
+                source,
+            },
+            Self::Io {
+                context: inner,
+                source,
+            } => Self::Io {
+                context: format!("{context}: {inner}"),
+                source,
+            },
+            Self::InvalidInput(inner) => Self::InvalidInput(format!("{context}: {inner}")),
+            Self::Unsupported(inner) => Self::Unsupported(format!("{context}: {inner}")),
+            // Cancelling is the same no matter where it happened.
+            Self::Cancelled(c) => Self::Cancelled(c),
+            // The error already points at the line it happened on.
+            Self::Math(e) => Self::Math(e),
+        }
+    }
+}
+
+/// Describe what was being done when an error happened, like `color_eyre`'s `wrap_err`.
+pub(crate) trait Context<T> {
+    fn context(self, context: &str) -> Result<T, SolverError>;
+
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T, SolverError>;
+}
+
+impl<T> Context<T> for Result<T, io::Error> {
+    fn context(self, context: &str) -> Result<T, SolverError> {
+        self.with_context(|| context.to_string())
+    }
+
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T, SolverError> {
+        self.map_err(|source| SolverError::Io {
+            context: context(),
+            source,
+        })
+    }
+}
+
+impl<T> Context<T> for Result<T, BadInputError> {
+    fn context(self, context: &str) -> Result<T, SolverError> {
+        self.with_context(|| context.to_string())
+    }
+
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T, SolverError> {
+        self.map_err(|source| SolverError::Parse {
+            context: context(),
+            source,
+        })
+    }
+}
+
+impl<T> Context<T> for Result<T, ParseIntError> {
+    fn context(self, context: &str) -> Result<T, SolverError> {
+        self.with_context(|| context.to_string())
+    }
+
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T, SolverError> {
+        self.map_err(|err| SolverError::InvalidInput(format!("{}: {err}", context())))
+    }
+}
+
+impl<T> Context<T> for Result<T, SolverError> {
+    fn context(self, context: &str) -> Result<T, SolverError> {
+        self.map_err(|e| e.prefixed(context))
+    }
+
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T, SolverError> {
+        self.map_err(|e| e.prefixed(&context()))
+    }
+}
+
+/// Shorthand for returning a [`SolverError::InvalidInput`], like `color_eyre`'s `bail!`.
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err($crate::error::SolverError::InvalidInput(format!($($arg)*)))
+    };
+}
+pub(crate) use bail;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_is_prefixed() {
+        let io: Result<(), _> = Err(io::Error::other("disk on fire"));
+        let err = io.context("Could not read input").unwrap_err();
+        assert!(
+            matches!(&err, SolverError::Io { context, .. } if context == "Could not read input")
+        );
+
+        let nested: Result<(), _> = Err(err);
+        let err = nested.context("Could not solve").unwrap_err();
+        assert_eq!(err.to_string(), "Could not solve: Could not read input");
+
+        let invalid: Result<(), _> = Err(SolverError::invalid_input("no `root` monkey"));
+        let err = invalid.with_context(|| "monkey 3".to_string()).unwrap_err();
+        assert!(
+            matches!(err, SolverError::InvalidInput(msg) if msg == "monkey 3: no `root` monkey")
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Step-by-step explanations of how a solver got to its answer, for `--explain`.
+//!
+//! Solvers explain themselves with [`explain!`], which emits a [`tracing`] event on its own
+//! [`TARGET`], so that explanations never get mixed up with any other logging. Nothing is printed
+//! (or even formatted) unless an [`ExplainLayer`] is listening, which is only the case when
+//! `--explain` is given.
+//!
+//! Explanations are meant for the small examples in the puzzle descriptions. A full puzzle input
+//! would explain itself for thousands of lines, so the layer stops after a set number of them.
+
+use std::{fmt, io, sync::Mutex};
+
+use tracing::{
+    field::{Field, Visit},
+    Event, Metadata, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    Layer, Registry,
+};
+
+/// The target that every explanation is emitted on.
+pub const TARGET: &str = "explain";
+
+/// How many lines of explanation to print, unless told otherwise.
+pub const DEFAULT_MAX_LINES: usize = 200;
+
+/// Explain a step of the solution, with the same arguments as [`format!`].
+///
+/// Nested steps should be indented by their depth, so that the explanation reads like the
+/// examples in the puzzle descriptions.
+macro_rules! explain {
+    ($($arg:tt)+) => {
+        ::tracing::info!(target: $crate::explain::TARGET, $($arg)+)
+    };
+}
+pub(crate) use explain;
+
+/// Whether anything is listening for explanations, for solvers that need to do extra work to
+/// explain themselves.
+pub(crate) fn enabled() -> bool {
+    tracing::enabled!(target: TARGET, tracing::Level::INFO)
+}
+
+/// Prints explanations to a writer, one message per line, until `max_lines` lines have been
+/// printed.
+pub struct ExplainLayer<W> {
+    max_lines: usize,
+    out: Mutex<Output<W>>,
+}
+
+struct Output<W> {
+    writer: W,
+    lines: usize,
+}
+
+impl<W: io::Write> ExplainLayer<W> {
+    pub fn new(writer: W, max_lines: usize) -> Self {
+        Self {
+            max_lines,
+            out: Mutex::new(Output { writer, lines: 0 }),
+        }
+    }
+
+    fn print(&self, message: &str) -> io::Result<()> {
+        let mut out = self
+            .out
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for line in message.split('\n') {
+            if out.lines < self.max_lines {
+                writeln!(out.writer, "{line}")?;
+            } else if out.lines == self.max_lines {
+                writeln!(
+                    out.writer,
+                    "… (explanation cut off after {} lines)",
+                    self.max_lines
+                )?;
+            }
+            out.lines += 1;
+        }
+        Ok(())
+    }
+}
+
+impl<W, S> Layer<S> for ExplainLayer<W>
+where
+    W: io::Write + Send + 'static,
+    S: Subscriber,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        metadata.target() == TARGET
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        // There's nowhere left to report a failure to explain, and it shouldn't stop the solver.
+        let _ = self.print(&message.0);
+    }
+}
+
+/// Collects an event's message, followed by any other fields as `key=value`.
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        use fmt::Write;
+
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+/// A subscriber that prints every explanation to `writer`, up to `max_lines` lines.
+///
+/// Install it with [`tracing::subscriber::set_global_default`], since solvers may run on
+/// another thread.
+pub fn subscriber<W>(writer: W, max_lines: usize) -> impl Subscriber + Send + Sync
+where
+    W: io::Write + Send + 'static,
+{
+    Registry::default().with(ExplainLayer::new(writer, max_lines))
+}
+
+/// Everything explained while running `f` on this thread, up to `max_lines` lines.
+#[cfg(test)]
+pub(crate) fn capture(max_lines: usize, f: impl FnOnce()) -> String {
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Buffer::default();
+    tracing::subscriber::with_default(subscriber(buffer.clone(), max_lines), f);
+    let bytes = buffer.0.lock().unwrap().clone();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_off_long_explanations() {
+        let explained = capture(3, || {
+            assert!(enabled());
+            explain!("first");
+            explain!("second\nthird");
+            explain!(count = 4, "fourth");
+            explain!("fifth");
+            tracing::info!("not an explanation");
+        });
+
+        assert_eq!(
+            explained,
+            "first\nsecond\nthird\n… (explanation cut off after 3 lines)\n"
+        );
+        assert!(!enabled());
+    }
+}
@@ -0,0 +1,205 @@
+//! Shapes in [taxicab geometry][wiki], where distance is measured along the grid.
+//!
+//! [wiki]: https://en.wikipedia.org/wiki/Taxicab_geometry
+
+use std::ops::RangeInclusive;
+
+/// A point on the integer grid, as `(x, y)`.
+pub(crate) type Point = (i64, i64);
+
+/// The number of steps between two points, moving along the grid.
+pub(crate) const fn manhattan((ax, ay): Point, (bx, by): Point) -> u64 {
+    ax.abs_diff(bx) + ay.abs_diff(by)
+}
+
+/// The number of steps between two points, if diagonal steps are allowed as well as steps along
+/// the grid.
+pub(crate) const fn chebyshev((ax, ay): Point, (bx, by): Point) -> u64 {
+    let (dx, dy) = (ax.abs_diff(bx), ay.abs_diff(by));
+    if dx > dy {
+        dx
+    } else {
+        dy
+    }
+}
+
+/// Rotate a point by 45°, into coordinates `(u, v) = (x + y, x - y)`.
+///
+/// In rotated coordinates, a [`Diamond`] becomes an axis-aligned [`Square`].
+pub(crate) const fn rotate((x, y): Point) -> Point {
+    (x + y, x - y)
+}
+
+/// Undo [`rotate`].
+///
+/// Only half of all `(u, v)` pairs line up with a point on the original grid (`u` and `v` must
+/// both be even or both be odd), so this returns `None` for the other half.
+pub(crate) const fn unrotate((u, v): Point) -> Option<Point> {
+    if (u + v) % 2 != 0 {
+        return None;
+    }
+    Some(((u + v) / 2, (u - v) / 2))
+}
+
+/// Every point within `radius` steps of `center`: a ball in taxicab geometry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Diamond {
+    pub(crate) center: Point,
+    pub(crate) radius: i64,
+}
+
+impl Diamond {
+    pub(crate) const fn new(center: Point, radius: i64) -> Self {
+        Self { center, radius }
+    }
+
+    /// The span of x-coordinates that the diamond covers in row `y`, if it reaches that far.
+    pub(crate) fn row(&self, y: i64) -> Option<RangeInclusive<i64>> {
+        let half_width = self.radius - (y - self.center.1).abs();
+        (half_width >= 0).then(|| self.center.0 - half_width..=self.center.0 + half_width)
+    }
+
+    /// Every point exactly `radius` steps from the center, going clockwise from the eastern
+    /// corner.
+    pub(crate) fn perimeter(&self) -> impl Iterator<Item = Point> {
+        let Self {
+            center: (cx, cy),
+            radius: r,
+        } = *self;
+
+        // Each edge starts on a corner, and stops just short of the next one. A diamond with a
+        // radius of zero is a single point.
+        let edge_len = r.max(1);
+        (0..4 * r).chain((r == 0).then_some(0)).map(move |step| {
+            let i = step % edge_len;
+            let (dx, dy) = match step / edge_len {
+                0 => (r - i, i),
+                1 => (-i, r - i),
+                2 => (i - r, -i),
+                _ => (i, i - r),
+            };
+            (cx + dx, cy + dy)
+        })
+    }
+
+    /// The axis-aligned square that this diamond turns into after [`rotate`]ing it.
+    pub(crate) const fn rotated(&self) -> Square {
+        let (u, v) = rotate(self.center);
+        Square {
+            u: (u - self.radius, u + self.radius),
+            v: (v - self.radius, v + self.radius),
+        }
+    }
+}
+
+/// An axis-aligned square in [`rotate`]d coordinates, as inclusive `(min, max)` bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Square {
+    pub(crate) u: (i64, i64),
+    pub(crate) v: (i64, i64),
+}
+
+impl Square {
+    pub(crate) const fn contains(&self, (u, v): Point) -> bool {
+        self.u.0 <= u && u <= self.u.1 && self.v.0 <= v && v <= self.v.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use nom::character::complete::{i32 as nom_i32, i64 as nom_i64};
+    use proptest::prelude::*;
+
+    use crate::parse::{
+        parse_input, parse_point2, parse_point3,
+        round_trip::{self, EXTREME_I32, EXTREME_I64},
+        PointFormat,
+    };
+
+    use super::*;
+
+    #[test]
+    fn perimeter_is_exactly_radius_away() {
+        let diamond = Diamond::new((3, -2), 4);
+        let perimeter = diamond.perimeter().collect::<Vec<_>>();
+
+        assert_eq!(perimeter.len(), 16);
+        assert_eq!(perimeter[0], (7, -2));
+        for point in perimeter {
+            assert_eq!(manhattan(point, (3, -2)), 4);
+        }
+        assert_eq!(
+            Diamond::new((1, 1), 0).perimeter().collect::<Vec<_>>(),
+            vec![(1, 1)]
+        );
+    }
+
+    #[test]
+    fn rows() {
+        let diamond = Diamond::new((0, 0), 2);
+        assert_eq!(diamond.row(0), Some(-2..=2));
+        assert_eq!(diamond.row(-2), Some(0..=0));
+        assert_eq!(diamond.row(3), None);
+    }
+
+    #[test]
+    fn rotation_preserves_containment() {
+        let diamond = Diamond::new((2, 5), 3);
+        let square = diamond.rotated();
+        for x in -3_i64..8 {
+            for y in 0_i64..11 {
+                let in_diamond = (x - 2).abs() + (y - 5).abs() <= 3;
+                assert_eq!(in_diamond, square.contains(rotate((x, y))));
+                assert_eq!(unrotate(rotate((x, y))), Some((x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn points_survive_being_written_and_parsed() {
+        let wide = || EXTREME_I64.into_iter().cartesian_product(EXTREME_I64);
+        let narrow = || EXTREME_I32.into_iter().cartesian_product(EXTREME_I32);
+
+        for format in [PointFormat::COMMAS, PointFormat::LABELLED] {
+            round_trip::check(
+                any::<Point>(),
+                wide(),
+                |written, &point| format.write2(written, point),
+                |written| parse_input(written, parse_point2(format, nom_i64)).ok(),
+            );
+            round_trip::check(
+                any::<(i64, i64, i64)>(),
+                wide().map(|(a, b)| (a, b, a)),
+                |written, &point| format.write3(written, point),
+                |written| parse_input(written, parse_point3(format, nom_i64)).ok(),
+            );
+            round_trip::check(
+                any::<(i32, i32, i32)>(),
+                narrow().map(|(x, y)| (x, y, x)),
+                |written, &point| format.write3(written, point),
+                |written| parse_input(written, parse_point3(format, nom_i32)).ok(),
+            );
+        }
+    }
+
+    #[test]
+    fn components_too_big_for_their_type_dont_parse() {
+        let limits = [i64::from(i32::MIN) - 1, i64::from(i32::MAX) + 1];
+        for too_big in limits {
+            let mut written = String::new();
+            PointFormat::COMMAS
+                .write2(&mut written, (0, too_big))
+                .unwrap();
+            assert!(
+                parse_input(&written, parse_point2(PointFormat::COMMAS, nom_i32)).is_err(),
+                "{written} shouldn't fit in an i32"
+            );
+        }
+        assert!(parse_input(
+            "x=9223372036854775808, y=0",
+            parse_point2(PointFormat::LABELLED, nom_i64)
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Which characters to draw pictures with, as picked with `--ascii`.
+//!
+//! Day 17's tower, day 10's screen and the other pictures are drawn with Unicode block, braille
+//! and box-drawing characters. Some terminals, like the old Windows console or a Linux virtual
+//! console, show those as boxes or question marks, so anything that draws them gets its
+//! characters from [`glyphs`], which falls back to plain [`ASCII`] ones. Like with
+//! [`color`](crate::color), which set is in effect is set for the whole crate by [`set`], since
+//! [`Display`](std::fmt::Display) impls have nowhere else to get it from.
+
+use std::{
+    env,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// The characters that pictures are drawn with.
+#[derive(Debug, Clone, Copy)]
+pub struct Glyphs {
+    /// A whole character cell filled in.
+    pub full: char,
+    /// The upper half of a cell filled in.
+    pub upper: char,
+    /// The lower half of a cell filled in.
+    pub lower: char,
+    /// Like [`Glyphs::full`], [`Glyphs::upper`] and [`Glyphs::lower`], but dotted, to stand out
+    /// from solid ones next to them.
+    pub dotted_full: char,
+    pub dotted_upper: char,
+    pub dotted_lower: char,
+    /// A lightly shaded cell, for empty space that should still show up.
+    pub shade: char,
+    /// A small ball, like a grain of sand.
+    pub ball: char,
+    /// The corners of a box, clockwise from the top left.
+    pub corners: [char; 4],
+    pub horizontal: char,
+    pub vertical: char,
+    /// How a 2×4 block of dots is drawn, given which dots are set as the bits of a braille
+    /// character's code point.
+    dots: fn(u8) -> char,
+}
+
+/// Unicode blocks, braille and rounded boxes.
+pub const UNICODE: Glyphs = Glyphs {
+    full: '█',
+    upper: '▀',
+    lower: '▄',
+    dotted_full: '⣿',
+    dotted_upper: '⠛',
+    dotted_lower: '⣤',
+    shade: '░',
+    ball: '○',
+    corners: ['╭', '╮', '╯', '╰'],
+    horizontal: '─',
+    vertical: '│',
+    dots: braille,
+};
+
+/// Plain ASCII, which every terminal can show.
+pub const ASCII: Glyphs = Glyphs {
+    full: '#',
+    upper: '"',
+    lower: ',',
+    dotted_full: '@',
+    dotted_upper: '^',
+    dotted_lower: 'o',
+    shade: '.',
+    ball: 'o',
+    corners: ['+', '+', '+', '+'],
+    horizontal: '-',
+    vertical: '|',
+    dots: ascii_dots,
+};
+
+impl Glyphs {
+    /// A character for a 2×4 block of dots, with the dots that are set as the bits of `dots`, in
+    /// the order that braille characters number them.
+    pub fn dots(&self, dots: u8) -> char {
+        (self.dots)(dots)
+    }
+
+    /// The top of a box with room for `width` characters inside of it.
+    pub fn box_top(&self, width: usize) -> String {
+        self.box_edge(width, self.corners[0], self.corners[1])
+    }
+
+    /// The bottom of a box with room for `width` characters inside of it.
+    pub fn box_bottom(&self, width: usize) -> String {
+        self.box_edge(width, self.corners[3], self.corners[2])
+    }
+
+    fn box_edge(&self, width: usize, left: char, right: char) -> String {
+        let side = self.horizontal.to_string().repeat(width);
+        format!("{left}{side}{right}")
+    }
+}
+
+/// The braille character with the dots set in `dots`.
+fn braille(dots: u8) -> char {
+    char::from_u32(0x2800 + u32::from(dots)).expect("all 256 braille patterns are characters")
+}
+
+/// An ASCII character that's about as dark as `dots` has dots set.
+fn ascii_dots(dots: u8) -> char {
+    match dots.count_ones() {
+        0 => ' ',
+        1..=2 => '.',
+        3..=4 => ':',
+        5..=6 => '+',
+        _ => '#',
+    }
+}
+
+/// Which glyphs to draw with: `--ascii` asks for [`GlyphChoice::Ascii`], and otherwise it's
+/// [`GlyphChoice::Auto`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GlyphChoice {
+    /// [`UNICODE`] if the terminal [seems to support it](unicode_supported), and [`ASCII`]
+    /// otherwise.
+    #[default]
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+impl GlyphChoice {
+    /// Whether this choice draws with [`ASCII`], right now.
+    pub fn ascii(self) -> bool {
+        match self {
+            Self::Unicode => false,
+            Self::Ascii => true,
+            Self::Auto => !unicode_supported(),
+        }
+    }
+}
+
+/// What [`glyphs`] says: which set to draw with, or that it hasn't been worked out yet.
+static CHOSEN: AtomicU8 = AtomicU8::new(UNDECIDED);
+
+const UNDECIDED: u8 = 0;
+const CHOSE_UNICODE: u8 = 1;
+const CHOSE_ASCII: u8 = 2;
+
+/// Draw pictures according to `choice` from now on.
+pub fn set(choice: GlyphChoice) {
+    let chosen = if choice.ascii() {
+        CHOSE_ASCII
+    } else {
+        CHOSE_UNICODE
+    };
+    CHOSEN.store(chosen, Ordering::Relaxed);
+}
+
+/// The characters to draw with, as last [`set`], or as [`GlyphChoice::Auto`] would pick if
+/// they've never been set.
+pub fn glyphs() -> &'static Glyphs {
+    match CHOSEN.load(Ordering::Relaxed) {
+        UNDECIDED => {
+            set(GlyphChoice::Auto);
+            glyphs()
+        }
+        CHOSE_ASCII => &ASCII,
+        _ => &UNICODE,
+    }
+}
+
+/// Whether the terminal can probably show the [`UNICODE`] glyphs, going by the environment.
+pub fn unicode_supported() -> bool {
+    unicode_supported_by(cfg!(windows), |name| {
+        env::var(name).ok().filter(|value| !value.is_empty())
+    })
+}
+
+/// [`unicode_supported`], on Windows or not, with the environment variables from `var`.
+fn unicode_supported_by(windows: bool, var: impl Fn(&str) -> Option<String>) -> bool {
+    if windows {
+        // The old console host can't show most of them, but the terminals that can all say who
+        // they are.
+        return ["WT_SESSION", "TERM_PROGRAM", "ConEmuANSI"]
+            .into_iter()
+            .any(|name| var(name).is_some());
+    }
+
+    // The Linux console's font only has a few of them, and a dumb terminal is anyone's guess.
+    if matches!(var("TERM").as_deref(), Some("linux" | "dumb")) {
+        return false;
+    }
+    // The first of these that's set decides the character set. Nothing being set at all is
+    // usually a container or a CI runner, whose output ends up somewhere that can show UTF-8.
+    match ["LC_ALL", "LC_CTYPE", "LANG"].into_iter().find_map(&var) {
+        Some(locale) => {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn probes_the_terminal() {
+        assert!(!unicode_supported_by(true, env(&[])));
+        assert!(!unicode_supported_by(true, env(&[("LANG", "en_US.UTF-8")])));
+        assert!(unicode_supported_by(true, env(&[("WT_SESSION", "1234")])));
+        assert!(unicode_supported_by(
+            true,
+            env(&[("TERM_PROGRAM", "vscode")])
+        ));
+
+        assert!(unicode_supported_by(false, env(&[])));
+        assert!(unicode_supported_by(false, env(&[("LANG", "en_US.UTF-8")])));
+        assert!(unicode_supported_by(false, env(&[("LC_CTYPE", "C.utf8")])));
+        assert!(!unicode_supported_by(false, env(&[("LANG", "C")])));
+        assert!(!unicode_supported_by(
+            false,
+            env(&[("LC_ALL", "POSIX"), ("LANG", "en_US.UTF-8")])
+        ));
+        assert!(!unicode_supported_by(
+            false,
+            env(&[("TERM", "linux"), ("LANG", "en_US.UTF-8")])
+        ));
+    }
+
+    #[test]
+    fn draws_with_either_set() {
+        assert_eq!(UNICODE.box_top(3), "╭───╮");
+        assert_eq!(UNICODE.box_bottom(3), "╰───╯");
+        assert_eq!(ASCII.box_top(3), "+---+");
+        assert_eq!(UNICODE.dots(0b0001_1011), UNICODE.dotted_upper);
+        assert_eq!(UNICODE.dots(0b1110_0100), UNICODE.dotted_lower);
+        assert_eq!(UNICODE.dots(0xff), UNICODE.dotted_full);
+        assert_eq!(ASCII.dots(0b0000_0001), '.');
+        assert_eq!(ASCII.dots(0xff), '#');
+
+        for (glyphs, ascii) in [(UNICODE, false), (ASCII, true)] {
+            let chars = [
+                glyphs.full,
+                glyphs.upper,
+                glyphs.lower,
+                glyphs.dotted_full,
+                glyphs.dotted_upper,
+                glyphs.dotted_lower,
+                glyphs.shade,
+                glyphs.ball,
+                glyphs.horizontal,
+                glyphs.vertical,
+            ];
+            assert_eq!(chars.iter().all(char::is_ascii), ascii);
+        }
+    }
+}
@@ -6,7 +6,7 @@ use std::fmt;
 ///
 /// Can be used for referencing cells in a [`Grid`].
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub(crate) struct GridCoord {
+pub struct GridCoord {
     pub(crate) x: usize,
     pub(crate) y: usize,
 }
@@ -24,7 +24,7 @@ impl From<(usize, usize)> for GridCoord {
 }
 
 /// A 2D grid of arbitrary values with a constant width and height.
-pub(crate) struct Grid<T> {
+pub struct Grid<T> {
     pub(crate) width: usize,
     pub(crate) height: usize,
     pub(crate) data: Vec<T>,
@@ -37,7 +37,7 @@ where
     /// Create a new grid with a constant width and height.
     ///
     /// The grid will be filled with default-initialized clones of whatever type `T` is.
-    pub(crate) fn new(width: usize, height: usize) -> Self {
+    pub fn new(width: usize, height: usize) -> Self {
         Self {
             width,
             height,
@@ -47,14 +47,14 @@ where
 }
 
 impl<T> Grid<T> {
-    pub(crate) const fn in_bounds(&self, coord: GridCoord) -> bool {
+    pub const fn in_bounds(&self, coord: GridCoord) -> bool {
         coord.x < self.width && coord.y < self.height
     }
 
     /// Get a _mutable_ reference to a value at some grid coordinate.
     ///
     /// Returns `None` if `coord` is out-of-bounds.
-    pub(crate) fn cell_mut(&mut self, coord: GridCoord) -> Option<&mut T> {
+    pub fn cell_mut(&mut self, coord: GridCoord) -> Option<&mut T> {
         if !self.in_bounds(coord) {
             return None;
         }
@@ -64,7 +64,7 @@ impl<T> Grid<T> {
     /// Get a reference to a value at some grid coordinate.
     ///
     /// Returns `None` if `coord` is out-of-bounds.
-    pub(crate) fn cell(&self, coord: GridCoord) -> Option<&T> {
+    pub fn cell(&self, coord: GridCoord) -> Option<&T> {
         if !self.in_bounds(coord) {
             return None;
         }
@@ -73,19 +73,24 @@ impl<T> Grid<T> {
 
     /// Get the grid's constant width.
     #[inline]
-    pub(crate) const fn width(&self) -> usize {
+    pub const fn width(&self) -> usize {
         self.width
     }
 
     /// Get the grid's constant height.
     #[inline]
-    pub(crate) const fn height(&self) -> usize {
+    pub const fn height(&self) -> usize {
         self.height
     }
 
-    pub(crate) const fn num_cells(&self) -> usize {
+    pub const fn num_cells(&self) -> usize {
         self.width * self.height
     }
+
+    /// How many bytes of heap memory the grid's cells take up.
+    pub fn heap_size(&self) -> usize {
+        self.data.capacity() * std::mem::size_of::<T>()
+    }
 }
 
 impl<T> fmt::Debug for Grid<T>
@@ -112,3 +117,182 @@ where
         Ok(())
     }
 }
+
+/// A 2D grid of booleans, packed into 64 cells per word.
+///
+/// Has the same coordinate API as a [`Grid<bool>`], but takes up an eighth of the memory. Good
+/// for big masks like occupied or visited cells.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Create a new grid with a constant width and height, with every cell set to `false`.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            words: vec![0; (width * height).div_ceil(64)],
+        }
+    }
+
+    pub const fn in_bounds(&self, coord: GridCoord) -> bool {
+        coord.x < self.width && coord.y < self.height
+    }
+
+    /// The word that a cell's bit lives in, and the mask for that bit.
+    const fn bit(&self, coord: GridCoord) -> (usize, u64) {
+        let index = coord.y * self.width + coord.x;
+        (index / 64, 1 << (index % 64))
+    }
+
+    /// Get the value at some grid coordinate.
+    ///
+    /// Returns `None` if `coord` is out-of-bounds.
+    pub fn cell(&self, coord: GridCoord) -> Option<bool> {
+        if !self.in_bounds(coord) {
+            return None;
+        }
+        let (word, mask) = self.bit(coord);
+        Some(self.words[word] & mask != 0)
+    }
+
+    /// Set the value at some grid coordinate, returning the old value.
+    ///
+    /// Returns `None` (and doesn't change anything) if `coord` is out-of-bounds.
+    pub fn set(&mut self, coord: GridCoord, value: bool) -> Option<bool> {
+        if !self.in_bounds(coord) {
+            return None;
+        }
+        let (word, mask) = self.bit(coord);
+        let old = self.words[word] & mask != 0;
+        if value {
+            self.words[word] |= mask;
+        } else {
+            self.words[word] &= !mask;
+        }
+        Some(old)
+    }
+
+    /// Set every cell back to `false`.
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// How many cells are set to `true`.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Iterate over the coordinates of every cell that's set to `true`, row by row.
+    pub fn iter_ones(&self) -> impl Iterator<Item = GridCoord> + '_ {
+        self.words.iter().enumerate().flat_map(move |(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let index = i * 64 + word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(GridCoord {
+                    x: index % self.width,
+                    y: index / self.width,
+                })
+            })
+        })
+    }
+
+    /// Get the grid's constant width.
+    #[inline]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the grid's constant height.
+    #[inline]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    pub const fn num_cells(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// How many bytes of heap memory the grid's cells take up.
+    pub fn heap_size(&self) -> usize {
+        self.words.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+impl From<&Grid<bool>> for BitGrid {
+    fn from(grid: &Grid<bool>) -> Self {
+        let mut bits = Self::new(grid.width, grid.height);
+        for (i, _) in grid.data.iter().enumerate().filter(|(_, &cell)| cell) {
+            bits.words[i / 64] |= 1 << (i % 64);
+        }
+        bits
+    }
+}
+
+impl fmt::Debug for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            writeln!(f, "{}x{} bit grid:", self.width, self.height)?;
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let cell = self.cell((x, y).into()).unwrap();
+                    write!(f, "{}", if cell { '#' } else { '.' })?;
+                }
+                writeln!(f)?;
+            }
+        } else {
+            f.debug_struct("BitGrid")
+                .field("width", &self.width)
+                .field("height", &self.height)
+                .field("ones", &self.count_ones())
+                .finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_grid_matches_bool_grid() {
+        let mut bools = Grid::<bool>::new(13, 11);
+        let mut bits = BitGrid::new(13, 11);
+
+        for (x, y) in [(0, 0), (12, 0), (5, 4), (12, 10), (7, 9)] {
+            *bools.cell_mut((x, y).into()).unwrap() = true;
+            assert_eq!(bits.set((x, y).into(), true), Some(false));
+        }
+        assert_eq!(bits.set((3, 11).into(), true), None);
+        assert_eq!(bits.set((7, 9).into(), false), Some(true));
+        *bools.cell_mut((7, 9).into()).unwrap() = false;
+
+        for y in 0..11 {
+            for x in 0..13 {
+                let coord = (x, y).into();
+                assert_eq!(bits.cell(coord), bools.cell(coord).copied());
+            }
+        }
+        assert_eq!(bits, BitGrid::from(&bools));
+        assert_eq!(bits.count_ones(), 4);
+        assert_eq!(
+            bits.iter_ones().collect::<Vec<_>>(),
+            vec![
+                (0, 0).into(),
+                (12, 0).into(),
+                (5, 4).into(),
+                (12, 10).into()
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Iterator adapters for reading challenge inputs a piece at a time.
+
+use std::io::{self, BufRead};
+
+/// Split `reader` into groups of lines separated by blank lines, like the elves' calorie lists on
+/// day 1 or the pairs of packets on day 13.
+///
+/// Lines are yielded without their line endings. Several blank lines in a row don't make an
+/// empty group, and the last group doesn't need a blank line after it. Only the group being
+/// yielded is held in memory, not the whole input.
+pub fn groups<R: BufRead>(reader: R) -> Groups<R> {
+    Groups {
+        lines: reader.lines(),
+    }
+}
+
+/// The iterator returned by [`groups`].
+#[derive(Debug)]
+pub struct Groups<R> {
+    lines: io::Lines<R>,
+}
+
+impl<R: BufRead> Iterator for Groups<R> {
+    type Item = io::Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut group = Vec::new();
+
+        for line in &mut self.lines {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if !line.trim().is_empty() {
+                group.push(line);
+            } else if !group.is_empty() {
+                return Some(Ok(group));
+            }
+        }
+
+        (!group.is_empty()).then_some(Ok(group))
+    }
+}
+
+/// Call `f` with every line of `reader`, without its line ending, stopping at the first error from
+/// either.
+///
+/// Unlike [`BufRead::lines`], which allocates a new `String` for every line, every line is read
+/// into the same buffer, so going through a long input stops allocating once the buffer is as long
+/// as the longest line. The catch is that `f` only gets to borrow each line.
+pub fn for_each_line<R, E, F>(reader: &mut R, mut f: F) -> Result<(), E>
+where
+    R: BufRead + ?Sized,
+    E: From<io::Error>,
+    F: FnMut(&str) -> Result<(), E>,
+{
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            return Ok(());
+        }
+
+        let line = buf.strip_suffix('\n').unwrap_or(&buf);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        f(line)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_blank_lines() {
+        let input = "\n1\n2\r\n\n\n3\n \n4\n5";
+        let groups = groups(input.as_bytes())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(groups, vec![vec!["1", "2"], vec!["3"], vec!["4", "5"]]);
+    }
+
+    #[test]
+    fn reuses_one_buffer_for_every_line() {
+        let mut lines = Vec::new();
+        for_each_line(&mut "a\r\n\nbc\nd".as_bytes(), |line| {
+            lines.push(line.to_string());
+            Ok::<_, io::Error>(())
+        })
+        .unwrap();
+        assert_eq!(lines, ["a", "", "bc", "d"]);
+
+        let mut seen = 0;
+        let result = for_each_line(&mut "1\nx\n3\n".as_bytes(), |line| {
+            seen += 1;
+            line.parse::<u32>()
+                .map(drop)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        });
+        assert!(result.is_err());
+        assert_eq!(seen, 2, "stops at the first error");
+    }
+}
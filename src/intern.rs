@@ -0,0 +1,162 @@
+//! A string interner, for parsers that look the same names up over and over.
+//!
+//! Every distinct string is copied once into a single growing buffer, and handed back as a
+//! [`Symbol`]: a small `Copy` index that can be compared, hashed and used as a key for free.
+//! Symbols are numbered in the order their strings were first interned, so they can index straight
+//! into a `Vec` as well.
+//!
+//! Built for day 21's monkey names and day 7's file names.
+
+// Only the tests count the strings or pick their own hasher so far.
+#![allow(dead_code)]
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::BuildHasher,
+};
+
+/// A string in an [`Interner`], which only means anything to the interner that handed it out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Symbol(u32);
+
+impl Symbol {
+    /// How many strings were interned before this one.
+    pub(crate) const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Hands out a [`Symbol`] for every distinct string, and turns them back into strings.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Interner<S = RandomState> {
+    /// Every interned string, one after the other.
+    arena: String,
+    entries: Vec<Entry>,
+    /// The first symbol whose string has each hash. Strings are hashed by the interner itself, so
+    /// the keys don't need to borrow from `arena`.
+    buckets: HashMap<u64, Symbol>,
+    hasher: S,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Where the string is in the arena.
+    start: u32,
+    end: u32,
+    /// The next symbol with the same hash, if there is one.
+    next: Option<Symbol>,
+}
+
+impl Interner {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S: BuildHasher> Interner<S> {
+    /// An empty interner that hashes strings with `hasher`.
+    pub(crate) fn with_hasher(hasher: S) -> Self {
+        Self {
+            arena: String::new(),
+            entries: Vec::new(),
+            buckets: HashMap::new(),
+            hasher,
+        }
+    }
+
+    /// The symbol for `s`, interning it if it hasn't been already.
+    pub(crate) fn intern(&mut self, s: &str) -> Symbol {
+        let hash = self.hasher.hash_one(s);
+        if let Some(symbol) = self.find(hash, s) {
+            return symbol;
+        }
+
+        let symbol = Symbol(self.entries.len() as u32);
+        let start = self.arena.len() as u32;
+        self.arena.push_str(s);
+        self.entries.push(Entry {
+            start,
+            end: self.arena.len() as u32,
+            next: self.buckets.insert(hash, symbol),
+        });
+        symbol
+    }
+
+    /// The symbol for `s`, if it's been interned.
+    pub(crate) fn get(&self, s: &str) -> Option<Symbol> {
+        self.find(self.hasher.hash_one(s), s)
+    }
+
+    fn find(&self, hash: u64, s: &str) -> Option<Symbol> {
+        let mut candidate = self.buckets.get(&hash).copied();
+        while let Some(symbol) = candidate {
+            if self.resolve(symbol) == s {
+                return Some(symbol);
+            }
+            candidate = self.entries[symbol.index()].next;
+        }
+        None
+    }
+
+    /// The string that `symbol` stands for.
+    ///
+    /// Panics if `symbol` came from a different interner, and wasn't handed out by this one.
+    pub(crate) fn resolve(&self, symbol: Symbol) -> &str {
+        let Entry { start, end, .. } = self.entries[symbol.index()];
+        &self.arena[start as usize..end as usize]
+    }
+
+    /// How many distinct strings have been interned.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    use super::*;
+
+    #[test]
+    fn interns_each_string_once() {
+        let mut names = Interner::new();
+        let root = names.intern("root");
+        let humn = names.intern("humn");
+        assert_eq!(names.intern("root"), root);
+        assert_ne!(root, humn);
+        assert_eq!((root.index(), humn.index()), (0, 1));
+        assert_eq!(names.len(), 2);
+
+        assert_eq!(names.resolve(humn), "humn");
+        assert_eq!(names.get("humn"), Some(humn));
+        assert_eq!(names.get("pppw"), None);
+        assert_eq!(names.intern(""), Symbol(2));
+        assert_eq!(names.resolve(Symbol(2)), "");
+    }
+
+    #[test]
+    fn tells_colliding_strings_apart() {
+        /// Hashes every string to the same number, so they all end up chained in one bucket.
+        #[derive(Default)]
+        struct Colliding;
+        impl Hasher for Colliding {
+            fn finish(&self) -> u64 {
+                0
+            }
+            fn write(&mut self, _: &[u8]) {}
+        }
+
+        let mut names = Interner::with_hasher(BuildHasherDefault::<Colliding>::default());
+        let words = ["a", "b", "ab", "ba", "abc"];
+        let symbols = words.map(|word| names.intern(word));
+        assert_eq!(names.buckets.len(), 1);
+        assert_eq!(words.map(|word| names.intern(word)), symbols);
+        assert_eq!(symbols.map(|symbol| names.resolve(symbol)), words);
+        assert_eq!(names.get("abcd"), None);
+    }
+}
@@ -0,0 +1,144 @@
+//! Sets of integers, kept as sorted runs of consecutive numbers.
+//!
+//! Puzzles like day 4's section assignments hand out ranges of numbers that are far too long to
+//! keep one by one, but only ever have a handful of ranges, so an [`IntervalSet`] stores the
+//! ranges themselves and works out unions and intersections from their ends.
+
+use std::ops::RangeInclusive;
+
+/// A set of integers, stored as the shortest list of inclusive ranges that covers it.
+///
+/// The ranges are kept sorted, and never overlap or touch, so two sets holding the same numbers
+/// always compare equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct IntervalSet {
+    ranges: Vec<RangeInclusive<i64>>,
+}
+
+impl IntervalSet {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add every number in `range` to the set. Empty ranges are ignored.
+    pub(crate) fn insert(&mut self, range: RangeInclusive<i64>) {
+        if range.is_empty() {
+            return;
+        }
+        let (mut start, mut end) = range.into_inner();
+
+        // Everything that overlaps or touches the new range gets merged into it.
+        let first = self
+            .ranges
+            .partition_point(|r| r.end().saturating_add(1) < start);
+        let last = self
+            .ranges
+            .partition_point(|r| *r.start() <= end.saturating_add(1));
+        if first < last {
+            start = start.min(*self.ranges[first].start());
+            end = end.max(*self.ranges[last - 1].end());
+        }
+        self.ranges.splice(first..last, [start..=end]);
+    }
+
+    /// How many numbers are in the set.
+    pub(crate) fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|r| r.start().abs_diff(*r.end()) + 1)
+            .sum()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Whether every number in `range` is in the set.
+    pub(crate) fn contains_range(&self, range: &RangeInclusive<i64>) -> bool {
+        // Since touching ranges are merged, all of `range` has to be inside a single one.
+        range.is_empty()
+            || self
+                .ranges
+                .iter()
+                .any(|r| r.start() <= range.start() && range.end() <= r.end())
+    }
+
+    /// Whether any number in `range` is in the set.
+    pub(crate) fn overlaps(&self, range: &RangeInclusive<i64>) -> bool {
+        self.ranges
+            .iter()
+            .any(|r| r.start() <= range.end() && range.start() <= r.end())
+    }
+
+    /// The numbers that are in both sets.
+    pub(crate) fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+
+        while let (Some(ra), Some(rb)) = (a.peek(), b.peek()) {
+            let start = *ra.start().max(rb.start());
+            let end = *ra.end().min(rb.end());
+            if start <= end {
+                ranges.push(start..=end);
+            }
+
+            // Whichever range ends first can't overlap anything else in the other set.
+            if ra.end() < rb.end() {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+
+        Self { ranges }
+    }
+}
+
+impl From<RangeInclusive<i64>> for IntervalSet {
+    fn from(range: RangeInclusive<i64>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl FromIterator<RangeInclusive<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<i64>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_and_intersects_ranges() {
+        let mut set = [10..=12, 1..=3, 4..=5, 11..=15]
+            .into_iter()
+            .collect::<IntervalSet>();
+        set.insert(RangeInclusive::new(20, 19));
+        assert_eq!(set.ranges, [1..=5, 10..=15]);
+        assert_eq!(set.len(), 11);
+        assert!(set.contains_range(&(2..=5)));
+        assert!(!set.contains_range(&(4..=10)));
+        assert!(set.overlaps(&(6..=10)));
+        assert!(!set.overlaps(&(6..=9)));
+
+        let other = [0..=1, 5..=11, 15..=30]
+            .into_iter()
+            .collect::<IntervalSet>();
+        assert_eq!(
+            set.intersection(&other).ranges,
+            [1..=1, 5..=5, 10..=11, 15..=15]
+        );
+        assert!(set.intersection(&IntervalSet::from(6..=9)).is_empty());
+    }
+}
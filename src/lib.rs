@@ -0,0 +1,45 @@
+//! Solves Advent of Code 2022 challenges in questionably-valid ways.
+
+// Some of the solvers' helpers only exist for their `--visualize` UIs, so builds without those go
+// without them. Default builds still catch anything that's actually dead.
+#![cfg_attr(not(all(feature = "gui", feature = "tui")), allow(dead_code))]
+
+#[cfg(feature = "gui")]
+mod atomic;
+mod automaton;
+pub mod challenge;
+pub mod charset;
+pub mod clock;
+pub mod color;
+mod cycledetect;
+pub mod doctor;
+mod dsu;
+pub mod error;
+pub mod explain;
+mod geometry;
+pub mod glyphs;
+pub mod grid;
+pub mod input_iter;
+mod intern;
+mod interval;
+pub mod math;
+mod mesh;
+#[cfg(feature = "gui")]
+mod param_panel;
+pub mod parse;
+mod pathfinding;
+pub mod prelude;
+pub mod raster;
+pub mod samples;
+pub mod settings;
+mod simulation;
+#[cfg(test)]
+mod snapshot;
+pub mod solver;
+pub mod sync;
+pub mod termplot;
+mod toposort;
+pub mod trace_io;
+#[cfg(feature = "tui")]
+mod tui_app;
+mod voxel;
@@ -1,12 +1,22 @@
-use std::path::PathBuf;
+use std::{
+    ffi::OsString,
+    io::Read,
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
+use aoc2022::{
+    challenge,
+    color::{self, ColorChoice},
+    doctor, explain,
+    glyphs::{self, GlyphChoice},
+    math::MathMode,
+    settings::{self, AnswerFormat, Settings},
+    solver, trace_io,
+};
 use color_eyre::{eyre::Context, Help};
 
-mod atomic;
-mod challenge;
-mod grid;
-mod solver;
-
 /// Help text to display when we receive `-h` or `--help` on the command line.
 const HELP: &str = "\
 aoc2022
@@ -15,11 +25,281 @@ Solves Advent of Code 2022 challenges in questionably-valid ways.
 
 USAGE:
   aoc2022 [OPTIONS] CHALLENGE_NUMBER SUBCHALLENGE
+  aoc2022 validate [OPTIONS] CHALLENGE_NUMBER SUBCHALLENGE
+  aoc2022 compare [OPTIONS] CHALLENGE_NUMBER SUBCHALLENGE --algos A,B
+  aoc2022 selftest
+  aoc2022 all [--results FILE]
+  aoc2022 gallery [--out DIR]
+  aoc2022 bench [--baseline save|check] [--max-regression PERCENT] [--runs N]
+  aoc2022 scramble [OPTIONS] CHALLENGE_NUMBER [SUBCHALLENGE]
+  aoc2022 generate CHALLENGE_NUMBER [--size N] [--seed SEED]
+  aoc2022 input encrypt|decrypt [--keyfile KEYFILE]
+  aoc2022 doctor
+  aoc2022 config show
+
+SUBCOMMANDS:
+  validate                   Only parse the input, and print some statistics
+                             about it instead of solving the challenge. Useful
+                             for checking that the right input file was saved.
+
+  compare                    Solve the challenge with two of its solver's
+                             algorithms (currently challenges 12, 14, 15, 16
+                             and 18), and check that their answers match. For
+                             solvers that stream their steps (see
+                             `--stream-events`), also print the first step
+                             where the two algorithms' states differ, and
+                             what's different about it.
+
+  selftest                   Run every solver against the example input from
+                             its puzzle description, and print which ones got
+                             the right answers. Doesn't need any input files.
+
+  all                        Solve both subchallenges of every challenge on its
+                             puzzle input from `./input/`, and print a table of
+                             their answers and runtimes. Afterwards, sum up the
+                             total runtime, the three slowest subchallenges,
+                             and which inputs and solvers are missing. If
+                             there's an `./answers.toml` with answers like
+                             `01a = 24000`, also check each answer against it.
+                             Shows how far it's got on stderr as it goes,
+                             unless `--quiet` is given.
+
+  gallery                    Run every solver that can draw what it ends up
+                             with (currently challenges 10, 12, 14, 17 and 18)
+                             on its sample, and save the pictures as PNG or
+                             text files, named like `17-tower.png`. Useful for
+                             spotting changes in how solvers behave by
+                             comparing the pictures against an earlier run's.
+                             Doesn't need any input files.
+
+  bench                      Time every solver, on its full puzzle input from
+                             `./input/` if there is one and on its sample
+                             otherwise, and print the median runtime of each.
+                             Shows how far it's got like `all` does.
+
+  scramble                   Print a randomized copy of the input with the same
+                             structure (currently challenges 16 and 21), so
+                             that it can be attached to a bug report without
+                             sharing the original puzzle input. SUBCHALLENGE
+                             defaults to `a`.
+
+  generate                   Print a made-up input that's valid for the
+                             challenge (currently challenges 6, 9 and 20),
+                             with `--size` moves, numbers or characters in it,
+                             for stress testing and benchmarking solvers on
+                             bigger inputs than the real ones.
+
+  input encrypt              Encrypt every `./input/*.txt`, replacing it with a
+                             `*.txt.enc`, so that puzzle inputs can be
+                             committed without publishing them. Encrypted
+                             inputs are decrypted on the fly when solving, as
+                             long as the key is available (see ENVIRONMENT).
+
+  input decrypt              Turn every `./input/*.txt.enc` back into a plain
+                             `*.txt`.
+
+  doctor                     Check that `./input/` exists and which puzzle
+                             inputs in it can be read, whether a session token
+                             is set, and whether the terminal can show the
+                             visualizations, with suggestions for fixing
+                             anything that's wrong.
+
+  config show                Print the settings in effect, as TOML: those from
+                             `./aoc2022.toml`, with the flags given applied.
+                             The file can set `input_dir` (instead of
+                             `./input/`), `format` (`plain`, `json` or
+                             `quiet`), `color`, `threads` (for the parallel
+                             searches), `session_file` (a file with the
+                             session token, for `doctor`), and per-challenge
+                             `[presets]` like `15 = \"real\"` and `[params.N]`
+                             tables. Flags given on the command line win over
+                             the file, and `--param` over `[params.N]`.
 
 FLAGS:
   -h, --help                 Prints this help message and exit.
+  --visualize                Show an interactive visualization of the solver
+                             while it runs, if it has one (currently
+                             challenges 5, 7, 8, 9, 10, 11, 12, 14 and
+                             22, and only if it was built with the `tui` or
+                             `gui` feature). The terminal UIs can be sped up
+                             with `--param tick_hz=N` (ticks per second) and
+                             `--param steps_per_tick=M`. Pressing `t` turns
+                             on turbo mode, which steps as fast as it can and
+                             only draws every `--param turbo_steps=K` steps
+                             (1000 by default). With `--param auto_exit=true`,
+                             they exit by themselves once the simulation is
+                             done, after showing it for `--param linger=S`
+                             seconds (3 by default), and print the answer.
+  --json                     Print the answer, its unit and any metadata as a
+                             single JSON object, instead of the solver's usual
+                             output.
+  --resume                   Pick up a long search where it left off, from the
+                             checkpoint saved by an earlier run that was
+                             interrupted or timed out (currently challenges 16
+                             and 19). Checkpoints are saved to `./.checkpoint/`
+                             every 30 seconds and deleted once the search
+                             finishes.
+  --no-cache                 Parse the input again, instead of reusing what an
+                             earlier run on the same input parsed (currently
+                             only challenge 16). Parsed inputs are cached in
+                             `./.parse-cache/`, and parsed again whenever the
+                             input file changes.
+  --verbose                  Print to stderr whether the parse cache had the
+                             input or not.
+  --explain                  Print a step-by-step explanation of how the
+                             answer was found to stderr, like the examples in
+                             the puzzle descriptions (currently challenges 13
+                             and 21). Meant for small inputs.
+  -q, --quiet                Hide everything the solver prints, to stdout and
+                             stderr, and print only the answer on a single
+                             line, without its unit. Pictures have their rows
+                             separated by `\\n`. Solvers that only print
+                             their answers themselves (like challenge 7's)
+                             print nothing at all.
+  --trace-io                 Keep track of how much of the input the solver
+                             read, print it to stderr afterwards, and warn
+                             about any lines it never got to. Useful for
+                             catching parsers that stop early and quietly
+                             ignore the rest of the input.
+  --artifacts-private        With `--artifacts-dir`, save a BLAKE2s hash of the
+                             input instead of the input itself, so that the
+                             bundle can be shared.
+  --strict                   Refuse puzzle inputs bigger than
+                             `--max-input-size`, instead of warning about
+                             them. Inputs that start like a web page (like the
+                             puzzle's page, saved by mistake) are always
+                             refused.
+  --check-consistency        Check shortcuts that solvers take against
+                             working things out the slow way, and fail if they
+                             disagree (currently challenge 17 b, which checks
+                             the tower's height after skipping ahead over a
+                             loop against dropping every piece for the first
+                             few times around it, 3 by default or
+                             `--param consistency_cycles=N`).
+  --skip-bad-lines           Leave out lines of the input that can't be parsed,
+                             with a warning pointing at each of them, instead
+                             of failing, to get a provisional answer from a
+                             partly corrupted input (currently challenges 2,
+                             4, 18 and 20). How many were left out is in the
+                             answer's metadata as `skipped_lines`.
 
 OPTIONS:
+  --param KEY=VALUE          Pass a solver-specific parameter. May be given
+                             more than once. Unknown parameters are ignored.
+
+  --export-mesh FILE         Save day 18's droplet as a mesh of its outside
+                             surface, as OBJ or STL going by FILE's extension
+                             (`.obj` or `.stl`). The same as
+                             `--param export_mesh=FILE`.
+
+  --transform EXPR           Change the input after parsing it, to see how the
+                             answer would turn out without editing the input
+                             file. EXPR is either `drop KIND ID` or
+                             `set KIND ID FIELD=VALUE`, like
+                             `drop monkey 2` (challenge 11),
+                             `set valve XX flow=0` (challenge 16), or
+                             `drop blueprint 3` (challenge 19). May be given
+                             more than once, and applied in order.
+
+  --post EXPR                Work something out from the answer, which becomes
+                             the answer instead. EXPR is arithmetic with
+                             `+ - * / %` and parentheses on whole numbers and
+                             names: `answer` for a number answer, the fields
+                             of an object answer, or anything in the answer's
+                             metadata (like `x` and `y`, the beacon's
+                             position on challenge 15 b, or `metrics.steps`).
+
+  --timeout SECONDS          Give up if the solver hasn't finished after this
+                             many seconds. Solvers with long searches
+                             (currently challenges 16 and 19) stop early and
+                             report how far they got.
+
+  --preset PRESET            Use a solver's parameters for a kind of input, either
+                             `sample` for the example in the puzzle description
+                             or `real` for a full puzzle input. Any `--param`
+                             given as well replaces the preset's value.
+
+  --math MODE                What to do when a number gets too big for its type
+                             in the arithmetic-heavy solvers (currently
+                             challenges 11, 17, 19, 20 and 21): `wrapping`
+                             (the default), `saturating`, or `checked`, which
+                             fails with the exact line that overflowed. Try
+                             `checked` when an answer looks wrong.
+
+  --explain-lines LINES      Stop explaining after this many lines. Defaults to
+                             200.
+
+  --color WHEN               Whether to color the pictures that solvers print:
+                             `auto` (the default) only colors them when stdout
+                             is a terminal and `NO_COLOR` isn't set, or
+                             `always` or `never`.
+
+  --ascii                    Draw the pictures that solvers print with plain
+                             ASCII instead of Unicode blocks, braille and box
+                             lines. ASCII is already used on terminals that
+                             don't seem to support those, like the old
+                             Windows console or with a non-UTF-8 locale.
+
+  --check ANSWER             Compare the answer against a known one, and exit
+                             with code 4 if they differ. The answer is
+                             compared the way `--quiet` prints it.
+
+  --baseline MODE            For `bench`, either `save` the runtimes to
+                             `./bench_baselines.toml`, or `check` them against
+                             the ones saved there and fail if any solver got
+                             slower by more than `--max-regression`.
+
+  --max-regression PERCENT   How much slower than its baseline a solver may
+                             get before `bench --baseline check` fails.
+                             Defaults to 20.
+
+  --max-input-size KIB       Warn about puzzle inputs bigger than this many
+                             kibibytes, which are probably something else
+                             saved by mistake. Defaults to 1024.
+
+  --runs N                   How many times `bench` runs each solver. Defaults
+                             to 5.
+
+  --seed SEED                Seed for `scramble` and `generate`, to get the
+                             same output as an earlier run. A random seed is
+                             used if not given, and printed to stderr.
+
+  --size N                   How many moves, numbers or characters `generate`
+                             makes up. Defaults to 10,000.
+
+  --keyfile KEYFILE          For `input encrypt` and `input decrypt`, derive
+                             the key from this file's contents instead of from
+                             the environment.
+
+  --out DIR                  Where `gallery` saves its pictures. Created if it
+                             doesn't exist. Defaults to `./gallery/`.
+
+  --results FILE             Also save the results of `all` to FILE, as JSON
+                             if it ends in `.json` or as a Markdown table if
+                             it ends in `.md`.
+
+  --artifacts-dir DIR        When the solver fails or panics, save everything
+                             needed to look into it to a new directory in DIR,
+                             named like `17b-1671235200000`: the input, the
+                             error or the panic and its backtrace, the
+                             solver's configuration, and any progress it had
+                             reported. Off by default.
+
+  --plugin PATH              Load more solvers from a dynamic library (only if
+                             aoc2022 was built with the `plugins` feature),
+                             for trying out other solutions without changing
+                             this crate. See `examples/plugin.rs` for how to
+                             write one. A plugin's solvers are named after its
+                             file, so `libfaster.so`'s solver for challenge 17
+                             is `faster:17`, next to the usual `builtin:17`.
+                             They're only used for challenges that don't have
+                             a solver yet, unless picked with `--solver`. May
+                             be given more than once.
+
+  --solver NAMESPACE         Use the solvers from NAMESPACE, like `faster`, for
+                             every challenge that it has one for, instead of
+                             the built-in ones.
+
   --input INPUT_FILE_PATH    Use a specific file as the puzzle input. If this
                              flag is not provided, then by default aoc2022 will
                              look for and use a file named
@@ -27,6 +307,33 @@ OPTIONS:
                              `./input/` directory (e.g. ./input/1b.txt or
                              ./input/01A.txt or ./input/25a.txt or so on).
 
+  --label LABEL              Use another input saved for the same challenge,
+                             named <CHALLENGE_NUMBER><SUBCHALLENGE>.LABEL.txt
+                             in the `./input/` directory (e.g.
+                             ./input/17b.friend1.txt for `--label friend1`).
+
+  --all-labels               Solve the challenge on its usual input and every
+                             labeled one, and print a table comparing their
+                             answers. Useful for finding out why a solver works
+                             on one person's input but not another's.
+
+  --sample                   Use the example from the puzzle description, which
+                             is built into aoc2022, instead of a file, with the
+                             `sample` preset unless `--preset` says otherwise.
+                             Fails with exit code 4 if the answer isn't the
+                             one the puzzle gives for it.
+
+  --algos A,B                For `compare`, the two algorithms to compare, as
+                             they'd be given to `--param algorithm=NAME`.
+
+  --stream-events FILE       Write every step of the simulation to FILE as
+                             JSON Lines, one object like
+                             `{\"step\":12,\"done\":false,\"metrics\":{...}}` per
+                             line, for drawing it with other tools (currently
+                             challenges 14 and 17). Pass `-` to stream to
+                             stdout, which hides the solver's own output like
+                             `--json` does.
+
 ARGS:
   <CHALLENGE_NUMBER>         The numeric challenge number to solve. May be
                              zero-padded - for example, passing `0022` or just
@@ -36,6 +343,26 @@ ARGS:
   <SUBCHALLENGE>             The subchallenge to execute. Must be `a`, `b`,
                              `A`, or `B`.
 
+EXIT CODES:
+  0                          Success.
+  1                          Anything else went wrong, like a missing input
+                             file or a failed `selftest`.
+  2                          The input couldn't be parsed, or made no sense to
+                             the solver.
+  3                          There's no solver for the challenge.
+  4                          The answer was wrong, according to `--check`.
+  5                          The solver timed out.
+
+ENVIRONMENT:
+  AOC2022_INPUT_PASSPHRASE   The passphrase to encrypt and decrypt inputs with.
+
+  AOC2022_INPUT_KEYFILE      A file to derive the key for encrypted inputs
+                             from, if there's no passphrase.
+
+  AOC_SESSION                Your adventofcode.com session cookie. Nothing
+                             here needs it, but `doctor` checks for it, since
+                             most tools for downloading inputs do.
+
 EXAMPLES:
   aoc2022 --help             Print this help message and exit.
 
@@ -45,81 +372,1300 @@ EXAMPLES:
   aoc2022 05 A --input custom.txt
                              Execute the solver for challenge 5, subchallenge b,
                              using the input file `./custom.txt`.
+
+  aoc2022 15 a --preset sample --input sample.txt
+                             Solve the example from the puzzle description for
+                             challenge 15, which asks about row 10 instead of
+                             row 2,000,000.
+
+  aoc2022 15 a --sample      Solve the example from the puzzle description for
+                             challenge 15 the same way, without needing a file,
+                             and check the answer.
+
+  aoc2022 17 b --all-labels  Solve challenge 17, subchallenge b, on
+                             `./input/17b.txt` and every labeled input like
+                             `./input/17b.friend1.txt`, and compare the answers.
+
+  aoc2022 14 b --stream-events sand.jsonl
+                             Solve challenge 14, subchallenge b, and save how
+                             much sand has settled after every tick.
+
+  aoc2022 compare 15 b --algos scan,rotated
+                             Solve challenge 15, subchallenge b, by scanning
+                             every row and by looking for gaps in rotated
+                             coordinates, and check that they agree.
+
+  aoc2022 16 a --transform \"set valve DD flow=0\"
+                             Solve challenge 16, subchallenge a, as if valve
+                             DD were stuck shut.
+
+  aoc2022 15 b --post \"x + y\"
+                             Solve challenge 15, subchallenge b, and add up
+                             the coordinates of the distress beacon instead
+                             of working out its tuning frequency.
+
+  aoc2022 validate 15 a      Check that `./input/15a.txt` can be parsed by the
+                             solver for challenge 15.
+
+  aoc2022 selftest           Check every solver against its sample input.
+
+  aoc2022 all --results results.md
+                             Solve every challenge, and save the results as a
+                             Markdown table.
+
+  aoc2022 gallery --out before
+                             Save pictures of every solver's sample to
+                             `./before/`, to compare against after a change.
+
+  aoc2022 bench --baseline check --max-regression 10
+                             Fail if any solver got more than 10% slower than
+                             the runtimes saved by `bench --baseline save`.
+
+  aoc2022 20 b --input samples/20.txt --quiet --check 1623178306
+                             Solve challenge 20, subchallenge b, on the example
+                             from its puzzle description, and fail if the
+                             answer isn't 1623178306.
+
+  aoc2022 scramble 21 --input input/21a.txt
+                             Print a scrambled copy of `./input/21a.txt`.
+
+  aoc2022 generate 20 --size 100000 --seed 1 > input/20a.txt
+                             Make up a file of 100,000 numbers to mix, 20 times
+                             as many as the real puzzle input has.
+
+  aoc2022 doctor             Check which puzzle inputs are ready to be solved.
+
+  aoc2022 19 b --timeout 60 --artifacts-dir artifacts
+                             Solve challenge 19, subchallenge b, and if it
+                             times out, save how far it got to
+                             `./artifacts/19b-<TIME>/`.
+
+  aoc2022 17 b --plugin libfaster.so --solver faster
+                             Solve challenge 17, subchallenge b, with the
+                             solver from the plugin `./libfaster.so`.
+
+  AOC2022_INPUT_PASSPHRASE=hunter2 aoc2022 input encrypt
+                             Encrypt every input in `./input/` with a
+                             passphrase.
 ";
 
+/// Every flag in [`HELP`], for suggesting one when an unknown flag is given.
+const FLAGS: &[&str] = &[
+    "-h",
+    "--help",
+    "--visualize",
+    "--json",
+    "--resume",
+    "--no-cache",
+    "--verbose",
+    "--explain",
+    "-q",
+    "--quiet",
+    "--trace-io",
+    "--param",
+    "--export-mesh",
+    "--transform",
+    "--post",
+    "--timeout",
+    "--preset",
+    "--math",
+    "--explain-lines",
+    "--color",
+    "--ascii",
+    "--check",
+    "--baseline",
+    "--max-regression",
+    "--runs",
+    "--seed",
+    "--size",
+    "--keyfile",
+    "--out",
+    "--results",
+    "--input",
+    "--label",
+    "--all-labels",
+    "--sample",
+    "--algos",
+    "--stream-events",
+    "--plugin",
+    "--solver",
+    "--artifacts-dir",
+    "--artifacts-private",
+    "--strict",
+    "--max-input-size",
+    "--check-consistency",
+    "--skip-bad-lines",
+];
+
 /// CLI app arguments.
 #[derive(Debug)]
 struct AppArgs {
-    challenge: challenge::ChallengeNumber,
-    subchallenge: challenge::Subchallenge,
+    command: Command,
     input_file: Option<PathBuf>,
+    /// Which of the labeled inputs in `./input/` to use instead of the usual one.
+    label: Option<String>,
+    all_labels: bool,
+    /// Use the solver's built-in sample instead of an input file.
+    sample: bool,
+    /// Where to write simulation events, with `-` for stdout.
+    stream_events: Option<PathBuf>,
+    /// The algorithms for `compare` to compare.
+    algos: Option<[String; 2]>,
+    visualize: bool,
+    params: Vec<(String, String)>,
+    /// Where day 18 saves its droplet's surface as a mesh.
+    export_mesh: Option<PathBuf>,
+    transforms: Vec<solver::Transform>,
+    post: Option<solver::PostExpr>,
+    timeout: Option<Duration>,
+    preset: Option<solver::Preset>,
+    seed: Option<u64>,
+    /// How big an input `generate` makes up.
+    size: usize,
+    resume: bool,
+    no_cache: bool,
+    verbose: bool,
+    math: MathMode,
+    color: ColorChoice,
+    glyphs: GlyphChoice,
+    json: bool,
+    quiet: bool,
+    trace_io: bool,
+    /// The answer that `--check` expects, as [`solver::Answer::plain`] would print it.
+    check: Option<String>,
+    /// How many lines of `--explain` output to print, or `None` if not explaining.
+    explain: Option<usize>,
+    runs: usize,
+    max_regression: f64,
+    keyfile: Option<PathBuf>,
+    /// Where `gallery` saves its pictures.
+    out: Option<PathBuf>,
+    /// Where `all` saves its results, as well as printing them.
+    results: Option<PathBuf>,
+    /// Dynamic libraries to load more solvers from.
+    plugins: Vec<PathBuf>,
+    /// The namespace to use solvers from, where it has them.
+    solver: Option<String>,
+    /// Where to save a bundle of everything about a failed run.
+    artifacts_dir: Option<PathBuf>,
+    artifacts_private: bool,
+    input_checks: challenge::InputChecks,
+    check_consistency: bool,
+    skip_bad_lines: bool,
+    /// Where puzzle inputs are looked for.
+    input_dir: PathBuf,
+    /// The settings from `aoc2022.toml`, for what the command line can't set for every challenge
+    /// at once.
+    settings: Settings,
+}
+
+/// The input couldn't be parsed, or made no sense to the solver.
+const EXIT_BAD_INPUT: i32 = 2;
+/// There's no solver for the challenge.
+const EXIT_NO_SOLVER: i32 = 3;
+/// The answer didn't match the one given to `--check`.
+const EXIT_WRONG_ANSWER: i32 = 4;
+/// The solver didn't finish before `--timeout`.
+const EXIT_TIMED_OUT: i32 = 5;
+
+fn main() {
+    if let Err(report) = run() {
+        // A web page saved as an input is easier to recognize with the page's start shown.
+        let html = report.chain().find_map(|e| match e.downcast_ref() {
+            Some(challenge::GetChallengeInputError::Html(e)) => Some(e),
+            _ => None,
+        });
+        if let Some(html) = html {
+            eprintln!("{}", html.render());
+        }
+        eprintln!("Error: {report:?}");
+        std::process::exit(exit_code(&report));
+    }
+}
+
+/// Set up how `color_eyre` and `miette` report errors, so that they're only colored if `choice`
+/// colors stderr.
+fn install_error_reporting(choice: ColorChoice) -> color_eyre::Result<()> {
+    let mut hooks = color_eyre::config::HookBuilder::default();
+    if !choice.colors_on(&std::io::stderr()) {
+        hooks = hooks.theme(color_eyre::config::Theme::new());
+    }
+    hooks.install()?;
+    miette::set_hook(Box::new(|_| Box::new(color::report_handler())))?;
+    Ok(())
+}
+
+/// Pick the exit code for an error, so that scripts can tell the common failures apart.
+fn exit_code(report: &color_eyre::Report) -> i32 {
+    use aoc2022::error::SolverError;
+    use solver::SolveError;
+
+    let solve_error = report.chain().find_map(|e| e.downcast_ref::<SolveError>());
+    match solve_error {
+        Some(SolveError::SolverExecutionError(
+            SolverError::Parse { .. } | SolverError::InvalidInput(_),
+        )) => EXIT_BAD_INPUT,
+        Some(
+            SolveError::NoSolverLoaded(_)
+            | SolveError::NoSample(_)
+            | SolveError::NoScrambler(_)
+            | SolveError::NoGenerator(_)
+            | SolveError::NoTransforms(_)
+            | SolveError::NoAlgorithms(_)
+            | SolveError::UnknownAlgorithm { .. }
+            | SolveError::UnknownNamespace { .. },
+        ) => EXIT_NO_SOLVER,
+        Some(SolveError::TimedOut { .. }) => EXIT_TIMED_OUT,
+        _ => 1,
+    }
 }
 
-fn main() -> color_eyre::Result<()> {
-    color_eyre::install()?;
+fn run() -> color_eyre::Result<()> {
+    let mut solver = solver::Solver::new();
 
-    let args = match parse_args() {
-        Ok(v) => v,
+    // Errors are only colored once `--color` is known, so none can be reported before then.
+    let settings = match Settings::load(Path::new(settings::SETTINGS_FILE)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            install_error_reporting(ColorChoice::default())?;
+            return Err(e.into());
+        }
+    };
+    let args = match parse_args(std::env::args_os().skip(1), &solver.challenges(), settings) {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            print!("{}", HELP);
+            return Ok(());
+        }
         Err(e) => {
             eprintln!("Error: {e}\nUSAGE: aoc2022 [OPTIONS] CHALLENGE_NUMBER SUBCHALLENGE");
             std::process::exit(1);
         }
     };
+    color::set(args.color);
+    install_error_reporting(args.color)?;
+    glyphs::set(args.glyphs);
+    #[cfg(feature = "parallel")]
+    if let Some(threads) = args.settings.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.get())
+            .build_global()
+            .wrap_err("Could not set up the search threads")?;
+    }
+    load_plugins(&mut solver, &args)?;
+
+    if args.sample
+        && !matches!(
+            args.command,
+            Command::Solve(_) | Command::Validate(_) | Command::Compare(_) | Command::Scramble(_)
+        )
+    {
+        color_eyre::eyre::bail!("`--sample` only works on a single challenge");
+    }
+
+    let target = match args.command {
+        Command::Solve(target)
+        | Command::Validate(target)
+        | Command::Compare(target)
+        | Command::Scramble(target) => target,
+        Command::SelfTest => {
+            let report = solver.self_test();
+            print!("{report}");
+            if report.is_err() {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::All => return run_all(&mut solver, &args),
+        Command::Gallery => return gallery(&mut solver, &args),
+        Command::Bench(baseline) => return bench(&mut solver, baseline, &args),
+        Command::Generate(challenge) => return generate(&solver, challenge, &args),
+        Command::Input(action) => return convert_inputs(action, &args),
+        Command::Doctor => return doctor(&solver, &args),
+        Command::ConfigShow => {
+            print!("{}", effective_settings(&args));
+            return Ok(());
+        }
+    };
+
+    if args.input_file.is_some() && (args.label.is_some() || args.all_labels) {
+        color_eyre::eyre::bail!("`--input` can't be combined with `--label` or `--all-labels`");
+    }
+    if args.sample && (args.input_file.is_some() || args.label.is_some() || args.all_labels) {
+        color_eyre::eyre::bail!(
+            "`--sample` can't be combined with `--input`, `--label` or `--all-labels`"
+        );
+    }
+    if args.all_labels && args.stream_events.is_some() {
+        color_eyre::eyre::bail!("`--stream-events` can't be combined with `--all-labels`");
+    }
+    if args.all_labels {
+        let Command::Solve(_) = args.command else {
+            color_eyre::eyre::bail!("`--all-labels` only works when solving a challenge");
+        };
+        return compare_labels(&mut solver, target, &args);
+    }
+
+    let input_file_buf = if args.sample {
+        let sample = solver.sample_input(target.challenge)?;
+        let input: challenge::ChallengeInput = Box::new(std::io::Cursor::new(sample));
+        input
+    } else {
+        open_input(target, &args)?
+    };
 
-    let input_file_buf =
-        challenge::get_challenge_input(args.challenge, args.subchallenge, &args.input_file)
+    if let Command::Scramble(_) = args.command {
+        let (scrambled, seed) = solver
+            .scramble(target.challenge, input_file_buf, args.seed)
             .wrap_err_with(|| {
                 format!(
-                    "Could not find input file for challenge {}, subchallenge {}",
-                    args.challenge, args.subchallenge
+                    "Error while scrambling input for challenge {}",
+                    target.challenge
                 )
-            });
+            })?;
 
-    let input_file_buf = if args.input_file.is_some() {
-        input_file_buf?
+        eprintln!("Scrambled with seed {seed}. Pass `--seed {seed}` to get the same output again.");
+        print!("{scrambled}");
+        return Ok(());
+    }
+
+    if let Command::Validate(_) = args.command {
+        let report = solver
+            .validate(target.challenge, input_file_buf)
+            .wrap_err_with(|| {
+                format!(
+                    "Error while validating input for challenge {}, subchallenge {}",
+                    target.challenge, target.subchallenge,
+                )
+            })?;
+
+        print!("{report}");
+        if report.is_err() {
+            std::process::exit(EXIT_BAD_INPUT);
+        }
+        return Ok(());
+    }
+
+    if let Command::Compare(_) = args.command {
+        return compare_algorithms(&mut solver, target, input_file_buf, &args);
+    }
+
+    if let Some(max_lines) = args.explain {
+        tracing::subscriber::set_global_default(explain::subscriber(std::io::stderr(), max_lines))
+            .wrap_err("Could not start explaining")?;
+    }
+
+    let config = solver_config(&solver, target, &args)?;
+
+    // A failed run's bundle has the input in it, which the solver would otherwise only read as it
+    // goes, so it gets read in full first.
+    let artifacts = args
+        .artifacts_dir
+        .as_ref()
+        .map(|dir| solver::ArtifactDump::new(dir, args.artifacts_private));
+    let (input_file_buf, input_copy) = if artifacts.is_some() {
+        solver::capture_panics();
+        let mut input = String::new();
+        let mut input_file_buf = input_file_buf;
+        input_file_buf
+            .read_to_string(&mut input)
+            .wrap_err("Could not read the input")?;
+        let copy: challenge::ChallengeInput = Box::new(std::io::Cursor::new(input.clone()));
+        (copy, input)
     } else {
-        input_file_buf.with_suggestion(|| format!(
-            "Make sure that the file `./input/{}{}.txt` exists, is readable, and contains valid UTF-8 data!",
-            args.challenge,
-            args.subchallenge
-        ))?
+        (input_file_buf, String::new())
     };
 
-    let mut solver = solver::Solver::new();
-    solver
-        .solve(args.challenge, args.subchallenge, input_file_buf)
+    let (input_file_buf, io_trace) = if args.trace_io {
+        let (input, trace) = trace_io::trace(input_file_buf);
+        (input, Some(trace))
+    } else {
+        (input_file_buf, None)
+    };
+
+    // Everything the solver prints would get in the way of the JSON, the bare answer, or the
+    // events.
+    let gagged = (args.json || args.quiet || streams_to_stdout(&args))
+        .then(gag::Gag::stdout)
+        .transpose()?;
+    let gagged_stderr = args.quiet.then(gag::Gag::stderr).transpose()?;
+    let answer = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        solver.solve(
+            target.challenge,
+            target.subchallenge,
+            input_file_buf,
+            &config,
+        )
+    }));
+    drop(gagged);
+    drop(gagged_stderr);
+    if let Some(artifacts) = &artifacts {
+        let failure = match &answer {
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => Some(solver::Failure::Error(error_chain(e))),
+            Err(panic) => Some(solver::Failure::panic(&**panic)),
+        };
+        if let Some(failure) = failure {
+            match artifacts.write(
+                target.challenge,
+                target.subchallenge,
+                &input_copy,
+                &config,
+                &failure,
+            ) {
+                Ok(bundle) => eprintln!("Saved what went wrong to {}", bundle.display()),
+                Err(e) => eprintln!("Warning: could not save what went wrong: {e}"),
+            }
+        }
+    }
+    let answer = answer
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
         .wrap_err_with(|| {
             format!(
                 "Error while solving challenge {}, subchallenge {}",
-                args.challenge, args.subchallenge,
+                target.challenge, target.subchallenge,
+            )
+        });
+    // A solver that timed out might still be holding on to its input, and has no report yet.
+    if let Some(report) = io_trace.as_ref().and_then(trace_io::IoTrace::report) {
+        eprintln!("{report}");
+    }
+    let answer = answer?;
+
+    if args.json {
+        let mut json = answer.to_json();
+        json["challenge"] = target.challenge.into();
+        json["subchallenge"] = target.subchallenge.as_str().into();
+        println!("{json}");
+    } else if args.quiet {
+        if !answer.is_empty() {
+            println!("{}", answer.plain());
+        }
+    } else {
+        print_answer(&answer)?;
+    }
+
+    if let Some(expected) = &args.check {
+        if answer.is_empty() {
+            color_eyre::eyre::bail!(
+                "The solver for challenge {} printed its answer itself, so it can't be checked",
+                target.challenge
+            );
+        }
+        let got = answer.plain();
+        if got != expected.trim() {
+            eprintln!("Wrong answer: expected {expected}, got {got}");
+            std::process::exit(EXIT_WRONG_ANSWER);
+        }
+    } else if args.sample && args.post.is_none() {
+        // `--post` turns the answer into something the puzzle never gave for its example.
+        if let Some(expected) = solver.sample_answer(target.challenge, target.subchallenge) {
+            if answer != expected {
+                eprintln!(
+                    "Wrong answer for the sample: expected {}, got {}",
+                    expected.short(),
+                    answer.short()
+                );
+                std::process::exit(EXIT_WRONG_ANSWER);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Open the input file for `target`, going by `--input` and `--label`.
+fn open_input(target: Target, args: &AppArgs) -> color_eyre::Result<challenge::ChallengeInput> {
+    let input = match &args.label {
+        Some(label) => challenge::get_labeled_challenge_input(
+            &args.input_dir,
+            target.challenge,
+            target.subchallenge,
+            label,
+            args.input_checks,
+        ),
+        None => challenge::get_challenge_input(
+            &args.input_dir,
+            target.challenge,
+            target.subchallenge,
+            &args.input_file,
+            args.input_checks,
+        ),
+    };
+    let input_file_buf = match input {
+        // The file was found, and these already say what's wrong with it.
+        Err(
+            err @ (challenge::GetChallengeInputError::Html(_)
+            | challenge::GetChallengeInputError::TooBig { .. }
+            | challenge::GetChallengeInputError::CryptError(_)),
+        ) => return Err(err.into()),
+        input => input.wrap_err_with(|| {
+            format!(
+                "Could not find input file for challenge {}, subchallenge {}",
+                target.challenge, target.subchallenge
+            )
+        }),
+    };
+
+    if args.input_file.is_some() {
+        return input_file_buf;
+    }
+    let label = args
+        .label
+        .as_ref()
+        .map_or(String::new(), |label| format!(".{label}"));
+    input_file_buf.with_suggestion(|| format!(
+        "Make sure that the file `{}/{}{}{label}.txt` exists, is readable, and contains valid UTF-8 data!",
+        args.input_dir.display(),
+        target.challenge,
+        target.subchallenge
+    ))
+}
+
+/// The config to solve `target` with, going by the command line.
+fn solver_config(
+    solver: &solver::Solver,
+    target: Target,
+    args: &AppArgs,
+) -> color_eyre::Result<solver::SolverConfig> {
+    let mut config = solver::SolverConfig {
+        visualize: args.visualize,
+        timeout: args.timeout,
+        checkpoint: solver::Checkpointer::for_challenge(
+            target.challenge,
+            target.subchallenge,
+            args.resume,
+        ),
+        parse_cache: if args.no_cache {
+            solver::ParseCache::default()
+        } else {
+            solver::ParseCache::new(solver::PARSE_CACHE_DIR).verbose(args.verbose)
+        },
+        math: args.math,
+        check_consistency: args.check_consistency,
+        skip_bad_lines: args.skip_bad_lines,
+        color: args.color,
+        transforms: args.transforms.clone(),
+        post: args.post.clone(),
+        events: match &args.stream_events {
+            None => solver::EventStream::default(),
+            Some(_) if streams_to_stdout(args) => {
+                solver::EventStream::stdout().wrap_err("Could not stream events to stdout")?
+            }
+            Some(path) => solver::EventStream::create(path).wrap_err_with(|| {
+                format!("Could not create {} for streaming events", path.display())
+            })?,
+        },
+        ..Default::default()
+    };
+    let preset = args
+        .preset
+        .or(args.sample.then_some(solver::Preset::Sample))
+        .or_else(|| args.settings.presets.get(&target.challenge).copied());
+    if let Some(preset) = preset {
+        config.params = solver.preset(target.challenge, preset).wrap_err_with(|| {
+            format!(
+                "Error while loading the {preset} preset for challenge {}",
+                target.challenge
+            )
+        })?;
+    }
+    for (key, value) in args
+        .settings
+        .params_for(target.challenge)
+        .iter()
+        .chain(&args.params)
+    {
+        config.params.insert(key, value);
+    }
+    if let Some(path) = &args.export_mesh {
+        config.params.insert("export_mesh", path.to_string_lossy());
+    }
+    Ok(config)
+}
+
+/// Whether `--stream-events` is writing to stdout.
+fn streams_to_stdout(args: &AppArgs) -> bool {
+    args.stream_events.as_deref() == Some(Path::new("-"))
+}
+
+/// Solve `target` on its usual input and every labeled one in `./input/`, and print a table of
+/// the answers.
+fn compare_labels(
+    solver: &mut solver::Solver,
+    target: Target,
+    args: &AppArgs,
+) -> color_eyre::Result<()> {
+    let Target {
+        challenge,
+        subchallenge,
+    } = target;
+    let input_dir = args.input_dir.as_path();
+
+    let mut inputs = Vec::new();
+    match challenge::get_challenge_input(
+        input_dir,
+        challenge,
+        subchallenge,
+        &None,
+        args.input_checks,
+    ) {
+        Ok(input) => inputs.push((solver::DEFAULT_LABEL.to_string(), input)),
+        Err(challenge::GetChallengeInputError::DefaultInputFileLocationError { .. }) => {}
+        Err(e) => return Err(e).wrap_err("Could not open the usual input"),
+    }
+    let labels = challenge::find_input_labels(input_dir, challenge, subchallenge)
+        .wrap_err_with(|| format!("Could not look for inputs in {}", input_dir.display()))?;
+    for label in labels {
+        let input = challenge::get_labeled_challenge_input(
+            input_dir,
+            challenge,
+            subchallenge,
+            &label,
+            args.input_checks,
+        )
+        .wrap_err_with(|| format!("Could not open the input labeled `{label}`"))?;
+        inputs.push((label, input));
+    }
+    if inputs.is_empty() {
+        color_eyre::eyre::bail!(
+            "There are no inputs for challenge {challenge}, subchallenge {subchallenge} in {}",
+            input_dir.display()
+        );
+    }
+
+    let config = solver_config(solver, target, args)?;
+    let report = solver.compare_inputs(challenge, subchallenge, inputs, &config);
+    print!("{report}");
+    if report.is_err() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Solve `target` with both of the algorithms from `--algos`, and print how they compare.
+fn compare_algorithms(
+    solver: &mut solver::Solver,
+    target: Target,
+    input: challenge::ChallengeInput,
+    args: &AppArgs,
+) -> color_eyre::Result<()> {
+    let Some(algorithms) = args.algos.clone() else {
+        color_eyre::eyre::bail!(
+            "`compare` needs two algorithms to compare, like `--algos scan,rotated`"
+        );
+    };
+    if args.stream_events.is_some() {
+        color_eyre::eyre::bail!("`--stream-events` can't be combined with `compare`");
+    }
+
+    let config = solver_config(solver, target, args)?;
+    let report = solver
+        .compare_algorithms(
+            target.challenge,
+            target.subchallenge,
+            input,
+            algorithms,
+            &config,
+        )
+        .wrap_err_with(|| {
+            format!(
+                "Error while comparing algorithms for challenge {}, subchallenge {}",
+                target.challenge, target.subchallenge,
             )
         })?;
+    print!("{report}");
+    if report.is_err() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Print the answer and its metadata for a person to read.
+fn print_answer(answer: &solver::Answer) -> color_eyre::Result<()> {
+    if !answer.is_empty() {
+        // Pictures need to start on their own line to come out right.
+        let text = answer.to_string();
+        if text.contains('\n') {
+            print!("answer:\n{text}");
+        } else {
+            println!("answer: {text}");
+        }
+    }
+    if !answer.metadata().is_empty() {
+        println!(
+            "metadata: {}",
+            serde_json::to_string_pretty(answer.metadata())
+                .wrap_err("Could not serialize answer metadata")?
+        );
+    }
 
     Ok(())
 }
 
-/// Parse CLI arguments.
-fn parse_args() -> Result<AppArgs, pico_args::Error> {
-    let mut pargs = pico_args::Arguments::from_env();
+/// What to do.
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    /// Solve the challenge.
+    Solve(Target),
+    /// Only parse the challenge input and report on it.
+    Validate(Target),
+    /// Solve the challenge with two algorithms, and compare how they went.
+    Compare(Target),
+    /// Check every solver against its sample input.
+    SelfTest,
+    /// Solve every challenge on its puzzle input, and sum up how it went.
+    All,
+    /// Save pictures of what every solver ends up with on its sample.
+    Gallery,
+    /// Print a randomized copy of the challenge input.
+    Scramble(Target),
+    /// Print a made-up input for a challenge.
+    Generate(challenge::ChallengeNumber),
+    /// Time every solver, and maybe save or check the timings against baselines.
+    Bench(Option<solver::BaselineMode>),
+    /// Encrypt or decrypt every input in `./input/`.
+    Input(InputAction),
+    /// Check the inputs, the environment and the terminal.
+    Doctor,
+    /// Print the settings from `aoc2022.toml`, with the command line's flags applied.
+    ConfigShow,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum InputAction {
+    Encrypt,
+    Decrypt,
+}
+
+impl std::str::FromStr for InputAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "encrypt" => Ok(Self::Encrypt),
+            "decrypt" => Ok(Self::Decrypt),
+            _ => Err(format!("expected `encrypt` or `decrypt`, not `{s}`")),
+        }
+    }
+}
+
+/// The challenge to work on.
+#[derive(Debug, Clone, Copy)]
+struct Target {
+    challenge: challenge::ChallengeNumber,
+    subchallenge: challenge::Subchallenge,
+}
+
+/// Why the CLI arguments couldn't be parsed.
+#[derive(Debug, thiserror::Error)]
+enum ArgsError {
+    #[error(transparent)]
+    Pico(#[from] pico_args::Error),
+    #[error("unknown flag `{flag}`{}", did_you_mean(suggestion))]
+    UnknownFlag {
+        flag: String,
+        /// The known flag that's closest to it, if any is close enough to be a typo.
+        suggestion: Option<&'static str>,
+    },
+    #[error("unexpected argument `{0}`")]
+    Unexpected(String),
+    #[error(
+        "there's no challenge {challenge}, since Advent of Code only has challenges 1 to 25. \
+         There are solvers for challenges {}",
+        list_challenges(available)
+    )]
+    NoSuchChallenge {
+        challenge: challenge::ChallengeNumber,
+        /// Every challenge with a solver.
+        available: Vec<challenge::ChallengeNumber>,
+    },
+}
+
+fn did_you_mean(suggestion: &Option<&str>) -> String {
+    suggestion.map_or_else(String::new, |flag| format!(", did you mean `{flag}`?"))
+}
+
+fn list_challenges(challenges: &[challenge::ChallengeNumber]) -> String {
+    challenges
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse CLI arguments, not including the program's name, falling back on `settings` for anything
+/// they don't say. `available` is every challenge with a solver, to point out when one that
+/// doesn't exist is asked for.
+///
+/// Returns `None` if help was asked for, in which case nothing else gets parsed.
+fn parse_args(
+    args: impl IntoIterator<Item = OsString>,
+    available: &[challenge::ChallengeNumber],
+    settings: Settings,
+) -> Result<Option<AppArgs>, ArgsError> {
+    let mut pargs = pico_args::Arguments::from_vec(args.into_iter().collect());
 
     // Help has a higher priority and should be handled separately.
     if pargs.contains(["-h", "--help"]) {
-        print!("{}", HELP);
-        std::process::exit(0);
+        return Ok(None);
+    }
+
+    // Options have to be taken out before the positional arguments, so that they don't get
+    // mistaken for them.
+    let visualize = pargs.contains("--visualize");
+    let resume = pargs.contains("--resume");
+    let no_cache = pargs.contains("--no-cache");
+    let verbose = pargs.contains("--verbose");
+    let json = pargs.contains("--json");
+    let quiet = pargs.contains(["-q", "--quiet"]);
+    let trace_io = pargs.contains("--trace-io");
+    let check = pargs.opt_value_from_str("--check")?;
+    let explain = pargs.contains("--explain");
+    let explain_lines = pargs.opt_value_from_str("--explain-lines")?;
+    let input_file = pargs.opt_value_from_os_str("--input", parse_path_arg)?;
+    let label = pargs.opt_value_from_str("--label")?;
+    let all_labels = pargs.contains("--all-labels");
+    let sample = pargs.contains("--sample");
+    let stream_events = pargs.opt_value_from_os_str("--stream-events", parse_path_arg)?;
+    let algos = pargs.opt_value_from_fn("--algos", parse_algos)?;
+    let keyfile = pargs.opt_value_from_os_str("--keyfile", parse_path_arg)?;
+    let out = pargs.opt_value_from_os_str("--out", parse_path_arg)?;
+    let results = pargs.opt_value_from_os_str("--results", parse_path_arg)?;
+    let plugins = pargs.values_from_os_str("--plugin", parse_path_arg)?;
+    let solver = pargs.opt_value_from_str("--solver")?;
+    let artifacts_dir = pargs.opt_value_from_os_str("--artifacts-dir", parse_path_arg)?;
+    let artifacts_private = pargs.contains("--artifacts-private");
+    let params = pargs.values_from_fn("--param", solver::parse_param)?;
+    let export_mesh = pargs.opt_value_from_os_str("--export-mesh", parse_path_arg)?;
+    let transforms = pargs.values_from_str("--transform")?;
+    let post = pargs.opt_value_from_str("--post")?;
+    let timeout = pargs.opt_value_from_fn("--timeout", parse_timeout)?;
+    let preset = pargs.opt_value_from_str("--preset")?;
+    let math = pargs.opt_value_from_str("--math")?;
+    let color = pargs.opt_value_from_str("--color")?;
+    let ascii = pargs.contains("--ascii");
+    let seed = pargs.opt_value_from_str("--seed")?;
+    let size = pargs.opt_value_from_str("--size")?;
+    let baseline = pargs.opt_value_from_str("--baseline")?;
+    let runs = pargs.opt_value_from_str("--runs")?;
+    let max_regression = pargs.opt_value_from_str("--max-regression")?;
+    let strict = pargs.contains("--strict");
+    let check_consistency = pargs.contains("--check-consistency");
+    let skip_bad_lines = pargs.contains("--skip-bad-lines");
+    let max_input_size: Option<u64> = pargs.opt_value_from_str("--max-input-size")?;
+
+    // Anything that still looks like a flag isn't one, and would otherwise be taken for a
+    // positional argument.
+    if let Some(flag) = pargs
+        .clone()
+        .finish()
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .find(|arg| arg.starts_with('-'))
+    {
+        return Err(ArgsError::UnknownFlag {
+            suggestion: closest_flag(&flag),
+            flag,
+        });
+    }
+
+    let subcommand = pargs.clone().subcommand()?;
+    let command = match subcommand.as_deref() {
+        Some("selftest") => {
+            pargs.subcommand()?;
+            Command::SelfTest
+        }
+        Some("all") => {
+            pargs.subcommand()?;
+            Command::All
+        }
+        Some("gallery") => {
+            pargs.subcommand()?;
+            Command::Gallery
+        }
+        Some("bench") => {
+            pargs.subcommand()?;
+            Command::Bench(baseline)
+        }
+        Some("input") => {
+            pargs.subcommand()?;
+            Command::Input(pargs.free_from_str()?)
+        }
+        Some("doctor") => {
+            pargs.subcommand()?;
+            Command::Doctor
+        }
+        Some("config") => {
+            pargs.subcommand()?;
+            let action: String = pargs.free_from_str()?;
+            if action != "show" {
+                return Err(ArgsError::Unexpected(action));
+            }
+            Command::ConfigShow
+        }
+        Some("validate") => {
+            pargs.subcommand()?;
+            Command::Validate(parse_target(&mut pargs)?)
+        }
+        Some("compare") => {
+            pargs.subcommand()?;
+            Command::Compare(parse_target(&mut pargs)?)
+        }
+        Some("scramble") => {
+            pargs.subcommand()?;
+            // The input is the same for both subchallenges, so there's no need to pick one.
+            Command::Scramble(Target {
+                challenge: pargs.free_from_str()?,
+                subchallenge: pargs
+                    .opt_free_from_str()?
+                    .unwrap_or(challenge::Subchallenge::A),
+            })
+        }
+        Some("generate") => {
+            pargs.subcommand()?;
+            Command::Generate(pargs.free_from_str()?)
+        }
+        _ => Command::Solve(parse_target(&mut pargs)?),
+    };
+
+    let challenge = match command {
+        Command::Solve(target)
+        | Command::Validate(target)
+        | Command::Compare(target)
+        | Command::Scramble(target) => Some(target.challenge),
+        Command::Generate(challenge) => Some(challenge),
+        _ => None,
+    };
+    if let Some(challenge) = challenge.filter(|c| !challenge::CHALLENGES.contains(c)) {
+        return Err(ArgsError::NoSuchChallenge {
+            challenge,
+            available: available.to_vec(),
+        });
     }
 
+    // `--json` and `--quiet` both win over the format in the settings.
+    let (json, quiet) = match settings.format {
+        _ if json || quiet => (json, quiet),
+        Some(AnswerFormat::Json) => (true, false),
+        Some(AnswerFormat::Quiet) => (false, true),
+        Some(AnswerFormat::Plain) | None => (false, false),
+    };
+
     let args = AppArgs {
-        challenge: pargs.free_from_str()?,
-        subchallenge: pargs.free_from_str()?,
-        input_file: pargs.opt_value_from_os_str("--input", parse_path_arg)?,
+        command,
+        input_file,
+        label,
+        all_labels,
+        sample,
+        stream_events,
+        algos,
+        visualize,
+        params,
+        export_mesh,
+        transforms,
+        post,
+        timeout,
+        preset,
+        seed,
+        size: size.unwrap_or(solver::DEFAULT_GENERATED_SIZE),
+        resume,
+        no_cache,
+        verbose,
+        math: math.unwrap_or_default(),
+        color: color.or(settings.color).unwrap_or_default(),
+        glyphs: if ascii {
+            GlyphChoice::Ascii
+        } else {
+            GlyphChoice::Auto
+        },
+        json,
+        quiet,
+        trace_io,
+        check,
+        explain: explain.then(|| explain_lines.unwrap_or(explain::DEFAULT_MAX_LINES)),
+        runs: runs.unwrap_or(solver::DEFAULT_RUNS),
+        max_regression: max_regression.unwrap_or(solver::DEFAULT_MAX_REGRESSION),
+        keyfile,
+        out,
+        results,
+        plugins,
+        solver,
+        artifacts_dir,
+        artifacts_private,
+        input_checks: challenge::InputChecks {
+            max_size: max_input_size.map_or(challenge::DEFAULT_MAX_INPUT_SIZE, |kib| {
+                kib.saturating_mul(1024)
+            }),
+            strict,
+        },
+        check_consistency,
+        skip_bad_lines,
+        input_dir: settings
+            .input_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(challenge::INPUT_DIR)),
+        settings,
+    };
+
+    if let Some(arg) = pargs.finish().first() {
+        return Err(ArgsError::Unexpected(arg.to_string_lossy().into_owned()));
+    }
+
+    Ok(Some(args))
+}
+
+/// The known flag that `flag` is most likely a typo of, if it's close enough to any of them.
+fn closest_flag(flag: &str) -> Option<&'static str> {
+    // `--flag=value` is the same flag as `--flag value`.
+    let flag = flag.split_once('=').map_or(flag, |(flag, _)| flag);
+    FLAGS
+        .iter()
+        .map(|&known| (edit_distance(flag, known), known))
+        .filter(|&(distance, known)| distance <= 2.max(known.len() / 4))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, known)| known)
+}
+
+/// How many characters have to be inserted, deleted or swapped for others to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    // The distances from the start of `a` so far to every start of `b`.
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Time every solver, then save the timings as baselines or check them against the saved ones.
+fn bench(
+    solver: &mut solver::Solver,
+    baseline: Option<solver::BaselineMode>,
+    args: &AppArgs,
+) -> color_eyre::Result<()> {
+    let path = std::path::Path::new(solver::BASELINE_FILE);
+    // Load the baselines first, so that a missing file doesn't waste a whole run.
+    let baselines = match baseline {
+        Some(solver::BaselineMode::Check) => Some(
+            solver::Baselines::load(path)
+                .suggestion("Save some first with `aoc2022 bench --baseline save`.")?,
+        ),
+        _ => None,
+    };
+
+    let report = solver.bench(
+        &args.input_dir,
+        args.runs,
+        args.input_checks,
+        progress(args).as_mut(),
+    );
+
+    if let Some(baselines) = baselines {
+        let check = report.check(&baselines, args.max_regression);
+        print!("{check}");
+        if check.is_err() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    print!("{report}");
+    if baseline == Some(solver::BaselineMode::Save) {
+        report.baselines().save(path)?;
+        println!("\nSaved baselines to {}", path.display());
+    }
+    if report.is_err() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Solve every challenge on its puzzle input, print how it went, and save it to `--results`.
+fn run_all(solver: &mut solver::Solver, args: &AppArgs) -> color_eyre::Result<()> {
+    // Check where the results are going first, so that a typo doesn't waste a whole run.
+    let format = match &args.results {
+        Some(path) => match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Some(OutputFormat::Json),
+            Some("md") => Some(OutputFormat::Markdown),
+            _ => {
+                return Err(color_eyre::eyre::eyre!(
+                    "Don't know what format to save {} in",
+                    path.display()
+                ))
+                .suggestion("Name it like `results.json` or `results.md`.")
+            }
+        },
+        None => None,
+    };
+
+    let answers_path = Path::new(solver::ANSWERS_FILE);
+    let answers = answers_path
+        .exists()
+        .then(|| solver::KnownAnswers::load(answers_path))
+        .transpose()?;
+
+    let report = solver.run_all(
+        &args.input_dir,
+        answers.as_ref(),
+        args.input_checks,
+        progress(args).as_mut(),
+    );
+    print!("{report}");
+
+    if let (Some(path), Some(format)) = (&args.results, format) {
+        let text = match format {
+            OutputFormat::Json => format!("{:#}\n", report.to_json()),
+            OutputFormat::Markdown => report.to_markdown(),
+        };
+        std::fs::write(path, text)
+            .wrap_err_with(|| format!("Could not save the results to {}", path.display()))?;
+        println!("\nSaved the results to {}", path.display());
+    }
+
+    if report.is_err() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Where `all` and `bench` show how far they've got: bars on a terminal, a line per run otherwise,
+/// and nothing with `--quiet`.
+fn progress(args: &AppArgs) -> Box<dyn solver::ProgressSink> {
+    if args.quiet {
+        Box::new(solver::NoProgress)
+    } else {
+        solver::stderr_progress()
+    }
+}
+
+/// What `all --results` saves its results as.
+enum OutputFormat {
+    Json,
+    Markdown,
+}
+
+/// The settings that are in effect: those from `aoc2022.toml`, with the command line's flags
+/// applied.
+fn effective_settings(args: &AppArgs) -> Settings {
+    let format = if args.json {
+        AnswerFormat::Json
+    } else if args.quiet {
+        AnswerFormat::Quiet
+    } else {
+        AnswerFormat::Plain
+    };
+    Settings {
+        input_dir: Some(args.input_dir.clone()),
+        format: Some(format),
+        color: Some(args.color),
+        ..args.settings.clone()
+    }
+}
+
+/// Encrypt or decrypt every input in the input directory, with the key from `--keyfile` or the
+/// environment.
+fn convert_inputs(action: InputAction, args: &AppArgs) -> color_eyre::Result<()> {
+    use challenge::crypt;
+
+    let secret = match &args.keyfile {
+        Some(path) => crypt::Secret::from_keyfile(path)?,
+        None => crypt::Secret::from_env()?
+            .ok_or(crypt::CryptError::NoKey)
+            .suggestion("Or pass `--keyfile KEYFILE`.")?,
+    };
+
+    let input_dir = args.input_dir.as_path();
+    let (written, done) = match action {
+        InputAction::Encrypt => (crypt::encrypt_dir(input_dir, &secret), "Encrypted"),
+        InputAction::Decrypt => (crypt::decrypt_dir(input_dir, &secret), "Decrypted"),
     };
+    let written = written
+        .wrap_err_with(|| format!("Could not convert the inputs in {}", input_dir.display()))?;
+
+    for path in &written {
+        println!("{}", path.display());
+    }
+    eprintln!("{done} {} inputs.", written.len());
+    Ok(())
+}
+
+/// Draw what the solvers that draw things make of their examples, and save it to `--out`.
+fn gallery(solver: &mut solver::Solver, args: &AppArgs) -> color_eyre::Result<()> {
+    let dir = args
+        .out
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(solver::DEFAULT_GALLERY_DIR));
+    let report = solver.gallery(&dir)?;
+    print!("{report}");
+    if report.is_err() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// An error and each of its causes, one per line.
+fn error_chain(error: &(dyn std::error::Error + 'static)) -> String {
+    std::iter::successors(Some(error), |e| e.source())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Load the solvers from every `--plugin`, and switch to the ones from `--solver`.
+fn load_plugins(solver: &mut solver::Solver, args: &AppArgs) -> color_eyre::Result<()> {
+    #[cfg(feature = "plugins")]
+    for path in &args.plugins {
+        solver.load_plugin(path)?;
+    }
+    #[cfg(not(feature = "plugins"))]
+    if !args.plugins.is_empty() {
+        return Err(color_eyre::eyre::eyre!("This build can't load plugins")
+            .suggestion("Rebuild aoc2022 with `--features plugins`"));
+    }
+
+    if let Some(namespace) = &args.solver {
+        solver.select(namespace)?;
+    }
+    Ok(())
+}
 
-    let remaining = pargs.finish();
-    if !remaining.is_empty() {
-        eprintln!("Warning: unused arguments left: {remaining:?}");
+/// Check that everything is in place to solve challenges, and suggest fixes for anything that
+/// isn't.
+fn doctor(solver: &solver::Solver, args: &AppArgs) -> color_eyre::Result<()> {
+    let report = doctor::DoctorReport::run(
+        &args.input_dir,
+        &solver.challenges(),
+        args.settings.session_file.as_deref(),
+    );
+    print!("{report}");
+
+    let problems = report.problems().count();
+    if problems == 0 {
+        return Ok(());
+    }
+    let mut error = color_eyre::eyre::eyre!("Found {problems} problems");
+    for suggestion in report
+        .problems()
+        .filter_map(|check| check.suggestion.clone())
+    {
+        error = error.suggestion(suggestion);
     }
+    Err(error)
+}
 
-    Ok(args)
+/// Parse the challenge number and subchallenge positional arguments.
+fn parse_target(pargs: &mut pico_args::Arguments) -> Result<Target, pico_args::Error> {
+    Ok(Target {
+        challenge: pargs.free_from_str()?,
+        subchallenge: pargs.free_from_str()?,
+    })
 }
 
 /// Parse an [`OsStr`][std::ffi::OsStr] into a [`PathBuf`].
@@ -129,3 +1675,170 @@ fn parse_args() -> Result<AppArgs, pico_args::Error> {
 fn parse_path_arg(s: &std::ffi::OsStr) -> Result<PathBuf, &'static str> {
     Ok(s.into())
 }
+
+/// Parse the two comma-separated algorithm names given to `--algos`.
+fn parse_algos(s: &str) -> Result<[String; 2], String> {
+    match s.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+        [a, b] if a == b => Err(format!("can't compare `{a}` with itself")),
+        [a, b] if !a.is_empty() && !b.is_empty() => Ok([a.to_string(), b.to_string()]),
+        _ => Err(format!(
+            "expected two algorithms like `scan,rotated`, not `{s}`"
+        )),
+    }
+}
+
+/// Print a made-up input for `challenge`.
+fn generate(
+    solver: &solver::Solver,
+    challenge: challenge::ChallengeNumber,
+    args: &AppArgs,
+) -> color_eyre::Result<()> {
+    let (generated, seed) = solver
+        .generate(challenge, args.size, args.seed)
+        .wrap_err_with(|| format!("Error while making up an input for challenge {challenge}"))?;
+
+    eprintln!("Generated with seed {seed}. Pass `--seed {seed}` to get the same output again.");
+    print!("{generated}");
+    Ok(())
+}
+
+/// Parse a (possibly fractional) number of seconds into a [`Duration`].
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    let secs: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    Duration::try_from_secs_f64(secs).map_err(|e| format!("{e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<AppArgs>, ArgsError> {
+        parse_args(
+            args.split_whitespace().map(OsString::from),
+            &[1, 2, 17],
+            Settings::default(),
+        )
+    }
+
+    #[test]
+    fn parses_flags_and_targets() {
+        let args = parse("--json 17 b --param floor=true -q").unwrap().unwrap();
+        let Command::Solve(target) = args.command else {
+            panic!("expected to solve, not {:?}", args.command);
+        };
+        assert_eq!(target.challenge, 17);
+        assert!(matches!(target.subchallenge, challenge::Subchallenge::B));
+        assert!(args.json && args.quiet && !args.visualize);
+        assert_eq!(args.params, [("floor".to_string(), "true".to_string())]);
+
+        assert!(matches!(
+            parse("scramble 2").unwrap().unwrap().command,
+            Command::Scramble(Target { challenge: 2, .. })
+        ));
+        assert_eq!(
+            parse("18 b --export-mesh droplet.stl")
+                .unwrap()
+                .unwrap()
+                .export_mesh,
+            Some(PathBuf::from("droplet.stl"))
+        );
+        let args = parse("generate 17 --size 50 --seed 3").unwrap().unwrap();
+        assert!(matches!(args.command, Command::Generate(17)));
+        assert_eq!((args.size, args.seed), (50, Some(3)));
+        assert!(parse("1 a --sample").unwrap().unwrap().sample);
+        assert!(parse("1 a --help --no-such-flag").unwrap().is_none());
+    }
+
+    #[test]
+    fn settings_fill_in_what_flags_dont_say() {
+        let settings = Settings {
+            input_dir: Some(PathBuf::from("puzzles")),
+            format: Some(AnswerFormat::Json),
+            color: Some(ColorChoice::Never),
+            ..Settings::default()
+        };
+        let parse = |args: &str| {
+            parse_args(
+                args.split_whitespace().map(OsString::from),
+                &[1],
+                settings.clone(),
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        let args = parse("1 a");
+        assert_eq!(args.input_dir, PathBuf::from("puzzles"));
+        assert!(args.json && !args.quiet);
+        assert_eq!(args.color, ColorChoice::Never);
+
+        assert_eq!(args.glyphs, GlyphChoice::Auto);
+
+        let args = parse("1 a --quiet --color always --ascii");
+        assert!(!args.json && args.quiet);
+        assert_eq!(args.color, ColorChoice::Always);
+        assert_eq!(args.glyphs, GlyphChoice::Ascii);
+        assert_eq!(effective_settings(&args).format, Some(AnswerFormat::Quiet));
+
+        assert!(matches!(parse("config show").command, Command::ConfigShow));
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_arguments() {
+        let err = parse("--visualise 1 a").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown flag `--visualise`, did you mean `--visualize`?"
+        );
+        let err = parse("1 a --tranform=x").unwrap_err();
+        assert!(
+            err.to_string().ends_with("did you mean `--transform`?"),
+            "{err}"
+        );
+        let err = parse("1 a --frobnicate").unwrap_err();
+        assert_eq!(err.to_string(), "unknown flag `--frobnicate`");
+        // `all`'s `--results` and `gallery`'s `--out` are far enough apart not to be mixed up.
+        let err = parse("all --outt results.md").unwrap_err();
+        assert!(err.to_string().ends_with("did you mean `--out`?"), "{err}");
+        let err = parse("all --result results.md").unwrap_err();
+        assert!(
+            err.to_string().ends_with("did you mean `--results`?"),
+            "{err}"
+        );
+
+        let err = parse("1 a extra").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected argument `extra`");
+    }
+
+    #[test]
+    fn rejects_challenges_past_christmas() {
+        for bad in ["0 a", "26 a", "validate 99 b", "scramble 30", "generate 0"] {
+            let err = parse(bad).unwrap_err();
+            assert!(
+                matches!(err, ArgsError::NoSuchChallenge { .. }),
+                "{bad}: {err}"
+            );
+            assert!(err.to_string().ends_with("challenges 1, 2, 17"), "{err}");
+        }
+        // A challenge without a solver is up to the solver to complain about.
+        assert!(parse("25 a").is_ok());
+    }
+
+    #[test]
+    fn every_flag_in_the_help_can_be_suggested() {
+        let mut documented = HELP
+            .lines()
+            .filter(|line| line.starts_with("  -"))
+            .flat_map(|line| {
+                line.split_whitespace()
+                    .map(|word| word.trim_end_matches(','))
+                    .take_while(|word| word.starts_with('-'))
+            })
+            .collect::<Vec<_>>();
+        let mut flags = FLAGS.to_vec();
+        documented.sort_unstable();
+        flags.sort_unstable();
+        assert_eq!(documented, flags);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
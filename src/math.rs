@@ -0,0 +1,251 @@
+//! Integer arithmetic that overflows however the user asked it to.
+//!
+//! Plain `+` and `*` panic on overflow in debug builds and silently wrap around in release builds,
+//! so an answer that's too big for its type just comes out wrong. Solvers that do a lot of
+//! arithmetic on big numbers go through [`MathMode`] instead, which can be switched to
+//! [`MathMode::Checked`] with `--math checked` to find out exactly where a number got too big.
+
+use std::{fmt, panic::Location, str::FromStr};
+
+use self::sealed::Op;
+
+/// What to do when integer arithmetic overflows, as given to `--math`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MathMode {
+    /// Fail with a [`MathError`] that says where the overflow happened.
+    Checked,
+    /// Wrap around, like release builds do with plain arithmetic.
+    #[default]
+    Wrapping,
+    /// Stop at the smallest or biggest number the type can hold.
+    Saturating,
+}
+
+impl MathMode {
+    /// `lhs + rhs`.
+    #[track_caller]
+    pub fn add<T: Integer>(self, lhs: T, rhs: T) -> Result<T, MathError> {
+        self.apply(Op::Add, lhs, rhs)
+    }
+
+    /// `lhs - rhs`.
+    #[track_caller]
+    pub fn sub<T: Integer>(self, lhs: T, rhs: T) -> Result<T, MathError> {
+        self.apply(Op::Sub, lhs, rhs)
+    }
+
+    /// `lhs * rhs`.
+    #[track_caller]
+    pub fn mul<T: Integer>(self, lhs: T, rhs: T) -> Result<T, MathError> {
+        self.apply(Op::Mul, lhs, rhs)
+    }
+
+    /// `lhs / rhs`. Dividing by zero fails no matter the mode, since there's nothing sensible to
+    /// wrap or saturate to.
+    #[track_caller]
+    pub fn div<T: Integer>(self, lhs: T, rhs: T) -> Result<T, MathError> {
+        self.apply(Op::Div, lhs, rhs)
+    }
+
+    /// `lhs % rhs`. Like [`MathMode::div`], this fails on a zero `rhs` no matter the mode.
+    #[track_caller]
+    pub fn rem<T: Integer>(self, lhs: T, rhs: T) -> Result<T, MathError> {
+        self.apply(Op::Rem, lhs, rhs)
+    }
+
+    #[track_caller]
+    fn apply<T: Integer>(self, op: Op, lhs: T, rhs: T) -> Result<T, MathError> {
+        // Closures can't track their caller, so this has to be looked up out here.
+        let location = Location::caller();
+        let error = |kind| MathError {
+            expression: format!("{lhs} {op} {rhs}"),
+            type_name: T::NAME,
+            kind,
+            location,
+        };
+
+        if matches!(op, Op::Div | Op::Rem) && rhs == T::ZERO {
+            return Err(error(MathErrorKind::DivideByZero));
+        }
+
+        let result = match self {
+            Self::Checked => T::checked(op, lhs, rhs),
+            Self::Wrapping => Some(T::wrapping(op, lhs, rhs)),
+            Self::Saturating => Some(T::saturating(op, lhs, rhs)),
+        };
+        result.ok_or_else(|| error(MathErrorKind::Overflow))
+    }
+}
+
+impl FromStr for MathMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Self::Checked),
+            "wrapping" => Ok(Self::Wrapping),
+            "saturating" => Ok(Self::Saturating),
+            _ => Err("expected `checked`, `wrapping` or `saturating`".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for MathMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Checked => write!(f, "checked"),
+            Self::Wrapping => write!(f, "wrapping"),
+            Self::Saturating => write!(f, "saturating"),
+        }
+    }
+}
+
+/// Arithmetic that went wrong under [`MathMode::Checked`], or a division by zero.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("`{expression}` {kind} {type_name} at {location}")]
+pub struct MathError {
+    /// The operation that failed, with its operands filled in, like `3 * 4`.
+    pub expression: String,
+    /// The type the operation was done in, like `i64`.
+    pub type_name: &'static str,
+    pub kind: MathErrorKind,
+    /// Where in the solver the operation was done.
+    pub location: &'static Location<'static>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathErrorKind {
+    /// The result didn't fit in the type.
+    Overflow,
+    /// The right-hand side of a division or remainder was zero.
+    DivideByZero,
+}
+
+impl fmt::Display for MathErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "overflowed"),
+            Self::DivideByZero => write!(f, "divided by zero in"),
+        }
+    }
+}
+
+/// A primitive integer type that [`MathMode`] can do arithmetic in.
+///
+/// Sealed, since the only types that make sense are the built-in ones.
+pub trait Integer: Copy + PartialEq + fmt::Display + sealed::Sealed {
+    /// The type's name, for error messages.
+    const NAME: &'static str;
+    const ZERO: Self;
+}
+
+mod sealed {
+    use std::fmt;
+
+    /// The operations themselves, kept out of [`Integer`](super::Integer) so that nobody else can
+    /// call or implement them.
+    pub trait Sealed: Sized {
+        fn checked(op: Op, lhs: Self, rhs: Self) -> Option<Self>;
+        fn wrapping(op: Op, lhs: Self, rhs: Self) -> Self;
+        fn saturating(op: Op, lhs: Self, rhs: Self) -> Self;
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Op {
+        Add,
+        Sub,
+        Mul,
+        Div,
+        Rem,
+    }
+
+    impl fmt::Display for Op {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let symbol = match self {
+                Self::Add => "+",
+                Self::Sub => "-",
+                Self::Mul => "*",
+                Self::Div => "/",
+                Self::Rem => "%",
+            };
+            f.write_str(symbol)
+        }
+    }
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Integer for $ty {
+                const NAME: &'static str = stringify!($ty);
+                const ZERO: Self = 0;
+            }
+
+            impl sealed::Sealed for $ty {
+                fn checked(op: Op, lhs: Self, rhs: Self) -> Option<Self> {
+                    match op {
+                        Op::Add => lhs.checked_add(rhs),
+                        Op::Sub => lhs.checked_sub(rhs),
+                        Op::Mul => lhs.checked_mul(rhs),
+                        Op::Div => lhs.checked_div(rhs),
+                        Op::Rem => lhs.checked_rem(rhs),
+                    }
+                }
+
+                fn wrapping(op: Op, lhs: Self, rhs: Self) -> Self {
+                    match op {
+                        Op::Add => lhs.wrapping_add(rhs),
+                        Op::Sub => lhs.wrapping_sub(rhs),
+                        Op::Mul => lhs.wrapping_mul(rhs),
+                        Op::Div => lhs.wrapping_div(rhs),
+                        Op::Rem => lhs.wrapping_rem(rhs),
+                    }
+                }
+
+                fn saturating(op: Op, lhs: Self, rhs: Self) -> Self {
+                    match op {
+                        Op::Add => lhs.saturating_add(rhs),
+                        Op::Sub => lhs.saturating_sub(rhs),
+                        Op::Mul => lhs.saturating_mul(rhs),
+                        Op::Div => lhs.saturating_div(rhs),
+                        // The only remainder that overflows is `MIN % -1`, which is 0 anyway.
+                        Op::Rem => lhs.wrapping_rem(rhs),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i32, i64, u32, u64, u128, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflows_by_mode() {
+        let err = MathMode::Checked.mul(i64::MAX, 2).unwrap_err();
+        let line = line!() - 1;
+        assert_eq!(err.kind, MathErrorKind::Overflow);
+        assert_eq!(err.location.file(), file!());
+        assert_eq!(err.location.line(), line);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "`9223372036854775807 * 2` overflowed i64 at {}",
+                err.location
+            )
+        );
+
+        assert_eq!(MathMode::Wrapping.mul(i64::MAX, 2).unwrap(), -2);
+        assert_eq!(MathMode::Saturating.mul(i64::MAX, 2).unwrap(), i64::MAX);
+        assert_eq!(MathMode::Saturating.sub(3_usize, 5).unwrap(), 0);
+        assert_eq!(MathMode::Checked.add(3_u128, 5).unwrap(), 8);
+
+        for mode in [MathMode::Checked, MathMode::Wrapping, MathMode::Saturating] {
+            let err = mode.rem(7_u64, 0).unwrap_err();
+            assert_eq!(err.kind, MathErrorKind::DivideByZero);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Triangle meshes, which can be saved as OBJ or binary STL files for 3D viewers and printers.
+//!
+//! Meshes are built out of the faces of voxels, so every corner is on whole-number coordinates,
+//! and corners shared between faces are only stored once.
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use nalgebra_glm::{IVec3, Vec3};
+
+use crate::error::{Context, SolverError};
+
+/// A surface made of triangles, each wound counter-clockwise when seen from the front.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Mesh {
+    vertices: Vec<IVec3>,
+    /// Where each of `vertices` is in the list, so that shared corners can be found again.
+    indices: HashMap<IVec3, u32>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a flat four-sided face, with its corners listed counter-clockwise as seen from the
+    /// front. It's split into two triangles.
+    pub(crate) fn add_quad(&mut self, corners: [IVec3; 4]) {
+        let [a, b, c, d] = corners.map(|corner| self.vertex(corner));
+        self.triangles.push([a, b, c]);
+        self.triangles.push([a, c, d]);
+    }
+
+    fn vertex(&mut self, position: IVec3) -> u32 {
+        *self.indices.entry(position).or_insert_with(|| {
+            self.vertices.push(position);
+            (self.vertices.len() - 1) as u32
+        })
+    }
+
+    pub(crate) fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub(crate) fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// The corners of every triangle, in winding order.
+    pub(crate) fn triangles(&self) -> impl Iterator<Item = [IVec3; 3]> + '_ {
+        self.triangles
+            .iter()
+            .map(|triangle| triangle.map(|i| self.vertices[i as usize]))
+    }
+
+    /// Write the mesh as a Wavefront OBJ file, which is plain text.
+    pub(crate) fn write_obj(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "# {} vertices, {} triangles",
+            self.vertex_count(),
+            self.triangle_count()
+        )?;
+        for v in &self.vertices {
+            writeln!(writer, "v {} {} {}", v.x, v.y, v.z)?;
+        }
+        // OBJ counts vertices from 1.
+        for [a, b, c] in &self.triangles {
+            writeln!(writer, "f {} {} {}", a + 1, b + 1, c + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Write the mesh as a binary STL file, which is what most slicers for 3D printers expect.
+    pub(crate) fn write_stl(&self, mut writer: impl Write) -> io::Result<()> {
+        // The header can say anything, as long as it doesn't start with `solid`, which would make
+        // it look like a text STL file.
+        let mut header = [0; 80];
+        let note = b"Binary STL from aoc2022";
+        header[..note.len()].copy_from_slice(note);
+        writer.write_all(&header)?;
+        writer.write_all(&(self.triangle_count() as u32).to_le_bytes())?;
+
+        for corners in self.triangles() {
+            let [a, b, c] =
+                corners.map(|corner| Vec3::new(corner.x as f32, corner.y as f32, corner.z as f32));
+            let normal = (b - a).cross(&(c - a)).normalize();
+            for v in [normal, a, b, c] {
+                for component in v.iter() {
+                    writer.write_all(&component.to_le_bytes())?;
+                }
+            }
+            // Nothing uses the "attribute byte count", so it's always zero.
+            writer.write_all(&[0, 0])?;
+        }
+        Ok(())
+    }
+
+    /// Save the mesh to `file`, replacing it if it already exists.
+    pub(crate) fn save(&self, file: &MeshFile) -> Result<(), SolverError> {
+        let context = || format!("Could not write mesh to {}", file.path.display());
+
+        let writer = io::BufWriter::new(fs::File::create(&file.path).with_context(context)?);
+        match file.format {
+            MeshFormat::Obj => self.write_obj(writer),
+            MeshFormat::Stl => self.write_stl(writer),
+        }
+        .with_context(context)
+    }
+}
+
+/// Which kind of file to save a [`Mesh`] as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MeshFormat {
+    Obj,
+    Stl,
+}
+
+impl MeshFormat {
+    /// The format that a file's extension asks for, if it's one of them.
+    fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "obj" => Some(Self::Obj),
+            "stl" => Some(Self::Stl),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MeshFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Obj => write!(f, "OBJ"),
+            Self::Stl => write!(f, "STL"),
+        }
+    }
+}
+
+/// Where to save a [`Mesh`], given as a path ending in `.obj` or `.stl`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MeshFile {
+    pub(crate) path: PathBuf,
+    pub(crate) format: MeshFormat,
+}
+
+impl FromStr for MeshFile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = PathBuf::from(s);
+        let format = MeshFormat::for_path(&path)
+            .ok_or_else(|| "expected a file ending in `.obj` or `.stl`".to_string())?;
+        Ok(Self { path, format })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_a_square() {
+        let mut mesh = Mesh::new();
+        mesh.add_quad([
+            IVec3::new(0, 0, 0),
+            IVec3::new(1, 0, 0),
+            IVec3::new(1, 1, 0),
+            IVec3::new(0, 1, 0),
+        ]);
+        assert_eq!((mesh.vertex_count(), mesh.triangle_count()), (4, 2));
+
+        let mut obj = Vec::new();
+        mesh.write_obj(&mut obj).unwrap();
+        assert_eq!(
+            String::from_utf8(obj).unwrap(),
+            "# 4 vertices, 2 triangles\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             f 1 2 3\nf 1 3 4\n"
+        );
+
+        let mut stl = Vec::new();
+        mesh.write_stl(&mut stl).unwrap();
+        assert_eq!(stl.len(), 80 + 4 + 2 * 50);
+        assert_eq!(stl[80..84], 2_u32.to_le_bytes());
+        // The first triangle faces up the z axis.
+        let normal_z = f32::from_le_bytes(stl[92..96].try_into().unwrap());
+        assert_eq!(normal_z, 1.0);
+
+        assert_eq!(
+            "droplet.STL".parse::<MeshFile>().unwrap().format,
+            MeshFormat::Stl
+        );
+        assert!("droplet.png".parse::<MeshFile>().is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! A side panel for changing a solver's parameters while its window is open.
+//!
+//! Every field edits one of the solver's [`SolverParams`], as a string, so a solver reads the
+//! panel's values back with the same code that handles `--param` on the command line, and gets
+//! the same errors for bad values.
+
+use std::ops::RangeInclusive;
+
+use crate::solver::SolverParams;
+
+/// A side panel with a widget for each parameter, and a button to restart with the new values.
+///
+/// Built up one field at a time, like
+/// `ParamPanel::new().text("spawns", "Spawn points", "500,0").toggle("floor", "Floor", true)`.
+pub(crate) struct ParamPanel {
+    fields: Vec<Field>,
+    /// Why the last values couldn't be applied, shown under the button.
+    error: Option<String>,
+}
+
+struct Field {
+    key: &'static str,
+    label: &'static str,
+    value: Value,
+}
+
+enum Value {
+    Text(String),
+    Toggle(bool),
+    Slider(f32, RangeInclusive<f32>),
+}
+
+impl ParamPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            fields: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Add a text box, for parameters like lists of points.
+    pub(crate) fn text(self, key: &'static str, label: &'static str, value: impl ToString) -> Self {
+        self.field(key, label, Value::Text(value.to_string()))
+    }
+
+    /// Add a checkbox, for parameters given as `key=true` or `key=false`.
+    pub(crate) fn toggle(self, key: &'static str, label: &'static str, value: bool) -> Self {
+        self.field(key, label, Value::Toggle(value))
+    }
+
+    /// Add a slider on a logarithmic scale, which suits speeds and sizes.
+    pub(crate) fn slider(
+        self,
+        key: &'static str,
+        label: &'static str,
+        range: RangeInclusive<f32>,
+        value: f32,
+    ) -> Self {
+        self.field(key, label, Value::Slider(value, range))
+    }
+
+    fn field(mut self, key: &'static str, label: &'static str, value: Value) -> Self {
+        self.fields.push(Field { key, label, value });
+        self
+    }
+
+    /// Every parameter as it currently is in the panel, whether or not it's been applied.
+    ///
+    /// Parameters that make sense to change on the fly, like speeds, can be read from here every
+    /// frame.
+    pub(crate) fn params(&self) -> SolverParams {
+        let mut params = SolverParams::default();
+        for field in &self.fields {
+            let value = match &field.value {
+                Value::Text(text) => text.clone(),
+                Value::Toggle(on) => on.to_string(),
+                Value::Slider(value, _) => value.to_string(),
+            };
+            params.insert(field.key, value);
+        }
+        params
+    }
+
+    /// Show why the parameters from the last [`ParamPanel::show`] couldn't be used, or clear the
+    /// error with `None`.
+    pub(crate) fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    /// Show the panel on the right of the window. Returns the parameters to restart with if the
+    /// button was clicked.
+    ///
+    /// Has to be called before the window's central panel is shown.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> Option<SolverParams> {
+        let mut apply = false;
+        egui::SidePanel::right("params")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Parameters");
+                ui.add_space(4.0);
+
+                for field in &mut self.fields {
+                    match &mut field.value {
+                        Value::Text(text) => {
+                            ui.label(field.label);
+                            ui.text_edit_singleline(text);
+                        }
+                        Value::Toggle(on) => {
+                            ui.checkbox(on, field.label);
+                        }
+                        Value::Slider(value, range) => {
+                            ui.label(field.label);
+                            ui.add(
+                                egui::Slider::new(value, range.clone())
+                                    .logarithmic(true)
+                                    .smallest_positive(0.1),
+                            );
+                        }
+                    }
+                    ui.add_space(4.0);
+                }
+
+                ui.separator();
+                apply = ui.button("Apply & restart simulation").clicked();
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+            });
+
+        apply.then(|| self.params())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_come_from_every_field() {
+        let panel = ParamPanel::new()
+            .text("spawns", "Spawn points", "500,0")
+            .toggle("floor", "Floor", true)
+            .slider("speed", "Speed", 0.1..=100.0, 2.5);
+
+        let params = panel.params();
+        assert_eq!(params.get::<String>("spawns").unwrap().unwrap(), "500,0");
+        assert_eq!(params.get::<bool>("floor").unwrap(), Some(true));
+        assert_eq!(params.get::<f32>("speed").unwrap(), Some(2.5));
+    }
+}
@@ -0,0 +1,854 @@
+//! Shared helpers for parsing challenge inputs with [`nom`] and [`nom_supreme`].
+
+use std::{collections::BTreeMap, fmt, ops::Range};
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+use nom::{
+    character::complete::{space0, space1},
+    combinator::all_consuming,
+    error::{context, ContextError, ParseError},
+    sequence::preceded,
+    AsChar, Compare, Finish, IResult, InputTake, InputTakeAtPosition, Parser,
+};
+use nom_locate::LocatedSpan;
+use nom_supreme::{
+    error::{ErrorTree, GenericErrorTree},
+    final_parser::final_parser,
+    tag::{complete::tag, TagError},
+};
+
+/// A string slice that keeps track of where it is in the original input.
+pub type Span<'a> = LocatedSpan<&'a str>;
+
+/// The most bad lines that [`parse_lines_all`] will point out in one report. Any more are only
+/// counted.
+pub const MAX_DIAGNOSTICS: usize = 20;
+
+/// A line's number in a challenge input, counting from 1 like editors do.
+pub type LineNo = usize;
+
+/// Run `parser` over an entire challenge input.
+///
+/// The parser has to consume all of `input`. Any parsing errors will be printed out to `stderr`
+/// with fancy formatting.
+pub fn parse_input<'a, T, P>(input: &'a str, parser: P) -> Result<T, BadInputError>
+where
+    P: Parser<Span<'a>, T, ErrorTree<Span<'a>>>,
+{
+    final_parser(parser)(Span::new(input)).map_err(|e: ErrorTree<Span>| {
+        let err = BadInputError::new(input, &e);
+        eprintln!("{}", err.render());
+        err
+    })
+}
+
+/// Run `parser` over every line of a challenge input, collecting the results.
+///
+/// The parser has to consume each line entirely. Like [`parse_input`], any parsing errors will be
+/// printed out to `stderr` with fancy formatting, pointing at the line and column where parsing
+/// went wrong.
+pub fn parse_lines<'a, T, P>(input: &'a str, parser: P) -> Result<Vec<T>, BadInputError>
+where
+    P: Parser<&'a str, T, ErrorTree<&'a str>>,
+{
+    parse_lines_with(input, parser, false).map(without_line_numbers)
+}
+
+/// Like [`parse_lines`], but carries on past bad lines instead of stopping at the first one.
+///
+/// Every bad line gets pointed out in the same report, like a compiler would, so that fixing up a
+/// hand-edited input doesn't take one run per mistake. Only the first [`MAX_DIAGNOSTICS`] are
+/// shown, and the rest are counted.
+pub fn parse_lines_all<'a, T, P>(input: &'a str, parser: P) -> Result<Vec<T>, BadInputError>
+where
+    P: Parser<&'a str, T, ErrorTree<&'a str>>,
+{
+    parse_lines_with(input, parser, true).map(without_line_numbers)
+}
+
+/// Like [`parse_lines_all`], but keeps the number of the line that each item came from.
+///
+/// Meant for programs, like a list of instructions, where some mistakes only show up once they
+/// run. The line number lets the error point back at the instruction that caused it.
+pub fn parse_numbered_lines<'a, T, P>(
+    input: &'a str,
+    parser: P,
+) -> Result<Vec<(LineNo, T)>, BadInputError>
+where
+    P: Parser<&'a str, T, ErrorTree<&'a str>>,
+{
+    parse_lines_with(input, parser, true)
+}
+
+/// Like [`parse_lines_all`], but with `skip_bad_lines`, bad lines are left out with a warning
+/// instead of failing, for `--skip-bad-lines`.
+///
+/// Returns what parsed, and how many lines were skipped. The warning points out the skipped lines
+/// the same way that the error would have, so that the corrupted parts of the input can be found
+/// while there's a provisional answer to go on.
+pub fn parse_lines_or_skip<'a, T, P>(
+    input: &'a str,
+    parser: P,
+    skip_bad_lines: bool,
+) -> Result<(Vec<T>, usize), BadInputError>
+where
+    P: Parser<&'a str, T, ErrorTree<&'a str>>,
+{
+    if !skip_bad_lines {
+        return parse_lines_all(input, parser).map(|parsed| (parsed, 0));
+    }
+
+    let (parsed, bad) = collect_lines(input, parser, true);
+    let skipped = bad.map_or(0, warn_skipped);
+    Ok((without_line_numbers(parsed), skipped))
+}
+
+/// Point out the lines that `bad` skipped in a warning, and count them.
+fn warn_skipped(mut bad: BadInputError) -> usize {
+    let skipped = bad.count();
+    bad.summary = Some(match skipped {
+        1 => "Skipped a line that couldn't be parsed".to_string(),
+        _ => format!("Skipped {skipped} lines that couldn't be parsed"),
+    });
+    eprintln!("{}", bad.render());
+    skipped
+}
+
+fn without_line_numbers<T>(numbered: Vec<(LineNo, T)>) -> Vec<T> {
+    numbered.into_iter().map(|(_, value)| value).collect()
+}
+
+fn parse_lines_with<'a, T, P>(
+    input: &'a str,
+    parser: P,
+    collect_all: bool,
+) -> Result<Vec<(LineNo, T)>, BadInputError>
+where
+    P: Parser<&'a str, T, ErrorTree<&'a str>>,
+{
+    match collect_lines(input, parser, collect_all) {
+        (parsed, None) => Ok(parsed),
+        (_, Some(err)) => {
+            eprintln!("{}", err.render());
+            Err(err)
+        }
+    }
+}
+
+/// Parse every line that parses, and point out the ones that don't, stopping at the first of those
+/// unless `collect_all`.
+fn collect_lines<'a, T, P>(
+    input: &'a str,
+    mut parser: P,
+    collect_all: bool,
+) -> (Vec<(LineNo, T)>, Option<BadInputError>)
+where
+    P: Parser<&'a str, T, ErrorTree<&'a str>>,
+{
+    let mut parsed = Vec::new();
+    let mut problems = Vec::new();
+    let mut omitted = 0;
+    let mut line_start = 0;
+
+    for (i, raw_line) in input.split_inclusive('\n').enumerate() {
+        let start = line_start;
+        line_start += raw_line.len();
+
+        let line = raw_line.strip_suffix('\n').unwrap_or(raw_line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        match all_consuming(|i| parser.parse(i))(line).finish() {
+            Ok((_, value)) => parsed.push((i + 1, value)),
+            Err(e) => {
+                if problems.len() < MAX_DIAGNOSTICS {
+                    // Every location in the error is a suffix of `line`.
+                    let (offset, kind) =
+                        furthest_error(&e, &|rest: &&str| start + line.len() - rest.len());
+                    problems.push((offset..offset, kind));
+                } else {
+                    omitted += 1;
+                }
+                if !collect_all {
+                    break;
+                }
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        return (parsed, None);
+    }
+    let err = BadInputError {
+        src: input.to_string(),
+        problems,
+        omitted,
+        summary: None,
+    };
+    (parsed, Some(err))
+}
+
+/// How many lines [`LineParser`] keeps after a bad line. The report shows bad lines this close
+/// together in one snippet, with every line in between.
+const LINES_SHOWN_AFTER: usize = 8;
+
+/// Parses an input a line at a time, as it's read with [`for_each_line`], and points out the
+/// lines that don't parse the same way that [`parse_lines_or_skip`] would.
+///
+/// Only the bad lines and the lines right around them are kept for the report, so the rest of the
+/// input never has to be held in memory. Blank lines are fine at the end of the input, and bad
+/// lines anywhere else.
+///
+/// [`for_each_line`]: crate::input_iter::for_each_line
+#[derive(Debug, Default)]
+pub struct LineParser {
+    /// Whether bad lines are left out with a warning, instead of failing.
+    skip_bad_lines: bool,
+    /// The number of the last line that was parsed, counting from 1.
+    line_no: usize,
+    /// How many blank lines came after the last line that wasn't blank.
+    blank_run: usize,
+    /// The last line that was parsed, in case the next one doesn't parse.
+    prev: String,
+    /// How many more lines to keep, to show after the last line that didn't parse.
+    keep_next: usize,
+    /// The lines to show in the report, by their numbers.
+    kept: BTreeMap<usize, String>,
+    /// Each bad line's number, how far into it parsing failed, and why.
+    problems: Vec<(usize, usize, String)>,
+    /// How many more bad lines there were, past [`MAX_DIAGNOSTICS`].
+    omitted: usize,
+}
+
+impl LineParser {
+    /// Start parsing an input, where bad lines are left out with a warning instead of failing if
+    /// `skip_bad_lines`, like [`parse_lines_or_skip`].
+    pub fn new(skip_bad_lines: bool) -> Self {
+        Self {
+            skip_bad_lines,
+            ..Default::default()
+        }
+    }
+
+    /// Parse the next line of the input with `parser`, which has to consume all of it.
+    ///
+    /// Returns `None` if the line is blank or doesn't parse. Either way, what went wrong is only
+    /// reported by [`LineParser::finish`].
+    pub fn parse<'a, T, P>(&mut self, line: &'a str, mut parser: P) -> Option<T>
+    where
+        P: Parser<&'a str, T, ErrorTree<&'a str>>,
+    {
+        if line.trim().is_empty() {
+            self.blank_run += 1;
+            return None;
+        }
+        for _ in 0..std::mem::take(&mut self.blank_run) {
+            self.next_line("");
+            self.problem("", 0, "blank line".to_string());
+            self.prev.clear();
+        }
+
+        self.next_line(line);
+        let parsed = match all_consuming(|i| parser.parse(i))(line).finish() {
+            Ok((_, value)) => Some(value),
+            Err(e) => {
+                // Every location in the error is a suffix of `line`.
+                let (offset, kind) = furthest_error(&e, &|rest: &&str| line.len() - rest.len());
+                self.problem(line, offset, kind);
+                None
+            }
+        };
+        self.prev.clear();
+        self.prev.push_str(line);
+        parsed
+    }
+
+    /// Count `line` as the next line of the input.
+    fn next_line(&mut self, line: &str) {
+        self.line_no += 1;
+        if self.keep_next > 0 {
+            self.keep_next -= 1;
+            self.kept.insert(self.line_no, line.to_string());
+        }
+    }
+
+    /// Point out that the current line, with `line` as its text, went wrong `offset` bytes in.
+    fn problem(&mut self, line: &str, offset: usize, kind: String) {
+        if self.problems.len() == MAX_DIAGNOSTICS {
+            self.omitted += 1;
+            return;
+        }
+        self.problems.push((self.line_no, offset, kind));
+        self.kept.insert(self.line_no, line.to_string());
+        if self.line_no > 1 {
+            let prev = self.prev.clone();
+            self.kept.entry(self.line_no - 1).or_insert(prev);
+        }
+        self.keep_next = LINES_SHOWN_AFTER;
+    }
+
+    /// Finish parsing the input, and report any lines that didn't parse.
+    ///
+    /// Returns how many lines were skipped, or an error pointing out every bad line if they
+    /// weren't supposed to be skipped. Either way, the bad lines are printed to `stderr`.
+    pub fn finish(self) -> Result<usize, BadInputError> {
+        if self.problems.is_empty() {
+            return Ok(0);
+        }
+
+        // Put every kept line back on its own line number, with the lines in between left blank,
+        // so that the report numbers them the same as the input.
+        let mut src = String::new();
+        let mut line_starts = Vec::new();
+        let last = self
+            .kept
+            .last_key_value()
+            .map_or(0, |(&line_no, _)| line_no);
+        for line_no in 1..=last {
+            line_starts.push(src.len());
+            if let Some(line) = self.kept.get(&line_no) {
+                src.push_str(line);
+            }
+            src.push('\n');
+        }
+        let problems = self
+            .problems
+            .into_iter()
+            .map(|(line_no, offset, kind)| {
+                let offset = line_starts[line_no - 1] + offset;
+                (offset..offset, kind)
+            })
+            .collect();
+
+        let bad = BadInputError {
+            src,
+            problems,
+            omitted: self.omitted,
+            summary: None,
+        };
+        if self.skip_bad_lines {
+            return Ok(warn_skipped(bad));
+        }
+        eprintln!("{}", bad.render());
+        Err(bad)
+    }
+}
+
+/// Use a parser written for [`Span`]s on a plain string slice, like the lines that
+/// [`parse_lines`] hands out.
+pub fn spanned<'a, T, P>(
+    mut parser: P,
+) -> impl FnMut(&'a str) -> IResult<&'a str, T, ErrorTree<&'a str>>
+where
+    P: Parser<Span<'a>, T, ErrorTree<Span<'a>>>,
+{
+    move |i| {
+        parser
+            .parse(Span::new(i))
+            .map(|(rest, value)| (*rest.fragment(), value))
+            .map_err(|e| e.map(|e| e.map_locations(|location: Span<'a>| *location.fragment())))
+    }
+}
+
+/// How the components of a point are written out, for [`parse_point2`] and [`parse_point3`] to
+/// read, and [`PointFormat::write2`] and [`PointFormat::write3`] to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointFormat {
+    /// What comes between two components. Any spaces after it are skipped.
+    pub separator: &'static str,
+    /// What gets written after the separator, like the puzzle inputs do.
+    pub spacing: &'static str,
+    /// What comes right before the `x`, `y` and `z` components, in that order.
+    pub prefixes: [&'static str; 3],
+}
+
+impl PointFormat {
+    /// Bare components separated by commas, like `498,4` or `2,2,2`.
+    pub const COMMAS: Self = Self {
+        separator: ",",
+        spacing: "",
+        prefixes: ["", "", ""],
+    };
+
+    /// Components labelled with their axes, like `x=-2, y=15`.
+    pub const LABELLED: Self = Self {
+        separator: ",",
+        spacing: " ",
+        prefixes: ["x=", "y=", "z="],
+    };
+
+    /// Write out a 2D point so that [`parse_point2`] reads it back in as the same point.
+    ///
+    /// ```
+    /// use aoc2022::prelude::*;
+    ///
+    /// let mut written = String::new();
+    /// PointFormat::LABELLED.write2(&mut written, (-2, 15))?;
+    /// assert_eq!(written, "x=-2, y=15");
+    /// # Ok::<(), std::fmt::Error>(())
+    /// ```
+    pub fn write2<T: fmt::Display>(&self, f: &mut impl fmt::Write, (x, y): (T, T)) -> fmt::Result {
+        self.write_components(f, &[&x, &y])
+    }
+
+    /// Write out a 3D point so that [`parse_point3`] reads it back in as the same point.
+    pub fn write3<T: fmt::Display>(
+        &self,
+        f: &mut impl fmt::Write,
+        (x, y, z): (T, T, T),
+    ) -> fmt::Result {
+        self.write_components(f, &[&x, &y, &z])
+    }
+
+    fn write_components(
+        &self,
+        f: &mut impl fmt::Write,
+        components: &[&dyn fmt::Display],
+    ) -> fmt::Result {
+        for (axis, component) in components.iter().enumerate() {
+            if axis > 0 {
+                write!(f, "{}{}", self.separator, self.spacing)?;
+            }
+            write!(f, "{}{component}", self.prefixes[axis])?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse an instruction written as an opcode and an operand with spaces between them, like
+/// `addx 3` or `R 4`.
+pub fn parse_instruction<I, O, A, E, P, Q>(
+    mut opcode: P,
+    mut operand: Q,
+) -> impl FnMut(I) -> IResult<I, (O, A), E>
+where
+    I: Clone + InputTakeAtPosition,
+    <I as InputTakeAtPosition>::Item: AsChar + Clone,
+    E: ParseError<I> + ContextError<I>,
+    P: Parser<I, O, E>,
+    Q: Parser<I, A, E>,
+{
+    move |i| {
+        let (i, op) = opcode.parse(i)?;
+        let (i, _) = space1(i)?;
+        let (i, arg) = context("operand", |i| operand.parse(i))(i)?;
+        Ok((i, (op, arg)))
+    }
+}
+
+/// Parse a 2D point written out like `format`, with each component parsed by `component`.
+///
+/// The point comes out as an `(x, y)` tuple, like a [`geometry::Point`](crate::geometry::Point)
+/// when the components are `i64`s.
+pub fn parse_point2<I, T, E, P>(
+    format: PointFormat,
+    mut component: P,
+) -> impl FnMut(I) -> IResult<I, (T, T), E>
+where
+    I: Clone + InputTake + Compare<&'static str> + InputTakeAtPosition,
+    <I as InputTakeAtPosition>::Item: AsChar + Clone,
+    E: ParseError<I> + ContextError<I> + TagError<I, &'static str>,
+    P: Parser<I, T, E>,
+{
+    move |i| {
+        let (i, x) = point_component(format, 0, &mut component, i)?;
+        let (i, y) = point_component(format, 1, &mut component, i)?;
+        Ok((i, (x, y)))
+    }
+}
+
+/// Parse a 3D point written out like `format`, with each component parsed by `component`.
+///
+/// The point comes out as an `(x, y, z)` tuple.
+pub fn parse_point3<I, T, E, P>(
+    format: PointFormat,
+    mut component: P,
+) -> impl FnMut(I) -> IResult<I, (T, T, T), E>
+where
+    I: Clone + InputTake + Compare<&'static str> + InputTakeAtPosition,
+    <I as InputTakeAtPosition>::Item: AsChar + Clone,
+    E: ParseError<I> + ContextError<I> + TagError<I, &'static str>,
+    P: Parser<I, T, E>,
+{
+    move |i| {
+        let (i, x) = point_component(format, 0, &mut component, i)?;
+        let (i, y) = point_component(format, 1, &mut component, i)?;
+        let (i, z) = point_component(format, 2, &mut component, i)?;
+        Ok((i, (x, y, z)))
+    }
+}
+
+/// Parse the component of a point along `axis`, along with the separator before it.
+fn point_component<I, T, E, P>(
+    format: PointFormat,
+    axis: usize,
+    component: &mut P,
+    i: I,
+) -> IResult<I, T, E>
+where
+    I: Clone + InputTake + Compare<&'static str> + InputTakeAtPosition,
+    <I as InputTakeAtPosition>::Item: AsChar + Clone,
+    E: ParseError<I> + ContextError<I> + TagError<I, &'static str>,
+    P: Parser<I, T, E>,
+{
+    const CONTEXTS: [&str; 3] = ["x component", "y component", "z component"];
+
+    let i = if axis == 0 {
+        i
+    } else {
+        let (i, _) = tag(format.separator)(i)?;
+        space0(i)?.0
+    };
+    context(
+        CONTEXTS[axis],
+        preceded(tag(format.prefixes[axis]), |i| component.parse(i)),
+    )(i)
+}
+
+/// A challenge input that couldn't be parsed, along with where parsing went wrong.
+#[derive(thiserror::Error, Debug)]
+pub struct BadInputError {
+    src: String,
+
+    /// The bytes of `src` that each problem covers, and what went wrong there.
+    problems: Vec<(Range<usize>, String)>,
+
+    /// How many more problems there were, past [`MAX_DIAGNOSTICS`].
+    omitted: usize,
+
+    /// What was wrong with the input as a whole, when it parsed but made no sense.
+    summary: Option<String>,
+}
+
+impl BadInputError {
+    /// Describe the error that got the furthest into `src`.
+    ///
+    /// When several alternatives all failed, the one that made the most progress is usually the
+    /// one the input was meant to match.
+    pub fn new(src: &str, err: &ErrorTree<Span>) -> Self {
+        let (offset, kind) = furthest_error(err, &|location: &Span| location.location_offset());
+        Self {
+            src: src.to_string(),
+            problems: vec![(offset..offset, kind)],
+            omitted: 0,
+            summary: None,
+        }
+    }
+
+    /// Point out problems with `src` that were found after it parsed, like a board that doesn't
+    /// fold up the way it should, under a `summary` of what's wrong.
+    ///
+    /// Each problem covers a range of bytes in `src`. Only the first [`MAX_DIAGNOSTICS`] are kept,
+    /// and the rest are counted. There has to be at least one problem.
+    pub fn spans(
+        src: &str,
+        summary: impl Into<String>,
+        problems: impl IntoIterator<Item = (Range<usize>, String)>,
+    ) -> Self {
+        let mut problems = problems.into_iter();
+        let kept = problems.by_ref().take(MAX_DIAGNOSTICS).collect::<Vec<_>>();
+        assert!(!kept.is_empty(), "a bad input needs at least one problem");
+        Self {
+            src: src.to_string(),
+            problems: kept,
+            omitted: problems.count(),
+            summary: Some(summary.into()),
+        }
+    }
+
+    /// The byte offset into the input where parsing first failed.
+    pub fn offset(&self) -> usize {
+        self.problems[0].0.start
+    }
+
+    /// The byte offset of every problem that's pointed out, in order.
+    pub fn offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        self.problems.iter().map(|(span, _)| span.start)
+    }
+
+    /// The line of every problem that's pointed out, in order.
+    pub fn lines(&self) -> impl Iterator<Item = LineNo> + '_ {
+        self.offsets().map(|offset| line_at(&self.src, offset))
+    }
+
+    /// How many problems there were in total, including those past [`MAX_DIAGNOSTICS`].
+    pub fn count(&self) -> usize {
+        self.problems.len() + self.omitted
+    }
+
+    /// Render the error with fancy formatting, pointing out where in the input it happened.
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        crate::color::report_handler()
+            .render_report(&mut s, self)
+            .expect("writing to a String can't fail");
+        s
+    }
+}
+
+impl fmt::Display for BadInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(summary) = &self.summary {
+            return write!(f, "{summary}");
+        }
+        match self.count() {
+            1 => write!(f, "Error parsing input"),
+            count => write!(f, "Error parsing input: {count} lines couldn't be parsed"),
+        }
+    }
+}
+
+impl Diagnostic for BadInputError {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.src)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(self.problems.iter().map(|(span, kind)| {
+            LabeledSpan::new(Some(kind.clone()), span.start, span.len())
+        })))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        (self.omitted > 0).then(|| {
+            Box::new(format!("...and {} more, which aren't shown", self.omitted))
+                as Box<dyn fmt::Display>
+        })
+    }
+}
+
+/// The line that the byte at `offset` in `src` is on.
+pub fn line_at(src: &str, offset: usize) -> LineNo {
+    let before = &src.as_bytes()[..offset.min(src.len())];
+    before.iter().filter(|&&byte| byte == b'\n').count() + 1
+}
+
+/// The bytes of `line` in `src`, without its line ending, for pointing out a line in a
+/// [`BadInputError::spans`]. Lines past the end are an empty span at the end of `src`.
+pub fn line_span(src: &str, line: LineNo) -> Range<usize> {
+    let mut start = 0;
+    for (i, raw_line) in src.split_inclusive('\n').enumerate() {
+        if i + 1 == line {
+            let text = raw_line.trim_end_matches(['\n', '\r']);
+            return start..start + text.len();
+        }
+        start += raw_line.len();
+    }
+    src.len()..src.len()
+}
+
+/// Describe the error from a parser for [`Span`]s that got the furthest, like
+/// [`BadInputError::new`] does, as a byte offset into what it was given and what went wrong there.
+///
+/// Useful for parsers that only see a piece of the input at a time, to point out the problem in
+/// their own way.
+pub fn furthest_span_error(err: &ErrorTree<Span>) -> (usize, String) {
+    furthest_error(err, &|location: &Span| location.location_offset())
+}
+
+fn furthest_error<I>(err: &ErrorTree<I>, offset_of: &impl Fn(&I) -> usize) -> (usize, String) {
+    match err {
+        GenericErrorTree::Base { location, kind } => (offset_of(location), kind.to_string()),
+
+        GenericErrorTree::Stack { base, contexts } => {
+            let (offset, kind) = furthest_error(base, offset_of);
+            match contexts.first() {
+                Some((_, context)) => (offset, format!("{kind}, {context}")),
+                None => (offset, kind),
+            }
+        }
+
+        GenericErrorTree::Alt(alts) => alts
+            .iter()
+            .map(|alt| furthest_error(alt, offset_of))
+            .max_by_key(|(offset, _)| *offset)
+            .unwrap_or_else(|| (0, "no alternatives to try".to_string())),
+    }
+}
+
+/// Checking that values survive being written out and parsed back in.
+#[cfg(test)]
+pub(crate) mod round_trip {
+    use std::fmt::{self, Debug};
+
+    use proptest::{
+        strategy::Strategy,
+        test_runner::{Config, TestCaseError, TestRunner},
+    };
+
+    /// Values at and next to the limits of `i32`, where writing and parsing tend to go wrong.
+    pub(crate) const EXTREME_I32: [i32; 6] = [i32::MIN, i32::MIN + 1, -1, 0, 1, i32::MAX];
+    /// Values at and next to the limits of `i64`, where writing and parsing tend to go wrong.
+    pub(crate) const EXTREME_I64: [i64; 6] = [i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX];
+
+    /// Check that every one of `extremes`, and then whatever `strategy` comes up with, can be
+    /// written out with `write` and come back the same from `parse`.
+    ///
+    /// Panics with the written text of the first value that doesn't.
+    pub(crate) fn check<S>(
+        strategy: S,
+        extremes: impl IntoIterator<Item = S::Value>,
+        write: impl Fn(&mut String, &S::Value) -> fmt::Result,
+        parse: impl Fn(&str) -> Option<S::Value>,
+    ) where
+        S: Strategy,
+        S::Value: PartialEq + Debug,
+    {
+        let round_trip = |value: &S::Value| {
+            let mut written = String::new();
+            write(&mut written, value).unwrap();
+            match parse(&written) {
+                Some(parsed) if parsed == *value => Ok(()),
+                parsed => Err(format!(
+                    "{value:?} was written as {written:?} and parsed as {parsed:?}"
+                )),
+            }
+        };
+
+        for value in extremes {
+            if let Err(e) = round_trip(&value) {
+                panic!("{e}");
+            }
+        }
+
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        if let Err(e) = runner.run(&strategy, |value| {
+            round_trip(&value).map_err(TestCaseError::fail)
+        }) {
+            panic!("{e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::character::complete::{i32, i64, u32};
+
+    use super::*;
+
+    #[test]
+    fn parse_lines_points_at_bad_line() {
+        assert_eq!(parse_lines("1\r\n2\n3\n", u32).unwrap(), vec![1, 2, 3]);
+
+        let err = parse_lines("1\n22\n3x\n4\n", u32).unwrap_err();
+        assert_eq!(err.offset(), "1\n22\n3".len());
+        assert_eq!(err.count(), 1);
+    }
+
+    #[test]
+    fn parse_lines_all_points_at_every_bad_line() {
+        let err = parse_lines_all("1\nx\n3\n4y\n5\n", u32).unwrap_err();
+        assert_eq!(err.offsets().collect::<Vec<_>>(), vec![2, 7]);
+        assert_eq!(err.lines().collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(
+            err.to_string(),
+            "Error parsing input: 2 lines couldn't be parsed"
+        );
+
+        assert_eq!(err.labels().unwrap().count(), 2);
+        assert!(err.help().is_none());
+
+        let input = "x\n".repeat(MAX_DIAGNOSTICS + 5);
+        let err = parse_lines_all(&input, u32).unwrap_err();
+        assert_eq!(err.offsets().count(), MAX_DIAGNOSTICS);
+        assert_eq!(err.count(), MAX_DIAGNOSTICS + 5);
+        assert_eq!(
+            err.help().unwrap().to_string(),
+            "...and 5 more, which aren't shown"
+        );
+    }
+
+    #[test]
+    fn skips_bad_lines_only_when_asked_to() {
+        let input = "1\nx\n3\n4y\n5\n";
+        assert_eq!(
+            parse_lines_or_skip(input, u32, true).unwrap(),
+            (vec![1, 3, 5], 2)
+        );
+        assert_eq!(
+            parse_lines_or_skip("1\n2\n", u32, true).unwrap(),
+            (vec![1, 2], 0)
+        );
+        let err = parse_lines_or_skip(input, u32, false).unwrap_err();
+        assert_eq!(err.lines().collect::<Vec<_>>(), vec![2, 4]);
+
+        // Every bad line is counted, even past the ones that the warning points out.
+        let input = "x\n7\n".repeat(MAX_DIAGNOSTICS + 5);
+        let (parsed, skipped) = parse_lines_or_skip(&input, u32, true).unwrap();
+        assert_eq!(parsed.len(), MAX_DIAGNOSTICS + 5);
+        assert_eq!(skipped, MAX_DIAGNOSTICS + 5);
+    }
+
+    #[test]
+    fn parses_line_by_line_like_the_whole_input() {
+        let parse = |input: &str, skip_bad_lines| {
+            let mut lines = LineParser::new(skip_bad_lines);
+            let parsed = input
+                .lines()
+                .filter_map(|line| lines.parse(line, u32))
+                .collect::<Vec<_>>();
+            lines.finish().map(|skipped| (parsed, skipped))
+        };
+
+        let input = "1\nx\n3\n4y\n5\n\n\n";
+        assert_eq!(parse(input, true).unwrap(), (vec![1, 3, 5], 2));
+        let err = parse(input, false).unwrap_err();
+        let whole = parse_lines_or_skip(input.trim_end(), u32, false).unwrap_err();
+        assert_eq!(err.lines().collect::<Vec<_>>(), [2, 4]);
+        assert_eq!(err.to_string(), whole.to_string());
+        assert!(
+            err.render().contains("3"),
+            "the lines around bad ones are shown"
+        );
+
+        let err = parse(&format!("{}x\n", "1\n".repeat(1000)), false).unwrap_err();
+        assert_eq!(err.lines().collect::<Vec<_>>(), [1001]);
+        let err = parse("1\n\n2\n", false).unwrap_err();
+        assert_eq!(err.lines().collect::<Vec<_>>(), [2]);
+
+        let err = parse(&"x\n".repeat(MAX_DIAGNOSTICS + 5), false).unwrap_err();
+        assert_eq!(err.count(), MAX_DIAGNOSTICS + 5);
+    }
+
+    #[test]
+    fn parses_points_in_either_format() {
+        let point = |i| parse_point2::<_, _, ErrorTree<&str>, _>(PointFormat::LABELLED, i64)(i);
+        assert_eq!(point("x=-2, y=15: rest").unwrap(), (": rest", (-2, 15)));
+        assert_eq!(point("x=3,y=4").unwrap(), ("", (3, 4)));
+        assert!(point("x=3, z=4").is_err());
+
+        let err =
+            parse_lines("2,2,2\n1,x,2\n", parse_point3(PointFormat::COMMAS, i32)).unwrap_err();
+        assert!(err.render().contains("y component"));
+        assert_eq!(
+            parse_lines("2,2,2\n-1,2,5\n", parse_point3(PointFormat::COMMAS, i32)).unwrap(),
+            vec![(2, 2, 2), (-1, 2, 5)]
+        );
+    }
+    #[test]
+    fn numbers_instructions_by_their_lines() {
+        let op = || {
+            parse_instruction(
+                nom::branch::alt((tag("addx"), tag("subx"))),
+                i32::<_, ErrorTree<&str>>,
+            )
+        };
+        assert_eq!(
+            parse_numbered_lines("addx 3\r\nsubx  -12\n", op()).unwrap(),
+            vec![(1, ("addx", 3)), (2, ("subx", -12))]
+        );
+
+        let err = parse_numbered_lines("addx 3\naddx\naddx 1\naddx x\n", op()).unwrap_err();
+        assert_eq!(err.lines().collect::<Vec<_>>(), vec![2, 4]);
+        assert!(err.render().contains("operand"), "{}", err.render());
+
+        assert_eq!(line_at("a\nb\nc", 0), 1);
+        assert_eq!(line_at("a\nb\nc", 4), 3);
+        assert_eq!(line_at("a\nb\nc", 99), 3);
+        assert_eq!(line_span("ab\r\ncd\ne", 2), 4..6);
+        assert_eq!(line_span("ab\r\ncd\ne", 3), 7..8);
+        assert_eq!(line_span("ab\r\ncd\ne", 4), 8..8);
+    }
+}
@@ -0,0 +1,372 @@
+//! Shortest-path search over implicit graphs, where a node's neighbors are only worked out when
+//! the search reaches it.
+//!
+//! The graphs here are *time-expanded*: each node is a position at a moment in time, and every
+//! step moves one minute forward. That's how mazes with moving obstacles are searched, where a
+//! cell that's blocked now might be open a minute later. If the obstacles repeat every `period`
+//! minutes, then two states at the same position whose times match modulo the period have the
+//! same futures, and only the earlier one needs searching. That keeps the search finite even
+//! when the goal can't be reached.
+//!
+//! Built with day 24's blizzard basin in mind. Graphs that don't change over time are just the
+//! special case where `period` is 1, like day 12's heightmap.
+//!
+//! Where steps cost different amounts, like climbing a steep slope versus walking on the flat,
+//! [`dijkstra`] finds the cheapest path instead of the shortest one.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    hash::Hash,
+};
+
+use crate::geometry::Point;
+
+/// A node in a time-expanded graph.
+pub(crate) trait TimedState: Sized {
+    /// Where on the grid this state is.
+    fn position(&self) -> Point;
+
+    /// How many minutes have passed to get to this state.
+    fn time(&self) -> u64;
+
+    /// Every state that can be reached in a single step, all at `time` (which is always one
+    /// minute after [`TimedState::time`]).
+    ///
+    /// Waiting in place is a successor like any other, if it's allowed.
+    fn successors(&self, time: u64) -> Vec<Self>;
+}
+
+/// The least common multiple of `a` and `b`, for combining the periods of obstacles that repeat
+/// at different rates.
+#[allow(dead_code)] // groundwork for day 24
+pub(crate) const fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+const fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Find the earliest state at `goal` that can be reached from any of the `starts`, using A* with
+/// `distance` to the goal as its heuristic.
+///
+/// Every step must cover at most 1 of `distance`, or the heuristic will overestimate and the
+/// answer might not be the earliest. That means [`manhattan`](crate::geometry::manhattan) for
+/// steps along the grid, or [`chebyshev`](crate::geometry::chebyshev) if diagonal steps are
+/// allowed too. Whatever decides which steps are possible
+/// must repeat every `period` minutes.
+///
+/// Returns `None` if the goal can't be reached at all.
+pub(crate) fn find_earliest<S: TimedState>(
+    starts: impl IntoIterator<Item = S>,
+    goal: Point,
+    period: u64,
+    distance: fn(Point, Point) -> u64,
+) -> Option<S> {
+    let mut frontier = BinaryHeap::new();
+    let mut seen = HashSet::new();
+    frontier.extend(
+        starts
+            .into_iter()
+            .map(|start| Candidate::new(start, goal, distance)),
+    );
+
+    while let Some(Candidate { state, .. }) = frontier.pop() {
+        if state.position() == goal {
+            return Some(state);
+        }
+
+        // States are popped in order of their estimate, and two states at the same position have
+        // the same distance left to go, so the first of them to be popped is the earliest.
+        if !seen.insert((state.position(), state.time() % period)) {
+            continue;
+        }
+
+        let time = state.time() + 1;
+        frontier.extend(
+            state
+                .successors(time)
+                .into_iter()
+                .map(|next| Candidate::new(next, goal, distance)),
+        );
+    }
+
+    None
+}
+
+/// Find the cheapest path from any of the `starts` to a node that `is_goal` accepts, where
+/// `successors` gives every node that can be stepped to from a node, along with what the step
+/// costs.
+///
+/// Steps can cost any amount, including nothing. Returns the path, including both ends, along with
+/// its total cost, or `None` if no goal can be reached. Of several equally cheap paths, the one
+/// whose nodes were found first wins.
+pub(crate) fn dijkstra<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut successors: impl FnMut(&N) -> I,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Option<(Vec<N>, u64)>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    let mut search = WeightedSearch {
+        found: Vec::new(),
+        cheapest: HashMap::new(),
+        frontier: BinaryHeap::new(),
+    };
+    for start in starts {
+        search.reach(start, 0, None);
+    }
+
+    while let Some(Reverse((cost, i))) = search.frontier.pop() {
+        let node = search.found[i].0.clone();
+        // A cheaper way here was found after this one was added to the frontier.
+        if search.cheapest[&node].1 != i {
+            continue;
+        }
+
+        if is_goal(&node) {
+            return Some((search.path_to(i), cost));
+        }
+
+        for (next, step) in successors(&node) {
+            search.reach(next, cost + step, Some(i));
+        }
+    }
+
+    None
+}
+
+/// Everything [`dijkstra`] has found so far.
+struct WeightedSearch<N> {
+    /// Every node that's been reached, along with where it was reached from, as an index into
+    /// `found`. A node is found again every time a cheaper way to it turns up.
+    found: Vec<(N, Option<usize>)>,
+    /// The cheapest cost found to each node so far, and where in `found` that way is.
+    cheapest: HashMap<N, (u64, usize)>,
+    /// The cost of every way in `found` that hasn't been followed yet, along with its index.
+    frontier: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl<N: Clone + Eq + Hash> WeightedSearch<N> {
+    /// Add a way to `node` that costs `cost`, unless there's already one at least as cheap.
+    fn reach(&mut self, node: N, cost: u64, from: Option<usize>) {
+        if self
+            .cheapest
+            .get(&node)
+            .is_some_and(|&(best, _)| best <= cost)
+        {
+            return;
+        }
+        let i = self.found.len();
+        self.found.push((node.clone(), from));
+        self.cheapest.insert(node, (cost, i));
+        self.frontier.push(Reverse((cost, i)));
+    }
+
+    /// The nodes along the way to `found[i]`, from where it started.
+    fn path_to(&self, i: usize) -> Vec<N> {
+        let mut path = Vec::new();
+        let mut next = Some(i);
+        while let Some(j) = next {
+            let (node, from) = &self.found[j];
+            path.push(node.clone());
+            next = *from;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// A state waiting in the frontier, along with the earliest time that it could possibly reach
+/// the goal.
+struct Candidate<S> {
+    estimate: u64,
+    state: S,
+}
+
+impl<S: TimedState> Candidate<S> {
+    fn new(state: S, goal: Point, distance: fn(Point, Point) -> u64) -> Self {
+        Self {
+            estimate: state.time() + distance(state.position(), goal),
+            state,
+        }
+    }
+}
+
+impl<S> PartialEq for Candidate<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl<S> Eq for Candidate<S> {}
+
+impl<S> PartialOrd for Candidate<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Ord for Candidate<S> {
+    /// Reversed, since [`BinaryHeap`] is a max-heap and the lowest estimate should come first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.cmp(&self.estimate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::manhattan;
+
+    /// A `width` by `height` room, where each row has one obstacle sliding east and each column
+    /// has one sliding south, both wrapping around at the walls.
+    struct Room {
+        width: i64,
+        height: i64,
+        walls: HashSet<Point>,
+    }
+
+    impl Room {
+        fn period(&self) -> u64 {
+            lcm(self.width as u64, self.height as u64)
+        }
+
+        fn blocked(&self, (x, y): Point, time: u64) -> bool {
+            let t = time as i64;
+            let in_bounds = (0..self.width).contains(&x) && (0..self.height).contains(&y);
+            !in_bounds
+                || self.walls.contains(&(x, y))
+                // The row's obstacle starts at x = y, and the column's at y = 2x.
+                || (y + t).rem_euclid(self.width) == x
+                || (2 * x + t).rem_euclid(self.height) == y
+        }
+
+        /// Every open neighbor of `position` at `time`, including itself.
+        fn moves(&self, (x, y): Point, time: u64) -> impl Iterator<Item = Point> + '_ {
+            [(x, y), (x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(move |&p| !self.blocked(p, time))
+        }
+
+        /// The earliest arrival at `goal`, found by stepping every reachable position forward a
+        /// minute at a time.
+        fn flood(&self, start: Point, goal: Point, max_time: u64) -> Option<u64> {
+            let mut reachable = HashSet::from([start]);
+            for time in 0..max_time {
+                if reachable.contains(&goal) {
+                    return Some(time);
+                }
+                reachable = reachable
+                    .iter()
+                    .flat_map(|&p| self.moves(p, time + 1))
+                    .collect();
+            }
+            None
+        }
+    }
+
+    struct Walker<'r> {
+        room: &'r Room,
+        position: Point,
+        time: u64,
+    }
+
+    impl TimedState for Walker<'_> {
+        fn position(&self) -> Point {
+            self.position
+        }
+
+        fn time(&self) -> u64 {
+            self.time
+        }
+
+        fn successors(&self, time: u64) -> Vec<Self> {
+            self.room
+                .moves(self.position, time)
+                .map(|position| Walker {
+                    room: self.room,
+                    position,
+                    time,
+                })
+                .collect()
+        }
+    }
+
+    fn room(width: i64, height: i64, walls: impl IntoIterator<Item = Point>) -> Room {
+        Room {
+            width,
+            height,
+            walls: walls.into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn dodges_moving_obstacles() {
+        let room = room(6, 4, [(2, 1), (2, 2), (4, 0)]);
+        let start = Walker {
+            room: &room,
+            position: (0, 3),
+            time: 0,
+        };
+        assert!(!room.blocked(start.position, 0));
+
+        let goal = (5, 0);
+        let expected = room.flood(start.position, goal, 1000);
+        // The obstacles should actually get in the way.
+        assert!(expected > Some(manhattan(start.position, goal)));
+
+        let found = find_earliest([start], goal, room.period(), manhattan);
+        assert_eq!(found.map(|walker| walker.time()), expected);
+    }
+
+    #[test]
+    fn gives_up_when_walled_off() {
+        let room = room(5, 5, (0..5).map(|y| (3, y)));
+        let start = Walker {
+            room: &room,
+            position: (0, 2),
+            time: 0,
+        };
+        assert!(!room.blocked(start.position, 0));
+
+        assert!(find_earliest([start], (4, 2), room.period(), manhattan).is_none());
+    }
+
+    #[test]
+    fn finds_the_cheapest_path_not_the_shortest() {
+        // Going straight from 0 to 3 is expensive, while the long way round is cheap, and the
+        // free step from 4 to 2 makes 4 worth going through.
+        let edges: &[(u8, u8, u64)] = &[(0, 3, 10), (0, 1, 2), (1, 4, 1), (4, 2, 0), (2, 3, 3)];
+        let successors = |&node: &u8| {
+            edges
+                .iter()
+                .filter(move |&&(from, _, _)| from == node)
+                .map(|&(_, to, cost)| (to, cost))
+        };
+
+        assert_eq!(
+            dijkstra([0], successors, |&node| node == 3),
+            Some((vec![0, 1, 4, 2, 3], 6))
+        );
+        // Starting anywhere along the way makes it cheaper still.
+        assert_eq!(
+            dijkstra([0, 4], successors, |&node| node == 3),
+            Some((vec![4, 2, 3], 3))
+        );
+        assert_eq!(dijkstra([3], successors, |&node| node == 0), None);
+    }
+
+    #[test]
+    fn lcm_of_periods() {
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(7, 5), 35);
+        assert_eq!(lcm(120, 25), 600);
+    }
+}
@@ -0,0 +1,35 @@
+//! Everything that using the crate as a library usually needs, to import in one go.
+//!
+//! ```
+//! use aoc2022::prelude::*;
+//!
+//! let mut solver = Solver::new();
+//! let input: ChallengeInput = Box::new(std::io::Cursor::new("mjqjpqmgbljsphdztnvjfqwrcgsmlb\n"));
+//! let answer = solver.solve(6, Subchallenge::A, input, &SolverConfig::default())?;
+//! assert_eq!(answer, Answer::new(7_usize));
+//! # Ok::<(), SolveError>(())
+//! ```
+//!
+//! The parsing helpers are the same ones the solvers use, so they can be tried out on their own:
+//!
+//! ```
+//! use aoc2022::prelude::*;
+//!
+//! let points = parse_lines_all(
+//!     "1,2,3\n4,5,6\n",
+//!     parse_point3(PointFormat::COMMAS, nom::character::complete::u32),
+//! )?;
+//! assert_eq!(points, [(1, 2, 3), (4, 5, 6)]);
+//! # Ok::<(), BadInputError>(())
+//! ```
+
+pub use crate::{
+    challenge::{ChallengeInput, ChallengeNumber, Subchallenge},
+    error::SolverError,
+    grid::{BitGrid, Grid, GridCoord},
+    parse::{
+        parse_input, parse_lines, parse_lines_all, parse_point2, parse_point3, BadInputError,
+        PointFormat, Span,
+    },
+    solver::{Answer, Preset, SolveError, Solver, SolverConfig, SolverParams},
+};
@@ -0,0 +1,153 @@
+//! Pictures made of colored pixels, which can be saved as PNG files.
+//!
+//! Visualizations draw on a [`Raster`] once, and then either show it in a terminal UI or write it
+//! out with [`Raster::save_png`], so that both always show the same thing.
+
+use std::{fs, io, path::Path};
+
+use owo_colors::Rgb;
+
+use crate::error::{Context, SolverError};
+
+/// A fixed-size picture, where every pixel has a color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Raster {
+    /// Create a picture filled with `background`.
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The color at `(x, y)`, or `None` if it's outside of the picture.
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    /// Color a single pixel. Does nothing if `(x, y)` is outside of the picture.
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Blow the picture up by `factor`, turning each pixel into a `factor` by `factor` square.
+    ///
+    /// Maps with one pixel per cell are too small to make out in an image viewer otherwise.
+    pub fn scaled(&self, factor: usize) -> Self {
+        let mut scaled = Self::new(self.width * factor, self.height * factor, Rgb(0, 0, 0));
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                scaled.pixels[y * scaled.width + x] =
+                    self.pixels[y / factor * self.width + x / factor];
+            }
+        }
+        scaled
+    }
+
+    /// Encode the picture as an 8-bit RGB PNG.
+    pub fn write_png(&self, writer: impl io::Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let data = self
+            .pixels
+            .iter()
+            .flat_map(|&Rgb(r, g, b)| [r, g, b])
+            .collect::<Vec<_>>();
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
+    }
+
+    /// Save the picture as a PNG file at `path`, replacing it if it already exists.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), SolverError> {
+        let path = path.as_ref();
+        let context = || format!("Could not write image to {}", path.display());
+
+        let file = fs::File::create(path).with_context(context)?;
+        self.write_png(io::BufWriter::new(file))
+            .with_context(context)
+    }
+}
+
+/// A color for `fraction` of the way along a heatmap scale, from a dark blue for 0 through red to
+/// a pale yellow for 1.
+///
+/// Fractions outside of `0.0..=1.0` are clamped.
+pub fn heat_color(fraction: f64) -> Rgb {
+    const STOPS: [(f64, f64, f64); 4] = [
+        (20.0, 20.0, 60.0),
+        (120.0, 30.0, 130.0),
+        (230.0, 70.0, 40.0),
+        (255.0, 240.0, 160.0),
+    ];
+
+    let scaled = fraction.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (scaled as usize).min(STOPS.len() - 2);
+    let t = scaled - i as f64;
+    let ((r0, g0, b0), (r1, g1, b1)) = (STOPS[i], STOPS[i + 1]);
+    let lerp = |a: f64, b: f64| (a + (b - a) * t).round() as u8;
+
+    Rgb(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = Rgb(255, 0, 0);
+    const BLACK: Rgb = Rgb(0, 0, 0);
+
+    #[test]
+    fn scaling_repeats_pixels() {
+        let mut raster = Raster::new(2, 1, BLACK);
+        raster.set(1, 0, RED);
+        raster.set(5, 5, RED);
+
+        let scaled = raster.scaled(3);
+        assert_eq!((scaled.width(), scaled.height()), (6, 3));
+        assert_eq!(scaled.get(2, 2), Some(BLACK));
+        assert_eq!(scaled.get(3, 0), Some(RED));
+        assert_eq!(scaled.get(6, 0), None);
+    }
+
+    #[test]
+    fn png_roundtrip() {
+        let mut raster = Raster::new(3, 2, BLACK);
+        raster.set(2, 1, RED);
+
+        let mut bytes = Vec::new();
+        raster.write_png(&mut bytes).unwrap();
+
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&data[15..18], &[255, 0, 0]);
+        assert_eq!(&data[..3], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn heat_scale_ends() {
+        assert_eq!(heat_color(0.0), Rgb(20, 20, 60));
+        assert_eq!(heat_color(1.0), Rgb(255, 240, 160));
+        assert_eq!(heat_color(7.0), heat_color(1.0));
+    }
+}
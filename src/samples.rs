@@ -0,0 +1,133 @@
+//! The example inputs from the puzzle descriptions, and what the solvers should answer for them.
+//!
+//! Every sample lives in `samples/NN.txt` at the root of the repository, and is built into the
+//! binary, so that `aoc2022 selftest` works from anywhere. The answers are all kept together in
+//! `samples/answers.txt`. The tests, the self-test and the examples in the docs all read from
+//! there, so a sample only ever has to be fixed in one place.
+
+use crate::challenge::{ChallengeNumber, Subchallenge};
+
+/// Builds [`INPUTS`] out of the challenge numbers that have a sample file.
+macro_rules! sample_files {
+    [$($challenge:literal => $file:literal),* $(,)?] => {
+        &[$(($challenge, include_str!(concat!("../samples/", $file, ".txt")))),*]
+    };
+}
+
+/// Every sample input, by challenge number.
+const INPUTS: &[(ChallengeNumber, &str)] = sample_files![
+    1 => "01",
+    2 => "02",
+    3 => "03",
+    4 => "04",
+    5 => "05",
+    6 => "06",
+    7 => "07",
+    8 => "08",
+    9 => "09",
+    10 => "10",
+    11 => "11",
+    12 => "12",
+    13 => "13",
+    14 => "14",
+    15 => "15",
+    16 => "16",
+    17 => "17",
+    18 => "18",
+    19 => "19",
+    20 => "20",
+    21 => "21",
+    22 => "22",
+];
+
+/// The answers to the samples, one `CHALLENGE SUBCHALLENGE ANSWER` per line.
+const ANSWERS: &str = include_str!("../samples/answers.txt");
+
+/// The sample input for a challenge, if there is one.
+///
+/// This is a `const fn`, so that tests can keep their sample in a constant with
+/// [`sample_input!`].
+pub const fn input(challenge: ChallengeNumber) -> Option<&'static str> {
+    let mut i = 0;
+    while i < INPUTS.len() {
+        if INPUTS[i].0 == challenge {
+            return Some(INPUTS[i].1);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// What a challenge's solver should answer for its sample, as `--quiet` would print it.
+pub fn answer(challenge: ChallengeNumber, subchallenge: Subchallenge) -> Option<&'static str> {
+    answers().find_map(|(number, sub, answer)| {
+        (number == challenge && sub == subchallenge.as_str()).then_some(answer)
+    })
+}
+
+/// [`answer`] parsed as a `T`, for a solver's tests.
+///
+/// Panics if there's no answer, or it isn't a `T`.
+pub fn golden<T>(challenge: ChallengeNumber, subchallenge: Subchallenge) -> T
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let answer = answer(challenge, subchallenge).unwrap_or_else(|| {
+        panic!("samples/answers.txt has no answer for {challenge} {subchallenge}")
+    });
+    answer.parse().unwrap_or_else(|e| {
+        panic!("Bad answer for {challenge} {subchallenge} in samples/answers.txt: {e}")
+    })
+}
+
+/// Every line of [`ANSWERS`], split up into its challenge, subchallenge and answer.
+fn answers() -> impl Iterator<Item = (ChallengeNumber, &'static str, &'static str)> {
+    ANSWERS
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut field = || fields.next().expect("samples/answers.txt has a short line");
+            let challenge = field()
+                .parse()
+                .expect("samples/answers.txt has a bad challenge number");
+            (challenge, field(), field())
+        })
+}
+
+/// The sample input for a challenge, as a `&'static str` constant.
+///
+/// Fails to compile if there's no `samples/NN.txt` for the challenge.
+macro_rules! sample_input {
+    ($challenge:expr) => {
+        match $crate::samples::input($challenge) {
+            Some(input) => input,
+            None => panic!("There is no sample input for this challenge"),
+        }
+    };
+}
+pub(crate) use sample_input;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_answer_has_a_sample() {
+        for (challenge, subchallenge, _) in answers() {
+            assert!(
+                input(challenge).is_some(),
+                "samples/answers.txt has an answer for {challenge} {subchallenge}, but there's no \
+                 sample input for it"
+            );
+            assert!(
+                matches!(subchallenge, "a" | "b"),
+                "{subchallenge} isn't a subchallenge"
+            );
+        }
+
+        assert_eq!(answer(5, Subchallenge::B), Some("MCD"));
+        assert_eq!(answer(22, Subchallenge::B), None);
+    }
+}
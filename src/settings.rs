@@ -0,0 +1,293 @@
+//! Defaults for the command line, from an optional [`SETTINGS_FILE`] in the working directory.
+//!
+//! Every setting is optional, and any flag given on the command line wins over the file:
+//!
+//! ```toml
+//! input_dir = "puzzles"
+//! format = "json"
+//! color = "never"
+//! threads = 4
+//! session_file = "/home/me/.config/aocd/token"
+//!
+//! [presets]
+//! 15 = "real"
+//!
+//! [params.16]
+//! plan = true
+//! ```
+//!
+//! Parameters for a challenge are used as if they were given with `--param` before any that
+//! actually are, and after the challenge's preset.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    challenge::{ChallengeNumber, CHALLENGES},
+    color::ColorChoice,
+    error::{Context, SolverError},
+    solver::Preset,
+};
+
+/// Where the settings are kept, relative to the working directory.
+pub const SETTINGS_FILE: &str = "aoc2022.toml";
+
+/// How answers are printed, as `format` in [`SETTINGS_FILE`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnswerFormat {
+    /// Whatever the solver prints, then the answer and its unit.
+    #[default]
+    Plain,
+    /// A single JSON object, like `--json`.
+    Json,
+    /// Only the answer, like `--quiet`.
+    Quiet,
+}
+
+impl FromStr for AnswerFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "json" => Ok(Self::Json),
+            "quiet" => Ok(Self::Quiet),
+            _ => Err("expected `plain`, `json` or `quiet`".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for AnswerFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plain => write!(f, "plain"),
+            Self::Json => write!(f, "json"),
+            Self::Quiet => write!(f, "quiet"),
+        }
+    }
+}
+
+/// Everything in [`SETTINGS_FILE`]. Anything it leaves out is `None` or empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    /// Where puzzle inputs are looked for, instead of [`INPUT_DIR`](crate::challenge::INPUT_DIR).
+    pub input_dir: Option<PathBuf>,
+    pub format: Option<AnswerFormat>,
+    pub color: Option<ColorChoice>,
+    /// How many threads the parallel searches use, instead of one per core.
+    pub threads: Option<NonZeroUsize>,
+    /// A file with the Advent of Code session token in it, for when
+    /// [`SESSION_VAR`](crate::doctor::SESSION_VAR) isn't set.
+    pub session_file: Option<PathBuf>,
+    /// The preset to use for each challenge, unless `--preset` is given.
+    pub presets: BTreeMap<ChallengeNumber, Preset>,
+    /// The parameters for each challenge, in order of their names.
+    pub params: BTreeMap<ChallengeNumber, Vec<(String, String)>>,
+}
+
+/// [`Settings`] as they're written in the file, before the challenge numbers and presets are
+/// checked.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SettingsFile {
+    input_dir: Option<PathBuf>,
+    #[serde(deserialize_with = "parsed")]
+    format: Option<AnswerFormat>,
+    #[serde(deserialize_with = "parsed")]
+    color: Option<ColorChoice>,
+    threads: Option<NonZeroUsize>,
+    session_file: Option<PathBuf>,
+    presets: BTreeMap<String, String>,
+    params: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+/// Deserialize a string with `T`'s [`FromStr`], so that settings are spelled like their flags.
+fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// A challenge number used as a key, like the `16` in `[params.16]`.
+fn challenge_key(table: &str, key: &str) -> Result<ChallengeNumber, String> {
+    key.trim_start_matches('0')
+        .parse()
+        .ok()
+        .filter(|challenge| CHALLENGES.contains(challenge))
+        .ok_or_else(|| format!("`{key}` in [{table}] isn't a challenge from 1 to 25"))
+}
+
+impl Settings {
+    /// Load the settings from `path`, or the defaults if there's no such file.
+    pub fn load(path: &Path) -> Result<Self, SolverError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Could not read settings from {}", path.display()))?;
+        Self::parse(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            .with_context(|| format!("Could not parse settings in {}", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let file: SettingsFile = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        let presets = file
+            .presets
+            .into_iter()
+            .map(|(key, preset)| {
+                let preset = preset
+                    .parse()
+                    .map_err(|e| format!("preset for `{key}`: {e}"))?;
+                Ok((challenge_key("presets", &key)?, preset))
+            })
+            .collect::<Result<_, String>>()?;
+
+        let params = file
+            .params
+            .into_iter()
+            .map(|(key, params)| {
+                let params = params
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = match value {
+                            toml::Value::String(value) => value,
+                            value => value.to_string(),
+                        };
+                        (name, value)
+                    })
+                    .collect();
+                Ok((challenge_key("params", &key)?, params))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self {
+            input_dir: file.input_dir,
+            format: file.format,
+            color: file.color,
+            threads: file.threads,
+            session_file: file.session_file,
+            presets,
+            params,
+        })
+    }
+
+    /// The parameters for `challenge`, which may be none.
+    pub fn params_for(&self, challenge: ChallengeNumber) -> &[(String, String)] {
+        self.params.get(&challenge).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Written as TOML, the way it would be written in [`SETTINGS_FILE`]. Settings that aren't set
+/// are left as comments.
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = |s: &dyn fmt::Display| toml::Value::String(s.to_string());
+        let path = |path: &Path| string(&path.display());
+
+        match &self.input_dir {
+            Some(dir) => writeln!(f, "input_dir = {}", path(dir))?,
+            None => writeln!(f, "# input_dir isn't set")?,
+        }
+        match self.format {
+            Some(format) => writeln!(f, "format = {}", string(&format))?,
+            None => writeln!(f, "# format isn't set")?,
+        }
+        match self.color {
+            Some(color) => writeln!(f, "color = {}", string(&color))?,
+            None => writeln!(f, "# color isn't set")?,
+        }
+        match self.threads {
+            Some(threads) => writeln!(f, "threads = {threads}")?,
+            None => writeln!(f, "# threads isn't set, so there's one per core")?,
+        }
+        match &self.session_file {
+            Some(file) => writeln!(f, "session_file = {}", path(file))?,
+            None => writeln!(f, "# session_file isn't set")?,
+        }
+
+        if !self.presets.is_empty() {
+            writeln!(f, "\n[presets]")?;
+            for (challenge, preset) in &self.presets {
+                writeln!(f, "{challenge} = {}", string(preset))?;
+            }
+        }
+        for (challenge, params) in &self.params {
+            writeln!(f, "\n[params.{challenge}]")?;
+            for (name, value) in params {
+                writeln!(f, "{name} = {}", string(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_writes_settings() {
+        let text = r#"
+input_dir = "puzzles"
+format = "json"
+color = "never"
+threads = 4
+
+[presets]
+15 = "real"
+
+[params.016]
+plan = true
+start = "AA"
+"#;
+        let settings = Settings::parse(text).unwrap();
+        assert_eq!(settings.input_dir, Some(PathBuf::from("puzzles")));
+        assert_eq!(settings.format, Some(AnswerFormat::Json));
+        assert_eq!(settings.color, Some(ColorChoice::Never));
+        assert_eq!(settings.threads, NonZeroUsize::new(4));
+        assert_eq!(settings.presets[&15], Preset::Real);
+        assert_eq!(
+            settings.params_for(16),
+            [
+                ("plan".to_string(), "true".to_string()),
+                ("start".to_string(), "AA".to_string())
+            ]
+        );
+        assert!(settings.params_for(15).is_empty());
+
+        let written = settings.to_string();
+        assert!(written.contains("# session_file isn't set"), "{written}");
+        assert_eq!(Settings::parse(&written).unwrap(), settings);
+        assert_eq!(Settings::parse("").unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        let err = |text| Settings::parse(text).unwrap_err();
+        assert!(err("colour = \"never\"").contains("unknown field `colour`"));
+        assert!(err("color = \"sometimes\"").contains("expected `auto`, `always` or `never`"));
+        assert!(err("threads = 0").contains("nonzero"));
+        assert_eq!(
+            err("[params.26]\nrow = 10"),
+            "`26` in [params] isn't a challenge from 1 to 25"
+        );
+        assert_eq!(
+            err("[presets]\n15 = \"full\""),
+            "preset for `15`: expected `sample` or `real`"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! A common interface for step-by-step simulations.
+//!
+//! Lets the TUI framework, headless runs, and anything else that wants to drive a day's
+//! simulation do it the same way, no matter which day it comes from.
+
+use std::{num::NonZeroUsize, time::Duration};
+
+use crate::{
+    error::SolverError,
+    solver::{EventStream, ParamError, SolverParams},
+};
+
+/// What happened during a single [`Simulation::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StepResult {
+    /// The simulation can keep going.
+    Continue,
+    /// The simulation has finished, and further steps won't change anything.
+    Done,
+}
+
+/// A simulation that can be advanced one step at a time.
+pub(crate) trait Simulation {
+    /// Advance the simulation by a single step.
+    fn step(&mut self) -> StepResult;
+
+    /// Put the simulation back into its initial state.
+    fn reset(&mut self);
+
+    /// Interesting numbers describing the current state of the simulation.
+    fn metrics(&self) -> serde_json::Value;
+
+    /// Step the simulation until it's done, or until `max_steps` steps have been taken.
+    ///
+    /// Returns the number of steps taken.
+    fn run(&mut self, max_steps: Option<usize>) -> usize {
+        let mut steps = 0;
+        while max_steps.is_none_or(|max| steps < max) {
+            steps += 1;
+            if self.step() == StepResult::Done {
+                break;
+            }
+        }
+        steps
+    }
+
+    /// Like [`Simulation::run`], but send the starting state and the state after every step to
+    /// `events`.
+    fn run_streaming(
+        &mut self,
+        max_steps: Option<usize>,
+        events: &EventStream,
+    ) -> Result<usize, SolverError> {
+        events.emit(0, StepResult::Continue, || self.metrics())?;
+        let mut steps = 0;
+        while max_steps.is_none_or(|max| steps < max) {
+            steps += 1;
+            let result = self.step();
+            events.emit(steps as u64, result, || self.metrics())?;
+            if result == StepResult::Done {
+                break;
+            }
+        }
+        Ok(steps)
+    }
+}
+
+/// How fast an interactive frontend steps through a simulation, from the `tick_hz`,
+/// `steps_per_tick` and `turbo_steps` parameters, and whether it exits by itself once the
+/// simulation's done, from the `auto_exit` and `linger` parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Pacing {
+    /// How long to wait between ticks, or `None` to leave it up to the frontend.
+    pub(crate) tick: Option<Duration>,
+    /// How many steps to take on every tick.
+    pub(crate) steps_per_tick: NonZeroUsize,
+    /// How many steps to take between frames in turbo mode, which steps as fast as it can.
+    pub(crate) turbo_steps: NonZeroUsize,
+    /// How long to keep showing a finished simulation before exiting by itself, or `None` to wait
+    /// for the user to quit.
+    pub(crate) auto_exit: Option<Duration>,
+}
+
+/// How many seconds a finished simulation stays on screen with `auto_exit`, unless `linger` says
+/// otherwise.
+const DEFAULT_LINGER_SECS: f64 = 3.0;
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Self {
+            tick: None,
+            steps_per_tick: NonZeroUsize::MIN,
+            turbo_steps: NonZeroUsize::new(1000).unwrap(),
+            auto_exit: None,
+        }
+    }
+}
+
+impl Pacing {
+    pub(crate) fn from_params(params: &SolverParams) -> Result<Self, ParamError> {
+        let default = Self::default();
+        // Rates so slow that a tick wouldn't fit in a `Duration` are turned away as well.
+        let tick = params
+            .get::<f64>("tick_hz")?
+            .map(|hz| {
+                Some(hz)
+                    .filter(|hz| hz.is_finite() && *hz > 0.0)
+                    .and_then(|hz| Duration::try_from_secs_f64(1.0 / hz).ok())
+                    .ok_or_else(|| ParamError::InvalidValue {
+                        key: "tick_hz".to_string(),
+                        value: hz.to_string(),
+                        reason: "expected a positive number of ticks per second".to_string(),
+                    })
+            })
+            .transpose()?;
+
+        let linger = params.get_or("linger", DEFAULT_LINGER_SECS)?;
+        let linger = Duration::try_from_secs_f64(linger).map_err(|e| ParamError::InvalidValue {
+            key: "linger".to_string(),
+            value: linger.to_string(),
+            reason: format!("expected a number of seconds that isn't negative or huge ({e})"),
+        })?;
+
+        Ok(Self {
+            tick,
+            steps_per_tick: params.get_or("steps_per_tick", default.steps_per_tick)?,
+            turbo_steps: params.get_or("turbo_steps", default.turbo_steps)?,
+            auto_exit: params.get_or("auto_exit", false)?.then_some(linger),
+        })
+    }
+
+    /// How long to wait between ticks, if the frontend would tick `default_hz` times a second.
+    pub(crate) fn tick_rate(&self, default_hz: f64) -> Duration {
+        self.tick
+            .unwrap_or_else(|| Duration::from_secs_f64(1.0 / default_hz))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_pacing_params() {
+        let mut params = SolverParams::default();
+        assert_eq!(Pacing::from_params(&params).unwrap(), Pacing::default());
+        assert_eq!(Pacing::default().tick_rate(4.0), Duration::from_millis(250));
+
+        params.insert("tick_hz", "200");
+        params.insert("steps_per_tick", "50");
+        let pacing = Pacing::from_params(&params).unwrap();
+        assert_eq!(pacing.tick_rate(60.0), Duration::from_millis(5));
+        assert_eq!(pacing.steps_per_tick.get(), 50);
+        assert_eq!(pacing.auto_exit, None);
+
+        params.insert("auto_exit", "true");
+        params.insert("linger", "0.5");
+        let pacing = Pacing::from_params(&params).unwrap();
+        assert_eq!(pacing.auto_exit, Some(Duration::from_millis(500)));
+        params.insert("linger", "-1");
+        assert!(Pacing::from_params(&params).is_err());
+        params.insert("linger", "0");
+
+        params.insert("steps_per_tick", "0");
+        assert!(Pacing::from_params(&params).is_err());
+        params.insert("steps_per_tick", "1");
+        params.insert("tick_hz", "0");
+        assert!(Pacing::from_params(&params).is_err());
+    }
+
+    #[test]
+    fn refuses_durations_too_long_to_keep() {
+        let mut params = SolverParams::default();
+        params.insert("tick_hz", "1e-320");
+        assert!(matches!(
+            Pacing::from_params(&params),
+            Err(ParamError::InvalidValue { key, .. }) if key == "tick_hz"
+        ));
+
+        let mut params = SolverParams::default();
+        params.insert("auto_exit", "true");
+        params.insert("linger", "1e300");
+        assert!(matches!(
+            Pacing::from_params(&params),
+            Err(ParamError::InvalidValue { key, .. }) if key == "linger"
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+//! Helpers for snapshot-testing what the solvers draw in the terminal, with [`insta`].
+//!
+//! Everything is compared as plain text. Colors would make every snapshot unreadable, and change
+//! with every tweak to a palette, while what matters is that the pictures keep their shape. Run
+//! `cargo insta review` (or the tests with `INSTA_UPDATE=always`) to accept a picture that was
+//! changed on purpose.
+
+/// Draw with the Unicode glyphs from now on, whatever the terminal running the tests can show, so
+/// that the snapshots don't depend on it.
+pub(crate) fn unicode_glyphs() {
+    crate::glyphs::set(crate::glyphs::GlyphChoice::Unicode);
+}
+
+/// Remove ANSI escape sequences, like colors, from `s`.
+pub(crate) fn strip_ansi(s: &str) -> String {
+    let mut plain = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+
+        // Control sequences, which is what colors are, end with a character from `@` to `~`.
+        // Every other escape is only one character long.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    plain
+}
+
+/// Draw `app` on a `width` by `height` terminal, and return what's on the screen afterwards, with
+/// the spaces at the end of each row trimmed off.
+#[cfg(feature = "tui")]
+pub(crate) fn render_tui(app: &impl crate::tui_app::TuiApp, width: u16, height: u16) -> String {
+    use tui::{backend::TestBackend, Terminal};
+
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| app.ui(f)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let mut screen = String::new();
+    for y in 0..height {
+        let row = (0..width)
+            .map(|x| buffer.get(x, y).symbol.as_str())
+            .collect::<String>();
+        screen.push_str(row.trim_end());
+        screen.push('\n');
+    }
+    screen
+}
+
+mod tests {
+    use owo_colors::OwoColorize;
+
+    use super::*;
+
+    #[test]
+    fn strips_colors() {
+        let colored = format!("{} and {}", "red".red(), "bold".bold().on_blue());
+        assert_ne!(colored, "red and bold");
+        assert_eq!(strip_ansi(&colored), "red and bold");
+        assert_eq!(strip_ansi("\x1b7saved\x1b8"), "saved");
+    }
+}
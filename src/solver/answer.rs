@@ -0,0 +1,282 @@
+use std::{any::Any, fmt};
+
+use serde_json::{json, Map, Value};
+
+/// How the value inside of an [`Answer`] is shown, in text, as JSON, and in tables.
+///
+/// Most answers are numbers, which look the same everywhere. Others are pictures drawn across
+/// several lines, like the letters on day 10's CRT, which have to be kept whole when printed
+/// but cut down to a single line in a table.
+pub trait AnswerDisplay {
+    /// Write out the whole answer, which may take up several lines.
+    fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Write out the answer on a single line, for tables.
+    ///
+    /// By default, this is the first line of the full answer, followed by `…` if there was more.
+    fn fmt_short(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let full = Full(self).to_string();
+        let mut lines = full.trim().lines();
+        f.write_str(lines.next().unwrap_or_default().trim_end())?;
+        if lines.next().is_some() {
+            f.write_str(" …")?;
+        }
+        Ok(())
+    }
+
+    /// The answer as JSON. By default, this is a string holding the whole answer.
+    fn to_json(&self) -> Value {
+        Value::String(Full(self).to_string())
+    }
+
+    /// Whether there's no answer at all, because the solver printed it out itself instead.
+    fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Shows an answer in full with [`fmt::Display`].
+struct Full<'a, T: ?Sized>(&'a T);
+
+impl<T: AnswerDisplay + ?Sized> fmt::Display for Full<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_answer(f)
+    }
+}
+
+/// Shows an answer on a single line with [`fmt::Display`].
+struct Short<'a, T: ?Sized>(&'a T);
+
+impl<T: AnswerDisplay + ?Sized> fmt::Display for Short<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_short(f)
+    }
+}
+
+macro_rules! impl_answer_display_for_numbers {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl AnswerDisplay for $ty {
+                fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt::Display::fmt(self, f)
+                }
+
+                fn to_json(&self) -> Value {
+                    json!(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_answer_display_for_numbers!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl AnswerDisplay for String {
+    fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl AnswerDisplay for &'static str {
+    fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+/// For solvers that print their answer out themselves.
+impl AnswerDisplay for () {
+    fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(none)")
+    }
+
+    fn to_json(&self) -> Value {
+        Value::Null
+    }
+
+    fn is_empty(&self) -> bool {
+        true
+    }
+}
+
+/// The result of solving a subchallenge.
+///
+/// Holds the answer itself, along with any extra information that a solver wants to report
+/// about how it got there (for example, the moves it made).
+///
+/// Two answers are equal if their values are of the same type and equal, no matter what their
+/// unit or metadata is.
+pub struct Answer {
+    value: Box<dyn Any + Send>,
+    /// What the answer is measured in, if that's not obvious from the puzzle.
+    unit: Option<&'static str>,
+    metadata: Map<String, Value>,
+    /// Compares `value` to another value, which might not be of the same type.
+    eq: fn(&dyn Any, &dyn Any) -> bool,
+    /// Gets `value`'s [`AnswerDisplay`] implementation back.
+    display: fn(&dyn Any) -> &dyn AnswerDisplay,
+}
+
+impl Answer {
+    /// Wrap up an answer with no unit or metadata.
+    pub fn new<T>(value: T) -> Self
+    where
+        T: Any + Send + PartialEq + AnswerDisplay,
+    {
+        Self {
+            value: Box::new(value),
+            unit: None,
+            metadata: Map::new(),
+            eq: |a, b| a.downcast_ref::<T>() == b.downcast_ref::<T>(),
+            display: |value| value.downcast_ref::<T>().unwrap(),
+        }
+    }
+
+    /// Say what the answer is measured in, like `"units tall"`.
+    pub fn with_unit(mut self, unit: &'static str) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Attach a piece of metadata to the answer, replacing any previous value for `key`.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Note that `skipped` lines of the input were left out for not parsing, with
+    /// `--skip-bad-lines`, so that the answer is only provisional. Nothing is noted if none were.
+    pub fn with_skipped_lines(self, skipped: usize) -> Self {
+        if skipped == 0 {
+            return self;
+        }
+        self.with_metadata("skipped_lines", skipped)
+    }
+
+    /// Get the answer, if it is of type `T`.
+    #[cfg_attr(not(test), allow(dead_code))] // only the tests need the raw answer so far
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+
+    pub fn unit(&self) -> Option<&'static str> {
+        self.unit
+    }
+
+    /// Everything that the solver reported alongside the answer.
+    pub fn metadata(&self) -> &Map<String, Value> {
+        &self.metadata
+    }
+
+    /// Whether the solver printed its answer out itself, instead of returning it.
+    pub fn is_empty(&self) -> bool {
+        self.value().is_empty()
+    }
+
+    /// The answer on a single line, with its unit, for tables.
+    ///
+    /// Use [`fmt::Display`] to get the whole answer instead.
+    pub fn short(&self) -> impl fmt::Display + '_ {
+        WithUnit(Short(self.value()), self.unit)
+    }
+
+    /// The whole answer on a single line, without its unit, for scripts to read.
+    ///
+    /// The rows of pictures are separated by `\n`, like in a JSON string.
+    pub fn plain(&self) -> String {
+        Full(self.value())
+            .to_string()
+            .trim_end()
+            .replace('\n', "\\n")
+    }
+
+    /// The answer, its unit, and its metadata, as a JSON object.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "answer": self.value().to_json(),
+            "unit": self.unit,
+            "metadata": self.metadata,
+        })
+    }
+
+    fn value(&self) -> &dyn AnswerDisplay {
+        (self.display)(&*self.value)
+    }
+}
+
+/// Puts a unit after an answer, if it has one.
+struct WithUnit<T>(T, Option<&'static str>);
+
+impl<T: fmt::Display> fmt::Display for WithUnit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)?;
+        if let Some(unit) = self.1 {
+            write!(f, " {unit}")?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        (self.eq)(&*self.value, &*other.value)
+    }
+}
+
+/// The whole answer, with its unit.
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        WithUnit(Full(self.value()), self.unit).fmt(f)
+    }
+}
+
+impl fmt::Debug for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Answer")
+            .field("value", &format_args!("{}", self.short()))
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Art;
+
+    impl AnswerDisplay for Art {
+        fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("#..#\n####\n#..#\n")
+        }
+    }
+
+    #[test]
+    fn multi_line_answers() {
+        let answer = Answer::new(Art);
+
+        assert_eq!(answer.to_string(), "#..#\n####\n#..#\n");
+        assert_eq!(answer.short().to_string(), "#..# …");
+        assert_eq!(answer.plain(), r"#..#\n####\n#..#");
+        assert_eq!(answer.to_json()["answer"], "#..#\n####\n#..#\n");
+        assert_eq!(
+            serde_json::to_string(&answer.to_json()["answer"]).unwrap(),
+            r##""#..#\n####\n#..#\n""##
+        );
+    }
+
+    #[test]
+    fn numbers_with_units() {
+        let answer = Answer::new(3068_u64).with_unit("units tall");
+
+        assert_eq!(answer.to_string(), "3068 units tall");
+        assert_eq!(answer.short().to_string(), "3068 units tall");
+        assert_eq!(answer.plain(), "3068");
+        assert_eq!(
+            answer.to_json(),
+            json!({ "answer": 3068, "unit": "units tall", "metadata": {} })
+        );
+        assert_eq!(answer, Answer::new(3068_u64));
+        assert!(!answer.is_empty() && Answer::new(()).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+//! Bundles of everything needed to look into a failed run, for `--artifacts-dir`.
+//!
+//! When a solver returns an error or panics, [`ArtifactDump::write`] saves a directory like
+//! `artifacts/17b-1671235200000/` with the input, what went wrong, how the solver was configured,
+//! and whatever progress it had reported, so that the failure can be looked into (or attached to a
+//! bug report) after the terminal has scrolled past it.
+
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use blake2::{Blake2s256, Digest};
+use serde_json::json;
+
+use crate::{
+    challenge::{ChallengeNumber, Subchallenge},
+    error::{Context, SolverError},
+};
+
+use super::SolverConfig;
+
+/// Where to save a bundle for each failed run, and whether to leave the input out of it.
+#[derive(Debug, Clone)]
+pub struct ArtifactDump {
+    dir: PathBuf,
+    /// Save a hash of the input instead of the input itself, since puzzle inputs shouldn't be
+    /// shared.
+    private: bool,
+}
+
+/// What went wrong with a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// The error the solver returned, with each of its causes on its own line.
+    Error(String),
+    /// What the solver panicked with, and the backtrace of the panic if it was caught by
+    /// [`capture_panics`].
+    Panic(String),
+}
+
+/// The latest panic's message and backtrace, as seen by the hook from [`capture_panics`].
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keep the message and backtrace of every panic from now on, for [`Failure::panic`]. The panic
+/// hook that was already set still gets called afterwards.
+pub fn capture_panics() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let panic = format!("{info}\n\nstack backtrace:\n{}", Backtrace::force_capture());
+        *LAST_PANIC
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(panic);
+        previous(info);
+    }));
+}
+
+impl Failure {
+    /// The failure for a panic that was caught with `payload`.
+    pub fn panic(payload: &(dyn Any + Send)) -> Self {
+        let captured = LAST_PANIC
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("(no message)");
+        Self::Panic(captured.unwrap_or_else(|| format!("panicked with: {message}")))
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Self::Error(_) => "error.txt",
+            Self::Panic(_) => "panic.txt",
+        }
+    }
+}
+
+impl ArtifactDump {
+    pub fn new(dir: impl Into<PathBuf>, private: bool) -> Self {
+        Self {
+            dir: dir.into(),
+            private,
+        }
+    }
+
+    /// Save a bundle for a failed run of a subchallenge on `input`, in a new directory named
+    /// after the subchallenge and the time, like `17b-1671235200000`. Returns the directory.
+    pub fn write(
+        &self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        input: &str,
+        config: &SolverConfig,
+        failure: &Failure,
+    ) -> Result<PathBuf, SolverError> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        let bundle = self
+            .dir
+            .join(format!("{challenge:02}{subchallenge}-{millis}"));
+        fs::create_dir_all(&bundle)
+            .with_context(|| format!("Could not create {}", bundle.display()))?;
+
+        if self.private {
+            let hash = Blake2s256::digest(input.as_bytes())
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>();
+            save(&bundle, "input.blake2s", format!("{hash}\n"))?;
+        } else {
+            save(&bundle, "input.txt", input)?;
+        }
+
+        let (Failure::Error(text) | Failure::Panic(text)) = failure;
+        save(&bundle, failure.file_name(), format!("{text}\n"))?;
+
+        let params = config
+            .params
+            .iter()
+            .map(|(key, value)| (key.to_string(), json!(value)))
+            .collect::<serde_json::Map<_, _>>();
+        let summary = json!({
+            "challenge": challenge,
+            "subchallenge": subchallenge.as_str(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "visualize": config.visualize,
+            "timeout_secs": config.timeout.map(|timeout| timeout.as_secs_f64()),
+            "math": config.math.to_string(),
+            "check_consistency": config.check_consistency,
+            "skip_bad_lines": config.skip_bad_lines,
+            "color": config.color.to_string(),
+            "params": params,
+            "transforms": config.transforms.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "post": config.post.as_ref().map(ToString::to_string),
+        });
+        save(&bundle, "config.json", format!("{summary:#}\n"))?;
+
+        // Solvers with long searches report how far they've got, which is the closest thing to
+        // a snapshot of where they were when things went wrong.
+        let progress = config.cancel.progress();
+        if !progress.is_empty() {
+            save(&bundle, "progress.json", format!("{:#}\n", json!(progress)))?;
+        }
+
+        Ok(bundle)
+    }
+}
+
+fn save(bundle: &Path, name: &str, contents: impl AsRef<[u8]>) -> Result<(), SolverError> {
+    let path = bundle.join(name);
+    fs::write(&path, contents).with_context(|| format!("Could not save {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_the_input_or_just_its_hash() -> Result<(), SolverError> {
+        let dir = std::env::temp_dir().join(format!("aoc2022-artifacts-{}", std::process::id()));
+        let mut config = SolverConfig::default();
+        config.params.insert("rows", "10");
+        config.cancel.report("rocks", 1234);
+        let failure = Failure::Error("Could not solve\nBad input".to_string());
+
+        let bundle =
+            ArtifactDump::new(&dir, false).write(17, Subchallenge::B, "<<>>", &config, &failure)?;
+        let name = bundle.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("17b-"), "{name}");
+        let read = |name: &str| fs::read_to_string(bundle.join(name)).unwrap();
+        assert_eq!(read("input.txt"), "<<>>");
+        assert_eq!(read("error.txt"), "Could not solve\nBad input\n");
+        let summary: serde_json::Value = serde_json::from_str(&read("config.json")).unwrap();
+        assert_eq!(summary["params"], json!({ "rows": "10" }));
+        assert_eq!(summary["subchallenge"], "b");
+        assert!(read("progress.json").contains("1234"));
+
+        let failure = Failure::panic(&"oh no");
+        assert_eq!(failure, Failure::Panic("panicked with: oh no".to_string()));
+        let bundle = ArtifactDump::new(&dir, true).write(
+            1,
+            Subchallenge::A,
+            "",
+            &SolverConfig::default(),
+            &failure,
+        )?;
+        let mut files = fs::read_dir(&bundle)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort_unstable();
+        assert_eq!(files, ["config.json", "input.blake2s", "panic.txt"]);
+        // BLAKE2s's hash of an empty input.
+        assert_eq!(
+            fs::read_to_string(bundle.join("input.blake2s")).unwrap(),
+            "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,368 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::Path, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    challenge::{ChallengeNumber, Subchallenge},
+    error::{Context, SolverError},
+};
+
+use super::SolveError;
+
+/// Where baselines are saved, relative to the working directory.
+pub const BASELINE_FILE: &str = "bench_baselines.toml";
+
+/// How many times each solver is run, unless told otherwise. The median of the runs is what gets
+/// reported.
+pub const DEFAULT_RUNS: usize = 5;
+
+/// How much slower than its baseline a solver can get, in percent, unless told otherwise.
+pub const DEFAULT_MAX_REGRESSION: f64 = 20.0;
+
+/// Slowdowns smaller than this are never counted as regressions, no matter the percentage.
+///
+/// Solving a sample only takes a few microseconds, so the usual jitter between runs would
+/// otherwise look like a huge regression.
+const NOISE_FLOOR: Duration = Duration::from_millis(1);
+
+/// What to do with the baselines in [`BASELINE_FILE`], for `aoc2022 bench --baseline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineMode {
+    /// Replace the baselines with this run's timings.
+    Save,
+    /// Compare this run's timings against the baselines, failing if any solver got too slow.
+    Check,
+}
+
+impl FromStr for BaselineMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "save" => Ok(Self::Save),
+            "check" => Ok(Self::Check),
+            _ => Err(format!("expected `save` or `check`, not `{s}`")),
+        }
+    }
+}
+
+/// Which input a solver was timed on.
+///
+/// The full puzzle input is used if it's in `./input/`, and the sample from the puzzle
+/// description otherwise. Timings on different inputs can't be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputKind {
+    Sample,
+    Real,
+}
+
+impl fmt::Display for InputKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Sample => "sample",
+            Self::Real => "real",
+        })
+    }
+}
+
+/// How long every solver took.
+#[derive(Debug)]
+pub struct BenchReport {
+    pub results: Vec<BenchResult>,
+}
+
+/// How long one subchallenge took to solve, as the median of several runs.
+#[derive(Debug)]
+pub struct BenchResult {
+    pub challenge: ChallengeNumber,
+    pub subchallenge: Subchallenge,
+    pub input: InputKind,
+    pub median: Result<Duration, SolveError>,
+}
+
+impl BenchResult {
+    /// The key for this subchallenge in [`BASELINE_FILE`], like `16b`.
+    fn key(&self) -> String {
+        format!("{:02}{}", self.challenge, self.subchallenge)
+    }
+}
+
+/// The median of `times`, which must not be empty.
+pub(super) fn median(mut times: Vec<Duration>) -> Duration {
+    times.sort_unstable();
+    let mid = times.len() / 2;
+    if times.len().is_multiple_of(2) {
+        (times[mid - 1] + times[mid]) / 2
+    } else {
+        times[mid]
+    }
+}
+
+impl BenchReport {
+    /// Whether any solver couldn't finish.
+    pub fn is_err(&self) -> bool {
+        self.results.iter().any(|r| r.median.is_err())
+    }
+
+    /// This run's timings, to save as the new baselines. Solvers that couldn't finish are left
+    /// out.
+    pub fn baselines(&self) -> Baselines {
+        Baselines(
+            self.results
+                .iter()
+                .filter_map(|result| {
+                    let median = result.median.as_ref().ok()?;
+                    Some((
+                        result.key(),
+                        Baseline {
+                            input: result.input,
+                            median_ms: median.as_secs_f64() * 1000.0,
+                        },
+                    ))
+                })
+                .collect(),
+        )
+    }
+
+    /// Compare this run against `baselines`, allowing each solver to get up to `max_regression`
+    /// percent slower.
+    pub fn check(&self, baselines: &Baselines, max_regression: f64) -> BaselineReport<'_> {
+        let rows = self
+            .results
+            .iter()
+            .map(|result| {
+                let verdict = match (&result.median, baselines.0.get(&result.key())) {
+                    (Err(_), _) => Verdict::Error,
+                    (Ok(_), None) => Verdict::New,
+                    (Ok(_), Some(baseline)) if baseline.input != result.input => {
+                        Verdict::DifferentInput(baseline.input)
+                    }
+                    (Ok(median), Some(baseline)) => {
+                        let before = Duration::from_secs_f64(baseline.median_ms / 1000.0);
+                        let change = (median.as_secs_f64() / before.as_secs_f64() - 1.0) * 100.0;
+                        let slowdown = median.saturating_sub(before);
+                        Verdict::Compared {
+                            before,
+                            change,
+                            regressed: change > max_regression && slowdown >= NOISE_FLOOR,
+                        }
+                    }
+                };
+                (result, verdict)
+            })
+            .collect();
+
+        BaselineReport {
+            rows,
+            max_regression,
+        }
+    }
+}
+
+pub(super) fn format_ms(duration: Duration) -> String {
+    format!("{:.3} ms", duration.as_secs_f64() * 1000.0)
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<11}{:<8}median", "challenge", "input")?;
+        for result in &self.results {
+            let median = match &result.median {
+                Ok(median) => format_ms(*median),
+                Err(e) => format!("ERROR: {e}"),
+            };
+            writeln!(f, "{:<11}{:<8}{median}", result.key(), result.input)?;
+        }
+        Ok(())
+    }
+}
+
+/// The median runtime of every subchallenge, as saved in [`BASELINE_FILE`].
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Baselines(BTreeMap<String, Baseline>);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    input: InputKind,
+    median_ms: f64,
+}
+
+impl Baselines {
+    pub fn load(path: &Path) -> Result<Self, SolverError> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Could not read baselines from {}", path.display()))?;
+        toml::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            .with_context(|| format!("Could not parse baselines in {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SolverError> {
+        let text = toml::to_string(self)
+            .map_err(io::Error::other)
+            .with_context(|| format!("Could not serialize baselines for {}", path.display()))?;
+        fs::write(path, text)
+            .with_context(|| format!("Could not save baselines to {}", path.display()))
+    }
+}
+
+/// How this run compared to the saved baselines.
+#[derive(Debug)]
+pub struct BaselineReport<'r> {
+    rows: Vec<(&'r BenchResult, Verdict)>,
+    max_regression: f64,
+}
+
+#[derive(Debug)]
+enum Verdict {
+    /// Timed against the baseline, and `change` percent slower (or faster, if negative).
+    Compared {
+        before: Duration,
+        change: f64,
+        regressed: bool,
+    },
+    /// There's no baseline for this subchallenge yet.
+    New,
+    /// The baseline was timed on a different input, so there's nothing to compare.
+    DifferentInput(InputKind),
+    /// The solver couldn't finish.
+    Error,
+}
+
+impl BaselineReport<'_> {
+    /// Whether any solver got too slow, or couldn't finish.
+    pub fn is_err(&self) -> bool {
+        self.rows.iter().any(|(_, verdict)| {
+            matches!(
+                verdict,
+                Verdict::Compared {
+                    regressed: true,
+                    ..
+                } | Verdict::Error
+            )
+        })
+    }
+}
+
+impl fmt::Display for BaselineReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<11}{:<8}{:<14}{:<14}change",
+            "challenge", "input", "baseline", "median"
+        )?;
+        for (result, verdict) in &self.rows {
+            let median = match &result.median {
+                Ok(median) => format_ms(*median),
+                Err(_) => "-".to_string(),
+            };
+            let (baseline, change) = match verdict {
+                Verdict::Compared {
+                    before,
+                    change,
+                    regressed,
+                } => (
+                    format_ms(*before),
+                    format!(
+                        "{change:+.1}%{}",
+                        if *regressed { "  REGRESSED" } else { "" }
+                    ),
+                ),
+                Verdict::New => ("-".to_string(), "new".to_string()),
+                Verdict::DifferentInput(input) => (
+                    "-".to_string(),
+                    format!("skipped, baseline is for the {input} input"),
+                ),
+                Verdict::Error => (
+                    "-".to_string(),
+                    format!("ERROR: {}", result.median.as_ref().unwrap_err()),
+                ),
+            };
+            writeln!(
+                f,
+                "{:<11}{:<8}{baseline:<14}{median:<14}{change}",
+                result.key(),
+                result.input
+            )?;
+        }
+
+        let regressed = self
+            .rows
+            .iter()
+            .filter(|(_, verdict)| {
+                matches!(
+                    verdict,
+                    Verdict::Compared {
+                        regressed: true,
+                        ..
+                    }
+                )
+            })
+            .count();
+        writeln!(
+            f,
+            "\n{regressed} of {} subchallenges regressed by more than {}%",
+            self.rows.len(),
+            self.max_regression
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(challenge: ChallengeNumber, input: InputKind, ms: u64) -> BenchResult {
+        BenchResult {
+            challenge,
+            subchallenge: Subchallenge::B,
+            input,
+            median: Ok(Duration::from_millis(ms)),
+        }
+    }
+
+    #[test]
+    fn flags_regressions() {
+        let before = BenchReport {
+            results: vec![
+                result(16, InputKind::Real, 100),
+                result(18, InputKind::Real, 100),
+                result(19, InputKind::Sample, 100),
+            ],
+        };
+        let baselines = before.baselines();
+        let saved = toml::to_string(&baselines).unwrap();
+        assert!(saved.contains("[16b]\ninput = \"real\"\nmedian_ms = 100.0\n"));
+        assert_eq!(toml::from_str::<Baselines>(&saved).unwrap(), baselines);
+
+        let after = BenchReport {
+            results: vec![
+                result(16, InputKind::Real, 150),
+                result(18, InputKind::Real, 110),
+                result(19, InputKind::Real, 900),
+                result(20, InputKind::Real, 5),
+            ],
+        };
+        let report = after.check(&baselines, 20.0);
+        let text = report.to_string();
+
+        assert!(report.is_err());
+        assert!(text.contains("16b        real    100.000 ms    150.000 ms    +50.0%  REGRESSED"));
+        assert!(text.contains("18b        real    100.000 ms    110.000 ms    +10.0%\n"));
+        assert!(text.contains("skipped, baseline is for the sample input"));
+        assert!(text.contains("20b        real    -             5.000 ms      new"));
+        assert!(text.contains("1 of 4 subchallenges regressed by more than 20%"));
+        assert!(!after.check(&baselines, 60.0).is_err());
+    }
+
+    #[test]
+    fn median_of_runs() {
+        let ms = Duration::from_millis;
+        assert_eq!(median(vec![ms(3), ms(1), ms(2)]), ms(2));
+        assert_eq!(
+            median(vec![ms(4), ms(1), ms(2), ms(3)]),
+            ms(2) + Duration::from_micros(500)
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    challenge::{ChallengeNumber, Subchallenge},
+    clock::SharedClock,
+    error::{Context, SolverError},
+};
+
+/// Where checkpoints are saved, relative to the working directory.
+pub const CHECKPOINT_DIR: &str = ".checkpoint";
+
+/// Bumped whenever a solver changes what it saves, so that old checkpoints are turned away
+/// instead of being misread.
+const FORMAT_VERSION: u32 = 2;
+
+/// Lets a long-running search save its progress every so often, so that a run that gets
+/// interrupted can pick up where it left off with `--resume` instead of starting over.
+///
+/// The default checkpointer is turned off, and never saves or loads anything. Solvers hold on to
+/// a clone from [`SolverConfig::checkpoint`](super::SolverConfig::checkpoint), call
+/// [`Checkpointer::load`] before searching, [`Checkpointer::tick`] as they go, and
+/// [`Checkpointer::clear`] once they're done.
+///
+/// Every checkpoint is saved along with a fingerprint of whatever the search depends on (usually
+/// the input and any parameters), so that a checkpoint is never resumed against a different
+/// puzzle.
+#[derive(Debug, Clone, Default)]
+pub struct Checkpointer {
+    /// Where to save checkpoints, or `None` if checkpoints are turned off.
+    path: Option<PathBuf>,
+    /// Whether to load the saved checkpoint before searching.
+    resume: bool,
+    /// How long to wait between saves.
+    interval: Duration,
+    last_saved: Option<Instant>,
+    /// Calls to [`Checkpointer::tick`] since the clock was last checked.
+    ticks: u32,
+    clock: SharedClock,
+}
+
+impl Checkpointer {
+    /// How long to wait between saves, unless told otherwise.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// How many calls to [`Checkpointer::tick`] to let through between looking at the clock,
+    /// which would otherwise take longer than a step of the search.
+    const TICKS_PER_CLOCK_CHECK: u32 = 4096;
+
+    /// Save checkpoints to `path`, loading it first if `resume` is set.
+    pub fn new(path: impl Into<PathBuf>, resume: bool) -> Self {
+        Self {
+            path: Some(path.into()),
+            resume,
+            interval: Self::DEFAULT_INTERVAL,
+            last_saved: None,
+            ticks: 0,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Save checkpoints for a subchallenge to its file in [`CHECKPOINT_DIR`], like
+    /// `.checkpoint/19b.bin`.
+    pub fn for_challenge(
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        resume: bool,
+    ) -> Self {
+        Self::new(
+            Path::new(CHECKPOINT_DIR).join(format!("{challenge:02}{subchallenge}.bin")),
+            resume,
+        )
+    }
+
+    /// Save every `interval` instead of every [`Checkpointer::DEFAULT_INTERVAL`].
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+
+    /// Tell when a save is due with `clock` instead of the real time.
+    pub fn with_clock(self, clock: SharedClock) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// The file that checkpoints are saved to, if they're turned on.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The saved progress for a search with the given `fingerprint`, if resuming was asked for
+    /// and there's a checkpoint to resume.
+    ///
+    /// Fails if the checkpoint was saved for a different fingerprint, or by a different version
+    /// of the solver.
+    pub(crate) fn load<T: DeserializeOwned>(
+        &self,
+        fingerprint: u64,
+    ) -> Result<Option<T>, SolverError> {
+        let Some(path) = self.path.as_deref().filter(|_| self.resume) else {
+            return Ok(None);
+        };
+        let context = || format!("Could not read checkpoint from {}", path.display());
+
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                eprintln!(
+                    "No checkpoint found at {}, starting from scratch",
+                    path.display()
+                );
+                return Ok(None);
+            }
+            Err(e) => return Err(e).with_context(context),
+        };
+
+        let (version, saved_fingerprint, progress): (u32, u64, T) =
+            bincode::deserialize_from(io::BufReader::new(file))
+                .map_err(invalid_data)
+                .with_context(context)?;
+
+        if version != FORMAT_VERSION {
+            return Err(SolverError::invalid_input(format!(
+                "The checkpoint at {} was saved by a different version of the solver",
+                path.display()
+            )));
+        }
+        if saved_fingerprint != fingerprint {
+            return Err(SolverError::invalid_input(format!(
+                "The checkpoint at {} was saved for a different input or different parameters",
+                path.display()
+            )));
+        }
+
+        eprintln!("Resuming from checkpoint at {}", path.display());
+        Ok(Some(progress))
+    }
+
+    /// Save `progress` if it's been long enough since the last save.
+    ///
+    /// Cheap enough to call on every step of a search. `progress` is only called if a save is
+    /// due.
+    pub(crate) fn tick<T: Serialize>(
+        &mut self,
+        fingerprint: u64,
+        progress: impl FnOnce() -> T,
+    ) -> Result<(), SolverError> {
+        if self.path.is_none() {
+            return Ok(());
+        }
+
+        self.ticks += 1;
+        if self.ticks < Self::TICKS_PER_CLOCK_CHECK {
+            return Ok(());
+        }
+        self.ticks = 0;
+
+        let last_saved = *self.last_saved.get_or_insert_with(|| self.clock.now());
+        if self.clock.since(last_saved) >= self.interval {
+            self.save(fingerprint, &progress())?;
+        }
+        Ok(())
+    }
+
+    /// Save `progress` right away, like when a search is cancelled.
+    pub(crate) fn save<T: Serialize>(
+        &mut self,
+        fingerprint: u64,
+        progress: &T,
+    ) -> Result<(), SolverError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let context = || format!("Could not save checkpoint to {}", path.display());
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(context)?;
+        }
+
+        // Getting interrupted partway through saving mustn't ruin the previous checkpoint.
+        write_atomically(path, &(FORMAT_VERSION, fingerprint, progress)).with_context(context)?;
+
+        self.last_saved = Some(self.clock.now());
+        Ok(())
+    }
+
+    /// Delete the checkpoint, once the search it's for has finished.
+    pub(crate) fn clear(&self) -> Result<(), SolverError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Could not remove checkpoint at {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A fingerprint of everything a search depends on, for [`Checkpointer::load`].
+///
+/// This uses the standard library's hasher, which may change between Rust versions. That only
+/// means an old checkpoint might be turned away after upgrading.
+pub(crate) fn fingerprint(key: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Save `value` to `path` with bincode, writing it to a temporary file first and then moving that
+/// into place, so that `path` never holds half of it.
+pub(crate) fn write_atomically(path: &Path, value: &impl Serialize) -> io::Result<()> {
+    let partial = path.with_extension("partial");
+    let mut writer = io::BufWriter::new(fs::File::create(&partial)?);
+    bincode::serialize_into(&mut writer, value).map_err(invalid_data)?;
+    io::Write::flush(&mut writer)?;
+    drop(writer);
+    fs::rename(&partial, path)
+}
+
+/// A bincode error as an I/O error, for data that couldn't be read or written as bincode.
+fn invalid_data(e: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_and_resumes() -> Result<(), SolverError> {
+        let dir = std::env::temp_dir().join(format!("aoc2022-checkpoint-{}", std::process::id()));
+        let path = dir.join("19b.bin");
+        let key = fingerprint(("input", 32));
+
+        let mut saver = Checkpointer::new(&path, false);
+        saver.save(key, &vec![1_u64, 2, 3])?;
+        assert_eq!(saver.load::<Vec<u64>>(key)?, None, "not resuming");
+
+        let resumer = Checkpointer::new(&path, true);
+        assert_eq!(resumer.load::<Vec<u64>>(key)?, Some(vec![1, 2, 3]));
+        assert!(matches!(
+            resumer.load::<Vec<u64>>(fingerprint(("other input", 32))),
+            Err(SolverError::InvalidInput(_))
+        ));
+
+        resumer.clear()?;
+        assert_eq!(resumer.load::<Vec<u64>>(key)?, None);
+
+        fs::remove_dir_all(dir).context("Could not clean up")?;
+        Ok(())
+    }
+
+    #[test]
+    fn saves_once_the_interval_has_passed() -> Result<(), SolverError> {
+        let path = std::env::temp_dir().join(format!("aoc2022-tick-{}.bin", std::process::id()));
+        let clock = crate::clock::FakeClock::new();
+        let mut checkpointer = Checkpointer::new(&path, true)
+            .with_interval(Duration::from_secs(30))
+            .with_clock(SharedClock::new(clock.clone()));
+        let key = fingerprint("ticks");
+
+        let ticks = |checkpointer: &mut Checkpointer, progress: u32| {
+            for _ in 0..Checkpointer::TICKS_PER_CLOCK_CHECK {
+                checkpointer.tick(key, || progress)?;
+            }
+            checkpointer.load::<u32>(key)
+        };
+        assert_eq!(ticks(&mut checkpointer, 1)?, None);
+        clock.advance(Duration::from_secs(29));
+        assert_eq!(ticks(&mut checkpointer, 2)?, None);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(ticks(&mut checkpointer, 3)?, Some(3));
+
+        // The next save is another interval after that one.
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(ticks(&mut checkpointer, 4)?, Some(3));
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(ticks(&mut checkpointer, 5)?, Some(5));
+
+        checkpointer.clear()
+    }
+}
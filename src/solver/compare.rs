@@ -0,0 +1,483 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use super::{bench::format_ms, Answer, SolveError, StepEvent};
+
+/// The label that the input saved without one, like `./input/17b.txt`, goes by.
+pub const DEFAULT_LABEL: &str = "default";
+
+/// How a solver did on several saved inputs for the same subchallenge, for `--all-labels`.
+#[derive(Debug)]
+pub struct ComparisonReport {
+    pub results: Vec<LabeledResult>,
+}
+
+/// How a solver did on one of the inputs.
+#[derive(Debug)]
+pub struct LabeledResult {
+    pub label: String,
+    pub answer: Result<Answer, SolveError>,
+    pub elapsed: Duration,
+}
+
+impl ComparisonReport {
+    /// Whether the solver couldn't finish on any of the inputs.
+    pub fn is_err(&self) -> bool {
+        self.results.iter().any(|r| r.answer.is_err())
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .results
+            .iter()
+            .map(|r| r.label.len())
+            .chain(["input".len()])
+            .max()
+            .unwrap()
+            + 2;
+
+        writeln!(f, "{:<width$}{:<14}answer", "input", "time")?;
+        for result in &self.results {
+            let answer = match &result.answer {
+                Ok(answer) => answer.short().to_string(),
+                Err(e @ SolveError::Skipped { .. }) => e.to_string(),
+                Err(e) => format!("ERROR: {e}"),
+            };
+            writeln!(
+                f,
+                "{:<width$}{:<14}{answer}",
+                result.label,
+                format_ms(result.elapsed)
+            )?;
+        }
+
+        let answers = self
+            .results
+            .iter()
+            .filter_map(|r| Some(r.answer.as_ref().ok()?.plain()))
+            .collect::<Vec<_>>();
+        let distinct = answers.iter().collect::<HashSet<_>>().len();
+        let skipped = self
+            .results
+            .iter()
+            .filter(|r| matches!(r.answer, Err(SolveError::Skipped { .. })))
+            .count();
+        write!(
+            f,
+            "\n{} inputs, {distinct} different answers, {} errors",
+            self.results.len(),
+            self.results.len() - answers.len() - skipped
+        )?;
+        if skipped > 0 {
+            write!(f, ", {skipped} skipped")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// How two of a solver's algorithms did on the same input, for `aoc2022 compare`.
+#[derive(Debug)]
+pub struct AlgorithmComparison {
+    pub runs: [AlgorithmRun; 2],
+}
+
+/// How one of the algorithms did.
+#[derive(Debug)]
+pub struct AlgorithmRun {
+    pub algorithm: String,
+    pub answer: Result<Answer, SolveError>,
+    pub elapsed: Duration,
+    /// Every step that the solver streamed while it ran, if it streams any.
+    pub steps: Vec<StepEvent>,
+}
+
+/// The first step where two algorithms' simulations aren't in the same state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub step: u64,
+    /// What each algorithm was at after that step, or `None` if it had already stopped.
+    pub events: [Option<StepEvent>; 2],
+}
+
+impl AlgorithmComparison {
+    /// Whether both algorithms finished with the same answer.
+    pub fn answers_match(&self) -> bool {
+        let [a, b] = &self.runs;
+        matches!((&a.answer, &b.answer), (Ok(a), Ok(b)) if a == b)
+    }
+
+    /// The first step where the algorithms went different ways, if they streamed their steps.
+    pub fn divergence(&self) -> Option<Divergence> {
+        let [a, b] = &self.runs;
+        first_divergence(&a.steps, &b.steps)
+    }
+
+    /// Whether the algorithms disagree about anything, or either couldn't finish.
+    pub fn is_err(&self) -> bool {
+        !self.answers_match() || self.divergence().is_some()
+    }
+
+    /// Write out the `stats` that either algorithm added to its answer side by side, like how
+    /// many states each one searched, if there are any.
+    fn write_stats(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = self.runs.each_ref().map(|run| {
+            run.answer
+                .as_ref()
+                .ok()
+                .and_then(|answer| answer.metadata().get("stats")?.as_object())
+        });
+        let keys = stats
+            .iter()
+            .flatten()
+            .flat_map(|stats| stats.keys())
+            .collect::<BTreeSet<_>>();
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let value = |i: usize, key: &str| {
+            stats[i]
+                .and_then(|stats| stats.get(key))
+                .map_or("-".to_string(), Value::to_string)
+        };
+        let [a, b] = &self.runs;
+        let width = keys
+            .iter()
+            .map(|key| key.len())
+            .max()
+            .unwrap()
+            .max("stats".len())
+            + 2;
+        let a_width = keys
+            .iter()
+            .map(|key| value(0, key).len())
+            .chain([a.algorithm.len()])
+            .max()
+            .unwrap()
+            + 2;
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<width$}{:<a_width$}{}",
+            "stats", a.algorithm, b.algorithm
+        )?;
+        for key in keys {
+            writeln!(
+                f,
+                "{key:<width$}{:<a_width$}{}",
+                value(0, key),
+                value(1, key)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Line up two streams of steps, and find the first one that isn't the same in both.
+fn first_divergence(a: &[StepEvent], b: &[StepEvent]) -> Option<Divergence> {
+    (0..a.len().max(b.len())).find_map(|i| {
+        let events = [a.get(i), b.get(i)];
+        if events[0] == events[1] {
+            return None;
+        }
+        Some(Divergence {
+            step: events.iter().flatten().next()?.step,
+            events: events.map(|event| event.cloned()),
+        })
+    })
+}
+
+/// Every value that's different between `a` and `b`, by its path through the objects they're in.
+fn diff_values(path: &str, a: Option<&Value>, b: Option<&Value>, diffs: &mut Vec<String>) {
+    match (a, b) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            for key in a.keys().chain(b.keys()).collect::<BTreeSet<_>>() {
+                diff_values(&format!("{path}.{key}"), a.get(key), b.get(key), diffs);
+            }
+        }
+        _ if a != b => {
+            let show =
+                |value: Option<&Value>| value.map_or("missing".to_string(), Value::to_string);
+            diffs.push(format!("{path}: {} vs {}", show(a), show(b)));
+        }
+        _ => {}
+    }
+}
+
+impl fmt::Display for AlgorithmComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .runs
+            .iter()
+            .map(|r| r.algorithm.len())
+            .chain(["algorithm".len()])
+            .max()
+            .unwrap()
+            + 2;
+
+        writeln!(f, "{:<width$}{:<14}answer", "algorithm", "time")?;
+        for run in &self.runs {
+            let answer = match &run.answer {
+                Ok(answer) => answer.short().to_string(),
+                Err(e) => format!("ERROR: {e}"),
+            };
+            writeln!(
+                f,
+                "{:<width$}{:<14}{answer}",
+                run.algorithm,
+                format_ms(run.elapsed)
+            )?;
+        }
+        self.write_stats(f)?;
+
+        writeln!(f)?;
+        let [a, b] = &self.runs;
+        if self.answers_match() {
+            writeln!(f, "The answers match.")?;
+        } else if a.answer.is_ok() && b.answer.is_ok() {
+            writeln!(f, "The answers are different.")?;
+        } else {
+            writeln!(f, "Not every algorithm found an answer.")?;
+        }
+
+        let Some(divergence) = self.divergence() else {
+            return match a.steps.len().max(b.steps.len()) {
+                0 => writeln!(
+                    f,
+                    "Neither algorithm streams its steps, so only the answers were compared."
+                ),
+                steps => writeln!(f, "All {steps} steps match."),
+            };
+        };
+        writeln!(
+            f,
+            "The first different step is step {}, {} vs {}:",
+            divergence.step, a.algorithm, b.algorithm
+        )?;
+        match &divergence.events {
+            [Some(x), Some(y)] => {
+                let mut diffs = Vec::new();
+                let done = |event: &StepEvent| Value::Bool(event.done);
+                diff_values("done", Some(&done(x)), Some(&done(y)), &mut diffs);
+                diff_values("metrics", Some(&x.metrics), Some(&y.metrics), &mut diffs);
+                for diff in diffs {
+                    writeln!(f, "  {diff}")?;
+                }
+                Ok(())
+            }
+            [_, None] => writeln!(f, "  {} stopped before it", b.algorithm),
+            [None, _] => writeln!(f, "  {} stopped before it", a.algorithm),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::{
+        challenge::{ChallengeInput, Subchallenge},
+        clock::{Clock, FakeClock, SharedClock},
+        error::SolverError,
+        solver::{ChallengeSolver, ChallengeSolverResult, Solver, SolverConfig, BUILTIN_NAMESPACE},
+    };
+
+    use super::*;
+
+    /// A solver for day 20 that takes far longer than any timeout on its fake clock, and then
+    /// never stops, even when it's cancelled.
+    #[derive(Debug)]
+    struct Sleepy(&'static str, FakeClock);
+
+    impl ChallengeSolver for Sleepy {
+        fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
+            20
+        }
+
+        fn namespace(&self) -> &str {
+            self.0
+        }
+
+        fn solve_a(&mut self, _input: &mut dyn io::BufRead) -> ChallengeSolverResult {
+            self.1.sleep(Duration::from_secs(3));
+            loop {
+                std::thread::park();
+            }
+        }
+
+        fn solve_b(&mut self, input: &mut dyn io::BufRead) -> ChallengeSolverResult {
+            self.solve_a(input)
+        }
+    }
+
+    /// Time out on the first of two inputs with a [`Sleepy`] solver from `namespace`.
+    fn time_out_on_the_first(namespace: &'static str) -> ComparisonReport {
+        let clock = FakeClock::new();
+        let mut solver = Solver::new();
+        solver
+            .challenge_solvers
+            .insert(20, Box::new(Sleepy(namespace, clock.clone())));
+        let input = |text: &str| -> ChallengeInput { Box::new(io::Cursor::new(text.to_string())) };
+        let inputs = vec![
+            ("slow".to_string(), input("")),
+            ("sample".to_string(), input("1\n2\n-3\n3\n-2\n0\n4\n")),
+        ];
+        let config = SolverConfig {
+            timeout: Some(Duration::from_millis(10)),
+            clock: SharedClock::new(clock),
+            ..SolverConfig::default()
+        };
+        solver.compare_inputs(20, Subchallenge::A, inputs, &config)
+    }
+
+    #[test]
+    fn a_builtin_solver_is_replaced_after_timing_out() {
+        let report = time_out_on_the_first(BUILTIN_NAMESPACE);
+        assert!(matches!(
+            report.results[0].answer,
+            Err(SolveError::TimedOut { after, .. }) if after == Duration::from_millis(10)
+        ));
+        assert_eq!(report.results[0].elapsed, Duration::from_secs(3));
+        let answer = report.results[1].answer.as_ref().unwrap();
+        assert_eq!(answer.downcast_ref::<i64>(), Some(&3));
+    }
+
+    #[test]
+    fn skips_the_rest_once_the_solver_is_lost() {
+        let report = time_out_on_the_first("plugin");
+        assert!(matches!(
+            &report.results[1].answer,
+            Err(SolveError::Skipped { timed_out_on, .. }) if timed_out_on == "slow"
+        ));
+        let table = report.to_string();
+        assert!(
+            table.contains("  Skipped, since the solver for challenge 20 never came back"),
+            "{table}"
+        );
+        assert!(table.ends_with("2 inputs, 0 different answers, 1 errors, 1 skipped\n"));
+    }
+
+    #[test]
+    fn lines_up_every_input() {
+        let result = |label: &str, answer| LabeledResult {
+            label: label.to_string(),
+            answer,
+            elapsed: Duration::from_millis(2),
+        };
+        let report = ComparisonReport {
+            results: vec![
+                result(DEFAULT_LABEL, Ok(Answer::new(3068))),
+                result("friend1", Ok(Answer::new(3068))),
+                result(
+                    "a-long-label",
+                    Err(SolverError::invalid_input("The input is empty").into()),
+                ),
+            ],
+        };
+
+        assert!(report.is_err());
+        assert_eq!(
+            report.to_string(),
+            "input         time          answer\n\
+             default       2.000 ms      3068\n\
+             friend1       2.000 ms      3068\n\
+             a-long-label  2.000 ms      ERROR: The input is empty\n\
+             \n\
+             3 inputs, 1 different answers, 1 errors\n"
+        );
+    }
+
+    #[test]
+    fn finds_the_first_different_step() {
+        let step = |step, settled| StepEvent {
+            step,
+            done: false,
+            metrics: serde_json::json!({ "settled": settled, "falling": 1 }),
+        };
+        let run = |algorithm: &str, answer, steps| AlgorithmRun {
+            algorithm: algorithm.to_string(),
+            answer: Ok(Answer::new(answer)),
+            elapsed: Duration::from_millis(2),
+            steps,
+        };
+
+        let same = AlgorithmComparison {
+            runs: [
+                run("naive", 24, vec![step(0, 0), step(1, 1)]),
+                run("fast", 24, vec![step(0, 0), step(1, 1)]),
+            ],
+        };
+        assert!(!same.is_err());
+        assert!(same
+            .to_string()
+            .ends_with("The answers match.\nAll 2 steps match.\n"));
+
+        let different = AlgorithmComparison {
+            runs: [
+                run("naive", 24, vec![step(0, 0), step(1, 1), step(2, 2)]),
+                run("fast", 25, vec![step(0, 0), step(1, 2)]),
+            ],
+        };
+        assert!(different.is_err());
+        assert_eq!(different.divergence().map(|d| d.step), Some(1));
+        assert_eq!(
+            different.to_string(),
+            "algorithm  time          answer\n\
+             naive      2.000 ms      24\n\
+             fast       2.000 ms      25\n\
+             \n\
+             The answers are different.\n\
+             The first different step is step 1, naive vs fast:\n  \
+             metrics.settled: 1 vs 2\n"
+        );
+
+        let shorter = first_divergence(&[step(0, 0), step(1, 1)], &[step(0, 0)]);
+        assert_eq!(shorter.map(|d| d.events), Some([Some(step(1, 1)), None]));
+    }
+
+    #[test]
+    fn lines_up_the_stats() {
+        let run = |algorithm: &str, stats| AlgorithmRun {
+            algorithm: algorithm.to_string(),
+            answer: Ok(Answer::new(1707).with_metadata("stats", stats)),
+            elapsed: Duration::from_millis(2),
+            steps: vec![],
+        };
+        let comparison = AlgorithmComparison {
+            runs: [
+                run(
+                    "partition",
+                    serde_json::json!({ "states_explored": 1821, "memo_size": 64 }),
+                ),
+                run(
+                    "joint",
+                    serde_json::json!({ "states_explored": 19, "pruned_by_bound": 53 }),
+                ),
+            ],
+        };
+
+        assert!(!comparison.is_err());
+        assert_eq!(
+            comparison.to_string(),
+            "algorithm  time          answer\n\
+             partition  2.000 ms      1707\n\
+             joint      2.000 ms      1707\n\
+             \n\
+             stats            partition  joint\n\
+             memo_size        64         -\n\
+             pruned_by_bound  -          53\n\
+             states_explored  1821       19\n\
+             \n\
+             The answers match.\n\
+             Neither algorithm streams its steps, so only the answers were compared.\n"
+        );
+    }
+}
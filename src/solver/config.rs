@@ -0,0 +1,195 @@
+use std::{collections::BTreeMap, fmt, str::FromStr, time::Duration};
+
+use crate::{clock::SharedClock, color::ColorChoice, math::MathMode, sync::CancellationToken};
+
+use super::{Checkpointer, EventStream, ParseCache, PostExpr, Transform};
+
+/// Run-time options that are passed to every solver before it starts solving.
+#[derive(Debug, Clone, Default)]
+pub struct SolverConfig {
+    /// Show an interactive visualization instead of just printing the result, for solvers
+    /// that have one.
+    pub visualize: bool,
+
+    /// Solver-specific parameters, given on the command line as `--param key=value`.
+    pub params: SolverParams,
+
+    /// Give up on solving after this long.
+    pub timeout: Option<Duration>,
+
+    /// Cancelled by [`Solver::solve`](super::Solver::solve) once `timeout` runs out. Solvers with
+    /// long-running searches should hold on to a clone and check it every so often.
+    pub cancel: CancellationToken,
+
+    /// Where solvers with long-running searches save their progress, so that an interrupted run
+    /// can be resumed. Turned off by default.
+    pub checkpoint: Checkpointer,
+
+    /// Where solvers with slow parsing keep what they parsed, so that solving the other
+    /// subchallenge on the same input doesn't parse it again. Turned off by default.
+    pub parse_cache: ParseCache,
+
+    /// What arithmetic-heavy solvers do when a number gets too big for its type. Wraps around by
+    /// default, like plain arithmetic in a release build.
+    pub math: MathMode,
+
+    /// Whether solvers that take a shortcut, like skipping ahead over a loop, also check it
+    /// against working things out the slow way, for `--check-consistency`. Off by default.
+    pub check_consistency: bool,
+
+    /// Whether solvers that read a record per line leave out the lines that don't parse, with a
+    /// warning, instead of failing, for `--skip-bad-lines`. Off by default.
+    pub skip_bad_lines: bool,
+
+    /// Where simulation solvers send each of their steps, for `--stream-events`. Turned off by
+    /// default.
+    pub events: EventStream,
+
+    /// Changes to make to the input after parsing it, given on the command line as
+    /// `--transform`, in the order to make them.
+    pub transforms: Vec<Transform>,
+
+    /// Arithmetic to work out from the answer once it's solved, given on the command line as
+    /// `--post`, which becomes the answer instead.
+    pub post: Option<PostExpr>,
+
+    /// Whether to color what solvers print, for `--color`. Colors only go to a terminal by
+    /// default.
+    pub color: ColorChoice,
+
+    /// What solvers and whatever runs them tell the time with. The real time by default, but
+    /// tests can swap in a [`FakeClock`](crate::clock::FakeClock).
+    pub clock: SharedClock,
+}
+
+/// What a solver's `--visualize` UI is built with, which decides the cargo feature it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frontend {
+    /// A terminal UI, from the `tui` feature.
+    Tui,
+    /// A native window, from the `gui` feature.
+    Gui,
+}
+
+impl Frontend {
+    /// The cargo feature that builds this frontend.
+    pub fn feature(self) -> &'static str {
+        match self {
+            Self::Tui => "tui",
+            Self::Gui => "gui",
+        }
+    }
+
+    /// Whether this binary was built with the frontend's feature.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Tui => cfg!(feature = "tui"),
+            Self::Gui => cfg!(feature = "gui"),
+        }
+    }
+}
+
+/// Solver-specific `key=value` parameters.
+///
+/// Values are kept as strings until a solver asks for one, so that each solver can decide what
+/// type its parameters should be.
+#[derive(Debug, Clone, Default)]
+pub struct SolverParams(BTreeMap<String, String>);
+
+impl SolverParams {
+    /// Set a parameter, replacing any previous value.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(key.into(), value.into());
+    }
+
+    /// Every parameter and its value, in order of their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Get a parameter parsed as a `T`, or `None` if it wasn't given.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, ParamError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.0
+            .get(key)
+            .map(|value| {
+                value.parse().map_err(|e: T::Err| ParamError::InvalidValue {
+                    key: key.to_string(),
+                    value: value.clone(),
+                    reason: e.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    /// Get a parameter parsed as a `T`, or `default` if it wasn't given.
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T, ParamError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+}
+
+/// Which kind of input to pick parameters for, as given to `--preset`.
+///
+/// Some puzzles ask a different question about the sample in their description than about the
+/// real input, like which row to look at on day 15. Each solver defines the parameters for each
+/// preset alongside its other parameters, so that nobody has to remember what they're called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// The example input from the puzzle description.
+    Sample,
+    /// A full puzzle input.
+    Real,
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sample" => Ok(Self::Sample),
+            "real" => Ok(Self::Real),
+            _ => Err("expected `sample` or `real`".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sample => write!(f, "sample"),
+            Self::Real => write!(f, "real"),
+        }
+    }
+}
+
+/// Parse a `key=value` pair, as given to `--param`.
+pub fn parse_param(s: &str) -> Result<(String, String), ParamError> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(ParamError::BadFormat(s.to_string())),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParamError {
+    #[error("Expected a parameter of the form `key=value`, found `{0}`")]
+    BadFormat(String),
+
+    #[error("Invalid value `{value}` for parameter `{key}`: {reason}")]
+    InvalidValue {
+        key: String,
+        value: String,
+        reason: String,
+    },
+}
@@ -0,0 +1,206 @@
+//! Streaming a simulation's steps as JSON Lines, for `--stream-events`.
+
+use std::{
+    fmt, fs,
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, SolverError},
+    simulation::StepResult,
+};
+
+/// One step of a simulation, as written to an [`EventStream`]: a single line of JSON, like
+/// `{"step":12,"done":false,"metrics":{"settled":3,"falling":1}}`.
+///
+/// The metrics are whatever the simulation reports about itself, so they differ from day to day.
+/// Anything that reads a stream back should go by this type rather than the JSON directly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepEvent {
+    /// How many steps had been taken, starting from 0 for the state before the first step.
+    pub step: u64,
+    /// Whether the simulation has finished.
+    pub done: bool,
+    pub metrics: serde_json::Value,
+}
+
+/// Where simulation solvers send each of their steps, so that something outside of the crate can
+/// draw them.
+///
+/// The default stream is turned off, and throws every event away. Solvers hold on to a clone from
+/// [`SolverConfig::events`](super::SolverConfig::events) and call [`EventStream::emit`] after
+/// every step, or run their simulation with
+/// [`Simulation::run_streaming`](crate::simulation::Simulation::run_streaming). Every event is
+/// written out as soon as it's emitted, so a visualizer can follow along live.
+#[derive(Clone, Default)]
+pub struct EventStream {
+    sink: Option<Arc<Mutex<Sink>>>,
+}
+
+type Sink = LineWriter<Box<dyn Write + Send>>;
+
+impl EventStream {
+    /// Write events to a new file at `path`, replacing it if it already exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(fs::File::create(path)?))
+    }
+
+    /// Write events to stdout.
+    ///
+    /// Events go to a copy of stdout made right now, so that they still get through if the
+    /// solver's own printing to stdout is hidden afterwards with [`gag`].
+    pub fn stdout() -> io::Result<Self> {
+        let stdout =
+            filedescriptor::FileDescriptor::dup(&io::stdout()).map_err(io::Error::other)?;
+        Ok(Self::new(stdout))
+    }
+
+    /// Keep events in memory, to be read back from the [`Recording`] once the simulation is done.
+    pub(crate) fn recording() -> (Self, Recording) {
+        let recording = Recording::default();
+        (Self::new(recording.clone()), recording)
+    }
+
+    fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            sink: Some(Arc::new(Mutex::new(LineWriter::new(Box::new(writer))))),
+        }
+    }
+
+    /// Whether events are going anywhere, or just thrown away.
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Write out an event for the state after `step` steps, if events are turned on.
+    pub(crate) fn emit(
+        &self,
+        step: u64,
+        result: StepResult,
+        metrics: impl FnOnce() -> serde_json::Value,
+    ) -> Result<(), SolverError> {
+        let Some(sink) = &self.sink else {
+            return Ok(());
+        };
+
+        let event = StepEvent {
+            step,
+            done: result == StepResult::Done,
+            metrics: metrics(),
+        };
+        let mut sink = sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        serde_json::to_writer(&mut *sink, &event)
+            .map_err(io::Error::from)
+            .and_then(|()| writeln!(sink))
+            .context("Could not write simulation event")
+    }
+}
+
+/// The events written to an [`EventStream::recording`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Recording(Arc<Mutex<Vec<u8>>>);
+
+impl Recording {
+    /// Every event recorded so far, in order.
+    pub(crate) fn events(&self) -> Vec<StepEvent> {
+        let buf = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Only `EventStream::emit` writes here, so every line is an event.
+        serde_json::Deserializer::from_slice(&buf)
+            .into_iter()
+            .map(|event| event.expect("recorded events should be valid JSON"))
+            .collect()
+    }
+}
+
+impl Write for Recording {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventStream")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::Simulation;
+
+    use super::*;
+
+    /// Counts up to a limit.
+    struct Counter(u64);
+
+    impl Simulation for Counter {
+        fn step(&mut self) -> StepResult {
+            self.0 += 1;
+            if self.0 == 3 {
+                StepResult::Done
+            } else {
+                StepResult::Continue
+            }
+        }
+
+        fn reset(&mut self) {
+            self.0 = 0;
+        }
+
+        fn metrics(&self) -> serde_json::Value {
+            serde_json::json!({ "count": self.0 })
+        }
+    }
+
+    #[test]
+    fn streams_one_line_per_step() -> Result<(), SolverError> {
+        let path =
+            std::env::temp_dir().join(format!("aoc2022-events-{}.jsonl", std::process::id()));
+        let events = EventStream::create(&path)?;
+        assert_eq!(Counter(0).run_streaming(None, &events)?, 3);
+        assert_eq!(Counter(0).run_streaming(None, &EventStream::default())?, 3);
+        drop(events);
+        let written = fs::read_to_string(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(
+            written.lines().next(),
+            Some(r#"{"step":0,"done":false,"metrics":{"count":0}}"#)
+        );
+        let events: Vec<StepEvent> = written
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            events.iter().map(|e| (e.step, e.done)).collect::<Vec<_>>(),
+            [(0, false), (1, false), (2, false), (3, true)]
+        );
+        assert_eq!(events[3].metrics["count"], 3);
+
+        let (events, recording) = EventStream::recording();
+        Counter(0).run_streaming(Some(2), &events)?;
+        assert_eq!(
+            recording.events().last().map(|e| (e.step, e.done)),
+            Some((2, false))
+        );
+        Ok(())
+    }
+}
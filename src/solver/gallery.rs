@@ -0,0 +1,174 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    challenge::ChallengeNumber,
+    error::{Context, SolverError},
+    raster::Raster,
+};
+
+use super::SolveError;
+
+/// Where `aoc2022 gallery` saves its pictures, unless told otherwise.
+pub const DEFAULT_GALLERY_DIR: &str = "gallery";
+
+/// A picture of what a solver ended up with, for `aoc2022 gallery`.
+///
+/// Returned by [`ChallengeSolver::gallery`][super::ChallengeSolver::gallery].
+#[derive(Debug)]
+pub struct Rendering {
+    /// What the picture shows, like `tower`, which goes in its file name.
+    pub name: &'static str,
+    pub picture: Picture,
+}
+
+#[derive(Debug)]
+pub enum Picture {
+    Png(Raster),
+    Text(String),
+}
+
+impl Rendering {
+    pub fn png(name: &'static str, raster: Raster) -> Self {
+        Self {
+            name,
+            picture: Picture::Png(raster),
+        }
+    }
+
+    pub fn text(name: &'static str, text: impl Into<String>) -> Self {
+        Self {
+            name,
+            picture: Picture::Text(text.into()),
+        }
+    }
+
+    /// The name of the file to save the picture to, like `17-tower.png`.
+    pub fn file_name(&self, challenge: ChallengeNumber) -> String {
+        let extension = match self.picture {
+            Picture::Png(_) => "png",
+            Picture::Text(_) => "txt",
+        };
+        format!("{challenge:02}-{}.{extension}", self.name)
+    }
+
+    /// Save the picture into `dir`, replacing whatever was there before. Returns where it went.
+    pub fn save(&self, dir: &Path, challenge: ChallengeNumber) -> Result<PathBuf, SolverError> {
+        let path = dir.join(self.file_name(challenge));
+        match &self.picture {
+            Picture::Png(raster) => raster.save_png(&path)?,
+            Picture::Text(text) => fs::write(&path, text)
+                .with_context(|| format!("Could not save {}", path.display()))?,
+        }
+        Ok(path)
+    }
+}
+
+/// What every solver that can draw itself drew from its sample.
+#[derive(Debug)]
+pub struct GalleryReport {
+    pub results: Vec<GalleryResult>,
+}
+
+/// What one solver drew from its sample.
+#[derive(Debug)]
+pub struct GalleryResult {
+    pub challenge: ChallengeNumber,
+    pub outcome: GalleryOutcome,
+}
+
+#[derive(Debug)]
+pub enum GalleryOutcome {
+    /// Where each of the solver's pictures was saved.
+    Saved(Vec<PathBuf>),
+    Error(SolveError),
+}
+
+impl GalleryReport {
+    /// Whether any solver couldn't draw its sample.
+    pub fn is_err(&self) -> bool {
+        self.results
+            .iter()
+            .any(|r| matches!(r.outcome, GalleryOutcome::Error(_)))
+    }
+
+    /// How many pictures were saved altogether.
+    pub fn saved(&self) -> usize {
+        self.results
+            .iter()
+            .map(|r| match &r.outcome {
+                GalleryOutcome::Saved(paths) => paths.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for GalleryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<11}saved", "challenge")?;
+        for result in &self.results {
+            let challenge = format!("{:02}", result.challenge);
+            match &result.outcome {
+                GalleryOutcome::Saved(paths) => {
+                    for (i, path) in paths.iter().enumerate() {
+                        let challenge = if i == 0 { challenge.as_str() } else { "" };
+                        writeln!(f, "{challenge:<11}{}", path.display())?;
+                    }
+                }
+                GalleryOutcome::Error(_) => writeln!(f, "{challenge:<11}ERROR")?,
+            }
+        }
+
+        let mut wrote_details = false;
+        for result in &self.results {
+            if let GalleryOutcome::Error(e) = &result.outcome {
+                if !wrote_details {
+                    writeln!(f)?;
+                    wrote_details = true;
+                }
+                writeln!(f, "challenge {}: error: {e}", result.challenge)?;
+            }
+        }
+
+        writeln!(f, "\n{} pictures saved", self.saved())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_every_picture() {
+        let report = GalleryReport {
+            results: vec![
+                GalleryResult {
+                    challenge: 12,
+                    outcome: GalleryOutcome::Saved(vec![
+                        PathBuf::from("gallery/12-path-a.png"),
+                        PathBuf::from("gallery/12-path-b.png"),
+                    ]),
+                },
+                GalleryResult {
+                    challenge: 14,
+                    outcome: GalleryOutcome::Error(SolveError::NoSolverLoaded(14)),
+                },
+            ],
+        };
+
+        assert!(report.is_err());
+        let text = report.to_string();
+        assert!(
+            text.contains("12         gallery/12-path-a.png\n           gallery/12-path-b.png"),
+            "{text}"
+        );
+        assert!(text.contains("challenge 14: error: No solver loaded for challenge 14."));
+        assert!(text.contains("2 pictures saved"));
+
+        let crt = Rendering::text("crt", "...");
+        assert_eq!(crt.file_name(10), "10-crt.txt");
+    }
+}
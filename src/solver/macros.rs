@@ -1,80 +1,88 @@
+/// Sets up a solver's sample from `samples/`, and tests the solver against it.
+///
+/// Takes the solver to test, then the challenge number of its sample along with the types of its
+/// answers, and then any other tests to put in the same `tests` module. Part B's answer can be
+/// left out, for solvers whose part B has nothing to check against.
 macro_rules! challenge_solver_test_boilerplate {
     {
         $challenge_solver:expr;
-        $sample_input:expr => {
-            a as $res_type_a:ty : $res_a:expr,
-            b as $res_type_b:ty : $res_b:expr $(,)?
+        $challenge:literal => {
+            a as $res_type_a:ty
+            $(, b as $res_type_b:ty)? $(,)?
         }
         $($other_tests:tt)*
     } => {
+        /// The example input from the puzzle description.
+        const SAMPLE_INPUT: &str = $crate::samples::sample_input!($challenge);
+
+        /// What the solver should answer for [`SAMPLE_INPUT`], for subchallenges A and B.
+        fn sample_answers() -> [Option<$crate::solver::Answer>; 2] {
+            use $crate::{challenge::Subchallenge, samples::golden, solver::Answer};
+
+            let a = golden::<$res_type_a>($challenge, Subchallenge::A);
+            [
+                Some(Answer::new(a)),
+                None $(.or(Some(Answer::new(golden::<$res_type_b>($challenge, Subchallenge::B)))))?,
+            ]
+        }
+
         #[cfg(test)]
         mod tests {
             use super::*;
-            use $crate::solver::ChallengeSolver;
+            use $crate::{error::SolverError, solver::ChallengeSolver};
             use std::io::Cursor;
 
-            const SAMPLE_INPUT: &str = $sample_input;
-
             #[test]
-            fn test_a() -> color_eyre::Result<()> {
-                color_eyre::install()?;
+            fn test_a() -> Result<(), SolverError> {
                 let mut input = Cursor::new(SAMPLE_INPUT);
                 let mut solver = $challenge_solver;
 
                 let res = solver.solve_a(&mut input)?;
 
-                let res = res.downcast_ref::<$res_type_a>().ok_or_else(|| {
-                    color_eyre::eyre::eyre!(
-                        "Could not cast challenge solver result to {}",
-                        stringify!($res_type_a)
-                    )
-                })?;
-
-                assert_eq!(res, &$res_a);
+                let [expected, _] = sample_answers();
+                assert_eq!(Some(res), expected);
 
                 Ok(())
             }
 
-            #[test]
-            fn test_b() -> color_eyre::Result<()> {
-                color_eyre::install()?;
-                let mut input = Cursor::new(SAMPLE_INPUT);
-                let mut solver = $challenge_solver;
+            $(
+                #[test]
+                fn test_b() -> Result<(), SolverError> {
+                    let mut input = Cursor::new(SAMPLE_INPUT);
+                    let mut solver = $challenge_solver;
 
-                let res = solver.solve_b(&mut input)?;
+                    let res = solver.solve_b(&mut input)?;
 
-                let res = res.downcast_ref::<$res_type_b>().ok_or_else(|| {
-                    color_eyre::eyre::eyre!(
-                        "Could not cast challenge solver result to {}",
-                        stringify!($res_type_b)
-                    )
-                })?;
+                    let expected = $crate::samples::golden::<$res_type_b>(
+                        $challenge,
+                        $crate::challenge::Subchallenge::B,
+                    );
+                    assert_eq!(res, $crate::solver::Answer::new(expected));
 
-                assert_eq!(res, &$res_b);
-
-                Ok(())
-            }
+                    Ok(())
+                }
+            )?
 
             $($other_tests)*
         }
     };
+}
+pub(super) use challenge_solver_test_boilerplate;
 
-    {
-        $challenge_solver:expr;
-        $sample_input:expr => {
-            b as $res_type_b:ty : $res_b:expr,
-            a as $res_type_a:ty : $res_a:expr $(,)?
+/// Implements [`ChallengeSolver::sample_input`](super::ChallengeSolver::sample_input) and
+/// [`ChallengeSolver::sample_answers`](super::ChallengeSolver::sample_answers) with the sample
+/// given to [`challenge_solver_test_boilerplate`].
+///
+/// Goes inside of a solver's `impl ChallengeSolver` block.
+macro_rules! sample_methods {
+    () => {
+        fn sample_input(&self) -> Option<&'static str> {
+            Some(SAMPLE_INPUT)
         }
-        $($other_tests:tt)*
-    } => {
-        $crate::solver::macros::challenge_solver_test_boilerplate!{
-            $challenge_solver;
-            $sample_input => {
-                a as $res_type_a: $res_a,
-                b as $res_type_b: $res_b,
-            }
-            $($other_tests)*
+
+        fn sample_answers(&self) -> [Option<$crate::solver::Answer>; 2] {
+            sample_answers()
         }
     };
 }
-pub(super) use challenge_solver_test_boilerplate;
+pub(super) use sample_methods;
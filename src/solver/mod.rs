@@ -1,9 +1,40 @@
-use std::{any::Any, collections::HashMap, fmt, fs, io};
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::Path,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
 
-use crate::challenge::{ChallengeNumber, Subchallenge};
+use crate::{
+    challenge::{self, ChallengeNumber, InputChecks, Subchallenge},
+    clock::SharedClock,
+    error::{Context, SolverError},
+    sync::CancellationToken,
+};
 
 mod macros; // must be defined before other modules!
 
+mod answer;
+mod artifacts;
+mod bench;
+mod checkpoint;
+mod compare;
+mod config;
+mod events;
+mod gallery;
+mod parse_cache;
+#[cfg(feature = "plugins")]
+mod plugin;
+mod post;
+mod progress;
+mod run_all;
+mod scramble;
+mod selftest;
+mod transform;
+mod validate;
+
 mod solver01;
 mod solver02;
 mod solver03;
@@ -25,16 +56,154 @@ mod solver18;
 mod solver19;
 mod solver20;
 mod solver21;
+mod solver22;
+
+use macros::{challenge_solver_test_boilerplate, sample_methods};
 
-pub(self) use macros::challenge_solver_test_boilerplate;
+pub use answer::{Answer, AnswerDisplay};
+pub use artifacts::{capture_panics, ArtifactDump, Failure};
+pub use bench::{
+    BaselineMode, BaselineReport, Baselines, BenchReport, BenchResult, InputKind, BASELINE_FILE,
+    DEFAULT_MAX_REGRESSION, DEFAULT_RUNS,
+};
+pub use checkpoint::{Checkpointer, CHECKPOINT_DIR};
+pub use compare::{
+    AlgorithmComparison, AlgorithmRun, ComparisonReport, Divergence, LabeledResult, DEFAULT_LABEL,
+};
+pub use config::{parse_param, Frontend, ParamError, Preset, SolverConfig, SolverParams};
+pub use events::{EventStream, StepEvent};
+pub use gallery::{
+    GalleryOutcome, GalleryReport, GalleryResult, Picture, Rendering, DEFAULT_GALLERY_DIR,
+};
+pub(crate) use parse_cache::CacheableParse;
+pub use parse_cache::{ParseCache, PARSE_CACHE_DIR};
+#[cfg(feature = "plugins")]
+pub use plugin::{
+    AbiVersionFn, FreeFn, PluginError, PluginSolver, RegisterFn, RegisterSolvers, SolveFn,
+    PLUGIN_ABI_VERSION,
+};
+pub use post::{PostError, PostExpr};
+pub use progress::{
+    stderr_progress, NoProgress, ProgressBars, ProgressLog, ProgressSink, RunStatus,
+};
+pub use run_all::{Check, KnownAnswers, RunAllReport, RunOutcome, RunResult, ANSWERS_FILE};
+pub use scramble::{Scrambler, SeedSource};
+pub use selftest::{Outcome, SampleResult, SelfTestReport};
+pub use transform::Transform;
+pub(crate) use transform::TransformableInput;
+pub use validate::{ParseStats, ValidationReport};
 
 /// A solver for a single challenge.
 ///
 /// Must be able to handle solving both subchallenges.
-trait ChallengeSolver: fmt::Debug {
+trait ChallengeSolver: fmt::Debug + Send {
     /// The challenge number that this solver is written for.
     fn challenge_number(&self) -> ChallengeNumber;
 
+    /// Where the solver came from, to tell it apart from other solvers for the same challenge.
+    ///
+    /// Returns [`BUILTIN_NAMESPACE`] by default, which is right for every solver in this crate.
+    fn namespace(&self) -> &str {
+        BUILTIN_NAMESPACE
+    }
+
+    /// Apply run-time configuration before solving.
+    ///
+    /// Does nothing by default, since most solvers don't have anything to configure.
+    fn configure(&mut self, _config: &SolverConfig) -> Result<(), ParamError> {
+        Ok(())
+    }
+
+    /// What the solver's `--visualize` UI is built with.
+    ///
+    /// Returns `None` by default, for solvers that don't have anything to show.
+    fn frontend(&self) -> Option<Frontend> {
+        None
+    }
+
+    /// Run only the parsing stage of the solver on `input`, without solving anything.
+    ///
+    /// Returns `None` by default, for solvers whose parsing isn't separate from solving.
+    fn validate(&self, _input: &str) -> Option<Result<ParseStats, SolverError>> {
+        None
+    }
+
+    /// Make a randomized copy of `input` with the same structure, for sharing in bug reports
+    /// without giving away the original puzzle input.
+    ///
+    /// Returns `None` by default, for solvers that don't know how to scramble their input.
+    fn scramble(
+        &self,
+        _input: &str,
+        _scrambler: &mut Scrambler,
+    ) -> Option<Result<String, SolverError>> {
+        None
+    }
+
+    /// Make up a valid input with about `size` of whatever the input is a list of, like moves or
+    /// numbers, for stress tests and benchmarks.
+    ///
+    /// Returns `None` by default, for solvers that don't know how to make up inputs.
+    fn generate(&self, _size: usize, _rng: &mut Scrambler) -> Option<Result<String, SolverError>> {
+        None
+    }
+
+    /// The names of the algorithms that `--param algorithm=NAME` picks between, for
+    /// `aoc2022 compare`.
+    ///
+    /// Returns no names by default, for solvers that only solve things one way.
+    fn algorithms(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether the solver applies [`SolverConfig::transforms`] to its input.
+    ///
+    /// Returns `false` by default, for solvers that can't change their input.
+    fn transformable(&self) -> bool {
+        false
+    }
+
+    /// Solve `input` without printing anything, and draw what the solver ended up with, for
+    /// `aoc2022 gallery`. Expects to have been configured first.
+    ///
+    /// Returns `None` by default, for solvers that don't have anything to draw.
+    fn gallery(&self, _input: &str) -> Option<Result<Vec<Rendering>, SolverError>> {
+        None
+    }
+
+    /// The example input from the puzzle description, for `aoc2022 selftest`.
+    ///
+    /// Returns `None` by default, for solvers without a sample.
+    fn sample_input(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// What the solver should answer for [`ChallengeSolver::sample_input`], for subchallenges A
+    /// and B.
+    ///
+    /// Returns `None` by default, for subchallenges whose answer isn't known, or that have nothing
+    /// to check it against, like ones that only print their answer.
+    fn sample_answers(&self) -> [Option<Answer>; 2] {
+        [None, None]
+    }
+
+    /// The parameters to solve a kind of input with, for `--preset`.
+    ///
+    /// Returns no parameters by default, for solvers that solve every input the same way.
+    fn preset(&self, _preset: Preset) -> SolverParams {
+        SolverParams::default()
+    }
+
+    /// The configuration to solve [`ChallengeSolver::sample_input`] with.
+    ///
+    /// Uses the parameters from [`Preset::Sample`] by default.
+    fn sample_config(&self) -> SolverConfig {
+        SolverConfig {
+            params: self.preset(Preset::Sample),
+            ..SolverConfig::default()
+        }
+    }
+
     /// Solve subchallenge A.
     fn solve_a(&mut self, input: &mut dyn io::BufRead) -> ChallengeSolverResult;
 
@@ -44,51 +213,55 @@ trait ChallengeSolver: fmt::Debug {
 
 type DynamicChallengeSolver = Box<dyn ChallengeSolver>;
 
-pub type ChallengeSolverResult = color_eyre::Result<Box<dyn Any>>;
+pub type ChallengeSolverResult = Result<Answer, SolverError>;
+
+/// The namespace of the solvers built into this crate.
+pub const BUILTIN_NAMESPACE: &str = "builtin";
+
+/// How big a made-up input is when no size is given, which is a few times bigger than the real
+/// puzzle inputs.
+pub const DEFAULT_GENERATED_SIZE: usize = 10_000;
+
+/// Which solver is which, when there's more than one for a challenge: the namespace it came from,
+/// and the challenge it solves. Shown like `builtin:17`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SolverId {
+    pub namespace: String,
+    pub challenge: ChallengeNumber,
+}
+
+impl SolverId {
+    fn of(solver: &dyn ChallengeSolver) -> Self {
+        Self {
+            namespace: solver.namespace().to_string(),
+            challenge: solver.challenge_number(),
+        }
+    }
+}
+
+impl fmt::Display for SolverId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:02}", self.namespace, self.challenge)
+    }
+}
 
 pub struct Solver {
+    /// The solver in use for each challenge.
     challenge_solvers: HashMap<ChallengeNumber, DynamicChallengeSolver>,
+    /// Every other solver, waiting for its namespace to be [selected](Solver::select).
+    standby: Vec<DynamicChallengeSolver>,
+    /// What [`Solver::bench`] and [`Solver::run_all`] time the solvers with, and hand to them.
+    clock: SharedClock,
+    /// Where [`Solver::scramble`] and [`Solver::generate`] get a seed from when they aren't given
+    /// one.
+    seeds: SeedSource,
 }
 
 impl Solver {
     pub fn new() -> Self {
-        macro_rules! build_solver_list {
-            [$($solver_ty:ty),* $(,)?] => {
-                vec![
-                    $(
-                        Box::<$solver_ty>::default(),
-                    )*
-                ]
-            };
-        }
-
-        let solvers: Vec<DynamicChallengeSolver> = build_solver_list![
-            solver01::Solver01,
-            solver02::Solver02,
-            solver03::Solver03,
-            solver04::Solver04,
-            solver05::Solver05,
-            solver06::Solver06,
-            solver07::Solver07,
-            solver08::Solver08,
-            solver09::Solver09,
-            solver10::Solver10,
-            solver11::Solver11,
-            solver12::Solver12,
-            solver13::Solver13,
-            solver14::Solver14,
-            solver15::Solver15,
-            solver16::Solver16,
-            solver17::Solver17,
-            solver18::Solver18,
-            solver19::Solver19,
-            solver20::Solver20,
-            solver21::Solver21,
-        ];
-
         let mut challenge_solvers = HashMap::new();
 
-        for solver in solvers {
+        for solver in builtin_solvers() {
             let challenge_number = solver.challenge_number();
             let previous = challenge_solvers.insert(challenge_number, solver);
 
@@ -99,24 +272,729 @@ impl Solver {
             );
         }
 
-        Self { challenge_solvers }
+        Self {
+            challenge_solvers,
+            standby: Vec::new(),
+            clock: SharedClock::default(),
+            seeds: SeedSource::default(),
+        }
     }
 
+    /// Time `bench` and `run_all` with `clock` instead of the real one.
+    pub fn with_clock(self, clock: SharedClock) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Pick seeds for `scramble` and `generate` from `seeds` instead of at random.
+    pub fn with_seeds(self, seeds: SeedSource) -> Self {
+        Self { seeds, ..self }
+    }
+
+    /// Every solver, whether it's in use or not, in order.
+    pub fn solver_ids(&self) -> Vec<SolverId> {
+        let mut ids = self
+            .challenge_solvers
+            .values()
+            .chain(&self.standby)
+            .map(|solver| SolverId::of(solver.as_ref()))
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Load every solver from the plugin at `path`. See [`PluginSolver`] for how to write one.
+    ///
+    /// A plugin's solver is used for its challenge if nothing else solves it yet, and otherwise
+    /// waits until its namespace is [selected](Self::select). Returns every solver it had.
+    #[cfg(feature = "plugins")]
+    pub fn load_plugin(&mut self, path: &Path) -> Result<Vec<SolverId>, SolveError> {
+        plugin::load(path)?
+            .into_iter()
+            .map(|solver| self.add(Box::new(solver)))
+            .collect()
+    }
+
+    /// Add a solver from outside this crate, which is used if there isn't one for its challenge
+    /// yet, and put on standby otherwise.
+    #[cfg(feature = "plugins")]
+    fn add(&mut self, solver: DynamicChallengeSolver) -> Result<SolverId, SolveError> {
+        use std::collections::hash_map::Entry;
+
+        let id = SolverId::of(solver.as_ref());
+        if self.solver_ids().contains(&id) {
+            return Err(SolveError::DuplicateSolver(id));
+        }
+        match self.challenge_solvers.entry(id.challenge) {
+            Entry::Vacant(entry) => {
+                entry.insert(solver);
+            }
+            Entry::Occupied(_) => self.standby.push(solver),
+        }
+        Ok(id)
+    }
+
+    /// Use the solvers from `namespace` for every challenge they solve, instead of whichever were
+    /// in use. Returns the challenges that it solves.
+    pub fn select(&mut self, namespace: &str) -> Result<Vec<ChallengeNumber>, SolveError> {
+        let (chosen, standby): (Vec<_>, Vec<_>) = std::mem::take(&mut self.standby)
+            .into_iter()
+            .partition(|solver| solver.namespace() == namespace);
+        self.standby = standby;
+
+        for solver in chosen {
+            let challenge = solver.challenge_number();
+            if let Some(previous) = self.challenge_solvers.insert(challenge, solver) {
+                self.standby.push(previous);
+            }
+        }
+
+        let mut challenges = self
+            .challenge_solvers
+            .values()
+            .filter(|solver| solver.namespace() == namespace)
+            .map(|solver| solver.challenge_number())
+            .collect::<Vec<_>>();
+        if challenges.is_empty() {
+            let mut known = self
+                .solver_ids()
+                .into_iter()
+                .map(|id| id.namespace)
+                .collect::<Vec<_>>();
+            known.dedup();
+            return Err(SolveError::UnknownNamespace {
+                namespace: namespace.to_string(),
+                known: known.join(", "),
+            });
+        }
+        challenges.sort_unstable();
+        Ok(challenges)
+    }
+
+    /// Every challenge that there's a solver for, in order.
+    pub fn challenges(&self) -> Vec<ChallengeNumber> {
+        let mut challenges = self.challenge_solvers.keys().copied().collect::<Vec<_>>();
+        challenges.sort_unstable();
+        challenges
+    }
+
+    /// Solve a challenge.
+    ///
+    /// If `config` has a timeout, the solver runs on a worker thread and [`SolveError::TimedOut`]
+    /// is returned if it doesn't finish in time. Time is measured with `config`'s clock.
     pub fn solve(
         &mut self,
         challenge: ChallengeNumber,
         subchallenge: Subchallenge,
-        mut input: io::BufReader<fs::File>,
-    ) -> Result<Box<dyn Any>, SolveError> {
-        if let Some(solver) = self.challenge_solvers.get_mut(&challenge) {
-            match subchallenge {
-                Subchallenge::A => Ok(solver.solve_a(&mut input)?),
-                Subchallenge::B => Ok(solver.solve_b(&mut input)?),
+        mut input: challenge::ChallengeInput,
+        config: &SolverConfig,
+    ) -> Result<Answer, SolveError> {
+        let Some(timeout) = config.timeout else {
+            let solver = self
+                .challenge_solvers
+                .get_mut(&challenge)
+                .ok_or(SolveError::NoSolverLoaded(challenge))?;
+            return run_solver(solver.as_mut(), subchallenge, &mut input, config);
+        };
+
+        // The solver is moved onto the worker thread, and sent back along with its result. If it
+        // never finishes, it's left running in the background, and a new one takes its place.
+        let mut solver = self
+            .challenge_solvers
+            .remove(&challenge)
+            .ok_or(SolveError::NoSolverLoaded(challenge))?;
+        let id = SolverId::of(solver.as_ref());
+        let worker_config = config.clone();
+        let (tx, rx) = mpsc::channel();
+        let deadline = config.clock.now().checked_add(timeout);
+
+        let worker = thread::Builder::new()
+            .name(format!("solver{challenge:02}"))
+            .spawn(move || {
+                let result = run_solver(solver.as_mut(), subchallenge, &mut input, &worker_config);
+                // Let go of the input before handing the result back, so that anything keeping
+                // track of it (like `--trace-io`) knows the solver is done with it.
+                drop(input);
+                // Nobody is listening anymore if we took too long, which is fine.
+                let _ = tx.send((solver, result));
+            })
+            .context("Could not spawn solver thread")?;
+
+        match config.clock.recv_until(&rx, deadline) {
+            Ok((solver, result)) => {
+                self.challenge_solvers.insert(challenge, solver);
+                result
+            }
+
+            Err(RecvTimeoutError::Timeout) => {
+                config.cancel.cancel();
+
+                // Give solvers that check for cancellation a moment to stop and report their
+                // progress, so they can be put back for next time. If one doesn't stop in time,
+                // it's rebuilt from scratch instead, which only works for this crate's own
+                // solvers; anything else, like a plugin, is lost.
+                let grace_deadline = deadline.and_then(|d| d.checked_add(CANCEL_GRACE_PERIOD));
+                let solver = config
+                    .clock
+                    .recv_until(&rx, grace_deadline)
+                    .map(|(solver, _)| solver)
+                    .ok()
+                    .or_else(|| {
+                        builtin_solvers()
+                            .into_iter()
+                            .find(|solver| SolverId::of(solver.as_ref()) == id)
+                    });
+                if let Some(solver) = solver {
+                    self.challenge_solvers.insert(challenge, solver);
+                }
+
+                Err(SolveError::TimedOut {
+                    after: timeout,
+                    progress: config.cancel.progress().into(),
+                })
             }
-        } else {
-            Err(SolveError::NoSolverLoaded(challenge))
+
+            // The worker only hangs up without sending anything if the solver panicked.
+            Err(RecvTimeoutError::Disconnected) => match worker.join() {
+                Err(panic) => std::panic::resume_unwind(panic),
+                Ok(()) => unreachable!("solver thread exited without sending a result"),
+            },
         }
     }
+
+    /// Solve a subchallenge on each of several `inputs`, each given with a label for where it
+    /// came from, to compare the answers.
+    ///
+    /// Anything the solver prints is hidden while it runs. If the solver times out and can't be
+    /// replaced, like a plugin's, the inputs after that are [skipped](SolveError::Skipped).
+    pub fn compare_inputs(
+        &mut self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        inputs: Vec<(String, challenge::ChallengeInput)>,
+        config: &SolverConfig,
+    ) -> ComparisonReport {
+        // The label of the input that the solver timed out on and was lost with, if it was.
+        let mut lost_on: Option<String> = None;
+        let results = inputs
+            .into_iter()
+            .map(|(label, input)| {
+                // Each input gets its own cancellation, so one timing out doesn't cancel the rest.
+                let config = SolverConfig {
+                    cancel: CancellationToken::new(),
+                    ..config.clone()
+                };
+                let _quiet = (gag::Gag::stdout().ok(), gag::Gag::stderr().ok());
+                let start = config.clock.now();
+                let answer = match &lost_on {
+                    Some(timed_out_on) => Err(SolveError::Skipped {
+                        challenge,
+                        timed_out_on: timed_out_on.clone(),
+                    }),
+                    None => self.solve(challenge, subchallenge, input, &config),
+                };
+                if matches!(answer, Err(SolveError::TimedOut { .. }))
+                    && !self.challenge_solvers.contains_key(&challenge)
+                {
+                    lost_on = Some(label.clone());
+                }
+                LabeledResult {
+                    label,
+                    answer,
+                    elapsed: config.clock.since(start),
+                }
+            })
+            .collect();
+
+        ComparisonReport { results }
+    }
+
+    /// Solve a subchallenge with two of its solver's algorithms in turn, on the same input, to
+    /// check that they agree.
+    ///
+    /// Every step that the solver streams is recorded instead of going to `config.events`, so that
+    /// the first step where the algorithms go different ways can be found. Anything the solver
+    /// prints is hidden while it runs.
+    pub fn compare_algorithms(
+        &mut self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        mut input: challenge::ChallengeInput,
+        algorithms: [String; 2],
+        config: &SolverConfig,
+    ) -> Result<AlgorithmComparison, SolveError> {
+        let solver = self
+            .challenge_solvers
+            .get(&challenge)
+            .ok_or(SolveError::NoSolverLoaded(challenge))?;
+        let known = solver.algorithms();
+        if known.len() < 2 {
+            return Err(SolveError::NoAlgorithms(challenge));
+        }
+        if let Some(unknown) = algorithms.iter().find(|a| !known.contains(&a.as_str())) {
+            return Err(SolveError::UnknownAlgorithm {
+                challenge,
+                algorithm: unknown.clone(),
+                known: known.join(", "),
+            });
+        }
+
+        let mut input_buf = String::new();
+        io::Read::read_to_string(&mut input, &mut input_buf)
+            .context("Could not read input file to string")?;
+
+        let runs = algorithms.map(|algorithm| {
+            // One algorithm timing out shouldn't cancel the other.
+            let mut config = SolverConfig {
+                cancel: CancellationToken::new(),
+                ..config.clone()
+            };
+            config.params.insert("algorithm", &algorithm);
+            let recording;
+            (config.events, recording) = EventStream::recording();
+
+            let _quiet = (gag::Gag::stdout().ok(), gag::Gag::stderr().ok());
+            let input = Box::new(io::Cursor::new(input_buf.clone()));
+            let start = config.clock.now();
+            let answer = self.solve(challenge, subchallenge, input, &config);
+            AlgorithmRun {
+                algorithm,
+                answer,
+                elapsed: config.clock.since(start),
+                steps: recording.events(),
+            }
+        });
+
+        Ok(AlgorithmComparison { runs })
+    }
+
+    /// Run every solver against its sample, and check its answers. Subchallenges without a sample
+    /// or an answer to check are [skipped](Outcome::Skip).
+    ///
+    /// Anything the solvers print is hidden while they run.
+    pub fn self_test(&mut self) -> SelfTestReport {
+        let results =
+            self.challenges()
+                .into_iter()
+                .map(|challenge| {
+                    let solver = self.challenge_solvers.get_mut(&challenge).unwrap();
+                    let input = solver.sample_input();
+                    let [expected_a, expected_b] = solver.sample_answers();
+                    let config = solver.sample_config();
+
+                    let outcomes = [(Subchallenge::A, expected_a), (Subchallenge::B, expected_b)]
+                        .map(|(subchallenge, expected)| {
+                            let (Some(input), Some(expected)) = (input, expected) else {
+                                return Outcome::Skip;
+                            };
+                            let _quiet = (gag::Gag::stdout().ok(), gag::Gag::stderr().ok());
+                            let mut input = io::Cursor::new(input);
+                            match run_solver(solver.as_mut(), subchallenge, &mut input, &config) {
+                                Ok(got) if got == expected => Outcome::Pass,
+                                Ok(got) => Outcome::Fail { expected, got },
+                                Err(e) => Outcome::Error(e),
+                            }
+                        });
+
+                    SampleResult {
+                        challenge,
+                        outcomes,
+                    }
+                })
+                .collect();
+
+        SelfTestReport { results }
+    }
+
+    /// Draw what every solver that can draw itself ends up with on its sample, and save the
+    /// pictures into `dir`, creating it if it doesn't exist.
+    ///
+    /// Anything the solvers print is hidden while they run.
+    pub fn gallery(&mut self, dir: &Path) -> Result<GalleryReport, SolveError> {
+        fs::create_dir_all(dir).with_context(|| format!("Could not create {}", dir.display()))?;
+
+        let mut results = Vec::new();
+        for challenge in self.challenges() {
+            let solver = self.challenge_solvers.get_mut(&challenge).unwrap();
+            let Some(input) = solver.sample_input() else {
+                continue;
+            };
+
+            let rendered = {
+                let _quiet = (gag::Gag::stdout().ok(), gag::Gag::stderr().ok());
+                solver
+                    .configure(&solver.sample_config())
+                    .map_err(SolveError::from)
+                    .and_then(|()| Ok(solver.gallery(input).transpose()?))
+            };
+            let outcome = match rendered {
+                Ok(Some(renderings)) => renderings
+                    .iter()
+                    .map(|rendering| rendering.save(dir, challenge))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_or_else(|e| GalleryOutcome::Error(e.into()), GalleryOutcome::Saved),
+                Ok(None) => continue,
+                Err(e) => GalleryOutcome::Error(e),
+            };
+            results.push(GalleryResult { challenge, outcome });
+        }
+
+        Ok(GalleryReport { results })
+    }
+
+    /// Time every solver, `runs` times each, on its full puzzle input from `input_dir` if there is
+    /// one and on its sample otherwise.
+    ///
+    /// Anything the solvers print is hidden while they run, and how far they've got is shown with
+    /// `progress` instead.
+    pub fn bench(
+        &mut self,
+        input_dir: &Path,
+        runs: usize,
+        checks: InputChecks,
+        progress: &mut dyn ProgressSink,
+    ) -> BenchReport {
+        let challenges = self.challenges();
+        let clock = &self.clock;
+        progress.plan(
+            &challenges.iter().map(|&c| (c, 2)).collect::<Vec<_>>(),
+            clock,
+        );
+        let mut results = Vec::new();
+        for challenge in challenges {
+            let solver = self.challenge_solvers.get_mut(&challenge).unwrap();
+            for subchallenge in [Subchallenge::A, Subchallenge::B] {
+                let real_input = challenge::get_challenge_input(
+                    input_dir,
+                    challenge,
+                    subchallenge,
+                    &None,
+                    checks,
+                )
+                .ok()
+                .and_then(|mut file| {
+                    let mut input = String::new();
+                    io::Read::read_to_string(&mut file, &mut input).ok()?;
+                    Some(input)
+                });
+                let (input, kind, config) = match real_input {
+                    Some(input) => {
+                        let config = SolverConfig {
+                            params: solver.preset(Preset::Real),
+                            clock: clock.clone(),
+                            ..SolverConfig::default()
+                        };
+                        (input, InputKind::Real, config)
+                    }
+                    None => match solver.sample_input() {
+                        Some(input) => {
+                            let config = SolverConfig {
+                                clock: clock.clone(),
+                                ..solver.sample_config()
+                            };
+                            (input.to_string(), InputKind::Sample, config)
+                        }
+                        None => {
+                            progress.start(challenge, subchallenge, &CancellationToken::new());
+                            progress.finish(RunStatus::Skipped, Duration::ZERO);
+                            continue;
+                        }
+                    },
+                };
+
+                progress.start(challenge, subchallenge, &config.cancel);
+                let started = clock.now();
+                let times = (0..runs.max(1))
+                    .map(|_| {
+                        let _quiet = (gag::Gag::stdout().ok(), gag::Gag::stderr().ok());
+                        let mut input = io::Cursor::new(input.as_str());
+                        let start = clock.now();
+                        run_solver(solver.as_mut(), subchallenge, &mut input, &config)?;
+                        Ok(clock.since(start))
+                    })
+                    .collect::<Result<Vec<_>, SolveError>>();
+                let status = match times {
+                    Ok(_) => RunStatus::Solved,
+                    Err(_) => RunStatus::Failed,
+                };
+                progress.finish(status, clock.since(started));
+
+                results.push(BenchResult {
+                    challenge,
+                    subchallenge,
+                    input: kind,
+                    median: times.map(bench::median),
+                });
+            }
+        }
+        progress.done();
+
+        BenchReport { results }
+    }
+
+    /// Solve both subchallenges of every challenge on its puzzle input from `input_dir`, timing
+    /// each one and checking its answer against `answers` if there are any.
+    ///
+    /// Anything the solvers print is hidden while they run, and how far they've got is shown with
+    /// `progress` instead.
+    pub fn run_all(
+        &mut self,
+        input_dir: &Path,
+        answers: Option<&KnownAnswers>,
+        checks: InputChecks,
+        progress: &mut dyn ProgressSink,
+    ) -> RunAllReport {
+        let challenges = self.challenges();
+        let clock = &self.clock;
+        progress.plan(
+            &challenges.iter().map(|&c| (c, 2)).collect::<Vec<_>>(),
+            clock,
+        );
+        let mut results = Vec::new();
+        for &challenge in &challenges {
+            let solver = self.challenge_solvers.get_mut(&challenge).unwrap();
+            for subchallenge in [Subchallenge::A, Subchallenge::B] {
+                use challenge::GetChallengeInputError::{DefaultInputFileLocationError, IoError};
+
+                let config = SolverConfig {
+                    params: solver.preset(Preset::Real),
+                    clock: clock.clone(),
+                    ..SolverConfig::default()
+                };
+                progress.start(challenge, subchallenge, &config.cancel);
+                let input = challenge::get_challenge_input(
+                    input_dir,
+                    challenge,
+                    subchallenge,
+                    &None,
+                    checks,
+                );
+                let outcome = match input {
+                    Err(DefaultInputFileLocationError { .. }) => RunOutcome::NoInput,
+                    // Without an `./input/` directory at all, every input is missing.
+                    Err(IoError(e)) if e.kind() == io::ErrorKind::NotFound => RunOutcome::NoInput,
+                    Err(e) => RunOutcome::Unreadable(e.to_string()),
+                    Ok(mut input) => {
+                        let _quiet = (gag::Gag::stdout().ok(), gag::Gag::stderr().ok());
+                        let start = clock.now();
+                        let answer = run_solver(solver.as_mut(), subchallenge, &mut input, &config);
+                        let time = clock.since(start);
+                        let expected = answers.and_then(|a| a.get(challenge, subchallenge));
+                        RunOutcome::Ran {
+                            time,
+                            check: Check::new(&answer, expected),
+                            answer,
+                        }
+                    }
+                };
+                let (status, elapsed) = match &outcome {
+                    RunOutcome::NoInput => (RunStatus::Skipped, Duration::ZERO),
+                    RunOutcome::Unreadable(_) => (RunStatus::Failed, Duration::ZERO),
+                    RunOutcome::Ran { time, answer, .. } => match answer {
+                        Ok(_) => (RunStatus::Solved, *time),
+                        Err(_) => (RunStatus::Failed, *time),
+                    },
+                };
+                progress.finish(status, elapsed);
+                results.push(RunResult {
+                    challenge,
+                    subchallenge,
+                    outcome,
+                });
+            }
+        }
+        progress.done();
+
+        RunAllReport {
+            results,
+            missing_solvers: run_all::missing_solvers(&challenges),
+            checked: answers.is_some(),
+        }
+    }
+
+    /// Check that `input` looks like a valid input for a challenge, without solving it.
+    pub fn validate(
+        &self,
+        challenge: ChallengeNumber,
+        mut input: challenge::ChallengeInput,
+    ) -> Result<ValidationReport, SolveError> {
+        let mut input_buf = String::new();
+        io::Read::read_to_string(&mut input, &mut input_buf)
+            .context("Could not read input file to string")?;
+
+        self.validate_str(challenge, &input_buf)
+    }
+
+    /// Like [`Solver::validate`], but for an input that's already been read into memory.
+    pub fn validate_str(
+        &self,
+        challenge: ChallengeNumber,
+        input: &str,
+    ) -> Result<ValidationReport, SolveError> {
+        let solver = self
+            .challenge_solvers
+            .get(&challenge)
+            .ok_or(SolveError::NoSolverLoaded(challenge))?;
+
+        Ok(ValidationReport::new(input, solver.validate(input)))
+    }
+
+    /// The parameters that a challenge's solver uses for a kind of input.
+    ///
+    /// Any parameters given on top of these should replace them.
+    pub fn preset(
+        &self,
+        challenge: ChallengeNumber,
+        preset: Preset,
+    ) -> Result<SolverParams, SolveError> {
+        let solver = self
+            .challenge_solvers
+            .get(&challenge)
+            .ok_or(SolveError::NoSolverLoaded(challenge))?;
+
+        Ok(solver.preset(preset))
+    }
+
+    /// The example input from the puzzle description for `challenge`, for `--sample`.
+    pub fn sample_input(&self, challenge: ChallengeNumber) -> Result<&'static str, SolveError> {
+        self.challenge_solvers
+            .get(&challenge)
+            .ok_or(SolveError::NoSolverLoaded(challenge))?
+            .sample_input()
+            .ok_or(SolveError::NoSample(challenge))
+    }
+
+    /// What the solver for `challenge` should answer for [`Solver::sample_input`], if it says.
+    pub fn sample_answer(
+        &self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+    ) -> Option<Answer> {
+        let [a, b] = self.challenge_solvers.get(&challenge)?.sample_answers();
+        match subchallenge {
+            Subchallenge::A => a,
+            Subchallenge::B => b,
+        }
+    }
+
+    /// Make a randomized copy of a challenge input, using the given seed or else a new one.
+    ///
+    /// Returns the scrambled input along with the seed that made it.
+    pub fn scramble(
+        &self,
+        challenge: ChallengeNumber,
+        mut input: challenge::ChallengeInput,
+        seed: Option<u64>,
+    ) -> Result<(String, u64), SolveError> {
+        let solver = self
+            .challenge_solvers
+            .get(&challenge)
+            .ok_or(SolveError::NoSolverLoaded(challenge))?;
+
+        let mut input_buf = String::new();
+        io::Read::read_to_string(&mut input, &mut input_buf)
+            .context("Could not read input file to string")?;
+
+        let seed = seed.unwrap_or_else(|| self.seeds.seed());
+        let scrambled = solver
+            .scramble(&input_buf, &mut Scrambler::new(seed))
+            .ok_or(SolveError::NoScrambler(challenge))??;
+        Ok((scrambled, seed))
+    }
+
+    /// Make up an input for a challenge with about `size` things in it, using the given seed or
+    /// else a new one.
+    ///
+    /// Returns the input along with the seed that made it.
+    pub fn generate(
+        &self,
+        challenge: ChallengeNumber,
+        size: usize,
+        seed: Option<u64>,
+    ) -> Result<(String, u64), SolveError> {
+        let solver = self
+            .challenge_solvers
+            .get(&challenge)
+            .ok_or(SolveError::NoSolverLoaded(challenge))?;
+
+        let seed = seed.unwrap_or_else(|| self.seeds.seed());
+        let generated = solver
+            .generate(size, &mut Scrambler::new(seed))
+            .ok_or(SolveError::NoGenerator(challenge))??;
+        Ok((generated, seed))
+    }
+}
+
+/// How long a timed-out solver gets to notice that it's been cancelled, counting from when it ran
+/// out of time.
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// A new one of every solver in this crate.
+fn builtin_solvers() -> Vec<DynamicChallengeSolver> {
+    macro_rules! build_solver_list {
+        [$($solver_ty:ty),* $(,)?] => {
+            vec![
+                $(
+                    Box::<$solver_ty>::default(),
+                )*
+            ]
+        };
+    }
+
+    build_solver_list![
+        solver01::Solver01,
+        solver02::Solver02,
+        solver03::Solver03,
+        solver04::Solver04,
+        solver05::Solver05,
+        solver06::Solver06,
+        solver07::Solver07,
+        solver08::Solver08,
+        solver09::Solver09,
+        solver10::Solver10,
+        solver11::Solver11,
+        solver12::Solver12,
+        solver13::Solver13,
+        solver14::Solver14,
+        solver15::Solver15,
+        solver16::Solver16,
+        solver17::Solver17,
+        solver18::Solver18,
+        solver19::Solver19,
+        solver20::Solver20,
+        solver21::Solver21,
+        solver22::Solver22,
+    ]
+}
+
+fn run_solver(
+    solver: &mut dyn ChallengeSolver,
+    subchallenge: Subchallenge,
+    input: &mut dyn io::BufRead,
+    config: &SolverConfig,
+) -> Result<Answer, SolveError> {
+    // Solvers ignore `--visualize` when their UI isn't built, so just let people know why.
+    if let Some(frontend) = solver
+        .frontend()
+        .filter(|f| config.visualize && !f.is_enabled())
+    {
+        eprintln!(
+            "Warning: `--visualize` needs the `{}` feature, which this binary was built without",
+            frontend.feature()
+        );
+    }
+    if !config.transforms.is_empty() && !solver.transformable() {
+        return Err(SolveError::NoTransforms(solver.challenge_number()));
+    }
+    crate::color::set(config.color);
+    solver.configure(config)?;
+    let answer = match subchallenge {
+        Subchallenge::A => solver.solve_a(input)?,
+        Subchallenge::B => solver.solve_b(input)?,
+    };
+    match &config.post {
+        Some(post) => Ok(post.apply(&answer)?),
+        None => Ok(answer),
+    }
 }
 
 impl Default for Solver {
@@ -130,6 +1008,64 @@ pub enum SolveError {
     #[error("No solver loaded for challenge {0}.")]
     NoSolverLoaded(ChallengeNumber),
 
+    #[error("The solver for challenge {0} doesn't know how to scramble its input.")]
+    NoScrambler(ChallengeNumber),
+
+    #[error("The solver for challenge {0} doesn't know how to make up an input.")]
+    NoGenerator(ChallengeNumber),
+
+    #[error("The solver for challenge {0} has no sample input.")]
+    NoSample(ChallengeNumber),
+
+    #[error("The solver for challenge {0} doesn't know how to transform its input.")]
+    NoTransforms(ChallengeNumber),
+
+    #[error("The solver for challenge {0} only has one algorithm, so there's nothing to compare.")]
+    NoAlgorithms(ChallengeNumber),
+
+    #[error(
+        "The solver for challenge {challenge} has no algorithm called `{algorithm}`. \
+         Its algorithms are: {known}."
+    )]
+    UnknownAlgorithm {
+        challenge: ChallengeNumber,
+        algorithm: String,
+        known: String,
+    },
+
+    #[error("There's already a solver called {0}.")]
+    DuplicateSolver(SolverId),
+
+    #[error(
+        "There are no solvers in a namespace called `{namespace}`. The namespaces are: {known}."
+    )]
+    UnknownNamespace { namespace: String, known: String },
+
+    #[cfg(feature = "plugins")]
+    #[error(transparent)]
+    Plugin(#[from] PluginError),
+
+    #[error(transparent)]
+    SolverExecutionError(#[from] SolverError),
+
     #[error(transparent)]
-    SolverExecutionError(#[from] color_eyre::Report),
+    BadParam(#[from] ParamError),
+
+    #[error(transparent)]
+    Post(#[from] PostError),
+
+    #[error("Timed out after {after:?}. Progress so far: {progress}")]
+    TimedOut {
+        after: Duration,
+        progress: serde_json::Value,
+    },
+
+    #[error(
+        "Skipped, since the solver for challenge {challenge} never came back from timing out on \
+         `{timed_out_on}`."
+    )]
+    Skipped {
+        challenge: ChallengeNumber,
+        timed_out_on: String,
+    },
 }
@@ -0,0 +1,192 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    challenge::ChallengeNumber,
+    error::{Context, SolverError},
+};
+
+use super::checkpoint::{fingerprint, write_atomically};
+
+/// Where parsed inputs are cached, relative to the working directory.
+pub const PARSE_CACHE_DIR: &str = ".parse-cache";
+
+/// Bumped whenever the layout of a cache file changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// A solver whose parsing stage is slow enough that its result is worth keeping between runs,
+/// like solving part A and then part B of the same input.
+pub(crate) trait CacheableParse {
+    /// What the input gets parsed into.
+    type Parsed: Serialize + DeserializeOwned;
+
+    /// Bumped whenever [`CacheableParse::Parsed`] changes, so that what older versions cached
+    /// gets parsed again instead of misread.
+    const PARSED_VERSION: u32;
+
+    /// Parse the input, without looking in the cache.
+    fn parse(input: &str) -> Result<Self::Parsed, SolverError>;
+}
+
+/// Keeps what solvers parsed out of an input on disk, so that running another subchallenge on
+/// the same input can skip straight to solving.
+///
+/// The default cache is turned off, and parses every time. Entries are keyed by a fingerprint of
+/// the input, so changing the input file is always a cache miss. Only the latest input for each
+/// challenge is kept.
+#[derive(Debug, Clone, Default)]
+pub struct ParseCache {
+    /// Where to keep cached inputs, or `None` if caching is turned off.
+    dir: Option<PathBuf>,
+    /// Whether to print to stderr if the cache had the input.
+    verbose: bool,
+}
+
+impl ParseCache {
+    /// Keep cached inputs in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            verbose: false,
+        }
+    }
+
+    /// Print whether the cache had each input to stderr, or not.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// The directory that cached inputs are kept in, if caching is turned on.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Parse `input` with `P`, or load what was parsed from the very same input last time.
+    ///
+    /// A cache entry that can't be read is parsed again, and one that can't be written is only
+    /// warned about, since the cache is never needed to get an answer.
+    pub(crate) fn parse<P: CacheableParse>(
+        &self,
+        challenge: ChallengeNumber,
+        input: &str,
+    ) -> Result<P::Parsed, SolverError> {
+        let Some(dir) = &self.dir else {
+            return P::parse(input);
+        };
+        let key = fingerprint((input, P::PARSED_VERSION));
+        let path = dir.join(format!("{challenge:02}-{key:016x}.bin"));
+
+        if let Some(parsed) = load(&path, key) {
+            if self.verbose {
+                eprintln!("parse cache: hit, loaded {}", path.display());
+            }
+            return Ok(parsed);
+        }
+
+        let parsed = P::parse(input)?;
+        match save(dir, challenge, &path, key, &parsed) {
+            Ok(()) if self.verbose => eprintln!("parse cache: miss, saved {}", path.display()),
+            Ok(()) => {}
+            Err(e) => eprintln!("Warning: {e}"),
+        }
+        Ok(parsed)
+    }
+}
+
+/// Load a cache entry, if there is one and it was saved for `key` by this version.
+fn load<T: DeserializeOwned>(path: &Path, key: u64) -> Option<T> {
+    let file = fs::File::open(path).ok()?;
+    let (version, saved_key, parsed): (u32, u64, T) =
+        bincode::deserialize_from(io::BufReader::new(file)).ok()?;
+    (version == FORMAT_VERSION && saved_key == key).then_some(parsed)
+}
+
+/// Save a cache entry, replacing whatever was cached for an older input to the same challenge.
+fn save<T: Serialize>(
+    dir: &Path,
+    challenge: ChallengeNumber,
+    path: &Path,
+    key: u64,
+    parsed: &T,
+) -> Result<(), SolverError> {
+    let context = || format!("Could not cache parsed input to {}", path.display());
+    fs::create_dir_all(dir).with_context(context)?;
+
+    let prefix = format!("{challenge:02}-");
+    for entry in fs::read_dir(dir).with_context(context)? {
+        let old = entry.with_context(context)?.path();
+        let is_stale = old
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&prefix));
+        if is_stale {
+            fs::remove_file(&old).with_context(context)?;
+        }
+    }
+
+    // Another run must never read half of an entry.
+    write_atomically(path, &(FORMAT_VERSION, key, parsed)).with_context(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    thread_local! {
+        static PARSES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct Numbers;
+
+    impl CacheableParse for Numbers {
+        type Parsed = Vec<u64>;
+        const PARSED_VERSION: u32 = 1;
+
+        fn parse(input: &str) -> Result<Self::Parsed, SolverError> {
+            PARSES.with(|parses| parses.set(parses.get() + 1));
+            Ok(crate::parse::parse_lines(
+                input,
+                nom::character::complete::u64,
+            )?)
+        }
+    }
+
+    #[test]
+    fn reuses_parsed_input_until_it_changes() -> Result<(), SolverError> {
+        let dir = std::env::temp_dir().join(format!("aoc2022-parse-cache-{}", std::process::id()));
+        let cache = ParseCache::new(&dir);
+        let parses = || PARSES.with(Cell::get);
+
+        assert_eq!(cache.parse::<Numbers>(1, "1\n2\n")?, vec![1, 2]);
+        assert_eq!(cache.parse::<Numbers>(1, "1\n2\n")?, vec![1, 2]);
+        assert_eq!(parses(), 1, "the second parse should come from the cache");
+
+        assert_eq!(cache.parse::<Numbers>(1, "3\n")?, vec![3]);
+        assert_eq!(parses(), 2);
+        assert_eq!(
+            fs::read_dir(&dir).context("Could not list cache")?.count(),
+            1,
+            "the entry for the old input should be gone"
+        );
+
+        // A corrupted entry is parsed again.
+        let entry = fs::read_dir(&dir)
+            .context("Could not list cache")?
+            .next()
+            .unwrap()
+            .context("Could not list cache")?;
+        fs::write(entry.path(), b"garbage").context("Could not corrupt cache")?;
+        assert_eq!(cache.parse::<Numbers>(1, "3\n")?, vec![3]);
+        assert_eq!(parses(), 3);
+
+        fs::remove_dir_all(dir).context("Could not clean up")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,397 @@
+//! Loading extra solvers at runtime from dynamic libraries, for `--plugin`.
+//!
+//! A plugin is a dynamic library (a `cdylib`, in Rust terms) that exports two C functions. The
+//! first, `plugin_abi_version`, with the signature of [`AbiVersionFn`], says which version of the
+//! interface it was built against, and is checked before anything else in the plugin is touched.
+//! The second, `register_solvers`, with the signature of [`RegisterSolvers`], is handed a callback
+//! to call once for every solver it has, with a [`PluginSolver`] describing it. Each of those
+//! solves one challenge: it gets the whole input, and hands back the answer as text, or an error
+//! message.
+//!
+//! Solvers from a plugin are put in a namespace named after the library's file, so a solver for
+//! day 17 in `libfaster.so` is `faster:17`, and never clashes with the built-in `builtin:17`.
+//! `examples/plugin.rs` is a whole plugin, which shows how one is put together.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use libloading::Library;
+
+use crate::{
+    challenge::{ChallengeNumber, Subchallenge},
+    error::SolverError,
+};
+
+use super::{Answer, ChallengeSolver, ChallengeSolverResult};
+
+/// The version of the plugin interface that this build understands, which plugins hand back from
+/// their [`AbiVersionFn`]. Bumped whenever the exported functions, [`PluginSolver`] or [`SolveFn`]
+/// change.
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+
+/// The name of the function that every plugin exports to say which interface it was built for.
+const VERSION_SYMBOL: &[u8] = b"plugin_abi_version\0";
+
+/// The name of the function that every plugin exports to register its solvers.
+const ENTRY_POINT: &[u8] = b"register_solvers\0";
+
+/// The function that every plugin exports as `plugin_abi_version`, which returns the
+/// [`PLUGIN_ABI_VERSION`] it was built against.
+///
+/// Only takes and returns plain values, so that it can be called safely whatever version the
+/// plugin was built for.
+pub type AbiVersionFn = unsafe extern "C" fn() -> u32;
+
+/// The function that every plugin exports as `register_solvers`.
+///
+/// It should call `register` once for each of its solvers, passing along `registrar` as it is.
+pub type RegisterSolvers = unsafe extern "C" fn(registrar: *mut c_void, register: RegisterFn);
+
+/// The callback that a plugin's [`RegisterSolvers`] calls for each of its solvers.
+pub type RegisterFn = unsafe extern "C" fn(registrar: *mut c_void, solver: PluginSolver);
+
+/// Solves one subchallenge, given as `b'a'` or `b'b'`, on the `input_len` bytes of UTF-8 at
+/// `input`.
+///
+/// Returns 0 and points `output` at the answer on success, or returns anything else and points
+/// `output` at an error message. Either way, `output` is a NUL-terminated UTF-8 string that gets
+/// handed back to [`PluginSolver::free`] once it's been read.
+pub type SolveFn = unsafe extern "C" fn(
+    subchallenge: u8,
+    input: *const u8,
+    input_len: usize,
+    output: *mut *mut c_char,
+) -> c_int;
+
+/// Frees a string that a [`SolveFn`] handed out.
+pub type FreeFn = unsafe extern "C" fn(string: *mut c_char);
+
+/// One of a plugin's solvers, as it describes itself to [`RegisterFn`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PluginSolver {
+    /// Always [`PLUGIN_ABI_VERSION`]. Plugins built for another version are already turned away
+    /// by their [`AbiVersionFn`] before they get this far, so this is only checked again to be
+    /// sure.
+    pub abi_version: u32,
+    /// The challenge that it solves.
+    pub challenge: u8,
+    pub solve: SolveFn,
+    pub free: FreeFn,
+}
+
+/// Why a plugin couldn't be loaded.
+#[derive(Debug, thiserror::Error)]
+#[error("Could not load the plugin at {}: {reason}", path.display())]
+pub struct PluginError {
+    path: PathBuf,
+    reason: String,
+}
+
+/// A solver from a plugin, along with the library it lives in, which has to stay loaded for as
+/// long as the solver is around.
+pub(super) struct LoadedSolver {
+    namespace: String,
+    solver: PluginSolver,
+    _library: Option<Arc<Library>>,
+}
+
+/// Load every solver from the plugin at `path`, namespaced by its file name.
+pub(super) fn load(path: &Path) -> Result<Vec<LoadedSolver>, PluginError> {
+    let error = |reason: String| PluginError {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let namespace = namespace(path).ok_or_else(|| error("it has no file name".to_string()))?;
+    // SAFETY: Loading a library runs its initializers, which can do anything. Plugins are trusted
+    // as much as the binary itself is, since the user asked for them by name.
+    let library = unsafe { Library::new(path) }.map_err(|e| error(e.to_string()))?;
+
+    // SAFETY: The version function is declared with the signature in the plugin interface, which
+    // hasn't changed between versions.
+    let version = unsafe { library.get::<AbiVersionFn>(VERSION_SYMBOL) }
+        .map_err(|e| error(format!("it doesn't export `plugin_abi_version` ({e})")))?;
+    // SAFETY: `version` takes nothing and returns a plain number, and stays loaded for the call.
+    check_abi_version(unsafe { version() }).map_err(error)?;
+
+    // SAFETY: The entry point is declared with the signature in the plugin interface.
+    let entry = unsafe { library.get::<RegisterSolvers>(ENTRY_POINT) }
+        .map_err(|e| error(format!("it doesn't export `register_solvers` ({e})")))?;
+
+    // SAFETY: `entry` is the plugin's `register_solvers`, which stays loaded until `library` is
+    // dropped, after the call.
+    let solvers = unsafe { register(*entry) };
+    let library = Arc::new(library);
+
+    solvers
+        .into_iter()
+        .map(|solver| {
+            if solver.abi_version != PLUGIN_ABI_VERSION {
+                return Err(error(format!(
+                    "its solver for challenge {} was built for version {} of the plugin \
+                     interface, but this is version {PLUGIN_ABI_VERSION}",
+                    solver.challenge, solver.abi_version
+                )));
+            }
+            Ok(LoadedSolver {
+                namespace: namespace.clone(),
+                solver,
+                _library: Some(Arc::clone(&library)),
+            })
+        })
+        .collect()
+}
+
+/// Turn away a plugin built against `version` of the interface, unless it's this one.
+fn check_abi_version(version: u32) -> Result<(), String> {
+    if version == PLUGIN_ABI_VERSION {
+        Ok(())
+    } else {
+        Err(format!(
+            "it was built for version {version} of the plugin interface, but this is version \
+             {PLUGIN_ABI_VERSION}"
+        ))
+    }
+}
+
+/// The namespace for a plugin's solvers: its file name, without any extension or `lib` prefix.
+fn namespace(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let stem = stem.strip_prefix("lib").unwrap_or(&stem);
+    (!stem.is_empty()).then(|| stem.to_string())
+}
+
+/// Call a plugin's `register_solvers`, and collect every solver it registers.
+///
+/// # Safety
+///
+/// `entry` has to follow the plugin interface.
+unsafe fn register(entry: RegisterSolvers) -> Vec<PluginSolver> {
+    unsafe extern "C" fn push(registrar: *mut c_void, solver: PluginSolver) {
+        // SAFETY: `registrar` is the `Vec` that `register` passed in, which outlives the call.
+        unsafe { (*registrar.cast::<Vec<PluginSolver>>()).push(solver) };
+    }
+
+    let mut solvers = Vec::<PluginSolver>::new();
+    // SAFETY: The caller promises that `entry` follows the interface, which only ever passes
+    // `registrar` back to `push`.
+    unsafe { entry((&mut solvers as *mut Vec<PluginSolver>).cast(), push) };
+    solvers
+}
+
+impl LoadedSolver {
+    fn solve(
+        &self,
+        subchallenge: Subchallenge,
+        input: &mut dyn io::BufRead,
+    ) -> ChallengeSolverResult {
+        let mut input_buf = String::new();
+        input.read_to_string(&mut input_buf)?;
+
+        let subchallenge = match subchallenge {
+            Subchallenge::A => b'a',
+            Subchallenge::B => b'b',
+        };
+        let mut output = std::ptr::null_mut();
+        // SAFETY: The input is valid for `input_len` bytes, and `output` for a write, for the
+        // whole call.
+        let status = unsafe {
+            (self.solver.solve)(
+                subchallenge,
+                input_buf.as_ptr(),
+                input_buf.len(),
+                &mut output,
+            )
+        };
+
+        if output.is_null() {
+            return Err(SolverError::Unsupported(format!(
+                "The plugin's solver for challenge {} didn't hand anything back",
+                self.solver.challenge
+            )));
+        }
+        // SAFETY: The plugin hands back a NUL-terminated string, which is only freed below.
+        let text = unsafe { CStr::from_ptr(output) }
+            .to_string_lossy()
+            .into_owned();
+        // SAFETY: `output` came from this plugin's `solve`, and isn't used again.
+        unsafe { (self.solver.free)(output) };
+
+        match status {
+            0 => Ok(Answer::new(text)),
+            _ => Err(SolverError::Unsupported(format!(
+                "The plugin's solver for challenge {} failed: {text}",
+                self.solver.challenge
+            ))),
+        }
+    }
+}
+
+impl fmt::Debug for LoadedSolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadedSolver")
+            .field("namespace", &self.namespace)
+            .field("challenge", &self.solver.challenge)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChallengeSolver for LoadedSolver {
+    fn challenge_number(&self) -> ChallengeNumber {
+        self.solver.challenge
+    }
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn solve_a(&mut self, input: &mut dyn io::BufRead) -> ChallengeSolverResult {
+        self.solve(Subchallenge::A, input)
+    }
+
+    fn solve_b(&mut self, input: &mut dyn io::BufRead) -> ChallengeSolverResult {
+        self.solve(Subchallenge::B, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CString, io::Cursor};
+
+    use super::*;
+    use crate::{
+        challenge::ChallengeInput,
+        solver::{SolveError, Solver, SolverConfig, SolverId, BUILTIN_NAMESPACE},
+    };
+
+    /// Shouts part A's input back, and turns part B away.
+    unsafe extern "C" fn shout(
+        subchallenge: u8,
+        input: *const u8,
+        input_len: usize,
+        output: *mut *mut c_char,
+    ) -> c_int {
+        let input = unsafe { std::slice::from_raw_parts(input, input_len) };
+        let (status, text) = match subchallenge {
+            b'a' => (0, String::from_utf8_lossy(input).to_uppercase()),
+            _ => (1, "no part B".to_string()),
+        };
+        unsafe { *output = CString::new(text).unwrap().into_raw() };
+        status
+    }
+
+    unsafe extern "C" fn free(string: *mut c_char) {
+        drop(unsafe { CString::from_raw(string) });
+    }
+
+    unsafe extern "C" fn register_solvers(registrar: *mut c_void, register: RegisterFn) {
+        for (abi_version, challenge) in [(PLUGIN_ABI_VERSION, 1), (PLUGIN_ABI_VERSION + 1, 2)] {
+            let solver = PluginSolver {
+                abi_version,
+                challenge,
+                solve: shout,
+                free,
+            };
+            unsafe { register(registrar, solver) };
+        }
+    }
+
+    fn shouter(challenge: u8) -> LoadedSolver {
+        LoadedSolver {
+            namespace: "shout".to_string(),
+            solver: PluginSolver {
+                abi_version: PLUGIN_ABI_VERSION,
+                challenge,
+                solve: shout,
+                free,
+            },
+            _library: None,
+        }
+    }
+
+    #[test]
+    fn solves_with_whatever_the_plugin_registered() {
+        let solvers = unsafe { register(register_solvers) };
+        assert_eq!(solvers.len(), 2);
+        assert_eq!(solvers[0].challenge, 1);
+        assert_eq!(solvers[1].abi_version, PLUGIN_ABI_VERSION + 1);
+
+        let mut solver = shouter(1);
+        assert_eq!(solver.challenge_number(), 1);
+        assert_eq!(
+            solver.solve_a(&mut Cursor::new("hello")).unwrap(),
+            Answer::new("HELLO".to_string())
+        );
+        let err = solver.solve_b(&mut Cursor::new("hello")).unwrap_err();
+        assert!(err.to_string().ends_with("failed: no part B"), "{err}");
+    }
+
+    #[test]
+    fn turns_away_other_interface_versions() {
+        assert!(check_abi_version(PLUGIN_ABI_VERSION).is_ok());
+        let err = check_abi_version(PLUGIN_ABI_VERSION - 1).unwrap_err();
+        assert_eq!(
+            err,
+            format!(
+                "it was built for version {} of the plugin interface, but this is version {}",
+                PLUGIN_ABI_VERSION - 1,
+                PLUGIN_ABI_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn namespaces_drop_lib_prefix_and_extension() {
+        assert_eq!(
+            namespace(Path::new("plugins/libfaster.so")).unwrap(),
+            "faster"
+        );
+        assert_eq!(namespace(Path::new("faster.dll")).unwrap(), "faster");
+        assert_eq!(namespace(Path::new("lib.so")), None);
+        assert!(load(Path::new("no-such-plugin.so")).is_err());
+    }
+
+    #[test]
+    fn namespaces_keep_solvers_for_the_same_challenge_apart() {
+        let mut solver = Solver::new();
+        let builtin = solver.challenges();
+        let shout = |challenge| SolverId {
+            namespace: "shout".to_string(),
+            challenge,
+        };
+
+        // Challenge 1 already has a solver, but challenge 25 doesn't, so that one's used at once.
+        assert_eq!(solver.add(Box::new(shouter(1))).unwrap(), shout(1));
+        assert_eq!(solver.add(Box::new(shouter(25))).unwrap(), shout(25));
+        assert!(matches!(
+            solver.add(Box::new(shouter(1))),
+            Err(SolveError::DuplicateSolver(id)) if id == shout(1)
+        ));
+        assert_eq!(solver.solver_ids().len(), builtin.len() + 2);
+        assert!(solver.solver_ids().contains(&shout(1)));
+        assert_eq!(solver.challenges().len(), builtin.len() + 1);
+
+        let solve = |solver: &mut Solver| {
+            let input: ChallengeInput = Box::new(Cursor::new("hey"));
+            solver.solve(1, Subchallenge::A, input, &SolverConfig::default())
+        };
+        assert!(solve(&mut solver).is_err());
+
+        assert_eq!(solver.select("shout").unwrap(), [1, 25]);
+        assert_eq!(solve(&mut solver).unwrap(), Answer::new("HEY".to_string()));
+        assert_eq!(solver.select(BUILTIN_NAMESPACE).unwrap(), builtin);
+        assert!(solve(&mut solver).is_err());
+
+        let err = solver.select("whisper").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "There are no solvers in a namespace called `whisper`. \
+             The namespaces are: builtin, shout."
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Working something out from an answer after solving, for `--post`.
+
+use std::{fmt, str::FromStr};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, alphanumeric1, char, i64 as nom_i64, multispace0, one_of},
+    combinator::{all_consuming, map, recognize},
+    multi::{fold_many0, many0_count},
+    sequence::{delimited, pair, preceded},
+    Finish, IResult,
+};
+use serde_json::{json, Value};
+
+use super::Answer;
+
+/// Arithmetic on the numbers in an answer, like `x * 4000000 + y`, to get something else out of
+/// it without changing the solver.
+///
+/// Names are looked up in the answer first, which is called `answer` when it's a number and has
+/// its fields looked up by name when it's an object. Anything else is looked up in the answer's
+/// metadata, with `.` between the names of nested fields, like `metrics.steps`. Everything is
+/// worked out with `i64`s, and overflowing one is an error rather than a wrong number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostExpr {
+    /// The expression as it was written, to show in errors and in the answer's metadata.
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(i64),
+    Name(String),
+    Neg(Box<Expr>),
+    Op(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// Why a [`PostExpr`] couldn't be worked out for an answer.
+#[derive(Debug, thiserror::Error)]
+#[error("Could not work out `{expression}` from the answer: {reason}")]
+pub struct PostError {
+    expression: String,
+    reason: String,
+}
+
+impl PostExpr {
+    /// Work the expression out for `answer`, giving back a new answer with the result.
+    ///
+    /// The new answer keeps the old one's metadata, and adds the expression and the old answer
+    /// to it under `post`. It has no unit, since there's no telling what the result measures.
+    pub fn apply(&self, answer: &Answer) -> Result<Answer, PostError> {
+        let json = answer.to_json();
+        let value = self.eval(&self.expr, &json).map_err(|reason| PostError {
+            expression: self.source.clone(),
+            reason,
+        })?;
+
+        let mut post = Answer::new(value);
+        for (key, value) in answer.metadata() {
+            post = post.with_metadata(key.clone(), value.clone());
+        }
+        Ok(post.with_metadata(
+            "post",
+            json!({ "expression": self.source, "answer": json["answer"] }),
+        ))
+    }
+
+    fn eval(&self, expr: &Expr, json: &Value) -> Result<i64, String> {
+        match expr {
+            Expr::Number(n) => Ok(*n),
+            Expr::Name(name) => lookup(json, name),
+            Expr::Neg(inner) => self
+                .eval(inner, json)?
+                .checked_neg()
+                .ok_or_else(|| "negating it overflowed".to_string()),
+            Expr::Op(op, lhs, rhs) => {
+                let (lhs, rhs) = (self.eval(lhs, json)?, self.eval(rhs, json)?);
+                let result = match op {
+                    Op::Add => lhs.checked_add(rhs),
+                    Op::Sub => lhs.checked_sub(rhs),
+                    Op::Mul => lhs.checked_mul(rhs),
+                    Op::Div | Op::Rem if rhs == 0 => {
+                        return Err(format!("`{lhs} {op} {rhs}` divides by zero"))
+                    }
+                    Op::Div => lhs.checked_div(rhs),
+                    Op::Rem => lhs.checked_rem(rhs),
+                };
+                result.ok_or_else(|| format!("`{lhs} {op} {rhs}` overflowed"))
+            }
+        }
+    }
+}
+
+/// Find the number called `name` in an answer's JSON, from [`Answer::to_json`].
+fn lookup(json: &Value, name: &str) -> Result<i64, String> {
+    let answer = &json["answer"];
+    let found = match (name, answer) {
+        ("answer", _) => Some(answer),
+        (_, Value::Object(fields)) if fields.contains_key(name) => fields.get(name),
+        _ => name
+            .split('.')
+            .try_fold(&json["metadata"], |value, field| value.get(field)),
+    };
+
+    match found {
+        Some(value) => value
+            .as_i64()
+            .ok_or_else(|| format!("`{name}` is {value}, which isn't a whole number")),
+        None => Err(format!(
+            "there's nothing called `{name}`. Its numbers are: {}",
+            numbers(json).join(", ")
+        )),
+    }
+}
+
+/// The names of every number in an answer's JSON, for pointing out what's there when a name
+/// isn't.
+fn numbers(json: &Value) -> Vec<String> {
+    fn walk(prefix: &str, value: &Value, names: &mut Vec<String>) {
+        match value {
+            Value::Number(_) => names.push(prefix.to_string()),
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    let name = match prefix {
+                        "" => key.clone(),
+                        _ => format!("{prefix}.{key}"),
+                    };
+                    walk(&name, value, names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut names = Vec::new();
+    match &json["answer"] {
+        Value::Object(_) => walk("", &json["answer"], &mut names),
+        answer => walk("answer", answer, &mut names),
+    }
+    walk("", &json["metadata"], &mut names);
+    names
+}
+
+impl FromStr for PostExpr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (_, expr) = all_consuming(delimited(multispace0, parse_sum, multispace0))(s)
+            .finish()
+            .map_err(|e: nom::error::Error<&str>| {
+                format!(
+                    "expected arithmetic like `x * 4000000 + y`, but `{s}` couldn't be parsed at \
+                     `{}`",
+                    e.input
+                )
+            })?;
+        Ok(Self {
+            source: s.trim().to_string(),
+            expr,
+        })
+    }
+}
+
+impl fmt::Display for PostExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+        })
+    }
+}
+
+/// Parse `parser`, skipping any spaces after it.
+fn token<'a, T>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, T> {
+    move |i| {
+        let (i, value) = parser(i)?;
+        let (i, _) = multispace0(i)?;
+        Ok((i, value))
+    }
+}
+
+/// Parse operands separated by any of `ops`, grouping them from the left.
+fn binary<'a>(
+    ops: &'static str,
+    operand: fn(&'a str) -> IResult<&'a str, Expr>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Expr> {
+    move |i| {
+        let (i, first) = operand(i)?;
+        fold_many0(
+            pair(token(one_of(ops)), operand),
+            move || first.clone(),
+            |lhs, (op, rhs)| {
+                let op = match op {
+                    '+' => Op::Add,
+                    '-' => Op::Sub,
+                    '*' => Op::Mul,
+                    '/' => Op::Div,
+                    _ => Op::Rem,
+                };
+                Expr::Op(op, Box::new(lhs), Box::new(rhs))
+            },
+        )(i)
+    }
+}
+
+fn parse_sum(i: &str) -> IResult<&str, Expr> {
+    binary("+-", parse_product)(i)
+}
+
+fn parse_product(i: &str) -> IResult<&str, Expr> {
+    binary("*/%", parse_factor)(i)
+}
+
+fn parse_factor(i: &str) -> IResult<&str, Expr> {
+    alt((
+        map(token(nom_i64), Expr::Number),
+        map(token(parse_name), |name: &str| Expr::Name(name.to_string())),
+        map(preceded(token(char('-')), parse_factor), |e| {
+            Expr::Neg(Box::new(e))
+        }),
+        delimited(token(char('(')), parse_sum, token(char(')'))),
+    ))(i)
+}
+
+/// A name like `x`, `beacon_x` or `metrics.steps`.
+fn parse_name(i: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        alt((alpha1, tag("_"))),
+        many0_count(alt((alphanumeric1, tag("_"), tag(".")))),
+    ))(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(expr: &str, answer: &Answer) -> Result<i64, PostError> {
+        let post = expr.parse::<PostExpr>().unwrap().apply(answer)?;
+        Ok(*post.downcast_ref::<i64>().unwrap())
+    }
+
+    #[test]
+    fn works_out_arithmetic_on_named_numbers() {
+        let answer = Answer::new(56000011_i64)
+            .with_unit("Hz")
+            .with_metadata("x", 14)
+            .with_metadata("y", 11)
+            .with_metadata("metrics", json!({ "steps": 7 }));
+
+        assert_eq!(post("x * 4000000 + y", &answer).unwrap(), 56000011);
+        assert_eq!(post("(x + y) * -2", &answer).unwrap(), -50);
+        assert_eq!(post("answer % 1000 - metrics.steps", &answer).unwrap(), 4);
+        assert_eq!(post(" 10 - 4 - 3 ", &answer).unwrap(), 3);
+
+        let applied = "x+y".parse::<PostExpr>().unwrap().apply(&answer).unwrap();
+        assert_eq!(applied.unit(), None);
+        assert_eq!(applied.metadata()["x"], 14);
+        assert_eq!(
+            applied.metadata()["post"],
+            json!({ "expression": "x+y", "answer": 56000011 })
+        );
+
+        let err = post("z", &answer).unwrap_err().to_string();
+        assert!(err.contains("answer, metrics.steps, x, y"), "{err}");
+        assert!(post("x / (y - 11)", &answer).is_err());
+        assert!(post("answer * answer * answer", &answer).is_err());
+
+        for bad in ["", "x +", "(x", "x y", "4x"] {
+            assert!(bad.parse::<PostExpr>().is_err(), "{bad:?} shouldn't parse");
+        }
+    }
+}
@@ -0,0 +1,568 @@
+//! Showing how far `aoc2022 all` and `aoc2022 bench` have got while they run.
+//!
+//! On a terminal, [`ProgressBars`] draws a bar for every day and one for the whole run, with how
+//! long is left, and keeps redrawing them while a slow solver runs so that whatever it reports to
+//! its [`CancellationToken`] shows up next to its day. Anywhere else, [`ProgressLog`] writes a
+//! plain line as each run finishes, which reads better in a CI log.
+
+use std::{
+    fmt::Write as _,
+    io::{self, IsTerminal, Write},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    challenge::{ChallengeNumber, Subchallenge},
+    clock::SharedClock,
+    sync::CancellationToken,
+};
+
+use super::bench::format_ms;
+
+/// How often [`ProgressBars`] are redrawn while a solver runs.
+const REDRAW_EVERY: Duration = Duration::from_millis(100);
+
+/// How many characters wide each bar is.
+const BAR_WIDTH: usize = 20;
+
+/// How much of a solver's own progress is shown next to its day, in characters.
+const MAX_DETAIL: usize = 48;
+
+/// Where [`Solver::run_all`](super::Solver::run_all) and [`Solver::bench`](super::Solver::bench)
+/// say what they're doing.
+///
+/// Anything the solvers print is hidden while they run, so a sink has to write somewhere that
+/// isn't, like the copy of stderr made by [`stderr_progress`].
+pub trait ProgressSink {
+    /// Everything that's going to be run: how many runs there are for each challenge. The runs
+    /// are timed with `clock`, which is what the sink should tell how long is left by too.
+    fn plan(&mut self, runs: &[(ChallengeNumber, usize)], clock: &SharedClock);
+
+    /// A run of `challenge`'s `subchallenge` is starting, and will report its progress to
+    /// `progress`.
+    fn start(
+        &mut self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        progress: &CancellationToken,
+    );
+
+    /// The run that was started last is over.
+    fn finish(&mut self, status: RunStatus, elapsed: Duration);
+
+    /// Everything has been run.
+    fn done(&mut self);
+}
+
+/// How a run went, as far as progress is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// The solver found an answer, right or not.
+    Solved,
+    /// The solver failed, or its input couldn't be read.
+    Failed,
+    /// There was nothing to run it on.
+    Skipped,
+}
+
+/// Show nothing at all.
+#[derive(Debug, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn plan(&mut self, _runs: &[(ChallengeNumber, usize)], _clock: &SharedClock) {}
+
+    fn start(&mut self, _: ChallengeNumber, _: Subchallenge, _: &CancellationToken) {}
+
+    fn finish(&mut self, _status: RunStatus, _elapsed: Duration) {}
+
+    fn done(&mut self) {}
+}
+
+/// Bars on stderr if it's a terminal, and a line per run otherwise.
+///
+/// Writes to a copy of stderr made right now, so that the progress still gets through while the
+/// solvers' own printing is hidden. Shows nothing if stderr can't be copied.
+pub fn stderr_progress() -> Box<dyn ProgressSink> {
+    let stderr = match filedescriptor::FileDescriptor::dup(&io::stderr()) {
+        Ok(stderr) => stderr,
+        Err(_) => return Box::new(NoProgress),
+    };
+    if io::stderr().is_terminal() {
+        Box::new(ProgressBars::new(stderr))
+    } else {
+        Box::new(ProgressLog::new(stderr))
+    }
+}
+
+/// What's been run so far, shared by both kinds of sink.
+#[derive(Debug)]
+struct Tally {
+    days: Vec<Day>,
+    clock: SharedClock,
+    started: Instant,
+    /// The day and subchallenge being run, when it started, and where it reports its progress.
+    current: Option<(usize, Subchallenge, Instant, CancellationToken)>,
+    /// How long the runs that actually ran took together, for guessing how long is left.
+    time_spent: Duration,
+    timed_runs: usize,
+}
+
+#[derive(Debug)]
+struct Day {
+    challenge: ChallengeNumber,
+    runs: usize,
+    finished: usize,
+    failed: usize,
+    time: Duration,
+}
+
+impl Tally {
+    fn new(runs: &[(ChallengeNumber, usize)], clock: SharedClock) -> Self {
+        Self {
+            days: runs
+                .iter()
+                .map(|&(challenge, runs)| Day {
+                    challenge,
+                    runs,
+                    finished: 0,
+                    failed: 0,
+                    time: Duration::ZERO,
+                })
+                .collect(),
+            started: clock.now(),
+            clock,
+            current: None,
+            time_spent: Duration::ZERO,
+            timed_runs: 0,
+        }
+    }
+
+    fn start(
+        &mut self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        progress: &CancellationToken,
+    ) {
+        if let Some(day) = self.days.iter().position(|d| d.challenge == challenge) {
+            self.current = Some((day, subchallenge, self.clock.now(), progress.clone()));
+        }
+    }
+
+    /// Count the current run as finished, and return its day and subchallenge.
+    fn finish(
+        &mut self,
+        status: RunStatus,
+        elapsed: Duration,
+    ) -> Option<(ChallengeNumber, Subchallenge)> {
+        let (day, subchallenge, _, _) = self.current.take()?;
+        let day = &mut self.days[day];
+        day.finished += 1;
+        day.time += elapsed;
+        if status == RunStatus::Failed {
+            day.failed += 1;
+        }
+        // Skipped runs take no time, and would make the rest look quicker than they are.
+        if status != RunStatus::Skipped {
+            self.time_spent += elapsed;
+            self.timed_runs += 1;
+        }
+        Some((day.challenge, subchallenge))
+    }
+
+    fn total(&self) -> usize {
+        self.days.iter().map(|d| d.runs).sum()
+    }
+
+    fn finished(&self) -> usize {
+        self.days.iter().map(|d| d.finished).sum()
+    }
+
+    /// How long the rest of the runs should take, if they take as long as the ones so far on
+    /// average. `None` until something has been timed.
+    fn time_left(&self) -> Option<Duration> {
+        let average = self.time_spent.checked_div(self.timed_runs as u32)?;
+        let left = self.total() - self.finished();
+        Some(average * left as u32)
+    }
+}
+
+/// A line per run, for when stderr isn't a terminal.
+pub struct ProgressLog {
+    out: Box<dyn Write + Send>,
+    tally: Tally,
+}
+
+impl ProgressLog {
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self {
+            out: Box::new(out),
+            tally: Tally::new(&[], SharedClock::default()),
+        }
+    }
+}
+
+impl ProgressSink for ProgressLog {
+    fn plan(&mut self, runs: &[(ChallengeNumber, usize)], clock: &SharedClock) {
+        self.tally = Tally::new(runs, clock.clone());
+    }
+
+    fn start(
+        &mut self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        progress: &CancellationToken,
+    ) {
+        self.tally.start(challenge, subchallenge, progress);
+    }
+
+    fn finish(&mut self, status: RunStatus, elapsed: Duration) {
+        let Some((challenge, subchallenge)) = self.tally.finish(status, elapsed) else {
+            return;
+        };
+        let status = match status {
+            RunStatus::Solved => format!("done in {}", format_ms(elapsed)),
+            RunStatus::Failed => format!("FAILED after {}", format_ms(elapsed)),
+            RunStatus::Skipped => "skipped".to_string(),
+        };
+        let total = self.tally.total();
+        let mut line = format!(
+            "[{:>width$}/{total}] {challenge:02}{subchallenge} {status}",
+            self.tally.finished(),
+            width = total.to_string().len(),
+        );
+        if let Some(left) = self.tally.time_left() {
+            write!(line, ", {} left", format_eta(left)).unwrap();
+        }
+        // Progress is only there to help, so a closed stderr shouldn't stop the run.
+        let _ = writeln!(self.out, "{line}");
+    }
+
+    fn done(&mut self) {
+        let _ = writeln!(
+            self.out,
+            "Ran {} subchallenges in {}",
+            self.tally.finished(),
+            format_eta(self.tally.clock.since(self.tally.started))
+        );
+    }
+}
+
+/// A bar for every day and one for the whole run, redrawn in place on a terminal.
+pub struct ProgressBars {
+    shared: Arc<Mutex<Bars>>,
+    redrawing: Option<thread::JoinHandle<()>>,
+}
+
+struct Bars {
+    out: Box<dyn Write + Send>,
+    tally: Tally,
+    /// How many lines were drawn last time, to be drawn over.
+    drawn: usize,
+    done: bool,
+}
+
+impl ProgressBars {
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Bars {
+                out: Box::new(out),
+                tally: Tally::new(&[], SharedClock::default()),
+                drawn: 0,
+                done: false,
+            })),
+            redrawing: None,
+        }
+    }
+
+    fn bars(&self) -> std::sync::MutexGuard<'_, Bars> {
+        self.shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ProgressSink for ProgressBars {
+    fn plan(&mut self, runs: &[(ChallengeNumber, usize)], clock: &SharedClock) {
+        let mut bars = self.bars();
+        bars.tally = Tally::new(runs, clock.clone());
+        bars.redraw();
+        drop(bars);
+
+        // The solvers run on this thread, so another one keeps the bars moving meanwhile.
+        let shared = Arc::clone(&self.shared);
+        self.redrawing = Some(thread::spawn(move || loop {
+            thread::sleep(REDRAW_EVERY);
+            let mut bars = shared
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if bars.done {
+                break;
+            }
+            bars.redraw();
+        }));
+    }
+
+    fn start(
+        &mut self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        progress: &CancellationToken,
+    ) {
+        let mut bars = self.bars();
+        bars.tally.start(challenge, subchallenge, progress);
+        bars.redraw();
+    }
+
+    fn finish(&mut self, status: RunStatus, elapsed: Duration) {
+        let mut bars = self.bars();
+        bars.tally.finish(status, elapsed);
+        bars.redraw();
+    }
+
+    fn done(&mut self) {
+        let mut bars = self.bars();
+        bars.done = true;
+        bars.redraw();
+        drop(bars);
+        if let Some(redrawing) = self.redrawing.take() {
+            let _ = redrawing.join();
+        }
+    }
+}
+
+impl Drop for ProgressBars {
+    fn drop(&mut self) {
+        if self.redrawing.is_some() {
+            self.done();
+        }
+    }
+}
+
+impl Bars {
+    fn redraw(&mut self) {
+        let lines = render(&self.tally, self.tally.clock.now());
+        let mut text = String::new();
+        if self.drawn > 0 {
+            // Back up to the first line drawn last time.
+            write!(text, "\x1b[{}A", self.drawn).unwrap();
+        }
+        for line in &lines {
+            writeln!(text, "\x1b[2K{line}").unwrap();
+        }
+        self.drawn = lines.len();
+        let _ = self.out.write_all(text.as_bytes());
+        let _ = self.out.flush();
+    }
+}
+
+/// The lines that [`ProgressBars`] draws: one per day, then one for the whole run.
+fn render(tally: &Tally, now: Instant) -> Vec<String> {
+    let mut lines = tally
+        .days
+        .iter()
+        .enumerate()
+        .map(|(i, day)| {
+            let mut line = format!(
+                "{:02}    {} {}/{}",
+                day.challenge,
+                bar(day.finished, day.runs),
+                day.finished,
+                day.runs
+            );
+            match &tally.current {
+                Some((current, subchallenge, started, progress)) if *current == i => {
+                    let elapsed = now.saturating_duration_since(*started);
+                    write!(line, "  {subchallenge}: {}", format_eta(elapsed)).unwrap();
+                    let detail = detail(progress);
+                    if !detail.is_empty() {
+                        write!(line, "  {detail}").unwrap();
+                    }
+                }
+                _ if day.runs > 0 && day.finished == day.runs => {
+                    write!(line, "  {}", format_ms(day.time)).unwrap();
+                    if day.failed > 0 {
+                        write!(line, ", {} FAILED", day.failed).unwrap();
+                    }
+                }
+                _ => {}
+            }
+            line
+        })
+        .collect::<Vec<_>>();
+
+    let (finished, total) = (tally.finished(), tally.total());
+    let mut overall = format!(
+        "all   {} {finished}/{total}  {} so far",
+        bar(finished, total),
+        format_eta(now.saturating_duration_since(tally.started))
+    );
+    match tally.time_left() {
+        Some(left) if finished < total => write!(overall, ", about {} left", format_eta(left)),
+        _ => Ok(()),
+    }
+    .unwrap();
+    lines.push(overall);
+    lines
+}
+
+/// A bar [`BAR_WIDTH`] wide, filled in by how much of `total` is `done`.
+fn bar(done: usize, total: usize) -> String {
+    let filled = (done * BAR_WIDTH).checked_div(total).unwrap_or(0);
+    format!("[{}{}]", "█".repeat(filled), "·".repeat(BAR_WIDTH - filled))
+}
+
+/// What a solver has reported about itself, like `rocks=1234, height=5678`, cut short at
+/// [`MAX_DETAIL`] characters.
+fn detail(progress: &CancellationToken) -> String {
+    let text = progress
+        .progress()
+        .iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => format!("{key}={s}"),
+            value => format!("{key}={value}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    if text.chars().count() > MAX_DETAIL {
+        let cut = text.chars().take(MAX_DETAIL - 1).collect::<String>();
+        format!("{cut}…")
+    } else {
+        text
+    }
+}
+
+/// A rough length of time, like `850 ms`, `12.3 s` or `4m 05s`.
+fn format_eta(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs < 1.0 {
+        format!("{} ms", duration.as_millis())
+    } else if secs < 60.0 {
+        format!("{secs:.1} s")
+    } else {
+        let secs = duration.as_secs();
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::FakeClock;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn logs_a_line_per_run() {
+        let out = Captured::default();
+        let mut log = ProgressLog::new(out.clone());
+        let token = CancellationToken::new();
+        let clock = FakeClock::new();
+        log.plan(&[(1, 2), (16, 2)], &SharedClock::new(clock.clone()));
+        for (challenge, subchallenge, status) in [
+            (1, Subchallenge::A, RunStatus::Solved),
+            (1, Subchallenge::B, RunStatus::Failed),
+            (16, Subchallenge::A, RunStatus::Skipped),
+        ] {
+            log.start(challenge, subchallenge, &token);
+            clock.advance(Duration::from_secs(2));
+            log.finish(status, Duration::from_secs(2));
+        }
+        log.done();
+
+        let text = out.text();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "[1/4] 01a done in 2000.000 ms, 6.0 s left",
+                "[2/4] 01b FAILED after 2000.000 ms, 4.0 s left",
+                "[3/4] 16a skipped, 2.0 s left",
+                "Ran 3 subchallenges in 6.0 s",
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_a_bar_per_day_with_the_solvers_progress() {
+        let clock = FakeClock::new();
+        let mut tally = Tally::new(&[(1, 2), (17, 2)], SharedClock::new(clock.clone()));
+        let token = CancellationToken::new();
+        tally.start(1, Subchallenge::A, &token);
+        tally.finish(RunStatus::Solved, Duration::from_millis(3));
+        tally.start(1, Subchallenge::B, &token);
+        tally.finish(RunStatus::Solved, Duration::from_millis(5));
+        token.report("rocks", 1234);
+        clock.advance(Duration::from_secs(60));
+        tally.start(17, Subchallenge::B, &token);
+        clock.advance(Duration::from_secs(15));
+
+        let lines = render(&tally, tally.clock.now());
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            format!("01    [{}] 2/2  8.000 ms", "█".repeat(20))
+        );
+        assert_eq!(
+            lines[1],
+            format!("17    [{}] 0/2  b: 15.0 s  rocks=1234", "·".repeat(20))
+        );
+        assert_eq!(
+            lines[2],
+            format!(
+                "all   [{}{}] 2/4  1m 15s so far, about 8 ms left",
+                "█".repeat(10),
+                "·".repeat(10)
+            )
+        );
+
+        token.report("note", "x".repeat(100));
+        assert_eq!(detail(&token).chars().count(), MAX_DETAIL);
+    }
+
+    #[test]
+    fn bars_are_drawn_over_themselves() {
+        let out = Captured::default();
+        let mut bars = ProgressBars::new(out.clone());
+        let token = CancellationToken::new();
+        let clock = FakeClock::new();
+        bars.plan(&[(1, 1)], &SharedClock::new(clock.clone()));
+        bars.start(1, Subchallenge::A, &token);
+        clock.advance(Duration::from_secs(3));
+        bars.finish(RunStatus::Solved, Duration::from_millis(1));
+        bars.done();
+
+        let text = out.text();
+        assert!(text.starts_with("\x1b[2K01 "), "{text:?}");
+        assert!(text.contains("\x1b[2A\x1b[2K01 "), "{text:?}");
+        let last = text.rsplit("\x1b[2A").next().unwrap();
+        assert!(
+            last.starts_with(&format!(
+                "\x1b[2K01    [{}] 1/1  1.000 ms\n",
+                "█".repeat(20)
+            )),
+            "{text:?}"
+        );
+        assert!(last.ends_with("1/1  3.0 s so far\n"), "{text:?}");
+    }
+}
@@ -0,0 +1,527 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::Path, time::Duration};
+
+use serde_json::{json, Value};
+
+use crate::{
+    challenge::{ChallengeNumber, Subchallenge, CHALLENGES},
+    error::{Context, SolverError},
+};
+
+use super::{bench::format_ms, Answer, SolveError};
+
+/// Where the known answers are kept, relative to the working directory.
+pub const ANSWERS_FILE: &str = "answers.toml";
+
+/// How many of the slowest subchallenges the summary points out.
+const SLOWEST: usize = 3;
+
+/// The known answer to every subchallenge that's been solved, from [`ANSWERS_FILE`].
+///
+/// Keys are like `16b`, and answers are written the way `--quiet` prints them, either as strings
+/// or as numbers:
+///
+/// ```toml
+/// 01a = 24000
+/// 10b = "##..##..\n..##..##"
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct KnownAnswers(BTreeMap<String, String>);
+
+impl KnownAnswers {
+    pub fn load(path: &Path) -> Result<Self, SolverError> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Could not read answers from {}", path.display()))?;
+        Self::parse(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            .with_context(|| format!("Could not parse answers in {}", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        let answers = toml::from_str::<BTreeMap<String, toml::Value>>(text)?
+            .into_iter()
+            .map(|(key, answer)| {
+                let answer = match answer {
+                    toml::Value::String(answer) => answer,
+                    answer => answer.to_string(),
+                };
+                // Picture answers can be written with real line breaks too.
+                (key, answer.trim().replace('\n', "\\n"))
+            })
+            .collect();
+        Ok(Self(answers))
+    }
+
+    pub(super) fn get(
+        &self,
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+    ) -> Option<&str> {
+        self.0
+            .get(&key(challenge, subchallenge))
+            .map(String::as_str)
+    }
+}
+
+/// How every subchallenge went on its puzzle input, for `aoc2022 all`.
+#[derive(Debug)]
+pub struct RunAllReport {
+    pub results: Vec<RunResult>,
+    /// Every challenge from 1 to 25 that there's no solver for.
+    pub missing_solvers: Vec<ChallengeNumber>,
+    /// Whether answers were checked against [`KnownAnswers`].
+    pub checked: bool,
+}
+
+/// How one subchallenge went.
+#[derive(Debug)]
+pub struct RunResult {
+    pub challenge: ChallengeNumber,
+    pub subchallenge: Subchallenge,
+    pub outcome: RunOutcome,
+}
+
+#[derive(Debug)]
+pub enum RunOutcome {
+    /// There's no input for it in `./input/`.
+    NoInput,
+    /// The input was there, but couldn't be read.
+    Unreadable(String),
+    /// The solver ran for `time` and came up with an answer or an error.
+    Ran {
+        time: Duration,
+        answer: Result<Answer, SolveError>,
+        check: Check,
+    },
+}
+
+/// How an answer compared to the known one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// There's no known answer to compare with, or nothing to compare.
+    Unknown,
+    Right,
+    Wrong {
+        expected: String,
+    },
+}
+
+impl Check {
+    pub(super) fn new(answer: &Result<Answer, SolveError>, expected: Option<&str>) -> Self {
+        match (answer, expected) {
+            (Ok(answer), Some(expected)) if !answer.is_empty() => {
+                if answer.plain() == expected {
+                    Self::Right
+                } else {
+                    Self::Wrong {
+                        expected: expected.to_string(),
+                    }
+                }
+            }
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// The key for a subchallenge in [`ANSWERS_FILE`] and in reports, like `16b`.
+fn key(challenge: ChallengeNumber, subchallenge: Subchallenge) -> String {
+    format!("{challenge:02}{subchallenge}")
+}
+
+impl RunResult {
+    fn key(&self) -> String {
+        key(self.challenge, self.subchallenge)
+    }
+
+    fn time(&self) -> Option<Duration> {
+        match self.outcome {
+            RunOutcome::Ran { time, .. } => Some(time),
+            _ => None,
+        }
+    }
+
+    fn check(&self) -> Option<&Check> {
+        match &self.outcome {
+            RunOutcome::Ran { check, .. } => Some(check),
+            _ => None,
+        }
+    }
+
+    /// The answer, or what went wrong instead, on one line.
+    fn answer_cell(&self) -> String {
+        match &self.outcome {
+            RunOutcome::NoInput => "no input".to_string(),
+            RunOutcome::Unreadable(e) => format!("ERROR: {e}"),
+            RunOutcome::Ran {
+                answer: Ok(answer), ..
+            } if answer.is_empty() => "(printed by the solver)".to_string(),
+            RunOutcome::Ran {
+                answer: Ok(answer), ..
+            } => answer.short().to_string(),
+            RunOutcome::Ran { answer: Err(e), .. } => format!("ERROR: {e}"),
+        }
+    }
+
+    fn check_cell(&self) -> String {
+        match self.check() {
+            Some(Check::Right) => "right".to_string(),
+            Some(Check::Wrong { expected }) => format!("WRONG, expected {expected}"),
+            Some(Check::Unknown) | None => "-".to_string(),
+        }
+    }
+
+    fn is_err(&self) -> bool {
+        match &self.outcome {
+            RunOutcome::NoInput => false,
+            RunOutcome::Unreadable(_) => true,
+            RunOutcome::Ran { answer, check, .. } => {
+                answer.is_err() || matches!(check, Check::Wrong { .. })
+            }
+        }
+    }
+}
+
+impl RunAllReport {
+    /// Whether any solver failed, got a wrong answer, or had an input it couldn't read.
+    pub fn is_err(&self) -> bool {
+        self.results.iter().any(RunResult::is_err)
+    }
+
+    /// How long all of the solvers took together.
+    pub fn total_time(&self) -> Duration {
+        self.results.iter().filter_map(RunResult::time).sum()
+    }
+
+    /// The subchallenges that took the longest, slowest first.
+    pub fn slowest(&self) -> Vec<(&RunResult, Duration)> {
+        let mut timed = self
+            .results
+            .iter()
+            .filter_map(|result| Some((result, result.time()?)))
+            .collect::<Vec<_>>();
+        timed.sort_by_key(|&(_, time)| std::cmp::Reverse(time));
+        timed.truncate(SLOWEST);
+        timed
+    }
+
+    /// Every subchallenge with a solver but no input, like `03b`, or just the challenge, like
+    /// `03`, when neither of its subchallenges has one.
+    pub fn missing_inputs(&self) -> Vec<String> {
+        let missing = self
+            .results
+            .iter()
+            .filter(|result| matches!(result.outcome, RunOutcome::NoInput))
+            .collect::<Vec<_>>();
+        missing
+            .iter()
+            .filter_map(|result| {
+                let both = missing
+                    .iter()
+                    .filter(|other| other.challenge == result.challenge)
+                    .count()
+                    == 2;
+                match (both, result.subchallenge) {
+                    (false, _) => Some(result.key()),
+                    (true, Subchallenge::A) => Some(format!("{:02}", result.challenge)),
+                    (true, Subchallenge::B) => None,
+                }
+            })
+            .collect()
+    }
+
+    /// How many answers were right, wrong, and couldn't be checked.
+    fn check_counts(&self) -> [usize; 3] {
+        let mut counts = [0; 3];
+        for check in self.results.iter().filter_map(RunResult::check) {
+            let i = match check {
+                Check::Right => 0,
+                Check::Wrong { .. } => 1,
+                Check::Unknown => 2,
+            };
+            counts[i] += 1;
+        }
+        counts
+    }
+
+    /// The table's header, and a row for each subchallenge.
+    fn table(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let mut header = vec!["challenge", "time", "answer"];
+        if self.checked {
+            header.push("check");
+        }
+        let rows = self
+            .results
+            .iter()
+            .map(|result| {
+                let mut row = vec![
+                    result.key(),
+                    result.time().map_or_else(|| "-".to_string(), format_ms),
+                    result.answer_cell(),
+                ];
+                if self.checked {
+                    row.push(result.check_cell());
+                }
+                row
+            })
+            .collect();
+        (header, rows)
+    }
+
+    /// The lines of the summary after the table.
+    fn summary(&self) -> Vec<String> {
+        let list = |items: Vec<String>| {
+            if items.is_empty() {
+                "none".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+        let ran = self.results.iter().filter(|r| r.time().is_some()).count();
+
+        let mut lines = vec![
+            format!(
+                "Total runtime: {} over {ran} subchallenges",
+                format_ms(self.total_time())
+            ),
+            format!(
+                "Slowest: {}",
+                list(
+                    self.slowest()
+                        .into_iter()
+                        .map(|(result, time)| format!("{} ({})", result.key(), format_ms(time)))
+                        .collect()
+                )
+            ),
+            format!("Missing inputs: {}", list(self.missing_inputs())),
+            format!(
+                "Missing solvers: {}",
+                list(
+                    self.missing_solvers
+                        .iter()
+                        .map(|challenge| format!("{challenge:02}"))
+                        .collect()
+                )
+            ),
+        ];
+        if self.checked {
+            let [right, wrong, unknown] = self.check_counts();
+            lines.push(format!(
+                "Answers: {right} right, {wrong} wrong, {unknown} unchecked"
+            ));
+        }
+        lines
+    }
+
+    /// The report as a Markdown table, followed by the summary as a list.
+    pub fn to_markdown(&self) -> String {
+        let (header, rows) = self.table();
+        let mut md = format!("| {} |\n", header.join(" | "));
+        md += &format!("|{}\n", " --- |".repeat(header.len()));
+        for row in rows {
+            let cells = row
+                .iter()
+                .map(|cell| cell.replace('|', "\\|"))
+                .collect::<Vec<_>>();
+            md += &format!("| {} |\n", cells.join(" | "));
+        }
+        md.push('\n');
+        for line in self.summary() {
+            md += &format!("- {line}\n");
+        }
+        md
+    }
+
+    /// The report as a JSON object, with every subchallenge's result and the summary.
+    pub fn to_json(&self) -> Value {
+        let results = self
+            .results
+            .iter()
+            .map(|result| {
+                let mut json = json!({
+                    "challenge": result.challenge,
+                    "subchallenge": result.subchallenge.as_str(),
+                    "time_ms": result.time().map(|time| time.as_secs_f64() * 1000.0),
+                });
+                match &result.outcome {
+                    RunOutcome::NoInput => json["missing_input"] = true.into(),
+                    RunOutcome::Unreadable(e) => json["error"] = e.as_str().into(),
+                    RunOutcome::Ran { answer, check, .. } => {
+                        match answer {
+                            Ok(answer) => json["answer"] = answer.to_json()["answer"].take(),
+                            Err(e) => json["error"] = e.to_string().into(),
+                        }
+                        if self.checked {
+                            json["check"] = match check {
+                                Check::Right => json!("right"),
+                                Check::Wrong { expected } => json!({ "wrong": expected }),
+                                Check::Unknown => Value::Null,
+                            };
+                        }
+                    }
+                }
+                json
+            })
+            .collect::<Vec<_>>();
+
+        let mut summary = json!({
+            "total_ms": self.total_time().as_secs_f64() * 1000.0,
+            "slowest": self
+                .slowest()
+                .into_iter()
+                .map(|(result, time)| json!({ "key": result.key(), "time_ms": time.as_secs_f64() * 1000.0 }))
+                .collect::<Vec<_>>(),
+            "missing_inputs": self.missing_inputs(),
+            "missing_solvers": self.missing_solvers,
+        });
+        if self.checked {
+            let [right, wrong, unchecked] = self.check_counts();
+            summary["answers"] = json!({ "right": right, "wrong": wrong, "unchecked": unchecked });
+        }
+
+        json!({ "results": results, "summary": summary })
+    }
+}
+
+/// Every challenge from 1 to 25 that isn't in `challenges`.
+pub(super) fn missing_solvers(challenges: &[ChallengeNumber]) -> Vec<ChallengeNumber> {
+    CHALLENGES
+        .filter(|challenge| !challenges.contains(challenge))
+        .collect()
+}
+
+/// The table, with every column as wide as its widest cell, and then the summary.
+impl fmt::Display for RunAllReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (header, rows) = self.table();
+        let widths = header
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .fold(title.len(), usize::max)
+            })
+            .collect::<Vec<_>>();
+
+        let header = header.iter().map(ToString::to_string).collect::<Vec<_>>();
+        for row in std::iter::once(&header).chain(&rows) {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        writeln!(f)?;
+        for line in self.summary() {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ran(
+        challenge: ChallengeNumber,
+        subchallenge: Subchallenge,
+        ms: u64,
+        answer: usize,
+        expected: Option<&str>,
+    ) -> RunResult {
+        let answer = Ok(Answer::new(answer));
+        RunResult {
+            challenge,
+            subchallenge,
+            outcome: RunOutcome::Ran {
+                time: Duration::from_millis(ms),
+                check: Check::new(&answer, expected),
+                answer,
+            },
+        }
+    }
+
+    fn report() -> RunAllReport {
+        let answers = KnownAnswers::parse("01a = 24000\n01b = \"45000\"\n02a = 15\n").unwrap();
+        let expected = |challenge, subchallenge| answers.get(challenge, subchallenge);
+        RunAllReport {
+            results: vec![
+                ran(1, Subchallenge::A, 2, 24000, expected(1, Subchallenge::A)),
+                ran(1, Subchallenge::B, 1, 45000, expected(1, Subchallenge::B)),
+                ran(2, Subchallenge::A, 40, 12, expected(2, Subchallenge::A)),
+                ran(2, Subchallenge::B, 7, 12, expected(2, Subchallenge::B)),
+                ran(3, Subchallenge::A, 1, 157, None),
+                RunResult {
+                    challenge: 3,
+                    subchallenge: Subchallenge::B,
+                    outcome: RunOutcome::NoInput,
+                },
+                RunResult {
+                    challenge: 4,
+                    subchallenge: Subchallenge::A,
+                    outcome: RunOutcome::NoInput,
+                },
+                RunResult {
+                    challenge: 4,
+                    subchallenge: Subchallenge::B,
+                    outcome: RunOutcome::NoInput,
+                },
+            ],
+            missing_solvers: missing_solvers(&(1..=22).collect::<Vec<_>>()),
+            checked: true,
+        }
+    }
+
+    #[test]
+    fn summarizes_the_run() {
+        let report = report();
+        assert!(report.is_err());
+        assert_eq!(report.total_time(), Duration::from_millis(51));
+
+        let text = report.to_string();
+        assert!(
+            text.starts_with(
+                "challenge  time       answer    check\n\
+                 01a        2.000 ms   24000     right\n\
+                 01b        1.000 ms   45000     right\n\
+                 02a        40.000 ms  12        WRONG, expected 15\n\
+                 02b        7.000 ms   12        -\n\
+                 03a        1.000 ms   157       -\n\
+                 03b        -          no input  -\n"
+            ),
+            "{text}"
+        );
+        assert!(text.contains("Total runtime: 51.000 ms over 5 subchallenges\n"));
+        assert!(text.contains("Slowest: 02a (40.000 ms), 02b (7.000 ms), 01a (2.000 ms)\n"));
+        assert!(text.contains("Missing inputs: 03b, 04\n"));
+        assert!(text.contains("Missing solvers: 23, 24, 25\n"));
+        assert!(text.contains("Answers: 2 right, 1 wrong, 2 unchecked\n"));
+    }
+
+    #[test]
+    fn exports_markdown_and_json() {
+        let report = report();
+
+        let md = report.to_markdown();
+        assert!(md.starts_with(
+            "| challenge | time | answer | check |\n| --- | --- | --- | --- |\n\
+             | 01a | 2.000 ms | 24000 | right |\n"
+        ));
+        assert!(md.contains("\n- Missing inputs: 03b, 04\n"));
+
+        let json = report.to_json();
+        assert_eq!(json["results"][0]["answer"], 24000);
+        assert_eq!(json["results"][2]["check"], json!({ "wrong": "15" }));
+        assert_eq!(json["results"][5]["missing_input"], true);
+        assert_eq!(json["summary"]["missing_inputs"], json!(["03b", "04"]));
+        assert_eq!(json["summary"]["slowest"][0]["key"], "02a");
+        assert_eq!(json["summary"]["missing_solvers"], json!([23, 24, 25]));
+        assert_eq!(
+            json["summary"]["answers"],
+            json!({ "right": 2, "wrong": 1, "unchecked": 2 })
+        );
+    }
+}
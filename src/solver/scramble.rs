@@ -0,0 +1,172 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A small, seeded source of randomness for scrambling challenge inputs, or making them up.
+///
+/// Passed to [`ChallengeSolver::scramble`][super::ChallengeSolver::scramble] and
+/// [`ChallengeSolver::generate`][super::ChallengeSolver::generate]. The same seed always gives the
+/// same scrambled input, so a bug report only needs the seed to be reproduced by someone with the
+/// original input, and a made-up input only needs its seed and size.
+#[derive(Debug, Clone)]
+pub struct Scrambler {
+    state: u64,
+}
+
+impl Scrambler {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// The next random number, using [SplitMix64][splitmix].
+    ///
+    /// [splitmix]: https://prng.di.unimi.it/splitmix64.c
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A random number in `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "can't pick a number below zero");
+        // Slightly biased for huge `n`, which doesn't matter for scrambling puzzle inputs.
+        self.next_u64() % n
+    }
+
+    /// A coin flip.
+    pub fn chance(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// Shuffle `items` in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Give every name in `names` a new, unique name made by `fresh`, except for the names in
+    /// `keep`, which stay as they are.
+    ///
+    /// `fresh` must be able to make more distinct names than there are in `names`, or this will
+    /// never return.
+    pub fn rename<T, F>(
+        &mut self,
+        names: impl IntoIterator<Item = T>,
+        keep: &[T],
+        mut fresh: F,
+    ) -> HashMap<T, T>
+    where
+        T: Clone + Eq + Hash,
+        F: FnMut(&mut Self) -> T,
+    {
+        let mut renames: HashMap<T, T> = keep
+            .iter()
+            .map(|name| (name.clone(), name.clone()))
+            .collect();
+        let mut taken = keep.iter().cloned().collect::<HashSet<_>>();
+
+        for name in names {
+            if renames.contains_key(&name) {
+                continue;
+            }
+            let new_name = std::iter::repeat_with(|| fresh(self))
+                .find(|candidate| !taken.contains(candidate))
+                .expect("`repeat_with` never runs out");
+            taken.insert(new_name.clone());
+            renames.insert(name, new_name);
+        }
+
+        renames
+    }
+
+    /// A random string of `len` characters from `alphabet`.
+    pub fn word(&mut self, alphabet: &[u8], len: usize) -> String {
+        (0..len)
+            .map(|_| alphabet[self.below(alphabet.len() as u64) as usize] as char)
+            .collect()
+    }
+}
+
+/// Where the seed for a [`Scrambler`] comes from when one isn't given.
+///
+/// A different seed every time by default, but tests can fix it, so that scrambling or making up
+/// an input without a seed comes out the same on every run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeedSource {
+    #[default]
+    Random,
+    Fixed(u64),
+}
+
+impl SeedSource {
+    /// Pick a seed.
+    pub fn seed(self) -> u64 {
+        match self {
+            // Any value will do, as long as it's different each time.
+            Self::Random => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64),
+            Self::Fixed(seed) => seed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Solver;
+
+    use super::*;
+
+    #[test]
+    fn same_seed_same_scramble() {
+        let scramble = |seed| {
+            let mut scrambler = Scrambler::new(seed);
+            let mut items = (0..20).collect::<Vec<_>>();
+            scrambler.shuffle(&mut items);
+            (items, scrambler.word(b"abc", 8))
+        };
+
+        assert_eq!(scramble(7), scramble(7));
+        assert_ne!(scramble(7), scramble(8));
+
+        let (mut items, _) = scramble(7);
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn renames_are_unique() {
+        let mut scrambler = Scrambler::new(21);
+        let names = ["root", "abcd", "humn", "efgh", "abcd"];
+        let renames = scrambler.rename(names, &["root", "humn"], |s| {
+            // Few enough options that collisions are bound to happen.
+            ["root", "humn", "aaaa", "bbbb", "cccc"][s.below(5) as usize]
+        });
+
+        assert_eq!(renames.len(), 4);
+        assert_eq!(renames["root"], "root");
+        assert_eq!(renames["humn"], "humn");
+        assert_ne!(renames["abcd"], renames["efgh"]);
+        assert!(!["root", "humn"].contains(&renames["abcd"]));
+    }
+
+    #[test]
+    fn fixed_seeds_stand_in_for_missing_ones() {
+        let solver = Solver::new().with_seeds(SeedSource::Fixed(20));
+        let (generated, seed) = solver.generate(20, 50, None).unwrap();
+        assert_eq!(seed, 20);
+        assert_eq!(solver.generate(20, 50, Some(20)).unwrap().0, generated);
+        assert_ne!(solver.generate(20, 50, Some(21)).unwrap().0, generated);
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+
+use crate::challenge::{ChallengeNumber, Subchallenge};
+
+use super::{Answer, SolveError};
+
+/// How every solver did against its sample.
+#[derive(Debug)]
+pub struct SelfTestReport {
+    pub results: Vec<SampleResult>,
+}
+
+/// How one solver did against its sample, for subchallenges A and B.
+#[derive(Debug)]
+pub struct SampleResult {
+    pub challenge: ChallengeNumber,
+    pub outcomes: [Outcome; 2],
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Pass,
+    Fail {
+        expected: Answer,
+        got: Answer,
+    },
+    Error(SolveError),
+    /// The solver has no sample, or no answer for this subchallenge to check against, so it
+    /// wasn't run.
+    Skip,
+}
+
+impl Outcome {
+    pub fn is_pass(&self) -> bool {
+        matches!(self, Self::Pass)
+    }
+
+    /// Whether the solver got the wrong answer, or couldn't finish.
+    pub fn is_err(&self) -> bool {
+        matches!(self, Self::Fail { .. } | Self::Error(_))
+    }
+
+    pub fn is_skip(&self) -> bool {
+        matches!(self, Self::Skip)
+    }
+}
+
+impl SelfTestReport {
+    /// Whether any solver got the wrong answer, or couldn't finish.
+    pub fn is_err(&self) -> bool {
+        self.results
+            .iter()
+            .flat_map(|r| &r.outcomes)
+            .any(Outcome::is_err)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<11}{:<7}b", "challenge", "a")?;
+        for result in &self.results {
+            let [a, b] = result.outcomes.each_ref().map(|o| match o {
+                Outcome::Pass => "pass",
+                Outcome::Fail { .. } => "FAIL",
+                Outcome::Error(_) => "ERROR",
+                Outcome::Skip => "skip",
+            });
+            writeln!(f, "{:<11}{a:<7}{b}", format!("{:02}", result.challenge))?;
+        }
+
+        let mut wrote_details = false;
+        for result in &self.results {
+            for (subchallenge, outcome) in [Subchallenge::A, Subchallenge::B]
+                .iter()
+                .zip(&result.outcomes)
+            {
+                let detail = match outcome {
+                    Outcome::Pass | Outcome::Skip => continue,
+                    Outcome::Fail { expected, got } => {
+                        format!("expected {}, got {}", expected.short(), got.short())
+                    }
+                    Outcome::Error(e) => format!("error: {e}"),
+                };
+                if !wrote_details {
+                    writeln!(f)?;
+                    wrote_details = true;
+                }
+                writeln!(
+                    f,
+                    "challenge {}, subchallenge {subchallenge}: {detail}",
+                    result.challenge
+                )?;
+            }
+        }
+
+        // A solver passes if it got every answer that there was to check right.
+        let skipped = self
+            .results
+            .iter()
+            .filter(|r| r.outcomes.iter().all(Outcome::is_skip))
+            .count();
+        let passed = self
+            .results
+            .iter()
+            .filter(|r| r.outcomes.iter().any(Outcome::is_pass))
+            .filter(|r| !r.outcomes.iter().any(Outcome::is_err))
+            .count();
+        writeln!(
+            f,
+            "\n{passed} of {} solvers passed, {skipped} skipped",
+            self.results.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_failures() {
+        let report = SelfTestReport {
+            results: vec![
+                SampleResult {
+                    challenge: 9,
+                    outcomes: [Outcome::Pass, Outcome::Pass],
+                },
+                SampleResult {
+                    challenge: 13,
+                    outcomes: [
+                        Outcome::Pass,
+                        Outcome::Fail {
+                            expected: Answer::new(140_usize),
+                            got: Answer::new(139_usize),
+                        },
+                    ],
+                },
+                SampleResult {
+                    challenge: 7,
+                    outcomes: [Outcome::Skip, Outcome::Skip],
+                },
+                SampleResult {
+                    challenge: 22,
+                    outcomes: [Outcome::Pass, Outcome::Skip],
+                },
+            ],
+        };
+
+        assert!(report.is_err());
+        let text = report.to_string();
+        assert!(text.contains("13         pass   FAIL"));
+        assert!(text.contains("07         skip   skip"));
+        assert!(text.contains("22         pass   skip"));
+        assert!(text.contains("challenge 13, subchallenge b: expected 140, got 139"));
+        assert!(!text.contains("challenge 7,"));
+        assert!(text.contains("2 of 4 solvers passed, 1 skipped"));
+    }
+}
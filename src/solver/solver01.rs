@@ -1,6 +1,11 @@
 use std::io::BufRead;
 
-use super::ChallengeSolver;
+use crate::{
+    error::{Context, SolverError},
+    input_iter,
+};
+
+use super::{Answer, ChallengeSolver};
 
 #[derive(Debug, Default)]
 pub struct Solver01;
@@ -11,60 +16,65 @@ impl ChallengeSolver for Solver01 {
         1
     }
 
+    super::sample_methods!();
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut max = 0_u64;
-        let mut current = 0_u64;
-
-        for line in input.lines() {
-            let line = line?;
-            let line = line.trim();
-
-            if line.is_empty() {
-                // we've stopped processing the current elf. Update the max calorie count.
-                max = max.max(current);
-                current = 0;
-            } else {
-                // update the current elf's calorie count.
-                current += line.parse::<u64>()?;
-            }
+
+        for current in elf_calories(input) {
+            max = max.max(current?);
         }
 
         println!("Max calorie count: {max}");
 
-        Ok(Box::new(()))
+        Ok(Answer::new(max))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut top_three = [0_u64; 3];
-        let mut current = 0_u64;
-
-        for line in input.lines() {
-            let line = line?;
-            let line = line.trim();
-
-            if line.is_empty() {
-                // we've stopped processing the current elf. Update the top three calorie counts.
-                if current > top_three[0] {
-                    top_three[2] = top_three[1];
-                    top_three[1] = top_three[0];
-                    top_three[0] = current;
-                } else if current > top_three[1] {
-                    top_three[2] = top_three[1];
-                    top_three[1] = current;
-                } else if current > top_three[2] {
-                    top_three[2] = current;
-                }
-
-                current = 0;
-            } else {
-                // update the current elf's calorie count.
-                current += line.parse::<u64>()?;
+
+        for current in elf_calories(input) {
+            let current = current?;
+
+            if current > top_three[0] {
+                top_three[2] = top_three[1];
+                top_three[1] = top_three[0];
+                top_three[0] = current;
+            } else if current > top_three[1] {
+                top_three[2] = top_three[1];
+                top_three[1] = current;
+            } else if current > top_three[2] {
+                top_three[2] = current;
             }
         }
 
+        let sum = top_three.iter().sum::<u64>();
         println!("Top three calorie counts: {top_three:?}");
-        println!("Sum: {}", top_three.iter().sum::<u64>());
+        println!("Sum: {sum}");
+
+        Ok(Answer::new(sum))
+    }
+}
+
+/// The total calorie count carried by each elf, in the order that they appear in the input.
+fn elf_calories(input: &mut dyn BufRead) -> impl Iterator<Item = Result<u64, SolverError>> + '_ {
+    input_iter::groups(input).map(|group| {
+        group
+            .context("Could not read input file")?
+            .iter()
+            .map(|line| {
+                line.trim()
+                    .parse::<u64>()
+                    .with_context(|| format!("Could not parse `{line}` as a calorie count"))
+            })
+            .sum()
+    })
+}
 
-        Ok(Box::new(()))
+super::challenge_solver_test_boilerplate! {
+    Solver01;
+    1 => {
+        a as u64,
+        b as u64,
     }
 }
@@ -1,6 +1,16 @@
 use std::{io::BufRead, str::FromStr};
 
-use color_eyre::eyre::Context;
+use nom::{
+    character::complete::{alpha1, char},
+    sequence::separated_pair,
+};
+use nom_supreme::ParserExt;
+
+use crate::{
+    error::{Context, SolverError},
+    input_iter::for_each_line,
+    parse::LineParser,
+};
 
 use super::ChallengeSolver;
 
@@ -93,7 +103,10 @@ impl FromStr for RoShamBo {
 }
 
 #[derive(Debug, Default)]
-pub struct Solver02;
+pub struct Solver02 {
+    /// Leave out rounds that don't parse, instead of failing.
+    skip_bad_lines: bool,
+}
 
 impl ChallengeSolver for Solver02 {
     #[inline]
@@ -101,79 +114,75 @@ impl ChallengeSolver for Solver02 {
         2
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut player_score = 0;
-
-        for line in input.lines() {
-            let line = line?;
-            let line = line.trim();
-
-            if line.is_empty() {
-                continue;
-            }
-
-            let mut cols = line.split(' ');
-
-            let opponent = cols
-                .next()
-                .ok_or(Solver02Error::OpponentMoveGet)?
-                .parse::<RoShamBo>()
-                .wrap_err("Could not parse opponent's move.")?;
+    super::sample_methods!();
 
-            let player = cols
-                .next()
-                .ok_or(Solver02Error::PlayerMoveGet)?
-                .parse::<RoShamBo>()
-                .wrap_err("Could not parse opponent's move.")?;
+    fn configure(&mut self, config: &super::SolverConfig) -> Result<(), super::ParamError> {
+        self.skip_bad_lines = config.skip_bad_lines;
+        Ok(())
+    }
 
-            let result = player.play_against(&opponent);
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let (player_score, skipped) =
+            score_guide(input, self.skip_bad_lines, |opponent, player: RoShamBo| {
+                let result = player.play_against(&opponent);
 
-            player_score += player.score() + result.score();
-        }
+                player.score() + result.score()
+            })?;
 
         println!("Total player score: {player_score}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(player_score).with_skipped_lines(skipped))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut player_score = 0;
-
-        for line in input.lines() {
-            let line = line?;
-            let line = line.trim();
-
-            if line.is_empty() {
-                continue;
-            }
+        let (player_score, skipped) = score_guide(
+            input,
+            self.skip_bad_lines,
+            |opponent, desired_outcome: RoundOutcome| {
+                let player = RoShamBo::get_desired_play(&opponent, &desired_outcome);
 
-            let mut cols = line.split(' ');
+                let result = player.play_against(&opponent);
 
-            let opponent = cols
-                .next()
-                .ok_or(Solver02Error::OpponentMoveGet)?
-                .parse::<RoShamBo>()
-                .wrap_err("Could not parse opponent's move.")?;
+                assert_eq!(desired_outcome, result);
 
-            let desired_outcome = cols
-                .next()
-                .ok_or(Solver02Error::DesiredRoundOutcomeGet)?
-                .parse::<RoundOutcome>()
-                .wrap_err("Could not parse desired round outcome.")?;
+                player.score() + result.score()
+            },
+        )?;
 
-            let player = RoShamBo::get_desired_play(&opponent, &desired_outcome);
-
-            let result = player.play_against(&opponent);
+        println!("Total player score: {player_score}");
 
-            assert_eq!(desired_outcome, result);
+        Ok(super::Answer::new(player_score).with_skipped_lines(skipped))
+    }
+}
 
-            player_score += player.score() + result.score();
+/// Add up the `score` of every round in the strategy guide, going by the opponent's move and the
+/// second column read as a `T`, along with how many rounds were skipped for not parsing.
+fn score_guide<T>(
+    input: &mut dyn BufRead,
+    skip_bad_lines: bool,
+    mut score: impl FnMut(RoShamBo, T) -> u32,
+) -> Result<(u32, usize), SolverError>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let mut lines = LineParser::new(skip_bad_lines);
+    let mut total = 0;
+    for_each_line(input, |line| {
+        let round = separated_pair(
+            alpha1.parse_from_str::<RoShamBo>(),
+            char(' '),
+            alpha1.parse_from_str::<T>(),
+        );
+        if let Some((opponent, second)) = lines.parse(line, round) {
+            total += score(opponent, second);
         }
+        Ok::<_, std::io::Error>(())
+    })
+    .context("Could not read input file")?;
 
-        println!("Total player score: {player_score}");
-
-        Ok(Box::new(()))
-    }
+    let skipped = lines.finish().context("Could not parse strategy guide")?;
+    Ok((total, skipped))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -183,13 +192,12 @@ enum Solver02Error {
 
     #[error("Could not parse `{0}` as a valid code for Rock, Paper, or Scissors.")]
     RoShamBoParse(String),
+}
 
-    #[error("Unable to get opponent's move.")]
-    OpponentMoveGet,
-
-    #[error("Unable to get player's move.")]
-    PlayerMoveGet,
-
-    #[error("Unable to get desired round outcome.")]
-    DesiredRoundOutcomeGet,
+super::challenge_solver_test_boilerplate! {
+    Solver02::default();
+    2 => {
+        a as u32,
+        b as u32,
+    }
 }
@@ -1,4 +1,6 @@
-use std::{collections::HashSet, io::BufRead};
+use std::io::BufRead;
+
+use crate::{charset::CharSet, error::SolverError, input_iter::for_each_line};
 
 use super::ChallengeSolver;
 
@@ -11,113 +13,64 @@ impl ChallengeSolver for Solver03 {
         3
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut compartment_a = HashSet::new();
-        let mut compartment_b = HashSet::new();
+    super::sample_methods!();
 
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut intersection_priority_sum = 0;
 
-        for line in input.lines() {
-            let line = line?;
+        for_each_line(input, |line| -> Result<(), SolverError> {
             let line = line.trim();
-
-            assert_eq!(
-                line.len() % 2,
-                0,
-                "All bags must contain an even number of items!"
-            );
-
-            let (a, b) = line.split_at(line.len() / 2);
-
-            assert!(
-                a.len() == b.len(),
-                "Each compartment must have same number of items!"
-            );
-
-            for (char_a, char_b) in a.chars().zip(b.chars()) {
-                compartment_a.insert(char_a);
-                compartment_b.insert(char_b);
-            }
-
-            for item in compartment_a.intersection(&compartment_b) {
-                intersection_priority_sum += item_priority(*item);
+            if line.len() % 2 != 0 {
+                return Err(SolverError::invalid_input(format!(
+                    "`{line}` has an odd number of items, so it can't be split into two \
+                     compartments"
+                )));
             }
 
-            compartment_a.drain();
-            compartment_b.drain();
-        }
+            let (a, b) = line.split_at(line.len() / 2);
+            let common = items(a)? & items(b)?;
+            intersection_priority_sum += common.priority_sum();
+            Ok(())
+        })?;
 
         println!("Interseciton item priority sum: {intersection_priority_sum}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(intersection_priority_sum))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut elf_one = HashSet::new();
-        let mut elf_two = HashSet::new();
-        let mut elf_three = HashSet::new();
+        let mut badge = CharSet::ALL;
         let mut intersection_priority_sum = 0;
 
-        for (i, line) in input.lines().enumerate() {
-            let line = line?;
-            let line = line.trim();
-
-            if i % 3 == 0 {
-                // first elf
-                for item in line.chars() {
-                    elf_one.insert(item);
-                }
-            } else if i % 3 == 1 {
-                // second elf
-                for item in line.chars() {
-                    elf_two.insert(item);
-                }
-            } else if i % 3 == 2 {
-                // third elf
-                for item in line.chars() {
-                    elf_three.insert(item);
-                }
-
-                // The badge is the only item common between all three elves
-                for common_item in elf_one
-                    .iter()
-                    .filter(|item| elf_two.contains(item))
-                    .filter(|item| elf_three.contains(item))
-                {
-                    intersection_priority_sum += item_priority(*common_item);
-                }
-
-                // drain all three elf hashsets for the next group
-                elf_one.drain();
-                elf_two.drain();
-                elf_three.drain();
+        let mut i = 0;
+        for_each_line(input, |line| -> Result<(), SolverError> {
+            // The badge is the only item common between all three elves in a group.
+            badge &= items(line.trim())?;
+            if i % 3 == 2 {
+                intersection_priority_sum += badge.priority_sum();
+                badge = CharSet::ALL;
             }
-        }
+
+            i += 1;
+            Ok(())
+        })?;
 
         println!("Interseciton item priority sum: {intersection_priority_sum}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(intersection_priority_sum))
     }
 }
 
-fn item_priority(item: char) -> u32 {
-    match item {
-        item @ 'a'..='z' => item as u32 - 96,
-        item @ 'A'..='Z' => item as u32 - 38,
-        _ => u32::MAX,
-    }
+/// The items in a rucksack, or in one of its compartments.
+fn items(line: &str) -> Result<CharSet, SolverError> {
+    CharSet::from_letters(line)
+        .map_err(|e| SolverError::invalid_input(format!("Bad item in `{line}`: {e}")))
 }
 
-#[test]
-fn test_item_priority() {
-    assert_eq!(item_priority('a'), 1);
-    assert_eq!(item_priority('t'), 20);
-    assert_eq!(item_priority('z'), 26);
-
-    assert_eq!(item_priority('A'), 27);
-    assert_eq!(item_priority('D'), 30);
-    assert_eq!(item_priority('Z'), 52);
-
-    assert_eq!(item_priority(' '), u32::MAX);
-    assert_eq!(item_priority('😅'), u32::MAX);
+super::challenge_solver_test_boilerplate! {
+    Solver03;
+    3 => {
+        a as u32,
+        b as u32,
+    }
 }
@@ -1,9 +1,30 @@
 use std::{io::BufRead, ops::RangeInclusive};
 
+use itertools::Itertools;
+use nom::{
+    character::complete::{char, u32},
+    combinator::map,
+    multi::separated_list1,
+    sequence::separated_pair,
+    IResult,
+};
+use nom_supreme::error::ErrorTree;
+use serde_json::{json, Value};
+
+use crate::{
+    error::{Context, SolverError},
+    input_iter::for_each_line,
+    interval::IntervalSet,
+    parse::LineParser,
+};
+
 use super::ChallengeSolver;
 
 #[derive(Debug, Default)]
-pub struct Solver04;
+pub struct Solver04 {
+    /// Leave out groups that don't parse, instead of failing.
+    skip_bad_lines: bool,
+}
 
 impl ChallengeSolver for Solver04 {
     #[inline]
@@ -11,79 +32,162 @@ impl ChallengeSolver for Solver04 {
         4
     }
 
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &super::SolverConfig) -> Result<(), super::ParamError> {
+        self.skip_bad_lines = config.skip_bad_lines;
+        Ok(())
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut containing_range_count = 0;
-
-        for line in input.lines() {
-            let line = line?;
-            let line = line.trim();
-
-            let (first_range_str, second_range_str) = line.split_once(',').unwrap();
-
-            let (first_range_lower_bound, first_range_upper_bound) = first_range_str
-                .split_once('-')
-                .map(|(a, b)| (a.parse::<u32>().unwrap(), b.parse::<u32>().unwrap()))
-                .unwrap();
-            let first_range = first_range_lower_bound..=first_range_upper_bound;
-
-            let (second_range_lower_bound, second_range_upper_bound) = second_range_str
-                .split_once('-')
-                .map(|(a, b)| (a.parse::<u32>().unwrap(), b.parse::<u32>().unwrap()))
-                .unwrap();
-            let second_range = second_range_lower_bound..=second_range_upper_bound;
-
-            if range_contains_other(&first_range, &second_range)
-                || range_contains_other(&second_range, &first_range)
-            {
-                println!("Found containing range pair: {first_range:?} and {second_range:?}");
-                containing_range_count += 1;
+        let (groups, skipped) = parse_groups(input, self.skip_bad_lines)?;
+        let mut containing_range_count = 0_usize;
+
+        for group in &groups {
+            for (first_range, second_range) in group.iter().tuple_combinations() {
+                if IntervalSet::from(first_range.clone()).contains_range(second_range)
+                    || IntervalSet::from(second_range.clone()).contains_range(first_range)
+                {
+                    println!("Found containing range pair: {first_range:?} and {second_range:?}");
+                    containing_range_count += 1;
+                }
             }
         }
 
         println!("Containing range count: {containing_range_count}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(containing_range_count)
+            .with_metadata("groups", group_stats(&groups))
+            .with_skipped_lines(skipped))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut overlapping_range_count = 0;
+        let (groups, skipped) = parse_groups(input, self.skip_bad_lines)?;
+        let mut overlapping_range_count = 0_usize;
+
+        for group in &groups {
+            for (first_range, second_range) in group.iter().tuple_combinations() {
+                if IntervalSet::from(first_range.clone()).overlaps(second_range) {
+                    println!("Found overlapping range pair: {first_range:?} and {second_range:?}");
+                    overlapping_range_count += 1;
+                }
+            }
+        }
 
-        for line in input.lines() {
-            let line = line?;
-            let line = line.trim();
+        println!("Overlapping range count: {overlapping_range_count}");
 
-            let (first_range_str, second_range_str) = line.split_once(',').unwrap();
+        Ok(super::Answer::new(overlapping_range_count)
+            .with_metadata("groups", group_stats(&groups))
+            .with_skipped_lines(skipped))
+    }
+}
 
-            let (first_range_lower_bound, first_range_upper_bound) = first_range_str
-                .split_once('-')
-                .map(|(a, b)| (a.parse::<u32>().unwrap(), b.parse::<u32>().unwrap()))
-                .unwrap();
-            let first_range = first_range_lower_bound..=first_range_upper_bound;
+/// Parse every line of the input into a group of section assignments, like `2-4,6-8`.
+///
+/// The puzzle only ever pairs the elves up, but any number of assignments can be given per line.
+/// Also returns how many lines were skipped for not parsing.
+fn parse_groups(
+    input: &mut dyn BufRead,
+    skip_bad_lines: bool,
+) -> Result<(Vec<Vec<Assignment>>, usize), SolverError> {
+    let mut lines = LineParser::new(skip_bad_lines);
+    let mut groups = Vec::new();
+    for_each_line(input, |line| {
+        groups.extend(lines.parse(line, separated_list1(char(','), parse_assignment)));
+        Ok::<_, std::io::Error>(())
+    })
+    .context("Could not read input file")?;
+
+    let skipped = lines
+        .finish()
+        .context("Could not parse section assignments")?;
+    Ok((groups, skipped))
+}
 
-            let (second_range_lower_bound, second_range_upper_bound) = second_range_str
-                .split_once('-')
-                .map(|(a, b)| (a.parse::<u32>().unwrap(), b.parse::<u32>().unwrap()))
-                .unwrap();
-            let second_range = second_range_lower_bound..=second_range_upper_bound;
+/// The range of sections that one elf is assigned to clean.
+type Assignment = RangeInclusive<i64>;
 
-            if ranges_overlap(&first_range, &second_range) {
-                println!("Found overlapping range pair: {first_range:?} and {second_range:?}");
-                overlapping_range_count += 1;
-            }
+fn parse_assignment(i: &str) -> IResult<&str, Assignment, ErrorTree<&str>> {
+    map(separated_pair(u32, char('-'), u32), |(start, end)| {
+        i64::from(start)..=i64::from(end)
+    })(i)
+}
+
+/// How the assignments in each group overlap all at once, rather than pair by pair.
+fn group_stats(groups: &[Vec<Assignment>]) -> Value {
+    let mut fully_shared_groups = 0;
+    let mut shared_sections = 0;
+    let mut covered_sections = 0;
+
+    for group in groups {
+        let shared = group
+            .iter()
+            .map(|assignment| IntervalSet::from(assignment.clone()))
+            .reduce(|shared, assignment| shared.intersection(&assignment))
+            .unwrap_or_default();
+        if !shared.is_empty() {
+            fully_shared_groups += 1;
         }
+        shared_sections += shared.len();
+        covered_sections += group.iter().cloned().collect::<IntervalSet>().len();
+    }
 
-        println!("Overlapping range count: {overlapping_range_count}");
+    json!({
+        "count": groups.len(),
+        "largest": groups.iter().map(Vec::len).max().unwrap_or(0),
+        // Groups where there's a section that every elf in the group has to clean.
+        "fully_shared": fully_shared_groups,
+        "shared_sections": shared_sections,
+        "covered_sections": covered_sections,
+    })
+}
 
-        Ok(Box::new(()))
+super::challenge_solver_test_boilerplate! {
+    Solver04::default();
+    4 => {
+        a as usize,
+        b as usize,
     }
-}
 
-#[inline]
-fn range_contains_other(range: &RangeInclusive<u32>, other: &RangeInclusive<u32>) -> bool {
-    range.start() <= other.start() && other.end() <= range.end()
-}
+    #[test]
+    fn counts_pairs_within_bigger_groups() -> Result<(), SolverError> {
+        let input = "1-10,2-5,4-9\n1-2,3-4,5-6\n";
+
+        let a = Solver04::default().solve_a(&mut Cursor::new(input))?;
+        assert_eq!(a.downcast_ref::<usize>(), Some(&2));
+        let b = Solver04::default().solve_b(&mut Cursor::new(input))?;
+        assert_eq!(b.downcast_ref::<usize>(), Some(&3));
+
+        assert_eq!(
+            b.metadata()["groups"],
+            json!({
+                "count": 2,
+                "largest": 3,
+                "fully_shared": 1,
+                "shared_sections": 2,
+                "covered_sections": 16,
+            })
+        );
+        Ok(())
+    }
 
-#[inline]
-fn ranges_overlap(a: &RangeInclusive<u32>, b: &RangeInclusive<u32>) -> bool {
-    a.start() <= b.end() && b.start() <= a.end()
+    #[test]
+    fn skips_corrupted_assignments_when_asked_to() -> Result<(), SolverError> {
+        let input = "2-4,6-8\n2-8,3-7\n2-#,4-8\n6-6,4-6\n\n2-6,4-";
+        assert!(Solver04::default().solve_a(&mut Cursor::new(input)).is_err());
+
+        let mut solver = Solver04::default();
+        solver.configure(&crate::solver::SolverConfig {
+            skip_bad_lines: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let a = solver.solve_a(&mut Cursor::new(input))?;
+        assert_eq!(a.downcast_ref::<usize>(), Some(&2));
+        assert_eq!(a.metadata()["skipped_lines"], 3);
+
+        let a = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert!(!a.metadata().contains_key("skipped_lines"));
+        Ok(())
+    }
 }
@@ -1,13 +1,25 @@
-use std::io::BufRead;
+use std::{fmt, io::BufRead};
 
-use itertools::Itertools;
+use nom::{character::complete::u64, combinator::map, sequence::tuple, IResult};
+use nom_supreme::{error::ErrorTree, tag::complete::tag};
 
-use super::ChallengeSolver;
+use crate::{
+    error::{Context, SolverError},
+    simulation::Pacing,
+};
 
-type Crate = String;
+use super::{Answer, ChallengeSolver, Frontend, ParamError, SolverConfig};
+
+#[cfg(feature = "tui")]
+mod viewer;
 
 #[derive(Debug, Default)]
-pub struct Solver05;
+pub struct Solver05 {
+    /// Watch the crane rearrange the stacks in a TUI, instead of just printing the answer.
+    visualize: bool,
+    /// How fast the crane moves in the TUI.
+    pacing: Pacing,
+}
 
 impl ChallengeSolver for Solver05 {
     #[inline]
@@ -15,248 +27,411 @@ impl ChallengeSolver for Solver05 {
         5
     }
 
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        self.pacing = Pacing::from_params(&config.params)?;
+        Ok(())
+    }
+
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        // We can assume that there will always be 9 stacks of crates.
-        const EMPTY_STACK: Vec<Crate> = Vec::new();
-        let mut stacks: [Vec<Crate>; 9] = [EMPTY_STACK; 9];
-        let mut stacks_built = false;
-
-        for line in input.lines() {
-            clear_terminal();
-            let line = line?;
-
-            // First, build up the stacks...
-            if !stacks_built {
-                if line[1..2].chars().next().unwrap().is_numeric() {
-                    // If the first non-whitespace character is a number, we've reached the stack labels.
-                    stacks_built = true;
-
-                    // At this point, the stacks are actually upside-down. Flip them!
-                    for stack in stacks.iter_mut() {
-                        let reversed = stack.iter().rev().cloned().collect::<Vec<_>>();
-                        *stack = reversed;
-                    }
-                } else {
-                    // Otherwise, just keep accumulating crates into stacks.
-
-                    // Once the line is trimmed, crate labels only occur in columns 2, 6, 10, 14, 18,
-                    // 22, 26, 30, and 34.
-                    for (i, (_, chr)) in line
-                        .char_indices()
-                        .filter(|(i, _)| [1, 5, 9, 13, 17, 21, 25, 29, 33].contains(i))
-                        .enumerate()
-                    {
-                        if !chr.is_whitespace() {
-                            stacks[i].push(chr.to_string());
-                        }
-                    }
-                    println!();
-                }
-            } else {
-                // Once the stacks are built, start processing moves.
-                let line = line.trim();
-                if line.is_empty() {
-                    continue;
-                }
+        self.rearrange(input, CrateMover::Model9000)
+    }
 
-                // We display the stack *before* each move.
-                print_stacks(&stacks);
-
-                // Parse the move command.
-                let mut move_count: usize = 0;
-                let mut src: usize = 0;
-                let mut dest: usize = 0;
-
-                for (keyword, param) in line.split_whitespace().tuples() {
-                    match keyword {
-                        "move" => {
-                            move_count = param.parse().unwrap();
-                        }
-                        "from" => {
-                            src = param.parse().unwrap();
-                        }
-                        "to" => {
-                            dest = param.parse().unwrap();
-                        }
-                        something_else => {
-                            return Err(color_eyre::eyre::eyre!(
-                                "Unknown keyword: {something_else}"
-                            ))
-                        }
-                    }
-                }
+    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        self.rearrange(input, CrateMover::Model9001)
+    }
+}
 
-                // Execute the move command.
-                for _ in 0..move_count {
-                    if let Some(crte) = stacks[src - 1].pop() {
-                        stacks[dest - 1].push(crte);
-                    }
-                }
+impl Solver05 {
+    /// Carry out every move in the input with `crane`, and answer with the crates that end up on
+    /// top of each stack.
+    fn rearrange(
+        &self,
+        input: &mut dyn BufRead,
+        crane: CrateMover,
+    ) -> super::ChallengeSolverResult {
+        let (stacks, moves) = parse_input(input)?;
+
+        #[cfg(feature = "tui")]
+        let stacks = if self.visualize {
+            viewer::watch(stacks, moves, crane, self.pacing)?
+        } else {
+            stacks.rearranged(&moves, crane)?
+        };
+        #[cfg(not(feature = "tui"))]
+        let stacks = stacks.rearranged(&moves, crane)?;
+
+        let stack_tops = stacks.tops();
+        println!("Stack tops: {stack_tops}");
+
+        Ok(Answer::new(stack_tops))
+    }
+}
+
+/// Which model of crane is doing the rearranging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CrateMover {
+    /// Moves crates one at a time, so the crates in each move end up in reverse order.
+    Model9000,
+    /// Moves several crates at once, so the crates in each move keep their order.
+    Model9001,
+}
+
+impl fmt::Display for CrateMover {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Model9000 => write!(f, "CrateMover 9000"),
+            Self::Model9001 => write!(f, "CrateMover 9001"),
+        }
+    }
+}
 
-                println!("Moving {move_count} crates from stack {src} to stack {dest}...");
+/// Stacks of crates, each listed from the bottom up by their labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Stacks(Vec<Vec<String>>);
+
+impl Stacks {
+    /// Parse the drawing of the starting stacks, including the row of stack numbers at the
+    /// bottom.
+    ///
+    /// Crates can have labels of any length, like `[AB]` or `[🍎]`, as long as each one sits over
+    /// its stack's number. Columns are counted in characters, not bytes.
+    fn parse(drawing: &str) -> Result<Self, SolverError> {
+        let mut lines = drawing.lines().rev();
+        let footer = lines
+            .next()
+            .ok_or_else(|| SolverError::invalid_input("The drawing of the stacks is empty"))?;
+        let columns = stack_columns(footer)?;
+
+        let mut stacks = vec![Vec::new(); columns.len()];
+        for line in lines {
+            for (span, label) in crates(line)? {
+                let stack = columns
+                    .iter()
+                    .position(|column| column.start <= span.end && span.start <= column.end)
+                    .ok_or_else(|| {
+                        SolverError::invalid_input(format!(
+                            "Crate [{label}] isn't above any of the {} stacks",
+                            columns.len()
+                        ))
+                    })?;
+                stacks[stack].push(label);
             }
         }
 
-        print_stacks(&stacks);
+        Ok(Self(stacks))
+    }
 
-        let stack_tops = stacks
-            .into_iter()
-            .map(|stack| stack.last().unwrap().clone())
-            .reduce(|acc, s| acc + &s)
-            .unwrap();
+    /// The stack labelled `number`, counting from 1.
+    fn stack_mut(&mut self, number: usize) -> Result<&mut Vec<String>, SolverError> {
+        number
+            .checked_sub(1)
+            .and_then(|i| self.0.get_mut(i))
+            .ok_or_else(|| SolverError::invalid_input(format!("There is no stack {number}")))
+    }
 
-        println!("\n\nStack tops: {stack_tops}");
+    /// Carry out a single move with `crane`.
+    fn apply(&mut self, mv: Move, crane: CrateMover) -> Result<(), SolverError> {
+        let from = self.stack_mut(mv.from)?;
+        let height = from.len();
+        let start = height.checked_sub(mv.count).ok_or_else(|| {
+            SolverError::invalid_input(format!(
+                "Can't {mv}, since stack {} only has {height} crate(s)",
+                mv.from
+            ))
+        })?;
+
+        let mut lifted = from.split_off(start);
+        if crane == CrateMover::Model9000 {
+            lifted.reverse();
+        }
+        self.stack_mut(mv.to)?.extend(lifted);
 
-        Ok(Box::new(()))
+        Ok(())
     }
 
-    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        // We can assume that there will always be 9 stacks of crates.
-        const EMPTY_STACK: Vec<Crate> = Vec::new();
-        let mut stacks: [Vec<Crate>; 9] = [EMPTY_STACK; 9];
-        let mut stacks_built = false;
-
-        for line in input.lines() {
-            clear_terminal();
-            let line = line?;
-
-            // First, build up the stacks...
-            if !stacks_built {
-                if line[1..2].chars().next().unwrap().is_numeric() {
-                    // If the first non-whitespace character is a number, we've reached the stack labels.
-                    stacks_built = true;
-
-                    // At this point, the stacks are actually upside-down. Flip them!
-                    for stack in stacks.iter_mut() {
-                        let reversed = stack.iter().rev().cloned().collect::<Vec<_>>();
-                        *stack = reversed;
-                    }
-                } else {
-                    // Otherwise, just keep accumulating crates into stacks.
-
-                    // Once the line is trimmed, crate labels only occur in columns 2, 6, 10, 14, 18,
-                    // 22, 26, 30, and 34.
-                    for (i, (_, chr)) in line
-                        .char_indices()
-                        .filter(|(i, _)| [1, 5, 9, 13, 17, 21, 25, 29, 33].contains(i))
-                        .enumerate()
-                    {
-                        if !chr.is_whitespace() {
-                            stacks[i].push(chr.to_string());
-                        }
-                    }
-                    println!();
-                }
-            } else {
-                // Once the stacks are built, start processing moves.
-                let line = line.trim();
-                if line.is_empty() {
-                    continue;
-                }
+    /// The stacks after `crane` has carried out every one of `moves`.
+    fn rearranged(mut self, moves: &[Move], crane: CrateMover) -> Result<Self, SolverError> {
+        for &mv in moves {
+            self.apply(mv, crane)?;
+        }
+        Ok(self)
+    }
 
-                // We display the stack *before* each move.
-                print_stacks(&stacks);
-
-                // Parse the move command.
-                let mut move_count: usize = 0;
-                let mut src: usize = 0;
-                let mut dest: usize = 0;
-
-                for (keyword, param) in line.split_whitespace().tuples() {
-                    match keyword {
-                        "move" => {
-                            move_count = param.parse().unwrap();
-                        }
-                        "from" => {
-                            src = param.parse().unwrap();
-                        }
-                        "to" => {
-                            dest = param.parse().unwrap();
-                        }
-                        something_else => {
-                            return Err(color_eyre::eyre::eyre!(
-                                "Unknown keyword: {something_else}"
-                            ))
-                        }
-                    }
-                }
+    /// The label of the crate on top of each stack, all together. Empty stacks are skipped.
+    fn tops(&self) -> String {
+        self.0
+            .iter()
+            .filter_map(|stack| stack.last().map(String::as_str))
+            .collect()
+    }
+}
 
-                // Execute the move command.
-                let mut buf = Vec::new();
-                for _ in 0..move_count {
-                    if let Some(crte) = stacks[src - 1].pop() {
-                        buf.push(crte);
-                    }
-                }
+/// The first and last columns that something in a line of the drawing takes up, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: usize,
+    end: usize,
+}
 
-                stacks[dest - 1].extend(buf.into_iter().rev());
+/// Where each stack's number is in the row of numbers at the bottom of the drawing. The numbers
+/// have to count up from 1, since that's how the moves refer to the stacks.
+fn stack_columns(footer: &str) -> Result<Vec<Span>, SolverError> {
+    let mut columns = Vec::<Span>::new();
+    let mut chars = footer.chars().enumerate().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut number = c.to_string();
+        let mut end = start;
+        while let Some((i, c)) = chars.next_if(|(_, c)| !c.is_whitespace()) {
+            number.push(c);
+            end = i;
+        }
 
-                println!("Moving {move_count} crates from stack {src} to stack {dest}...");
+        let expected = columns.len() + 1;
+        if number != expected.to_string() {
+            return Err(SolverError::invalid_input(format!(
+                "Expected stack number {expected} at the bottom of the drawing, but found \
+                 `{number}`"
+            )));
+        }
+        columns.push(Span { start, end });
+    }
+    Ok(columns)
+}
+
+/// Every crate in a line of the drawing, like `[A]` or `[AB]`, with where it is and its label.
+fn crates(line: &str) -> Result<Vec<(Span, String)>, SolverError> {
+    let mut crates = Vec::new();
+    let mut chars = line.chars().enumerate();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => continue,
+            '[' => {}
+            c => {
+                return Err(SolverError::invalid_input(format!(
+                    "Expected a crate like `[A]` in `{line}`, but found `{c}` in column {}",
+                    start + 1
+                )))
             }
         }
 
-        print_stacks(&stacks);
+        let mut label = String::new();
+        let end = loop {
+            match chars.next() {
+                Some((end, ']')) => break end,
+                Some((_, c)) => label.push(c),
+                None => {
+                    return Err(SolverError::invalid_input(format!(
+                        "The crate in column {} of `{line}` is never closed with `]`",
+                        start + 1
+                    )))
+                }
+            }
+        };
+        if label.trim().is_empty() {
+            return Err(SolverError::invalid_input(format!(
+                "The crate in column {} of `{line}` has no label",
+                start + 1
+            )));
+        }
+        crates.push((Span { start, end }, label));
+    }
+    Ok(crates)
+}
 
-        let stack_tops = stacks
-            .into_iter()
-            .map(|stack| stack.last().unwrap().clone())
-            .reduce(|acc, s| acc + &s)
-            .unwrap();
+/// An instruction to move `count` crates from the top of one stack to the top of another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move {
+    count: usize,
+    from: usize,
+    to: usize,
+}
 
-        println!("\n\nStack tops: {stack_tops}");
+impl Move {
+    fn parse(i: &str) -> IResult<&str, Self, ErrorTree<&str>> {
+        let number = || map(u64, |n| n as usize);
+        map(
+            tuple((
+                tag("move "),
+                number(),
+                tag(" from "),
+                number(),
+                tag(" to "),
+                number(),
+            )),
+            |(_, count, _, from, _, to)| Self { count, from, to },
+        )(i)
+    }
+}
 
-        Ok(Box::new(()))
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
     }
 }
 
-fn clear_terminal() {
-    print!("\x1B[2J");
+/// The stacks partway through being rearranged.
+#[derive(Debug, Clone)]
+struct State {
+    stacks: Stacks,
+    /// The index of the move that was just (partly) carried out, if any.
+    move_index: Option<usize>,
+    /// How many crates were just put down on the destination stack of that move.
+    placed: usize,
 }
 
-fn print_stacks(stacks: &[Vec<Crate>; 9]) {
-    print!("\x1B[1;1H");
+/// Every state that the stacks pass through while `crane` carries out `moves`, starting with
+/// `stacks` itself.
+///
+/// The CrateMover 9000 lifts one crate at a time, so every crate that it moves gets its own
+/// state. The CrateMover 9001 moves a whole batch of crates in one go.
+fn rearrangement_states(
+    stacks: Stacks,
+    moves: &[Move],
+    crane: CrateMover,
+) -> Result<Vec<State>, SolverError> {
+    let mut states = vec![State {
+        stacks,
+        move_index: None,
+        placed: 0,
+    }];
+
+    for (move_index, &mv) in moves.iter().enumerate() {
+        let steps = match crane {
+            CrateMover::Model9000 => vec![Move { count: 1, ..mv }; mv.count],
+            CrateMover::Model9001 => vec![mv],
+        };
+
+        for step in steps {
+            let mut stacks = states.last().unwrap().stacks.clone();
+            stacks.apply(step, crane)?;
+            states.push(State {
+                stacks,
+                move_index: Some(move_index),
+                placed: step.count,
+            });
+        }
+    }
 
-    let mut grid = Vec::new();
+    Ok(states)
+}
 
-    let tallest_stack = stacks
-        .iter()
-        .map(|stack| stack.len())
-        .reduce(|acc, l| acc.max(l))
-        .unwrap();
+fn parse_input(input: &mut dyn BufRead) -> Result<(Stacks, Vec<Move>), SolverError> {
+    let mut input_buf = String::new();
+    input
+        .read_to_string(&mut input_buf)
+        .context("Could not read input file to string")?;
+    let input_buf = input_buf.replace("\r\n", "\n");
 
-    for (i, stack) in stacks.iter().enumerate() {
-        grid.push(vec![None; tallest_stack]);
-        for (j, crte) in stack.iter().enumerate() {
-            grid[i][tallest_stack - 1 - j] = Some(crte);
-        }
+    let (drawing, moves) = input_buf.split_once("\n\n").ok_or_else(|| {
+        SolverError::invalid_input(
+            "Expected a blank line between the drawing of the stacks and the moves",
+        )
+    })?;
+
+    let stacks = Stacks::parse(drawing).context("Could not parse the drawing of the stacks")?;
+    let moves = crate::parse::parse_lines(moves.trim_end(), Move::parse)
+        .context("Could not parse moves")?;
+
+    Ok((stacks, moves))
+}
+
+super::challenge_solver_test_boilerplate! {
+    Solver05::default();
+    5 => {
+        a as String,
+        b as String,
     }
 
-    let grid = transpose(grid);
+    #[test]
+    fn states_follow_each_crate() -> Result<(), SolverError> {
+        let (stacks, moves) = parse_input(&mut SAMPLE_INPUT.as_bytes())?;
 
-    for line in grid.iter() {
-        for crte in line.iter() {
-            if let Some(crte) = crte {
-                print!("[{crte}] ");
-            } else {
-                print!("    ");
-            }
-        }
-        println!();
+        // The 9000 gets a state per crate moved, and the 9001 a state per move.
+        let states_9000 = rearrangement_states(stacks.clone(), &moves, CrateMover::Model9000)?;
+        assert_eq!(states_9000.len(), 1 + 1 + 3 + 2 + 1);
+        let states_9001 = rearrangement_states(stacks, &moves, CrateMover::Model9001)?;
+        assert_eq!(states_9001.len(), 1 + moves.len());
+
+        assert_eq!(states_9000[2].stacks.0[2], ["P", "D"]);
+        assert_eq!(states_9001[2].stacks.0[2], ["P", "Z", "N", "D"]);
+        assert_eq!(states_9001[2].placed, 3);
+        assert_eq!(states_9000.last().unwrap().stacks.tops(), "CMZ");
+
+        Ok(())
     }
 
-    println!(" 1   2   3   4   5   6   7   8   9\n");
-}
+    #[test]
+    fn rejects_moves_from_missing_stacks() -> Result<(), SolverError> {
+        let (mut stacks, _) = parse_input(&mut SAMPLE_INPUT.as_bytes())?;
+        let mv = Move { count: 1, from: 4, to: 1 };
+        assert!(stacks.apply(mv, CrateMover::Model9000).is_err());
+        let mv = Move { count: 5, from: 1, to: 2 };
+        assert!(stacks.apply(mv, CrateMover::Model9001).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn finds_stacks_by_their_numbers_past_nine() -> Result<(), SolverError> {
+        let drawing = "\
+[A]                                             [L]
+[B] [C] [D] [E] [F] [G] [H] [I] [J] [K]     [M] [N]
+ 1   2   3   4   5   6   7   8   9  10  11  12  13";
+        let stacks = Stacks::parse(drawing)?;
+        assert_eq!(stacks.0.len(), 13);
+        assert_eq!(stacks.0[0], ["B", "A"]);
+        assert_eq!(stacks.0[9], ["K"]);
+        assert!(stacks.0[10].is_empty());
+        assert_eq!(stacks.0[12], ["N", "L"]);
+
+        let input = format!("{drawing}\n\nmove 2 from 13 to 11\nmove 1 from 1 to 12\n");
+        let stacks = rearrange(&input, CrateMover::Model9001)?;
+        assert_eq!(stacks.0[10], ["N", "L"]);
+        assert_eq!(stacks.tops(), "BCDEFGHIJKLA");
+        Ok(())
+    }
+
+    #[test]
+    fn reads_labels_of_any_width() -> Result<(), SolverError> {
+        let drawing = concat!(
+            "     [🍎]\n",
+            "[Ab] [🍐]  [Été]\n",
+            " 1    2     3",
+        );
+        let stacks = Stacks::parse(drawing)?;
+        assert_eq!(stacks.0, [vec!["Ab"], vec!["🍐", "🍎"], vec!["Été"]]);
+
+        let input = format!("{drawing}\n\nmove 2 from 2 to 3\n");
+        assert_eq!(rearrange(&input, CrateMover::Model9000)?.tops(), "Ab🍐");
+        assert_eq!(rearrange(&input, CrateMover::Model9001)?.tops(), "Ab🍎");
+        Ok(())
+    }
 
-fn transpose<T>(v: Vec<Vec<T>>) -> Vec<Vec<T>> {
-    assert!(!v.is_empty());
-    let len = v[0].len();
-    let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
-    (0..len)
-        .map(|_| {
-            iters
-                .iter_mut()
-                .map(|n| n.next().unwrap())
-                .collect::<Vec<T>>()
-        })
-        .collect()
+    #[test]
+    fn rejects_crates_it_cant_place() {
+        for drawing in [
+            "[A] [B]\n 1",
+            "[A] [B\n 1   2",
+            "[A] B\n 1   2",
+            "[A] []\n 1   2",
+            "[A] [B]\n 1   3",
+        ] {
+            assert!(Stacks::parse(drawing).is_err(), "{drawing}");
+        }
+    }
+
+    /// Parse `input`, and rearrange the stacks with `crane`.
+    fn rearrange(input: &str, crane: CrateMover) -> Result<Stacks, SolverError> {
+        let (stacks, moves) = parse_input(&mut input.as_bytes())?;
+        stacks.rearranged(&moves, crane)
+    }
 }
@@ -0,0 +1,279 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    error::SolverError,
+    simulation::{Pacing, StepResult},
+    tui_app::{self, Control, TuiApp},
+};
+
+use super::{rearrangement_states, CrateMover, Move, Stacks, State};
+
+/// Watch `crane` carry out `moves` on `stacks`, and return the stacks once they're done.
+pub(super) fn watch(
+    stacks: Stacks,
+    moves: Vec<Move>,
+    crane: CrateMover,
+    pacing: Pacing,
+) -> Result<Stacks, SolverError> {
+    let states = rearrangement_states(stacks, &moves, crane)?;
+    let mut viewer = Viewer::new(crane, moves, states);
+    tui_app::run(&mut viewer, 30.0, pacing)?;
+    Ok(viewer.into_final_stacks())
+}
+
+/// Animates a crane rearranging the stacks of crates.
+///
+/// Steps through states made by [`super::rearrangement_states`], drawing the stacks as columns and
+/// highlighting the crates that were just put down.
+pub(super) struct Viewer {
+    crane: CrateMover,
+    moves: Vec<Move>,
+    states: Vec<State>,
+    current: usize,
+    running: bool,
+}
+
+impl Viewer {
+    pub(super) fn new(crane: CrateMover, moves: Vec<Move>, states: Vec<State>) -> Self {
+        assert!(!states.is_empty(), "there has to be an initial state");
+        Self {
+            crane,
+            moves,
+            states,
+            current: 0,
+            running: false,
+        }
+    }
+
+    /// The stacks after every move has been carried out, no matter how far the animation got.
+    pub(super) fn into_final_stacks(mut self) -> Stacks {
+        self.states.pop().unwrap().stacks
+    }
+
+    fn state(&self) -> &State {
+        &self.states[self.current]
+    }
+
+    fn finished(&self) -> bool {
+        self.current == self.states.len() - 1
+    }
+
+    fn step_forward(&mut self) {
+        if self.finished() {
+            self.running = false;
+        } else {
+            self.current += 1;
+        }
+    }
+
+    fn step_back(&mut self) {
+        self.current = self.current.saturating_sub(1);
+    }
+
+    fn stack_lines(&self) -> Vec<Spans<'static>> {
+        let state = self.state();
+        let stacks = &state.stacks.0;
+        let tallest = stacks.iter().map(Vec::len).max().unwrap_or(0);
+        // Every stack is as wide as the widest label, so that labels of different lengths line up.
+        let width = stacks
+            .iter()
+            .flatten()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(1);
+
+        // The crates that were just put down are on top of the destination stack.
+        let placed_on = state.move_index.map(|i| self.moves[i].to - 1);
+        let crate_style = |stack: usize, height: usize| {
+            let just_placed =
+                placed_on == Some(stack) && height + state.placed >= stacks[stack].len();
+            if just_placed {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            }
+        };
+
+        let mut lines = (0..tallest)
+            .rev()
+            .map(|height| {
+                stacks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, stack)| match stack.get(height) {
+                        Some(crte) => {
+                            Span::styled(format!("[{crte:^width$}] "), crate_style(i, height))
+                        }
+                        None => Span::raw(" ".repeat(width + 3)),
+                    })
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect::<Vec<Spans>>();
+
+        lines.push(Spans::from(
+            (1..=stacks.len())
+                .map(|n| format!("{n:^w$} ", w = width + 2))
+                .collect::<String>(),
+        ));
+        lines
+    }
+
+    fn status_lines(&self) -> Vec<Spans<'static>> {
+        let label = Style::default().add_modifier(Modifier::BOLD);
+
+        let state = if self.finished() {
+            Span::styled("DONE", Style::default().fg(Color::Red))
+        } else if self.running {
+            Span::styled("RUNNING", Style::default().fg(Color::Green))
+        } else {
+            Span::styled("PAUSED", Style::default().fg(Color::Yellow))
+        };
+
+        vec![
+            Spans::from(vec![Span::styled("state: ", label), state]),
+            Spans::from(vec![
+                Span::styled("crane: ", label),
+                Span::raw(self.crane.to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("step:  ", label),
+                Span::raw(format!("{}/{}", self.current, self.states.len() - 1)),
+            ]),
+            Spans::from(vec![
+                Span::styled("tops:  ", label),
+                Span::raw(self.state().stacks.tops()),
+            ]),
+        ]
+    }
+
+    fn render_stacks<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let lines = self.stack_lines();
+
+        // Keep the bottom of the stacks in view when they're too tall to fit.
+        let visible = area.height.saturating_sub(2) as usize;
+        let scroll = lines.len().saturating_sub(visible) as u16;
+
+        let stacks = Paragraph::new(lines)
+            .block(Block::default().title("Stacks").borders(Borders::ALL))
+            .scroll((scroll, 0));
+        f.render_widget(stacks, area);
+    }
+
+    fn render_moves<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let items = self
+            .moves
+            .iter()
+            .map(|mv| ListItem::new(mv.to_string()))
+            .collect::<Vec<_>>();
+
+        let list = List::new(items)
+            .block(Block::default().title("Moves").borders(Borders::ALL))
+            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default();
+        state.select(self.state().move_index);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+impl TuiApp for Viewer {
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(28)])
+            .split(outer[0]);
+
+        let sidebar = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(6), Constraint::Min(0)])
+            .split(columns[1]);
+
+        self.render_stacks(f, columns[0]);
+
+        let status = Paragraph::new(self.status_lines())
+            .block(Block::default().title("Crane").borders(Borders::ALL));
+        f.render_widget(status, sidebar[0]);
+
+        self.render_moves(f, sidebar[1]);
+
+        let help = Paragraph::new(Spans::from(
+            "[space] run/pause  [s/→] step  [←] step back  [r] reset  [q] quit",
+        ));
+        f.render_widget(help, outer[1]);
+    }
+
+    fn on_tick(&mut self) {
+        if self.running {
+            self.step_forward();
+        }
+    }
+
+    fn status(&self) -> StepResult {
+        if self.finished() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if key.kind == KeyEventKind::Release {
+            return Control::Continue;
+        }
+
+        match key.code {
+            KeyCode::Char(' ') => self.running = !self.running && !self.finished(),
+            KeyCode::Char('s') | KeyCode::Right => {
+                self.running = false;
+                self.step_forward();
+            }
+            KeyCode::Left => {
+                self.running = false;
+                self.step_back();
+            }
+            KeyCode::Char('r') => {
+                self.running = false;
+                self.current = 0;
+            }
+            _ => {}
+        }
+
+        Control::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{samples::sample_input, snapshot::render_tui};
+
+    use super::{super::parse_input, *};
+
+    #[test]
+    fn draws_the_stacks_mid_move() -> Result<(), SolverError> {
+        let (stacks, moves) = parse_input(&mut sample_input!(5).as_bytes())?;
+        let states = rearrangement_states(stacks, &moves, CrateMover::Model9001)?;
+        let mut viewer = Viewer::new(CrateMover::Model9001, moves, states);
+        viewer.step_forward();
+        viewer.step_forward();
+
+        insta::assert_snapshot!("stacks_after_two_moves", render_tui(&viewer, 60, 14));
+        Ok(())
+    }
+}
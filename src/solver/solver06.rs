@@ -1,8 +1,8 @@
-use std::{collections::VecDeque, io::BufRead};
+use std::io::BufRead;
 
-use itertools::Itertools;
+use crate::error::SolverError;
 
-use super::ChallengeSolver;
+use super::{ChallengeSolver, Scrambler};
 
 #[derive(Debug, Default)]
 pub struct Solver06;
@@ -13,73 +13,292 @@ impl ChallengeSolver for Solver06 {
         6
     }
 
+    super::sample_methods!();
+
+    fn generate(&self, size: usize, rng: &mut Scrambler) -> Option<Result<String, SolverError>> {
+        Some(generate(size, rng))
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut buf = String::new();
-        input.read_line(&mut buf).unwrap();
+        solve(input, START_OF_PACKET_LEN)
+    }
+
+    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        solve(input, START_OF_MESSAGE_LEN)
+    }
+}
+
+/// How many different characters in a row mark the start of a packet.
+const START_OF_PACKET_LEN: usize = 4;
+/// How many different characters in a row mark the start of a message.
+const START_OF_MESSAGE_LEN: usize = 14;
+
+fn solve(input: &mut dyn BufRead, marker_len: usize) -> super::ChallengeSolverResult {
+    let marker = find_marker(input, marker_len)?
+        .ok_or_else(|| SolverError::invalid_input("There's no marker"))?;
+    println!(
+        "\nFound marker `{}` after processing {} characters",
+        marker.text, marker.end
+    );
+
+    Ok(super::Answer::new(marker.end))
+}
+
+/// Make up a signal `len` characters long, with its first start-of-message marker somewhere in
+/// its second half.
+///
+/// Until the marker, the signal only uses the letters `abc`, so that there can't be a marker any
+/// sooner. The marker starts with the same letter as the one before it, so that neither marker
+/// can start any earlier either, and the start-of-packet marker is its first four letters. If the
+/// marker starts after `p` characters, the answers are `p + 4` and `p + 14`.
+fn generate(len: usize, rng: &mut Scrambler) -> Result<String, SolverError> {
+    let before_marker = len
+        .checked_sub(START_OF_MESSAGE_LEN)
+        .filter(|&room| room >= 1)
+        .ok_or_else(|| {
+            SolverError::invalid_input(format!(
+                "A signal needs at least {} characters to fit a marker in, not {len}",
+                START_OF_MESSAGE_LEN + 1
+            ))
+        })?;
+    let start = (len / 2).clamp(1, before_marker);
+    let start = start + rng.below((before_marker - start) as u64 + 1) as usize;
+
+    let mut signal = rng.word(b"abc", start);
+    let mut letters = (b'd'..=b'z').collect::<Vec<_>>();
+    rng.shuffle(&mut letters);
+    let repeated = signal.as_bytes()[start - 1];
+    signal.extend(
+        std::iter::once(repeated)
+            .chain(letters[..START_OF_MESSAGE_LEN - 1].iter().copied())
+            .map(char::from),
+    );
 
-        let mut tokens = VecDeque::with_capacity(4);
-        let mut processed_count = 0;
-        let mut marker = None;
+    signal.push_str(&rng.word(b"abcdefghijklmnopqrstuvwxyz", len - signal.len()));
+    signal.push('\n');
+    Ok(signal)
+}
+
+/// A run of characters that are all different.
+#[derive(Debug, PartialEq, Eq)]
+struct Marker {
+    /// How many characters had been processed by the end of the marker.
+    end: usize,
+    text: String,
+}
+
+/// Find the first `len` characters in a row that are all different, reading the signal from
+/// `input` up to the end of its first line.
+///
+/// Slides a window along the signal as it's read, keeping count of how often each letter shows up
+/// in it, so only the last `len` characters are ever kept around. That lets the signal be longer
+/// than would fit in memory.
+fn find_marker(input: &mut dyn BufRead, len: usize) -> Result<Option<Marker>, SolverError> {
+    let mut window = Window::new(len);
 
-        for token in buf.chars() {
-            if token == '\n' {
+    loop {
+        let chunk = input.fill_buf()?;
+        if chunk.is_empty() {
+            return Ok(None);
+        }
+
+        let mut used = 0;
+        let mut line_ended = false;
+        let mut found = false;
+        for &c in chunk {
+            used += 1;
+            if c == b'\n' || c == b'\r' {
+                line_ended = true;
                 break;
             }
-
-            if tokens.len() == 4 {
-                tokens.pop_front();
+            if window.push(c)? {
+                found = true;
+                break;
             }
+        }
+        input.consume(used);
 
-            tokens.push_back(token);
-            processed_count += 1;
+        if found {
+            return Ok(Some(window.marker()));
+        }
+        if line_ended {
+            return Ok(None);
+        }
+    }
+}
 
-            //println!("{tokens:?}");
+/// The last few characters of the signal, and how often each letter shows up in them.
+struct Window {
+    /// The characters in the window, as a ring buffer that `processed` wraps around.
+    chars: Vec<u8>,
+    counts: [u32; 26],
+    /// How many different letters show up more than once in the window.
+    repeated: usize,
+    /// How many characters have been pushed so far.
+    processed: usize,
+}
 
-            if tokens.iter().unique().count() == 4 {
-                marker = Some(tokens.iter().join(""));
-                break;
+impl Window {
+    fn new(len: usize) -> Self {
+        Self {
+            chars: vec![0; len],
+            counts: [0; 26],
+            repeated: 0,
+            processed: 0,
+        }
+    }
+
+    /// Slide the window along by one character, and return whether every character in it is now
+    /// different.
+    fn push(&mut self, c: u8) -> Result<bool, SolverError> {
+        if !c.is_ascii_lowercase() {
+            return Err(SolverError::invalid_input(format!(
+                "The signal should only have lowercase letters in it, but character {} is `{}`",
+                self.processed + 1,
+                c.escape_ascii()
+            )));
+        }
+
+        let len = self.chars.len();
+        let slot = self.processed % len;
+        if self.processed >= len {
+            let old = usize::from(self.chars[slot] - b'a');
+            self.counts[old] -= 1;
+            if self.counts[old] == 1 {
+                self.repeated -= 1;
             }
         }
 
-        if let Some(marker) = marker {
-            println!("\nFound marker `{marker}` after processing {processed_count} characters");
+        let new = usize::from(c - b'a');
+        self.counts[new] += 1;
+        if self.counts[new] == 2 {
+            self.repeated += 1;
         }
+        self.chars[slot] = c;
+        self.processed += 1;
 
-        Ok(Box::new(()))
+        Ok(self.processed >= len && self.repeated == 0)
     }
 
-    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut buf = String::new();
-        input.read_line(&mut buf).unwrap();
+    /// The characters in the window, oldest first.
+    fn marker(&self) -> Marker {
+        let (newer, older) = self.chars.split_at(self.processed % self.chars.len());
+        Marker {
+            end: self.processed,
+            text: older.iter().chain(newer).map(|&c| char::from(c)).collect(),
+        }
+    }
+}
 
-        let mut tokens = VecDeque::with_capacity(4);
-        let mut processed_count = 0;
-        let mut marker = None;
+super::challenge_solver_test_boilerplate! {
+    Solver06;
+    6 => {
+        a as usize,
+        b as usize,
+    }
 
-        for token in buf.chars() {
-            if token == '\n' {
-                break;
-            }
+    #[test]
+    fn finds_markers_from_the_puzzle() {
+        let examples = [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ];
+        let marker_end = |signal: &str, len| {
+            find_marker(&mut signal.as_bytes(), len)
+                .unwrap()
+                .map(|marker| marker.end)
+        };
+        for (signal, packet, message) in examples {
+            assert_eq!(marker_end(signal, START_OF_PACKET_LEN), Some(packet));
+            assert_eq!(marker_end(signal, START_OF_MESSAGE_LEN), Some(message));
+        }
+        assert_eq!(marker_end("aaaaaaa", START_OF_PACKET_LEN), None);
+        // Only the first line is the signal.
+        assert_eq!(marker_end("aaa\nabcd", START_OF_PACKET_LEN), None);
 
-            if tokens.len() == 14 {
-                tokens.pop_front();
-            }
+        assert_eq!(
+            find_marker(
+                &mut &b"mjqjpqmgbljsphdztnvjfqwrcgsmlb\n"[..],
+                START_OF_MESSAGE_LEN
+            )
+            .unwrap(),
+            Some(Marker {
+                end: 19,
+                text: "qmgbljsphdztnv".to_string()
+            })
+        );
+        assert!(find_marker(&mut "abcé".as_bytes(), START_OF_PACKET_LEN).is_err());
+    }
 
-            tokens.push_back(token);
-            processed_count += 1;
+    #[test]
+    fn finds_markers_in_generated_signals() {
+        for (seed, len) in [(1, 15), (2, 16), (3, 100), (4, 10_000), (5, 100_000)] {
+            let signal = generate(len, &mut Scrambler::new(seed)).unwrap();
+            assert_eq!(signal.len(), len + 1);
+            let start = signal.find(|c| !"abc".contains(c)).unwrap() - 1;
+            assert!(start >= len / 2 || len < 30, "{start} of {len}");
 
-            //println!("{tokens:?}");
+            let marker = |marker_len| find_marker(&mut signal.as_bytes(), marker_len).unwrap();
+            let packet = marker(START_OF_PACKET_LEN).unwrap();
+            let message = marker(START_OF_MESSAGE_LEN).unwrap();
+            assert_eq!(packet.end, start + START_OF_PACKET_LEN);
+            assert_eq!(message.end, start + START_OF_MESSAGE_LEN);
+            assert!(message.text.starts_with(&packet.text));
+        }
+        let signal = |seed| generate(50, &mut Scrambler::new(seed)).unwrap();
+        assert_eq!(signal(7), signal(7));
+        assert_ne!(signal(7), signal(8));
+        assert!(generate(START_OF_MESSAGE_LEN, &mut Scrambler::new(1)).is_err());
+    }
 
-            if tokens.iter().unique().count() == 14 {
-                marker = Some(tokens.iter().join(""));
-                break;
+    /// A signal that's made up as it's read, so that it never has to fit in memory: `abc` over and
+    /// over for `len` characters, then the rest of the alphabet.
+    struct SyntheticSignal {
+        len: usize,
+        read: usize,
+    }
+
+    impl std::io::Read for SyntheticSignal {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            const TAIL: &[u8] = b"defghijklmnopqrstuvwxyz\n";
+            let mut written = 0;
+            for byte in buf.iter_mut() {
+                *byte = match self.read.checked_sub(self.len) {
+                    None => b"abc"[self.read % 3],
+                    Some(i) if i < TAIL.len() => TAIL[i],
+                    Some(_) => break,
+                };
+                self.read += 1;
+                written += 1;
             }
+            Ok(written)
         }
+    }
 
-        if let Some(marker) = marker {
-            println!("\nFound marker `{marker}` after processing {processed_count} characters");
-        }
+    /// The most memory that the process has used so far, in kilobytes, if the OS says.
+    fn peak_memory_kb() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+        line.split_whitespace().nth(1)?.parse().ok()
+    }
 
-        Ok(Box::new(()))
+    #[test]
+    #[ignore = "reads a 1 GiB signal, which takes a while"]
+    fn streams_huge_signals_in_constant_memory() {
+        const LEN: usize = (1 << 30) / 3 * 3;
+
+        let before = peak_memory_kb();
+        let mut signal = std::io::BufReader::new(SyntheticSignal { len: LEN, read: 0 });
+        let answer = solve(&mut signal, START_OF_MESSAGE_LEN).unwrap();
+        // The `abc` just before the tail makes up the start of the marker.
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&(LEN + 11)));
+
+        if let (Some(before), Some(after)) = (before, peak_memory_kb()) {
+            let grew_by = after.saturating_sub(before);
+            assert!(grew_by < 64 * 1024, "memory use grew by {grew_by} kB");
+        }
     }
 }
@@ -1,127 +1,117 @@
-use std::io::BufRead;
+use std::{fmt, io::BufRead};
 
-use camino::Utf8PathBuf;
-use color_eyre::eyre::Context;
 use id_tree::{InsertBehavior, Node, NodeId, Tree};
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while1},
-    combinator::{all_consuming, map},
+    bytes::complete::take_while1,
+    combinator::map,
     sequence::{preceded, separated_pair},
-    Finish, IResult,
+    IResult,
 };
+use nom_supreme::{error::ErrorTree, tag::complete::tag};
 
-use super::ChallengeSolver;
+use crate::{
+    error::{Context, SolverError},
+    intern::{Interner, Symbol},
+    parse::BadInputError,
+};
+#[cfg(feature = "tui")]
+use crate::{simulation::Pacing, tui_app};
+
+#[cfg(feature = "tui")]
+use self::browser::{Browser, Highlight};
+
+use super::{ChallengeSolver, Frontend, ParamError, ParseStats, SolverConfig};
+
+#[cfg(feature = "tui")]
+mod browser;
 
 #[derive(Debug, Default)]
-pub struct Solver07;
+pub struct Solver07 {
+    /// Browse the filesystem tree in a TUI, after printing the answer.
+    visualize: bool,
+}
 
 impl ChallengeSolver for Solver07 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         7
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut vm = Vm::new().wrap_err("Couldn't create VM")?;
-
-        for line in input.lines() {
-            let line = line?;
-
-            let parsed = all_consuming(parse_line)(&line).finish().unwrap().1;
-
-            match parsed {
-                Line::Command(cmd) => match cmd {
-                    Command::Ls => {} // Just ignore ls
+    super::sample_methods!();
 
-                    Command::Cd(path) => match path.as_str() {
-                        // We start in `/`, and we never go back to it. So just ignore it.
-                        "/" => {}
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        Ok(())
+    }
 
-                        ".." => {
-                            vm.cd_parent_dir()
-                                .wrap_err("Couldn't `cd` into parent directory")?;
-                        }
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
+    }
 
-                        _ => {
-                            vm.cd(&path)
-                                .wrap_err("Couldn't `cd` into a child directory")?;
-                        }
-                    },
-                },
+    fn validate(&self, input: &str) -> Option<Result<ParseStats, SolverError>> {
+        Some(parse_input(input).map_err(Into::into).map(|lines| {
+            let count = |f: fn(&Line) -> bool| lines.iter().filter(|l| f(l)).count();
+            ParseStats::default()
+                .entity(
+                    "cd commands",
+                    count(|l| matches!(l, Line::Command(Command::Cd(_)))),
+                )
+                .entity(
+                    "ls commands",
+                    count(|l| matches!(l, Line::Command(Command::Ls))),
+                )
+                .entity(
+                    "directories",
+                    count(|l| matches!(l, Line::Entry(Entry::Dir(_)))),
+                )
+                .entity(
+                    "files",
+                    count(|l| matches!(l, Line::Entry(Entry::File(..)))),
+                )
+        }))
+    }
 
-                Line::Entry(entry) => {
-                    vm.add_entry(entry)
-                        .wrap_err("Couldn't add entry to VM's file tree")?;
-                }
-            }
-        }
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let vm = read_tree(input)?;
 
-        let mut s = String::new();
-        vm.tree.write_formatted(&mut s)?;
-        println!("{s}");
+        println!("{vm}");
 
         println!(
             "\nComputing sum of sizes of all dirs with individual sizes of at most 100_000..."
         );
         let sum = vm
             .tree
-            .traverse_pre_order(vm.tree.root_node_id().unwrap())?
+            .traverse_pre_order(vm.tree.root_node_id().unwrap())
+            .expect(TREE_IDS)
             // only consider directories with children!
             .filter(|d| !d.children().is_empty())
-            .map(|d| total_size(&vm.tree, d).unwrap())
-            .filter(|&s| s <= 100_000)
-            .inspect(|s| {
-                dbg!(s);
-            })
+            .map(|d| total_size(&vm.tree, d))
+            .filter(|&s| s <= SMALL_DIR_SIZE)
             .sum::<u64>();
 
         println!("\nDone! Sum of sizes = {sum}");
 
-        Ok(Box::new(()))
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            let mut browser = Browser::new(&vm, Highlight::Small);
+            tui_app::run(&mut browser, 4.0, Pacing::default())?;
+        }
+
+        Ok(super::Answer::new(sum))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut vm = Vm::new().wrap_err("Couldn't create VM")?;
-
-        for line in input.lines() {
-            let line = line?;
-
-            let parsed = all_consuming(parse_line)(&line).finish().unwrap().1;
-
-            match parsed {
-                Line::Command(cmd) => match cmd {
-                    Command::Ls => {} // Just ignore ls
-
-                    Command::Cd(path) => match path.as_str() {
-                        // We start in `/`, and we never go back to it. So just ignore it.
-                        "/" => {}
-
-                        ".." => {
-                            vm.cd_parent_dir()
-                                .wrap_err("Couldn't `cd` into parent directory")?;
-                        }
-
-                        _ => {
-                            vm.cd(&path)
-                                .wrap_err("Couldn't `cd` into a child directory")?;
-                        }
-                    },
-                },
-
-                Line::Entry(entry) => {
-                    vm.add_entry(entry)
-                        .wrap_err("Couldn't add entry to VM's file tree")?;
-                }
-            }
-        }
+        let vm = read_tree(input)?;
 
-        let mut s = String::new();
-        vm.tree.write_formatted(&mut s)?;
-        println!("{s}");
+        println!("{vm}");
 
         const NEEDED_FREE_SPACE: u64 = 30_000_000;
 
-        let used_space = total_size(&vm.tree, vm.tree.get(vm.tree.root_node_id().unwrap())?)?;
+        let root = vm
+            .tree
+            .get(vm.tree.root_node_id().unwrap())
+            .expect(TREE_IDS);
+        let used_space = total_size(&vm.tree, root);
         let free_space = TOTAL_SPACE.checked_sub(used_space).unwrap();
         let minimum_space_to_free = NEEDED_FREE_SPACE.checked_sub(free_space).unwrap();
 
@@ -133,154 +123,280 @@ impl ChallengeSolver for Solver07 {
 
         let (removed_dir_size, dir_to_remove) = vm
             .tree
-            .traverse_pre_order(vm.tree.root_node_id().unwrap())?
+            .traverse_pre_order_ids(vm.tree.root_node_id().unwrap())
+            .expect(TREE_IDS)
+            .map(|id| (vm.tree.get(&id).expect(TREE_IDS), id))
             // only consider directories with children!
-            .filter(|d| !d.children().is_empty())
-            .map(|d| (total_size(&vm.tree, d).unwrap(), d))
+            .filter(|(d, _)| !d.children().is_empty())
+            .map(|(d, id)| (total_size(&vm.tree, d), id))
             .filter(|(s, _)| *s >= minimum_space_to_free)
-            .inspect(|s| {
-                dbg!(s.0);
-            })
             .reduce(|acc, d| if acc.0 <= d.0 { acc } else { d })
             .unwrap();
 
         println!("\nFound directory of size {removed_dir_size} to remove!");
-        println!("(path: {})", dir_to_remove.data().path);
+        println!("(name: {})", vm.name(&dir_to_remove));
+
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            let mut browser = Browser::new(
+                &vm,
+                Highlight::Candidate {
+                    dir: dir_to_remove,
+                    to_free: minimum_space_to_free,
+                },
+            );
+            tui_app::run(&mut browser, 4.0, Pacing::default())?;
+        }
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(removed_dir_size))
     }
 }
 
+/// Replay the terminal output to rebuild the filesystem tree.
+fn read_tree(input: &mut dyn BufRead) -> Result<Vm, SolverError> {
+    let mut input_buf = String::new();
+    input
+        .read_to_string(&mut input_buf)
+        .context("Could not read input file to string")?;
+
+    let mut vm = Vm::new();
+
+    for parsed in parse_input(&input_buf).context("Could not parse terminal output")? {
+        match parsed {
+            Line::Command(cmd) => match cmd {
+                Command::Ls => {} // Just ignore ls
+
+                Command::Cd(path) => match path {
+                    // We start in `/`, and we never go back to it. So just ignore it.
+                    "/" => {}
+
+                    ".." => {
+                        vm.cd_parent_dir()
+                            .context("Couldn't `cd` into parent directory")?;
+                    }
+
+                    _ => {
+                        vm.cd(path)
+                            .context("Couldn't `cd` into a child directory")?;
+                    }
+                },
+            },
+
+            Line::Entry(entry) => {
+                vm.add_entry(entry)
+                    .context("Couldn't add entry to VM's file tree")?;
+            }
+        }
+    }
+
+    Ok(vm)
+}
+
 ///////////////////////// VIRTUAL MACHINE
 
 const TOTAL_SPACE: u64 = 70_000_000;
 
+/// Part A only adds up directories that are at most this big.
+const SMALL_DIR_SIZE: u64 = 100_000;
+
 #[derive(Debug)]
 struct FsEntry {
-    path: Utf8PathBuf,
+    /// The entry's own name, like `b.txt`, interned in [`Vm::names`].
+    name: Symbol,
     size: u64,
 }
 
-fn total_size(tree: &Tree<FsEntry>, node: &Node<FsEntry>) -> color_eyre::Result<u64> {
+/// Every node ID that the VM uses comes from its own tree, so looking one up can't fail.
+const TREE_IDS: &str = "node IDs should all come from the VM's tree";
+
+fn total_size(tree: &Tree<FsEntry>, node: &Node<FsEntry>) -> u64 {
     let mut total = node.data().size;
     for child in node.children() {
-        total += total_size(tree, tree.get(child)?)?;
+        total += total_size(tree, tree.get(child).expect(TREE_IDS));
     }
-    Ok(total)
+    total
 }
 
 #[derive(Debug)]
 struct Vm {
     tree: Tree<FsEntry>,
+    /// The name of every file and directory. The same few names tend to come up all over the tree.
+    names: Interner,
     pwd: NodeId,
 }
 
 impl Vm {
-    fn new() -> color_eyre::Result<Self> {
+    fn new() -> Self {
+        let mut names = Interner::new();
         let mut tree = Tree::new();
-        let root = tree.insert(
-            Node::new(FsEntry {
-                path: "/".into(),
-                size: 0,
-            }),
-            InsertBehavior::AsRoot,
-        )?;
-        Ok(Self { tree, pwd: root })
+        let root = tree
+            .insert(
+                Node::new(FsEntry {
+                    name: names.intern("/"),
+                    size: 0,
+                }),
+                InsertBehavior::AsRoot,
+            )
+            .expect(TREE_IDS);
+        Self {
+            tree,
+            names,
+            pwd: root,
+        }
+    }
+
+    /// The name of a file or directory, like `b.txt`.
+    fn name(&self, id: &NodeId) -> &str {
+        self.names
+            .resolve(self.tree.get(id).expect(TREE_IDS).data().name)
     }
 
-    fn cd_parent_dir(&mut self) -> color_eyre::Result<()> {
+    fn cd_parent_dir(&mut self) -> Result<(), SolverError> {
         self.pwd = self
             .tree
-            .get(&self.pwd)?
+            .get(&self.pwd)
+            .expect(TREE_IDS)
             .parent()
-            .ok_or(color_eyre::eyre::eyre!(
-                "Tried to cd to parent when pwd is already `/`"
-            ))?
+            .ok_or_else(|| {
+                SolverError::invalid_input("Tried to cd to parent when pwd is already `/`")
+            })?
             .clone();
         Ok(())
     }
 
-    fn cd(&mut self, path: &Utf8PathBuf) -> color_eyre::Result<()> {
+    fn cd(&mut self, name: &str) -> Result<(), SolverError> {
         let node = Node::new(FsEntry {
-            path: path.clone(),
+            name: self.names.intern(name),
             size: 0,
         });
         self.pwd = self
             .tree
-            .insert(node, InsertBehavior::UnderNode(&self.pwd))?;
+            .insert(node, InsertBehavior::UnderNode(&self.pwd))
+            .expect(TREE_IDS);
         Ok(())
     }
 
-    fn add_entry(&mut self, entry: Entry) -> color_eyre::Result<()> {
+    fn add_entry(&mut self, entry: Entry) -> Result<(), SolverError> {
         match entry {
             Entry::Dir(_dir_path) => {
                 // Ignore. This is handled when `cd`ing into directories.
             }
 
-            Entry::File(size, path) => {
-                let node = Node::new(FsEntry { size, path });
+            Entry::File(size, name) => {
+                let node = Node::new(FsEntry {
+                    name: self.names.intern(name),
+                    size,
+                });
                 self.tree
-                    .insert(node, InsertBehavior::UnderNode(&self.pwd))?;
+                    .insert(node, InsertBehavior::UnderNode(&self.pwd))
+                    .expect(TREE_IDS);
             }
         }
 
         Ok(())
     }
+
+    /// Draw the subtree under `id`, with every line after the first starting with `indent`.
+    fn write_subtree(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        id: &NodeId,
+        indent: &mut String,
+    ) -> fmt::Result {
+        let children = self.tree.get(id).expect(TREE_IDS).children();
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let (branch, rail) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            write!(f, "{indent}{branch}")?;
+            self.write_entry(f, child)?;
+
+            let len = indent.len();
+            indent.push_str(rail);
+            self.write_subtree(f, child, indent)?;
+            indent.truncate(len);
+        }
+        Ok(())
+    }
+
+    /// Write a file's name and size, or a directory's name.
+    fn write_entry(&self, f: &mut fmt::Formatter<'_>, id: &NodeId) -> fmt::Result {
+        let node = self.tree.get(id).expect(TREE_IDS);
+        if node.children().is_empty() {
+            writeln!(f, "{} ({})", self.name(id), node.data().size)
+        } else {
+            writeln!(f, "{}", self.name(id))
+        }
+    }
+}
+
+/// Draws the filesystem tree like the `tree` command does, with the size of every file.
+impl fmt::Display for Vm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let root = self.tree.root_node_id().expect(TREE_IDS);
+        self.write_entry(f, root)?;
+        self.write_subtree(f, root, &mut String::new())
+    }
 }
 
 ///////////////////////// PARSING INPUT
 
-fn parse_path(i: &str) -> IResult<&str, Utf8PathBuf> {
-    map(
-        take_while1(|c: char| "abcdefghijklmnopqrstuvwxyz0123456789./".contains(c)),
-        Into::into,
-    )(i)
+/// Parse every line of the terminal output.
+fn parse_input(input: &str) -> Result<Vec<Line<'_>>, BadInputError> {
+    crate::parse::parse_lines(input, parse_line)
+}
+
+fn parse_path(i: &str) -> IResult<&str, &str, ErrorTree<&str>> {
+    take_while1(|c: char| "abcdefghijklmnopqrstuvwxyz0123456789./".contains(c))(i)
 }
 
 #[derive(Debug)]
 struct Ls;
 
-fn parse_ls(i: &str) -> IResult<&str, Ls> {
+fn parse_ls(i: &str) -> IResult<&str, Ls, ErrorTree<&str>> {
     map(tag("ls"), |_| Ls)(i)
 }
 
 #[derive(Debug)]
-struct Cd(Utf8PathBuf);
+struct Cd<'a>(&'a str);
 
-fn parse_cd(i: &str) -> IResult<&str, Cd> {
+fn parse_cd(i: &str) -> IResult<&str, Cd<'_>, ErrorTree<&str>> {
     map(preceded(tag("cd "), parse_path), Cd)(i)
 }
 
 #[derive(Debug)]
-enum Command {
+enum Command<'a> {
     Ls,
-    Cd(Utf8PathBuf),
+    Cd(&'a str),
 }
 
-impl From<Ls> for Command {
+impl From<Ls> for Command<'_> {
     fn from(_: Ls) -> Self {
         Self::Ls
     }
 }
 
-impl From<Cd> for Command {
-    fn from(Cd(path): Cd) -> Self {
+impl<'a> From<Cd<'a>> for Command<'a> {
+    fn from(Cd(path): Cd<'a>) -> Self {
         Command::Cd(path)
     }
 }
 
-fn parse_command(i: &str) -> IResult<&str, Command> {
+fn parse_command(i: &str) -> IResult<&str, Command<'_>, ErrorTree<&str>> {
     let (i, _) = tag("$ ")(i)?;
     alt((map(parse_ls, Into::into), map(parse_cd, Into::into)))(i)
 }
 
 #[derive(Debug)]
-enum Entry {
-    Dir(Utf8PathBuf),
-    File(u64, Utf8PathBuf),
+enum Entry<'a> {
+    Dir(&'a str),
+    File(u64, &'a str),
 }
 
-fn parse_entry(i: &str) -> IResult<&str, Entry> {
+fn parse_entry(i: &str) -> IResult<&str, Entry<'_>, ErrorTree<&str>> {
     let parse_file = map(
         separated_pair(nom::character::complete::u64, tag(" "), parse_path),
         |(size, path)| Entry::File(size, path),
@@ -291,14 +407,84 @@ fn parse_entry(i: &str) -> IResult<&str, Entry> {
 }
 
 #[derive(Debug)]
-enum Line {
-    Command(Command),
-    Entry(Entry),
+enum Line<'a> {
+    Command(Command<'a>),
+    Entry(Entry<'a>),
 }
 
-fn parse_line(i: &str) -> IResult<&str, Line> {
+fn parse_line(i: &str) -> IResult<&str, Line<'_>, ErrorTree<&str>> {
     alt((
         map(parse_command, Line::Command),
         map(parse_entry, Line::Entry),
     ))(i)
 }
+
+super::challenge_solver_test_boilerplate! {
+    Solver07::default();
+    7 => {
+        a as u64,
+        b as u64,
+    }
+
+    #[test]
+    fn draws_the_tree() -> Result<(), SolverError> {
+        let vm = read_tree(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert_eq!(
+            vm.to_string(),
+            "/\n\
+             ├── b.txt (14848514)\n\
+             ├── c.dat (8504156)\n\
+             ├── a\n\
+             │   ├── f (29116)\n\
+             │   ├── g (2557)\n\
+             │   ├── h.lst (62596)\n\
+             │   └── e\n\
+             │       └── i (584)\n\
+             └── d\n\
+             \u{20}   ├── j (4060174)\n\
+             \u{20}   ├── d.log (8033020)\n\
+             \u{20}   ├── d.ext (5626152)\n\
+             \u{20}   └── k (7214296)\n"
+        );
+        // One name for each of the 14 files and directories.
+        assert_eq!(vm.names.len(), 14);
+        Ok(())
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn browses_the_tree() -> Result<(), SolverError> {
+        let vm = read_tree(&mut Cursor::new(SAMPLE_INPUT))?;
+        let name = |row: &browser::Row| vm.name(&row.id).to_string();
+
+        let mut browser = Browser::new(&vm, Highlight::Small);
+        let names: Vec<_> = browser.rows().iter().map(name).collect();
+        // Directories only show up once they're `cd`ed into, after the files listed before that.
+        assert_eq!(names, ["/", "b.txt", "c.dat", "a", "d"]);
+
+        // Expand `a`, then put the biggest things first.
+        browser.move_selection(3);
+        browser.toggle();
+        browser.toggle_sort();
+        let rows = browser.rows();
+        let names: Vec<_> = rows.iter().map(name).collect();
+        assert_eq!(
+            names,
+            ["/", "d", "b.txt", "c.dat", "a", "h.lst", "f", "g", "e"]
+        );
+        assert_eq!(rows[5].depth, 2);
+
+        // Part B's candidate starts out visible, even when it's deep in the tree.
+        let e = rows[8].id.clone();
+        let browser = Browser::new(
+            &vm,
+            Highlight::Candidate {
+                dir: e.clone(),
+                to_free: 500,
+            },
+        );
+        assert!(browser.rows().iter().any(|row| row.id == e));
+
+        Ok(())
+    }
+}
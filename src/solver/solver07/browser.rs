@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use id_tree::{NodeId, Tree};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::tui_app::{Control, TuiApp};
+
+use super::{total_size, FsEntry, Vm, SMALL_DIR_SIZE, TREE_IDS};
+
+/// Which directories stand out, depending on the part being solved.
+pub(super) enum Highlight {
+    /// Every directory that part A adds up.
+    Small,
+    /// The directory that part B picked to delete, to free up at least `to_free`.
+    Candidate { dir: NodeId, to_free: u64 },
+}
+
+/// A line in the browser: a file or directory, nested `depth` levels under `/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Row {
+    pub(super) id: NodeId,
+    pub(super) depth: usize,
+}
+
+/// Browses the filesystem tree, one expandable directory at a time.
+pub(super) struct Browser<'t> {
+    vm: &'t Vm,
+    tree: &'t Tree<FsEntry>,
+    /// The total size of every file and directory.
+    sizes: HashMap<NodeId, u64>,
+    expanded: HashSet<NodeId>,
+    /// Which of the visible [`Row`]s is selected.
+    selected: usize,
+    /// List the biggest children first, instead of in the order `ls` listed them.
+    by_size: bool,
+    highlight: Highlight,
+}
+
+impl<'t> Browser<'t> {
+    /// Start out with `/` expanded, along with everything above the deletion candidate if there
+    /// is one, which starts out selected.
+    pub(super) fn new(vm: &'t Vm, highlight: Highlight) -> Self {
+        let tree = &vm.tree;
+        let root = tree.root_node_id().expect(TREE_IDS);
+        let sizes = tree
+            .traverse_pre_order_ids(root)
+            .expect(TREE_IDS)
+            .map(|id| {
+                let size = total_size(tree, tree.get(&id).expect(TREE_IDS));
+                (id, size)
+            })
+            .collect();
+
+        let mut browser = Self {
+            vm,
+            tree,
+            sizes,
+            expanded: HashSet::from([root.clone()]),
+            selected: 0,
+            by_size: false,
+            highlight,
+        };
+
+        if let Highlight::Candidate { dir, .. } = &browser.highlight {
+            let dir = dir.clone();
+            browser
+                .expanded
+                .extend(tree.ancestor_ids(&dir).expect(TREE_IDS).cloned());
+            browser.select(&dir);
+        }
+
+        browser
+    }
+
+    /// Every file and directory that can be seen, in order, with their children under them if
+    /// they're expanded.
+    pub(super) fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut stack = vec![Row {
+            id: self.tree.root_node_id().expect(TREE_IDS).clone(),
+            depth: 0,
+        }];
+
+        while let Some(row) = stack.pop() {
+            if self.expanded.contains(&row.id) {
+                // Pushed in reverse, so that they come off of the stack in order.
+                let children = self.sorted_children(&row.id);
+                stack.extend(children.into_iter().rev().map(|id| Row {
+                    id,
+                    depth: row.depth + 1,
+                }));
+            }
+            rows.push(row);
+        }
+
+        rows
+    }
+
+    fn sorted_children(&self, id: &NodeId) -> Vec<NodeId> {
+        let mut children = self.tree.get(id).expect(TREE_IDS).children().clone();
+        if self.by_size {
+            children.sort_by_key(|child| std::cmp::Reverse(self.sizes[child]));
+        }
+        children
+    }
+
+    fn is_dir(&self, id: &NodeId) -> bool {
+        !self.tree.get(id).expect(TREE_IDS).children().is_empty()
+    }
+
+    fn selected_id(&self) -> NodeId {
+        self.rows().swap_remove(self.selected).id
+    }
+
+    /// Select `id`, if it can be seen.
+    fn select(&mut self, id: &NodeId) {
+        if let Some(index) = self.rows().iter().position(|row| &row.id == id) {
+            self.selected = index;
+        }
+    }
+
+    /// Show or hide the selected directory's children.
+    pub(super) fn toggle(&mut self) {
+        let id = self.selected_id();
+        if !self.expanded.remove(&id) && self.is_dir(&id) {
+            self.expanded.insert(id);
+        }
+    }
+
+    /// Hide the selected directory's children, or if they're already hidden, go up to its
+    /// parent.
+    fn collapse(&mut self) {
+        let id = self.selected_id();
+        if !self.expanded.remove(&id) {
+            if let Some(parent) = self.tree.get(&id).expect(TREE_IDS).parent() {
+                let parent = parent.clone();
+                self.select(&parent);
+            }
+        }
+    }
+
+    /// Switch between sorting by size and listing in the original order, keeping the same entry
+    /// selected.
+    pub(super) fn toggle_sort(&mut self) {
+        let id = self.selected_id();
+        self.by_size = !self.by_size;
+        self.select(&id);
+    }
+
+    pub(super) fn move_selection(&mut self, offset: isize) {
+        let last = self.rows().len() - 1;
+        self.selected = self.selected.saturating_add_signed(offset).min(last);
+    }
+
+    fn style(&self, id: &NodeId) -> Style {
+        let is_dir = self.is_dir(id);
+        match &self.highlight {
+            Highlight::Candidate { dir, .. } if dir == id => Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            Highlight::Small if is_dir && self.sizes[id] <= SMALL_DIR_SIZE => {
+                Style::default().fg(Color::Green)
+            }
+            _ if is_dir => Style::default().fg(Color::Cyan),
+            _ => Style::default(),
+        }
+    }
+
+    /// The full path to a file or directory, like `/a/e/i`.
+    fn path(&self, id: &NodeId) -> String {
+        let mut names: Vec<_> = self
+            .tree
+            .ancestor_ids(id)
+            .expect(TREE_IDS)
+            .map(|ancestor| self.vm.name(ancestor))
+            .collect();
+        names.reverse();
+        names.push(self.vm.name(id));
+        // The root is already called `/`.
+        format!("/{}", names[1..].join("/"))
+    }
+
+    fn tree_items(&self) -> Vec<ListItem<'static>> {
+        self.rows()
+            .iter()
+            .map(|row| {
+                let node = self.tree.get(&row.id).expect(TREE_IDS);
+                let marker = match (self.is_dir(&row.id), self.expanded.contains(&row.id)) {
+                    (false, _) => "  ",
+                    (true, false) => "▸ ",
+                    (true, true) => "▾ ",
+                };
+                let name = if self.is_dir(&row.id) && node.parent().is_some() {
+                    format!("{}/", self.vm.name(&row.id))
+                } else {
+                    self.vm.name(&row.id).to_string()
+                };
+                ListItem::new(Spans::from(vec![
+                    Span::raw(format!("{:indent$}{marker}", "", indent = 2 * row.depth)),
+                    Span::styled(name, self.style(&row.id)),
+                    Span::styled(
+                        format!("  {}", self.sizes[&row.id]),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect()
+    }
+
+    fn info_lines(&self) -> Vec<Spans<'static>> {
+        let label = Style::default().add_modifier(Modifier::BOLD);
+        let id = self.selected_id();
+        let node = self.tree.get(&id).expect(TREE_IDS);
+
+        let mut lines = vec![
+            Spans::from(vec![
+                Span::styled("path:  ", label),
+                Span::raw(self.path(&id)),
+            ]),
+            Spans::from(vec![
+                Span::styled("size:  ", label),
+                Span::raw(self.sizes[&id].to_string()),
+            ]),
+        ];
+        if self.is_dir(&id) {
+            lines.push(Spans::from(vec![
+                Span::styled("items: ", label),
+                Span::raw(node.children().len().to_string()),
+            ]));
+        }
+        lines.push(Spans::default());
+
+        match &self.highlight {
+            Highlight::Small => lines.push(Spans::from(Span::styled(
+                format!("at most {SMALL_DIR_SIZE}"),
+                Style::default().fg(Color::Green),
+            ))),
+            Highlight::Candidate { to_free, .. } => {
+                lines.push(Spans::from(vec![
+                    Span::styled("to free: ", label),
+                    Span::raw(to_free.to_string()),
+                ]));
+                lines.push(Spans::from(Span::styled(
+                    "smallest big enough",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )));
+            }
+        }
+        lines.push(Spans::from(Span::styled(
+            "directory",
+            Style::default().fg(Color::Cyan),
+        )));
+        lines.push(Spans::default());
+        lines.push(Spans::from(if self.by_size {
+            "sorted by size"
+        } else {
+            "in listing order"
+        }));
+
+        lines
+    }
+}
+
+impl TuiApp for Browser<'_> {
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(32)])
+            .split(outer[0]);
+
+        let list = List::new(self.tree_items())
+            .block(Block::default().title("Day 7").borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+        f.render_stateful_widget(list, columns[0], &mut state);
+
+        let info = Paragraph::new(self.info_lines())
+            .block(Block::default().title("Selected").borders(Borders::ALL));
+        f.render_widget(info, columns[1]);
+
+        let help = Paragraph::new(Spans::from(
+            "[↑/↓] move  [→/enter] expand  [←] collapse  [s] sort by size  [q] quit",
+        ));
+        f.render_widget(help, outer[1]);
+    }
+
+    /// The tree doesn't change, so there's nothing to update.
+    fn on_tick(&mut self) {}
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if key.kind == KeyEventKind::Release {
+            return Control::Continue;
+        }
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::Enter | KeyCode::Char(' ') => self.toggle(),
+            KeyCode::Right | KeyCode::Char('l') => {
+                let id = self.selected_id();
+                if self.is_dir(&id) {
+                    self.expanded.insert(id);
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+            KeyCode::Char('s') => self.toggle_sort(),
+            _ => {}
+        }
+
+        Control::Continue
+    }
+}
@@ -1,72 +1,207 @@
-use std::io::BufRead;
+use std::{io::BufRead, path::PathBuf};
 
-use color_eyre::eyre::Context;
+use owo_colors::Rgb;
 
-use crate::grid::{Grid, GridCoord};
+use crate::{
+    error::{Context, SolverError},
+    grid::{BitGrid, Grid, GridCoord},
+    raster::{self, Raster},
+};
+#[cfg(feature = "tui")]
+use crate::{simulation::Pacing, tui_app};
 
-use super::ChallengeSolver;
+#[cfg(feature = "tui")]
+use self::viewer::Viewer;
+
+use super::{ChallengeSolver, Frontend, ParamError, SolverConfig};
+
+#[cfg(feature = "tui")]
+mod viewer;
+
+/// How many pixels wide each tree is in saved pictures.
+const PNG_SCALE: usize = 8;
 
 #[derive(Debug, Default)]
-pub struct Solver08;
+pub struct Solver08 {
+    /// Look at the maps of the forest in a TUI, instead of just printing the answer.
+    visualize: bool,
+    /// Where to save a picture of the map for the part being solved, if anywhere.
+    png: Option<PathBuf>,
+}
 
 impl ChallengeSolver for Solver08 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         8
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut grid = String::new();
-        input
-            .read_to_string(&mut grid)
-            .wrap_err("Could not read input file")?;
-
-        let grid = parse_grid(&grid).wrap_err("Could not parse grid")?;
+    super::sample_methods!();
 
-        let all_coords = (0..grid.height())
-            .into_iter()
-            .flat_map(|y| (0..grid.width()).map(move |x| GridCoord::from((x, y))));
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        self.png = config.params.get("png")?;
+        Ok(())
+    }
 
-        let num_visible_cells = all_coords
-            .filter(|&coord| {
-                let coord_height = grid.cell(coord).unwrap();
-                let deltas: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
+    }
 
-                deltas.iter().any(|&(dx, dy)| {
-                    let mut cells_in_line = iter_trees_in_dir(&grid, coord, (dx, dy));
-                    cells_in_line.all(|height| height < coord_height)
-                })
-            })
-            .count();
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let forest = self.read_forest(input)?;
 
+        let num_visible_cells = forest.visible_count();
         println!("Number of visible trees: {num_visible_cells}");
 
-        Ok(Box::new(()))
+        self.show(&forest, Tab::Visibility)?;
+
+        Ok(super::Answer::new(num_visible_cells))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let forest = self.read_forest(input)?;
+
+        let (best_place, best_score) = forest.best_spot();
+        println!("Best location: {best_place:?}");
+        println!("      ↳ score: {best_score}");
+
+        self.show(&forest, Tab::Scenic)?;
+
+        Ok(super::Answer::new(best_score))
+    }
+}
+
+impl Solver08 {
+    fn read_forest(&self, input: &mut dyn BufRead) -> Result<Forest, SolverError> {
         let mut grid = String::new();
         input
             .read_to_string(&mut grid)
-            .wrap_err("Could not read input file")?;
+            .context("Could not read input file")?;
+
+        Ok(Forest::new(parse_grid(&grid)?))
+    }
+
+    /// Save the map for `tab` as a picture and show the visualization, if either was asked for.
+    fn show(&self, forest: &Forest, tab: Tab) -> Result<(), SolverError> {
+        if let Some(path) = &self.png {
+            let map = match tab {
+                Tab::Visibility => forest.visibility_raster(),
+                Tab::Scenic => forest.scenic_raster(),
+            };
+            map.scaled(PNG_SCALE).save_png(path)?;
+            println!("Saved map to {}", path.display());
+        }
+
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            let mut viewer = Viewer::new(forest, tab);
+            tui_app::run(&mut viewer, 4.0, Pacing::default())?;
+        }
+
+        Ok(())
+    }
+}
 
-        let grid = parse_grid(&grid).wrap_err("Could not parse grid")?;
+/// One of the maps that the viewer can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    /// Which trees can be seen from outside of the forest, for part A.
+    Visibility,
+    /// How scenic the view from each tree is, for part B.
+    Scenic,
+}
 
-        let all_coords = (0..grid.height())
-            .into_iter()
-            .flat_map(|y| (0..grid.width()).map(move |x| GridCoord::from((x, y))));
+/// The heights of every tree, along with which ones can be seen from outside of the forest and
+/// how scenic the view from each one is.
+struct Forest {
+    heights: Grid<u32>,
+    visible: BitGrid,
+    scores: Grid<usize>,
+}
 
-        let (best_place, best_score) = all_coords
-            .map(|coord| (coord, scenic_score(&grid, coord)))
+impl Forest {
+    fn new(heights: Grid<u32>) -> Self {
+        let mut visible = BitGrid::new(heights.width(), heights.height());
+        let mut scores = Grid::new(heights.width(), heights.height());
+
+        for coord in all_coords(&heights) {
+            let coord_height = heights.cell(coord).unwrap();
+            let is_visible = DIRECTIONS.iter().any(|&delta| {
+                let mut cells_in_line = iter_trees_in_dir(&heights, coord, delta);
+                cells_in_line.all(|height| height < coord_height)
+            });
+            visible.set(coord, is_visible);
+            *scores.cell_mut(coord).unwrap() = scenic_score(&heights, coord);
+        }
+
+        Self {
+            heights,
+            visible,
+            scores,
+        }
+    }
+
+    fn visible_count(&self) -> usize {
+        self.visible.count_ones()
+    }
+
+    /// The tree with the highest scenic score, and its score.
+    fn best_spot(&self) -> (GridCoord, usize) {
+        all_coords(&self.heights)
+            .map(|coord| (coord, *self.scores.cell(coord).unwrap()))
             .max_by_key(|(_, score)| *score)
-            .unwrap();
+            .unwrap()
+    }
 
-        println!("Best location: {best_place:?}");
-        println!("      ↳ score: {best_score}");
+    /// A heightmap with one pixel per tree, colored with [`tree_color`].
+    fn visibility_raster(&self) -> Raster {
+        let mut raster = Raster::new(self.heights.width(), self.heights.height(), Rgb(0, 0, 0));
+        for coord in all_coords(&self.heights) {
+            let height = *self.heights.cell(coord).unwrap() as u8;
+            let color = tree_color(height, self.visible.cell(coord).unwrap());
+            raster.set(coord.x, coord.y, color);
+        }
+        raster
+    }
 
-        Ok(Box::new(()))
+    /// A heatmap of every tree's scenic score, with the best spot marked in cyan.
+    ///
+    /// Most trees have tiny scores next to the best one, so the scale is logarithmic to tell them
+    /// apart.
+    fn scenic_raster(&self) -> Raster {
+        let (best, best_score) = self.best_spot();
+        let mut raster = Raster::new(self.heights.width(), self.heights.height(), Rgb(0, 0, 0));
+        for coord in all_coords(&self.heights) {
+            let score = *self.scores.cell(coord).unwrap();
+            let fraction = (score as f64).ln_1p() / (best_score.max(1) as f64).ln_1p();
+            raster.set(coord.x, coord.y, raster::heat_color(fraction));
+        }
+        raster.set(best.x, best.y, BEST_SPOT_COLOR);
+        raster
     }
 }
 
+/// The color of a tree on the visibility map: green if it's hidden, or gold if it's visible.
+/// Taller trees are brighter.
+const fn tree_color(height: u8, visible: bool) -> Rgb {
+    if visible {
+        Rgb(90 + 18 * height, 70 + 15 * height, 10)
+    } else {
+        Rgb(10, 50 + 18 * height, 30)
+    }
+}
+
+/// The color that marks the tree with the best scenic score.
+const BEST_SPOT_COLOR: Rgb = Rgb(0, 255, 255);
+
+/// Looking left, right, up and down.
+const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Every coordinate in `grid`, row by row.
+fn all_coords<T>(grid: &Grid<T>) -> impl Iterator<Item = GridCoord> {
+    let width = grid.width();
+    (0..grid.height()).flat_map(move |y| (0..width).map(move |x| GridCoord::from((x, y))))
+}
+
 fn parse_grid(input: &str) -> Result<Grid<u32>, Solver08Error> {
     let width = input.lines().next().unwrap().len();
     let height = input.lines().count();
@@ -94,7 +229,7 @@ fn iter_trees_in_dir(
     coord: GridCoord,
     (dx, dy): (isize, isize),
 ) -> impl Iterator<Item = &u32> {
-    (1..).into_iter().map_while(move |i| {
+    (1..).map_while(move |i| {
         let coord = GridCoord {
             x: coord.x.checked_add_signed(dx * i)?,
             y: coord.y.checked_add_signed(dy * i)?,
@@ -122,8 +257,8 @@ fn count_visible_trees_in_dir(
 }
 
 fn scenic_score(grid: &Grid<u32>, coord: GridCoord) -> usize {
-    let dirs: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-    dirs.into_iter()
+    DIRECTIONS
+        .into_iter()
         .map(|(dx, dy)| count_visible_trees_in_dir(grid, coord, (dx, dy)))
         .product()
 }
@@ -135,3 +270,33 @@ enum Solver08Error {
     )]
     ParseGridNonAsciiDigit { chr: char, coord: GridCoord },
 }
+
+impl From<Solver08Error> for SolverError {
+    fn from(err: Solver08Error) -> Self {
+        Self::InvalidInput(err.to_string())
+    }
+}
+
+super::challenge_solver_test_boilerplate! {
+    Solver08::default();
+    8 => {
+        a as usize,
+        b as usize,
+    }
+
+    #[test]
+    fn sample_forest() {
+        let forest = Forest::new(parse_grid(SAMPLE_INPUT).unwrap());
+
+        assert_eq!(forest.visible_count(), 21);
+        assert_eq!(forest.best_spot(), (GridCoord { x: 2, y: 3 }, 8));
+
+        let scenic = forest.scenic_raster();
+        assert_eq!(scenic.get(2, 3), Some(BEST_SPOT_COLOR));
+        assert_eq!(scenic.get(0, 0), Some(raster::heat_color(0.0)));
+        assert_eq!(
+            forest.visibility_raster().get(1, 1),
+            Some(tree_color(5, true))
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Tabs},
+    Frame,
+};
+
+use crate::{
+    grid::GridCoord,
+    raster::{self, Raster},
+    tui_app::{self, Control, TuiApp},
+};
+
+use super::{tree_color, Forest, Tab, BEST_SPOT_COLOR};
+
+impl Tab {
+    const ALL: [Self; 2] = [Self::Visibility, Self::Scenic];
+
+    const fn title(self) -> &'static str {
+        match self {
+            Self::Visibility => "Visibility",
+            Self::Scenic => "Scenic score",
+        }
+    }
+
+    const fn other(self) -> Self {
+        match self {
+            Self::Visibility => Self::Scenic,
+            Self::Scenic => Self::Visibility,
+        }
+    }
+}
+
+/// Shows the maps of the forest, one tab at a time.
+pub(super) struct Viewer {
+    tab: Tab,
+    visibility: Raster,
+    scenic: Raster,
+    trees: usize,
+    visible: usize,
+    best: (GridCoord, usize),
+}
+
+impl Viewer {
+    pub(super) fn new(forest: &Forest, tab: Tab) -> Self {
+        Self {
+            tab,
+            visibility: forest.visibility_raster(),
+            scenic: forest.scenic_raster(),
+            trees: forest.heights.num_cells(),
+            visible: forest.visible_count(),
+            best: forest.best_spot(),
+        }
+    }
+
+    fn map(&self) -> &Raster {
+        match self.tab {
+            Tab::Visibility => &self.visibility,
+            Tab::Scenic => &self.scenic,
+        }
+    }
+
+    fn info_lines(&self) -> Vec<Spans<'static>> {
+        let label = Style::default().add_modifier(Modifier::BOLD);
+        let swatch = |owo_colors::Rgb(r, g, b)| {
+            Span::styled("██ ", Style::default().fg(Color::Rgb(r, g, b)))
+        };
+
+        match self.tab {
+            Tab::Visibility => vec![
+                Spans::from(vec![
+                    Span::styled("trees:   ", label),
+                    Span::raw(self.trees.to_string()),
+                ]),
+                Spans::from(vec![
+                    Span::styled("visible: ", label),
+                    Span::styled(self.visible.to_string(), Style::default().fg(Color::Cyan)),
+                ]),
+                Spans::default(),
+                Spans::from(vec![swatch(tree_color(9, true)), Span::raw("visible")]),
+                Spans::from(vec![swatch(tree_color(9, false)), Span::raw("hidden")]),
+                Spans::from("brighter trees are taller"),
+            ],
+
+            Tab::Scenic => {
+                let (coord, score) = self.best;
+                vec![
+                    Spans::from(vec![
+                        Span::styled("best spot: ", label),
+                        Span::raw(format!("({}, {})", coord.x, coord.y)),
+                    ]),
+                    Spans::from(vec![
+                        Span::styled("score:     ", label),
+                        Span::styled(score.to_string(), Style::default().fg(Color::Cyan)),
+                    ]),
+                    Spans::default(),
+                    Spans::from(vec![swatch(BEST_SPOT_COLOR), Span::raw("best spot")]),
+                    Spans::from(vec![
+                        swatch(raster::heat_color(1.0)),
+                        Span::raw("high score"),
+                    ]),
+                    Spans::from(vec![swatch(raster::heat_color(0.0)), Span::raw("no view")]),
+                ]
+            }
+        }
+    }
+}
+
+impl TuiApp for Viewer {
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(f.size());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(28)])
+            .split(outer[1]);
+
+        let tabs = Tabs::new(
+            Tab::ALL
+                .iter()
+                .map(|tab| Spans::from(tab.title()))
+                .collect(),
+        )
+        .block(Block::default().title("Day 8").borders(Borders::ALL))
+        .select(Tab::ALL.iter().position(|&tab| tab == self.tab).unwrap())
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+        f.render_widget(tabs, outer[0]);
+
+        let map_block = Block::default()
+            .title(self.tab.title())
+            .borders(Borders::ALL);
+        let map = Paragraph::new(tui_app::raster_lines(
+            self.map(),
+            map_block.inner(columns[0]),
+        ))
+        .block(map_block);
+        f.render_widget(map, columns[0]);
+
+        let info = Paragraph::new(self.info_lines())
+            .block(Block::default().title("Forest").borders(Borders::ALL));
+        f.render_widget(info, columns[1]);
+
+        let help = Paragraph::new(Spans::from("[tab/←/→] switch map  [q] quit"));
+        f.render_widget(help, outer[2]);
+    }
+
+    /// The maps don't change, so there's nothing to update.
+    fn on_tick(&mut self) {}
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if key.kind == KeyEventKind::Release {
+            return Control::Continue;
+        }
+
+        if let KeyCode::Tab | KeyCode::BackTab | KeyCode::Left | KeyCode::Right = key.code {
+            self.tab = self.tab.other();
+        }
+
+        Control::Continue
+    }
+}
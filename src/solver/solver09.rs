@@ -1,636 +1,174 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{BTreeMap, HashMap, VecDeque},
     fmt,
     hash::Hash,
-    io::{self, BufRead},
+    io::BufRead,
     ops,
-    time::{Duration, Instant},
 };
 
-use color_eyre::eyre::Context;
-use crossterm::{
-    event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
-        MouseEvent, MouseEventKind,
-    },
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
 use itertools::Itertools;
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::space1,
-    combinator::{all_consuming, map, value},
-    sequence::{preceded, tuple},
-    Finish, IResult,
+    combinator::{map, value},
+    IResult,
 };
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{
-        canvas::{Canvas, Line, Points},
-        Block, Borders, Paragraph,
-    },
-    Frame, Terminal,
+use nom_supreme::{error::ErrorTree, tag::complete::tag};
+use serde_json::json;
+
+use crate::{
+    error::{Context, SolverError},
+    parse::{line_span, parse_instruction, parse_numbered_lines, BadInputError},
+    simulation::{Pacing, Simulation, StepResult},
 };
 
-use super::ChallengeSolver;
+use super::{Answer, ChallengeSolver, Frontend, ParamError, Scrambler, SolverConfig};
+
+#[cfg(feature = "tui")]
+mod viewer;
 
 #[derive(Debug, Default)]
-pub struct Solver09;
+pub struct Solver09 {
+    /// Watch the rope move in a TUI, instead of just printing the answer.
+    visualize: bool,
+    /// How many knots the rope has, including the head. Defaults to 2 for part A and 10 for part
+    /// B.
+    knots: Option<usize>,
+    /// How fast the rope moves in the TUI, and how many instructions it follows per frame.
+    pacing: Pacing,
+}
 
 impl ChallengeSolver for Solver09 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         9
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        // setup terminal
-        enable_raw_mode().wrap_err("Could not initialize terminal UI")?;
-        let mut stdout = io::stdout();
-        crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
-            .wrap_err("Could not initialize terminal UI")?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).wrap_err("Could not initialize terminal UI")?;
-
-        // Initialize app
-        let app = AppA::new(input)?;
-
-        // Run the app
-        let tick_rate = Duration::from_secs_f64(1.0 / 60.0);
-        let res = app.run(&mut terminal, tick_rate);
-
-        // Restore terminal
-        disable_raw_mode().wrap_err("Could not deinitialize terminal UI")?;
-        crossterm::execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )
-        .wrap_err("Could not deinitialize terminal UI")?;
-        terminal
-            .show_cursor()
-            .wrap_err("Could not deinitialize terminal UI")?;
-
-        // Remember to unwrap the result of running the app AFTER restoring the terminal
-        res?;
-
-        Ok(Box::new(()))
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        self.knots = config.params.get("knots")?;
+        self.pacing = Pacing::from_params(&config.params)?;
+        if let Some(knots @ 0..=1) = self.knots {
+            return Err(ParamError::InvalidValue {
+                key: "knots".to_string(),
+                value: knots.to_string(),
+                reason: "a rope needs at least a head and a tail".to_string(),
+            });
+        }
+        Ok(())
     }
 
-    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        // setup terminal
-        enable_raw_mode().wrap_err("Could not initialize terminal UI")?;
-        let mut stdout = io::stdout();
-        crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
-            .wrap_err("Could not initialize terminal UI")?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).wrap_err("Could not initialize terminal UI")?;
-
-        // Initialize app
-        let app = AppB::new(input)?;
-
-        // Run the app
-        let tick_rate = Duration::from_secs_f64(1.0 / 60.0);
-        let res = app.run(&mut terminal, tick_rate);
-
-        // Restore terminal
-        disable_raw_mode().wrap_err("Could not deinitialize terminal UI")?;
-        crossterm::execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )
-        .wrap_err("Could not deinitialize terminal UI")?;
-        terminal
-            .show_cursor()
-            .wrap_err("Could not deinitialize terminal UI")?;
-
-        // Remember to unwrap the result of running the app AFTER restoring the terminal
-        res?;
-
-        Ok(Box::new(()))
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
     }
-}
 
-struct AppA {
-    instructions: VecDeque<Instruction>,
-    head: GridPos,
-    tail: GridPos,
-    tail_visited_positions: HashSet<GridPos>,
-    instructions_scroll: u16,
-}
-
-impl AppA {
-    fn new(input: &mut dyn BufRead) -> color_eyre::Result<Self> {
-        let instructions = input
-            .lines()
-            .map(|l| -> color_eyre::Result<Instruction> {
-                l.wrap_err("Could not read line from input file")
-                    .map(|l| all_consuming(Instruction::parse)(&l).finish().unwrap().1)
-            })
-            .collect::<Result<VecDeque<Instruction>, _>>()
-            .wrap_err("Could not parse instructions")?;
-
-        Ok(Self {
-            instructions,
-            head: GridPos { x: 0, y: 0 },
-            tail: GridPos { x: 0, y: 0 },
-            tail_visited_positions: HashSet::default(),
-            instructions_scroll: 0,
-        })
+    fn generate(&self, size: usize, rng: &mut Scrambler) -> Option<Result<String, SolverError>> {
+        Some(Ok(generate(size, rng)))
     }
 
-    /// Run the app.
-    fn run<B: Backend>(
-        mut self,
-        terminal: &mut Terminal<B>,
-        tick_rate: Duration,
-    ) -> color_eyre::Result<()> {
-        let mut last_tick = Instant::now();
-        loop {
-            terminal
-                .draw(|f| self.ui(f))
-                .wrap_err("Error while drawing UI frame.")?;
-
-            let timeout = tick_rate
-                .checked_sub(last_tick.elapsed())
-                .unwrap_or_else(|| Duration::from_secs(0));
-
-            if event::poll(timeout).wrap_err("Could not poll terminal for new I/O events")? {
-                match event::read().wrap_err("Could not read terminal I/O event")? {
-                    Event::Key(key) => match key {
-                        KeyEvent {
-                            code: KeyCode::Char('q'),
-                            ..
-                        } => return Ok(()),
-
-                        KeyEvent {
-                            code: KeyCode::Up,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_up(1);
-                        }
-
-                        KeyEvent {
-                            code: KeyCode::Down,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_down(1);
-                        }
-
-                        KeyEvent {
-                            code: KeyCode::PageUp,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_up(10);
-                        }
-
-                        KeyEvent {
-                            code: KeyCode::PageDown,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_down(10);
-                        }
-
-                        _ => {}
-                    },
-
-                    Event::Mouse(ev) => match ev {
-                        MouseEvent {
-                            kind: MouseEventKind::ScrollUp,
-                            ..
-                        } => {
-                            self.scroll_up(2);
-                        }
-
-                        MouseEvent {
-                            kind: MouseEventKind::ScrollDown,
-                            ..
-                        } => {
-                            self.scroll_down(2);
-                        }
-
-                        _ => {}
-                    },
-
-                    _ => {}
-                }
-            }
-
-            if last_tick.elapsed() >= tick_rate {
-                self.on_tick();
-                last_tick = Instant::now();
-            }
-        }
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let rope = Rope::new(parse_instructions(input)?, self.knots.unwrap_or(2));
+        self.drag_rope(rope)
     }
 
-    /// Render the app UI to a tui frame
-    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
-        let chunks = Layout::default()
-            .direction(tui::layout::Direction::Horizontal)
-            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-            .split(f.size());
-
-        let sidebar_chunks = Layout::default()
-            .direction(tui::layout::Direction::Vertical)
-            .constraints([
-                Constraint::Length(3),
-                Constraint::Min(1),
-                Constraint::Percentage(30),
-            ])
-            .split(chunks[0]);
-
-        // Render the current count of visited places
-        let visited_block = Block::default()
-            .title("Tail locations")
-            .borders(Borders::ALL);
-        let visited = Paragraph::new(Span::styled(
-            self.tail_visited_positions.len().to_string(),
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        ))
-        .block(visited_block);
-        f.render_widget(visited, sidebar_chunks[0]);
-
-        // Render out all the instructions
-        let instructions_block = Block::default().title("Instructions").borders(Borders::ALL);
-        let instructions = Paragraph::new(
-            self.instructions
-                .iter()
-                .map(|i| Spans::from(i.to_string()))
-                .collect::<Vec<_>>(),
-        )
-        .block(instructions_block)
-        .scroll((self.instructions_scroll, 0));
-        f.render_widget(instructions, sidebar_chunks[1]);
-
-        // Render the simulation
-        let simulation_renderer = |ctx: &mut tui::widgets::canvas::Context| {
-            // Draw all the locations visited by the tail
-            let tail_visited_positions = self
-                .tail_visited_positions
-                .iter()
-                .map(|pos| (pos.x as f64, pos.y as f64))
-                .collect::<Vec<_>>();
-            ctx.draw(&Points {
-                coords: &tail_visited_positions,
-                color: Color::Rgb(100, 0, 0),
-            });
-
-            // Draw origin
-            ctx.layer();
-            ctx.draw(&Points {
-                coords: &[(0.0, 0.0)],
-                color: Color::White,
-            });
-
-            // Draw the rope itself
-            ctx.layer();
-            ctx.draw(&Line {
-                x1: self.tail.x as _,
-                y1: self.tail.y as _,
-                x2: self.head.x as _,
-                y2: self.head.y as _,
-                color: Color::Yellow,
-            });
-
-            // Draw the rope's tail
-            ctx.layer();
-            ctx.draw(&Points {
-                coords: &[(self.tail.x as f64, self.tail.y as f64)],
-                color: Color::LightRed,
-            });
+    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let rope = Rope::new(parse_instructions(input)?, self.knots.unwrap_or(10));
+        self.drag_rope(rope)
+    }
+}
 
-            // Draw the rope's head
-            ctx.layer();
-            ctx.draw(&Points {
-                coords: &[(self.head.x as f64, self.head.y as f64)],
-                color: Color::Green,
-            });
-        };
+impl Solver09 {
+    /// Carry out all of the rope's instructions, and count where its tail has been.
+    fn drag_rope(&self, mut rope: Rope) -> super::ChallengeSolverResult {
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            rope = viewer::watch(rope, self.pacing)?;
+        }
 
-        let simulation_block = Block::default().title("Simulation").borders(Borders::ALL);
-        let simulation_canvas = Canvas::default()
-            .block(simulation_block)
-            .x_bounds([-250.0, 250.0])
-            .y_bounds([-250.0, 250.0])
-            .paint(simulation_renderer);
-        f.render_widget(simulation_canvas, chunks[1]);
-
-        // Render a zoomed-in view of the rope
-        let closeup_block = Block::default().title("Close-up").borders(Borders::ALL);
-        let closeup_canvas = Canvas::default()
-            .block(closeup_block)
-            .x_bounds([self.head.x as f64 - 10.0, self.head.x as f64 + 10.0])
-            .y_bounds([self.head.y as f64 - 10.0, self.head.y as f64 + 10.0])
-            .paint(simulation_renderer);
-        f.render_widget(closeup_canvas, sidebar_chunks[2]);
-    }
-
-    fn scroll_up(&mut self, offset: u16) {
-        self.instructions_scroll = self.instructions_scroll.saturating_sub(offset);
-    }
-
-    fn scroll_down(&mut self, offset: u16) {
-        self.instructions_scroll = (self.instructions_scroll.saturating_add(offset))
-            .min(self.instructions.len().saturating_sub(1) as _);
-    }
-
-    /// Update the app's simulation
-    fn on_tick(&mut self) {
-        let Some(instruction) = self.instructions.front_mut() else { return; };
-        self.head += instruction.dir.delta();
-
-        let diff = self.head - self.tail;
-        let (dx, dy) = match (diff.x, diff.y) {
-            // overlapping
-            (0, 0) => (0, 0),
-
-            // touching up/left/down/right
-            (0, 1) | (1, 0) | (0, -1) | (-1, 0) => (0, 0),
-            // touching diagonally
-            (1, 1) | (1, -1) | (-1, 1) | (-1, -1) => (0, 0),
-
-            // Need to move tail up/down/left/right
-            (0, 2) => (0, 1),
-            (0, -2) => (0, -1),
-            (2, 0) => (1, 0),
-            (-2, 0) => (-1, 0),
-
-            // Need to move the tail diagonally right
-            (2, 1) => (1, 1),
-            (2, -1) => (1, -1),
-
-            // Need to move the tail diagonally left
-            (-2, 1) => (-1, 1),
-            (-2, -1) => (-1, -1),
-
-            // Need to move the tail up/down diagonally
-            (1, 2) => (1, 1),
-            (-1, 2) => (-1, 1),
-            (1, -2) => (1, -1),
-            (-1, -2) => (-1, -1),
-
-            _ => panic!("unhandled case: tail - head = {diff:?}"),
-        };
+        // Finish dragging, in case the visualization was closed before the last instruction.
+        rope.run(None);
 
-        self.tail.x += dx;
-        self.tail.y += dy;
-        self.tail_visited_positions.insert(self.tail);
+        let visited = rope.tail_visits.len();
+        println!("tail visited {visited} positions");
 
-        instruction.dist -= 1;
-        if instruction.dist == 0 {
-            self.instructions.pop_front();
-        }
+        Ok(Answer::new(visited)
+            .with_metadata("metrics", rope.metrics())
+            .with_metadata("visits", rope.visit_stats()))
     }
 }
 
-struct AppB {
+/// A rope made up of knots, being dragged around by its head.
+struct Rope {
+    /// Every instruction from the input, so that the rope can be reset.
+    all_instructions: Vec<Instruction>,
+    /// The instructions that still have to be carried out.
     instructions: VecDeque<Instruction>,
-    knots: [GridPos; 10],
-    tail_visited_positions: HashSet<GridPos>,
-    instructions_scroll: u16,
+    /// The rope's knots, starting with the head.
+    knots: Vec<GridPos>,
+    /// How many times the tail has moved into each position, counting where it started as one.
+    tail_visits: HashMap<GridPos, u32>,
 }
 
-impl AppB {
-    fn new(input: &mut dyn BufRead) -> color_eyre::Result<Self> {
-        let instructions = input
-            .lines()
-            .map(|l| -> color_eyre::Result<Instruction> {
-                l.wrap_err("Could not read line from input file")
-                    .map(|l| all_consuming(Instruction::parse)(&l).finish().unwrap().1)
-            })
-            .collect::<Result<VecDeque<Instruction>, _>>()
-            .wrap_err("Could not parse instructions")?;
-
-        Ok(Self {
-            instructions,
-            knots: [GridPos { x: 0, y: 0 }; 10],
-            tail_visited_positions: HashSet::default(),
-            instructions_scroll: 0,
-        })
-    }
-
-    /// Run the app.
-    fn run<B: Backend>(
-        mut self,
-        terminal: &mut Terminal<B>,
-        tick_rate: Duration,
-    ) -> color_eyre::Result<()> {
-        let mut last_tick = Instant::now();
-        loop {
-            terminal
-                .draw(|f| self.ui(f))
-                .wrap_err("Error while drawing UI frame.")?;
-
-            let timeout = tick_rate
-                .checked_sub(last_tick.elapsed())
-                .unwrap_or_else(|| Duration::from_secs(0));
-
-            if event::poll(timeout).wrap_err("Could not poll terminal for new I/O events")? {
-                match event::read().wrap_err("Could not read terminal I/O event")? {
-                    Event::Key(key) => match key {
-                        KeyEvent {
-                            code: KeyCode::Char('q'),
-                            ..
-                        } => return Ok(()),
-
-                        KeyEvent {
-                            code: KeyCode::Up,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_up(1);
-                        }
-
-                        KeyEvent {
-                            code: KeyCode::Down,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_down(1);
-                        }
-
-                        KeyEvent {
-                            code: KeyCode::PageUp,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_up(10);
-                        }
-
-                        KeyEvent {
-                            code: KeyCode::PageDown,
-                            kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                            ..
-                        } => {
-                            self.scroll_down(10);
-                        }
-
-                        _ => {}
-                    },
-
-                    Event::Mouse(ev) => match ev {
-                        MouseEvent {
-                            kind: MouseEventKind::ScrollUp,
-                            ..
-                        } => {
-                            self.scroll_up(2);
-                        }
-
-                        MouseEvent {
-                            kind: MouseEventKind::ScrollDown,
-                            ..
-                        } => {
-                            self.scroll_down(2);
-                        }
-
-                        _ => {}
-                    },
-
-                    _ => {}
-                }
-            }
-
-            if last_tick.elapsed() >= tick_rate {
-                self.on_tick();
-                last_tick = Instant::now();
-            }
+impl Rope {
+    fn new(instructions: Vec<Instruction>, knot_count: usize) -> Self {
+        let start = GridPos { x: 0, y: 0 };
+        Self {
+            instructions: instructions.iter().copied().collect(),
+            all_instructions: instructions,
+            knots: vec![start; knot_count],
+            tail_visits: HashMap::from([(start, 1)]),
         }
     }
 
-    /// Render the app UI to a tui frame
-    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
-        let chunks = Layout::default()
-            .direction(tui::layout::Direction::Horizontal)
-            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-            .split(f.size());
-
-        let sidebar_chunks = Layout::default()
-            .direction(tui::layout::Direction::Vertical)
-            .constraints([
-                Constraint::Length(3),
-                Constraint::Min(1),
-                Constraint::Percentage(30),
-            ])
-            .split(chunks[0]);
-
-        // Render the current count of visited places
-        let visited_block = Block::default()
-            .title("Tail locations")
-            .borders(Borders::ALL);
-        let visited = Paragraph::new(Span::styled(
-            self.tail_visited_positions.len().to_string(),
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        ))
-        .block(visited_block);
-        f.render_widget(visited, sidebar_chunks[0]);
-
-        // Render out all the instructions
-        let instructions_block = Block::default().title("Instructions").borders(Borders::ALL);
-        let instructions = Paragraph::new(
-            self.instructions
-                .iter()
-                .map(|i| Spans::from(i.to_string()))
-                .collect::<Vec<_>>(),
-        )
-        .block(instructions_block)
-        .scroll((self.instructions_scroll, 0));
-        f.render_widget(instructions, sidebar_chunks[1]);
-
-        // Render the simulation
-        let simulation_renderer = |ctx: &mut tui::widgets::canvas::Context| {
-            // Draw all the locations visited by the tail
-            let tail_visited_positions = self
-                .tail_visited_positions
-                .iter()
-                .map(|pos| (pos.x as f64, pos.y as f64))
-                .collect::<Vec<_>>();
-            ctx.draw(&Points {
-                coords: &tail_visited_positions,
-                color: Color::Rgb(100, 0, 0),
-            });
-
-            // Draw origin
-            ctx.layer();
-            ctx.draw(&Points {
-                coords: &[(0.0, 0.0)],
-                color: Color::White,
-            });
-
-            // Draw the rope itself
-            ctx.layer();
-            for (p1, p2) in self.knots.iter().tuple_windows() {
-                ctx.draw(&Line {
-                    x1: p1.x as _,
-                    y1: p1.y as _,
-                    x2: p2.x as _,
-                    y2: p2.y as _,
-                    color: Color::Yellow,
-                });
+    /// The smallest and largest coordinates that the rope has reached so far, as
+    /// `[[min_x, max_x], [min_y, max_y]]`.
+    fn extents(&self) -> [[f64; 2]; 2] {
+        let range = |coord: fn(&GridPos) -> i32| {
+            let coords = self.tail_visits.keys().chain(&self.knots);
+            match coords.map(coord).minmax().into_option() {
+                Some((min, max)) => [min as f64, max as f64],
+                None => [0.0, 0.0],
             }
-
-            // Draw the rope's knots
-            ctx.layer();
-            let knot_points = self
-                .knots
-                .iter()
-                .skip(1)
-                .map(|pos| (pos.x as f64, pos.y as f64))
-                .collect::<Vec<_>>();
-            ctx.draw(&Points {
-                coords: &knot_points,
-                color: Color::LightRed,
-            });
-
-            // Draw the rope's head
-            ctx.layer();
-            ctx.draw(&Points {
-                coords: &[(self.knots[0].x as f64, self.knots[0].y as f64)],
-                color: Color::Green,
-            });
         };
-
-        let simulation_block = Block::default().title("Simulation").borders(Borders::ALL);
-        let simulation_canvas = Canvas::default()
-            .block(simulation_block)
-            .x_bounds([-500.0, 500.0])
-            .y_bounds([-500.0, 500.0])
-            .paint(simulation_renderer);
-        f.render_widget(simulation_canvas, chunks[1]);
-
-        // Render a zoomed-in view of the rope
-        let closeup_block = Block::default().title("Close-up").borders(Borders::ALL);
-        let closeup_canvas = Canvas::default()
-            .block(closeup_block)
-            .x_bounds([self.knots[0].x as f64 - 10.0, self.knots[0].x as f64 + 10.0])
-            .y_bounds([self.knots[0].y as f64 - 10.0, self.knots[0].y as f64 + 10.0])
-            .paint(simulation_renderer);
-        f.render_widget(closeup_canvas, sidebar_chunks[2]);
+        [range(|pos| pos.x), range(|pos| pos.y)]
     }
 
-    fn scroll_up(&mut self, offset: u16) {
-        self.instructions_scroll = self.instructions_scroll.saturating_sub(offset);
+    /// The position the tail has moved into most often, and how many times. Ties go to the
+    /// lowest `x`, then the lowest `y`.
+    fn most_visited(&self) -> (GridPos, u32) {
+        self.tail_visits
+            .iter()
+            .map(|(&pos, &count)| (pos, count))
+            .max_by_key(|&(pos, count)| (count, std::cmp::Reverse((pos.x, pos.y))))
+            .expect("the tail has always visited where it started")
     }
 
-    fn scroll_down(&mut self, offset: u16) {
-        self.instructions_scroll = (self.instructions_scroll.saturating_add(offset))
-            .min(self.instructions.len().saturating_sub(1) as _);
+    /// Where the tail went most, how many times it moved into a position all told, and how many
+    /// positions it visited how many times, for the answer's metadata.
+    fn visit_stats(&self) -> serde_json::Value {
+        let (most_visited, max) = self.most_visited();
+        let mut positions_by_visits = BTreeMap::<u32, usize>::new();
+        for &count in self.tail_visits.values() {
+            *positions_by_visits.entry(count).or_default() += 1;
+        }
+        json!({
+            "most_visited": [most_visited.x, most_visited.y],
+            "max": max,
+            "total_visits": self.tail_visits.values().map(|&count| u64::from(count)).sum::<u64>(),
+            "positions_by_visits": positions_by_visits,
+        })
     }
+}
 
-    /// Update the app's simulation
-    fn on_tick(&mut self) {
-        let Some(instruction) = self.instructions.front_mut() else { return; };
+impl Simulation for Rope {
+    /// Move the head by one position, and have the rest of the knots follow it.
+    ///
+    /// Once a knot stays put, none of the knots behind it can move either, so long ropes only
+    /// pay for the part that's actually moving.
+    fn step(&mut self) -> StepResult {
+        let Some(instruction) = self.instructions.front_mut() else {
+            return StepResult::Done;
+        };
         self.knots[0] += instruction.dir.delta();
 
         for i in 1..self.knots.len() {
@@ -673,11 +211,14 @@ impl AppB {
                 _ => panic!("unhandled case: knots[{}] - knots[{i}] = {diff:?}", i - 1),
             };
 
+            if (dx, dy) == (0, 0) {
+                break;
+            }
             self.knots[i].x += dx;
             self.knots[i].y += dy;
 
             if i == self.knots.len() - 1 {
-                self.tail_visited_positions.insert(self.knots[i]);
+                *self.tail_visits.entry(self.knots[i]).or_default() += 1;
             }
         }
 
@@ -685,9 +226,93 @@ impl AppB {
         if instruction.dist == 0 {
             self.instructions.pop_front();
         }
+
+        if self.instructions.is_empty() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(std::mem::take(&mut self.all_instructions), self.knots.len());
+    }
+
+    fn metrics(&self) -> serde_json::Value {
+        let head = self.knots[0];
+        let tail = self.knots[self.knots.len() - 1];
+        json!({
+            "head": [head.x, head.y],
+            "tail": [tail.x, tail.y],
+            "tail_visited": self.tail_visits.len(),
+            "instructions_left": self.instructions.len(),
+        })
     }
 }
 
+/// Parse the challenge input into a list of movement instructions.
+fn parse_instructions(input: &mut dyn BufRead) -> Result<Vec<Instruction>, SolverError> {
+    let mut input_buf = String::new();
+    input
+        .read_to_string(&mut input_buf)
+        .context("Could not read input file to string")?;
+
+    let instructions = parse_numbered_lines(&input_buf, Instruction::parse)
+        .context("Could not parse instructions")?;
+
+    // Follow the head through every move up front, so that a move it can't make points back at
+    // its line instead of panicking halfway through dragging the rope.
+    let mut problems = Vec::new();
+    let mut head = (0i64, 0i64);
+    for &(line, ins) in &instructions {
+        let delta = ins.dir.delta();
+        let dist = i64::from(ins.dist);
+        head = (
+            head.0 + i64::from(delta.x) * dist,
+            head.1 + i64::from(delta.y) * dist,
+        );
+        let problem = if ins.dist == 0 {
+            "this doesn't move the head at all"
+        } else if i32::try_from(head.0).is_err() || i32::try_from(head.1).is_err() {
+            "this moves the head off the edge of the map"
+        } else {
+            continue;
+        };
+        problems.push((line_span(&input_buf, line), problem.to_string()));
+        // Everything after a move off the map would be off it too.
+        if ins.dist != 0 {
+            break;
+        }
+    }
+    if !problems.is_empty() {
+        return Err(BadInputError::spans(
+            &input_buf,
+            "Some moves can't be made",
+            problems,
+        ))
+        .context("Could not follow instructions");
+    }
+
+    Ok(instructions.into_iter().map(|(_, ins)| ins).collect())
+}
+
+/// The furthest that a made-up move goes, which is about as far as the real ones do.
+const MAX_GENERATED_DIST: u64 = 20;
+
+/// Make up `moves` moves in random directions.
+///
+/// The head wanders about like a random walk, so it stays within a few thousand steps of where it
+/// started even for millions of moves, nowhere near the edge of the map.
+fn generate(moves: usize, rng: &mut Scrambler) -> String {
+    (0..moves)
+        .map(|_| {
+            let dir = ["U", "D", "L", "R"][rng.below(4) as usize];
+            let dist = 1 + rng.below(MAX_GENERATED_DIST);
+            format!("{dir} {dist}\n")
+        })
+        .collect()
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct GridPos {
     x: i32,
@@ -750,7 +375,7 @@ enum Direction {
 
 impl Direction {
     /// Try to parse a string into a direction.
-    fn parse(i: &str) -> IResult<&str, Self> {
+    fn parse(i: &str) -> IResult<&str, Self, ErrorTree<&str>> {
         alt((
             value(Self::Up, tag("U")),
             value(Self::Down, tag("D")),
@@ -804,12 +429,9 @@ struct Instruction {
 
 impl Instruction {
     /// Try to parse a direction and a distance into a movement instruction.
-    fn parse(i: &str) -> IResult<&str, Self> {
+    fn parse(i: &str) -> IResult<&str, Self, ErrorTree<&str>> {
         map(
-            tuple((
-                Direction::parse,
-                preceded(space1, nom::character::complete::u32),
-            )),
+            parse_instruction(Direction::parse, nom::character::complete::u32),
             |(dir, dist)| Self { dir, dist },
         )(i)
     }
@@ -822,3 +444,127 @@ impl fmt::Display for Instruction {
         arrows.fmt(f)
     }
 }
+
+super::challenge_solver_test_boilerplate! {
+    Solver09::default();
+    9 => {
+        a as usize,
+        b as usize,
+    }
+
+    #[test]
+    fn larger_sample() -> Result<(), SolverError> {
+        let answer = Solver09::default().solve_b(&mut Cursor::new(LARGER_SAMPLE))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&36));
+        Ok(())
+    }
+
+    #[test]
+    fn counts_visits_per_position() -> Result<(), SolverError> {
+        let answer = Solver09::default().solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert_eq!(
+            answer.metadata()["visits"],
+            json!({
+                "most_visited": [4, 3],
+                "max": 2,
+                "total_visits": 14,
+                "positions_by_visits": { "1": 12, "2": 1 },
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_ropes_without_a_tail() {
+        let mut config = SolverConfig::default();
+        config.params.insert("knots", "1");
+        assert!(matches!(
+            Solver09::default().configure(&config),
+            Err(ParamError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn points_at_moves_it_cant_make() {
+        let err = Solver09::default()
+            .solve_a(&mut Cursor::new("R 4\nU 0\nL 3\nD 0\n"))
+            .unwrap_err();
+        let SolverError::Parse { source, .. } = &err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        assert_eq!(source.lines().collect::<Vec<_>>(), vec![2, 4]);
+
+        let far = "R 2000000000\nU 1\nR 2000000000\nL 5\n";
+        let err = Solver09::default()
+            .solve_a(&mut Cursor::new(far))
+            .unwrap_err();
+        let SolverError::Parse { source, .. } = &err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        assert_eq!(source.lines().collect::<Vec<_>>(), vec![3]);
+        assert!(source.render().contains("off the edge of the map"));
+    }
+
+    #[test]
+    fn drags_ropes_along_generated_moves() -> Result<(), SolverError> {
+        let input = generate(20_000, &mut Scrambler::new(9));
+        assert_eq!(input, generate(20_000, &mut Scrambler::new(9)));
+
+        // Drag the rope one step at a time in the most obvious way, to check against.
+        let tail_visits = |knots: usize| {
+            let mut rope = vec![(0i32, 0i32); knots];
+            let mut visited = std::collections::HashSet::from([(0, 0)]);
+            for line in input.lines() {
+                let (dir, dist) = line.split_once(' ').unwrap();
+                let (dx, dy) = match dir {
+                    "U" => (0, 1),
+                    "D" => (0, -1),
+                    "L" => (-1, 0),
+                    _ => (1, 0),
+                };
+                for _ in 0..dist.parse::<u32>().unwrap() {
+                    rope[0] = (rope[0].0 + dx, rope[0].1 + dy);
+                    for i in 1..knots {
+                        let (x, y) = (rope[i - 1].0 - rope[i].0, rope[i - 1].1 - rope[i].1);
+                        if x.abs() > 1 || y.abs() > 1 {
+                            rope[i] = (rope[i].0 + x.signum(), rope[i].1 + y.signum());
+                        }
+                    }
+                    visited.insert(rope[knots - 1]);
+                }
+            }
+            visited.len()
+        };
+
+        let answer = Solver09::default().solve_a(&mut Cursor::new(&input))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&tail_visits(2)));
+        let answer = Solver09::default().solve_b(&mut Cursor::new(&input))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&tail_visits(10)));
+        Ok(())
+    }
+
+    #[test]
+    fn drags_long_ropes_quickly() -> Result<(), SolverError> {
+        // The larger sample leaves the head 11 left and 15 up of where it started, so repeating
+        // it keeps dragging the rope somewhere new.
+        let input = LARGER_SAMPLE.repeat(100);
+        let mut solver = Solver09 {
+            knots: Some(1000),
+            ..Default::default()
+        };
+
+        let start = std::time::Instant::now();
+        let answer = solver.solve_b(&mut Cursor::new(input))?;
+        let elapsed = start.elapsed();
+
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&509));
+        assert!(
+            elapsed < std::time::Duration::from_secs(5),
+            "dragging a 1000-knot rope took {elapsed:?}"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+const LARGER_SAMPLE: &str = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20\n";
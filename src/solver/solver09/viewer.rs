@@ -0,0 +1,563 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
+use itertools::Itertools;
+use owo_colors::Rgb;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{
+        canvas::{Canvas, Context, Line, Points},
+        Block, Borders, Paragraph,
+    },
+    Frame,
+};
+
+use crate::{
+    error::SolverError,
+    raster,
+    simulation::{Pacing, Simulation, StepResult},
+    tui_app::{self, Control, TuiApp, Viewport, TURBO_HELP},
+};
+
+use super::Rope;
+
+/// Watch the rope carry out its instructions, and return it once the view is closed.
+pub(super) fn watch(rope: Rope, pacing: Pacing) -> Result<Rope, SolverError> {
+    // Part A's view only draws the head and the knot right behind it.
+    if rope.knots.len() == 2 {
+        let mut app = AppA::new(rope);
+        tui_app::run(&mut app, 60.0, pacing)?;
+        Ok(app.rope)
+    } else {
+        let mut app = AppB::new(rope);
+        tui_app::run(&mut app, 60.0, pacing)?;
+        Ok(app.rope)
+    }
+}
+
+/// The key for switching the heatmap on and off, for the simulation's title.
+const HEATMAP_HELP: &str = "[h] heatmap";
+
+/// How many colors the heatmap is drawn with.
+const HEAT_LEVELS: usize = 16;
+
+/// Draw every position the tail has visited, either all in dark red or, for the heatmap, colored
+/// by how often it was visited.
+fn draw_tail_visits(ctx: &mut Context, rope: &Rope, heatmap: bool) {
+    if !heatmap {
+        let visited = rope
+            .tail_visits
+            .keys()
+            .map(|pos| (pos.x as f64, pos.y as f64))
+            .collect::<Vec<_>>();
+        ctx.draw(&Points {
+            coords: &visited,
+            color: Color::Rgb(100, 0, 0),
+        });
+        return;
+    }
+
+    // Most positions are only visited a few times, while the busiest ones can be visited hundreds
+    // of times, so the scale is logarithmic to tell the quieter ones apart. Points are drawn a
+    // color at a time, so they're sorted into a handful of levels first.
+    let (_, max) = rope.most_visited();
+    let mut levels = vec![Vec::new(); HEAT_LEVELS];
+    for (pos, &visits) in &rope.tail_visits {
+        let heat = (visits as f64).ln_1p() / (max as f64).ln_1p();
+        let level = (heat * (HEAT_LEVELS - 1) as f64).round() as usize;
+        levels[level].push((pos.x as f64, pos.y as f64));
+    }
+    for (level, coords) in levels.iter().enumerate() {
+        let Rgb(r, g, b) = raster::heat_color(level as f64 / (HEAT_LEVELS - 1) as f64);
+        ctx.draw(&Points {
+            coords,
+            color: Color::Rgb(r, g, b),
+        });
+    }
+}
+
+/// How many positions the tail has visited, and with the heatmap on, which one it visited most.
+fn visited_summary(rope: &Rope, heatmap: bool) -> Spans<'static> {
+    let mut spans = vec![Span::styled(
+        rope.tail_visits.len().to_string(),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )];
+    if heatmap {
+        let (pos, visits) = rope.most_visited();
+        spans.push(Span::raw(format!(
+            "  (most: {visits}× at {}, {})",
+            pos.x, pos.y
+        )));
+    }
+    Spans::from(spans)
+}
+
+struct AppA {
+    rope: Rope,
+    instructions_scroll: u16,
+    viewport: Viewport,
+    /// Color the tail's trail by how often it visited each position.
+    heatmap: bool,
+}
+
+impl AppA {
+    fn new(rope: Rope) -> Self {
+        Self {
+            rope,
+            instructions_scroll: 0,
+            viewport: Viewport::default(),
+            heatmap: false,
+        }
+    }
+
+    fn scroll_up(&mut self, offset: u16) {
+        self.instructions_scroll = self.instructions_scroll.saturating_sub(offset);
+    }
+
+    fn scroll_down(&mut self, offset: u16) {
+        self.instructions_scroll = (self.instructions_scroll.saturating_add(offset))
+            .min(self.rope.instructions.len().saturating_sub(1) as _);
+    }
+}
+
+impl TuiApp for AppA {
+    /// Render the app UI to a tui frame
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(tui::layout::Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+            .split(f.size());
+
+        let sidebar_chunks = Layout::default()
+            .direction(tui::layout::Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(1),
+                Constraint::Percentage(30),
+            ])
+            .split(chunks[0]);
+
+        // Render the current count of visited places
+        let visited_block = Block::default()
+            .title("Tail locations")
+            .borders(Borders::ALL);
+        let visited =
+            Paragraph::new(visited_summary(&self.rope, self.heatmap)).block(visited_block);
+        f.render_widget(visited, sidebar_chunks[0]);
+
+        // Render out all the instructions
+        let instructions_block = Block::default().title("Instructions").borders(Borders::ALL);
+        let instructions = Paragraph::new(
+            self.rope
+                .instructions
+                .iter()
+                .map(|i| Spans::from(i.to_string()))
+                .collect::<Vec<_>>(),
+        )
+        .block(instructions_block)
+        .scroll((self.instructions_scroll, 0));
+        f.render_widget(instructions, sidebar_chunks[1]);
+
+        // Render the simulation
+        let simulation_renderer = |ctx: &mut tui::widgets::canvas::Context| {
+            draw_tail_visits(ctx, &self.rope, self.heatmap);
+
+            // Draw origin
+            ctx.layer();
+            ctx.draw(&Points {
+                coords: &[(0.0, 0.0)],
+                color: Color::White,
+            });
+
+            // Draw the rope itself
+            ctx.layer();
+            ctx.draw(&Line {
+                x1: self.rope.knots[1].x as _,
+                y1: self.rope.knots[1].y as _,
+                x2: self.rope.knots[0].x as _,
+                y2: self.rope.knots[0].y as _,
+                color: Color::Yellow,
+            });
+
+            // Draw the rope's tail
+            ctx.layer();
+            ctx.draw(&Points {
+                coords: &[(self.rope.knots[1].x as f64, self.rope.knots[1].y as f64)],
+                color: Color::LightRed,
+            });
+
+            // Draw the rope's head
+            ctx.layer();
+            ctx.draw(&Points {
+                coords: &[(self.rope.knots[0].x as f64, self.rope.knots[0].y as f64)],
+                color: Color::Green,
+            });
+        };
+
+        let simulation_block = Block::default()
+            .title(format!(
+                "Simulation  {}  {HEATMAP_HELP}  {TURBO_HELP}",
+                Viewport::HELP
+            ))
+            .borders(Borders::ALL);
+        let [x_bounds, y_bounds] = self
+            .viewport
+            .bounds(self.rope.extents(), simulation_block.inner(chunks[1]));
+        let simulation_canvas = Canvas::default()
+            .block(simulation_block)
+            .x_bounds(x_bounds)
+            .y_bounds(y_bounds)
+            .paint(simulation_renderer);
+        f.render_widget(simulation_canvas, chunks[1]);
+
+        // Render a zoomed-in view of the rope
+        let closeup_block = Block::default().title("Close-up").borders(Borders::ALL);
+        let closeup_canvas = Canvas::default()
+            .block(closeup_block)
+            .x_bounds([
+                self.rope.knots[0].x as f64 - 10.0,
+                self.rope.knots[0].x as f64 + 10.0,
+            ])
+            .y_bounds([
+                self.rope.knots[0].y as f64 - 10.0,
+                self.rope.knots[0].y as f64 + 10.0,
+            ])
+            .paint(simulation_renderer);
+        f.render_widget(closeup_canvas, sidebar_chunks[2]);
+    }
+
+    /// Update the app's simulation
+    fn on_tick(&mut self) {
+        self.rope.step();
+    }
+
+    fn status(&self) -> StepResult {
+        if self.rope.instructions.is_empty() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if self.viewport.on_key(key) {
+            return Control::Continue;
+        }
+
+        match key {
+            KeyEvent {
+                code: KeyCode::Up,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_up(1);
+            }
+
+            KeyEvent {
+                code: KeyCode::Down,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_down(1);
+            }
+
+            KeyEvent {
+                code: KeyCode::PageUp,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_up(10);
+            }
+
+            KeyEvent {
+                code: KeyCode::PageDown,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_down(10);
+            }
+
+            KeyEvent {
+                code: KeyCode::Char('r'),
+                kind: KeyEventKind::Press,
+                ..
+            } => {
+                self.rope.reset();
+                self.instructions_scroll = 0;
+            }
+
+            KeyEvent {
+                code: KeyCode::Char('h'),
+                kind: KeyEventKind::Press,
+                ..
+            } => {
+                self.heatmap = !self.heatmap;
+            }
+
+            _ => {}
+        }
+
+        Control::Continue
+    }
+
+    fn on_mouse(&mut self, ev: MouseEvent) {
+        match ev {
+            MouseEvent {
+                kind: MouseEventKind::ScrollUp,
+                ..
+            } => {
+                self.scroll_up(2);
+            }
+
+            MouseEvent {
+                kind: MouseEventKind::ScrollDown,
+                ..
+            } => {
+                self.scroll_down(2);
+            }
+
+            _ => {}
+        }
+    }
+}
+
+struct AppB {
+    rope: Rope,
+    instructions_scroll: u16,
+    viewport: Viewport,
+    /// Color the tail's trail by how often it visited each position.
+    heatmap: bool,
+}
+
+impl AppB {
+    fn new(rope: Rope) -> Self {
+        Self {
+            rope,
+            instructions_scroll: 0,
+            viewport: Viewport::default(),
+            heatmap: false,
+        }
+    }
+
+    fn scroll_up(&mut self, offset: u16) {
+        self.instructions_scroll = self.instructions_scroll.saturating_sub(offset);
+    }
+
+    fn scroll_down(&mut self, offset: u16) {
+        self.instructions_scroll = (self.instructions_scroll.saturating_add(offset))
+            .min(self.rope.instructions.len().saturating_sub(1) as _);
+    }
+}
+
+impl TuiApp for AppB {
+    /// Render the app UI to a tui frame
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(tui::layout::Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+            .split(f.size());
+
+        let sidebar_chunks = Layout::default()
+            .direction(tui::layout::Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(1),
+                Constraint::Percentage(30),
+            ])
+            .split(chunks[0]);
+
+        // Render the current count of visited places
+        let visited_block = Block::default()
+            .title("Tail locations")
+            .borders(Borders::ALL);
+        let visited =
+            Paragraph::new(visited_summary(&self.rope, self.heatmap)).block(visited_block);
+        f.render_widget(visited, sidebar_chunks[0]);
+
+        // Render out all the instructions
+        let instructions_block = Block::default().title("Instructions").borders(Borders::ALL);
+        let instructions = Paragraph::new(
+            self.rope
+                .instructions
+                .iter()
+                .map(|i| Spans::from(i.to_string()))
+                .collect::<Vec<_>>(),
+        )
+        .block(instructions_block)
+        .scroll((self.instructions_scroll, 0));
+        f.render_widget(instructions, sidebar_chunks[1]);
+
+        // Render the simulation
+        let simulation_renderer = |ctx: &mut tui::widgets::canvas::Context| {
+            draw_tail_visits(ctx, &self.rope, self.heatmap);
+
+            // Draw origin
+            ctx.layer();
+            ctx.draw(&Points {
+                coords: &[(0.0, 0.0)],
+                color: Color::White,
+            });
+
+            // Draw the rope itself
+            ctx.layer();
+            for (p1, p2) in self.rope.knots.iter().tuple_windows() {
+                ctx.draw(&Line {
+                    x1: p1.x as _,
+                    y1: p1.y as _,
+                    x2: p2.x as _,
+                    y2: p2.y as _,
+                    color: Color::Yellow,
+                });
+            }
+
+            // Draw the rope's knots
+            ctx.layer();
+            let knot_points = self
+                .rope
+                .knots
+                .iter()
+                .skip(1)
+                .map(|pos| (pos.x as f64, pos.y as f64))
+                .collect::<Vec<_>>();
+            ctx.draw(&Points {
+                coords: &knot_points,
+                color: Color::LightRed,
+            });
+
+            // Draw the rope's head
+            ctx.layer();
+            ctx.draw(&Points {
+                coords: &[(self.rope.knots[0].x as f64, self.rope.knots[0].y as f64)],
+                color: Color::Green,
+            });
+        };
+
+        let simulation_block = Block::default()
+            .title(format!(
+                "Simulation  {}  {HEATMAP_HELP}  {TURBO_HELP}",
+                Viewport::HELP
+            ))
+            .borders(Borders::ALL);
+        let [x_bounds, y_bounds] = self
+            .viewport
+            .bounds(self.rope.extents(), simulation_block.inner(chunks[1]));
+        let simulation_canvas = Canvas::default()
+            .block(simulation_block)
+            .x_bounds(x_bounds)
+            .y_bounds(y_bounds)
+            .paint(simulation_renderer);
+        f.render_widget(simulation_canvas, chunks[1]);
+
+        // Render a zoomed-in view of the rope
+        let closeup_block = Block::default().title("Close-up").borders(Borders::ALL);
+        let closeup_canvas = Canvas::default()
+            .block(closeup_block)
+            .x_bounds([
+                self.rope.knots[0].x as f64 - 10.0,
+                self.rope.knots[0].x as f64 + 10.0,
+            ])
+            .y_bounds([
+                self.rope.knots[0].y as f64 - 10.0,
+                self.rope.knots[0].y as f64 + 10.0,
+            ])
+            .paint(simulation_renderer);
+        f.render_widget(closeup_canvas, sidebar_chunks[2]);
+    }
+
+    /// Update the app's simulation
+    fn on_tick(&mut self) {
+        self.rope.step();
+    }
+
+    fn status(&self) -> StepResult {
+        if self.rope.instructions.is_empty() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if self.viewport.on_key(key) {
+            return Control::Continue;
+        }
+
+        match key {
+            KeyEvent {
+                code: KeyCode::Up,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_up(1);
+            }
+
+            KeyEvent {
+                code: KeyCode::Down,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_down(1);
+            }
+
+            KeyEvent {
+                code: KeyCode::PageUp,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_up(10);
+            }
+
+            KeyEvent {
+                code: KeyCode::PageDown,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.scroll_down(10);
+            }
+
+            KeyEvent {
+                code: KeyCode::Char('r'),
+                kind: KeyEventKind::Press,
+                ..
+            } => {
+                self.rope.reset();
+                self.instructions_scroll = 0;
+            }
+
+            KeyEvent {
+                code: KeyCode::Char('h'),
+                kind: KeyEventKind::Press,
+                ..
+            } => {
+                self.heatmap = !self.heatmap;
+            }
+
+            _ => {}
+        }
+
+        Control::Continue
+    }
+
+    fn on_mouse(&mut self, ev: MouseEvent) {
+        match ev {
+            MouseEvent {
+                kind: MouseEventKind::ScrollUp,
+                ..
+            } => {
+                self.scroll_up(2);
+            }
+
+            MouseEvent {
+                kind: MouseEventKind::ScrollDown,
+                ..
+            } => {
+                self.scroll_down(2);
+            }
+
+            _ => {}
+        }
+    }
+}
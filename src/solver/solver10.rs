@@ -1,41 +1,94 @@
-use std::{fmt, io::BufRead};
-
-use color_eyre::eyre::Context;
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::space1,
-    combinator::{all_consuming, map, value},
-    sequence::preceded,
-    Finish, IResult,
+use std::io::BufRead;
+
+#[cfg(feature = "tui")]
+use crate::tui_app;
+use crate::{
+    error::{Context, SolverError},
+    parse::{line_span, parse_numbered_lines, BadInputError, LineNo},
+    simulation::Pacing,
 };
 
-use super::ChallengeSolver;
+use self::cpu::{CpuFault, CrtGeometry, Instruction, Machine};
+#[cfg(feature = "tui")]
+use self::debugger::Debugger;
+
+use super::{ChallengeSolver, Frontend, ParamError, ParseStats, Rendering, SolverConfig};
+
+mod cpu;
+#[cfg(feature = "tui")]
+mod debugger;
 
 #[derive(Debug, Default)]
-pub struct Solver10;
+pub struct Solver10 {
+    /// Step through the program in the interactive debugger instead of running it headless.
+    visualize: bool,
+    /// How many cycles a second the debugger runs at.
+    pacing: Pacing,
+    /// The size of the CRT, and of its sprite.
+    geometry: CrtGeometry,
+}
 
 impl ChallengeSolver for Solver10 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         10
     }
 
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        self.pacing = Pacing::from_params(&config.params)?;
+        self.geometry = CrtGeometry::from_params(&config.params)?;
+        Ok(())
+    }
+
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
+    }
+
+    fn validate(&self, input: &str) -> Option<Result<ParseStats, SolverError>> {
+        Some(Program::parse(&mut input.as_bytes()).map(|program| {
+            let instructions = &program.instructions;
+            let addx = instructions
+                .iter()
+                .filter(|(_, ins)| matches!(ins, Instruction::AddX(_)))
+                .count();
+            ParseStats::default()
+                .entity("instructions", instructions.len())
+                .entity("addx", addx)
+                .entity("noop", instructions.len() - addx)
+        }))
+    }
+
+    super::sample_methods!();
+
+    fn gallery(&self, input: &str) -> Option<Result<Vec<Rendering>, SolverError>> {
+        Some(Program::parse(&mut input.as_bytes()).and_then(|program| {
+            let mut machine = Machine::new(program.instructions.clone(), self.geometry);
+            while !machine.halted() {
+                machine.draw();
+                machine.tick().map_err(|fault| program.fault(fault))?;
+            }
+            Ok(vec![Rendering::text(
+                "crt",
+                format!("{:?}\n", machine.display),
+            )])
+        }))
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        // Parse instructions
-        let instructions = input
-            .lines()
-            .map(|l| -> color_eyre::Result<Instruction> {
-                l.wrap_err("Could not read line from input file")
-                    .map(|l| all_consuming(Instruction::parse)(&l).finish().unwrap().1)
-            })
-            .collect::<Result<Vec<Instruction>, _>>()
-            .wrap_err("Could not parse instructions")?;
+        let program = Program::parse(input)?;
+
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            let debugger = run_debugger(&program, self.geometry, self.pacing)?;
+            println!("total: {}", debugger.signal_total());
+            return Ok(super::Answer::new(debugger.signal_total()));
+        }
 
         // Execute instructions
         println!("=============");
         println!("| EXECUTION |");
         println!("=============");
-        let mut machine = Machine::new(instructions);
+        let mut machine = Machine::new(program.instructions.clone(), self.geometry);
 
         let mut total = 0;
         let mut count = 0;
@@ -43,19 +96,19 @@ impl ChallengeSolver for Solver10 {
         loop {
             println!("{machine:?}");
 
-            if matches!(machine.cpu.cycle, 20 | 60 | 100 | 140 | 180 | 220) {
-                total += machine.cpu.cycle as i64 * machine.cpu.x as i64;
+            if machine.is_interesting_cycle() {
+                total += machine.signal_strength();
                 count += 1;
                 println!(
                     "CYCLE: {}, X: {}, STRENGTH: {}, TOTAL: {}",
                     machine.cpu.cycle,
                     machine.cpu.x,
-                    machine.cpu.cycle as i64 * machine.cpu.x as i64,
+                    machine.signal_strength(),
                     total,
                 );
             }
 
-            if !machine.tick() {
+            if !machine.tick().map_err(|fault| program.fault(fault))? {
                 break;
             }
         }
@@ -67,239 +120,163 @@ impl ChallengeSolver for Solver10 {
         println!("total: {total}");
         println!("interesting count: {count}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(total))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        // Parse instructions
-        let instructions = input
-            .lines()
-            .map(|l| -> color_eyre::Result<Instruction> {
-                l.wrap_err("Could not read line from input file")
-                    .map(|l| all_consuming(Instruction::parse)(&l).finish().unwrap().1)
-            })
-            .collect::<Result<Vec<Instruction>, _>>()
-            .wrap_err("Could not parse instructions")?;
+        let program = Program::parse(input)?;
+
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            let debugger = run_debugger(&program, self.geometry, self.pacing)?;
+            println!("{:?}", debugger.machine().display);
+            return Ok(super::Answer::new(debugger.machine().display.clone()));
+        }
 
         // Execute instructions
         println!("=============");
         println!("| EXECUTION |");
         println!("=============");
-        let mut machine = Machine::new(instructions);
+        let mut machine = Machine::new(program.instructions.clone(), self.geometry);
 
-        loop {
+        // Like the debugger, stop as soon as the last instruction is done, rather than drawing one
+        // more pixel on a line of its own.
+        while !machine.halted() {
             machine.draw();
             println!("{machine:?}");
-            if !machine.tick() {
-                break;
-            }
+            machine.tick().map_err(|fault| program.fault(fault))?;
         }
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(machine.display))
     }
 }
 
-struct Machine {
-    instructions: Vec<Instruction>,
-    cpu: Cpu,
-    display: CrtDisplay,
+/// A CPU program, along with the input it came from so that faults can point back at it.
+struct Program {
+    src: String,
+    instructions: Vec<(LineNo, Instruction)>,
 }
 
-impl Machine {
-    fn new(instructions: Vec<Instruction>) -> Self {
-        let cpu = Cpu::new(&instructions);
-        Self {
-            instructions,
-            cpu,
-            display: CrtDisplay::default(),
-        }
-    }
-
-    fn tick(&mut self) -> bool {
-        self.cpu.execute(&self.instructions)
+impl Program {
+    /// Parse the challenge input into a CPU program.
+    fn parse(input: &mut dyn BufRead) -> Result<Self, SolverError> {
+        let mut src = String::new();
+        input
+            .read_to_string(&mut src)
+            .context("Could not read input file to string")?;
+
+        let instructions = parse_numbered_lines(&src, Instruction::parse)
+            .context("Could not parse instructions")?;
+        Ok(Self { src, instructions })
     }
 
-    fn draw(&mut self) {
-        self.display.draw(self.cpu.cycle as _, self.cpu.x);
+    /// The error for a fault, pointing out the line of the instruction that caused it.
+    fn fault(&self, fault: CpuFault) -> SolverError {
+        SolverError::Parse {
+            context: "Could not run the program".to_string(),
+            source: BadInputError::spans(
+                &self.src,
+                format!("The CPU faulted on cycle {}", fault.cycle),
+                [(line_span(&self.src, fault.line), fault.to_string())],
+            ),
+        }
     }
 }
 
-impl fmt::Debug for Machine {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(
-            f,
-            "cycle={:<3} pc={:<3} x={:<3} cur_ins={:?}",
-            self.cpu.cycle, self.cpu.pc, self.cpu.x, self.cpu.cur_ins
-        )?;
-        self.display.fmt(f)
+/// Run the interactive debugger until the user quits, returning it in its final state. Fails if
+/// the program faulted while it was being debugged.
+#[cfg(feature = "tui")]
+fn run_debugger(
+    program: &Program,
+    geometry: CrtGeometry,
+    pacing: Pacing,
+) -> Result<Debugger, SolverError> {
+    let mut debugger = Debugger::new(program.instructions.clone(), geometry);
+    tui_app::run(&mut debugger, 60.0, pacing)?;
+    match debugger.fault() {
+        Some(fault) => Err(program.fault(fault)),
+        None => Ok(debugger),
     }
 }
 
-#[derive(Debug)]
-struct Cpu {
-    x: i32,
-    pc: usize,
-    cycle: usize,
-    cur_ins: Option<(Instruction, u8)>,
-}
-
-impl Cpu {
-    fn new(instructions: &[Instruction]) -> Self {
-        let mut this = Self {
-            x: 1,
-            pc: 0,
-            cycle: 1,
-            cur_ins: None,
-        };
-        this.decode(instructions);
-        this
-    }
-
-    fn decode(&mut self, instructions: &[Instruction]) {
-        self.cur_ins = instructions.get(self.pc).map(|ins| (*ins, ins.cycles()));
-        self.pc += 1;
+super::challenge_solver_test_boilerplate! {
+    Solver10::default();
+    10 => {
+        a as i64,
     }
 
-    fn execute(&mut self, instructions: &[Instruction]) -> bool {
-        if self.cur_ins.is_none() {
-            return false;
-        }
-
-        let (ins, cycles_left) = self.cur_ins.as_mut().unwrap();
-        *cycles_left -= 1;
+    use std::fmt::Write;
 
-        if *cycles_left == 0 {
-            match ins {
-                Instruction::Noop => {}
-                Instruction::AddX(x) => self.x += *x,
-            }
-            self.decode(instructions);
-        }
+    use super::cpu::CrtDisplay;
 
-        self.cycle += 1;
-
-        true
-    }
-}
-
-struct CrtDisplay {
-    display_lines: Vec<u64>,
-}
-
-impl CrtDisplay {
-    fn new() -> Self {
-        Self {
-            display_lines: Vec::new(),
-        }
-    }
-
-    fn draw(&mut self, cycle: u64, x: i32) {
-        let cycle = cycle - 1;
-        let crt_line = (cycle / 40) as usize;
-        if crt_line + 1 > self.display_lines.len() {
-            self.display_lines.push(0);
-        }
-        let crt_line = self.display_lines.get_mut(crt_line).unwrap();
-        let cycle_mask = cycle_mask(cycle);
-        let sprite = sprite_value(x as _);
-        *crt_line |= cycle_mask & sprite;
-    }
-}
-
-impl Default for CrtDisplay {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+    /// A program that keeps the sprite over every other pair of pixels, drawing stripes two
+    /// pixels wide like the ones at the start of the puzzle's example.
+    fn stripes() -> String {
+        // Every `addx` takes two cycles, so each one draws a pair of pixels with where the one
+        // before it left the sprite.
+        let sprite = |pair: i32| match pair % 20 {
+            p if p % 2 == 0 => 2 * p,
+            _ => -10,
+        };
 
-impl fmt::Debug for CrtDisplay {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "╭──────────────────────────────────────────╮")?;
-        for line in &self.display_lines {
-            write!(f, "│ ")?;
-            for i in 0..40 {
-                let c = if line & cycle_mask(i) > 0 { '█' } else { ' ' };
-                write!(f, "{c}")?;
-            }
-            writeln!(f, " │")?;
+        let mut program = String::new();
+        let mut x = 1;
+        for pair in 0..120 {
+            let next = sprite(pair + 1);
+            writeln!(program, "addx {}", next - x).unwrap();
+            x = next;
         }
-        write!(f, "╰──────────────────────────────────────────╯")
-    }
-}
-
-const DISPLAY_MASK: u64 = 0b1111_1111_1111_1111_1111_1111_1111_1111_1111_1111;
-
-fn sprite_value(pos: i32) -> u64 {
-    let model = 0b1_1100_0000_0000_0000_0000_0000_0000_0000_0000_0000_u64;
-    let shifted;
-    if pos < 0 {
-        (shifted, _) = model.overflowing_shl((-pos).try_into().unwrap());
-    } else {
-        (shifted, _) = model.overflowing_shr(pos.try_into().unwrap());
+        program
     }
-    shifted & DISPLAY_MASK
-}
-
-fn cycle_mask(cycle: u64) -> u64 {
-    (0b1000_0000_0000_0000_0000_0000_0000_0000_0000_0000 >> (cycle % 40)) & DISPLAY_MASK
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Instruction {
-    Noop,
-    AddX(i32),
-}
-
-impl Instruction {
-    fn parse_noop(i: &str) -> IResult<&str, Self> {
-        value(Self::Noop, tag("noop"))(i)
-    }
+    #[test]
+    fn crt_draws_the_sprite() -> Result<(), SolverError> {
+        let answer = Solver10::default().solve_b(&mut Cursor::new(stripes()))?;
+        let display = answer.downcast_ref::<CrtDisplay>().unwrap();
 
-    fn parse_add_reg(i: &str) -> IResult<&str, Self> {
-        map(
-            preceded(tag("addx"), preceded(space1, nom::character::complete::i32)),
-            Self::AddX,
-        )(i)
+        crate::snapshot::unicode_glyphs();
+        insta::assert_snapshot!("crt_stripes", format!("{display:?}"));
+        Ok(())
     }
-
-    /// Try to parse an instruction
-    fn parse(i: &str) -> IResult<&str, Self> {
-        alt((Self::parse_noop, Self::parse_add_reg))(i)
+    #[test]
+    fn crt_takes_any_shape() -> Result<(), SolverError> {
+        let mut solver = Solver10::default();
+        let mut config = SolverConfig::default();
+        config.params.insert("crt_width", "5");
+        config.params.insert("crt_height", "2");
+        config.params.insert("sprite_width", "1");
+        solver.configure(&config).unwrap();
+
+        // `X` stays at 1 the whole time, and anything past the second line is off the screen.
+        let noops = "noop\n".repeat(12);
+        let answer = solver.solve_b(&mut Cursor::new(noops.clone()))?;
+        assert_eq!(answer.plain(), ".#...\\n.#...");
+
+        config.params.insert("sprite_width", "4");
+        solver.configure(&config).unwrap();
+        let answer = solver.solve_b(&mut Cursor::new(noops))?;
+        assert_eq!(answer.plain(), "####.\\n####.");
+
+        config.params.insert("crt_height", "0");
+        assert!(solver.configure(&config).is_err());
+        Ok(())
     }
 
-    /// Get the number of cycles that this instruction should be executed for.
-    fn cycles(&self) -> u8 {
-        match self {
-            Instruction::Noop => 1,
-            Instruction::AddX(_) => 2,
-        }
+    #[test]
+    fn faults_point_at_their_line() {
+        let program = format!("noop\naddx {}\nnoop\naddx 1\nnoop\n", i32::MAX - 1);
+        let err = Solver10::default()
+            .solve_b(&mut Cursor::new(program))
+            .unwrap_err();
+        let SolverError::Parse { source, .. } = &err else {
+            panic!("expected the fault to point into the input, got {err:?}");
+        };
+        assert_eq!(source.lines().collect::<Vec<_>>(), vec![4]);
+        let report = source.render();
+        assert!(report.contains("cycle 6"), "{report}");
+        assert!(
+            report.contains("`addx 1` overflows X, which is 2147483647"),
+            "{report}"
+        );
     }
 }
-
-#[test]
-fn test_sprite_value() {
-    assert_eq!(
-        format!("{:040b}", sprite_value(0)),
-        "1100000000000000000000000000000000000000"
-    );
-    assert_eq!(
-        format!("{:040b}", sprite_value(1)),
-        "1110000000000000000000000000000000000000"
-    );
-    assert_eq!(
-        format!("{:040b}", sprite_value(38)),
-        "0000000000000000000000000000000000000111"
-    );
-    assert_eq!(
-        format!("{:040b}", sprite_value(39)),
-        "0000000000000000000000000000000000000011"
-    );
-    assert_eq!(
-        format!("{:040b}", sprite_value(40)),
-        "0000000000000000000000000000000000000001"
-    );
-    assert_eq!(
-        format!("{:040b}", sprite_value(-1)),
-        "1000000000000000000000000000000000000000"
-    );
-}
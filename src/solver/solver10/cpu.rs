@@ -0,0 +1,354 @@
+use std::{fmt, num::NonZeroUsize};
+
+use nom::{
+    branch::alt,
+    combinator::{map, value},
+    IResult,
+};
+use nom_supreme::{error::ErrorTree, tag::complete::tag};
+
+use crate::{
+    glyphs,
+    grid::BitGrid,
+    parse::{parse_instruction, LineNo},
+    solver::{AnswerDisplay, ParamError, SolverParams},
+};
+
+/// The cycles during which the signal strength is sampled for part A.
+pub const INTERESTING_CYCLES: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
+/// The size of the CRT, and of the sprite that it draws with, from the `crt_width`, `crt_height`
+/// and `sprite_width` parameters. The puzzle's CRT is 40 pixels wide and 6 lines high, with a
+/// sprite 3 pixels wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrtGeometry {
+    /// How many pixels there are in each line.
+    pub width: usize,
+    /// How many lines there are.
+    pub height: usize,
+    /// How many pixels the sprite covers, centered on `X`, or just right of it when it's even.
+    pub sprite_width: usize,
+}
+
+impl CrtGeometry {
+    pub fn from_params(params: &SolverParams) -> Result<Self, ParamError> {
+        let default = Self::default();
+        let get = |key, default| {
+            params
+                .get_or(key, NonZeroUsize::new(default).unwrap())
+                .map(NonZeroUsize::get)
+        };
+        Ok(Self {
+            width: get("crt_width", default.width)?,
+            height: get("crt_height", default.height)?,
+            sprite_width: get("sprite_width", default.sprite_width)?,
+        })
+    }
+
+    /// Whether the sprite covers column `col` while it's at `x`.
+    pub fn sprite_covers(&self, x: i32, col: usize) -> bool {
+        let width = self.sprite_width as i64;
+        let left = i64::from(x) - (width - 1) / 2;
+        (left..left + width).contains(&(col as i64))
+    }
+
+    /// The line and column of the pixel that the beam is over during `cycle`, counting from 1.
+    pub fn beam(&self, cycle: usize) -> (usize, usize) {
+        let pixel = cycle - 1;
+        (pixel / self.width, pixel % self.width)
+    }
+}
+
+impl Default for CrtGeometry {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+        }
+    }
+}
+
+/// An instruction that can't be carried out, from the line it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("`{instruction}` overflows X, which is {x}")]
+pub struct CpuFault {
+    pub line: LineNo,
+    /// The cycle that the instruction would have finished during.
+    pub cycle: usize,
+    pub instruction: Instruction,
+    /// What `X` was before the instruction.
+    pub x: i32,
+}
+
+pub struct Machine {
+    /// The program, with the input line that each instruction came from.
+    pub instructions: Vec<(LineNo, Instruction)>,
+    pub cpu: Cpu,
+    pub display: CrtDisplay,
+}
+
+impl Machine {
+    pub fn new(instructions: Vec<(LineNo, Instruction)>, geometry: CrtGeometry) -> Self {
+        let cpu = Cpu::new(&instructions);
+        Self {
+            instructions,
+            cpu,
+            display: CrtDisplay::new(geometry),
+        }
+    }
+
+    /// Execute one cycle. Returns `false` once the program has run to completion. A fault leaves
+    /// the machine as it was before the cycle.
+    pub fn tick(&mut self) -> Result<bool, CpuFault> {
+        self.cpu.execute(&self.instructions)
+    }
+
+    /// Draw the pixel that the CRT's beam is currently over.
+    pub fn draw(&mut self) {
+        self.display.draw(self.cpu.cycle, self.cpu.x);
+    }
+
+    /// Returns `true` if there are no more instructions left to execute.
+    pub fn halted(&self) -> bool {
+        self.cpu.cur_ins.is_none()
+    }
+
+    /// The signal strength during the current cycle (the cycle number multiplied by `X`).
+    pub fn signal_strength(&self) -> i64 {
+        self.cpu.cycle as i64 * self.cpu.x as i64
+    }
+
+    /// Returns `true` if the signal strength should be sampled during the current cycle.
+    pub fn is_interesting_cycle(&self) -> bool {
+        INTERESTING_CYCLES.contains(&self.cpu.cycle)
+    }
+}
+
+impl fmt::Debug for Machine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "cycle={:<3} pc={:<3} x={:<3} cur_ins={:?}",
+            self.cpu.cycle, self.cpu.pc, self.cpu.x, self.cpu.cur_ins
+        )?;
+        self.display.fmt(f)
+    }
+}
+
+#[derive(Debug)]
+pub struct Cpu {
+    pub x: i32,
+    pub pc: usize,
+    pub cycle: usize,
+    pub cur_ins: Option<(Instruction, u8)>,
+}
+
+impl Cpu {
+    fn new(instructions: &[(LineNo, Instruction)]) -> Self {
+        let mut this = Self {
+            x: 1,
+            pc: 0,
+            cycle: 1,
+            cur_ins: None,
+        };
+        this.decode(instructions);
+        this
+    }
+
+    fn decode(&mut self, instructions: &[(LineNo, Instruction)]) {
+        self.cur_ins = instructions
+            .get(self.pc)
+            .map(|&(_, ins)| (ins, ins.cycles()));
+        self.pc += 1;
+    }
+
+    fn execute(&mut self, instructions: &[(LineNo, Instruction)]) -> Result<bool, CpuFault> {
+        let Some((ins, cycles_left)) = self.cur_ins.as_mut() else {
+            return Ok(false);
+        };
+
+        if *cycles_left == 1 {
+            match *ins {
+                Instruction::Noop => {}
+                Instruction::AddX(x) => {
+                    self.x = self.x.checked_add(x).ok_or(CpuFault {
+                        line: instructions[self.pc - 1].0,
+                        cycle: self.cycle,
+                        instruction: *ins,
+                        x: self.x,
+                    })?;
+                }
+            }
+            self.decode(instructions);
+        } else {
+            *cycles_left -= 1;
+        }
+
+        self.cycle += 1;
+
+        Ok(true)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct CrtDisplay {
+    geometry: CrtGeometry,
+    pixels: BitGrid,
+    /// The number of lines that the beam has started drawing so far.
+    lines: usize,
+}
+
+impl CrtDisplay {
+    fn new(geometry: CrtGeometry) -> Self {
+        Self {
+            geometry,
+            pixels: BitGrid::new(geometry.width, geometry.height),
+            lines: 0,
+        }
+    }
+
+    fn draw(&mut self, cycle: usize, x: i32) {
+        let (line, col) = self.geometry.beam(cycle);
+        // Anything after the last line is drawn off the bottom of the screen.
+        if line >= self.geometry.height {
+            return;
+        }
+        self.lines = self.lines.max(line + 1);
+        if self.geometry.sprite_covers(x, col) {
+            self.pixels.set((col, line).into(), true);
+        }
+    }
+
+    /// The number of lines that the beam has started drawing so far.
+    pub fn num_lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Returns `true` if the pixel at column `col` of line `line` is lit.
+    pub fn is_lit(&self, line: usize, col: usize) -> bool {
+        self.pixels.cell((col, line).into()).unwrap_or(false)
+    }
+}
+
+impl Default for CrtDisplay {
+    fn default() -> Self {
+        Self::new(CrtGeometry::default())
+    }
+}
+
+impl fmt::Debug for CrtDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let glyphs = glyphs::glyphs();
+        writeln!(f, "{}", glyphs.box_top(self.geometry.width + 2))?;
+        for line in 0..self.num_lines() {
+            write!(f, "{} ", glyphs.vertical)?;
+            for col in 0..self.geometry.width {
+                let c = if self.is_lit(line, col) {
+                    glyphs.full
+                } else {
+                    ' '
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f, " {}", glyphs.vertical)?;
+        }
+        write!(f, "{}", glyphs.box_bottom(self.geometry.width + 2))
+    }
+}
+
+/// The letters on the screen are the answer to part B, drawn with `#` and `.` like in the puzzle.
+impl AnswerDisplay for CrtDisplay {
+    fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in 0..self.num_lines() {
+            for col in 0..self.geometry.width {
+                f.write_str(if self.is_lit(line, col) { "#" } else { "." })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    /// The first line of pixels doesn't say much on its own, so tables just say what's there.
+    fn fmt_short(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}x{} CRT picture]",
+            self.geometry.width,
+            self.num_lines()
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    AddX(i32),
+}
+
+impl Instruction {
+    fn parse_noop(i: &str) -> IResult<&str, Self, ErrorTree<&str>> {
+        value(Self::Noop, tag("noop"))(i)
+    }
+
+    fn parse_add_reg(i: &str) -> IResult<&str, Self, ErrorTree<&str>> {
+        map(
+            parse_instruction(tag("addx"), nom::character::complete::i32),
+            |(_, x)| Self::AddX(x),
+        )(i)
+    }
+
+    /// Try to parse an instruction
+    pub fn parse(i: &str) -> IResult<&str, Self, ErrorTree<&str>> {
+        alt((Self::parse_noop, Self::parse_add_reg))(i)
+    }
+
+    /// Get the number of cycles that this instruction should be executed for.
+    pub fn cycles(&self) -> u8 {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::AddX(_) => 2,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "noop"),
+            Instruction::AddX(x) => write!(f, "addx {x}"),
+        }
+    }
+}
+
+#[test]
+fn test_sprite_covers() {
+    let covered = |x, geometry: CrtGeometry| {
+        (0..geometry.width)
+            .map(|col| {
+                if geometry.sprite_covers(x, col) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>()
+    };
+    let crt = CrtGeometry::default();
+    assert_eq!(covered(0, crt), format!("##{}", ".".repeat(38)));
+    assert_eq!(covered(1, crt), format!("###{}", ".".repeat(37)));
+    assert_eq!(covered(38, crt), format!("{}###", ".".repeat(37)));
+    assert_eq!(covered(39, crt), format!("{}##", ".".repeat(38)));
+    assert_eq!(covered(40, crt), format!("{}#", ".".repeat(39)));
+    assert_eq!(covered(-1, crt), format!("#{}", ".".repeat(39)));
+
+    let narrow = |sprite_width| CrtGeometry {
+        width: 8,
+        height: 1,
+        sprite_width,
+    };
+    assert_eq!(covered(3, narrow(1)), "...#....");
+    assert_eq!(covered(3, narrow(2)), "...##...");
+    assert_eq!(covered(3, narrow(4)), "..####..");
+    assert_eq!(covered(3, narrow(5)), ".#####..");
+}
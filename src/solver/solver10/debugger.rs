@@ -0,0 +1,261 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    glyphs,
+    parse::LineNo,
+    simulation::StepResult,
+    tui_app::{Control, TuiApp},
+};
+
+use super::cpu::{CpuFault, CrtGeometry, Instruction, Machine};
+
+/// An interactive debugger for the day 10 CPU and CRT.
+///
+/// Shows the CRT being raster-scanned one cycle at a time, alongside the CPU's
+/// registers, the instruction currently being executed, and the running signal
+/// strength total from part A.
+pub struct Debugger {
+    instructions: Vec<(LineNo, Instruction)>,
+    geometry: CrtGeometry,
+    machine: Machine,
+    running: bool,
+    signal_total: i64,
+    /// Why the machine stopped, if it couldn't carry out an instruction.
+    fault: Option<CpuFault>,
+}
+
+impl Debugger {
+    pub fn new(instructions: Vec<(LineNo, Instruction)>, geometry: CrtGeometry) -> Self {
+        Self {
+            machine: Machine::new(instructions.clone(), geometry),
+            instructions,
+            geometry,
+            running: false,
+            signal_total: 0,
+            fault: None,
+        }
+    }
+
+    /// The sum of the signal strengths sampled so far.
+    pub fn signal_total(&self) -> i64 {
+        self.signal_total
+    }
+
+    /// The machine being debugged.
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// The fault that stopped the machine, if there was one.
+    pub fn fault(&self) -> Option<CpuFault> {
+        self.fault
+    }
+
+    /// Whether the machine can't go any further, because it finished or faulted.
+    fn stopped(&self) -> bool {
+        self.machine.halted() || self.fault.is_some()
+    }
+
+    /// Execute a single cycle, if the program hasn't finished yet.
+    fn step(&mut self) {
+        if self.stopped() {
+            self.running = false;
+            return;
+        }
+
+        if self.machine.is_interesting_cycle() {
+            self.signal_total += self.machine.signal_strength();
+        }
+
+        // The pixel is drawn during the cycle, before the instruction finishes at the end of it,
+        // so it's still drawn when the instruction faults.
+        self.machine.draw();
+        if let Err(fault) = self.machine.tick() {
+            self.fault = Some(fault);
+            self.running = false;
+        }
+    }
+
+    /// Start over from the first cycle.
+    fn reset(&mut self) {
+        self.machine = Machine::new(self.instructions.clone(), self.geometry);
+        self.running = false;
+        self.signal_total = 0;
+        self.fault = None;
+    }
+
+    fn crt_lines(&self) -> Vec<Spans<'static>> {
+        let (beam_line, beam_col) = self.geometry.beam(self.machine.cpu.cycle);
+        let sprite = self.machine.cpu.x;
+
+        (0..self.geometry.height)
+            .map(|line| {
+                (0..self.geometry.width)
+                    .map(|col| {
+                        let lit = self.machine.display.is_lit(line, col);
+                        let mut style = Style::default().fg(Color::Green);
+
+                        if line == beam_line && !self.machine.halted() {
+                            if self.geometry.sprite_covers(sprite, col) {
+                                style = style.bg(Color::DarkGray);
+                            }
+                            if col == beam_col {
+                                style = style.bg(Color::Yellow);
+                            }
+                        }
+
+                        let glyph = if lit { glyphs::glyphs().full } else { ' ' };
+                        Span::styled(glyph.to_string(), style)
+                    })
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect()
+    }
+
+    fn register_lines(&self) -> Vec<Spans<'static>> {
+        let cpu = &self.machine.cpu;
+        let label = Style::default().add_modifier(Modifier::BOLD);
+
+        let current = match cpu.cur_ins {
+            Some((ins, cycles_left)) => format!("{ins} ({cycles_left} cycle(s) left)"),
+            None => "halted".to_string(),
+        };
+
+        let state = if let Some(fault) = self.fault {
+            Span::styled(
+                format!("FAULT on line {}: {fault}", fault.line),
+                Style::default().fg(Color::Red),
+            )
+        } else if self.machine.halted() {
+            Span::styled("HALTED", Style::default().fg(Color::Red))
+        } else if self.running {
+            Span::styled("RUNNING", Style::default().fg(Color::Green))
+        } else {
+            Span::styled("PAUSED", Style::default().fg(Color::Yellow))
+        };
+
+        vec![
+            Spans::from(vec![Span::styled("state:   ", label), state]),
+            Spans::from(vec![
+                Span::styled("cycle:   ", label),
+                Span::raw(cpu.cycle.to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("X:       ", label),
+                Span::raw(cpu.x.to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("PC:      ", label),
+                Span::raw(cpu.pc.to_string()),
+            ]),
+            Spans::from(vec![Span::styled("current: ", label), Span::raw(current)]),
+            Spans::from(vec![
+                Span::styled("signal:  ", label),
+                Span::raw(self.signal_total.to_string()),
+            ]),
+        ]
+    }
+}
+
+impl TuiApp for Debugger {
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(self.geometry.width as u16 + 2),
+                Constraint::Min(0),
+            ])
+            .split(outer[0]);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(self.geometry.height as u16 + 2),
+                Constraint::Min(0),
+            ])
+            .split(columns[0]);
+
+        let crt = Paragraph::new(self.crt_lines())
+            .block(Block::default().title("CRT").borders(Borders::ALL));
+        f.render_widget(crt, left[0]);
+
+        let registers = Paragraph::new(self.register_lines())
+            .block(Block::default().title("CPU").borders(Borders::ALL));
+        f.render_widget(registers, left[1]);
+
+        self.render_program(f, columns[1]);
+
+        let help = Paragraph::new(Spans::from(
+            "[space] run/pause  [s/→] step  [r] reset  [q] quit",
+        ));
+        f.render_widget(help, outer[1]);
+    }
+
+    fn on_tick(&mut self) {
+        if self.running {
+            self.step();
+        }
+    }
+
+    fn status(&self) -> StepResult {
+        if self.stopped() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if key.kind == KeyEventKind::Release {
+            return Control::Continue;
+        }
+
+        match key.code {
+            KeyCode::Char(' ') => self.running = !self.running && !self.stopped(),
+            KeyCode::Char('s') | KeyCode::Right => {
+                self.running = false;
+                self.step();
+            }
+            KeyCode::Char('r') => self.reset(),
+            _ => {}
+        }
+
+        Control::Continue
+    }
+}
+
+impl Debugger {
+    fn render_program<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let items = self
+            .instructions
+            .iter()
+            .map(|(line, ins)| ListItem::new(format!("{line:>4}  {ins}")))
+            .collect::<Vec<_>>();
+
+        // `pc` always points one past the instruction that's currently executing.
+        let mut state = ListState::default();
+        if !self.machine.halted() {
+            state.select(Some(self.machine.cpu.pc - 1));
+        }
+
+        let list = List::new(items)
+            .block(Block::default().title("Program").borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
@@ -1,87 +1,110 @@
 mod parse;
+#[cfg(feature = "tui")]
+mod viewer;
 
 use std::io::BufRead;
 
-use color_eyre::eyre::Context;
-use miette::GraphicalReportHandler;
-use nom_supreme::{
-    error::{BaseErrorKind, ErrorTree, GenericErrorTree},
-    final_parser::final_parser,
+#[cfg(feature = "tui")]
+use crate::tui_app;
+use crate::{
+    error::{Context, SolverError},
+    math::{MathError, MathMode},
+    simulation::Pacing,
 };
 
-use self::parse::{parse_all_monkeys, Monkey, Span};
+use self::parse::Monkey;
+#[cfg(feature = "tui")]
+use self::viewer::Viewer;
 
-use super::ChallengeSolver;
+use super::{
+    ChallengeSolver, Frontend, ParamError, ParseStats, SolverConfig, Transform, TransformableInput,
+};
 
 #[derive(Debug, Default)]
-pub struct Solver11;
+pub struct Solver11 {
+    /// How many rounds to simulate, instead of the 20 from part A or the 10,000 from part B.
+    rounds: Option<usize>,
+    visualize: bool,
+    /// How many times a second the viewer plays out its rounds.
+    pacing: Pacing,
+    math: MathMode,
+    /// Changes to make to the monkeys before simulating them, from `--transform`.
+    transforms: Vec<Transform>,
+}
 
 impl ChallengeSolver for Solver11 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         11
     }
 
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.rounds = config.params.get("rounds")?;
+        self.visualize = config.visualize;
+        self.pacing = Pacing::from_params(&config.params)?;
+        self.math = config.math;
+        self.transforms = config.transforms.clone();
+        Ok(())
+    }
+
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
+    }
+
+    fn transformable(&self) -> bool {
+        true
+    }
+
+    fn validate(&self, input: &str) -> Option<Result<ParseStats, SolverError>> {
+        Some(
+            parse::parse_input(input)
+                .map_err(Into::into)
+                .map(|monkeys| {
+                    let items = monkeys.iter().map(|m| m.items.len()).sum();
+                    ParseStats::default()
+                        .entity("monkeys", monkeys.len())
+                        .entity("items", items)
+                }),
+        )
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         // Parse the monkeys
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
-
-        let input = Span::new(&input_buf);
-
-        let monkeys_res: Result<_, ErrorTree<Span>> =
-            final_parser(parse_all_monkeys::<ErrorTree<Span>>)(input);
-
-        let monkeys = match monkeys_res {
-            Ok(monkeys) => monkeys,
-            Err(e) => {
-                match e {
-                    GenericErrorTree::Base { location, kind } => {
-                        let offset = location.location_offset().into();
-                        let err = BadInputError {
-                            src: &input_buf,
-                            bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                            kind,
-                        };
-                        let mut s = String::new();
-                        GraphicalReportHandler::new()
-                            .render_report(&mut s, &err)
-                            .unwrap();
-                        eprintln!("{s}");
-                    }
-
-                    GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-                    GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-                }
-                return Err(color_eyre::eyre::eyre!("Failed to parse input"));
-            }
-        };
+            .context("Could not read input file to string")?;
+
+        let mut monkeys = parse::parse_input(&input_buf)
+            .context("Could not parse input file as a list of monkeys")?;
+        monkeys.apply_all(&self.transforms)?;
 
         // Simulate the monkeys
-        let mut monkeys = monkeys;
-        for i in 0..20 {
+        let rounds = self.rounds.unwrap_or(20);
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            let monkeys = self.visualize(monkeys, rounds, true, None)?;
+            let monkey_business = monkey_business(&monkeys);
+            println!("\nMonkey business: {monkey_business}");
+            return Ok(super::Answer::new(monkey_business));
+        }
+
+        for i in 0..rounds {
             println!("\n============");
             println!("| ROUND {i:<2} |");
             println!("============");
 
-            do_round(&mut monkeys, true, None);
+            do_round(&mut monkeys, true, None, self.math, |_| {})?;
             for monkey in &monkeys {
                 println!("{monkey:?}");
             }
         }
 
-        // Calculate the resultant monkey business
-        let mut all_inspect_counts = monkeys
-            .iter()
-            .map(|m| m.items_inspected)
-            .collect::<Vec<_>>();
-        all_inspect_counts.sort_unstable_by_key(|&c| std::cmp::Reverse(c));
-
-        let monkey_business = all_inspect_counts.into_iter().take(2).product::<u128>();
+        let monkey_business = monkey_business(&monkeys);
         println!("\nMonkey business: {monkey_business}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(monkey_business))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
@@ -89,106 +112,153 @@ impl ChallengeSolver for Solver11 {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
-
-        let input = Span::new(&input_buf);
-
-        let monkeys_res: Result<_, ErrorTree<Span>> =
-            final_parser(parse_all_monkeys::<ErrorTree<Span>>)(input);
-
-        let monkeys = match monkeys_res {
-            Ok(monkeys) => monkeys,
-            Err(e) => {
-                match e {
-                    GenericErrorTree::Base { location, kind } => {
-                        let offset = location.location_offset().into();
-                        let err = BadInputError {
-                            src: &input_buf,
-                            bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                            kind,
-                        };
-                        let mut s = String::new();
-                        GraphicalReportHandler::new()
-                            .render_report(&mut s, &err)
-                            .unwrap();
-                        eprintln!("{s}");
-                    }
-
-                    GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-                    GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-                }
-                return Err(color_eyre::eyre::eyre!("Failed to parse input"));
-            }
-        };
+            .context("Could not read input file to string")?;
+
+        let mut monkeys = parse::parse_input(&input_buf)
+            .context("Could not parse input file as a list of monkeys")?;
+        monkeys.apply_all(&self.transforms)?;
 
         // Simulate the monkeys
         let divisor_product = monkeys.iter().map(|m| m.divisor).product::<u128>();
         dbg!(divisor_product);
 
-        let mut monkeys = monkeys;
-        for i in 0..10_000 {
+        let rounds = self.rounds.unwrap_or(10_000);
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            let monkeys = self.visualize(monkeys, rounds, false, Some(divisor_product))?;
+            let monkey_business = monkey_business(&monkeys);
+            println!("\nMonkey business: {monkey_business}");
+            return Ok(super::Answer::new(monkey_business));
+        }
+
+        for i in 0..rounds {
             if i % 100 == 0 {
                 println!("Round {i}");
             }
 
-            do_round(&mut monkeys, false, Some(divisor_product));
+            do_round(
+                &mut monkeys,
+                false,
+                Some(divisor_product),
+                self.math,
+                |_| {},
+            )?;
         }
 
-        // Calculate the resultant monkey business
-        let mut all_inspect_counts = monkeys
-            .iter()
-            .map(|m| m.items_inspected)
-            .collect::<Vec<_>>();
-        all_inspect_counts.sort_unstable_by_key(|&c| std::cmp::Reverse(c));
-
-        let monkey_business = all_inspect_counts.into_iter().take(2).product::<u128>();
+        let monkey_business = monkey_business(&monkeys);
         println!("\nMonkey business: {monkey_business}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(monkey_business))
+    }
+}
+
+#[cfg(feature = "tui")]
+impl Solver11 {
+    /// Simulate `rounds` rounds in the terminal UI, and return the monkeys as they are afterwards.
+    fn visualize(
+        &self,
+        monkeys: Vec<Monkey>,
+        rounds: usize,
+        div_by_three: bool,
+        divisor_product: Option<u128>,
+    ) -> Result<Vec<Monkey>, SolverError> {
+        let mut viewer = Viewer::new(monkeys, rounds, div_by_three, divisor_product, self.math);
+        tui_app::run(&mut viewer, 30.0, self.pacing)?;
+        Ok(viewer.into_monkeys()?)
+    }
+}
+
+/// Monkeys can be dropped with `--transform 'drop monkey ID'`, to see how the others do without
+/// them. Their items are dropped along with them.
+impl TransformableInput for Vec<Monkey> {
+    fn apply(&mut self, transform: &Transform) -> Result<(), String> {
+        let Transform::Drop { kind, id } = transform else {
+            return Err("monkeys can only be dropped, like `drop monkey 2`".to_string());
+        };
+        if kind != "monkey" {
+            return Err(format!("expected `monkey`, not `{kind}`"));
+        }
+
+        let monkey = self
+            .iter_mut()
+            .find(|monkey| monkey.id.to_string() == *id && !monkey.removed)
+            .ok_or_else(|| format!("there's no monkey {id}"))?;
+        monkey.removed = true;
+        monkey.items.clear();
+        Ok(())
     }
 }
 
-fn do_round(monkeys: &mut [Monkey], div_by_three: bool, divisor_product: Option<u128>) {
+/// An item being thrown from one monkey to another, with its worry level once it's caught.
+#[derive(Debug, Clone, Copy)]
+struct Throw {
+    from: usize,
+    to: usize,
+    item: u128,
+}
+
+/// The number of items inspected by the two busiest monkeys, multiplied together.
+fn monkey_business(monkeys: &[Monkey]) -> u128 {
+    let mut all_inspect_counts = monkeys
+        .iter()
+        .map(|m| m.items_inspected)
+        .collect::<Vec<_>>();
+    all_inspect_counts.sort_unstable_by_key(|&c| std::cmp::Reverse(c));
+
+    all_inspect_counts.into_iter().take(2).product()
+}
+
+/// Have every monkey take its turn once, calling `on_throw` for every item thrown.
+fn do_round(
+    monkeys: &mut [Monkey],
+    div_by_three: bool,
+    divisor_product: Option<u128>,
+    math: MathMode,
+    mut on_throw: impl FnMut(Throw),
+) -> Result<(), MathError> {
     let num_monkeys = monkeys.len();
 
     for i in 0..num_monkeys {
+        if monkeys[i].removed {
+            continue;
+        }
         let old_monkey;
 
         {
             let monkey = &mut monkeys[i];
             old_monkey = monkey.clone();
-            monkey.items_inspected += old_monkey.items.len() as u128;
+            monkey.items_inspected =
+                math.add(monkey.items_inspected, old_monkey.items.len() as u128)?;
         }
 
         for mut item in old_monkey.items.iter().copied() {
             if let Some(divisor_product) = divisor_product {
-                item %= divisor_product;
+                item = math.rem(item, divisor_product)?;
             }
 
-            item = old_monkey.operation.eval(item);
+            item = old_monkey.operation.eval(item, math)?;
 
             if div_by_three {
-                item /= 3;
+                item = math.div(item, 3)?;
             }
 
-            if item % old_monkey.divisor == 0 {
-                monkeys[old_monkey.receiver_if_true].items.push(item);
+            let to = if math.rem(item, old_monkey.divisor)? == 0 {
+                old_monkey.receiver_if_true
             } else {
-                monkeys[old_monkey.receiver_if_false].items.push(item);
-            }
+                old_monkey.receiver_if_false
+            };
+            monkeys[to].items.push(item);
+            on_throw(Throw { from: i, to, item });
         }
         monkeys[i].items.clear();
     }
+    Ok(())
 }
 
-#[derive(thiserror::Error, Debug, miette::Diagnostic)]
-#[error("Error parsing input")]
-struct BadInputError<'a> {
-    #[source_code]
-    src: &'a str,
-
-    #[label("{kind}")]
-    bad_bit: miette::SourceSpan,
-
-    kind: BaseErrorKind<&'static str, Box<dyn std::error::Error + Send + Sync>>,
+super::challenge_solver_test_boilerplate! {
+    Solver11::default();
+    11 => {
+        a as u128,
+        b as u128,
+    }
 }
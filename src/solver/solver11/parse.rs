@@ -8,9 +8,19 @@ use nom::{
     sequence::{preceded, terminated, tuple},
     IResult,
 };
-use nom_locate::LocatedSpan;
+use nom_supreme::error::ErrorTree;
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+use crate::{
+    math::{MathError, MathMode},
+    parse::{BadInputError, Span},
+};
+
+/// Parse the challenge input into a vector of [`Monkey`]s.
+///
+/// Any parsing errors will be printed out to `stderr` with fancy formatting.
+pub fn parse_input(input: &str) -> Result<Vec<Monkey>, BadInputError> {
+    crate::parse::parse_input(input, parse_all_monkeys::<ErrorTree<Span>>)
+}
 
 pub fn parse_all_monkeys<'a, E: ParseError<Span<'a>>>(
     i: Span<'a>,
@@ -30,6 +40,9 @@ pub struct Monkey {
     pub divisor: u128,
     pub receiver_if_true: usize,
     pub receiver_if_false: usize,
+    /// Taken out with `--transform 'drop monkey ID'`. It never takes a turn, so anything thrown
+    /// to it is lost.
+    pub removed: bool,
 }
 
 impl Monkey {
@@ -79,6 +92,7 @@ impl Monkey {
                 divisor,
                 receiver_if_true,
                 receiver_if_false,
+                removed: false,
             },
         ))
     }
@@ -92,10 +106,10 @@ pub enum Operation {
 
 impl Operation {
     /// Evaluate an operation given an old worry value.
-    pub fn eval(self, old: u128) -> u128 {
+    pub fn eval(self, old: u128, math: MathMode) -> Result<u128, MathError> {
         match self {
-            Operation::Add(l, r) => l.eval(old) + r.eval(old),
-            Operation::Mul(l, r) => l.eval(old) * r.eval(old),
+            Operation::Add(l, r) => math.add(l.eval(old), r.eval(old)),
+            Operation::Mul(l, r) => math.mul(l.eval(old), r.eval(old)),
         }
     }
 
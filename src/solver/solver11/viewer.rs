@@ -0,0 +1,286 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{BarChart, Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    math::{MathError, MathMode},
+    simulation::StepResult,
+    tui_app::{Control, TuiApp},
+};
+
+use super::{do_round, monkey_business, parse::Monkey, Throw};
+
+/// The most rounds that can be simulated between two frames.
+const MAX_ROUNDS_PER_TICK: usize = 1024;
+
+/// Animates the monkeys playing keep away.
+///
+/// Shows what each monkey is holding between rounds, the items that flew between them in the last
+/// round, and how many items each of them has inspected so far. Part B's thousands of rounds can
+/// be fast-forwarded by simulating more of them per tick.
+pub(super) struct Viewer {
+    monkeys: Vec<Monkey>,
+    div_by_three: bool,
+    divisor_product: Option<u128>,
+    math: MathMode,
+    round: usize,
+    rounds: usize,
+    rounds_per_tick: usize,
+    /// Every item thrown in the last round simulated, in the order they were thrown.
+    throws: Vec<Throw>,
+    running: bool,
+    /// What went wrong in the round that couldn't be finished, which stops the animation.
+    error: Option<MathError>,
+}
+
+impl Viewer {
+    pub(super) fn new(
+        monkeys: Vec<Monkey>,
+        rounds: usize,
+        div_by_three: bool,
+        divisor_product: Option<u128>,
+        math: MathMode,
+    ) -> Self {
+        Self {
+            monkeys,
+            div_by_three,
+            divisor_product,
+            math,
+            round: 0,
+            rounds,
+            rounds_per_tick: 1,
+            throws: Vec::new(),
+            running: false,
+            error: None,
+        }
+    }
+
+    /// The monkeys after every round has been simulated, no matter how far the animation got.
+    pub(super) fn into_monkeys(mut self) -> Result<Vec<Monkey>, MathError> {
+        self.step(self.rounds);
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.monkeys),
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.round == self.rounds || self.error.is_some()
+    }
+
+    /// Simulate up to `rounds` more rounds, only keeping track of the throws in the last one.
+    fn step(&mut self, rounds: usize) {
+        for _ in 0..rounds.min(self.rounds - self.round) {
+            if self.error.is_some() {
+                break;
+            }
+
+            self.throws.clear();
+            let throws = &mut self.throws;
+            let result = do_round(
+                &mut self.monkeys,
+                self.div_by_three,
+                self.divisor_product,
+                self.math,
+                |throw| throws.push(throw),
+            );
+            match result {
+                Ok(()) => self.round += 1,
+                Err(e) => self.error = Some(e),
+            }
+        }
+        if self.finished() {
+            self.running = false;
+        }
+    }
+
+    fn monkey_lines(&self) -> Vec<Spans<'static>> {
+        let label = Style::default().add_modifier(Modifier::BOLD);
+        self.monkeys
+            .iter()
+            .enumerate()
+            .map(|(i, monkey)| {
+                let items = monkey.items.iter().map(u128::to_string).collect::<Vec<_>>();
+                Spans::from(vec![
+                    Span::styled(format!("Monkey {i}: "), label),
+                    Span::raw(items.join(", ")),
+                ])
+            })
+            .collect()
+    }
+
+    fn throw_lines(&self) -> Vec<Spans<'static>> {
+        let arrow = Style::default().fg(Color::Yellow);
+        let caught = Style::default().fg(Color::Green);
+        self.throws
+            .iter()
+            .map(|throw| {
+                Spans::from(vec![
+                    Span::raw(format!("{:>2} ", throw.from)),
+                    Span::styled("──▶", arrow),
+                    Span::raw(format!(" {:<2} ", throw.to)),
+                    Span::styled(throw.item.to_string(), caught),
+                ])
+            })
+            .collect()
+    }
+
+    fn status_lines(&self) -> Vec<Spans<'static>> {
+        let label = Style::default().add_modifier(Modifier::BOLD);
+
+        let state = if self.error.is_some() {
+            Span::styled("OVERFLOWED", Style::default().fg(Color::Red))
+        } else if self.finished() {
+            Span::styled("DONE", Style::default().fg(Color::Red))
+        } else if self.running {
+            Span::styled("RUNNING", Style::default().fg(Color::Green))
+        } else {
+            Span::styled("PAUSED", Style::default().fg(Color::Yellow))
+        };
+
+        let mut lines = vec![
+            Spans::from(vec![Span::styled("state:    ", label), state]),
+            Spans::from(vec![
+                Span::styled("round:    ", label),
+                Span::raw(format!("{}/{}", self.round, self.rounds)),
+            ]),
+            Spans::from(vec![
+                Span::styled("per tick: ", label),
+                Span::raw(self.rounds_per_tick.to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("business: ", label),
+                Span::raw(monkey_business(&self.monkeys).to_string()),
+            ]),
+        ];
+        if let Some(e) = &self.error {
+            lines.push(Spans::from(Span::styled(
+                e.to_string(),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        lines
+    }
+
+    fn render_inspections<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let labels = (0..self.monkeys.len())
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        let data = labels
+            .iter()
+            .zip(&self.monkeys)
+            .map(|(label, monkey)| {
+                let inspected = u64::try_from(monkey.items_inspected).unwrap_or(u64::MAX);
+                (label.as_str(), inspected)
+            })
+            .collect::<Vec<_>>();
+
+        // Spread the bars out over the whole width.
+        let count = self.monkeys.len().max(1) as u16;
+        let bar_width = (area.width.saturating_sub(2) / count)
+            .saturating_sub(1)
+            .max(1);
+
+        let chart = BarChart::default()
+            .block(
+                Block::default()
+                    .title("Items inspected")
+                    .borders(Borders::ALL),
+            )
+            .data(&data)
+            .bar_width(bar_width)
+            .bar_style(Style::default().fg(Color::Cyan))
+            .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+        f.render_widget(chart, area);
+    }
+}
+
+impl TuiApp for Viewer {
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(50),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(f.size());
+
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(24),
+                Constraint::Length(26),
+            ])
+            .split(outer[0]);
+
+        let monkeys = Paragraph::new(self.monkey_lines())
+            .block(Block::default().title("Holding").borders(Borders::ALL));
+        f.render_widget(monkeys, top[0]);
+
+        // Keep the latest throws in view when there are too many to fit.
+        let throws = self.throw_lines();
+        let visible = top[1].height.saturating_sub(2) as usize;
+        let scroll = throws.len().saturating_sub(visible) as u16;
+        let throws = Paragraph::new(throws)
+            .block(Block::default().title("Last round").borders(Borders::ALL))
+            .scroll((scroll, 0));
+        f.render_widget(throws, top[1]);
+
+        // Wrapped, so that an overflow's location fits in the narrow column.
+        let status = Paragraph::new(self.status_lines())
+            .block(Block::default().title("Keep away").borders(Borders::ALL))
+            .wrap(Wrap { trim: true });
+        f.render_widget(status, top[2]);
+
+        self.render_inspections(f, outer[1]);
+
+        let help = Paragraph::new(Spans::from(
+            "[space] run/pause  [s/→] step  [+-] rounds per tick  [f] finish  [q] quit",
+        ));
+        f.render_widget(help, outer[2]);
+    }
+
+    fn on_tick(&mut self) {
+        if self.running {
+            self.step(self.rounds_per_tick);
+        }
+    }
+
+    fn status(&self) -> StepResult {
+        if self.finished() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if key.kind == KeyEventKind::Release {
+            return Control::Continue;
+        }
+
+        match key.code {
+            KeyCode::Char(' ') => self.running = !self.running && !self.finished(),
+            KeyCode::Char('s') | KeyCode::Right => {
+                self.running = false;
+                self.step(1);
+            }
+            KeyCode::Char('+' | '=') => {
+                self.rounds_per_tick = (self.rounds_per_tick * 2).min(MAX_ROUNDS_PER_TICK);
+            }
+            KeyCode::Char('-') => self.rounds_per_tick = (self.rounds_per_tick / 2).max(1),
+            KeyCode::Char('f') => self.step(self.rounds),
+            _ => {}
+        }
+
+        Control::Continue
+    }
+}
@@ -1,502 +1,471 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fmt,
-    io::{self, BufRead},
-    time::{Duration, Instant},
+use std::{fmt, fs, io::BufRead, path::PathBuf, rc::Rc, str::FromStr};
+
+use owo_colors::Rgb;
+use serde_json::json;
+
+use crate::{
+    error::{Context, SolverError},
+    geometry::{chebyshev, manhattan, Point},
+    grid::{Grid, GridCoord},
+    pathfinding::{self, TimedState},
+    raster::{self, Raster},
+    simulation::Pacing,
 };
 
-use color_eyre::eyre::Context;
-use crossterm::{
-    event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
-    },
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{
-        canvas::{self, Canvas},
-        Block, Borders, Paragraph,
-    },
-    Frame, Terminal,
-};
+use super::{Answer, ChallengeSolver, Frontend, ParamError, Rendering, SolverConfig, SolverParams};
 
-use crate::grid::{Grid, GridCoord};
-
-use super::ChallengeSolver;
+#[cfg(feature = "tui")]
+mod viewer;
 
 #[derive(Debug, Default)]
-pub struct Solver12;
+pub struct Solver12 {
+    /// Watch the search spread across the heightmap in a TUI, before printing the answer.
+    visualize: bool,
+    /// How fast the search spreads in the TUI.
+    pacing: Pacing,
+    rules: ClimbRules,
+    algorithm: Algorithm,
+    /// What each step costs, for [`Algorithm::Dijkstra`].
+    costs: TerrainCosts,
+    /// Print the elevation along the shortest path as a sparkline, from `--param profile=true`.
+    profile: bool,
+    /// Where to save the elevation along the shortest path as CSV, if anywhere, from
+    /// `--param profile_csv=PATH`.
+    profile_csv: Option<PathBuf>,
+}
 
 impl ChallengeSolver for Solver12 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         12
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        // parse grid
-        let mut input_buf = String::new();
-        input
-            .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
-        let grid = Grid::parse(&input_buf);
+    super::sample_methods!();
 
-        // Initialize app
-        let app = App::new(grid, InitialSet::StartingCell);
-
-        // setup terminal
-        enable_raw_mode().wrap_err("Could not initialize terminal UI")?;
-        let mut stdout = io::stdout();
-        crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
-            .wrap_err("Could not initialize terminal UI")?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).wrap_err("Could not initialize terminal UI")?;
-
-        // Run the app
-        let tick_rate = Duration::from_secs_f64(1.0 / 60.0);
-        let res = app.run(&mut terminal, tick_rate);
-
-        // Restore terminal
-        disable_raw_mode().wrap_err("Could not deinitialize terminal UI")?;
-        crossterm::execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )
-        .wrap_err("Could not deinitialize terminal UI")?;
-        terminal
-            .show_cursor()
-            .wrap_err("Could not deinitialize terminal UI")?;
+    fn algorithms(&self) -> &'static [&'static str] {
+        &["astar", "dijkstra"]
+    }
 
-        // Remember to unwrap the result of running the app AFTER restoring the terminal
-        res?;
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        self.pacing = Pacing::from_params(&config.params)?;
+        self.rules = ClimbRules::from_params(&config.params)?;
+        self.algorithm = config.params.get_or("algorithm", Algorithm::default())?;
+        self.costs = TerrainCosts::from_params(&config.params)?;
+        self.profile = config.params.get_or("profile", false)?;
+        self.profile_csv = config.params.get("profile_csv")?;
+        Ok(())
+    }
 
-        Ok(Box::new(()))
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
+    }
+
+    fn gallery(&self, input: &str) -> Option<Result<Vec<Rendering>, SolverError>> {
+        let grid = Grid::parse(input);
+        let draw = |name, initial_set| {
+            let (path, _) = self.climb(&grid, &initial_set)?;
+            Ok(Rendering::png(
+                name,
+                path_raster(&grid, &path).scaled(GALLERY_SCALE),
+            ))
+        };
+        Some(
+            [
+                ("path-a", InitialSet::StartingCell),
+                ("path-b", InitialSet::LowestElevationCell),
+            ]
+            .into_iter()
+            .map(|(name, initial_set)| draw(name, initial_set))
+            .collect(),
+        )
+    }
+
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        self.hike(input, InitialSet::StartingCell)
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        self.hike(input, InitialSet::LowestElevationCell)
+    }
+}
+
+impl Solver12 {
+    /// Find the fewest steps, or the least effort with [`Algorithm::Dijkstra`], from any of the
+    /// `initial_set` to the end.
+    fn hike(
+        &self,
+        input: &mut dyn BufRead,
+        initial_set: InitialSet,
+    ) -> super::ChallengeSolverResult {
         // parse grid
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+            .context("Could not read input file to string")?;
         let grid = Grid::parse(&input_buf);
 
-        // Initialize app
-        let app = App::new(grid, InitialSet::LowestElevationCell);
-
-        // setup terminal
-        enable_raw_mode().wrap_err("Could not initialize terminal UI")?;
-        let mut stdout = io::stdout();
-        crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
-            .wrap_err("Could not initialize terminal UI")?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).wrap_err("Could not initialize terminal UI")?;
-
-        // Run the app
-        let tick_rate = Duration::from_secs_f64(1.0 / 60.0);
-        let res = app.run(&mut terminal, tick_rate);
-
-        // Restore terminal
-        disable_raw_mode().wrap_err("Could not deinitialize terminal UI")?;
-        crossterm::execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )
-        .wrap_err("Could not deinitialize terminal UI")?;
-        terminal
-            .show_cursor()
-            .wrap_err("Could not deinitialize terminal UI")?;
+        let (path, cost) = self.climb(&grid, &initial_set)?;
+        let steps = path.len() as u64 - 1;
+        match self.algorithm {
+            Algorithm::AStar => println!("Fewest steps to the end: {steps}"),
+            Algorithm::Dijkstra => println!("Least effort to the end: {cost} ({steps} steps)"),
+        }
+
+        let elevations = path
+            .iter()
+            .map(|&coord| grid.cell(coord).unwrap().elevation())
+            .collect::<Vec<_>>();
+        if self.profile {
+            println!("Elevation along the way: {}", sparkline(&elevations));
+        }
+        if let Some(csv_path) = &self.profile_csv {
+            fs::write(csv_path, profile_csv(&elevations)).with_context(|| {
+                format!("Could not save elevation profile to {}", csv_path.display())
+            })?;
+            println!("Saved elevation profile to {}", csv_path.display());
+        }
+
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            viewer::watch(grid, initial_set, self.rules, self.pacing)?;
+        }
+
+        let path = path
+            .iter()
+            .map(|coord| [coord.x, coord.y])
+            .collect::<Vec<_>>();
+        let mut answer = Answer::new(cost)
+            .with_metadata("path", json!(path))
+            .with_metadata("elevations", json!(elevations));
+        if self.algorithm == Algorithm::Dijkstra {
+            answer = answer.with_metadata("steps", steps);
+        }
+        Ok(answer)
+    }
 
-        // Remember to unwrap the result of running the app AFTER restoring the terminal
-        res?;
+    /// Find the squares along one of the best paths from any of the `initial_set` to the end,
+    /// including both ends, along with what it costs. That's the number of steps, unless it was
+    /// found with [`Algorithm::Dijkstra`].
+    fn climb(
+        &self,
+        grid: &Grid<Cell>,
+        initial_set: &InitialSet,
+    ) -> Result<(Vec<GridCoord>, u64), SolverError> {
+        let end = find_cells(grid, |cell| matches!(cell, Cell::End))
+            .next()
+            .ok_or_else(|| SolverError::invalid_input("The heightmap has no end square `E`"))?;
+        let starts = initial_set.starts(grid);
+        if starts.is_empty() {
+            return Err(SolverError::invalid_input(
+                "The heightmap has nowhere to start from",
+            ));
+        }
 
-        Ok(Box::new(()))
+        let unreachable =
+            || SolverError::invalid_input("The end can't be reached with these climbing rules");
+        match self.algorithm {
+            Algorithm::AStar => {
+                let hikers = starts.into_iter().map(|position| Hiker {
+                    grid,
+                    rules: self.rules,
+                    position,
+                    steps: 0,
+                    trail: None,
+                });
+                let hiker =
+                    pathfinding::find_earliest(hikers, to_point(end), 1, self.rules.distance())
+                        .ok_or_else(unreachable)?;
+                Ok((hiker.path(), hiker.steps))
+            }
+            Algorithm::Dijkstra => {
+                let elevation = |coord| grid.cell(coord).unwrap().elevation();
+                pathfinding::dijkstra(
+                    starts,
+                    |&from| {
+                        grid.walkable_neighbors(from, self.rules)
+                            .map(move |to| (to, self.costs.step(elevation(from), elevation(to))))
+                    },
+                    |&coord| coord == end,
+                )
+                .ok_or_else(unreachable)
+            }
+        }
     }
 }
 
-enum InitialSet {
-    StartingCell,
-    LowestElevationCell,
+/// How many pixels across each square is in the gallery's pictures.
+const GALLERY_SCALE: usize = 16;
+
+/// The color of the squares along the path, in the gallery's pictures.
+const PATH_COLOR: Rgb = Rgb(120, 230, 120);
+
+/// Draw the heightmap from the lowest squares in dark blue to the highest in pale yellow, with
+/// the squares along `path` in green.
+fn path_raster(grid: &Grid<Cell>, path: &[GridCoord]) -> Raster {
+    let mut raster = Raster::new(grid.width(), grid.height(), Rgb(0, 0, 0));
+    for coord in find_cells(grid, |_| true) {
+        let elevation = grid.cell(coord).unwrap().elevation();
+        raster.set(
+            coord.x,
+            coord.y,
+            raster::heat_color(f64::from(elevation) / 25.0),
+        );
+    }
+    for coord in path {
+        raster.set(coord.x, coord.y, PATH_COLOR);
+    }
+    raster
 }
 
-struct App {
-    grid: Grid<Cell>,
-    visited: HashMap<GridCoord, CellRecord>,
-    current: HashSet<GridCoord>,
-    num_steps: usize,
-    end_found: bool,
+/// The eight heights of a sparkline, from lowest to highest.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
-    initial_set: InitialSet,
+/// Draw `elevations` as a one-line bar chart, one character per square, scaled so that `a` is
+/// the lowest bar and `z` the highest.
+fn sparkline(elevations: &[u8]) -> String {
+    elevations
+        .iter()
+        .map(|&elevation| SPARKS[usize::from(elevation.min(25)) * (SPARKS.len() - 1) / 25])
+        .collect()
+}
 
-    show_glyphs: bool,
-    show_walkable_neighbors: bool,
+/// Each square's elevation, by how many steps into the path it is.
+fn profile_csv(elevations: &[u8]) -> String {
+    let mut csv = String::from("distance,elevation\n");
+    for (distance, elevation) in elevations.iter().enumerate() {
+        csv.push_str(&format!("{distance},{elevation}\n"));
+    }
+    csv
 }
 
-impl App {
-    fn new(grid: Grid<Cell>, initial_set: InitialSet) -> Self {
-        Self {
-            grid,
-            visited: Default::default(),
-            current: Default::default(),
-            num_steps: 0,
-            end_found: false,
+/// How to search for the way to the end.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    /// Find the fewest steps with A*, like the puzzle asks for.
+    #[default]
+    AStar,
+    /// Find the least effort with Dijkstra's algorithm, weighing each step by its
+    /// [`TerrainCosts`].
+    Dijkstra,
+}
 
-            initial_set,
+impl FromStr for Algorithm {
+    type Err = String;
 
-            show_glyphs: false,
-            show_walkable_neighbors: false,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "astar" => Ok(Self::AStar),
+            "dijkstra" => Ok(Self::Dijkstra),
+            _ => Err("expected `astar` or `dijkstra`".to_string()),
         }
     }
+}
 
-    /// Run the app.
-    fn run<B: Backend>(
-        mut self,
-        terminal: &mut Terminal<B>,
-        tick_rate: Duration,
-    ) -> color_eyre::Result<()> {
-        let mut last_tick = Instant::now();
-        loop {
-            terminal
-                .draw(|f| self.ui(f))
-                .wrap_err("Error while drawing UI frame.")?;
-
-            let timeout = tick_rate
-                .checked_sub(last_tick.elapsed())
-                .unwrap_or_else(|| Duration::from_secs(0));
-
-            if event::poll(timeout).wrap_err("Could not poll terminal for new I/O events")? {
-                match event::read().wrap_err("Could not read terminal I/O event")? {
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('q'),
-                        ..
-                    }) => return Ok(()),
-
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('g'),
-                        kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                        ..
-                    }) => {
-                        self.show_glyphs = !self.show_glyphs;
-                    }
-
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('n'),
-                        kind: KeyEventKind::Press | KeyEventKind::Release,
-                        ..
-                    }) => {
-                        self.show_walkable_neighbors = !self.show_walkable_neighbors;
-                    }
-
-                    _ => (),
-                }
-            }
+/// How much effort each step takes, for [`Algorithm::Dijkstra`].
+///
+/// By default every step costs 1 whichever way it goes, so the least effort is the fewest steps.
+/// Making climbing cost more finds the route with the least climbing instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TerrainCosts {
+    /// What every step costs, from `--param step_cost=N`.
+    step: u64,
+    /// What each level of elevation climbed costs on top, from `--param ascent_cost=N`.
+    ascent: u64,
+    /// What each level of elevation descended costs on top, from `--param descent_cost=N`.
+    descent: u64,
+}
 
-            if last_tick.elapsed() >= tick_rate {
-                self.on_tick();
-                last_tick = Instant::now();
-            }
+impl Default for TerrainCosts {
+    fn default() -> Self {
+        Self {
+            step: 1,
+            ascent: 0,
+            descent: 0,
         }
     }
+}
 
-    /// Render the app UI to a tui frame
-    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
-        // Split screen up into main areas
-        let chunks = Layout::default()
-            .direction(tui::layout::Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(3)])
-            .split(f.size());
-        let main_chunk = &chunks[0];
-        let info_chunk = &chunks[1];
-
-        // Render the main simulation
-        let main_block = Block::default().borders(Borders::NONE);
-        let main_canvas = Canvas::default()
-            .block(main_block)
-            .x_bounds([0.0, self.grid.width() as f64])
-            .y_bounds([0.0, self.grid.height() as f64])
-            .background_color(Color::Rgb(0, 0, 0))
-            .paint(|ctx| {
-                // Paint the grid
-                let grid_height = self.grid.height();
-                let grid_width = self.grid.width();
-                for y in 0..grid_height {
-                    for x in 0..grid_width {
-                        let cell = self.grid.cell((x, grid_height - 1 - y).into()).unwrap();
-
-                        let (glyph, color) = match cell {
-                            Cell::Start => ("S".to_string(), Color::Rgb(216, 27, 96)),
-                            Cell::End => ("E".to_string(), Color::Rgb(30, 136, 229)),
-                            Cell::Square(elevation) => {
-                                let glyph = format!("{elevation}");
-                                let elevation = *elevation as f32 / 25.0;
-                                let f = (elevation * 255.0) as u8;
-                                (glyph, Color::Rgb(f, f, f))
-                            }
-                        };
-                        let Color::Rgb(r, g, b) = color else { unreachable!(); };
-
-                        let fill_points = (0..=20)
-                            .flat_map(|fill_x| {
-                                let fill_x = fill_x as f64 / 20.0 + x as f64;
-                                (0..=20).map(move |fill_y| {
-                                    let fill_y = fill_y as f64 / 20.0 + y as f64;
-                                    (fill_x, fill_y)
-                                })
-                            })
-                            .collect::<Vec<_>>();
-
-                        ctx.draw(&canvas::Points {
-                            coords: &fill_points,
-                            color,
-                        });
-
-                        if self.show_glyphs {
-                            ctx.print(
-                                x as f64 + 0.5,
-                                y as f64 + 0.5,
-                                Spans(vec![Span::styled(
-                                    glyph,
-                                    Style::default().bg(color).fg(Color::Rgb(
-                                        255 - r,
-                                        255 - g,
-                                        255 - b,
-                                    )),
-                                )]),
-                            );
-                        }
-                    }
-                }
-
-                // Optionally paint walkable neighbors
-                if self.show_walkable_neighbors {
-                    ctx.layer();
-
-                    for y in 0..grid_height {
-                        for x in 0..grid_width {
-                            let coord: GridCoord = (x, grid_height - 1 - y).into();
-                            for ncoord in self.grid.walkable_neighbors(coord) {
-                                let (x, y) = (x as f64, y as f64);
-                                let dx = ncoord.x as f64 - x;
-                                let dy = grid_height as f64 - 1.0 - ncoord.y as f64 - y;
-
-                                ctx.draw(&canvas::Line {
-                                    x1: x + 0.5 + dx * 0.05,
-                                    y1: y + 0.5 + dy * 0.05,
-                                    x2: x + 0.5 + dx * 0.45,
-                                    y2: y + 0.5 + dy * 0.45,
-                                    color: Color::Rgb(255, 193, 7),
-                                });
-                                ctx.draw(&canvas::Rectangle {
-                                    x: x + 0.5 + dx * 0.45 - 0.05,
-                                    y: y + 0.5 + dy * 0.45 - 0.05,
-                                    width: 0.1,
-                                    height: 0.1,
-                                    color: Color::Rgb(255, 193, 7),
-                                })
-                            }
-                        }
-                    }
-                }
-
-                // Render the search lines
-                ctx.layer();
-                for coord in self.current.iter() {
-                    // use a text label as a "circle"
-                    ctx.print(
-                        coord.x as f64 + 0.5,
-                        grid_height as f64 - (coord.y as f64 + 0.5),
-                        Spans(vec![Span::styled(
-                            "●",
-                            Style::default().fg(Color::Rgb(255, 193, 7)),
-                        )]),
-                    );
-
-                    // draw a polyline from the current coord all the way back to the start
-                    let record = self.visited.get(coord).unwrap();
-                    let mut curr = record;
-                    let mut coord = *coord;
-                    while let Some(prev) = curr.prev.as_ref() {
-                        curr = self.visited.get(prev).unwrap();
-
-                        let (x, y) = (prev.x as f64, prev.y as f64);
-                        let dx = coord.x as f64 - x;
-                        let dy = coord.y as f64 - y;
-
-                        ctx.draw(&canvas::Line {
-                            x1: x + 0.5 + dx * 0.2,
-                            y1: grid_height as f64 - (y + 0.5 + dy * 0.2),
-                            x2: x + 0.5 + dx * 0.8,
-                            y2: grid_height as f64 - (y + 0.5 + dy * 0.8),
-                            color: Color::Rgb(255, 193, 7),
-                        });
-
-                        coord = *prev;
-                    }
-                }
-            });
-        f.render_widget(main_canvas, *main_chunk);
-
-        // Split the information block up into areas
-        let info_chunks = Layout::default()
-            .direction(tui::layout::Direction::Horizontal)
-            .constraints([
-                Constraint::Min(1),
-                Constraint::Length(27),
-                Constraint::Length(28),
-            ])
-            .split(*info_chunk);
-        let info_main_chunk = info_chunks[0];
-        let info_glyph_display_chunk = info_chunks[1];
-        let info_walkable_neighbors_chunk = info_chunks[2];
-
-        // Render simulation information
-        let info_block = Block::default().borders(Borders::ALL);
-        let info_paragraph = Paragraph::new(self.status_text()).block(info_block);
-        f.render_widget(info_paragraph, info_main_chunk);
-
-        // Render instructions on how to show the debug elevation glyphs
-        let info_glyph_display_block = Block::default().borders(Borders::ALL);
-        let info_glyph_display_paragraph = Paragraph::new(Spans(vec![
-            Span::raw("Elevation ["),
-            Span::styled(
-                "g",
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .fg(Color::Cyan),
-            ),
-            Span::raw("]lyph display"),
-        ]))
-        .block(info_glyph_display_block);
-        f.render_widget(info_glyph_display_paragraph, info_glyph_display_chunk);
-
-        // Render instructions on how to show the walkable neigbours
-        let info_walkable_neighbors_block = Block::default().borders(Borders::ALL);
-        let info_walkable_neighbors_paragraph = Paragraph::new(Spans(vec![
-            Span::raw("Show walkable ["),
-            Span::styled(
-                "n",
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .fg(Color::Cyan),
-            ),
-            Span::raw("]eighbours"),
-        ]))
-        .block(info_walkable_neighbors_block);
-        f.render_widget(
-            info_walkable_neighbors_paragraph,
-            info_walkable_neighbors_chunk,
-        )
+impl TerrainCosts {
+    fn from_params(params: &SolverParams) -> Result<Self, ParamError> {
+        let default = Self::default();
+        Ok(Self {
+            step: params.get_or("step_cost", default.step)?,
+            ascent: params.get_or("ascent_cost", default.ascent)?,
+            descent: params.get_or("descent_cost", default.descent)?,
+        })
     }
 
-    /// Update the app's simulation
-    fn on_tick(&mut self) {
-        if self.end_found {
-            return;
+    /// What a step from a square at elevation `from` to one at `to` costs.
+    fn step(&self, from: u8, to: u8) -> u64 {
+        let per_level = if to >= from {
+            self.ascent
+        } else {
+            self.descent
+        };
+        self.step + per_level * u64::from(from.abs_diff(to))
+    }
+}
+
+enum InitialSet {
+    StartingCell,
+    LowestElevationCell,
+}
+
+impl InitialSet {
+    /// Every square that the search starts from.
+    fn starts(&self, grid: &Grid<Cell>) -> Vec<GridCoord> {
+        match self {
+            Self::StartingCell => find_cells(grid, |cell| matches!(cell, Cell::Start))
+                .take(1)
+                .collect(),
+            Self::LowestElevationCell => find_cells(grid, |cell| cell.elevation() == 0).collect(),
         }
+    }
+}
 
-        let grid_height = self.grid.height();
-        let grid_width = self.grid.width();
-
-        if self.current.is_empty() {
-            // find start coordinate
-            match self.initial_set {
-                InitialSet::StartingCell => {
-                    'outer: for y in 0..grid_height {
-                        for x in 0..grid_width {
-                            let coord = (x, y).into();
-                            if let Cell::Start = self.grid.cell(coord).unwrap() {
-                                self.current.insert(coord);
-                                self.visited.insert(coord, CellRecord { prev: None });
-                                break 'outer;
-                            }
-                        }
-                    }
-                }
-
-                InitialSet::LowestElevationCell => {
-                    for y in 0..grid_height {
-                        for x in 0..grid_width {
-                            let coord = (x, y).into();
-                            if let Cell::Start | Cell::Square(0) = self.grid.cell(coord).unwrap() {
-                                self.current.insert(coord);
-                                self.visited.insert(coord, CellRecord { prev: None });
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            // Visit the current cells' neigbours
-            let current = std::mem::take(&mut self.current);
-            let mut next = HashSet::new();
-            let mut visited = std::mem::take(&mut self.visited);
-
-            'outer: for curr in current {
-                for ncoord in self.grid.walkable_neighbors(curr) {
-                    if visited.contains_key(&ncoord) {
-                        // don't visit it again!
-                        continue;
-                    }
-
-                    if !self.end_found {
-                        if let Some(&Cell::End) = self.grid.cell(ncoord) {
-                            // found the end coordinate!
-                            self.end_found = true;
-                            break 'outer;
-                        }
-                    }
-
-                    visited.insert(ncoord, CellRecord { prev: Some(curr) });
-                    next.insert(ncoord);
-                }
-            }
+/// Which squares can be climbed to from a neighboring square.
+///
+/// The defaults are the puzzle's rules: one step up at most, any distance down, and no diagonal
+/// steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ClimbRules {
+    /// How much higher the next square can be, from `--param max_ascent=N`.
+    max_ascent: u8,
+    /// How much lower the next square can be, from `--param max_descent=N`, or `None` for no
+    /// limit.
+    max_descent: Option<u8>,
+    /// Whether diagonal steps are allowed, from `--param diagonal=true`.
+    diagonal: bool,
+}
 
-            self.current = next;
-            self.visited = visited;
-            self.num_steps += 1;
+impl Default for ClimbRules {
+    fn default() -> Self {
+        Self {
+            max_ascent: 1,
+            max_descent: None,
+            diagonal: false,
         }
     }
+}
 
-    fn num_visited(&self) -> usize {
-        self.visited.len()
+impl ClimbRules {
+    const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    const DIAGONAL: [(isize, isize); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+    fn from_params(params: &SolverParams) -> Result<Self, ParamError> {
+        let default = Self::default();
+        Ok(Self {
+            max_ascent: params.get_or("max_ascent", default.max_ascent)?,
+            max_descent: params.get("max_descent")?,
+            diagonal: params.get_or("diagonal", default.diagonal)?,
+        })
     }
 
-    const fn num_steps(&self) -> usize {
-        self.num_steps
+    /// Whether a square at elevation `to` can be climbed to from one at `from`.
+    fn allows(&self, from: u8, to: u8) -> bool {
+        if to >= from {
+            to - from <= self.max_ascent
+        } else {
+            self.max_descent
+                .is_none_or(|max_descent| from - to <= max_descent)
+        }
     }
 
-    fn status_text(&self) -> Spans {
-        let percent = self.num_visited() as f64 / self.grid.num_cells() as f64 * 100.0;
-        let mut spans = vec![Span::raw(format!(
-            "{} steps, {}/{} visited ({percent:.01}%) - ",
-            self.num_steps(),
-            self.num_visited(),
-            self.grid.num_cells()
-        ))];
+    /// The steps that can be taken from a square, before looking at elevations.
+    fn deltas(&self) -> impl Iterator<Item = (isize, isize)> {
+        let diagonal = if self.diagonal {
+            &Self::DIAGONAL[..]
+        } else {
+            &[]
+        };
+        Self::ORTHOGONAL.into_iter().chain(diagonal.iter().copied())
+    }
 
-        if self.end_found {
-            spans.push(Span::styled(
-                "COMPLETE",
-                Style::default()
-                    .fg(Color::Rgb(193, 255, 7))
-                    .add_modifier(Modifier::BOLD),
-            ));
+    /// How far apart two squares are, counting only the steps that these rules allow.
+    fn distance(&self) -> fn(Point, Point) -> u64 {
+        if self.diagonal {
+            chebyshev
         } else {
-            spans.push(Span::styled(
-                "SEARCHING",
-                Style::default().fg(Color::Rgb(255, 193, 7)),
-            ));
+            manhattan
         }
+    }
+}
+
+/// Someone part of the way through a hike, for [`pathfinding::find_earliest`].
+///
+/// The heightmap never changes, so every step is just one minute later.
+struct Hiker<'g> {
+    grid: &'g Grid<Cell>,
+    rules: ClimbRules,
+    position: GridCoord,
+    steps: u64,
+    /// The squares that were climbed through to get here, most recent first, not counting the
+    /// current one. Shared between every hiker that took the same steps so far.
+    trail: Option<Rc<Trail>>,
+}
 
-        Spans(spans)
+/// One step of a [`Hiker`]'s trail, along with all of the steps before it.
+struct Trail {
+    position: GridCoord,
+    previous: Option<Rc<Trail>>,
+}
+
+impl Hiker<'_> {
+    /// Every square the hiker went through, from where it started to where it is now.
+    fn path(&self) -> Vec<GridCoord> {
+        let mut path = vec![self.position];
+        let mut trail = self.trail.as_deref();
+        while let Some(step) = trail {
+            path.push(step.position);
+            trail = step.previous.as_deref();
+        }
+        path.reverse();
+        path
     }
 }
 
+impl TimedState for Hiker<'_> {
+    fn position(&self) -> Point {
+        to_point(self.position)
+    }
+
+    fn time(&self) -> u64 {
+        self.steps
+    }
+
+    fn successors(&self, time: u64) -> Vec<Self> {
+        let trail = Rc::new(Trail {
+            position: self.position,
+            previous: self.trail.clone(),
+        });
+        self.grid
+            .walkable_neighbors(self.position, self.rules)
+            .map(|position| Hiker {
+                grid: self.grid,
+                rules: self.rules,
+                position,
+                steps: time,
+                trail: Some(Rc::clone(&trail)),
+            })
+            .collect()
+    }
+}
+
+fn to_point(coord: GridCoord) -> Point {
+    (coord.x as i64, coord.y as i64)
+}
+
+/// Every square in `grid` that matches `predicate`, row by row.
+fn find_cells<'g>(
+    grid: &'g Grid<Cell>,
+    predicate: impl Fn(&Cell) -> bool + 'g,
+) -> impl Iterator<Item = GridCoord> + 'g {
+    let width = grid.width();
+    (0..grid.height())
+        .flat_map(move |y| (0..width).map(move |x| GridCoord::from((x, y))))
+        .filter(move |&coord| predicate(grid.cell(coord).unwrap()))
+}
+
 #[derive(Clone, Copy)]
 enum Cell {
     /// Start position
@@ -527,16 +496,16 @@ impl fmt::Debug for Cell {
     }
 }
 
-struct CellRecord {
-    prev: Option<GridCoord>,
-}
-
 trait GridExt {
     /// Parse the input file into a heightmap grid
     fn parse(input: &str) -> Self;
 
     /// Get the walkable neighbours next to a grid cell.
-    fn walkable_neighbors(&self, coord: GridCoord) -> Box<dyn Iterator<Item = GridCoord> + '_>;
+    fn walkable_neighbors(
+        &self,
+        coord: GridCoord,
+        rules: ClimbRules,
+    ) -> Box<dyn Iterator<Item = GridCoord> + '_>;
 }
 
 impl GridExt for Grid<Cell> {
@@ -563,11 +532,14 @@ impl GridExt for Grid<Cell> {
         }
     }
 
-    fn walkable_neighbors(&self, coord: GridCoord) -> Box<dyn Iterator<Item = GridCoord> + '_> {
+    fn walkable_neighbors(
+        &self,
+        coord: GridCoord,
+        rules: ClimbRules,
+    ) -> Box<dyn Iterator<Item = GridCoord> + '_> {
         let curr_elev = self.cell(coord).unwrap().elevation();
-        let deltas: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
 
-        Box::new(deltas.into_iter().filter_map(move |(dx, dy)| {
+        Box::new(rules.deltas().filter_map(move |(dx, dy)| {
             Some(GridCoord {
                 x: coord.x.checked_add_signed(dx)?,
                 y: coord.y.checked_add_signed(dy)?,
@@ -575,8 +547,115 @@ impl GridExt for Grid<Cell> {
             .filter(|&coord| self.in_bounds(coord))
             .filter(|&coord| {
                 let other_elev = self.cell(coord).unwrap().elevation();
-                other_elev <= curr_elev + 1
+                rules.allows(curr_elev, other_elev)
             })
         }))
     }
 }
+
+super::challenge_solver_test_boilerplate! {
+    Solver12::default();
+    12 => {
+        a as u64,
+        b as u64,
+    }
+
+    #[test]
+    fn custom_climb_rules() -> Result<(), SolverError> {
+        let solve_a = |params: &str| {
+            let mut config = SolverConfig::default();
+            for param in params.split_whitespace() {
+                let (key, value) = param.split_once('=').unwrap();
+                config.params.insert(key, value);
+            }
+            let mut solver = Solver12::default();
+            solver.configure(&config).unwrap();
+            solver.solve_a(&mut Cursor::new(SAMPLE_INPUT))
+        };
+
+        let fewest_steps = |params| -> Result<u64, SolverError> {
+            Ok(*solve_a(params)?.downcast_ref::<u64>().unwrap())
+        };
+
+        // Diagonal steps cut the corners off of the spiral.
+        assert!(fewest_steps("diagonal=true")? < 31);
+        // Climbing anything means heading straight for the end.
+        assert_eq!(fewest_steps("max_ascent=25")?, 7);
+        assert_eq!(fewest_steps("max_ascent=25 diagonal=true")?, 5);
+        // The spiral only ever goes up, so a limit on going down doesn't get in the way...
+        assert_eq!(fewest_steps("max_descent=0")?, 31);
+        // ...until nothing can be climbed at all.
+        assert!(matches!(
+            solve_a("max_ascent=0 max_descent=0"),
+            Err(SolverError::InvalidInput(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn weighs_steps_with_dijkstra() -> Result<(), SolverError> {
+        let solve = |input: &str, part_b: bool, params: &str| {
+            let mut config = SolverConfig::default();
+            config.params.insert("algorithm", "dijkstra");
+            for param in params.split_whitespace() {
+                let (key, value) = param.split_once('=').unwrap();
+                config.params.insert(key, value);
+            }
+            let mut solver = Solver12::default();
+            solver.configure(&config).unwrap();
+            let mut input = Cursor::new(input.to_string());
+            let answer = if part_b {
+                solver.solve_b(&mut input)?
+            } else {
+                solver.solve_a(&mut input)?
+            };
+            let steps = answer.metadata()["steps"].as_u64().unwrap();
+            Ok::<_, SolverError>((*answer.downcast_ref::<u64>().unwrap(), steps))
+        };
+
+        // Every step costs the same by default, so it agrees with A*.
+        assert_eq!(solve(SAMPLE_INPUT, false, "")?, (31, 31));
+        assert_eq!(solve(SAMPLE_INPUT, true, "")?, (29, 29));
+        // Getting from `a` to `z` always means climbing 25 levels, and the fewest steps never go
+        // down, so they're still the least effort.
+        assert_eq!(solve(SAMPLE_INPUT, false, "ascent_cost=10")?, (281, 31));
+
+        // The fewest steps go over the hill in the top row, but once coming down off it costs
+        // something, going around along the bottom row is easier.
+        let hill = "SbcbE\naaaaa\n";
+        assert_eq!(solve(hill, false, "max_ascent=25")?, (4, 4));
+        assert_eq!(solve(hill, false, "max_ascent=25 descent_cost=10")?, (6, 6));
+        // Counting only the climbing, the hill's extra level up makes going around easier too.
+        assert_eq!(
+            solve(hill, false, "max_ascent=25 step_cost=0 ascent_cost=1")?,
+            (25, 6)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_climbs_from_start_to_end() -> Result<(), SolverError> {
+        let answer = Solver12::default().solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+
+        let path = answer.metadata()["path"].as_array().unwrap();
+        assert_eq!(path.len(), 32);
+        assert_eq!(path[0], json!([0, 0]));
+        assert_eq!(path[31], json!([5, 2]));
+
+        let elevations = answer.metadata()["elevations"].as_array().unwrap();
+        assert_eq!(elevations.len(), path.len());
+        assert_eq!(elevations[0], 0);
+        assert_eq!(elevations[31], 25);
+
+        let elevations = [0, 1, 12, 25];
+        assert_eq!(sparkline(&elevations), "▁▁▄█");
+        assert_eq!(
+            profile_csv(&elevations),
+            "distance,elevation\n0,0\n1,1\n2,12\n3,25\n"
+        );
+
+        Ok(())
+    }
+}
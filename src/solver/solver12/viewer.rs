@@ -0,0 +1,396 @@
+use std::collections::{HashMap, HashSet};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{
+        canvas::{self, Canvas},
+        Block, Borders, Paragraph,
+    },
+    Frame,
+};
+
+use crate::{
+    error::SolverError,
+    grid::{Grid, GridCoord},
+    simulation::{Pacing, StepResult},
+    tui_app::{self, Control, TuiApp, Viewport},
+};
+
+use super::{Cell, ClimbRules, GridExt, InitialSet};
+
+/// Watch the search spread across the heightmap, until the view is closed.
+pub(super) fn watch(
+    grid: Grid<Cell>,
+    initial_set: InitialSet,
+    rules: ClimbRules,
+    pacing: Pacing,
+) -> Result<(), SolverError> {
+    let mut app = App::new(grid, initial_set, rules);
+    tui_app::run(&mut app, 60.0, pacing)?;
+    Ok(())
+}
+
+struct App {
+    grid: Grid<Cell>,
+    visited: HashMap<GridCoord, CellRecord>,
+    current: HashSet<GridCoord>,
+    num_steps: usize,
+    end_found: bool,
+
+    initial_set: InitialSet,
+    rules: ClimbRules,
+
+    show_glyphs: bool,
+    show_walkable_neighbors: bool,
+    viewport: Viewport,
+}
+
+impl App {
+    fn new(grid: Grid<Cell>, initial_set: InitialSet, rules: ClimbRules) -> Self {
+        Self {
+            grid,
+            visited: Default::default(),
+            current: Default::default(),
+            num_steps: 0,
+            end_found: false,
+
+            initial_set,
+            rules,
+
+            show_glyphs: false,
+            show_walkable_neighbors: false,
+            viewport: Viewport::default(),
+        }
+    }
+
+    fn num_visited(&self) -> usize {
+        self.visited.len()
+    }
+
+    const fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    fn status_text(&self) -> Spans<'_> {
+        let percent = self.num_visited() as f64 / self.grid.num_cells() as f64 * 100.0;
+        let mut spans = vec![Span::raw(format!(
+            "{} steps, {}/{} visited ({percent:.01}%) - ",
+            self.num_steps(),
+            self.num_visited(),
+            self.grid.num_cells()
+        ))];
+
+        if self.end_found {
+            spans.push(Span::styled(
+                "COMPLETE",
+                Style::default()
+                    .fg(Color::Rgb(193, 255, 7))
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else {
+            spans.push(Span::styled(
+                "SEARCHING",
+                Style::default().fg(Color::Rgb(255, 193, 7)),
+            ));
+        }
+
+        Spans(spans)
+    }
+}
+
+impl TuiApp for App {
+    /// Render the app UI to a tui frame
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        // Split screen up into main areas
+        let chunks = Layout::default()
+            .direction(tui::layout::Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(f.size());
+        let main_chunk = &chunks[0];
+        let info_chunk = &chunks[1];
+
+        // Render the main simulation
+        let main_block = Block::default().borders(Borders::NONE);
+        let [x_bounds, y_bounds] = self.viewport.bounds(
+            [
+                [0.0, self.grid.width() as f64],
+                [0.0, self.grid.height() as f64],
+            ],
+            main_block.inner(*main_chunk),
+        );
+        let main_canvas = Canvas::default()
+            .block(main_block)
+            .x_bounds(x_bounds)
+            .y_bounds(y_bounds)
+            .background_color(Color::Rgb(0, 0, 0))
+            .paint(|ctx| {
+                // Paint the grid
+                let grid_height = self.grid.height();
+                let grid_width = self.grid.width();
+                for y in 0..grid_height {
+                    for x in 0..grid_width {
+                        let cell = self.grid.cell((x, grid_height - 1 - y).into()).unwrap();
+
+                        let (glyph, color) = match cell {
+                            Cell::Start => ("S".to_string(), Color::Rgb(216, 27, 96)),
+                            Cell::End => ("E".to_string(), Color::Rgb(30, 136, 229)),
+                            Cell::Square(elevation) => {
+                                let glyph = format!("{elevation}");
+                                let elevation = *elevation as f32 / 25.0;
+                                let f = (elevation * 255.0) as u8;
+                                (glyph, Color::Rgb(f, f, f))
+                            }
+                        };
+                        let Color::Rgb(r, g, b) = color else {
+                            unreachable!();
+                        };
+
+                        let fill_points = (0..=20)
+                            .flat_map(|fill_x| {
+                                let fill_x = fill_x as f64 / 20.0 + x as f64;
+                                (0..=20).map(move |fill_y| {
+                                    let fill_y = fill_y as f64 / 20.0 + y as f64;
+                                    (fill_x, fill_y)
+                                })
+                            })
+                            .collect::<Vec<_>>();
+
+                        ctx.draw(&canvas::Points {
+                            coords: &fill_points,
+                            color,
+                        });
+
+                        if self.show_glyphs {
+                            ctx.print(
+                                x as f64 + 0.5,
+                                y as f64 + 0.5,
+                                Spans(vec![Span::styled(
+                                    glyph,
+                                    Style::default().bg(color).fg(Color::Rgb(
+                                        255 - r,
+                                        255 - g,
+                                        255 - b,
+                                    )),
+                                )]),
+                            );
+                        }
+                    }
+                }
+
+                // Optionally paint walkable neighbors
+                if self.show_walkable_neighbors {
+                    ctx.layer();
+
+                    for y in 0..grid_height {
+                        for x in 0..grid_width {
+                            let coord: GridCoord = (x, grid_height - 1 - y).into();
+                            for ncoord in self.grid.walkable_neighbors(coord, self.rules) {
+                                let (x, y) = (x as f64, y as f64);
+                                let dx = ncoord.x as f64 - x;
+                                let dy = grid_height as f64 - 1.0 - ncoord.y as f64 - y;
+
+                                ctx.draw(&canvas::Line {
+                                    x1: x + 0.5 + dx * 0.05,
+                                    y1: y + 0.5 + dy * 0.05,
+                                    x2: x + 0.5 + dx * 0.45,
+                                    y2: y + 0.5 + dy * 0.45,
+                                    color: Color::Rgb(255, 193, 7),
+                                });
+                                ctx.draw(&canvas::Rectangle {
+                                    x: x + 0.5 + dx * 0.45 - 0.05,
+                                    y: y + 0.5 + dy * 0.45 - 0.05,
+                                    width: 0.1,
+                                    height: 0.1,
+                                    color: Color::Rgb(255, 193, 7),
+                                })
+                            }
+                        }
+                    }
+                }
+
+                // Render the search lines
+                ctx.layer();
+                for coord in self.current.iter() {
+                    // use a text label as a "circle"
+                    ctx.print(
+                        coord.x as f64 + 0.5,
+                        grid_height as f64 - (coord.y as f64 + 0.5),
+                        Spans(vec![Span::styled(
+                            "●",
+                            Style::default().fg(Color::Rgb(255, 193, 7)),
+                        )]),
+                    );
+
+                    // draw a polyline from the current coord all the way back to the start
+                    let record = self.visited.get(coord).unwrap();
+                    let mut curr = record;
+                    let mut coord = *coord;
+                    while let Some(prev) = curr.prev.as_ref() {
+                        curr = self.visited.get(prev).unwrap();
+
+                        let (x, y) = (prev.x as f64, prev.y as f64);
+                        let dx = coord.x as f64 - x;
+                        let dy = coord.y as f64 - y;
+
+                        ctx.draw(&canvas::Line {
+                            x1: x + 0.5 + dx * 0.2,
+                            y1: grid_height as f64 - (y + 0.5 + dy * 0.2),
+                            x2: x + 0.5 + dx * 0.8,
+                            y2: grid_height as f64 - (y + 0.5 + dy * 0.8),
+                            color: Color::Rgb(255, 193, 7),
+                        });
+
+                        coord = *prev;
+                    }
+                }
+            });
+        f.render_widget(main_canvas, *main_chunk);
+
+        // Split the information block up into areas
+        let info_chunks = Layout::default()
+            .direction(tui::layout::Direction::Horizontal)
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(27),
+                Constraint::Length(28),
+                Constraint::Length(Viewport::HELP.len() as u16 + 3),
+            ])
+            .split(*info_chunk);
+        let info_main_chunk = info_chunks[0];
+        let info_glyph_display_chunk = info_chunks[1];
+        let info_walkable_neighbors_chunk = info_chunks[2];
+        let info_viewport_chunk = info_chunks[3];
+
+        // Render simulation information
+        let info_block = Block::default().borders(Borders::ALL);
+        let info_paragraph = Paragraph::new(self.status_text()).block(info_block);
+        f.render_widget(info_paragraph, info_main_chunk);
+
+        // Render instructions on how to show the debug elevation glyphs
+        let info_glyph_display_block = Block::default().borders(Borders::ALL);
+        let info_glyph_display_paragraph = Paragraph::new(Spans(vec![
+            Span::raw("Elevation ["),
+            Span::styled(
+                "g",
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .fg(Color::Cyan),
+            ),
+            Span::raw("]lyph display"),
+        ]))
+        .block(info_glyph_display_block);
+        f.render_widget(info_glyph_display_paragraph, info_glyph_display_chunk);
+
+        // Render instructions on how to show the walkable neigbours
+        let info_walkable_neighbors_block = Block::default().borders(Borders::ALL);
+        let info_walkable_neighbors_paragraph = Paragraph::new(Spans(vec![
+            Span::raw("Show walkable ["),
+            Span::styled(
+                "n",
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .fg(Color::Cyan),
+            ),
+            Span::raw("]eighbours"),
+        ]))
+        .block(info_walkable_neighbors_block);
+        f.render_widget(
+            info_walkable_neighbors_paragraph,
+            info_walkable_neighbors_chunk,
+        );
+
+        // Render instructions on how to move around the heightmap
+        let info_viewport_block = Block::default().borders(Borders::ALL);
+        let info_viewport_paragraph = Paragraph::new(Viewport::HELP).block(info_viewport_block);
+        f.render_widget(info_viewport_paragraph, info_viewport_chunk);
+    }
+
+    /// Update the app's simulation
+    fn on_tick(&mut self) {
+        if self.status() == StepResult::Done {
+            return;
+        }
+
+        if self.current.is_empty() {
+            // find start coordinates
+            for coord in self.initial_set.starts(&self.grid) {
+                self.current.insert(coord);
+                self.visited.insert(coord, CellRecord { prev: None });
+            }
+        } else {
+            // Visit the current cells' neigbours
+            let current = std::mem::take(&mut self.current);
+            let mut next = HashSet::new();
+            let mut visited = std::mem::take(&mut self.visited);
+
+            'outer: for curr in current {
+                for ncoord in self.grid.walkable_neighbors(curr, self.rules) {
+                    if visited.contains_key(&ncoord) {
+                        // don't visit it again!
+                        continue;
+                    }
+
+                    if !self.end_found {
+                        if let Some(&Cell::End) = self.grid.cell(ncoord) {
+                            // found the end coordinate!
+                            self.end_found = true;
+                            break 'outer;
+                        }
+                    }
+
+                    visited.insert(ncoord, CellRecord { prev: Some(curr) });
+                    next.insert(ncoord);
+                }
+            }
+
+            self.current = next;
+            self.visited = visited;
+            self.num_steps += 1;
+        }
+    }
+
+    /// The search is done once it finds the end, or runs out of cells to visit.
+    fn status(&self) -> StepResult {
+        if self.end_found || (self.num_steps > 0 && self.current.is_empty()) {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if self.viewport.on_key(key) {
+            return Control::Continue;
+        }
+
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('g'),
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            } => {
+                self.show_glyphs = !self.show_glyphs;
+            }
+
+            KeyEvent {
+                code: KeyCode::Char('n'),
+                kind: KeyEventKind::Press | KeyEventKind::Release,
+                ..
+            } => {
+                self.show_walkable_neighbors = !self.show_walkable_neighbors;
+            }
+
+            _ => (),
+        }
+
+        Control::Continue
+    }
+}
+
+struct CellRecord {
+    prev: Option<GridCoord>,
+}
@@ -1,128 +1,273 @@
-use std::{
-    cmp::{self, Ordering},
-    fmt,
-    io::BufRead,
+use std::{cmp::Ordering, io::BufRead};
+
+use crate::{
+    error::{Context, SolverError},
+    explain::explain,
+    parse::{BadInputError, MAX_DIAGNOSTICS},
 };
 
-use color_eyre::eyre::Context;
-use serde::Deserialize;
+use self::packet::{cmp_packets, cmp_packets_explained, Issue, Node};
 
-use super::ChallengeSolver;
+use super::{Answer, ChallengeSolver, ParamError, SolverConfig};
 
-#[derive(Deserialize, Clone, PartialEq, Eq)]
-#[serde(untagged)]
-enum Node {
-    Number(u64),
-    List(Vec<Node>),
-}
-
-impl Node {
-    fn with_slice<T>(&self, f: impl FnOnce(&[Node]) -> T) -> T {
-        match self {
-            Self::List(l) => f(&l[..]),
-            Self::Number(n) => f(&[Self::Number(*n)]),
-        }
-    }
-}
-
-impl cmp::PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        match (self, other) {
-            (Node::Number(a), Node::Number(b)) => a.partial_cmp(b),
-
-            (l, r) => Some(l.with_slice(|l| {
-                r.with_slice(|r| {
-                    l.iter()
-                        .zip(r.iter())
-                        .map(|(aa, bb)| aa.cmp(bb))
-                        // return the first ordering that isn't `Equal`
-                        .find(|&ord| ord != Ordering::Equal)
-                        // or compare the lengths
-                        .unwrap_or_else(|| l.len().cmp(&r.len()))
-                })
-            })),
-        }
-    }
-}
-
-impl cmp::Ord for Node {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
-    }
-}
-
-impl fmt::Debug for Node {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Number(n) => write!(f, "{n}"),
-            Self::List(l) => f.debug_list().entries(l).finish(),
-        }
-    }
-}
+mod packet;
 
 #[derive(Debug, Default)]
-pub struct Solver13;
+pub struct Solver13 {
+    /// Skip over trailing commas and stray whitespace in packets, with `--param lenient=true`,
+    /// warning about each one. Inputs pasted through chat apps tend to pick them up.
+    lenient: bool,
+}
 
 impl ChallengeSolver for Solver13 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         13
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut input_buf = String::new();
-        input
-            .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.lenient = config.params.get_or("lenient", false)?;
+        Ok(())
+    }
 
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut sum = 0;
 
-        for (i, groups) in input_buf.split("\n\n").enumerate() {
+        for (i, pair) in self.read_packets(input)?.into_iter().enumerate() {
             let i = i + 1;
 
-            let mut nodes = groups
-                .lines()
-                .map(|line| serde_json::from_str::<Node>(line).unwrap());
-            let l = nodes.next().unwrap();
-            let r = nodes.next().unwrap();
+            let [(_, l), (_, r)] = <[_; 2]>::try_from(pair).map_err(|pair| {
+                SolverError::invalid_input(format!(
+                    "Pair {i}, starting on line {}, doesn't have two packets",
+                    pair[0].0
+                ))
+            })?;
+
+            explain!("== Pair {i} ==");
+            let in_order = cmp_packets_explained(&l, &r) == Ordering::Less;
+            explain!("");
 
             println!("\n== Pair {i} ==");
             println!("l = {l:?}");
             println!("r = {r:?}");
-            println!("l < r = {}", l < r);
+            println!("l < r = {in_order}");
 
-            if l < r {
+            if in_order {
                 sum += i;
             }
         }
 
         println!("\n---\n\nsum = {sum}");
 
-        Ok(Box::new(()))
+        Ok(Answer::new(sum))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let dividers = vec![
-            Node::List(vec![Node::Number(2)]),
-            Node::List(vec![Node::Number(6)]),
-        ];
-
-        let mut packets = input
-            .lines()
-            .map(|s| s.unwrap())
-            .filter(|s| !s.is_empty())
-            .map(|line| serde_json::from_str::<Node>(&line).unwrap())
-            .chain(dividers.iter().cloned())
-            .collect::<Vec<_>>();
+        let dividers = [Node::divider(2), Node::divider(6)];
 
-        packets.sort();
+        let packets = self
+            .read_packets(input)?
+            .into_iter()
+            .flatten()
+            .map(|(_, packet)| packet)
+            .collect::<Vec<_>>();
 
+        // A divider's (1-based) index in the sorted list is one more than the number of packets
+        // (including the other dividers) that would be sorted before it, so there's no need to
+        // actually sort anything.
         let decoder_key = dividers
             .iter()
-            .map(|d| packets.binary_search(d).unwrap() + 1)
+            .map(|d| {
+                let index = 1 + packets
+                    .iter()
+                    .chain(dividers.iter())
+                    .filter(|p| cmp_packets(p, d) == Ordering::Less)
+                    .count();
+                explain!(
+                    "{} packets come before {d:?}, so it ends up at index {index}",
+                    index - 1
+                );
+                index
+            })
             .product::<usize>();
 
         println!("decoder_key = {decoder_key}");
 
-        Ok(Box::new(()))
+        Ok(Answer::new(decoder_key))
+    }
+}
+
+impl Solver13 {
+    /// Read every packet in the input, in groups separated by blank lines, along with the
+    /// (1-based) line each one is on.
+    ///
+    /// Every line that isn't a packet gets pointed out. When being lenient, everything that got
+    /// skipped over is warned about on `stderr`.
+    fn read_packets(
+        &self,
+        input: &mut dyn BufRead,
+    ) -> Result<Vec<Vec<(usize, Node)>>, SolverError> {
+        let mut input_buf = String::new();
+        input
+            .read_to_string(&mut input_buf)
+            .context("Could not read input file to string")?;
+
+        let mut groups = vec![Vec::new()];
+        let mut problems = Vec::new();
+        let mut warnings = Vec::new();
+        let mut line_start = 0;
+
+        for (number, raw_line) in input_buf.split_inclusive('\n').enumerate() {
+            let start = line_start;
+            line_start += raw_line.len();
+            let line = raw_line.strip_suffix('\n').unwrap_or(raw_line);
+
+            // Where something was skipped, as a line and column for warning about it.
+            let mut skipped = |offset: usize, issue| {
+                let column = line[..offset].chars().count() + 1;
+                warnings.push((number + 1, column, issue));
+            };
+
+            if line.trim().is_empty() {
+                match line {
+                    "" => {}
+                    _ if !self.lenient => {}
+                    "\r" => skipped(0, Issue::WindowsLineEnding),
+                    _ => skipped(0, Issue::Whitespace),
+                }
+                if groups.last().is_some_and(|group| !group.is_empty()) {
+                    groups.push(Vec::new());
+                }
+                continue;
+            }
+
+            let parsed = if self.lenient {
+                Node::parse_lenient(line).map(|(packet, recovered)| {
+                    for fix in recovered {
+                        skipped(fix.offset, fix.issue);
+                    }
+                    packet
+                })
+            } else {
+                Node::parse(line)
+            };
+            match parsed {
+                Ok(packet) => groups.last_mut().unwrap().push((number + 1, packet)),
+                Err(e) => {
+                    let mut reason = e.reason;
+                    if !self.lenient && Node::parse_lenient(line).is_ok() {
+                        reason += " (`--param lenient=true` would skip over this)";
+                    }
+                    problems.push((start + e.offset..start + e.offset, reason));
+                }
+            }
+        }
+
+        for (number, column, issue) in warnings.iter().take(MAX_DIAGNOSTICS) {
+            eprintln!("warning: line {number}, column {column}: {issue}");
+        }
+        if warnings.len() > MAX_DIAGNOSTICS {
+            eprintln!(
+                "warning: ...and {} more, which aren't shown",
+                warnings.len() - MAX_DIAGNOSTICS
+            );
+        }
+
+        if !problems.is_empty() {
+            let err = BadInputError::spans(&input_buf, "Some lines aren't packets", problems);
+            eprintln!("{}", err.render());
+            return Err(err).context("Could not parse the packets");
+        }
+
+        if groups.last().is_some_and(Vec::is_empty) {
+            groups.pop();
+        }
+        Ok(groups)
+    }
+}
+
+super::challenge_solver_test_boilerplate! {
+    Solver13::default();
+    13 => {
+        a as usize,
+        b as usize,
+    }
+
+    #[test]
+    fn mixed_types_compare_as_lists() {
+        let l = Node::parse("[[1],[2,3,4]]").unwrap();
+        let r = Node::parse("[[1],4]").unwrap();
+        assert_eq!(cmp_packets(&l, &r), Ordering::Less);
+        assert_eq!(cmp_packets(&r, &l), Ordering::Greater);
+        assert_eq!(cmp_packets(&l, &l), Ordering::Equal);
+    }
+
+    #[test]
+    fn lenient_parsing_skips_over_mangled_packets() {
+        let (packet, recovered) = Node::parse_lenient(" [1, [2,3,],]\r").unwrap();
+        assert_eq!(packet, Node::parse("[1,[2,3]]").unwrap());
+        let skipped = recovered
+            .iter()
+            .map(|fix| (fix.offset, fix.issue))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            skipped,
+            [
+                (0, Issue::Whitespace),
+                (4, Issue::Whitespace),
+                (9, Issue::TrailingComma),
+                (11, Issue::TrailingComma),
+                (13, Issue::WindowsLineEnding),
+            ]
+        );
+
+        let err = Node::parse("[1, 2]").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert!(Node::parse("[1,2]\r").is_ok());
+        assert!(Node::parse("[1,2,]").is_err());
+        assert!(Node::parse_lenient("[1,,2]").is_err());
+        assert!(Node::parse_lenient("[1,2").is_err());
+
+        let mangled = SAMPLE_INPUT
+            .replace(',', ", ")
+            .replace("4]", "4,]")
+            .replace("]\n", "]\r\n")
+            .replace("\n\n", "\n \n");
+        let mut solver = Solver13::default();
+        assert!(solver.solve_a(&mut mangled.as_bytes()).is_err());
+
+        let mut config = SolverConfig::default();
+        config.params.insert("lenient", "true");
+        solver.configure(&config).unwrap();
+        assert_eq!(
+            solver.solve_a(&mut mangled.as_bytes()).unwrap(),
+            Answer::new(13_usize)
+        );
+        assert_eq!(
+            solver.solve_b(&mut mangled.as_bytes()).unwrap(),
+            Answer::new(140_usize)
+        );
+    }
+
+    #[test]
+    fn explains_like_the_puzzle() {
+        let l = Node::parse("[[1],[2,3,4]]").unwrap();
+        let r = Node::parse("[[1],4]").unwrap();
+        let explained = crate::explain::capture(100, || {
+            cmp_packets_explained(&l, &r);
+        });
+
+        assert_eq!(
+            explained,
+            "- Compare [[1], [2, 3, 4]] vs [[1], 4]\n\
+             \x20 - Compare [1] vs [1]\n\
+             \x20   - Compare 1 vs 1\n\
+             \x20 - Compare [2, 3, 4] vs 4\n\
+             \x20   - Mixed types; convert right to [4] and retry comparison\n\
+             \x20   - Compare [2, 3, 4] vs [4]\n\
+             \x20     - Compare 2 vs 4\n\
+             \x20       - Left side is smaller, so inputs are in the right order\n"
+        );
     }
 }
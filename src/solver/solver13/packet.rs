@@ -0,0 +1,290 @@
+use std::{cmp::Ordering, fmt, slice};
+
+use nom::{
+    branch::alt,
+    character::complete::{char, multispace0, u64 as nom_u64},
+    combinator::{eof, map},
+    error::context,
+    Finish, IResult,
+};
+use nom_supreme::error::ErrorTree;
+
+use crate::{
+    explain::explain,
+    parse::{furthest_span_error, Span},
+};
+
+/// A distress signal packet, or some part of one.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Node {
+    Number(u64),
+    List(Vec<Node>),
+}
+
+/// A line that isn't a packet.
+#[derive(Debug, thiserror::Error)]
+#[error("{reason}")]
+pub struct PacketError {
+    /// The byte offset into the line where parsing went wrong.
+    pub offset: usize,
+    pub reason: String,
+}
+
+/// Something that a lenient parse skipped over, which isn't part of the puzzle's packet format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recovered {
+    /// The byte offset into the line where it was.
+    pub offset: usize,
+    pub issue: Issue,
+}
+
+/// The ways that a packet pasted through a chat app tends to get mangled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Issue {
+    /// A comma right before the end of a list, like `[1,2,]`.
+    TrailingComma,
+    /// Spaces or tabs anywhere on the line, like `[1, 2]`.
+    Whitespace,
+    /// A `\r` at the end of the line.
+    WindowsLineEnding,
+}
+
+impl Node {
+    /// Parse a packet from a line like `[1,[2,[3,[4,[5,6,7]]]],8,9]`.
+    ///
+    /// The line has to be exactly in the puzzle's format, apart from a Windows line ending.
+    pub fn parse(line: &str) -> Result<Self, PacketError> {
+        PacketParser::new(false).parse(line).map(|(node, _)| node)
+    }
+
+    /// Like [`Node::parse`], but skips over trailing commas and stray whitespace, and points out
+    /// everything it skipped, in the order it was on the line.
+    pub fn parse_lenient(line: &str) -> Result<(Self, Vec<Recovered>), PacketError> {
+        PacketParser::new(true).parse(line)
+    }
+
+    /// Build a divider packet, like `[[2]]`.
+    pub fn divider(n: u64) -> Self {
+        Self::List(vec![Self::List(vec![Self::Number(n)])])
+    }
+}
+
+/// Compare two packets, following the distress signal's ordering rules.
+///
+/// Numbers compare numerically, lists compare element-by-element and then by length, and a
+/// number compared against a list is treated as a list containing just that number.
+pub fn cmp_packets(l: &Node, r: &Node) -> Ordering {
+    compare(l, r, None)
+}
+
+/// Like [`cmp_packets`], but [`explain`]s every step of the comparison the same way that the
+/// puzzle description does.
+pub fn cmp_packets_explained(l: &Node, r: &Node) -> Ordering {
+    compare(l, r, Some(0))
+}
+
+/// Compare two packets, explaining each step at `depth` if it's given.
+fn compare(l: &Node, r: &Node, depth: Option<usize>) -> Ordering {
+    say(depth, format_args!("Compare {l:?} vs {r:?}"));
+    let inner = depth.map(|depth| depth + 1);
+
+    match (l, r) {
+        (Node::Number(a), Node::Number(b)) => {
+            let ord = a.cmp(b);
+            match ord {
+                Ordering::Less => say(
+                    inner,
+                    format_args!("Left side is smaller, so inputs are in the right order"),
+                ),
+                Ordering::Greater => say(
+                    inner,
+                    format_args!("Right side is smaller, so inputs are not in the right order"),
+                ),
+                Ordering::Equal => {}
+            }
+            ord
+        }
+
+        (Node::List(l), Node::List(r)) => compare_lists(l, r, inner),
+
+        (Node::Number(_), Node::List(r)) => {
+            say(
+                inner,
+                format_args!("Mixed types; convert left to [{l:?}] and retry comparison"),
+            );
+            retry_as_lists(slice::from_ref(l), r, inner)
+        }
+
+        (Node::List(l), Node::Number(_)) => {
+            say(
+                inner,
+                format_args!("Mixed types; convert right to [{r:?}] and retry comparison"),
+            );
+            retry_as_lists(l, slice::from_ref(r), inner)
+        }
+    }
+}
+
+fn retry_as_lists(l: &[Node], r: &[Node], depth: Option<usize>) -> Ordering {
+    say(depth, format_args!("Compare {l:?} vs {r:?}"));
+    compare_lists(l, r, depth.map(|depth| depth + 1))
+}
+
+fn compare_lists(l: &[Node], r: &[Node], depth: Option<usize>) -> Ordering {
+    l.iter()
+        .zip(r.iter())
+        .map(|(aa, bb)| compare(aa, bb, depth))
+        // return the first ordering that isn't `Equal`
+        .find(|&ord| ord != Ordering::Equal)
+        // or compare the lengths
+        .unwrap_or_else(|| {
+            let ord = l.len().cmp(&r.len());
+            match ord {
+                Ordering::Less => say(
+                    depth,
+                    format_args!("Left side ran out of items, so inputs are in the right order"),
+                ),
+                Ordering::Greater => say(
+                    depth,
+                    format_args!(
+                        "Right side ran out of items, so inputs are not in the right order"
+                    ),
+                ),
+                Ordering::Equal => {}
+            }
+            ord
+        })
+}
+
+/// Explain a step of a comparison as a bullet point, indented by its `depth`.
+fn say(depth: Option<usize>, step: fmt::Arguments<'_>) {
+    if let Some(depth) = depth {
+        explain!("{:indent$}- {step}", "", indent = 2 * depth);
+    }
+}
+
+type PResult<'a, T> = IResult<Span<'a>, T, ErrorTree<Span<'a>>>;
+
+/// Parses a packet, keeping track of what it skipped over if it's lenient.
+struct PacketParser {
+    lenient: bool,
+    recovered: Vec<Recovered>,
+}
+
+impl PacketParser {
+    fn new(lenient: bool) -> Self {
+        Self {
+            lenient,
+            recovered: Vec::new(),
+        }
+    }
+
+    fn parse(mut self, line: &str) -> Result<(Node, Vec<Recovered>), PacketError> {
+        let line = match line.strip_suffix('\r') {
+            Some(stripped) => {
+                self.recover(stripped.len(), Issue::WindowsLineEnding);
+                stripped
+            }
+            None => line,
+        };
+
+        match self.packet(Span::new(line)).finish() {
+            Ok((_, packet)) => {
+                self.recovered.sort_by_key(|fix| fix.offset);
+                Ok((packet, self.recovered))
+            }
+            Err(e) => {
+                let (offset, reason) = furthest_span_error(&e);
+                Err(PacketError { offset, reason })
+            }
+        }
+    }
+
+    fn packet<'a>(&mut self, i: Span<'a>) -> PResult<'a, Node> {
+        let i = self.space(i);
+        let (i, packet) = context("a packet", |i| self.list(i))(i)?;
+        let i = self.space(i);
+        let (i, _) = context("the end of the line", eof)(i)?;
+        Ok((i, packet))
+    }
+
+    fn node<'a>(&mut self, i: Span<'a>) -> PResult<'a, Node> {
+        alt((map(nom_u64, Node::Number), |i| self.list(i)))(i)
+    }
+
+    fn list<'a>(&mut self, i: Span<'a>) -> PResult<'a, Node> {
+        let (i, _) = char('[')(i)?;
+        let mut i = self.space(i);
+        let mut items = Vec::new();
+        if let Ok((i, _)) = char::<_, ErrorTree<Span>>(']')(i) {
+            return Ok((i, Node::List(items)));
+        }
+
+        loop {
+            let (rest, item) = context("a number or a list", |i| self.node(i))(i)?;
+            items.push(item);
+            let rest = self.space(rest);
+            let (rest, separator) =
+                context("the rest of the list", alt((char(','), char(']'))))(rest)?;
+            if separator == ']' {
+                return Ok((rest, Node::List(items)));
+            }
+
+            let comma = rest.location_offset() - 1;
+            i = self.space(rest);
+            if self.lenient {
+                if let Ok((i, _)) = char::<_, ErrorTree<Span>>(']')(i) {
+                    self.recover(comma, Issue::TrailingComma);
+                    return Ok((i, Node::List(items)));
+                }
+            }
+        }
+    }
+
+    /// Skip over any whitespace, if being lenient.
+    fn space<'a>(&mut self, i: Span<'a>) -> Span<'a> {
+        if !self.lenient {
+            return i;
+        }
+        let (rest, space) = multispace0::<_, ErrorTree<Span>>(i).unwrap_or((i, i));
+        if !space.is_empty() {
+            self.recover(space.location_offset(), Issue::Whitespace);
+        }
+        rest
+    }
+
+    fn recover(&mut self, offset: usize, issue: Issue) {
+        self.recovered.push(Recovered { offset, issue });
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::TrailingComma => "skipped a trailing comma",
+            Self::Whitespace => "skipped some stray whitespace",
+            Self::WindowsLineEnding => "skipped a Windows line ending",
+        })
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_packets(self, other)
+    }
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::List(l) => f.debug_list().entries(l).finish(),
+        }
+    }
+}
@@ -1,353 +1,295 @@
 use std::{
+    collections::HashSet,
     fmt,
     io::BufRead,
-    ops::{Deref, DerefMut},
+    str::FromStr,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc, Mutex, MutexGuard,
+        Arc, Mutex,
     },
-    time::{Duration, Instant},
 };
 
-use color_eyre::eyre::Context;
-use eframe::emath;
 use genawaiter::rc::Gen;
-use miette::GraphicalReportHandler;
 use nom::{
     character::complete::{self as nom_cc, space0},
     combinator::map,
-    error::ParseError,
+    error::{ContextError, ParseError},
     multi::separated_list1,
-    sequence::{separated_pair, tuple},
+    sequence::tuple,
     IResult,
 };
-use nom_locate::LocatedSpan;
 use nom_supreme::{
-    error::{BaseErrorKind, ErrorTree, GenericErrorTree},
-    final_parser::final_parser,
+    error::ErrorTree,
     tag::{complete::tag, TagError},
 };
 use once_cell::sync::OnceCell;
+use serde_json::json;
 
-use crate::atomic::AtomicF32;
+use crate::{
+    error::{Context, SolverError},
+    glyphs,
+    parse::{parse_point2, BadInputError, PointFormat, Span},
+    simulation::{Simulation, StepResult},
+};
+
+use super::{
+    Answer, ChallengeSolver, EventStream, Frontend, ParamError, ParseStats, Rendering,
+    SolverConfig, SolverParams,
+};
 
-use super::ChallengeSolver;
+#[cfg(feature = "gui")]
+mod viewer;
 
 #[derive(Debug, Default)]
-pub struct Solver14;
+pub struct Solver14 {
+    /// Watch the sand fall in a GUI, instead of just printing the answer.
+    visualize: bool,
+    settings: Settings,
+    algorithm: Algorithm,
+    /// Where to send how much sand has settled after every tick, for `--stream-events`.
+    events: EventStream,
+}
+
+/// How to work out where the sand settles without a GUI. The viewer always steps through it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    /// Drop one grain at a time, starting each one from where the path of the grain before it
+    /// last branched, instead of from the top. With more than one spawn point, the sand falls in
+    /// [`Algorithm::Steps`] instead, since how much comes from each one depends on their grains
+    /// falling side by side.
+    #[default]
+    Path,
+    /// Move every falling grain down a cell at a time, like the viewer shows it.
+    Steps,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "path" => Ok(Self::Path),
+            "steps" => Ok(Self::Steps),
+            _ => Err("expected `path` or `steps`".to_string()),
+        }
+    }
+}
 
 impl ChallengeSolver for Solver14 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         14
     }
 
-    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let mut input_buf = String::new();
-        input
-            .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+    super::sample_methods!();
 
-        let input = Span::new(&input_buf);
+    fn algorithms(&self) -> &'static [&'static str] {
+        &["path", "steps"]
+    }
 
-        let polylines_res: Result<_, ErrorTree<Span>> =
-            final_parser(Polyline::parse_all::<ErrorTree<Span>>)(input);
+    fn validate(&self, input: &str) -> Option<Result<ParseStats, SolverError>> {
+        Some(parse_input(input).map_err(Into::into).map(|polylines| {
+            let points = || polylines.iter().flat_map(|pl| pl.points.iter());
+            ParseStats::default()
+                .entity("rock paths", polylines.len())
+                .entity("path points", points().count())
+                .bounds(points().map(|p| (p.x as i64, p.y as i64)))
+        }))
+    }
 
-        let mut polylines = match polylines_res {
-            Ok(polylines) => polylines,
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        self.settings = Settings::from_params(&config.params)?;
+        self.algorithm = config.params.get_or("algorithm", Algorithm::default())?;
+        self.events = config.events.clone();
+        Ok(())
+    }
 
-            Err(e) => {
-                match e {
-                    GenericErrorTree::Base { location, kind } => {
-                        let offset = location.location_offset().into();
-                        let err = BadInputError {
-                            src: &input_buf,
-                            bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                            kind,
-                        };
-                        let mut s = String::new();
-                        GraphicalReportHandler::new()
-                            .render_report(&mut s, &err)
-                            .unwrap();
-                        eprintln!("{s}");
-                    }
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Gui)
+    }
 
-                    GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-                    GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-                }
-                return Err(color_eyre::eyre::eyre!("Failed to parse input"));
-            }
+    fn gallery(&self, input: &str) -> Option<Result<Vec<Rendering>, SolverError>> {
+        let polylines = match parse_input(input) {
+            Ok(polylines) => polylines,
+            Err(e) => return Some(Err(e.into())),
         };
-
-        // Setup the simulation grid
-        let grid = Grid::new(&mut polylines, false);
-
-        // Start the eframe app
-        let native_options = eframe::NativeOptions::default();
-        eframe::run_native(
-            "AOC2022 C14A",
-            native_options,
-            Box::new(move |cc| Box::new(App::new(cc, grid))),
-        );
-
-        Ok(Box::new(()))
+        let draw = |name, floor| {
+            let grid = self.settings.grid(&mut polylines.clone(), floor);
+            let mut grid = Arc::into_inner(grid).expect("grid shouldn't be shared yet");
+            grid.run(None);
+            Rendering::text(name, format!("{grid:#?}"))
+        };
+        Some(Ok(vec![draw("sand-a", false), draw("sand-b", true)]))
     }
 
-    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+            .context("Could not read input file to string")?;
 
-        let input = Span::new(&input_buf);
+        let mut polylines = parse_input(&input_buf)
+            .context("Could not parse input file as a list of rock paths")?;
 
-        let polylines_res: Result<_, ErrorTree<Span>> =
-            final_parser(Polyline::parse_all::<ErrorTree<Span>>)(input);
+        #[cfg(feature = "gui")]
+        if self.visualize {
+            viewer::watch("AOC2022 C14A", polylines, self.settings.clone(), false);
+            return Ok(Answer::new(()));
+        }
 
-        let mut polylines = match polylines_res {
-            Ok(polylines) => polylines,
+        // Setup the simulation grid
+        let grid = self.settings.grid(&mut polylines, false);
+        run_headless(grid, self.algorithm, &self.events)
+    }
 
-            Err(e) => {
-                match e {
-                    GenericErrorTree::Base { location, kind } => {
-                        let offset = location.location_offset().into();
-                        let err = BadInputError {
-                            src: &input_buf,
-                            bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                            kind,
-                        };
-                        let mut s = String::new();
-                        GraphicalReportHandler::new()
-                            .render_report(&mut s, &err)
-                            .unwrap();
-                        eprintln!("{s}");
-                    }
+    fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let mut input_buf = String::new();
+        input
+            .read_to_string(&mut input_buf)
+            .context("Could not read input file to string")?;
 
-                    GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-                    GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-                }
-                return Err(color_eyre::eyre::eyre!("Failed to parse input"));
-            }
-        };
+        let mut polylines = parse_input(&input_buf)
+            .context("Could not parse input file as a list of rock paths")?;
 
-        // Setup the simulation grid
-        let grid = Grid::new(&mut polylines, true);
-
-        // Start the eframe app
-        let native_options = eframe::NativeOptions::default();
-        eframe::run_native(
-            "AOC2022 C14B",
-            native_options,
-            Box::new(move |cc| Box::new(App::new(cc, grid))),
-        );
+        #[cfg(feature = "gui")]
+        if self.visualize {
+            viewer::watch("AOC2022 C14B", polylines, self.settings.clone(), true);
+            return Ok(Answer::new(()));
+        }
 
-        Ok(Box::new(()))
+        // Setup the simulation grid
+        let grid = self.settings.grid(&mut polylines, true);
+        run_headless(grid, self.algorithm, &self.events)
     }
 }
 
-/// The main eframe app
-struct App {
+/// Run the simulation to completion without a GUI, returning the amount of settled sand.
+fn run_headless(
     grid: Arc<Grid>,
-    speed_factor: Arc<AtomicF32>,
-    simulation_running: Arc<AtomicBool>,
-}
-
-impl App {
-    fn new(_cc: &eframe::CreationContext<'_>, grid: Arc<Grid>) -> Self {
-        Self {
-            grid,
-            speed_factor: Arc::new(AtomicF32::new(1.0)),
-            simulation_running: Arc::new(AtomicBool::new(false)),
+    algorithm: Algorithm,
+    events: &EventStream,
+) -> super::ChallengeSolverResult {
+    let mut grid = Arc::into_inner(grid).expect("grid shouldn't be shared yet");
+    match algorithm {
+        Algorithm::Path if grid.spawns.len() == 1 => grid.fill(events)?,
+        _ => {
+            grid.run_streaming(None, events)?;
         }
     }
 
-    fn start_simulation(&self, ctx: egui::Context) -> Option<std::thread::JoinHandle<usize>> {
-        if self.simulation_running.load(Ordering::SeqCst) {
-            None
-        } else {
-            self.simulation_running.store(true, Ordering::SeqCst);
-
-            let base_rate = Duration::from_secs_f64(1.0 / 30.0);
+    let settled = grid.settled.load(Ordering::Relaxed);
+    println!("settled sand: {settled}");
 
-            let grid = Arc::clone(&self.grid);
-            let speed_factor = Arc::clone(&self.speed_factor);
-            let simulation_running = Arc::clone(&self.simulation_running);
-
-            grid.reset();
-
-            Some(std::thread::spawn(move || {
-                println!("Starting simulation");
-
-                let mut last_tick = Instant::now();
-
-                while simulation_running.load(Ordering::Relaxed) {
-                    let res = grid.step();
-                    ctx.request_repaint();
-
-                    if res {
-                        break;
-                    }
-
-                    let speed_factor = speed_factor.load(Ordering::Relaxed);
+    let emitters: Vec<_> = grid
+        .spawns
+        .iter()
+        .zip(&grid.settled_per_spawn)
+        .map(|(spawn, settled)| {
+            json!({ "spawn": [spawn.x, spawn.y], "settled": settled.load(Ordering::Relaxed) })
+        })
+        .collect();
 
-                    if speed_factor <= f32::EPSILON {
-                        std::thread::sleep(Duration::from_millis(500));
-                        continue;
-                    }
+    Ok(Answer::new(settled)
+        .with_metadata("metrics", grid.metrics())
+        .with_metadata("emitters", emitters))
+}
 
-                    let elapsed = last_tick.elapsed();
-                    let time_left_over =
-                        Duration::from_secs_f32(base_rate.as_secs_f32() / speed_factor)
-                            .checked_sub(elapsed);
-                    if let Some(t) = time_left_over {
-                        std::thread::sleep(t);
-                    }
-                    last_tick = Instant::now();
-                }
+/// Sand spawns at point (500, 0), unless other spawn points are given.
+const SAND_SPAWN: Point = Point { x: 500, y: 0 };
 
-                println!("Stopping simulation");
+/// Everything about the simulation that can be changed with `--param`, or from the viewer's side
+/// panel.
+#[derive(Debug, Clone)]
+struct Settings {
+    /// Where sand pours in from.
+    spawns: Spawns,
+    /// Whether there's a floor under the rocks, from `--param floor=true|false`. Part B has one
+    /// unless this says otherwise, and part A doesn't.
+    floor: Option<bool>,
+    /// How much faster than 30 steps a second the viewer starts out, from `--param speed=N`.
+    speed: f32,
+}
 
-                grid.settled.load(Ordering::Relaxed)
-            }))
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            spawns: Spawns::default(),
+            floor: None,
+            speed: 1.0,
         }
     }
+}
 
-    fn ui_controls(&mut self, ui: &mut egui::Ui, ctx: egui::Context) -> egui::Response {
-        ui.horizontal(|ui| {
-            let mut local_speed_factor = self.speed_factor.load(Ordering::Acquire);
-            ui.add(
-                egui::Slider::new(&mut local_speed_factor, 0.0..=32.0)
-                    .text("Speed")
-                    .suffix("x")
-                    .logarithmic(true)
-                    .smallest_positive(0.1),
-            );
-            self.speed_factor
-                .store(local_speed_factor, Ordering::Release);
-
-            ui.separator();
-            let simulation_running = self.simulation_running.load(Ordering::Relaxed);
-            if ui
-                .add_enabled(!simulation_running, egui::Button::new("▶"))
-                .clicked()
-            {
-                self.start_simulation(ctx);
-            }
-
-            if ui
-                .add_enabled(simulation_running, egui::Button::new("■"))
-                .clicked()
-            {
-                self.simulation_running.store(false, Ordering::Relaxed);
-            }
-
-            ui.separator();
-            ui.label(format!(
-                "Settled grains: {}",
-                self.grid.settled.load(Ordering::Relaxed)
-            ));
+impl Settings {
+    fn from_params(params: &SolverParams) -> Result<Self, ParamError> {
+        Ok(Self {
+            spawns: params.get_or("spawns", Spawns::default())?,
+            floor: params.get("floor")?,
+            speed: params.get_or("speed", 1.0)?,
         })
-        .response
     }
 
-    fn ui_canvas(&mut self, ui: &mut egui::Ui) -> egui::Response {
-        let grid_origin = *self.grid.origin.get().unwrap();
-
-        let (response, painter) = ui.allocate_painter(
-            ui.available_size_before_wrap(),
-            egui::Sense::focusable_noninteractive(),
-        );
-
-        let to_screen = emath::RectTransform::from_to(
-            egui::Rect::from_center_size(
-                (
-                    grid_origin.x as f32 + self.grid.width() as f32 / 2.0,
-                    grid_origin.y as f32 + self.grid.height() as f32 / 2.0,
-                )
-                    .into(),
-                (
-                    self.grid.width() as f32 * 1.1,
-                    self.grid.height() as f32 * 1.1,
-                )
-                    .into(),
-            ),
-            egui::Rect::from_center_size(
-                response.rect.center(),
-                emath::vec2(
-                    self.grid.aspect_ratio() * response.rect.height(),
-                    response.rect.height(),
-                ),
-            ),
-        );
-
-        for y in 0..self.grid.height() {
-            for x in 0..self.grid.width() {
-                let point = Point {
-                    x: x as _,
-                    y: y as _,
-                } + grid_origin;
-
-                let cell = self.grid.cell(point).unwrap();
-                let color = match cell {
-                    // don't actually draw air cells
-                    Cell::Air => {
-                        continue;
-                    }
-
-                    Cell::Rock => egui::Color32::from_rgb(165, 156, 145),
-                    Cell::Sand => egui::Color32::from_rgb(206, 201, 139),
-                };
-
-                painter.rect_filled(
-                    to_screen.transform_rect(egui::Rect::from_min_size(
-                        emath::pos2(point.x as _, point.y as _),
-                        emath::vec2(1.0, 1.0),
-                    )),
-                    0.0,
-                    color,
-                );
-            }
-        }
+    /// Set up a grid with `rock_walls` in it, with a floor if the settings or `part_floor` say so.
+    fn grid(&self, rock_walls: &mut Vec<Polyline>, part_floor: bool) -> Arc<Grid> {
+        Grid::new(rock_walls, self.floor.unwrap_or(part_floor), &self.spawns.0)
+    }
+}
 
-        {
-            let current_grains = self.grid.current_grains.lock().unwrap();
-            for point in current_grains.iter() {
-                painter.rect_filled(
-                    to_screen.transform_rect(egui::Rect::from_min_size(
-                        emath::pos2(point.x as _, point.y as _),
-                        emath::vec2(1.0, 1.0),
-                    )),
-                    4.0,
-                    egui::Color32::from_rgb(255, 193, 7),
-                );
-            }
-        }
+/// Every point that sand pours in from, given as `--param spawns=500,0;480,0`.
+#[derive(Debug, Clone)]
+struct Spawns(Vec<Point>);
 
-        response
+impl Default for Spawns {
+    fn default() -> Self {
+        Self(vec![SAND_SPAWN])
     }
 }
 
-impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            self.ui_controls(ui, ctx.clone());
-            egui::Frame::dark_canvas(ui.style()).show(ui, |ui| {
-                self.ui_canvas(ui);
-            });
-        });
+impl FromStr for Spawns {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(|point| {
+                let (x, y) = point
+                    .trim()
+                    .split_once(',')
+                    .ok_or_else(|| format!("expected a point like `500,0`, not `{point}`"))?;
+                Ok(Point {
+                    x: x.trim()
+                        .parse()
+                        .map_err(|e| format!("bad x in `{point}`: {e}"))?,
+                    y: y.trim()
+                        .parse()
+                        .map_err(|e| format!("bad y in `{point}`: {e}"))?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
     }
+}
 
-    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        self.simulation_running.store(false, Ordering::SeqCst);
+impl fmt::Display for Spawns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, point) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{point}")?;
+        }
+        Ok(())
     }
 }
 
-/// Sand spawns at point (500, 0)
-const SAND_SPAWN: Point = Point { x: 500, y: 0 };
-
-type Span<'a> = LocatedSpan<&'a str>;
+/// Parse the challenge input into a list of rock paths.
+///
+/// Any parsing errors will be printed out to `stderr` with fancy formatting.
+fn parse_input(input: &str) -> Result<Vec<Polyline>, BadInputError> {
+    crate::parse::parse_input(input, Polyline::parse_all::<ErrorTree<Span>>)
+}
 
 #[derive(
-    Copy, Clone, PartialEq, Eq, derive_more::Add, derive_more::AddAssign, derive_more::Sub,
+    Copy, Clone, PartialEq, Eq, Hash, derive_more::Add, derive_more::AddAssign, derive_more::Sub,
 )]
 struct Point {
     x: i32,
@@ -358,11 +300,11 @@ impl Point {
     /// Try to parse a point from a string like `-43,2`.
     fn parse<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Self, E>
     where
-        E: ParseError<Span<'a>> + TagError<Span<'a>, &'static str>,
+        E: ParseError<Span<'a>> + ContextError<Span<'a>> + TagError<Span<'a>, &'static str>,
     {
-        let (i, (x, y)) = separated_pair(nom_cc::i32, tag(","), nom_cc::i32)(i)?;
-
-        Ok((i, Self { x, y }))
+        map(parse_point2(PointFormat::COMMAS, nom_cc::i32), |(x, y)| {
+            Self { x, y }
+        })(i)
     }
 
     /// For each component (x, y) of self, returns a number representing the sign.
@@ -384,6 +326,13 @@ impl fmt::Debug for Point {
     }
 }
 
+/// Writes the point out like `-43,2`, the way [`Point::parse`] reads it.
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        PointFormat::COMMAS.write2(f, (self.x, self.y))
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Polyline {
     points: Vec<Point>,
@@ -393,7 +342,7 @@ impl Polyline {
     /// Try to parse a polyline from a list like `3,2 -> 6,2 -> 6,-3`
     fn parse<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Self, E>
     where
-        E: ParseError<Span<'a>> + TagError<Span<'a>, &'static str>,
+        E: ParseError<Span<'a>> + ContextError<Span<'a>> + TagError<Span<'a>, &'static str>,
     {
         map(
             separated_list1(tuple((space0, tag("->"), space0)), Point::parse),
@@ -404,7 +353,7 @@ impl Polyline {
     /// Parse all of the challenge input into a list of polylines
     fn parse_all<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Vec<Self>, E>
     where
-        E: ParseError<Span<'a>> + TagError<Span<'a>, &'static str>,
+        E: ParseError<Span<'a>> + ContextError<Span<'a>> + TagError<Span<'a>, &'static str>,
     {
         nom::sequence::terminated(
             separated_list1(nom_cc::newline, Self::parse),
@@ -437,6 +386,14 @@ impl Polyline {
     }
 }
 
+/// A grain of sand that's still falling.
+#[derive(Debug, Clone, Copy)]
+struct Grain {
+    pos: Point,
+    /// Which of [`Grid::spawns`] it came from.
+    emitter: usize,
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Cell {
     Air,
@@ -454,17 +411,23 @@ struct Grid {
     cells: Mutex<Vec<Cell>>,
     orig_cells: Mutex<Vec<Cell>>,
     settled: AtomicUsize,
-    current_grains: Mutex<Vec<Point>>,
+    /// Where sand pours in from, one grain per emitter every step.
+    spawns: Vec<Point>,
+    /// How much of the settled sand came from each of [`Grid::spawns`].
+    settled_per_spawn: Vec<AtomicUsize>,
+    current_grains: Mutex<Vec<Grain>>,
+    /// Set once a grain of sand has fallen out of the grid.
+    overflowed: AtomicBool,
 }
 
 impl Grid {
-    fn new(rock_walls: &mut Vec<Polyline>, with_floor: bool) -> Arc<Self> {
+    fn new(rock_walls: &mut Vec<Polyline>, with_floor: bool, spawns: &[Point]) -> Arc<Self> {
         let (mut min_x, mut min_y, mut max_x, mut max_y) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
 
         for point in rock_walls
             .iter()
             .flat_map(|pl| pl.points.iter())
-            .chain(std::iter::once(&SAND_SPAWN))
+            .chain(spawns)
         {
             min_x = min_x.min(point.x);
             min_y = min_y.min(point.y);
@@ -474,8 +437,13 @@ impl Grid {
 
         if with_floor {
             let floor_y = max_y + 2;
-            min_x = 300;
-            max_x = 700;
+            // Sand piles up at most as wide as it falls tall, so the floor only has to reach that
+            // far past each spawn point, plus a little room to spare.
+            for spawn in spawns {
+                let reach = floor_y - spawn.y + 1;
+                min_x = min_x.min(spawn.x - reach);
+                max_x = max_x.max(spawn.x + reach);
+            }
             max_y = floor_y;
             rock_walls.push(Polyline {
                 points: vec![
@@ -509,7 +477,10 @@ impl Grid {
             cells: Mutex::new(vec![Cell::Air; w * h]),
             orig_cells: Mutex::new(Vec::new()),
             settled: AtomicUsize::from(0),
+            spawns: spawns.to_vec(),
+            settled_per_spawn: spawns.iter().map(|_| AtomicUsize::from(0)).collect(),
             current_grains: Mutex::new(Vec::new()),
+            overflowed: AtomicBool::new(false),
         };
 
         for point in rock_walls.iter().flat_map(|pl| pl.path_points()) {
@@ -549,38 +520,6 @@ impl Grid {
         Some(&mut cells[idx])
     }
 
-    /// Get an _mutable_ reference to a value at some grid coordinate, **and**
-    /// a [`std::sync::MutexGuard`] that must be dropped at the same time as the
-    /// reference.
-    ///
-    /// As long as the [`std::sync::MutexGuard`] is held onto, any further calls
-    /// to this function will result in blocking the tread and potential deadlocks.
-    pub(crate) fn cell_mut_ref(&self, point: Point) -> Option<impl DerefMut<Target = Cell> + '_> {
-        let idx = self.cell_index(point)?;
-        let guard = self.cells.lock().ok()?;
-
-        struct CellMutexGuardBundle<'a> {
-            guard: MutexGuard<'a, Vec<Cell>>,
-            idx: usize,
-        }
-
-        impl<'a> Deref for CellMutexGuardBundle<'a> {
-            type Target = Cell;
-
-            fn deref(&self) -> &Self::Target {
-                &self.guard[self.idx]
-            }
-        }
-
-        impl<'a> DerefMut for CellMutexGuardBundle<'a> {
-            fn deref_mut(&mut self) -> &mut Self::Target {
-                &mut self.guard[self.idx]
-            }
-        }
-
-        Some(CellMutexGuardBundle { guard, idx })
-    }
-
     /// Get a copy of a value at some grid coordinate.
     ///
     /// Returns `None` if `coord` is out-of-bounds.
@@ -617,6 +556,10 @@ impl Grid {
         }
 
         self.settled.store(0, Ordering::Relaxed);
+        for settled in &self.settled_per_spawn {
+            settled.store(0, Ordering::Relaxed);
+        }
+        self.overflowed.store(false, Ordering::Relaxed);
 
         {
             let mut current_grains = self.current_grains.lock().unwrap();
@@ -626,53 +569,73 @@ impl Grid {
 
     /// Step the simulation.
     ///
-    /// Returns `true` if the simulation has completed.
+    /// Every spawn point that isn't blocked gets a new grain of sand. Returns `true` if the
+    /// simulation has completed, once every spawn point is blocked and no sand is falling.
     fn step(&self) -> bool {
-        if matches!(self.cell(Point { x: 500, y: 0 }).unwrap(), Cell::Sand) {
-            // don't step, we're done
-            return true;
-        }
-
         let mut current_grains = {
             let mut current_grains = self.current_grains.lock().unwrap();
             std::mem::take(&mut *current_grains)
         };
 
+        // Held for the whole step, rather than locked again for every cell that gets looked at.
+        let mut cells = self.cells.lock().unwrap();
+        let cell = |cells: &[Cell], pos| self.cell_index(pos).map(|idx| cells[idx]);
+        let is_open = |cells: &[Cell], pos| matches!(cell(cells, pos), Some(Cell::Air));
+
+        if current_grains.is_empty() && !self.spawns.iter().any(|&spawn| is_open(&cells, spawn)) {
+            // don't step, we're done
+            return true;
+        }
+
+        // Grains from different emitters can't fall into the same spot at once. A lone emitter's
+        // grains always stay a step apart, so there's no need to keep track of them.
+        let mut taken = (self.spawns.len() > 1).then(HashSet::new);
+        let mut claim = |pos| taken.as_mut().is_none_or(|taken| taken.insert(pos));
+
         let _ = VecExt::drain_filter(&mut current_grains, |grain| {
-            let straight_down = *grain + Point { x: 0, y: 1 };
-            let down_left = *grain + Point { x: -1, y: 1 };
-            let down_right = *grain + Point { x: 1, y: 1 };
+            let straight_down = grain.pos + Point { x: 0, y: 1 };
+            let down_left = grain.pos + Point { x: -1, y: 1 };
+            let down_right = grain.pos + Point { x: 1, y: 1 };
             let options = [straight_down, down_left, down_right];
 
             // Can we move?
-            if let Some(pos) = options
-                .into_iter()
-                .find(|pos| matches!(self.cell(*pos), Some(Cell::Air)))
-            {
-                *grain = pos;
+            if let Some(pos) = options.into_iter().find(|pos| is_open(&cells, *pos)) {
+                if claim(pos) {
+                    grain.pos = pos;
+                } else {
+                    // Wait for the other grain to get out of the way.
+                    claim(grain.pos);
+                }
                 return false; // keep it
             }
 
             // If not, are we moving off-screen?
-            if options.into_iter().any(|pos| self.cell(pos).is_none()) {
+            if options.into_iter().any(|pos| cell(&cells, pos).is_none()) {
+                self.overflowed.store(true, Ordering::Relaxed);
                 return true; // remove it
             }
 
             // If not, then we've settled
             self.settled.fetch_add(1, Ordering::Relaxed);
+            self.settled_per_spawn[grain.emitter].fetch_add(1, Ordering::Relaxed);
 
-            {
-                let mut cell_ref = self.cell_mut_ref(*grain).unwrap();
-                *cell_ref = Cell::Sand;
-            }
+            cells[self.cell_index(grain.pos).unwrap()] = Cell::Sand;
 
             // Remove it
             true
         })
         .count();
 
-        current_grains.push(SAND_SPAWN);
+        for (emitter, &spawn) in self.spawns.iter().enumerate() {
+            if is_open(&cells, spawn) && claim(spawn) {
+                current_grains.push(Grain {
+                    pos: spawn,
+                    emitter,
+                });
+            }
+        }
 
+        drop(cells);
         {
             let mut cg = self.current_grains.lock().unwrap();
             *cg = current_grains;
@@ -682,9 +645,104 @@ impl Grid {
     }
 }
 
+impl Grid {
+    /// Pour sand in from the only spawn point until it's blocked or sand falls out of the grid,
+    /// like stepping the simulation would.
+    ///
+    /// A grain falls along the same cells as the one before it, up until the cell that one
+    /// settled in, so the path that the last grain took is kept and the next grain starts from
+    /// the end of it. That way every cell is fallen into about once, rather than once for every
+    /// grain that falls past it. Every grain is sent to `events` as a step once it's done falling.
+    fn fill(&mut self, events: &EventStream) -> Result<(), SolverError> {
+        let [spawn] = self.spawns[..] else {
+            panic!(
+                "can only fill from one spawn point, not {}",
+                self.spawns.len()
+            );
+        };
+        let mut cells = std::mem::take(self.cells.get_mut().unwrap());
+        let is_open = |cells: &[Cell], pos| {
+            matches!(self.cell_index(pos).map(|idx| cells[idx]), Some(Cell::Air))
+        };
+
+        let mut path = Vec::new();
+        if is_open(&cells, spawn) {
+            path.push(spawn);
+        }
+        let start = if path.is_empty() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        };
+        events.emit(0, start, || self.metrics())?;
+
+        let mut grains = 0;
+        while let Some(&pos) = path.last() {
+            let options = [
+                pos + Point { x: 0, y: 1 },
+                pos + Point { x: -1, y: 1 },
+                pos + Point { x: 1, y: 1 },
+            ];
+            if let Some(next) = options.into_iter().find(|pos| is_open(&cells, *pos)) {
+                path.push(next);
+                continue;
+            }
+
+            grains += 1;
+            if options
+                .into_iter()
+                .any(|pos| self.cell_index(pos).is_none())
+            {
+                self.overflowed.store(true, Ordering::Relaxed);
+                events.emit(grains, StepResult::Done, || self.metrics())?;
+                break;
+            }
+
+            cells[self.cell_index(pos).unwrap()] = Cell::Sand;
+            self.settled.fetch_add(1, Ordering::Relaxed);
+            self.settled_per_spawn[0].fetch_add(1, Ordering::Relaxed);
+            path.pop();
+
+            let result = if path.is_empty() {
+                StepResult::Done
+            } else {
+                StepResult::Continue
+            };
+            events.emit(grains, result, || self.metrics())?;
+        }
+
+        *self.cells.get_mut().unwrap() = cells;
+        Ok(())
+    }
+}
+
+impl Simulation for Grid {
+    /// Step the simulation, finishing as soon as either a grain of sand falls out of the grid or
+    /// the sand spawn point gets blocked.
+    fn step(&mut self) -> StepResult {
+        if Grid::step(self) || self.overflowed.load(Ordering::Relaxed) {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn reset(&mut self) {
+        Grid::reset(self);
+    }
+
+    fn metrics(&self) -> serde_json::Value {
+        json!({
+            "settled": self.settled.load(Ordering::Relaxed),
+            "falling": self.current_grains.lock().unwrap().len(),
+        })
+    }
+}
+
 impl fmt::Debug for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if f.alternate() {
+            let glyphs = glyphs::glyphs();
             let width = self.width();
             let height = self.height();
             writeln!(
@@ -702,9 +760,9 @@ impl fmt::Debug for Grid {
                     } + *self.origin.get().unwrap();
                     let cell = self.cell(p).unwrap();
                     let glyph = match cell {
-                        Cell::Air => "░",
-                        Cell::Rock => "█",
-                        Cell::Sand => "○",
+                        Cell::Air => glyphs.shade,
+                        Cell::Rock => glyphs.full,
+                        Cell::Sand => glyphs.ball,
                     };
                     write!(f, "{glyph}")?;
                 }
@@ -725,13 +783,13 @@ impl fmt::Debug for Grid {
 trait VecExt<T> {
     /// The [new `drain_filter` iterator][Vec::drain_filter] from the standard library, currently
     /// only available in nightly.
-    fn drain_filter<F>(&mut self, filter: F) -> DrainFilter<T, F>
+    fn drain_filter<F>(&mut self, filter: F) -> DrainFilter<'_, T, F>
     where
         F: FnMut(&mut T) -> bool;
 }
 
 impl<T> VecExt<T> for Vec<T> {
-    fn drain_filter<F>(&mut self, filter: F) -> DrainFilter<T, F>
+    fn drain_filter<F>(&mut self, filter: F) -> DrainFilter<'_, T, F>
     where
         F: FnMut(&mut T) -> bool,
     {
@@ -806,14 +864,141 @@ where
     }
 }
 
-#[derive(thiserror::Error, Debug, miette::Diagnostic)]
-#[error("Error parsing input")]
-struct BadInputError<'a> {
-    #[source_code]
-    src: &'a str,
+super::challenge_solver_test_boilerplate! {
+    Solver14::default();
+    14 => {
+        a as usize,
+        b as usize,
+    }
+
+    use std::fmt::Write;
+
+    use proptest::prelude::*;
+
+    use crate::parse::round_trip::{self, EXTREME_I32};
+
+    #[test]
+    fn points_and_spawns_survive_being_written_and_parsed() {
+        let point = || any::<(i32, i32)>().prop_map(|(x, y)| Point { x, y });
+        let extremes = || {
+            EXTREME_I32
+                .into_iter()
+                .flat_map(|x| EXTREME_I32.into_iter().map(move |y| Point { x, y }))
+        };
+
+        round_trip::check(
+            point(),
+            extremes(),
+            |written, point| write!(written, "{point}"),
+            |written| crate::parse::parse_input(written, Point::parse).ok(),
+        );
+        round_trip::check(
+            prop::collection::vec(point(), 1..5),
+            [extremes().collect()],
+            |written, points| write!(written, "{}", Spawns(points.clone())),
+            |written| written.parse::<Spawns>().ok().map(|spawns| spawns.0),
+        );
+    }
+
+    #[test]
+    fn multiple_emitters() -> Result<(), SolverError> {
+        let mut config = SolverConfig::default();
+        config.params.insert("spawns", "500,0; 530,0");
+        let mut solver = Solver14::default();
+        solver.configure(&config).unwrap();
+
+        // Each emitter fills a triangle 5 rows tall on the floor, and the one at 500 has two rocks
+        // in the way.
+        let answer = solver.solve_b(&mut Cursor::new("500,3 -> 501,3\n"))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&48));
+        assert_eq!(
+            answer.metadata()["emitters"],
+            serde_json::json!([
+                { "spawn": [500, 0], "settled": 23 },
+                { "spawn": [530, 0], "settled": 25 },
+            ])
+        );
+
+        config.params.insert("spawns", "500");
+        assert!(solver.configure(&config).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn floor_is_as_wide_as_the_pile() -> Result<(), SolverError> {
+        // The floor is at y=212, so the pile reaches from x=288 to x=712, past where the floor
+        // used to end.
+        let answer = Solver14::default().solve_b(&mut Cursor::new("500,210 -> 501,210\n"))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&(212 * 212 - 2)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn floor_can_be_toggled() -> Result<(), SolverError> {
+        let mut config = SolverConfig::default();
+        config.params.insert("floor", "false");
+        let mut solver = Solver14::default();
+        solver.configure(&config).unwrap();
+
+        // Without its floor, part B is just part A again.
+        let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&24));
+
+        config.params.insert("floor", "true");
+        solver.configure(&config).unwrap();
+        let answer = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&93));
 
-    #[label("{kind}")]
-    bad_bit: miette::SourceSpan,
+        assert_eq!(solver.settings.spawns.to_string(), "500,0");
 
-    kind: BaseErrorKind<&'static str, Box<dyn std::error::Error + Send + Sync>>,
+        Ok(())
+    }
+
+    #[test]
+    fn both_algorithms_agree() -> Result<(), SolverError> {
+        let solve = |algorithm, input: &str, part_b| -> Result<usize, SolverError> {
+            let mut config = SolverConfig::default();
+            config.params.insert("algorithm", algorithm);
+            let mut solver = Solver14::default();
+            solver.configure(&config).unwrap();
+            let mut input = Cursor::new(input.to_string());
+            let answer = if part_b {
+                solver.solve_b(&mut input)?
+            } else {
+                solver.solve_a(&mut input)?
+            };
+            Ok(*answer.downcast_ref::<usize>().unwrap())
+        };
+
+        let walls = "490,20 -> 510,20\n495,8 -> 495,12 -> 505,12\n500,4 -> 502,4\n";
+        for input in [SAMPLE_INPUT, walls] {
+            for part_b in [false, true] {
+                assert_eq!(
+                    solve("path", input, part_b)?,
+                    solve("steps", input, part_b)?,
+                    "{input:?}, part B: {part_b}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_streams_a_step_for_every_grain() -> Result<(), SolverError> {
+        let (events, recording) = EventStream::recording();
+        let mut grid = Settings::default().grid(&mut parse_input(SAMPLE_INPUT)?, false);
+        Arc::get_mut(&mut grid).unwrap().fill(&events)?;
+
+        // 24 grains settle, and the 25th falls out of the bottom.
+        let events = recording.events();
+        assert_eq!(events.len(), 26);
+        assert!(events[..25].iter().all(|event| !event.done));
+        assert_eq!((events[25].step, events[25].done), (25, true));
+        assert_eq!(events[24].metrics["settled"], 24);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,259 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use eframe::emath;
+
+use crate::{atomic::AtomicF32, clock::SharedClock, param_panel::ParamPanel, solver::SolverParams};
+
+use super::{Cell, Grain, Grid, Point, Polyline, Settings};
+
+/// Watch the sand fall onto the rock paths in `polylines` in a window titled `title`, until it's
+/// closed.
+///
+/// The settings can be changed from a side panel, which starts the sand falling again on a new
+/// grid. `part_floor` says whether there's a floor when the settings don't say.
+pub(super) fn watch(title: &str, polylines: Vec<Polyline>, settings: Settings, part_floor: bool) {
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        title,
+        native_options,
+        Box::new(move |cc| Box::new(App::new(cc, polylines, settings, part_floor))),
+    );
+}
+
+/// The main eframe app
+struct App {
+    /// The rock paths from the input, kept around to build a new grid whenever the settings change.
+    polylines: Vec<Polyline>,
+    part_floor: bool,
+    grid: Arc<Grid>,
+    speed_factor: Arc<AtomicF32>,
+    /// Keeps the current simulation thread going. Every run gets a new flag, so that a thread
+    /// that's slow to notice it was stopped can't be picked back up by the next run.
+    simulation_running: Arc<AtomicBool>,
+    params: ParamPanel,
+    /// What the simulation thread paces itself with.
+    clock: SharedClock,
+}
+
+impl App {
+    fn new(
+        _cc: &eframe::CreationContext<'_>,
+        polylines: Vec<Polyline>,
+        settings: Settings,
+        part_floor: bool,
+    ) -> Self {
+        let params = ParamPanel::new()
+            .text("spawns", "Spawn points", &settings.spawns)
+            .toggle("floor", "Floor", settings.floor.unwrap_or(part_floor))
+            .slider("speed", "Speed", 0.0..=32.0, settings.speed);
+
+        Self {
+            grid: settings.grid(&mut polylines.clone(), part_floor),
+            polylines,
+            part_floor,
+            speed_factor: Arc::new(AtomicF32::new(settings.speed)),
+            simulation_running: Arc::new(AtomicBool::new(false)),
+            params,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Stop the simulation, and start it again from scratch with the settings in `params`.
+    fn restart(&mut self, params: &SolverParams, ctx: egui::Context) {
+        let settings = match Settings::from_params(params) {
+            Ok(settings) => settings,
+            Err(e) => {
+                self.params.set_error(Some(e.to_string()));
+                return;
+            }
+        };
+        self.params.set_error(None);
+
+        self.simulation_running.store(false, Ordering::SeqCst);
+        self.grid = settings.grid(&mut self.polylines.clone(), self.part_floor);
+        self.start_simulation(ctx);
+    }
+
+    fn start_simulation(&mut self, ctx: egui::Context) -> Option<std::thread::JoinHandle<usize>> {
+        if self.simulation_running.load(Ordering::SeqCst) {
+            None
+        } else {
+            self.simulation_running = Arc::new(AtomicBool::new(true));
+
+            let base_rate = Duration::from_secs_f64(1.0 / 30.0);
+
+            let grid = Arc::clone(&self.grid);
+            let speed_factor = Arc::clone(&self.speed_factor);
+            let simulation_running = Arc::clone(&self.simulation_running);
+            let clock = self.clock.clone();
+
+            grid.reset();
+
+            Some(std::thread::spawn(move || {
+                println!("Starting simulation");
+
+                let mut last_tick = clock.now();
+
+                while simulation_running.load(Ordering::Relaxed) {
+                    let res = grid.step();
+                    ctx.request_repaint();
+
+                    if res {
+                        break;
+                    }
+
+                    let speed_factor = speed_factor.load(Ordering::Relaxed);
+
+                    if speed_factor <= f32::EPSILON {
+                        clock.sleep(Duration::from_millis(500));
+                        continue;
+                    }
+
+                    let elapsed = clock.since(last_tick);
+                    let time_left_over =
+                        Duration::from_secs_f32(base_rate.as_secs_f32() / speed_factor)
+                            .checked_sub(elapsed);
+                    if let Some(t) = time_left_over {
+                        clock.sleep(t);
+                    }
+                    last_tick = clock.now();
+                }
+
+                println!("Stopping simulation");
+
+                grid.settled.load(Ordering::Relaxed)
+            }))
+        }
+    }
+
+    fn ui_controls(&mut self, ui: &mut egui::Ui, ctx: egui::Context) -> egui::Response {
+        ui.horizontal(|ui| {
+            let simulation_running = self.simulation_running.load(Ordering::Relaxed);
+            if ui
+                .add_enabled(!simulation_running, egui::Button::new("▶"))
+                .clicked()
+            {
+                self.start_simulation(ctx);
+            }
+
+            if ui
+                .add_enabled(simulation_running, egui::Button::new("■"))
+                .clicked()
+            {
+                self.simulation_running.store(false, Ordering::Relaxed);
+            }
+
+            ui.separator();
+            ui.label(format!(
+                "Settled grains: {}",
+                self.grid.settled.load(Ordering::Relaxed)
+            ));
+        })
+        .response
+    }
+
+    fn ui_canvas(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let grid_origin = *self.grid.origin.get().unwrap();
+
+        let (response, painter) = ui.allocate_painter(
+            ui.available_size_before_wrap(),
+            egui::Sense::focusable_noninteractive(),
+        );
+
+        let to_screen = emath::RectTransform::from_to(
+            egui::Rect::from_center_size(
+                (
+                    grid_origin.x as f32 + self.grid.width() as f32 / 2.0,
+                    grid_origin.y as f32 + self.grid.height() as f32 / 2.0,
+                )
+                    .into(),
+                (
+                    self.grid.width() as f32 * 1.1,
+                    self.grid.height() as f32 * 1.1,
+                )
+                    .into(),
+            ),
+            egui::Rect::from_center_size(
+                response.rect.center(),
+                emath::vec2(
+                    self.grid.aspect_ratio() * response.rect.height(),
+                    response.rect.height(),
+                ),
+            ),
+        );
+
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
+                let point = Point {
+                    x: x as _,
+                    y: y as _,
+                } + grid_origin;
+
+                let cell = self.grid.cell(point).unwrap();
+                let color = match cell {
+                    // don't actually draw air cells
+                    Cell::Air => {
+                        continue;
+                    }
+
+                    Cell::Rock => egui::Color32::from_rgb(165, 156, 145),
+                    Cell::Sand => egui::Color32::from_rgb(206, 201, 139),
+                };
+
+                painter.rect_filled(
+                    to_screen.transform_rect(egui::Rect::from_min_size(
+                        emath::pos2(point.x as _, point.y as _),
+                        emath::vec2(1.0, 1.0),
+                    )),
+                    0.0,
+                    color,
+                );
+            }
+        }
+
+        {
+            let current_grains = self.grid.current_grains.lock().unwrap();
+            for Grain { pos: point, .. } in current_grains.iter() {
+                painter.rect_filled(
+                    to_screen.transform_rect(egui::Rect::from_min_size(
+                        emath::pos2(point.x as _, point.y as _),
+                        emath::vec2(1.0, 1.0),
+                    )),
+                    4.0,
+                    egui::Color32::from_rgb(255, 193, 7),
+                );
+            }
+        }
+
+        response
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(params) = self.params.show(ctx) {
+            self.restart(&params, ctx.clone());
+        }
+        // The speed doesn't need a restart to change.
+        if let Ok(speed) = self.params.params().get_or("speed", 1.0) {
+            self.speed_factor.store(speed, Ordering::Release);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.ui_controls(ui, ctx.clone());
+            egui::Frame::dark_canvas(ui.style()).show(ui, |ui| {
+                self.ui_canvas(ui);
+            });
+        });
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.simulation_running.store(false, Ordering::SeqCst);
+    }
+}
@@ -1,67 +1,236 @@
 mod parse;
 
-use std::{collections::HashSet, io::BufRead, ops::RangeInclusive};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    io::BufRead,
+    ops::RangeInclusive,
+    str::FromStr,
+};
 
-use color_eyre::eyre::Context;
 use itertools::Itertools;
 
+use crate::{
+    error::{Context, SolverError},
+    geometry::{self, Diamond, Square},
+};
+
 use self::parse::{Point, Record};
 
-use super::ChallengeSolver;
+use super::{
+    AnswerDisplay, ChallengeSolver, ParamError, ParseStats, Preset, SolverConfig, SolverParams,
+};
+
+#[derive(Debug)]
+pub struct Solver15 {
+    /// The row to count impossible beacon positions in, for part A.
+    row: i64,
+    /// Several rows to count impossible beacon positions in at once, for part A, given as
+    /// `--param rows=10,2000000`. Replaces `row` if given.
+    rows: Option<Rows>,
+    /// The largest x- or y-coordinate that the missing beacon can have, for part B.
+    max: i64,
+    algorithm: Algorithm,
+}
+
+impl Default for Solver15 {
+    fn default() -> Self {
+        Self {
+            row: 2_000_000,
+            rows: None,
+            max: 4_000_000,
+            algorithm: Algorithm::default(),
+        }
+    }
+}
+
+/// How to search for the missing beacon in part B.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    /// Check the sensors' coverage of every row, one at a time.
+    #[default]
+    Scan,
+    /// Look for gaps between the edges of the sensors' coverage, in rotated coordinates.
+    Rotated,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scan" => Ok(Self::Scan),
+            "rotated" => Ok(Self::Rotated),
+            _ => Err("expected `scan` or `rotated`".to_string()),
+        }
+    }
+}
+
+/// The rows to count impossible beacon positions in, given as `--param rows=10,2000000`.
+#[derive(Debug, Clone)]
+struct Rows(Vec<i64>);
+
+impl FromStr for Rows {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|row| {
+                row.trim()
+                    .parse()
+                    .map_err(|e| format!("bad row `{row}`: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+/// How many impossible beacon positions there are in each of several rows, for part A with
+/// `--param rows=...`.
+#[derive(Debug, PartialEq)]
+struct RowCounts(BTreeMap<i64, usize>);
+
+/// Each row on its own line, and as an object from row to count in JSON.
+impl AnswerDisplay for RowCounts {
+    fn fmt_answer(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row, count) in &self.0 {
+            writeln!(f, "row {row}: {count}")?;
+        }
+        Ok(())
+    }
 
-#[derive(Debug, Default)]
-pub struct Solver15;
+    fn fmt_short(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .0
+            .iter()
+            .map(|(row, count)| format!("row {row}: {count}"));
+        write!(f, "{}", rows.format(", "))
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        self.0
+            .iter()
+            .map(|(row, count)| (row.to_string(), (*count).into()))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
 
 impl ChallengeSolver for Solver15 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         15
     }
 
+    super::sample_methods!();
+
+    fn algorithms(&self) -> &'static [&'static str] {
+        &["scan", "rotated"]
+    }
+
+    fn preset(&self, preset: Preset) -> SolverParams {
+        let defaults = Self::default();
+        let (row, max) = match preset {
+            Preset::Sample => (10, 20),
+            Preset::Real => (defaults.row, defaults.max),
+        };
+
+        let mut params = SolverParams::default();
+        params.insert("row", row.to_string());
+        params.insert("max", max.to_string());
+        params
+    }
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        let defaults = Self::default();
+        self.row = config.params.get_or("row", defaults.row)?;
+        self.rows = config.params.get("rows")?;
+        self.max = config.params.get_or("max", defaults.max)?;
+        self.algorithm = config.params.get_or("algorithm", defaults.algorithm)?;
+        Ok(())
+    }
+
+    fn validate(&self, input: &str) -> Option<Result<ParseStats, SolverError>> {
+        Some(Map::parse(input).map(|map| {
+            let beacons = map.records.iter().map(|r| r.beacon).unique().count();
+            ParseStats::default()
+                .entity("sensors", map.records.len())
+                .entity("distinct beacons", beacons)
+                .bounds(
+                    map.records
+                        .iter()
+                        .flat_map(|r| [r.sensor, r.beacon])
+                        .map(|p| (p.x, p.y)),
+                )
+        }))
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+            .context("Could not read input file to string")?;
 
         let map = Map::parse(&input_buf)?;
         map.dump();
 
-        let y = 2_000_000;
-        dbg!(map.num_impossible_beacon_positions(y));
+        if let Some(Rows(rows)) = &self.rows {
+            let counts = map.impossible_beacon_positions_by_row(rows);
+            for (row, impossible) in &counts {
+                println!("impossible beacon positions in row {row} = {impossible}");
+            }
+            return Ok(super::Answer::new(RowCounts(counts)));
+        }
 
-        Ok(Box::new(()))
+        let impossible = map.num_impossible_beacon_positions(self.row);
+        println!(
+            "impossible beacon positions in row {} = {impossible}",
+            self.row
+        );
+
+        Ok(super::Answer::new(impossible))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+            .context("Could not read input file to string")?;
 
         let map = Map::parse(&input_buf)?;
         map.dump();
 
-        let y = 2_000_000;
-        dbg!(map.num_impossible_beacon_positions(y));
-
-        let range = 0..=4_000_000;
-        let bp = map.beacon_position(&range, &range).unwrap();
+        let range = 0..=self.max;
+        let bp = match self.algorithm {
+            Algorithm::Scan => map.beacon_position(&range, &range),
+            Algorithm::Rotated => map.beacon_position_rotated(&range, &range),
+        }
+        .ok_or_else(|| {
+            SolverError::invalid_input("Every position in the search area is covered by a sensor")
+        })?;
         dbg!(bp);
 
-        println!("tuning frequency = {}", bp.x * 4_000_000 + bp.y);
+        let tuning_frequency = bp.x * 4_000_000 + bp.y;
+        println!("tuning frequency = {tuning_frequency}");
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(tuning_frequency)
+            .with_metadata("x", bp.x)
+            .with_metadata("y", bp.y))
     }
 }
 
 struct Map {
     records: Vec<Record>,
+    /// What each of the sensors in `records` covers, worked out once instead of for every row.
+    coverages: Vec<Diamond>,
 }
 
 impl Map {
-    fn parse(input: &str) -> Result<Self, MapError> {
+    fn parse(input: &str) -> Result<Self, SolverError> {
+        let records: Vec<Record> = parse::parse_input(input)
+            .context("Error parsing challenge input while building map")?;
         Ok(Self {
-            records: parse::parse_input(input)?,
+            coverages: records.iter().map(Record::coverage).collect(),
+            records,
         })
     }
 
@@ -74,23 +243,11 @@ impl Map {
 
     /// Returns a sorted iterator through all coverage ranges with a particular y-coordinate.
     fn ranges(&self, y: i64) -> impl Iterator<Item = RangeInclusive<i64>> {
-        let mut ranges = Vec::new();
-        for rec in &self.records {
-            let radius = rec.sensor.manhattan_dist(rec.beacon);
-            let y_dist = (y - rec.sensor.y).abs();
-
-            if y_dist > radius {
-                // coverage area doesn't touch line at `y`
-                continue;
-            }
-
-            let d = radius - y_dist;
-            let middle = rec.sensor.x;
-            let start = middle - d;
-            let end = middle + d;
-            let range = start..=end;
-            ranges.push(range);
-        }
+        let mut ranges = self
+            .coverages
+            .iter()
+            .filter_map(|coverage| coverage.row(y))
+            .collect::<Vec<_>>();
         ranges.sort_unstable_by_key(|r| *r.start());
 
         ranges.into_iter().coalesce(|a, b| {
@@ -126,24 +283,41 @@ impl Map {
 
     /// Return the number of impossible beacon positions with a particular y-coordinate.
     fn num_impossible_beacon_positions(&self, y: i64) -> usize {
-        let beacon_x_coords = self
-            .records
-            .iter()
-            .filter(|rec| rec.beacon.y == y)
-            .map(|rec| rec.beacon.x)
-            .collect::<HashSet<_>>();
-
-        self.ranges(y)
-            .map(|r| {
-                let range_size = (r.end() - r.start() + 1) as usize;
-                let num_beacons_in_range = beacon_x_coords.iter().filter(|x| r.contains(x)).count();
-                range_size - num_beacons_in_range
+        self.impossible_beacon_positions_by_row(&[y])[&y]
+    }
+
+    /// Return the number of impossible beacon positions in each of several rows.
+    fn impossible_beacon_positions_by_row(&self, rows: &[i64]) -> BTreeMap<i64, usize> {
+        let mut beacon_x_coords: HashMap<i64, HashSet<i64>> = HashMap::new();
+        for rec in &self.records {
+            beacon_x_coords
+                .entry(rec.beacon.y)
+                .or_default()
+                .insert(rec.beacon.x);
+        }
+
+        rows.iter()
+            .map(|&y| {
+                let beacons = beacon_x_coords.get(&y);
+                let impossible = self
+                    .ranges(y)
+                    .map(|r| {
+                        let range_size = (r.end() - r.start() + 1) as usize;
+                        let num_beacons_in_range = beacons
+                            .into_iter()
+                            .flatten()
+                            .filter(|x| r.contains(x))
+                            .count();
+                        range_size - num_beacons_in_range
+                    })
+                    .sum::<usize>();
+                (y, impossible)
             })
-            .sum::<usize>()
+            .collect()
     }
 
-    // Return the position of a missing beacon, where its coordinates (x, y) are within
-    // some range.
+    /// Return the position of a missing beacon, where its coordinates (x, y) are within
+    /// some range.
     fn beacon_position(
         &self,
         x_range: &RangeInclusive<i64>,
@@ -158,13 +332,152 @@ impl Map {
                 })
         })
     }
+
+    /// Like [`Map::beacon_position`], but without looking at every row.
+    ///
+    /// Rotating by 45° turns each sensor's coverage into an axis-aligned square. A lone uncovered
+    /// position has to be boxed in by those squares, so it sits in a one-wide gap between the
+    /// edges of two squares in both the `u` and `v` directions. Finding those gaps only takes
+    /// looking at each pair of sensors.
+    fn beacon_position_rotated(
+        &self,
+        x_range: &RangeInclusive<i64>,
+        y_range: &RangeInclusive<i64>,
+    ) -> Option<Point> {
+        let squares = self
+            .coverages
+            .iter()
+            .map(Diamond::rotated)
+            .collect::<Vec<_>>();
+
+        let gaps = |side: fn(&Square) -> (i64, i64)| {
+            squares
+                .iter()
+                .cartesian_product(&squares)
+                .filter(|(a, b)| side(a).1 + 2 == side(b).0)
+                .map(|(a, _)| side(a).1 + 1)
+                .unique()
+                .collect::<Vec<_>>()
+        };
+
+        let is_candidate = |&(x, y): &geometry::Point| {
+            x_range.contains(&x)
+                && y_range.contains(&y)
+                && !squares
+                    .iter()
+                    .any(|sq| sq.contains(geometry::rotate((x, y))))
+        };
+
+        gaps(|sq| sq.u)
+            .into_iter()
+            .cartesian_product(gaps(|sq| sq.v))
+            .filter_map(geometry::unrotate)
+            .find(is_candidate)
+            .or_else(|| {
+                // Near the edges of the search area, the edges themselves can do some of the
+                // boxing in. Fall back on checking everywhere just out of reach of a sensor.
+                self.coverages
+                    .iter()
+                    .flat_map(|d| Diamond::new(d.center, d.radius + 1).perimeter())
+                    .find(is_candidate)
+            })
+            .map(Point::from)
+    }
 }
 
-#[derive(thiserror::Error, Debug)]
-enum MapError {
-    #[error("Error parsing challenge input while building map")]
-    ParseError {
-        #[from]
-        source: parse::ParseInputError,
-    },
+super::challenge_solver_test_boilerplate! {
+    Solver15 {
+        row: 10,
+        max: 20,
+        ..Default::default()
+    };
+    15 => {
+        a as usize,
+        b as i64,
+    }
+
+    use std::fmt::Write;
+
+    use proptest::prelude::*;
+
+    use crate::parse::round_trip::{self, EXTREME_I64};
+
+    #[test]
+    fn records_survive_being_written_and_parsed() {
+        let extremes = EXTREME_I64
+            .into_iter()
+            .flat_map(|x| EXTREME_I64.into_iter().map(move |y| (x, y)))
+            .map(|(x, y)| (Point { x, y }, Point { x: y, y: x }));
+
+        round_trip::check(
+            any::<(i64, i64, i64, i64)>()
+                .prop_map(|(sx, sy, bx, by)| (Point { x: sx, y: sy }, Point { x: bx, y: by })),
+            extremes,
+            |written, &(sensor, beacon)| writeln!(written, "{}", Record { sensor, beacon }),
+            |written| {
+                let records = parse::parse_input(written).ok()?;
+                Some((records[0].sensor, records[0].beacon))
+            },
+        );
+    }
+
+    #[test]
+    fn rotated_search_finds_the_same_beacon() {
+        let map = Map::parse(SAMPLE_INPUT).unwrap();
+        let range = 0..=20;
+        let expected = Some(Point { x: 14, y: 11 });
+        assert_eq!(map.beacon_position(&range, &range), expected);
+        assert_eq!(map.beacon_position_rotated(&range, &range), expected);
+
+        // With a single sensor there aren't any gaps between sensors, so this needs the
+        // fallback. The only position out of the sensor's reach is the corner at (0, 0).
+        let map = Map::parse("Sensor at x=3, y=3: closest beacon is at x=3, y=8\n").unwrap();
+        let range = 0..=4;
+        assert_eq!(
+            map.beacon_position_rotated(&range, &range),
+            Some(Point { x: 0, y: 0 })
+        );
+    }
+
+    #[test]
+    fn counts_several_rows_at_once() -> Result<(), SolverError> {
+        let mut solver = Solver15::default();
+        let mut config = SolverConfig::default();
+        config.params.insert("rows", "9, 10,11");
+        solver.configure(&config).unwrap();
+        let answer = solver.solve_a(&mut SAMPLE_INPUT.as_bytes())?;
+
+        let map = Map::parse(SAMPLE_INPUT)?;
+        let expected: BTreeMap<_, _> = [9, 10, 11]
+            .map(|row| (row, map.num_impossible_beacon_positions(row)))
+            .into();
+        assert_eq!(expected[&10], 26);
+        assert_eq!(answer, super::super::Answer::new(RowCounts(expected)));
+        assert_eq!(
+            answer.to_json()["answer"],
+            serde_json::json!({ "9": 25, "10": 26, "11": 28 })
+        );
+
+        config.params.insert("rows", "10,eleven");
+        assert!(solver.configure(&config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn presets_pick_the_row() -> Result<(), ParamError> {
+        let mut solver = Solver15::default();
+
+        solver.configure(&solver.sample_config())?;
+        assert_eq!((solver.row, solver.max), (10, 20));
+
+        let mut config = SolverConfig {
+            params: solver.preset(Preset::Real),
+            ..SolverConfig::default()
+        };
+        config.params.insert("max", "100");
+        solver.configure(&config)?;
+        assert_eq!((solver.row, solver.max), (2_000_000, 100));
+
+        Ok(())
+    }
 }
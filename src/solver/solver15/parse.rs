@@ -1,79 +1,27 @@
 use std::fmt;
 
-use miette::GraphicalReportHandler;
 use nom::{
-    character::complete::{self as nom_cc, multispace0, multispace1, space0},
+    character::complete as nom_cc,
     combinator::map,
-    error::ParseError,
-    sequence::{preceded, separated_pair, tuple},
-    IResult, Parser,
+    error::{ContextError, ParseError},
+    sequence::{preceded, separated_pair},
+    IResult,
 };
-use nom_locate::LocatedSpan;
 use nom_supreme::{
-    error::{BaseErrorKind, ErrorTree, GenericErrorTree},
-    final_parser::final_parser,
-    multi::collect_separated_terminated,
+    error::ErrorTree,
     tag::{complete::tag, TagError},
-    ParserExt,
 };
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+use crate::{
+    geometry::Diamond,
+    parse::{parse_point2, spanned, BadInputError, PointFormat, Span},
+};
 
-/// Parse the challenge input into a vector of [`Record`]s.
+/// Parse the challenge input into a vector of [`Record`]s, one per line.
 ///
-/// Any parsing errors will be printed out to `stderr` with fancy formatting.
-pub fn parse_input(input: &str) -> Result<Vec<Record>, ParseInputError> {
-    let input_span = Span::new(input);
-
-    let records_res: Result<_, ErrorTree<Span>> =
-        final_parser(Record::parse_all::<ErrorTree<Span>>)(input_span);
-
-    match records_res {
-        Ok(records) => Ok(records),
-
-        Err(e) => match e {
-            GenericErrorTree::Base { location, kind } => {
-                let offset = location.location_offset().into();
-                let err = BadInputError {
-                    src: input.to_string(),
-                    bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                    kind,
-                };
-
-                let mut s = String::new();
-                GraphicalReportHandler::new()
-                    .render_report(&mut s, &err)
-                    .unwrap();
-                eprintln!("{s}");
-
-                Err(err.into())
-            }
-
-            GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-            GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-        },
-    }
-}
-
-#[derive(thiserror::Error, Debug, miette::Diagnostic)]
-#[error("Error parsing input")]
-pub struct BadInputError {
-    #[source_code]
-    src: String,
-
-    #[label("{kind}")]
-    bad_bit: miette::SourceSpan,
-
-    kind: BaseErrorKind<&'static str, Box<dyn std::error::Error + Send + Sync>>,
-}
-
-#[derive(thiserror::Error, Debug)]
-pub enum ParseInputError {
-    #[error("Failed to parse input due to bad input")]
-    BadInputError {
-        #[from]
-        source: BadInputError,
-    },
+/// Every bad line will be printed out to `stderr` with fancy formatting.
+pub fn parse_input(input: &str) -> Result<Vec<Record>, BadInputError> {
+    crate::parse::parse_lines_all(input, spanned(Record::parse::<ErrorTree<Span>>))
 }
 
 #[derive(Debug)]
@@ -84,36 +32,50 @@ pub struct Record {
     pub beacon: Point,
 }
 
+/// Writes the record out the way it is in the puzzle input, for [`Record::parse`] to read back in.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Sensor at ")?;
+        PointFormat::LABELLED.write2(f, (self.sensor.x, self.sensor.y))?;
+        f.write_str(": closest beacon is at ")?;
+        PointFormat::LABELLED.write2(f, (self.beacon.x, self.beacon.y))
+    }
+}
+
 impl Record {
-    /// Parse multiple newline-seperated records into a vector.
-    pub fn parse_all<'a, E: ParseError<Span<'a>> + TagError<Span<'a>, &'static str>>(
-        i: Span<'a>,
-    ) -> IResult<Span<'a>, Vec<Self>, E> {
-        collect_separated_terminated(
-            Self::parse,
-            multispace1,
-            tuple((multispace0, Self::parse.peek().not())),
+    /// Everything that the sensor has ruled out: all points at least as close to it as its beacon.
+    pub fn coverage(&self) -> Diamond {
+        Diamond::new(
+            (self.sensor.x, self.sensor.y),
+            self.sensor.manhattan_dist(self.beacon),
         )
-        .parse(i)
     }
 
     /// Parses a record, including sensor location and beacon location.
     ///
     /// # Examples
     ///
-    /// Basic usage:
+    /// Records aren't public, but validating an input parses every record in it, and reports
+    /// where the sensors and beacons are:
     ///
     /// ```
-    /// let (_, record) = Point::parse(Span::new(
-    ///     "Sensor at x=272, y=1998931: closest beacon is at x=10411, y=2000000"
-    /// )).unwrap();
+    /// use aoc2022::prelude::*;
     ///
-    /// assert_eq!(record.sensor, Point { x: 272, y: 1998931 });
-    /// assert_eq!(record.beacon, Point { x: 10411, y: 2000000 });
+    /// let report = Solver::new().validate_str(
+    ///     15,
+    ///     "Sensor at x=272, y=1998931: closest beacon is at x=10411, y=2000000\n",
+    /// )?;
+    /// let stats = report.parsed.unwrap()?;
+    ///
+    /// assert_eq!(stats.entities[0], ("sensors", 1));
+    /// let bounds = stats.bounds.unwrap();
+    /// assert_eq!((bounds.min, bounds.max), ((272, 1998931), (10411, 2000000)));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn parse<'a, E: ParseError<Span<'a>> + TagError<Span<'a>, &'static str>>(
-        i: Span<'a>,
-    ) -> IResult<Span<'a>, Self, E> {
+    pub fn parse<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Self, E>
+    where
+        E: ParseError<Span<'a>> + ContextError<Span<'a>> + TagError<Span<'a>, &'static str>,
+    {
         map(
             separated_pair(
                 preceded(tag("Sensor at "), Point::parse),
@@ -136,23 +98,23 @@ impl Point {
     ///
     /// # Examples
     ///
-    /// Basic usage:
+    /// Points are parsed with [`parse_point2`], which is public:
     ///
     /// ```
-    /// let (_, point) = Point::parse(Span::new("x=-3, y=42")).unwrap();
-    /// assert_eq!(point, Point { x: -3, y: 42 });
+    /// use aoc2022::prelude::*;
+    ///
+    /// let point = parse_input(
+    ///     "x=-3, y=42",
+    ///     parse_point2(PointFormat::LABELLED, nom::character::complete::i64),
+    /// )?;
+    /// assert_eq!(point, (-3, 42));
+    /// # Ok::<(), BadInputError>(())
     /// ```
-    pub fn parse<'a, E: ParseError<Span<'a>> + TagError<Span<'a>, &'static str>>(
-        i: Span<'a>,
-    ) -> IResult<Span<'a>, Self, E> {
-        map(
-            separated_pair(
-                preceded(tag("x="), nom_cc::i64),
-                tuple((tag(","), space0)),
-                preceded(tag("y="), nom_cc::i64),
-            ),
-            |(x, y)| Self { x, y },
-        )(i)
+    pub fn parse<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Self, E>
+    where
+        E: ParseError<Span<'a>> + ContextError<Span<'a>> + TagError<Span<'a>, &'static str>,
+    {
+        map(parse_point2(PointFormat::LABELLED, nom_cc::i64), Self::from)(i)
     }
 
     /// Calculate the [Manhattan Distance][wiki] between two points.
@@ -1,98 +1,414 @@
-use std::{collections::HashMap, io::BufRead};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::BufRead,
+    str::FromStr,
+};
 
-use color_eyre::eyre::Context;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use self::{
+    joint::JointSearch,
     namemap::NameMap,
     parse::{Name, Valve},
 };
 
-use super::ChallengeSolver;
+use crate::{
+    error::{Context, SolverError},
+    sync::{CancellationToken, Cancelled},
+};
+
+use super::{
+    checkpoint, Answer, CacheableParse, ChallengeSolver, Checkpointer, ParamError, ParseCache,
+    ParseStats, Scrambler, SolverConfig, Transform, TransformableInput,
+};
 
+mod joint;
 mod namemap;
 mod parse;
 
 #[derive(Debug, Default)]
-pub struct Solver16;
+pub struct Solver16 {
+    /// Include the order that valves are opened in with the answer.
+    show_plan: bool,
+    /// Print how much searching it took, and include it with the answer.
+    show_stats: bool,
+    algorithm: Algorithm,
+    cancel: CancellationToken,
+    /// Saves the search's progress as it goes.
+    checkpoint: Checkpointer,
+    /// Keeps the network between runs, since finding every path through it takes a while.
+    parse_cache: ParseCache,
+    /// Changes to make to the valves before searching them, from `--transform`.
+    transforms: Vec<Transform>,
+}
+
+/// How to split up the valves between the human and the elephant in part B.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    /// Find the best plan for every set of valves on its own, then pick the best pair of plans
+    /// that don't share any valves.
+    #[default]
+    Partition,
+    /// Search through both of them moving at once, handing out valves as they go. Can't be
+    /// resumed from a checkpoint.
+    Joint,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "partition" => Ok(Self::Partition),
+            "joint" => Ok(Self::Joint),
+            _ => Err("expected `partition` or `joint`".to_string()),
+        }
+    }
+}
 
 impl ChallengeSolver for Solver16 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         16
     }
 
+    super::sample_methods!();
+
+    fn algorithms(&self) -> &'static [&'static str] {
+        &["partition", "joint"]
+    }
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.show_plan = config.params.get_or("plan", false)?;
+        self.show_stats = config.params.get_or("stats", false)?;
+        self.algorithm = config.params.get_or("algorithm", Algorithm::default())?;
+        self.cancel = config.cancel.clone();
+        self.checkpoint = config.checkpoint.clone();
+        self.parse_cache = config.parse_cache.clone();
+        self.transforms = config.transforms.clone();
+        Ok(())
+    }
+
+    fn transformable(&self) -> bool {
+        true
+    }
+
+    fn validate(&self, input: &str) -> Option<Result<ParseStats, SolverError>> {
+        Some(parse::parse_input(input).map_err(Into::into).map(|valves| {
+            let working = valves.iter().filter(|v| v.flow > 0).count();
+            let tunnels = valves.iter().map(|v| v.links.len()).sum();
+            ParseStats::default()
+                .entity("valves", valves.len())
+                .entity("valves with flow", working)
+                .entity("tunnels", tunnels)
+        }))
+    }
+
+    fn scramble(
+        &self,
+        input: &str,
+        scrambler: &mut Scrambler,
+    ) -> Option<Result<String, SolverError>> {
+        Some(
+            parse::parse_input(input)
+                .map_err(Into::into)
+                .map(|valves| scramble(valves, scrambler)),
+        )
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+            .context("Could not read input file to string")?;
+        let input_buf = self.transformed(input_buf)?;
 
-        let net = Network::new(&input_buf)?;
-        let state = State {
-            net: &net,
-            position: Name(*b"AA"),
-            max_turns: 30,
-            turn: 0,
-            pressure: 0,
-            open_valves: Default::default(),
-        };
+        let net = self.parse_cache.parse::<Self>(16, &input_buf)?;
+        let search = self.search(&net, &input_buf, 30)?;
 
-        let mut best = Best::default();
-        let state = state.apply_best_moves(&mut best);
-        println!("final_pressure = {}", state.pressure);
+        let (pressure, plan) = search
+            .best
+            .values()
+            .max_by_key(|(pressure, _)| *pressure)
+            .unwrap();
+        println!("final_pressure = {pressure}");
 
-        Ok(Box::new(()))
+        let mut answer = Answer::new(*pressure);
+        if self.show_plan {
+            answer = answer.with_metadata("plan", plan_json(plan, "human"));
+        }
+        Ok(self.with_stats(answer, search.stats()))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
+            .context("Could not read input file to string")?;
+        let input_buf = self.transformed(input_buf)?;
 
-        let net = Network::new(&input_buf)?;
-        let state = State {
-            net: &net,
-            position: Name(*b"AA"),
-            max_turns: 26,
-            turn: 0,
-            pressure: 0,
-            open_valves: Default::default(),
+        let net = self.parse_cache.parse::<Self>(16, &input_buf)?;
+        let (best_pressure, human, elephant, stats) = match self.algorithm {
+            Algorithm::Partition => self.partition(&net, &input_buf, 26)?,
+            Algorithm::Joint => self.joint_search(&net, 26)?,
         };
 
-        let mut best = Best::default();
-        state.apply_best_moves(&mut best);
+        println!("final_pressure = {best_pressure}");
+
+        let mut answer = Answer::new(best_pressure);
+        if self.show_plan {
+            let mut plan = plan_json(&human, "human");
+            plan.extend(plan_json(&elephant, "elephant"));
+            plan.sort_by_key(|step| step["minute"].as_u64());
+            answer = answer.with_metadata("plan", plan);
+        }
+        Ok(self.with_stats(answer, stats))
+    }
+}
+
+impl Solver16 {
+    /// The input with any `--transform`s applied, written back out, so that the parse cache and
+    /// checkpoints go by the valves that are actually being searched.
+    fn transformed(&self, input: String) -> Result<String, SolverError> {
+        if self.transforms.is_empty() {
+            return Ok(input);
+        }
+
+        let mut valves = parse::parse_input(&input)
+            .context("Could not parse challenge input into a valve network")?;
+        valves.apply_all(&self.transforms)?;
+        Ok(valves.iter().map(|valve| format!("{valve}\n")).collect())
+    }
+
+    /// Print `stats` and add them to the answer, if `--param stats=true` asked for them.
+    fn with_stats(&self, answer: Answer, stats: SearchStats) -> Answer {
+        if !self.show_stats {
+            return answer;
+        }
+        let stats = stats.to_json();
+        for (key, value) in &stats {
+            println!("{key} = {value}");
+        }
+        answer.with_metadata("stats", stats)
+    }
+
+    /// Find the best pressure for every set of valves that can be opened in `max_turns`,
+    /// starting from `AA`.
+    ///
+    /// The search saves its progress to the checkpoint every so often, and reports how far it got
+    /// if it's cancelled.
+    fn search(
+        &mut self,
+        net: &Network,
+        input: &str,
+        max_turns: u64,
+    ) -> Result<ValveSearch, SolverError> {
+        let fingerprint = checkpoint::fingerprint((input, max_turns, self.show_plan));
+        let mut search = self
+            .checkpoint
+            .load(fingerprint)?
+            .unwrap_or_else(|| ValveSearch::new(max_turns, self.show_plan));
+
+        while !search.is_done() {
+            if let Err(cancelled) = search.step(net, &self.cancel) {
+                self.cancel.report("valve_sets_explored", search.best.len());
+                self.cancel.report(
+                    "best_pressure",
+                    search.best.values().map(|(pressure, _)| *pressure).max(),
+                );
+                self.checkpoint.save(fingerprint, &search)?;
+                return Err(cancelled.into());
+            }
+            self.checkpoint.tick(fingerprint, || search.clone())?;
+        }
+
+        self.checkpoint.clear()?;
+        Ok(search)
+    }
 
-        let best_pressure = best
+    /// Find the best pressure that two can release together, and both of their plans, by
+    /// pairing up the best plans for sets of valves that don't overlap.
+    fn partition(
+        &mut self,
+        net: &Network,
+        input: &str,
+        max_turns: u64,
+    ) -> Result<(u64, Vec<Step>, Vec<Step>, SearchStats), SolverError> {
+        let search = self.search(net, input, max_turns)?;
+
+        let (human, elephant) = search
+            .best
             .iter()
             .tuple_combinations()
             .filter(|(human, elephant)| human.0.is_disjoint(elephant.0))
-            .map(|(human, elephant)| human.1 + elephant.1)
-            .max()
+            .max_by_key(|(human, elephant)| (human.1).0 + (elephant.1).0)
+            .map(|(human, elephant)| (human.1, elephant.1))
             .unwrap();
+        Ok((
+            human.0 + elephant.0,
+            human.1.clone(),
+            elephant.1.clone(),
+            search.stats(),
+        ))
+    }
 
-        println!("final_pressure = {best_pressure}");
+    /// Like [`Solver16::partition`], but with [`JointSearch`].
+    fn joint_search(
+        &mut self,
+        net: &Network,
+        max_turns: u64,
+    ) -> Result<(u64, Vec<Step>, Vec<Step>, SearchStats), SolverError> {
+        let mut search = JointSearch::new(net, max_turns, &self.cancel)?;
+        match search.run() {
+            Ok((pressure, human, elephant)) => Ok((pressure, human, elephant, search.stats())),
+            Err(cancelled) => {
+                self.cancel
+                    .report("states_explored", search.states_explored());
+                Err(cancelled.into())
+            }
+        }
+    }
+}
+
+impl CacheableParse for Solver16 {
+    type Parsed = Network;
+    const PARSED_VERSION: u32 = 1;
 
-        Ok(Box::new(()))
+    fn parse(input: &str) -> Result<Network, SolverError> {
+        Network::new(input)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(transparent)]
 struct Flow(u64);
 
 type Path = Vec<(Name, Name)>;
 type Connections = NameMap<(Path, Flow)>;
-type Best = HashMap<NameMap<()>, u64>;
+/// The best pressure (and the plan that achieves it) found for each set of open valves.
+type Best = HashMap<NameMap<()>, (u64, Vec<Step>)>;
+
+/// A valve being opened as part of a plan.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Step {
+    /// The minute during which the valve is opened.
+    minute: u64,
+    valve: Name,
+    flow: u64,
+    /// The total pressure this valve releases before time runs out.
+    pressure: u64,
+}
+
+/// How much work a search did, for `--param stats=true`, so that the algorithms can be compared
+/// on more than how long they take.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SearchStats {
+    /// How many states were searched onwards from.
+    states_explored: u64,
+    /// How many best pressures were remembered: one for each set of valves when partitioning,
+    /// and one for each state in the joint search.
+    memo_size: u64,
+    /// States that weren't searched, because they'd already been reached with as much pressure.
+    pruned_by_memo: u64,
+    /// States that weren't searched, because they couldn't beat the best plan found so far.
+    pruned_by_bound: u64,
+    /// The most valves opened by any plan that was looked at.
+    max_depth: u64,
+}
+
+impl SearchStats {
+    /// Every count by its name, for the answer's metadata.
+    fn to_json(self) -> serde_json::Map<String, serde_json::Value> {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(stats)) => stats,
+            _ => unreachable!("the stats are a struct of numbers"),
+        }
+    }
+}
+
+/// Convert a plan into JSON, for the answer's metadata.
+fn plan_json(plan: &[Step], who: &str) -> Vec<serde_json::Value> {
+    plan.iter()
+        .map(|step| {
+            json!({
+                "minute": step.minute,
+                "who": who,
+                "valve": step.valve.to_string(),
+                "flow": step.flow,
+                "pressure": step.pressure,
+            })
+        })
+        .collect()
+}
+
+/// Valves can have their flow changed with `--transform 'set valve XX flow=0'`, to see how much
+/// each one matters.
+impl TransformableInput for Vec<Valve> {
+    fn apply(&mut self, transform: &Transform) -> Result<(), String> {
+        let Transform::Set {
+            kind,
+            id,
+            field,
+            value,
+        } = transform
+        else {
+            return Err("valves can only be changed, like `set valve XX flow=0`".to_string());
+        };
+        if kind != "valve" || field != "flow" {
+            return Err(format!(
+                "only a valve's flow can be set, like `set valve XX flow=0`, not `{kind}`'s `{field}`"
+            ));
+        }
+
+        let valve = self
+            .iter_mut()
+            .find(|valve| valve.name.to_string() == *id)
+            .ok_or_else(|| format!("there's no valve called {id}"))?;
+        valve.flow = value
+            .parse()
+            .map_err(|e| format!("bad flow `{value}`: {e}"))?;
+        Ok(())
+    }
+}
+
+/// Rename every valve except for `AA`, give each valve that has any flow a new flow rate, and
+/// shuffle the valves and their tunnels.
+///
+/// Valves without any flow are left that way, since they're what make the tunnels between the
+/// working valves longer than a single step.
+fn scramble(mut valves: Vec<Valve>, scrambler: &mut Scrambler) -> String {
+    let renames = scrambler.rename(
+        valves.iter().map(|valve| valve.name),
+        &[Name(*b"AA")],
+        |s| Name::from_usize(s.below(parse::MAX_NAME as u64) as usize),
+    );
+
+    for valve in &mut valves {
+        valve.name = renames[&valve.name];
+        if valve.flow > 0 && valve.name != Name(*b"AA") {
+            valve.flow = 1 + scrambler.below(25);
+        }
+        for link in &mut valve.links {
+            *link = renames.get(link).copied().unwrap_or(*link);
+        }
+        scrambler.shuffle(&mut valve.links);
+    }
+    scrambler.shuffle(&mut valves);
+
+    valves.iter().map(|valve| format!("{valve}\n")).collect()
+}
 
-struct Network {
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Network {
     valves: NameMap<(Valve, Connections)>,
 }
 
 impl Network {
-    fn new(input: &str) -> Result<Self, NetworkError> {
+    fn new(input: &str) -> Result<Self, SolverError> {
         let mut net = Self {
-            valves: parse::parse_input(input)?
+            valves: parse::parse_input(input)
+                .context("Could not parse challenge input into a valve network")?
                 .into_iter()
                 // Start off with zero connections (since we're still parsing)
                 .map(|valve| (valve.name, (valve, Connections::default())))
@@ -151,18 +467,10 @@ impl Network {
     }
 }
 
-#[derive(Debug, thiserror::Error)]
-enum NetworkError {
-    #[error("Could not parse challenge input into a valve network")]
-    BadInput {
-        #[from]
-        source: parse::ParseInputError,
-    },
-}
-
 #[derive(Debug, Clone)]
 struct Move<'a> {
     reward: u64,
+    flow: u64,
     target: Name,
     path: &'a Path,
 }
@@ -175,25 +483,107 @@ impl Move<'_> {
     }
 }
 
-#[derive(Clone)]
-struct State<'a> {
-    net: &'a Network,
+/// A depth-first search through every order that the valves can be opened in, recording the best
+/// pressure for each set of open valves.
+///
+/// The states still to visit are kept on an explicit stack instead of the call stack, so that a
+/// search can be saved partway through and picked back up later.
+#[derive(Clone, Serialize, Deserialize)]
+struct ValveSearch {
+    stack: Vec<State>,
+    best: Best,
+    /// Whether to keep track of the plan that reaches each state, or leave every plan empty.
+    record_plans: bool,
+    /// Everything but the memo size, which is however many sets of valves are in `best`.
+    stats: SearchStats,
+}
+
+impl ValveSearch {
+    fn new(max_turns: u64, record_plans: bool) -> Self {
+        let start = State {
+            position: Name(*b"AA"),
+            max_turns,
+            turn: 0,
+            pressure: 0,
+            open_valves: Default::default(),
+            depth: 0,
+            plan: vec![],
+        };
+
+        Self {
+            stack: vec![start],
+            best: Best::default(),
+            record_plans,
+            stats: SearchStats::default(),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// How much searching it's taken so far. Every state is searched onwards from, even if it
+    /// isn't the best for its valves, so nothing is ever pruned.
+    fn stats(&self) -> SearchStats {
+        SearchStats {
+            memo_size: self.best.len() as u64,
+            ..self.stats
+        }
+    }
+
+    /// Visit the next state on the stack, and push every move that can be made from it.
+    fn step(&mut self, net: &Network, cancel: &CancellationToken) -> Result<(), Cancelled> {
+        cancel.check()?;
+        let Some(state) = self.stack.pop() else {
+            return Ok(());
+        };
+        self.stats.states_explored += 1;
+        self.stats.max_depth = self.stats.max_depth.max(state.depth);
+
+        let next = state
+            .moves(net)
+            .map(|mv| state.apply(&mv, self.record_plans))
+            .collect::<Vec<_>>();
+        self.stack.extend(next);
+
+        // Nothing searches onwards from this state anymore, so its valves and plan can be kept
+        // without copying them.
+        match self.best.entry(state.open_valves) {
+            Entry::Occupied(mut e) => {
+                if state.pressure > e.get().0 {
+                    e.insert((state.pressure, state.plan));
+                }
+            }
+            Entry::Vacant(e) => {
+                e.insert((state.pressure, state.plan));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct State {
     position: Name,
     max_turns: u64,
     turn: u64,
     pressure: u64,
     open_valves: NameMap<()>,
+    /// How many valves have been opened so far.
+    depth: u64,
+    /// The valves opened so far, in order, if the search is recording plans.
+    plan: Vec<Step>,
 }
 
-impl State<'_> {
+impl State {
     fn turns_left(&self) -> u64 {
         self.max_turns - self.turn
     }
 
     /// Compute all moves and expected reward (pressure contributed till time
     /// runs out if we travel to it and open it now)
-    fn moves(&self) -> impl Iterator<Item = Move> + '_ {
-        let (_valves, connections) = &self.net.valves.get(self.position).unwrap();
+    fn moves<'a>(&'a self, net: &'a Network) -> impl Iterator<Item = Move<'a>> + 'a {
+        let (_valves, connections) = &net.valves.get(self.position).unwrap();
         connections.iter().filter_map(|(name, (path, flow))| {
             if self.open_valves.contains(name) {
                 return None;
@@ -210,57 +600,243 @@ impl State<'_> {
 
             Some(Move {
                 reward,
+                flow: flow.0,
                 target: name,
                 path,
             })
         })
     }
 
-    // fn find_best_moves(&self) -> (Self, Vec<Move>) {
-    //     let mut best_moves = vec![];
-    //     let mut best_state = self.clone();
+    /// Apply a given move, adding it to the plan if `record_plan`.
+    fn apply(&self, mv: &Move, record_plan: bool) -> Self {
+        let mut next = self.clone();
+        next.position = mv.target;
+        next.turn += mv.cost();
+        next.pressure += mv.reward;
+        next.open_valves.insert(mv.target, ());
+        next.depth += 1;
+        if record_plan {
+            next.plan.push(Step {
+                minute: next.turn,
+                valve: mv.target,
+                flow: mv.flow,
+                pressure: mv.reward,
+            });
+        }
+        next
+    }
+}
+
+super::challenge_solver_test_boilerplate! {
+    Solver16::default();
+    16 => {
+        a as u64,
+        b as u64,
+    }
 
-    //     for mv in self.moves() {
-    //         let next = self.apply(&mv);
-    //         let (next, mut next_moves) = next.find_best_moves();
-    //         next_moves.push(mv);
-    //         if next.pressure > best_state.pressure {
-    //             best_moves = next_moves;
-    //             best_state = next;
-    //         }
-    //     }
+    #[test]
+    fn scrambling_keeps_the_network_shape() -> Result<(), SolverError> {
+        let solver = Solver16::default();
+        let scrambled = solver
+            .scramble(SAMPLE_INPUT, &mut Scrambler::new(16))
+            .unwrap()?;
+
+        assert_ne!(scrambled, SAMPLE_INPUT);
+        assert!(scrambled.contains("Valve AA has flow rate=0; "));
+        assert_eq!(
+            solver.validate(&scrambled).unwrap()?.entities,
+            solver.validate(SAMPLE_INPUT).unwrap()?.entities,
+        );
+        Solver16::default().solve_a(&mut Cursor::new(scrambled))?;
+
+        Ok(())
+    }
 
-    //     (best_state, best_moves)
-    // }
+    #[test]
+    fn plan_adds_up() -> Result<(), SolverError> {
+        let mut solver = Solver16 {
+            show_plan: true,
+            ..Default::default()
+        };
+        let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT))?;
 
-    fn apply_best_moves(&self, best: &mut Best) -> Self {
-        let mut best_state = self.clone();
+        let plan = answer.metadata()["plan"].as_array().unwrap();
+        let total: u64 = plan.iter().map(|step| step["pressure"].as_u64().unwrap()).sum();
+        assert_eq!(total, 1707);
+        assert!(plan.iter().any(|step| step["who"] == "elephant"));
 
-        best.entry(self.open_valves.clone())
-            .and_modify(|v| {
-                if self.pressure > *v {
-                    *v = self.pressure;
-                }
-            })
-            .or_insert(self.pressure);
+        Ok(())
+    }
 
-        for mv in self.moves() {
-            let next = self.apply(&mv).apply_best_moves(best);
-            if next.pressure > best_state.pressure {
-                best_state = next;
+    #[test]
+    fn plans_are_only_recorded_when_shown() -> Result<(), SolverError> {
+        let net = Network::new(SAMPLE_INPUT)?;
+        let cancel = CancellationToken::new();
+        let search = |record_plans| -> Result<ValveSearch, SolverError> {
+            let mut search = ValveSearch::new(30, record_plans);
+            while !search.is_done() {
+                search.step(&net, &cancel)?;
             }
+            Ok(search)
+        };
+
+        let (with, without) = (search(true)?, search(false)?);
+        assert_eq!(with.stats(), without.stats());
+        assert!(with.best.values().any(|(_, plan)| !plan.is_empty()));
+        assert!(without.best.values().all(|(_, plan)| plan.is_empty()));
+        for (valves, (pressure, _)) in &without.best {
+            assert_eq!(with.best[valves].0, *pressure);
         }
+        Ok(())
+    }
 
-        best_state
+    #[test]
+    fn joint_search_agrees_with_partitioning() -> Result<(), SolverError> {
+        let solve_b = |input: &str, algorithm| -> Result<u64, SolverError> {
+            let mut solver = Solver16 {
+                algorithm,
+                show_plan: true,
+                ..Default::default()
+            };
+            let answer = solver.solve_b(&mut Cursor::new(input))?;
+
+            let plan = answer.metadata()["plan"].as_array().unwrap();
+            let total: u64 = plan.iter().map(|step| step["pressure"].as_u64().unwrap()).sum();
+            let pressure = *answer.downcast_ref::<u64>().unwrap();
+            assert_eq!(total, pressure);
+            Ok(pressure)
+        };
+
+        assert_eq!(solve_b(SAMPLE_INPUT, Algorithm::Joint)?, 1707);
+        for seed in 0..5 {
+            let scrambled = Solver16::default()
+                .scramble(SAMPLE_INPUT, &mut Scrambler::new(seed))
+                .unwrap()?;
+            assert_eq!(
+                solve_b(&scrambled, Algorithm::Joint)?,
+                solve_b(&scrambled, Algorithm::Partition)?,
+                "seed {seed}"
+            );
+        }
+
+        Ok(())
     }
 
-    /// Apply a given move
-    fn apply(&self, mv: &Move) -> Self {
-        let mut next = self.clone();
-        next.position = mv.target;
-        next.turn += mv.cost();
-        next.pressure += mv.reward;
-        next.open_valves.insert(mv.target, ());
-        next
+    #[test]
+    fn joint_search_refuses_too_many_valves() {
+        // A corridor of 65 valves that are all worth opening, one too many for the bit mask.
+        let names: Vec<String> = (0..65)
+            .map(|i| {
+                let [a, b] = [b'B' + i / 26, b'A' + i % 26].map(char::from);
+                format!("{a}{b}")
+            })
+            .collect();
+        let mut input = format!("Valve AA has flow rate=0; tunnels lead to valves {}\n", names[0]);
+        for (i, name) in names.iter().enumerate() {
+            let neighbors: Vec<&str> = [i.checked_sub(1), Some(i + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|j| names.get(j).map(String::as_str))
+                .chain((i == 0).then_some("AA"))
+                .collect();
+            input += &format!(
+                "Valve {name} has flow rate=1; tunnels lead to valves {}\n",
+                neighbors.join(", ")
+            );
+        }
+
+        let mut solver = Solver16 {
+            algorithm: Algorithm::Joint,
+            ..Default::default()
+        };
+        let err = solver.solve_b(&mut Cursor::new(input)).unwrap_err();
+        assert!(matches!(err, SolverError::Unsupported(_)), "{err}");
+        assert!(err.to_string().contains("64 valves"), "{err}");
+    }
+
+    #[test]
+    fn both_algorithms_report_their_stats() -> Result<(), SolverError> {
+        let stats = |algorithm| -> Result<serde_json::Value, SolverError> {
+            let mut solver = Solver16 {
+                algorithm,
+                show_stats: true,
+                ..Default::default()
+            };
+            let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT))?;
+            assert_eq!(answer.downcast_ref::<u64>(), Some(&1707));
+            Ok(answer.metadata()["stats"].clone())
+        };
+
+        let partition = stats(Algorithm::Partition)?;
+        let joint = stats(Algorithm::Joint)?;
+        for stats in [&partition, &joint] {
+            assert!(stats["states_explored"].as_u64().unwrap() > 0, "{stats}");
+            assert!(stats["memo_size"].as_u64().unwrap() > 0, "{stats}");
+            assert_eq!(stats["max_depth"], 6, "{stats}");
+        }
+        assert_eq!(partition["pruned_by_memo"], 0);
+        assert_eq!(partition["pruned_by_bound"], 0);
+        assert!(joint["pruned_by_bound"].as_u64().unwrap() > 0, "{joint}");
+
+        let mut solver = Solver16::default();
+        let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert!(!answer.metadata().contains_key("stats"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn transforms_change_flows() -> Result<(), SolverError> {
+        let mut solver = Solver16 {
+            transforms: vec!["set valve DD flow=0".parse().unwrap()],
+            ..Default::default()
+        };
+        let answer = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+
+        let edited = SAMPLE_INPUT.replace("Valve DD has flow rate=20", "Valve DD has flow rate=0");
+        let expected = Solver16::default().solve_a(&mut Cursor::new(edited))?;
+        assert_eq!(answer, expected);
+        assert_ne!(answer.downcast_ref::<u64>(), Some(&1651));
+
+        solver.transforms = vec!["set valve ZZ flow=3".parse().unwrap()];
+        assert!(solver.solve_a(&mut Cursor::new(SAMPLE_INPUT)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_non_ascii_valve_names() {
+        assert!(parse::parse_input("Valve Aé has flow rate=0; tunnel leads to valve AA\n").is_err());
+        assert!(parse::parse_input("Valve A1 has flow rate=0; tunnel leads to valve AA\n").is_err());
+    }
+
+    #[test]
+    fn resumes_from_checkpoint() -> Result<(), SolverError> {
+        let path = std::env::temp_dir().join(format!("aoc2022-16b-{}.bin", std::process::id()));
+        let mut solver = Solver16 {
+            checkpoint: Checkpointer::new(&path, true),
+            ..Default::default()
+        };
+
+        solver.cancel.cancel();
+        assert!(solver.solve_b(&mut Cursor::new(SAMPLE_INPUT)).is_err());
+        assert!(path.exists(), "cancelling should save a checkpoint");
+
+        solver.cancel = CancellationToken::new();
+        let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert_eq!(answer.downcast_ref::<u64>(), Some(&1707));
+        assert!(!path.exists(), "finishing should clear the checkpoint");
+
+        Ok(())
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let mut solver = Solver16::default();
+        solver.cancel.cancel();
+
+        let err = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT)).unwrap_err();
+        assert!(matches!(err, SolverError::Cancelled(Cancelled)));
+        assert!(solver.cancel.progress().contains_key("valve_sets_explored"));
     }
 }
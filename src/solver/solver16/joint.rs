@@ -0,0 +1,244 @@
+//! An exact search for part B, with the human and the elephant moving at the same time.
+//!
+//! Instead of finding the best plan for every set of valves and pairing up disjoint sets
+//! afterwards, this searches every way of handing out the valves between both of them as they go.
+//! Whoever has the most time left always picks their next valve, or decides to stop.
+//!
+//! Two things keep the search small. The pressure released on the way to each state is
+//! remembered, so a state that's reached again in a different order with less pressure is only
+//! searched once. And branches that couldn't beat the best plan so far, even if every valve left
+//! were opened by whoever is closest to it, are cut off.
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::{
+    error::SolverError,
+    sync::{CancellationToken, Cancelled},
+};
+
+use super::{parse::Name, Network, SearchStats, Step};
+
+/// Someone walking around the tunnels and opening valves.
+///
+/// Ordered by how much time they have left first, so that whoever picks next is the greatest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Agent {
+    time_left: u64,
+    /// The valve they're at, as an index into [`JointSearch::valves`], or the starting valve if
+    /// it's past the end.
+    at: usize,
+}
+
+/// The human and the elephant, in either order, and the valves that have been opened so far as a
+/// bit set.
+type Key = ([Agent; 2], u64);
+
+pub(super) struct JointSearch<'c> {
+    max_turns: u64,
+    /// Every valve that's worth opening, with its flow rate.
+    valves: Vec<(Name, u64)>,
+    /// How many minutes it takes to walk from one valve to another, including from the starting
+    /// valve at the end.
+    distances: Vec<Vec<u64>>,
+    /// The most pressure that any path to each state has released so far. Getting back to a state
+    /// with less than that can't lead anywhere better.
+    memo: HashMap<Key, u64>,
+    /// The plans of whoever is at each index of the agents, as they're being searched.
+    plans: [Vec<Step>; 2],
+    /// The best total pressure found so far, and the plans that release it.
+    best: (u64, [Vec<Step>; 2]),
+    /// Everything but the memo size, which is however many states are in `memo`.
+    stats: SearchStats,
+    cancel: &'c CancellationToken,
+}
+
+impl<'c> JointSearch<'c> {
+    /// Set up a search of `net`.
+    ///
+    /// Fails if more than 64 valves are worth opening, since the open ones are kept in a `u64`
+    /// bit set.
+    pub(super) fn new(
+        net: &Network,
+        max_turns: u64,
+        cancel: &'c CancellationToken,
+    ) -> Result<Self, SolverError> {
+        let valves: Vec<_> = net
+            .valves
+            .iter()
+            .filter(|(_, (valve, _))| valve.flow > 0)
+            .map(|(name, (valve, _))| (name, valve.flow))
+            .collect();
+        if valves.len() > u64::BITS as usize {
+            return Err(SolverError::Unsupported(format!(
+                "The joint search can only keep track of {} valves with a flow rate in its bit \
+                 mask, but there are {}",
+                u64::BITS,
+                valves.len()
+            )));
+        }
+
+        let names: Vec<_> = valves
+            .iter()
+            .map(|(name, _)| *name)
+            .chain([Name(*b"AA")])
+            .collect();
+        let distances = names
+            .iter()
+            .map(|&from| {
+                let (_, connections) = net.valves.get(from).unwrap();
+                names
+                    .iter()
+                    .map(|&to| {
+                        connections
+                            .get(to)
+                            .map_or(u64::MAX, |(path, _)| path.len() as u64)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            max_turns,
+            valves,
+            distances,
+            memo: HashMap::new(),
+            plans: Default::default(),
+            best: Default::default(),
+            stats: SearchStats::default(),
+            cancel,
+        })
+    }
+
+    /// How many states have been searched so far.
+    pub(super) fn states_explored(&self) -> usize {
+        self.memo.len()
+    }
+
+    /// How much searching it's taken so far.
+    pub(super) fn stats(&self) -> SearchStats {
+        SearchStats {
+            memo_size: self.memo.len() as u64,
+            ..self.stats
+        }
+    }
+
+    /// Find the most pressure that can be released, along with the human's and the elephant's
+    /// plans for getting it.
+    pub(super) fn run(&mut self) -> Result<(u64, Vec<Step>, Vec<Step>), Cancelled> {
+        let start = Agent {
+            time_left: self.max_turns,
+            at: self.valves.len(),
+        };
+        self.search([start, start], 0, 0)?;
+
+        let (pressure, [human, elephant]) = std::mem::take(&mut self.best);
+        Ok((pressure, human, elephant))
+    }
+
+    /// Search every way that `agents` could go on from here, having already released `pressure`
+    /// by opening the valves in `opened`.
+    ///
+    /// `agents[0]` is always the human, and `agents[1]` the elephant.
+    fn search(&mut self, agents: [Agent; 2], opened: u64, pressure: u64) -> Result<(), Cancelled> {
+        match self.memo.entry(Self::key(agents, opened)) {
+            Entry::Occupied(e) if *e.get() >= pressure => {
+                self.stats.pruned_by_memo += 1;
+                return Ok(());
+            }
+            Entry::Occupied(mut e) => {
+                e.insert(pressure);
+            }
+            Entry::Vacant(e) => {
+                e.insert(pressure);
+            }
+        }
+        self.cancel.check()?;
+        self.stats.max_depth = self.stats.max_depth.max(u64::from(opened.count_ones()));
+
+        if pressure > self.best.0 {
+            self.best = (pressure, self.plans.clone());
+        }
+        if pressure + self.bound(agents, opened) <= self.best.0 {
+            self.stats.pruned_by_bound += 1;
+            return Ok(());
+        }
+        self.stats.states_explored += 1;
+
+        // Whoever has the most time left picks next.
+        let who = usize::from(agents[1] > agents[0]);
+        let agent = agents[who];
+        if agent.time_left == 0 {
+            return Ok(());
+        }
+
+        // Trying the best moves first finds good plans sooner, so more of the rest is pruned.
+        let mut moves: Vec<_> = self.moves(agent, opened).collect();
+        moves.sort_by_key(|&(valve, moved)| std::cmp::Reverse(self.reward(valve, moved)));
+
+        for (valve, moved) in moves {
+            let reward = self.reward(valve, moved);
+            let (name, flow) = self.valves[valve];
+            self.plans[who].push(Step {
+                minute: self.max_turns - moved.time_left,
+                valve: name,
+                flow,
+                pressure: reward,
+            });
+
+            let mut next = agents;
+            next[who] = moved;
+            let result = self.search(next, opened | 1 << valve, pressure + reward);
+            self.plans[who].pop();
+            result?;
+        }
+
+        // Leaving the rest of the valves to the other one might be even better.
+        let mut next = agents;
+        next[who].time_left = 0;
+        self.search(next, opened, pressure)
+    }
+
+    /// The most pressure that could possibly still be released, if whoever is closest to each
+    /// valve could open it right away.
+    fn bound(&self, agents: [Agent; 2], opened: u64) -> u64 {
+        (0..self.valves.len())
+            .filter(|valve| opened & 1 << valve == 0)
+            .map(|valve| {
+                agents
+                    .iter()
+                    .filter_map(|&agent| self.moves_to(agent, valve))
+                    .map(|moved| self.reward(valve, moved))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// The human and the elephant are interchangeable, so whoever picks next always goes first.
+    fn key(mut agents: [Agent; 2], opened: u64) -> Key {
+        agents.sort_by(|a, b| b.cmp(a));
+        (agents, opened)
+    }
+
+    /// Every valve that `agent` could still walk to and open in time, and where that leaves them.
+    fn moves(&self, agent: Agent, opened: u64) -> impl Iterator<Item = (usize, Agent)> + '_ {
+        (0..self.valves.len())
+            .filter(move |valve| opened & 1 << valve == 0)
+            .filter_map(move |valve| Some((valve, self.moves_to(agent, valve)?)))
+    }
+
+    /// Where `agent` ends up after walking to `valve` and opening it, if there's time.
+    fn moves_to(&self, agent: Agent, valve: usize) -> Option<Agent> {
+        let cost = self.distances[agent.at][valve].checked_add(1)?;
+        let time_left = agent.time_left.checked_sub(cost).filter(|&t| t > 0)?;
+        Some(Agent {
+            time_left,
+            at: valve,
+        })
+    }
+
+    /// The pressure released by opening `valve` with as much time left as `agent` has.
+    fn reward(&self, valve: usize, agent: Agent) -> u64 {
+        self.valves[valve].1 * agent.time_left
+    }
+}
@@ -1,5 +1,7 @@
 use std::{fmt, hash::Hash};
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use super::parse::{Name, MAX_NAME};
 
 #[derive(Clone)]
@@ -106,3 +108,26 @@ where
         self.values.hash(state);
     }
 }
+
+/// Saved as a list of `(name, value)` pairs, leaving out all of the missing names.
+impl<T> Serialize for NameMap<T>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Some formats need to know how long a list is up front, which a filtered iterator can't
+        // tell them.
+        serializer.collect_seq(self.iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'de, T> Deserialize<'de> for NameMap<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<(Name, T)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
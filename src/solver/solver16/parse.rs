@@ -1,9 +1,9 @@
 use std::fmt;
 
-use miette::GraphicalReportHandler;
+use itertools::Itertools;
 use nom::{
     branch::alt,
-    bytes::complete::take,
+    bytes::complete::take_while_m_n,
     character::complete::{self as nom_cc, multispace0, multispace1},
     combinator::map,
     error::ParseError,
@@ -11,72 +11,21 @@ use nom::{
     sequence::{preceded, tuple},
     IResult, Parser,
 };
-use nom_locate::LocatedSpan;
 use nom_supreme::{
-    error::{BaseErrorKind, ErrorTree, GenericErrorTree},
-    final_parser::final_parser,
+    error::ErrorTree,
     multi::collect_separated_terminated,
     tag::{complete::tag, TagError},
     ParserExt,
 };
+use serde::{Deserialize, Serialize};
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+use crate::parse::{BadInputError, Span};
 
 /// Parse the challenge input into a vector of [`Valve`]s.
 ///
 /// Any parsing errors will be printed out to `stderr` with fancy formatting.
-pub fn parse_input(input: &str) -> Result<Vec<Valve>, ParseInputError> {
-    let input_span = Span::new(input);
-
-    let valves_res: Result<_, ErrorTree<Span>> =
-        final_parser(Valve::parse_all::<ErrorTree<Span>>)(input_span);
-
-    match valves_res {
-        Ok(records) => Ok(records),
-
-        Err(e) => match e {
-            GenericErrorTree::Base { location, kind } => {
-                let offset = location.location_offset().into();
-                let err = BadInputError {
-                    src: input.to_string(),
-                    bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                    kind,
-                };
-
-                let mut s = String::new();
-                GraphicalReportHandler::new()
-                    .render_report(&mut s, &err)
-                    .unwrap();
-                eprintln!("{s}");
-
-                Err(err.into())
-            }
-
-            GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-            GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-        },
-    }
-}
-
-#[derive(thiserror::Error, Debug, miette::Diagnostic)]
-#[error("Error parsing input")]
-pub struct BadInputError {
-    #[source_code]
-    src: String,
-
-    #[label("{kind}")]
-    bad_bit: miette::SourceSpan,
-
-    kind: BaseErrorKind<&'static str, Box<dyn std::error::Error + Send + Sync>>,
-}
-
-#[derive(thiserror::Error, Debug)]
-pub enum ParseInputError {
-    #[error("Failed to parse input due to bad input")]
-    BadInputError {
-        #[from]
-        source: BadInputError,
-    },
+pub fn parse_input(input: &str) -> Result<Vec<Valve>, BadInputError> {
+    crate::parse::parse_input(input, Valve::parse_all::<ErrorTree<Span>>)
 }
 
 /// Valve names are always two characters: this is more compact than a `String`,
@@ -84,7 +33,7 @@ pub enum ParseInputError {
 /// really convenient.
 ///
 /// The number of unique valve names are 26^2 = 676.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Name(pub [u8; 2]);
 
 /// The maximum value that a [`Name`] can be converted to using [`Name::as_usize()`].
@@ -95,9 +44,10 @@ impl Name {
     where
         E: ParseError<Span<'a>>,
     {
-        map(take(2_usize), |slice: Span<'a>| {
-            Self(slice.as_bytes().try_into().unwrap())
-        })(i)
+        map(
+            take_while_m_n(2, 2, |c: char| c.is_ascii_uppercase()),
+            |slice: Span<'a>| Self([slice.as_bytes()[0], slice.as_bytes()[1]]),
+        )(i)
     }
 
     /// Returns this name as a `usize` between 0 and 26^2 (= 676).
@@ -105,13 +55,13 @@ impl Name {
         let [a, b] = self.0;
 
         debug_assert!(
-            (b'A'..=b'Z').contains(&a),
+            a.is_ascii_uppercase(),
             "`a` had a value outside the range {}..={}",
             b'A',
             b'Z'
         );
         debug_assert!(
-            (b'A'..=b'Z').contains(&b),
+            b.is_ascii_uppercase(),
             "`b` had a value outside the range {}..={}",
             b'A',
             b'Z'
@@ -149,7 +99,7 @@ impl fmt::Display for Name {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Valve {
     pub name: Name,
     pub flow: u64,
@@ -174,19 +124,22 @@ impl Valve {
     ///
     /// # Examples
     ///
-    /// Basic usage:
+    /// Valves aren't public, but validating an input parses every valve in it, and counts them
+    /// up:
     ///
     /// ```
-    /// let valve = Valve::parse(Span::new(
-    ///     "Valve AA has flow rate=10; tunnels lead to valves DD, II, BB"
-    /// )).unwrap().1;
+    /// use aoc2022::prelude::*;
+    ///
+    /// let report = Solver::new().validate_str(
+    ///     16,
+    ///     "Valve AA has flow rate=10; tunnels lead to valves DD, II, BB\n",
+    /// )?;
     ///
-    /// assert_eq!(format!("{}", valve.name), "AA".to_string());
-    /// assert_eq!(valve.flow, 10);
     /// assert_eq!(
-    ///     format!("{:?}", valve.links),
-    ///     "[DD, II, BB]".to_string(),
+    ///     report.parsed.unwrap()?.entities,
+    ///     [("valves", 1), ("valves with flow", 1), ("tunnels", 3)],
     /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     fn parse<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Self, E>
     where
@@ -208,3 +161,14 @@ impl Valve {
         )(i)
     }
 }
+
+impl fmt::Display for Valve {
+    /// Writes the valve the same way that the challenge input does.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Valve {} has flow rate={}; ", self.name, self.flow)?;
+        match self.links.as_slice() {
+            [link] => write!(f, "tunnel leads to valve {link}"),
+            links => write!(f, "tunnels lead to valves {}", links.iter().join(", ")),
+        }
+    }
+}
@@ -1,230 +1,157 @@
-use std::{collections::HashMap, fmt, io::BufRead};
+use std::{fmt, io::BufRead};
 
-use itertools::Itertools;
 use owo_colors::{colors::*, OwoColorize, Rgb};
-
-use crate::solver::solver17::parse::PIECES;
+use serde_json::json;
+
+use crate::{
+    color,
+    cycledetect::{FastForward, Skip},
+    error::SolverError,
+    glyphs,
+    math::MathMode,
+    raster::Raster,
+    simulation::{Simulation, StepResult},
+    solver::solver17::parse::PIECES,
+    termplot::{Ink, PixelCanvas},
+};
 
 use self::parse::{Coord, Jet, Piece};
 
+use super::{EventStream, ParamError, Rendering, SolverConfig};
+
 mod parse;
 
 const CHAMBER_WIDTH: usize = 7;
 const CHAMBER_WIDTH_MASK: u8 = 0b0111_1111;
 
+/// How many pixels across each block is in the gallery's picture of the tower.
+const GALLERY_SCALE: usize = 4;
+
+/// How many times around the loop `--check-consistency` drops every piece for, by default.
+const CONSISTENCY_CYCLES: usize = 3;
+
 #[derive(Debug, Default)]
-pub struct Solver17;
+pub struct Solver17 {
+    /// How many pieces to print the final resting place of, from `--param trace=N`.
+    trace: usize,
+    math: MathMode,
+    /// Where to send the tower's height after every piece, for `--stream-events`.
+    events: EventStream,
+    /// How many times around the loop to check part B's skip against, from
+    /// `--param consistency_cycles=N`, or `None` without `--check-consistency`.
+    consistency_cycles: Option<usize>,
+}
 
 impl super::ChallengeSolver for Solver17 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         17
     }
 
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.trace = config.params.get_or("trace", 0)?;
+        self.math = config.math;
+        self.events = config.events.clone();
+        self.consistency_cycles = config
+            .check_consistency
+            .then(|| {
+                config
+                    .params
+                    .get_or("consistency_cycles", CONSISTENCY_CYCLES)
+            })
+            .transpose()?;
+        Ok(())
+    }
+
+    fn gallery(&self, input: &str) -> Option<Result<Vec<Rendering>, SolverError>> {
+        let draw = || {
+            let jets = Jet::parse_all(&jet_line(&mut input.as_bytes())?)?;
+            let mut state = State::new(jets);
+            state.run(Some(2022));
+            Ok(vec![Rendering::png(
+                "tower",
+                state.raster().scaled(GALLERY_SCALE),
+            )])
+        };
+        Some(draw())
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let target = 2022;
         let verbose_output = false;
 
-        let input = input.lines().next().unwrap()?;
+        let input = jet_line(input)?;
         let jets = Jet::parse_all(&input)?;
-        let mut state = State::default();
-
-        while state.piece_count != target {
-            // New piece starts falling
-            let piece = &PIECES[state.piece_count % PIECES.len()];
-            state.curr.x = 2;
-            state.curr.y = state.top + 3;
-
-            if verbose_output {
-                println!("== Piece {} begins falling ==", state.piece_count + 1);
-                println!("{state}");
-            }
-
-            loop {
-                // jet fires
-                let jet = &jets[state.jet_count % jets.len()];
-                let new_curr = match jet {
-                    Jet::Left => (state.curr.x.saturating_sub(1), state.curr.y).into(),
-                    Jet::Right => (state.curr.x + 1, state.curr.y).into(),
-                };
-                if state.is_new_curr_valid(&new_curr, piece) {
-                    state.curr = new_curr;
-                }
-                state.jet_count += 1;
-
-                if verbose_output {
-                    println!("Jet of gas pushes piece {jet} :",);
-                    println!("{state}");
-                }
+        self.print_trace(&jets);
+        let mut state = State::new(jets);
+        state.verbose = verbose_output;
 
-                // piece falls
-                let new_curr = (state.curr.x, state.curr.y.saturating_sub(1)).into();
-                if state.curr.y == 0 || !state.is_new_curr_valid(&new_curr, piece) {
-                    break;
-                }
-                state.curr = new_curr;
-
-                if verbose_output {
-                    println!("Piece falls 1 unit:");
-                    println!("{state}");
-                }
-            }
-
-            // piece settles
-            for offset in piece.coords {
-                let Coord { x, y } = state.curr + *offset;
-
-                while state.map.len() <= y {
-                    state.map.push(0);
-                    state.color_map.push([Rgb(255, 255, 255); CHAMBER_WIDTH]);
-                }
-
-                state.map[y] |= pack_x_coord(x);
-                state.color_map[y][x] = piece.color;
-
-                state.top = state.top.max(y + 1);
-            }
-
-            // prep for next iteration
-            state.piece_count += 1;
-            if verbose_output {
-                println!();
-            }
-        }
+        state.run_streaming(Some(target), &self.events)?;
 
         if verbose_output {
             println!();
         }
         println!("== Final tower height: {} ==", state.top);
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(state.top).with_unit("units tall"))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
         let target = 1_000_000_000_000;
         let verbose_output = false;
+        let math = self.math;
 
-        let input = input.lines().next().unwrap()?;
+        let input = jet_line(input)?;
         let jets = Jet::parse_all(&input)?;
-        let mut state = State::default();
-
-        state.seen.reserve(input.len() * jets.len());
-
+        self.print_trace(&jets);
+        let jet_count = jets.len();
+        let mut state = State::new(jets);
+        state.verbose = verbose_output;
+
+        // At the third occurance of a key, the heights repeat. This is because some of the first
+        // pieces will have hit the floor. By the time a combination of piece and jet comes around
+        // again, the fallen blocks only interact with other blocks when falling. That is the first
+        // repeatable cycle.
+        let mut fast_forward = FastForward::new(2);
+
+        self.events
+            .emit(0, StepResult::Continue, || state.metrics())?;
         while state.piece_count != target {
-            // New piece starts falling
-            let piece = &PIECES[state.piece_count % PIECES.len()];
-            state.curr.x = 2;
-            state.curr.y = state.top + 3;
-
-            if verbose_output {
-                println!("== Piece {} begins falling ==", state.piece_count + 1);
-                println!("{state}");
-            }
-
-            loop {
-                // jet fires
-                let jet = &jets[state.jet_count % jets.len()];
-                let new_curr = match jet {
-                    Jet::Left => (state.curr.x.saturating_sub(1), state.curr.y).into(),
-                    Jet::Right => (state.curr.x + 1, state.curr.y).into(),
-                };
-                if state.is_new_curr_valid(&new_curr, piece) {
-                    state.curr = new_curr;
-                }
-                state.jet_count += 1;
-
-                if verbose_output {
-                    println!("Jet of gas pushes piece {jet} :",);
-                    println!("{state}");
-                }
-
-                // piece falls
-                let new_curr = (state.curr.x, state.curr.y.saturating_sub(1)).into();
-                if state.curr.y == 0 || !state.is_new_curr_valid(&new_curr, piece) {
-                    break;
-                }
-                state.curr = new_curr;
-
-                if verbose_output {
-                    println!("Piece falls 1 unit:");
-                    println!("{state}");
-                }
-            }
-
-            // piece settles
-            for offset in piece.coords {
-                let Coord { x, y } = state.curr + *offset;
-
-                while state.map.len() <= y {
-                    state.map.push(0);
-                    state.color_map.push([Rgb(255, 255, 255); CHAMBER_WIDTH]);
-                }
-
-                state.map[y] |= pack_x_coord(x);
-                state.color_map[y][x] = piece.color;
-
-                state.top = state.top.max(y + 1);
-            }
+            state.drop_piece();
 
             // Look for a cycle!
-            if state.added_by_repeats == 0 {
-                let key = SeenKey {
-                    piece_index: state.piece_count % PIECES.len(),
-                    jet_index: state.jet_count % jets.len(),
-                };
-
-                // At the third occurance of a key, the values in the seen map repeat.
-                // This is because some of the first pieces will have hit the floor.
-                // By the time a combination of pieces_idx, jets_idx comes around again, the fallen
-                // blocks only interact with other blocks when falling. That is the first
-                // repeatable cycle.
-                if let Some(SeenState {
-                    seen_key_count: 2,
-                    piece_count: old_piece_count,
-                    top: old_top,
-                }) = state.seen.get(&key)
-                {
-                    // add as many pieces as possible without hitting the goal piece_count
-
-                    println!("Cycle detected!");
-                    println!("  current piece count = {}", state.piece_count);
-                    println!("  current top         = {}", state.top);
-                    println!("  old piece count     = {old_piece_count}");
-                    println!("  old top             = {old_top}");
-
-                    let delta_piece_count = state.piece_count - old_piece_count;
-                    let delta_top = state.top - old_top;
-                    println!("  delta piece count   = {delta_piece_count}");
-                    println!("  delta top           = {delta_top}");
-
-                    let repeats = (target - state.piece_count) / delta_piece_count;
-                    println!("  repeats             = {repeats}");
-
-                    println!(
-                        "Adding {} pieces (for {} additional levels)",
-                        repeats * delta_piece_count,
-                        repeats * delta_top,
-                    );
-
-                    state.piece_count += repeats * delta_piece_count;
-                    state.added_by_repeats += repeats * delta_top;
-
-                    println!("  new piece count     = {}", state.piece_count);
+            let key = SeenKey {
+                piece_index: state.piece_count % PIECES.len(),
+                jet_index: state.jet_count % jet_count,
+            };
+            if let Some(skip) =
+                fast_forward.observe(key, state.piece_count, state.top, target, math)?
+            {
+                // add as many pieces as possible without hitting the goal piece_count
+
+                println!("Cycle detected!");
+                println!("  current piece count = {}", state.piece_count);
+                println!("  current top         = {}", state.top);
+                println!("  old piece count     = {}", skip.last.step);
+                println!("  old top             = {}", skip.last.metric);
+                println!("  delta piece count   = {}", skip.period);
+                println!("  delta top           = {}", skip.gain);
+                println!("  repeats             = {}", skip.repeats);
+                println!(
+                    "Adding {} pieces (for {} additional levels)",
+                    skip.steps, skip.metric
+                );
+
+                let found_after = state.piece_count + 1;
+                state.piece_count = math.add(state.piece_count, skip.steps)?;
+                state.added_by_repeats = math.add(state.added_by_repeats, skip.metric)?;
+
+                println!("  new piece count     = {}", state.piece_count);
+
+                if let Some(cycles) = self.consistency_cycles {
+                    check_skip(&state.jets, &skip, found_after, state.top, cycles)?;
                 }
-
-                // Update seen map
-                state
-                    .seen
-                    .entry(key)
-                    .and_modify(|seen_state| {
-                        seen_state.seen_key_count += 1;
-                        seen_state.piece_count = state.piece_count;
-                        seen_state.top = state.top;
-                    })
-                    .or_insert(SeenState {
-                        seen_key_count: 1,
-                        piece_count: state.piece_count,
-                        top: state.top,
-                    });
             }
 
             // prep for next iteration
@@ -232,23 +159,116 @@ impl super::ChallengeSolver for Solver17 {
             if verbose_output {
                 println!();
             }
+
+            // After a cycle is skipped over, the piece count jumps ahead by far more than one.
+            let result = if state.piece_count == target {
+                StepResult::Done
+            } else {
+                StepResult::Continue
+            };
+            self.events
+                .emit(state.piece_count as u64, result, || state.metrics())?;
         }
 
         if verbose_output {
             println!();
         }
+        let height = math.add(state.top, state.added_by_repeats)?;
+        println!("== Final tower height: {height} ==");
+        println!("({} levels added by repeats)", state.added_by_repeats);
+
+        Ok(super::Answer::new(height).with_unit("units tall"))
+    }
+}
+
+/// The jet pattern, which is all on the first line. Anything after it is ignored, which
+/// `--trace-io` will point out.
+fn jet_line(input: &mut dyn BufRead) -> Result<String, SolverError> {
+    input
+        .lines()
+        .next()
+        .ok_or_else(|| SolverError::invalid_input("The input is empty"))?
+        .map_err(SolverError::from)
+}
+
+/// Check that the tower grows by `skip.gain` every time around the loop, like skipping ahead
+/// assumes, by dropping every piece for `cycles` times around it. A loop that was trusted too
+/// early would otherwise quietly give the wrong height.
+///
+/// The skip was found once `pieces` pieces had fallen, with the tower `height` tall.
+fn check_skip(
+    jets: &[Jet],
+    skip: &Skip,
+    pieces: usize,
+    height: usize,
+    cycles: usize,
+) -> Result<(), SolverError> {
+    println!("Checking the cycle against {cycles} cycles of dropping every piece:");
+    let mut state = State::new(jets.to_vec());
+    state.trace(pieces);
+
+    let mut discrepancy = None;
+    for cycle in 1..=cycles {
+        state.trace(skip.period);
+        let extrapolated = height + cycle * skip.gain;
+        let verdict = if state.top == extrapolated {
+            "ok"
+        } else {
+            "MISMATCH"
+        };
         println!(
-            "== Final tower height: {} ==",
-            state.top + state.added_by_repeats
+            "  after {cycle} cycles: simulated top = {}, extrapolated top = {extrapolated} ({verdict})",
+            state.top
         );
-        println!("({} levels added by repeats)", state.added_by_repeats);
+        if state.top != extrapolated {
+            discrepancy.get_or_insert((cycle, state.top, extrapolated));
+        }
+    }
 
-        Ok(Box::new(()))
+    match discrepancy {
+        None => Ok(()),
+        Some((cycle, simulated, extrapolated)) => Err(SolverError::invalid_input(format!(
+            "The cycle of {} pieces found after piece {pieces} doesn't hold up: {cycle} cycles \
+             later the tower is {simulated} tall, not {extrapolated}",
+            skip.period
+        ))),
     }
 }
 
+impl Solver17 {
+    /// Print where each of the first [`Solver17::trace`] pieces comes to rest.
+    fn print_trace(&self, jets: &[Jet]) {
+        if self.trace == 0 {
+            return;
+        }
+
+        let mut state = State::new(jets.to_vec());
+        for (i, trace) in state.trace(self.trace).into_iter().enumerate() {
+            let Coord { x, y } = trace.origin;
+            println!(
+                "piece {:>4} settled at ({x}, {y}), tower is {} tall",
+                i + 1,
+                trace.height
+            );
+        }
+    }
+}
+
+/// Where a piece came to rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PieceTrace {
+    /// The bottom-left corner of the piece's bounding box.
+    origin: Coord,
+    /// How tall the tower was once the piece settled.
+    height: usize,
+}
+
 #[derive(Default, Debug)]
 struct State {
+    /// The jet pattern, which repeats forever.
+    jets: Vec<Jet>,
+    /// Print out every movement of every piece.
+    verbose: bool,
     /// Keeps track of how many jets have blown, in total
     jet_count: usize,
     /// Keeps track of how many pieces have started to fall
@@ -261,52 +281,116 @@ struct State {
     color_map: Vec<[Rgb; CHAMBER_WIDTH]>,
     /// The current origin of the currently-falling piece.
     curr: Coord,
-    /// A map to keep track of seen combinations of `PIECES` and `jets` indices
-    /// so that the simulation can be fast-forwarded.
-    seen: HashMap<SeenKey, SeenState>,
     /// The number of pieces added by repeats.
     added_by_repeats: usize,
 }
 
-/// The combination of the index into `PIECES` and the index into `jets`.
+/// The combination of the index into `PIECES` and the index into `jets`, which decides how the
+/// next piece falls.
 #[derive(Debug, Default, PartialEq, Eq, Hash)]
 struct SeenKey {
     piece_index: usize,
     jet_index: usize,
 }
 
-impl From<(usize, usize)> for SeenKey {
-    fn from((piece_index, jet_index): (usize, usize)) -> Self {
+impl State {
+    fn new(jets: Vec<Jet>) -> Self {
         Self {
-            piece_index,
-            jet_index,
+            jets,
+            ..Self::default()
         }
     }
-}
 
-/// For keeping track of the state whenever unique combinations of [`SeenKey::piece_index`]
-/// and [`SeenKey::jet_index`] arise.
-#[derive(Default, Debug)]
-struct SeenState {
-    /// A count of how many times this [`SeenKey`] has been seen.
-    seen_key_count: usize,
-    /// The [`State::piece_count`] the last time this key was seen.
-    piece_count: usize,
-    /// The [`State::top`] the last time this key was seen.
-    top: usize,
-}
+    /// Drop the next piece, letting the jets push it around until it comes to rest.
+    ///
+    /// Doesn't count the piece in [`State::piece_count`], so that callers can look at the state
+    /// first.
+    fn drop_piece(&mut self) -> PieceTrace {
+        // New piece starts falling
+        let piece = &PIECES[self.piece_count % PIECES.len()];
+        self.curr.x = 2;
+        self.curr.y = self.top + 3;
+
+        if self.verbose {
+            println!("== Piece {} begins falling ==", self.piece_count + 1);
+            println!("{self}");
+        }
 
-impl From<(usize, usize, usize)> for SeenState {
-    fn from((seen_key_count, piece_count, top): (usize, usize, usize)) -> Self {
-        Self {
-            seen_key_count,
-            piece_count,
-            top,
+        loop {
+            // jet fires
+            let jet = self.jets[self.jet_count % self.jets.len()];
+            let new_curr = match jet {
+                Jet::Left => (self.curr.x.saturating_sub(1), self.curr.y).into(),
+                Jet::Right => (self.curr.x + 1, self.curr.y).into(),
+            };
+            if self.is_new_curr_valid(&new_curr, piece) {
+                self.curr = new_curr;
+            }
+            self.jet_count += 1;
+
+            if self.verbose {
+                println!("Jet of gas pushes piece {jet} :",);
+                println!("{self}");
+            }
+
+            // piece falls
+            let new_curr = (self.curr.x, self.curr.y.saturating_sub(1)).into();
+            if self.curr.y == 0 || !self.is_new_curr_valid(&new_curr, piece) {
+                break;
+            }
+            self.curr = new_curr;
+
+            if self.verbose {
+                println!("Piece falls 1 unit:");
+                println!("{self}");
+            }
+        }
+
+        // piece settles
+        for offset in piece.coords {
+            let Coord { x, y } = self.curr + *offset;
+
+            while self.map.len() <= y {
+                self.map.push(0);
+                self.color_map.push([Rgb(255, 255, 255); CHAMBER_WIDTH]);
+            }
+
+            self.map[y] |= pack_x_coord(x);
+            self.color_map[y][x] = piece.color;
+
+            self.top = self.top.max(y + 1);
+        }
+
+        PieceTrace {
+            origin: self.curr,
+            height: self.top,
         }
     }
-}
 
-impl State {
+    /// Drop the next `count` pieces, and trace where each of them came to rest.
+    fn trace(&mut self, count: usize) -> Vec<PieceTrace> {
+        (0..count)
+            .map(|_| {
+                let trace = self.drop_piece();
+                self.piece_count += 1;
+                trace
+            })
+            .collect()
+    }
+
+    /// Draw the settled rocks in their colors, one pixel per block, with the floor at the bottom.
+    fn raster(&self) -> Raster {
+        let mut raster = Raster::new(CHAMBER_WIDTH, self.top, Rgb(0, 0, 0));
+        for (y, (row, row_colors)) in self.map.iter().zip(&self.color_map).enumerate() {
+            for (x, &color) in row_colors.iter().enumerate() {
+                if row & pack_x_coord(x) != 0 {
+                    raster.set(x, self.top.saturating_sub(y + 1), color);
+                }
+            }
+        }
+        raster
+    }
+
     /// Determine if a new `curr` coordinate would be valid if the state were
     /// to use it.
     fn is_new_curr_valid(&mut self, new_curr: &Coord, piece: &Piece) -> bool {
@@ -326,137 +410,173 @@ impl State {
 impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let curr_piece = &PIECES[self.piece_count % PIECES.len()];
+        let curr_coords = curr_piece.coords.iter().map(|offset| self.curr + *offset);
+
+        let glyphs = glyphs::glyphs();
+        let frame = |s: &str| {
+            if color::enabled() {
+                s.fg::<CustomColor<100, 100, 100>>().to_string()
+            } else {
+                s.to_string()
+            }
+        };
 
         // Write the top of the display box
-        writeln!(f, "{}", "╭───────╮".fg::<CustomColor<100, 100, 100>>())?;
-
-        // Blocks are drawn using half-height unicode block characters so that
-        // they appear square-ish in the terminal.
-        // Unicode block characters for copy-pasting:
-        // - block in upper half of cell:                               ▀
-        // - block in lower half of cell:                               ▄
-        // - 2 blocks, one in upper half and one in lower half of cell: █
-        // - current piece's block in upper half of cell:               ⠛
-        // - current piece's block in lower half of cell:               ⣤
-        // - current piece's 2 blocks taking up entire cell:            ⣿
-        // - current piece's block in upper half of cell, existing
-        //   block in lower half of cell (an unfortunate compramise):   ░
-
-        #[derive(Clone, Copy)]
-        enum HalfCell {
-            Air,
-            Existing,
-            Current,
-        }
-
-        // First, copy the current map and convert each cell to an enum value
-        let mut print: Vec<Vec<_>> = self
-            .map
-            .iter()
-            .zip(self.color_map.iter())
-            .map(|(row, row_colors)| {
-                (0..CHAMBER_WIDTH)
-                    .into_iter()
-                    .map(|x| {
-                        if (row & pack_x_coord(x)) == 0 {
-                            (HalfCell::Air, row_colors[x])
-                        } else {
-                            (HalfCell::Existing, row_colors[x])
-                        }
-                    })
-                    .collect()
-            })
-            .collect();
-
-        // Next, figure out how many rows we need to add to `print` to make
-        // space for the current piece, add them, and add the current piece.
-        let mut local_top = self.top;
-        for offset in curr_piece.coords {
-            let Coord { x, y } = self.curr + *offset;
-
-            // Make sure we have enough air cells
-            while print.len() <= y {
-                print.push(vec![(HalfCell::Air, Rgb(255, 255, 255)); CHAMBER_WIDTH]);
+        writeln!(f, "{}", frame(&glyphs.box_top(CHAMBER_WIDTH)))?;
+
+        // Blocks are drawn two to a character so that they appear square-ish in the terminal,
+        // which needs an even number of rows. Settled blocks are solid, and the current piece is
+        // dotted so that it stands out.
+        let local_top = curr_coords
+            .clone()
+            .map(|Coord { y, .. }| y + 1)
+            .fold(self.top, usize::max)
+            .next_multiple_of(2);
+        let mut canvas = PixelCanvas::new(CHAMBER_WIDTH, local_top);
+
+        // The chamber's y grows upwards, and the canvas's grows downwards.
+        let pixel = |Coord { x, y }: Coord| (x as i64, (local_top - 1 - y) as i64);
+
+        for (y, (row, row_colors)) in self.map.iter().zip(&self.color_map).enumerate() {
+            for (x, &color) in row_colors.iter().enumerate() {
+                if row & pack_x_coord(x) != 0 {
+                    canvas.set(pixel(Coord { x, y }), Ink::solid(color));
+                }
             }
-
-            // Convert the piece's coordinate to a piece cell
-            print[y][x] = (HalfCell::Current, curr_piece.color);
-
-            // Update the current local top count
-            local_top = local_top.max(y + 1);
         }
-
-        // Make sure we have an even amount of cells, adding a row of air if we don't
-        if local_top % 2 != 0 {
-            local_top += 1;
-            print.push(vec![(HalfCell::Air, Rgb(255, 255, 255)); CHAMBER_WIDTH]);
+        for coord in curr_coords {
+            canvas.set(pixel(coord), Ink::dotted(curr_piece.color));
         }
 
-        // Actually print out the pieces. We use sliding windows of size 2 so that
-        // we can represent pieces using half-height block and braille characters.
-        let iter = (0..local_top).rev().tuples::<(_, _)>();
-
-        for (top_half_row, bottom_half_row) in iter {
-            let mut row_str = String::with_capacity(3 * 7); // 7 chars @ max 3 bytes per UTF-8 char
-
-            for col in 0..CHAMBER_WIDTH {
-                let (top_half, top_half_color) = &print[top_half_row][col];
-                let (bottom_half, bottom_half_color) = &print[bottom_half_row][col];
-
-                let chr = match (top_half, bottom_half) {
-                    (HalfCell::Air, HalfCell::Air) => " ".to_string(),
-                    (HalfCell::Air, HalfCell::Existing) => {
-                        format!("{}", "▄".color(*bottom_half_color))
-                    }
-                    (HalfCell::Existing, HalfCell::Air) => {
-                        format!("{}", "▀".color(*top_half_color))
-                    }
-                    (HalfCell::Existing, HalfCell::Existing) => {
-                        if top_half_color == bottom_half_color {
-                            format!("{}", "█".color(*bottom_half_color))
-                        } else {
-                            format!(
-                                "{}",
-                                "▄".color(*bottom_half_color).on_color(*top_half_color)
-                            )
-                        }
-                    }
-                    (HalfCell::Air, HalfCell::Current) => {
-                        format!("{}", "⣤".color(*bottom_half_color))
-                    }
-                    (HalfCell::Current, HalfCell::Air) => format!("{}", "⠛".color(*top_half_color)),
-                    (HalfCell::Current, HalfCell::Current) => {
-                        if top_half_color == bottom_half_color {
-                            format!("{}", "⣿".color(*bottom_half_color))
-                        } else {
-                            format!(
-                                "{}",
-                                "⣤".color(*bottom_half_color).on_color(*top_half_color)
-                            )
-                        }
-                    }
-                    (HalfCell::Existing, HalfCell::Current) => format!(
-                        "{}",
-                        "⣤".color(*bottom_half_color).on_color(*top_half_color)
-                    ),
-                    (HalfCell::Current, HalfCell::Existing) => format!(
-                        "{}",
-                        "⠛".color(*top_half_color).on_color(*bottom_half_color)
-                    ),
-                };
-
-                row_str.push_str(&chr);
-            }
-
-            writeln!(f, "{0}{row_str}{0}", "│".fg::<CustomColor<100, 100, 100>>())?;
+        let side = frame(&glyphs.vertical.to_string());
+        for row_str in canvas.half_block_rows() {
+            writeln!(f, "{side}{row_str}{side}")?;
         }
 
         // Write the bottom of the display box
-        write!(f, "{}", "╰───────╯".fg::<CustomColor<100, 100, 100>>())
+        write!(f, "{}", frame(&glyphs.box_bottom(CHAMBER_WIDTH)))
     }
 }
 
 /// Packs an x coordinate into a u8
+impl Simulation for State {
+    /// Drop a single piece. The tower can always grow taller, so this never finishes.
+    fn step(&mut self) -> StepResult {
+        self.drop_piece();
+        self.piece_count += 1;
+        if self.verbose {
+            println!();
+        }
+        StepResult::Continue
+    }
+
+    fn reset(&mut self) {
+        let verbose = self.verbose;
+        *self = Self::new(std::mem::take(&mut self.jets));
+        self.verbose = verbose;
+    }
+
+    fn metrics(&self) -> serde_json::Value {
+        json!({
+            "pieces": self.piece_count,
+            "height": self.top + self.added_by_repeats,
+            "jets": self.jet_count,
+        })
+    }
+}
+
 const fn pack_x_coord(x: usize) -> u8 {
     1_u8.wrapping_shl(x as _) & CHAMBER_WIDTH_MASK
 }
+
+super::challenge_solver_test_boilerplate! {
+    Solver17::default();
+    17 => {
+        a as usize,
+        b as usize,
+    }
+
+    #[test]
+    fn first_pieces_settle_like_the_puzzle_description() {
+        let jets = Jet::parse_all(SAMPLE_INPUT).unwrap();
+        let traces = State::new(jets).trace(10);
+
+        // Read off of the picture of the tower after ten rocks have fallen.
+        let settled: Vec<_> = traces
+            .iter()
+            .map(|trace| ((trace.origin.x, trace.origin.y), trace.height))
+            .collect();
+        assert_eq!(
+            settled,
+            [
+                ((2, 0), 1),
+                ((2, 1), 4),
+                ((0, 3), 6),
+                ((4, 3), 7),
+                ((4, 7), 9),
+                ((1, 9), 10),
+                ((1, 10), 13),
+                ((3, 12), 15),
+                ((4, 13), 17),
+                ((0, 12), 17),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_the_skip_against_dropping_every_piece() -> Result<(), SolverError> {
+        let mut solver = Solver17::default();
+        let mut config = SolverConfig {
+            check_consistency: true,
+            ..SolverConfig::default()
+        };
+        config.params.insert("consistency_cycles", "5");
+        solver.configure(&config).unwrap();
+        assert_eq!(solver.consistency_cycles, Some(5));
+        let [_, expected] = sample_answers();
+        assert_eq!(Some(solver.solve_b(&mut Cursor::new(SAMPLE_INPUT))?), expected);
+
+        // The sample's tower grows by 53 every 35 pieces.
+        let jets = Jet::parse_all(SAMPLE_INPUT).unwrap();
+        let mut state = State::new(jets.clone());
+        state.trace(100);
+        let skip = |gain| Skip {
+            last: crate::cycledetect::Sighting {
+                count: 2,
+                step: 64,
+                metric: 0,
+            },
+            period: 35,
+            gain,
+            repeats: 0,
+            steps: 0,
+            metric: 0,
+        };
+        check_skip(&jets, &skip(53), 100, state.top, 3)?;
+        let err = check_skip(&jets, &skip(54), 100, state.top, 3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "The cycle of 35 pieces found after piece 100 doesn't hold up: 1 cycles later the \
+                 tower is {} tall, not {}",
+                state.top + 53,
+                state.top + 54
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn draws_the_tower() {
+        let mut state = State::new(Jet::parse_all(SAMPLE_INPUT).unwrap());
+        state.trace(10);
+        // The eleventh rock has just appeared, and is drawn dotted.
+        state.curr = (2, state.top + 3).into();
+
+        // Where two rocks of different colors share a character, the top one is drawn as the
+        // background color, so it's left out of the plain picture.
+        crate::snapshot::unicode_glyphs();
+        let picture = crate::snapshot::strip_ansi(&state.to_string());
+        insta::assert_snapshot!("tower_after_ten_rocks", picture);
+    }
+}
@@ -2,7 +2,9 @@ use std::{fmt, str::FromStr};
 
 use owo_colors::Rgb;
 
-#[derive(Debug)]
+use crate::error::SolverError;
+
+#[derive(Debug, Clone, Copy)]
 pub enum Jet {
     Left,
     Right,
@@ -39,6 +41,12 @@ pub struct ParseJetError {
     found: String,
 }
 
+impl From<ParseJetError> for SolverError {
+    fn from(err: ParseJetError) -> Self {
+        Self::InvalidInput(err.to_string())
+    }
+}
+
 impl fmt::Display for Jet {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -1,230 +1,462 @@
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    io::BufRead,
-};
+mod surface;
+
+use std::{collections::HashSet, fmt::Write, io::BufRead, str::FromStr};
 
-use color_eyre::eyre::{eyre, Context};
-use itertools::Itertools;
 use nalgebra_glm::IVec3;
+use nom::{character::complete::i32, combinator::map, IResult};
+use nom_supreme::error::ErrorTree;
+use serde_json::json;
+
+use crate::{
+    error::{Context, SolverError},
+    input_iter::for_each_line,
+    mesh::{Mesh, MeshFile},
+    parse::{parse_point3, LineParser, PointFormat},
+    voxel::{Axis, Bounds, Face, VoxelWorld},
+};
+
+use super::{ParamError, Rendering, SolverConfig};
 
 #[derive(Debug, Default)]
-pub struct Solver18;
+pub struct Solver18 {
+    /// Label every trapped pocket of vacuum in part B, and report them in the answer's metadata.
+    pockets: bool,
+    /// Save the droplet's outside surface as a mesh, with `--export-mesh PATH` or
+    /// `--param export_mesh=PATH`. The path's extension picks the format, either `.obj` or `.stl`.
+    export_mesh: Option<MeshFile>,
+    algorithm: Algorithm,
+    /// Leave out cubes that don't parse, instead of failing.
+    skip_bad_lines: bool,
+}
+
+/// How part B finds the faces that touch the outside.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    /// Flood fill the vacuum around the droplet, and count the faces that touch it.
+    #[default]
+    FloodFill,
+    /// Walk over the droplet's faces, without filling anything in. See [`surface`].
+    SurfaceWalk,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flood" => Ok(Self::FloodFill),
+            "surface" => Ok(Self::SurfaceWalk),
+            _ => Err("expected `flood` or `surface`".to_string()),
+        }
+    }
+}
 
 impl super::ChallengeSolver for Solver18 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         18
     }
 
+    super::sample_methods!();
+
+    fn algorithms(&self) -> &'static [&'static str] {
+        &["flood", "surface"]
+    }
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.pockets = config.params.get_or("pockets", false)?;
+        self.export_mesh = config.params.get("export_mesh")?;
+        self.algorithm = config.params.get_or("algorithm", Algorithm::default())?;
+        self.skip_bad_lines = config.skip_bad_lines;
+        Ok(())
+    }
+
+    fn gallery(&self, input: &str) -> Option<Result<Vec<Rendering>, SolverError>> {
+        Some(
+            read_droplet(&mut input.as_bytes(), false).map(|(droplet, bounds, _)| {
+                let (_, regions) = split_vacuum(&droplet, bounds);
+                vec![Rendering::text("slices", slices(&droplet, &regions))]
+            }),
+        )
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let (world, world_bounds) =
-            parse_input(input).wrap_err("Could not parse challenge input to a set of points")?;
+        let (droplet, bounds, skipped) = read_droplet(input, self.skip_bad_lines)?;
 
-        println!("world bounds: {world_bounds:#?}");
+        println!("world bounds: {bounds:#?}");
 
-        let surface_area = calc_surface_area(&world);
+        let surface_area = calc_surface_area(&droplet);
         println!("surface area = {surface_area}");
 
-        Ok(Box::new(()))
+        if let Some(file) = &self.export_mesh {
+            let faces = match self.algorithm {
+                Algorithm::FloodFill => {
+                    let (outside, _) = split_vacuum(&droplet, bounds);
+                    droplet.faces_touching(&outside).collect()
+                }
+                Algorithm::SurfaceWalk => surface::exterior_surface(&droplet, bounds).faces,
+            };
+            save_mesh(&faces, file)?;
+        }
+
+        Ok(super::Answer::new(surface_area).with_skipped_lines(skipped))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let (mut world, mut world_bounds) =
-            parse_input(input).wrap_err("Could not parse challenge input to a set of points")?;
-
-        // Fill in the world with:
-        // - Voxel::Air, 1 cell outside of the world's current bounding box, increasing the world's
-        //   bounding box by 1 cell in all directions
-        // - Voxel::Vacuum in all positions not taken up my Voxel::Lava or Voxel::Air
-
-        // First, preemptively grow the world_bounds by 1 in all directions
-        world_bounds.x_max += 1;
-        world_bounds.x_min -= 1;
-
-        world_bounds.y_max += 1;
-        world_bounds.y_min -= 1;
-
-        world_bounds.z_max += 1;
-        world_bounds.z_min -= 1;
-
-        println!("world bounds: {world_bounds:#?}");
-
-        // Reserve additional memory for the World HashMap to grow
-        let voxel_count = (world_bounds.x_max - world_bounds.x_min + 1)
-            * (world_bounds.y_max - world_bounds.y_min + 1)
-            * (world_bounds.z_max - world_bounds.z_min + 1);
-        let voxel_count: usize = voxel_count.try_into()?;
-        world.reserve(voxel_count.saturating_sub(world.capacity()));
-
-        // Iterate through all positions
-        for ((x, y), z) in (world_bounds.x_min..=world_bounds.x_max)
-            .cartesian_product(world_bounds.y_min..=world_bounds.y_max)
-            .cartesian_product(world_bounds.z_min..=world_bounds.z_max)
-        {
-            let is_perimeter = (x == world_bounds.x_max || x == world_bounds.x_min)
-                || (y == world_bounds.y_max || y == world_bounds.y_min)
-                || (z == world_bounds.z_max || z == world_bounds.z_min);
-
-            if is_perimeter {
-                // If we're on the world's perimeter, insert Voxel::Air
-                match world.entry([x, y, z].into()) {
-                    Entry::Occupied(_) => unreachable!(
-                        "A voxel already exists in perimeter position ({x}, {y}, {z}), \
-                         which shouldn't be possible"
-                    ),
-                    Entry::Vacant(entry) => {
-                        entry.insert(Voxel::Air);
-                    }
-                }
-            } else {
-                // Otherwise, insert Voxel::Vacuum if the entry is unoccupied
-                world.entry([x, y, z].into()).or_insert(Voxel::Vacuum);
-            }
+        let (droplet, bounds, skipped) = read_droplet(input, self.skip_bad_lines)?;
+        println!("world bounds: {bounds:#?}");
+
+        if self.algorithm == Algorithm::SurfaceWalk {
+            return Ok(self
+                .walk_surface(&droplet, bounds)?
+                .with_skipped_lines(skipped));
         }
 
-        // Begin simulating a cellular automaton.
-        // Each loop, iterate through all Voxel::Vacuum's. If a Voxel::Vacuum is
-        // adjacent to a Voxel::Air, turn it into a Voxel::Air.
-        // Stop the loop when we detect that the no changes are made to the world
-        // during a cycle.
-        loop {
-            let mut new_air_coords = Vec::new();
-
-            #[rustfmt::skip]
-            let neighbors: [IVec3; 6] = [
-                [1, 0, 0].into(), [-1, 0, 0].into(),
-                [0, 1, 0].into(), [0, -1, 0].into(),
-                [0, 0, 1].into(), [0, 0, -1].into(),
-            ];
-
-            for (coord, _) in world.iter().filter(|(_, voxel)| **voxel == Voxel::Vacuum) {
-                'inner: for neighbor in &neighbors {
-                    let neighbor_coord = coord + neighbor;
-                    if let Some(&Voxel::Air) = world.get(&neighbor_coord) {
-                        new_air_coords.push(*coord);
-                        break 'inner;
-                    }
-                }
-            }
+        let (outside, regions) = split_vacuum(&droplet, bounds);
 
-            for coord in &new_air_coords {
-                world.insert(*coord, Voxel::Air);
-            }
+        // Finally, calculate the surface area of the droplet, only counting the faces that touch
+        // the outside.
+        let surface_area = count_faces(&droplet, &outside);
+        println!("surface area = {surface_area}");
 
-            if new_air_coords.is_empty() {
-                break;
-            }
+        if let Some(file) = &self.export_mesh {
+            save_mesh(&droplet.faces_touching(&outside).collect::<Vec<_>>(), file)?;
         }
 
-        // Finally, calculate the surface area of the droplet, excluding any droplet faces that
-        // are adjacent to Voxel::Vacuum or Voxel::Lava.
-        let surface_area = calc_surface_area(&world);
+        let filled = outside.len() + regions.iter().map(HashSet::len).sum::<usize>();
+        println!("filled {filled} cells of vacuum");
+        let mut answer = super::Answer::new(surface_area)
+            .with_metadata("cells_filled", filled)
+            .with_skipped_lines(skipped);
+        if self.pockets {
+            let pockets = label_pockets(&droplet, &regions);
+            let internal_surface_area = pockets.iter().map(|p| p.surface_area).sum::<usize>();
+            println!(
+                "{} trapped pocket(s), with {internal_surface_area} internal surface area",
+                pockets.len()
+            );
+
+            answer = answer.with_metadata(
+                "pockets",
+                json!({
+                    "count": pockets.len(),
+                    "exterior_surface_area": surface_area,
+                    "internal_surface_area": internal_surface_area,
+                    "pockets": pockets.iter().map(|pocket| json!({
+                        "volume": pocket.volume,
+                        "surface_area": pocket.surface_area,
+                    })).collect::<Vec<_>>(),
+                }),
+            );
+        }
+
+        Ok(answer)
+    }
+}
+
+impl Solver18 {
+    /// Solve part B with [`Algorithm::SurfaceWalk`].
+    fn walk_surface(&self, droplet: &Droplet, bounds: Bounds) -> super::ChallengeSolverResult {
+        if self.pockets {
+            return Err(SolverError::Unsupported(
+                "Measuring pockets needs their vacuum filled in, so it only works with \
+                 `--param algorithm=flood`"
+                    .to_string(),
+            ));
+        }
+
+        let exterior = surface::exterior_surface(droplet, bounds);
+        let surface_area = exterior.faces.len();
         println!("surface area = {surface_area}");
+        println!("walked {} faces", exterior.walked);
+
+        if let Some(file) = &self.export_mesh {
+            save_mesh(&exterior.faces, file)?;
+        }
 
-        Ok(Box::new(()))
+        Ok(super::Answer::new(surface_area).with_metadata("faces_walked", exterior.walked))
     }
 }
 
-type World = HashMap<IVec3, Voxel>;
+fn save_mesh(faces: &[Face], file: &MeshFile) -> Result<(), SolverError> {
+    let mesh = exterior_mesh(faces);
+    mesh.save(file)?;
+    println!(
+        "Saved {} mesh with {} triangles to {}",
+        file.format,
+        mesh.triangle_count(),
+        file.path.display()
+    );
+    Ok(())
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-enum Voxel {
-    Lava,
-    Air,
-    Vacuum,
+/// The cubes of lava that make up the droplet.
+type Droplet = VoxelWorld<()>;
+
+/// Read the droplet and its bounds, along with how many lines were skipped for not parsing.
+fn read_droplet(
+    input: &mut dyn BufRead,
+    skip_bad_lines: bool,
+) -> Result<(Droplet, Bounds, usize), SolverError> {
+    let (droplet, skipped) = parse_input(input, skip_bad_lines)
+        .context("Could not parse challenge input to a set of points")?;
+    let bounds = droplet
+        .bounds()
+        .ok_or_else(|| SolverError::invalid_input("The droplet doesn't have any cubes in it"))?;
+    Ok((droplet, bounds, skipped))
 }
 
-#[derive(Debug)]
-struct WorldBounds {
-    pub x_min: i32,
-    pub x_max: i32,
+fn parse_input(
+    input: &mut dyn BufRead,
+    skip_bad_lines: bool,
+) -> Result<(Droplet, usize), SolverError> {
+    let mut lines = LineParser::new(skip_bad_lines);
+    let mut points = Vec::new();
+    for_each_line(input, |line| {
+        points.extend(lines.parse(line, parse_point));
+        Ok::<_, std::io::Error>(())
+    })
+    .context("Could not read input file")?;
+
+    let skipped = lines.finish()?;
+    Ok((
+        VoxelWorld::from_cells(points.into_iter().map(|p| (p, ()))),
+        skipped,
+    ))
+}
 
-    pub y_min: i32,
-    pub y_max: i32,
+/// Parse one cube's position, like `2,2,2`.
+fn parse_point(i: &str) -> IResult<&str, IVec3, ErrorTree<&str>> {
+    map(parse_point3(PointFormat::COMMAS, i32), |(x, y, z)| {
+        IVec3::new(x, y, z)
+    })(i)
+}
 
-    pub z_min: i32,
-    pub z_max: i32,
+/// Split the vacuum around the droplet into the cells connected to the outside, and the pockets
+/// trapped inside of the droplet.
+fn split_vacuum(droplet: &Droplet, bounds: Bounds) -> (HashSet<IVec3>, Vec<HashSet<IVec3>>) {
+    // Grow the bounds by one cell in all directions, so that there's always a path of empty
+    // cells around the outside of the droplet. Every empty cell then either connects to the
+    // outside, or is part of a pocket of vacuum trapped inside of the droplet.
+    let bounds = bounds.grown(1);
+    let mut regions = droplet.components(bounds, |cube| cube.is_none());
+    let outside = regions.remove(0);
+    debug_assert!(outside.contains(&bounds.min));
+    (outside, regions)
 }
 
-fn parse_input(input: &mut dyn BufRead) -> color_eyre::Result<(World, WorldBounds)> {
-    let mut points = World::new();
+/// A connected region of vacuum that's sealed off from the outside by lava.
+#[derive(Debug, PartialEq, Eq)]
+struct Pocket {
+    /// How many cells the pocket takes up.
+    volume: usize,
+    /// How many lava faces border the pocket. These count towards part A's surface area, but not
+    /// part B's.
+    surface_area: usize,
+}
 
-    let mut bounds = WorldBounds {
-        x_min: i32::MAX,
-        x_max: i32::MIN,
+/// Measure each of the `regions` of vacuum trapped in the droplet, ordered from largest to
+/// smallest.
+fn label_pockets(droplet: &Droplet, regions: &[HashSet<IVec3>]) -> Vec<Pocket> {
+    let mut pockets = regions
+        .iter()
+        .map(|region| Pocket {
+            volume: region.len(),
+            surface_area: count_faces(droplet, region),
+        })
+        .collect::<Vec<_>>();
+
+    pockets.sort_unstable_by_key(|pocket| std::cmp::Reverse(pocket.volume));
+    pockets
+}
+
+/// How many faces of the droplet touch one of the empty `cells`.
+fn count_faces(droplet: &Droplet, cells: &HashSet<IVec3>) -> usize {
+    droplet.faces_touching(cells).count()
+}
 
-        y_min: i32::MAX,
-        y_max: i32::MIN,
+/// Draw the droplet one layer at a time from the bottom up, with lava as `#`, pockets of vacuum
+/// trapped inside of it as `o`, and the outside as `.`.
+fn slices(droplet: &Droplet, pockets: &[HashSet<IVec3>]) -> String {
+    let cells = droplet.iter().map(|(position, ())| (position, '#'));
+    let pockets = pockets.iter().flatten().map(|&position| (position, 'o'));
+    let world = VoxelWorld::from_cells(cells.chain(pockets));
 
-        z_min: i32::MAX,
-        z_max: i32::MIN,
+    let mut text = String::new();
+    let Some(bounds) = world.bounds() else {
+        return text;
     };
+    for z in bounds.min.z..=bounds.max.z {
+        let slice = world.slice(Axis::Z, z).unwrap();
+        if z > bounds.min.z {
+            text.push('\n');
+        }
+        writeln!(text, "z = {z}").unwrap();
+        for row in slice.data.chunks(slice.width) {
+            text.extend(row.iter().map(|cell| cell.unwrap_or('.')));
+            text.push('\n');
+        }
+    }
+    text
+}
 
-    for line in input.lines() {
-        let line = line.wrap_err("Could not read line from input file to string")?;
-
-        let mut split = line.split(',');
-
-        let (x_str, y_str, z_str) = (
-            split
-                .next()
-                .ok_or_else(|| eyre!("Could not get x component from line {}", &line))?,
-            split
-                .next()
-                .ok_or_else(|| eyre!("Could not get y component from line {}", &line))?,
-            split
-                .next()
-                .ok_or_else(|| eyre!("Could not get z component from line {}", &line))?,
-        );
+/// The `faces` of the droplet that touch the outside, as a closed surface with every triangle
+/// facing outwards.
+fn exterior_mesh(faces: &[Face]) -> Mesh {
+    let mut mesh = Mesh::new();
+    for face in faces {
+        mesh.add_quad(face.corners());
+    }
+    mesh
+}
 
-        let (x, y, z): (i32, i32, i32) = (
-            x_str
-                .parse()
-                .wrap_err_with(|| format!("Could not parse as x component: {x_str}"))?,
-            y_str
-                .parse()
-                .wrap_err_with(|| format!("Could not parse as y component: {y_str}"))?,
-            z_str
-                .parse()
-                .wrap_err_with(|| format!("Could not parse as z component: {z_str}"))?,
-        );
+/// How many faces of the droplet don't touch another cube, whether they face the outside or not.
+fn calc_surface_area(droplet: &Droplet) -> usize {
+    droplet
+        .iter()
+        .flat_map(|(cube, _)| droplet.neighbors(cube))
+        .filter(|(_, neighbor)| neighbor.is_none())
+        .count()
+}
 
-        points.insert([x, y, z].into(), Voxel::Lava);
+super::challenge_solver_test_boilerplate! {
+    Solver18::default();
+    18 => {
+        a as usize,
+        b as usize,
+    }
 
-        bounds.x_min = bounds.x_min.min(x);
-        bounds.x_max = bounds.x_max.max(x);
+    use proptest::prelude::*;
 
-        bounds.y_min = bounds.y_min.min(y);
-        bounds.y_max = bounds.y_max.max(y);
+    use crate::parse::round_trip::{self, EXTREME_I32};
 
-        bounds.z_min = bounds.z_min.min(z);
-        bounds.z_max = bounds.z_max.max(z);
-    }
+    #[test]
+    fn cubes_survive_being_written_and_parsed() {
+        let extremes = EXTREME_I32
+            .into_iter()
+            .flat_map(|x| EXTREME_I32.into_iter().map(move |y| IVec3::new(x, y, x)));
 
-    Ok((points, bounds))
-}
+        round_trip::check(
+            any::<(i32, i32, i32)>().prop_map(|(x, y, z)| IVec3::new(x, y, z)),
+            extremes,
+            |written, cube| PointFormat::COMMAS.write3(written, (cube.x, cube.y, cube.z)),
+            |written| match parse_point(written) {
+                Ok(("", cube)) => Some(cube),
+                _ => None,
+            },
+        );
+        assert!(parse_point("2147483648,0,0").is_err());
+    }
 
-fn calc_surface_area(world: &World) -> i32 {
-    let mut area = 0;
+    #[test]
+    fn slices_show_the_trapped_pocket() {
+        let (droplet, bounds, _) = read_droplet(&mut Cursor::new(SAMPLE_INPUT), false).unwrap();
+        let (_, regions) = split_vacuum(&droplet, bounds);
+        let text = slices(&droplet, &regions);
 
-    for (point, _) in world.iter().filter(|(_, voxel)| **voxel == Voxel::Lava) {
-        #[rustfmt::skip]
-        let neighbors: [IVec3; 6] = [
-            [1, 0, 0].into(), [-1, 0, 0].into(),
-            [0, 1, 0].into(), [0, -1, 0].into(),
-            [0, 0, 1].into(), [0, 0, -1].into(),
-        ];
+        assert!(text.starts_with("z = 1\n...\n.#.\n...\n"), "{text}");
+        assert!(text.contains("z = 5\n.#.\n#o#\n.#.\n"), "{text}");
+        assert_eq!(text.matches('#').count(), droplet.len());
+    }
 
-        for neighbor in &neighbors {
-            let coord = point + neighbor;
-            let neighbor_voxel = world.get(&coord);
+    #[test]
+    fn labels_trapped_pockets() {
+        let mut solver = Solver18 {
+            pockets: true,
+            ..Default::default()
+        };
+        let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT)).unwrap();
+
+        // Part A finds 64 sides, and part B 58: the difference is the single trapped cell.
+        assert_eq!(
+            answer.metadata()["pockets"],
+            json!({
+                "count": 1,
+                "exterior_surface_area": 58,
+                "internal_surface_area": 6,
+                "pockets": [{ "volume": 1, "surface_area": 6 }],
+            })
+        );
+    }
 
-            // Only include empty adjacent integer cells in the surface area calculation
-            // (OR cells that contain only Voxel::Air, and never Voxel::Lava)
-            if neighbor_voxel.is_none() || matches!(neighbor_voxel, Some(Voxel::Air)) {
-                area += 1;
+    #[test]
+    fn walking_the_surface_agrees_with_flood_filling() {
+        let mut solver = Solver18 {
+            algorithm: Algorithm::SurfaceWalk,
+            ..Default::default()
+        };
+        let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT)).unwrap();
+        assert_eq!(answer.metadata()["faces_walked"], 64);
+        let [_, expected] = sample_answers();
+        assert_eq!(Some(answer), expected);
+
+        // Random clumps of cubes have plenty of separate pieces, pockets, and pieces floating in
+        // the pockets.
+        let mut rng = crate::solver::Scrambler::new(18);
+        for _ in 0..40 {
+            let size = 3 + rng.below(6) as i32;
+            let fullness = 1 + rng.below(4);
+            let cubes = Bounds {
+                min: IVec3::zeros(),
+                max: IVec3::repeat(size - 1),
             }
+            .iter()
+            .filter(|_| rng.below(5) < fullness)
+            .map(|cube| (cube, ()));
+            let droplet = VoxelWorld::from_cells(cubes);
+            let Some(bounds) = droplet.bounds() else {
+                continue;
+            };
+
+            let (outside, _) = split_vacuum(&droplet, bounds);
+            let mut expected = droplet.faces_touching(&outside).collect::<Vec<_>>();
+            let mut walked = surface::exterior_surface(&droplet, bounds).faces;
+            let key = |face: &Face| {
+                (
+                    face.voxel.as_slice().to_vec(),
+                    face.normal.as_slice().to_vec(),
+                )
+            };
+            expected.sort_by_key(key);
+            walked.sort_by_key(key);
+            assert_eq!(walked, expected);
         }
+
+        solver.pockets = true;
+        let err = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT)).unwrap_err();
+        assert!(matches!(err, SolverError::Unsupported(_)));
     }
 
-    area
+    #[test]
+    fn exports_a_closed_exterior_mesh() {
+        let (droplet, bounds, _) = read_droplet(&mut Cursor::new(SAMPLE_INPUT), false).unwrap();
+        let (outside, _) = split_vacuum(&droplet, bounds);
+        let mesh = exterior_mesh(&droplet.faces_touching(&outside).collect::<Vec<_>>());
+        assert_eq!(mesh.triangle_count(), 58 * 2);
+
+        // Every edge of a closed surface with consistent winding is walked once in each
+        // direction, by the triangles on either side of it.
+        let mut edges = std::collections::HashMap::<_, i32>::new();
+        for [a, b, c] in mesh.triangles() {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                *edges.entry((from, to)).or_default() += 1;
+                *edges.entry((to, from)).or_default() -= 1;
+            }
+        }
+        assert!(edges.values().all(|&count| count == 0));
+
+        let path = std::env::temp_dir().join(format!("aoc2022-droplet-{}.stl", std::process::id()));
+        let mut config = SolverConfig::default();
+        config
+            .params
+            .insert("export_mesh", path.to_string_lossy().into_owned());
+        let mut solver = Solver18::default();
+        solver.configure(&config).unwrap();
+        solver.solve_b(&mut Cursor::new(SAMPLE_INPUT)).unwrap();
+        let saved = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.len(), 84 + 50 * mesh.triangle_count());
+
+        config.params.insert("export_mesh", "droplet.png");
+        assert!(solver.configure(&config).is_err());
+    }
 }
@@ -0,0 +1,154 @@
+//! Finding the droplet's outside surface by walking from face to face, for
+//! `--param algorithm=surface`.
+//!
+//! Flood filling the space around the droplet takes memory for every empty cell in its bounds,
+//! which grows with the droplet's volume. Walking over its faces only ever keeps track of the faces
+//! themselves, which grow with its surface area instead.
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use nalgebra_glm::IVec3;
+
+use crate::{
+    dsu::DisjointSets,
+    voxel::{Bounds, Face, FACE_NEIGHBORS},
+};
+
+use super::Droplet;
+
+/// The faces of a droplet that touch the outside, and how much walking it took to find them.
+#[derive(Debug)]
+pub(super) struct ExteriorSurface {
+    pub(super) faces: Vec<Face>,
+    /// How many faces were walked over, outside or not.
+    pub(super) walked: usize,
+}
+
+/// Find every face of the droplet that touches the outside, without filling in any vacuum.
+///
+/// The faces that touch each other across an edge, seen from the vacuum that they both touch,
+/// are walked into groups, starting from the droplet's lowest face along x. Each group is one
+/// closed surface of the droplet, either facing the outside, or facing a pocket trapped inside of
+/// it. A ray cast from a group's furthest face along x never crosses the group again, so it either
+/// leaves the droplet's bounds, meaning that the group faces the outside, or hits another group
+/// that faces the same vacuum.
+pub(super) fn exterior_surface(droplet: &Droplet, bounds: Bounds) -> ExteriorSurface {
+    let mut groups = HashMap::new();
+    let mut surfaces: Vec<Vec<Face>> = Vec::new();
+
+    // The droplet's lowest cube along x has nothing below it, so it's where the outside surface
+    // starts.
+    let lowest = droplet
+        .iter()
+        .map(|(cube, _)| cube)
+        .min_by_key(|cube| cube.x);
+    let start = lowest.map(|voxel| Face {
+        voxel,
+        normal: -IVec3::x(),
+    });
+    let exposed = droplet.iter().flat_map(|(voxel, _)| {
+        FACE_NEIGHBORS
+            .into_iter()
+            .map(move |normal| Face { voxel, normal })
+            .filter(|face| droplet.get(face.voxel + face.normal).is_none())
+    });
+    for face in start.into_iter().chain(exposed) {
+        if let Entry::Vacant(entry) = groups.entry(face) {
+            entry.insert(surfaces.len());
+            surfaces.push(walk(droplet, face, surfaces.len(), &mut groups));
+        }
+    }
+
+    let mut same_vacuum = DisjointSets::new(surfaces.len());
+    let mut outside = vec![false; surfaces.len()];
+    for (group, surface) in surfaces.iter().enumerate() {
+        let furthest = surface
+            .iter()
+            .filter(|face| face.normal == IVec3::x())
+            .max_by_key(|face| face.voxel.x)
+            .expect("a closed surface faces every way");
+        match cast_ray(droplet, bounds, furthest.voxel + furthest.normal) {
+            None => outside[group] = true,
+            Some(hit) => {
+                same_vacuum.union(group, groups[&hit]);
+            }
+        }
+    }
+
+    // Every group facing the outside was joined to one whose ray left the bounds.
+    let walked = groups.len();
+    let faces = same_vacuum
+        .components()
+        .filter(|members| members.iter().any(|&group| outside[group]))
+        .flatten()
+        .flat_map(|group| std::mem::take(&mut surfaces[group]))
+        .collect();
+    ExteriorSurface { faces, walked }
+}
+
+/// Every face reachable from `start` by walking across edges, which are labeled as being in
+/// `group` as they're found.
+fn walk(
+    droplet: &Droplet,
+    start: Face,
+    group: usize,
+    groups: &mut HashMap<Face, usize>,
+) -> Vec<Face> {
+    let mut surface = vec![start];
+    let mut stack = vec![start];
+    while let Some(face) = stack.pop() {
+        for direction in FACE_NEIGHBORS {
+            if direction == face.normal || direction == -face.normal {
+                continue;
+            }
+            let next = across_edge(droplet, face, direction);
+            if let Entry::Vacant(entry) = groups.entry(next) {
+                entry.insert(group);
+                surface.push(next);
+                stack.push(next);
+            }
+        }
+    }
+    surface
+}
+
+/// The face on the other side of `face`'s edge towards `direction`, going around the vacuum that
+/// `face` touches.
+fn across_edge(droplet: &Droplet, face: Face, direction: IVec3) -> Face {
+    let Face { voxel, normal } = face;
+    if droplet.get(voxel + normal + direction).is_some() {
+        // The edge is in a corner, and the next face looks back the other way.
+        Face {
+            voxel: voxel + normal + direction,
+            normal: -direction,
+        }
+    } else if droplet.get(voxel + direction).is_some() {
+        // The neighboring cube's face is level with this one.
+        Face {
+            voxel: voxel + direction,
+            normal,
+        }
+    } else {
+        // The edge sticks out, so the surface wraps around this cube.
+        Face {
+            voxel,
+            normal: direction,
+        }
+    }
+}
+
+/// The first face that a ray from the empty cell at `from` hits, going up along x, or `None` if it
+/// leaves `bounds` without hitting anything.
+fn cast_ray(droplet: &Droplet, bounds: Bounds, from: IVec3) -> Option<Face> {
+    let mut position = from;
+    while bounds.contains(position) {
+        if droplet.get(position).is_some() {
+            return Some(Face {
+                voxel: position,
+                normal: -IVec3::x(),
+            });
+        }
+        position.x += 1;
+    }
+    None
+}
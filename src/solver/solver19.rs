@@ -1,134 +1,552 @@
-use std::io::BufRead;
+use std::{fmt, io::BufRead, num::NonZeroUsize};
 
-use color_eyre::eyre::Context;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
-use self::parse::{Blueprint, Resources};
+use crate::{
+    clock::SharedClock,
+    error::{Context, SolverError},
+    explain::{self, explain},
+    math::MathMode,
+    sync::{CancellationToken, Cancelled},
+};
 
+use super::TransformableInput;
+
+use self::{
+    packed::{Lanes, Mask},
+    parse::{Blueprint, Resources},
+};
+
+mod packed;
 mod parse;
+mod whatif;
 
-#[derive(Debug, Default)]
-pub struct Solver19;
+#[derive(Debug)]
+pub struct Solver19 {
+    cancel: CancellationToken,
+    /// Saves part B's progress as it goes.
+    checkpoint: super::Checkpointer,
+    /// How many blueprints from the start of the list to use in part B.
+    blueprints: usize,
+    math: MathMode,
+    /// Work out which robots to build when for each blueprint's best count, from
+    /// `--param schedules=true`. Always done for `--explain`.
+    schedules: bool,
+    /// Changes to make to the blueprints before searching them, from `--transform`.
+    transforms: Vec<super::Transform>,
+    /// Also see how the best counts change with a bit more or less time, or a head start, from
+    /// `--param whatif=true`.
+    whatif: Option<whatif::Sweep>,
+    /// What the time each part takes is measured with.
+    clock: SharedClock,
+}
+
+impl Default for Solver19 {
+    fn default() -> Self {
+        Self {
+            cancel: CancellationToken::default(),
+            checkpoint: super::Checkpointer::default(),
+            blueprints: 3,
+            math: MathMode::default(),
+            schedules: false,
+            transforms: Vec::new(),
+            whatif: None,
+            clock: SharedClock::default(),
+        }
+    }
+}
 
 impl super::ChallengeSolver for Solver19 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         19
     }
 
+    super::sample_methods!();
+
+    fn preset(&self, preset: super::Preset) -> super::SolverParams {
+        // The elephants only leave enough time to check the first three blueprints, but the
+        // sample only has two.
+        let blueprints = match preset {
+            super::Preset::Sample => 2,
+            super::Preset::Real => Self::default().blueprints,
+        };
+
+        let mut params = super::SolverParams::default();
+        params.insert("blueprints", blueprints.to_string());
+        params
+    }
+
+    fn configure(&mut self, config: &super::SolverConfig) -> Result<(), super::ParamError> {
+        self.cancel = config.cancel.clone();
+        self.checkpoint = config.checkpoint.clone();
+        self.blueprints = config
+            .params
+            .get_or("blueprints", Self::default().blueprints)?;
+        self.math = config.math;
+        self.schedules = config.params.get_or("schedules", false)?;
+        self.transforms = config.transforms.clone();
+        self.clock = config.clock.clone();
+
+        let minutes = config.params.get_or("whatif_minutes", 2)?;
+        if minutes > whatif::MAX_EXTRA_MINUTES {
+            return Err(super::ParamError::InvalidValue {
+                key: "whatif_minutes".to_string(),
+                value: minutes.to_string(),
+                reason: format!("can be at most {}", whatif::MAX_EXTRA_MINUTES),
+            });
+        }
+        let sweep = whatif::Sweep {
+            minutes,
+            bonus: config.params.get_or("whatif_bonus", 1)?,
+            threads: config
+                .params
+                .get::<NonZeroUsize>("threads")?
+                .map(NonZeroUsize::get),
+        };
+        self.whatif = config.params.get_or("whatif", false)?.then_some(sweep);
+        Ok(())
+    }
+
+    fn transformable(&self) -> bool {
+        true
+    }
+
+    fn validate(&self, input: &str) -> Option<Result<super::ParseStats, SolverError>> {
+        Some(
+            parse::parse_input(input)
+                .map_err(Into::into)
+                .map(|blueprints| {
+                    super::ParseStats::default().entity("blueprints", blueprints.len())
+                }),
+        )
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let start_time = std::time::Instant::now();
+        let start_time = self.clock.now();
 
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
-        let blueprints = parse::parse_input(&input_buf)
-            .wrap_err("Could not parse input file as a list of blueprints")?;
+            .context("Could not read input file to string")?;
+        let mut blueprints = parse::parse_input(&input_buf)
+            .context("Could not parse input file as a list of blueprints")?;
+        blueprints.apply_all(&self.transforms)?;
 
-        let cumulative_quality = part_a(&blueprints);
+        let geodes = part_a(&blueprints, &self.cancel)?;
+        let cumulative_quality =
+            blueprints
+                .iter()
+                .zip(&geodes)
+                .try_fold(0, |sum, (blueprint, &geodes)| {
+                    let quality = self.math.mul(blueprint.id as usize, geodes as usize)?;
+                    self.math.add(sum, quality)
+                })?;
         println!("cumulative quality: {cumulative_quality}");
 
-        println!("elapsed time: {:?}", start_time.elapsed());
+        println!("elapsed time: {:?}", self.clock.since(start_time));
 
-        Ok(Box::new(cumulative_quality))
+        let answer = super::Answer::new(cumulative_quality);
+        let answer = self.with_whatif(answer, &blueprints, PART_A_MINUTES, &geodes)?;
+        Ok(self.with_schedules(answer, &blueprints, PART_A_MINUTES, &geodes))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let start_time = std::time::Instant::now();
+        let start_time = self.clock.now();
 
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read input file to string")?;
-        let blueprints = parse::parse_input(&input_buf)
-            .wrap_err("Could not parse input file as a list of blueprints")?;
+            .context("Could not read input file to string")?;
+        let mut blueprints = parse::parse_input(&input_buf)
+            .context("Could not parse input file as a list of blueprints")?;
+        blueprints.apply_all(&self.transforms)?;
 
-        let geode_product = part_b(&blueprints);
+        let blueprints = &blueprints[..self.blueprints.min(blueprints.len())];
+        let geodes = part_b(blueprints, &self.cancel, &mut self.checkpoint)?;
+        let geode_product = geodes.iter().try_fold(1, |product, &geodes| {
+            self.math.mul(product, geodes as usize)
+        })?;
         println!("geode product: {geode_product}");
 
-        println!("elapsed time: {:?}", start_time.elapsed());
+        println!("elapsed time: {:?}", self.clock.since(start_time));
 
-        Ok(Box::new(geode_product))
+        let answer = super::Answer::new(geode_product);
+        let answer = self.with_whatif(answer, blueprints, PART_B_MINUTES, &geodes)?;
+        Ok(self.with_schedules(answer, blueprints, PART_B_MINUTES, &geodes))
     }
 }
 
-fn part_a(blueprints: &[Blueprint]) -> usize {
-    blueprints
-        .par_iter()
-        .map(|blueprint| {
-            let mut best = 0;
-            geode_dfs(blueprint, State::new(24), &mut best);
-            blueprint.id as usize * best as usize
-        })
-        .sum()
+impl Solver19 {
+    /// Print and add how each blueprint's best count of `geodes` would change with a bit more or
+    /// less time than `minutes`, or a head start, if that was asked for.
+    fn with_whatif(
+        &self,
+        answer: super::Answer,
+        blueprints: &[Blueprint],
+        minutes: u8,
+        geodes: &[u8],
+    ) -> Result<super::Answer, SolverError> {
+        let Some(sweep) = self.whatif else {
+            return Ok(answer);
+        };
+
+        let whatif = sweep.run(blueprints, minutes, geodes, &self.cancel)?;
+        print!("{whatif}");
+        Ok(answer.with_metadata("whatif", whatif.to_json()))
+    }
+
+    /// Add which robots to build when to get each blueprint's `geodes`, if they were asked for.
+    fn with_schedules(
+        &self,
+        answer: super::Answer,
+        blueprints: &[Blueprint],
+        minutes: u8,
+        geodes: &[u8],
+    ) -> super::Answer {
+        if !self.schedules && !explain::enabled() {
+            return answer;
+        }
+
+        let schedules = blueprints
+            .iter()
+            .zip(geodes)
+            .map(|(blueprint, &geodes)| {
+                let builds = build_schedule(blueprint, minutes, geodes);
+                explain!("Blueprint {}: {geodes} geodes", blueprint.id);
+                for build in &builds {
+                    explain!("  minute {:>2}: build {}", build.minute, build.robot);
+                }
+                json!({ "blueprint": blueprint.id, "geodes": geodes, "builds": builds })
+            })
+            .collect::<Vec<_>>();
+        answer.with_metadata("schedules", schedules)
+    }
 }
 
-fn part_b(blueprints: &[Blueprint]) -> usize {
+/// Blueprints can be dropped with `--transform 'drop blueprint ID'`, to see how the answer does
+/// without them.
+impl super::TransformableInput for Vec<Blueprint> {
+    fn apply(&mut self, transform: &super::Transform) -> Result<(), String> {
+        let super::Transform::Drop { kind, id } = transform else {
+            return Err("blueprints can only be dropped, like `drop blueprint 3`".to_string());
+        };
+        if kind != "blueprint" {
+            return Err(format!("expected `blueprint`, not `{kind}`"));
+        }
+        let len = self.len();
+        self.retain(|blueprint| blueprint.id.to_string() != *id);
+        if self.len() == len {
+            return Err(format!("there's no blueprint {id}"));
+        }
+        Ok(())
+    }
+}
+
+/// How many minutes there are to crack geodes in part A.
+const PART_A_MINUTES: u8 = 24;
+
+/// The most geodes each blueprint can crack in part A's 24 minutes.
+fn part_a(blueprints: &[Blueprint], cancel: &CancellationToken) -> Result<Vec<u8>, Cancelled> {
+    // Every blueprint is searched on its own, so they can all be searched at once.
+    #[cfg(feature = "parallel")]
+    let blueprints = blueprints.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let blueprints = blueprints.iter();
+
     blueprints
-        .iter()
-        .take(3)
-        .map(|blueprint| {
-            let mut best = 0;
-            geode_dfs(blueprint, State::new(32), &mut best);
-            best as usize
-        })
-        .product()
+        .map(|blueprint| best_geodes(blueprint, PART_A_MINUTES, cancel))
+        .collect()
 }
 
-/// Conduct a depth-first search of the optimal geode production technique given a blueprint,
-/// a starting state, and a prior "best" geode count.
+/// The most geodes each of `blueprints` can crack in part B's 32 minutes.
 ///
-/// `best` will be set to a new best geode count if a higher count is found.
-fn geode_dfs(blueprint: &Blueprint, state: State, best: &mut u8) {
-    *best = state.geodes_secured.max(*best);
+/// This is the slowest part of the puzzle, so the search saves its progress to `checkpoint` every
+/// so often, and whenever it's cancelled.
+fn part_b(
+    blueprints: &[Blueprint],
+    cancel: &CancellationToken,
+    checkpoint: &mut super::Checkpointer,
+) -> Result<Vec<u8>, SolverError> {
+    let fingerprint = super::checkpoint::fingerprint((blueprints, PART_B_MINUTES));
+    let mut progress = checkpoint
+        .load(fingerprint)?
+        .unwrap_or_else(PartBProgress::default);
+
+    for blueprint in &blueprints[progress.geodes.len()..] {
+        let mut search = progress
+            .search
+            .take()
+            .unwrap_or_else(|| GeodeSearch::new(PART_B_MINUTES));
 
-    for state in state.future_states(blueprint) {
-        if state.possible_geodes(blueprint) > *best {
-            geode_dfs(blueprint, state, best);
+        while !search.is_done() {
+            if let Err(cancelled) = search.step(blueprint, cancel) {
+                progress.search = Some(search);
+                checkpoint.save(fingerprint, &progress)?;
+                return Err(cancelled.into());
+            }
+            checkpoint.tick(fingerprint, || PartBProgress {
+                geodes: progress.geodes.clone(),
+                search: Some(search.clone()),
+            })?;
         }
+
+        cancel.report(format!("blueprint {} geodes", blueprint.id), search.best);
+        progress.geodes.push(search.best);
     }
+
+    checkpoint.clear()?;
+    Ok(progress.geodes)
 }
 
-#[derive(Debug, Clone)]
-struct State {
-    minutes_remaining: u8,
-    geodes_secured: u8,
-    resources: Resources,
-    resources_rate: Resources,
+/// How many minutes the elephants leave for part B.
+const PART_B_MINUTES: u8 = 32;
+
+/// How far part B has gotten, as saved in a checkpoint.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PartBProgress {
+    /// The most geodes for each blueprint that's been searched so far, in order.
+    geodes: Vec<u8>,
+    /// The search for the next blueprint, if it's been started.
+    search: Option<GeodeSearch>,
 }
 
-impl State {
-    fn new(minutes_remaining: u8) -> Self {
+/// Find the most geodes that can be cracked with a blueprint in the given number of minutes.
+///
+/// Each finished blueprint is reported to `cancel` as progress.
+fn best_geodes(
+    blueprint: &Blueprint,
+    minutes: u8,
+    cancel: &CancellationToken,
+) -> Result<u8, Cancelled> {
+    let best = search_from(blueprint, State::new(minutes), cancel)?;
+    cancel.report(format!("blueprint {} geodes", blueprint.id), best);
+    Ok(best)
+}
+
+/// Find the most geodes that can be cracked with a blueprint, starting out from `start`.
+fn search_from(
+    blueprint: &Blueprint,
+    start: State,
+    cancel: &CancellationToken,
+) -> Result<u8, Cancelled> {
+    let mut search = GeodeSearch {
+        best: 0,
+        stack: vec![start],
+        visited: 0,
+    };
+    while !search.is_done() {
+        search.step(blueprint, cancel)?;
+    }
+    Ok(search.best)
+}
+
+/// A depth-first search for the optimal geode production technique given a blueprint.
+///
+/// The states still to visit are kept on an explicit stack instead of the call stack, so that a
+/// search can be saved partway through and picked back up later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeodeSearch {
+    /// The most geodes cracked by any state visited so far.
+    best: u8,
+    stack: Vec<State>,
+    /// How many states have been visited so far.
+    visited: u64,
+}
+
+impl GeodeSearch {
+    fn new(minutes: u8) -> Self {
         Self {
-            minutes_remaining,
-            geodes_secured: 0,
-            resources: Resources::default(),
-            resources_rate: Resources::ONE_ORE,
+            best: 0,
+            stack: vec![State::new(minutes)],
+            visited: 0,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Visit the next state on the stack, skipping it if it can't beat the best count so far.
+    fn step(&mut self, blueprint: &Blueprint, cancel: &CancellationToken) -> Result<(), Cancelled> {
+        cancel.check()?;
+        let Some(state) = self.stack.pop() else {
+            return Ok(());
+        };
+
+        // The bound is checked when a state is visited rather than when it's pushed, since the
+        // best count might have gone up in between. The starting state is always visited.
+        if self.visited > 0 && state.possible_geodes(blueprint) <= self.best {
+            return Ok(());
+        }
+        self.visited += 1;
+        self.best = state.geodes_secured().max(self.best);
+
+        // Pushed in reverse, so that they're visited in the order `future_states` gives them.
+        let next = state.future_states(blueprint).collect::<Vec<_>>();
+        self.stack.extend(next.into_iter().rev());
+        Ok(())
+    }
+}
+
+/// A robot that gets built at some point while cracking geodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Robot {
+    Ore,
+    Clay,
+    Obsidian,
+    Geode,
+}
+
+impl fmt::Display for Robot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ore => write!(f, "ore robot"),
+            Self::Clay => write!(f, "clay robot"),
+            Self::Obsidian => write!(f, "obsidian robot"),
+            Self::Geode => write!(f, "geode robot"),
         }
     }
+}
+
+/// Starting to build a robot during a minute, counting up from 1 like the puzzle does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+struct Build {
+    minute: u8,
+    robot: Robot,
+}
+
+/// Which robots to build when to crack `geodes` geodes with a blueprint in `minutes`, where
+/// `geodes` is the most that [`best_geodes`] found.
+///
+/// The search only keeps track of the best count, so this searches again, this time remembering
+/// the way there. Knowing the count up front cuts off every branch that can't reach it, so it
+/// takes a lot less time than the first search did.
+fn build_schedule(blueprint: &Blueprint, minutes: u8, geodes: u8) -> Vec<Build> {
+    fn search(state: State, blueprint: &Blueprint, geodes: u8, path: &mut Vec<State>) -> bool {
+        if state.geodes_secured() >= geodes {
+            return true;
+        }
+        if state.possible_geodes(blueprint) < geodes {
+            return false;
+        }
+
+        for next in state.future_states(blueprint) {
+            path.push(next);
+            if search(next, blueprint, geodes, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let start = State::new(minutes);
+    let mut states = vec![start];
+    search(start, blueprint, geodes, &mut states);
+
+    states
+        .windows(2)
+        .map(|pair| Build {
+            // The robot is started in the minute before it's done, and the state after it
+            // counts from the end of that minute.
+            minute: minutes - pair[1].minutes_remaining(),
+            robot: pair[1].robot_built_since(pair[0]),
+        })
+        .collect()
+}
+
+/// A point in the search, packed into a single `u64`.
+///
+/// Lanes 0 to 2 hold the ore, clay and obsidian collected so far, and lanes 4 to 6 the number of
+/// robots collecting each of them. Lane 3 is the number of geodes secured, and lane 7 the number
+/// of minutes remaining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct State(Lanes);
 
-    /// Try to build a robot at some point in the future.
+impl State {
+    const ORE: usize = 0;
+    const CLAY: usize = 1;
+    const OBSIDIAN: usize = 2;
+    const GEODES_SECURED: usize = 3;
+    const RATES_OFFSET: usize = 4;
+    const MINUTES_REMAINING: usize = 7;
+
+    const ORE_ROBOT: Lanes = Lanes::single(Self::RATES_OFFSET + Self::ORE, 1);
+    const CLAY_ROBOT: Lanes = Lanes::single(Self::RATES_OFFSET + Self::CLAY, 1);
+    const OBSIDIAN_ROBOT: Lanes = Lanes::single(Self::RATES_OFFSET + Self::OBSIDIAN, 1);
+
+    fn new(minutes_remaining: u8) -> Self {
+        Self(Lanes::from_bytes([0, 0, 0, 0, 1, 0, 0, minutes_remaining]))
+    }
+
+    /// Like [`State::new`], but with `stock` already collected.
+    fn stocked(minutes_remaining: u8, stock: Resources) -> Self {
+        Self(Self::new(minutes_remaining).0.wrapping_add(stock.into()))
+    }
+
+    /// The number of robots collecting each resource, in the same lanes as the resources
+    /// themselves.
+    fn rates(self) -> Lanes {
+        self.0
+            .shift_down(Self::RATES_OFFSET)
+            .masked(Mask::lanes(Self::ORE..Self::GEODES_SECURED))
+    }
+
+    fn geodes_secured(self) -> u8 {
+        self.0.lane(Self::GEODES_SECURED)
+    }
+
+    fn minutes_remaining(self) -> u8 {
+        self.0.lane(Self::MINUTES_REMAINING)
+    }
+
+    /// Which robot was built to get from `earlier`, one of the states right before this one in
+    /// the search, to this one.
+    fn robot_built_since(self, earlier: Self) -> Robot {
+        // Geode robots don't have a rate, so they're the only robot that doesn't change one.
+        [
+            (Self::ORE, Robot::Ore),
+            (Self::CLAY, Robot::Clay),
+            (Self::OBSIDIAN, Robot::Obsidian),
+        ]
+        .into_iter()
+        .find(|&(lane, _)| self.rates().lane(lane) > earlier.rates().lane(lane))
+        .map_or(Robot::Geode, |(_, robot)| robot)
+    }
+
+    /// Try to build a robot at some point in the future, adding `robot` to the state once it's
+    /// built.
     ///
     /// Returns `Some(State)` if a state was found at some point in the future
     /// where building the robot is viable, including the new count of minutes
     /// remaining, the new resources rate, and the new resources count.
-    fn choose_robot(&self, cost: Resources, robot: Resources) -> Option<Self> {
-        (1..self.minutes_remaining).rev().zip(0..).find_map(
-            |(minutes_remaining, minutes_passed)| {
-                // Figure out how many resources have been produced at this point in the future
-                let resources = self.resources + self.resources_rate * minutes_passed;
-
-                // If we have enough resources to build the requested robot, then
-                // return the new state
-                resources.checked_sub(cost).map(|remaining_resources| Self {
-                    resources: remaining_resources + self.resources_rate,
-                    resources_rate: self.resources_rate + robot,
-
-                    minutes_remaining,
-                    geodes_secured: self.geodes_secured,
-                })
-            },
-        )
+    fn choose_robot(self, cost: Resources, robot: Lanes) -> Option<Self> {
+        let cost = Lanes::from(cost);
+        let rates = self.rates();
+        let one_minute = Lanes::single(Self::MINUTES_REMAINING, 1);
+
+        // Each minute spent waiting collects another round of resources, until there's enough to
+        // pay for the robot. Lanes that `cost` doesn't use are zero in it, so they're always
+        // covered.
+        let mut state = self.0;
+        for _ in 1..self.minutes_remaining() {
+            if let Some(remaining) = state.checked_sub(cost) {
+                // The robot takes a minute to build, during which the others keep collecting.
+                return Some(Self(
+                    remaining
+                        .wrapping_add(rates)
+                        .wrapping_add(robot)
+                        .wrapping_sub(one_minute),
+                ));
+            }
+            state = state.wrapping_add(rates).wrapping_sub(one_minute);
+        }
+
+        None
     }
 
     /// Return an iterator over the next possible States if any robots are
@@ -139,28 +557,30 @@ impl State {
             .ore
             .max(blueprint.obsidian_robot_cost.ore)
             .max(blueprint.geode_robot_cost.ore);
+        let rates = self.rates();
 
         // Figure out which robots are "viable" to be built, always with a preference
         // to building higher-tier robots (up to geode robots).
-        let ore_robot_viable = self.resources_rate.ore < max_higher_tier_ore_cost;
-        let clay_robot_viable = self.resources_rate.clay < blueprint.obsidian_robot_cost.clay;
-        let obsidian_robot_viable = self.resources_rate.obsidian
+        let ore_robot_viable = rates.lane(Self::ORE) < max_higher_tier_ore_cost;
+        let clay_robot_viable = rates.lane(Self::CLAY) < blueprint.obsidian_robot_cost.clay;
+        let obsidian_robot_viable = rates.lane(Self::OBSIDIAN)
             < blueprint.geode_robot_cost.obsidian
-            && self.resources_rate.clay > 0;
-        let geode_robot_viable = self.resources_rate.obsidian > 0;
+            && rates.lane(Self::CLAY) > 0;
+        let geode_robot_viable = rates.lane(Self::OBSIDIAN) > 0;
 
         [
-            ore_robot_viable
-                .then(|| self.choose_robot(blueprint.ore_robot_cost, Resources::ONE_ORE)),
+            ore_robot_viable.then(|| self.choose_robot(blueprint.ore_robot_cost, Self::ORE_ROBOT)),
             clay_robot_viable
-                .then(|| self.choose_robot(blueprint.clay_robot_cost, Resources::ONE_CLAY)),
+                .then(|| self.choose_robot(blueprint.clay_robot_cost, Self::CLAY_ROBOT)),
             obsidian_robot_viable
-                .then(|| self.choose_robot(blueprint.obsidian_robot_cost, Resources::ONE_OBSIDIAN)),
+                .then(|| self.choose_robot(blueprint.obsidian_robot_cost, Self::OBSIDIAN_ROBOT)),
+            // A geode robot cracks one geode every minute for the rest of the time, so they can
+            // all be counted up front instead of tracking a geode rate.
             geode_robot_viable.then(|| {
-                self.choose_robot(blueprint.geode_robot_cost, Default::default())
-                    .map(|state| Self {
-                        geodes_secured: state.geodes_secured + state.minutes_remaining,
-                        ..state
+                self.choose_robot(blueprint.geode_robot_cost, Lanes::default())
+                    .map(|state| {
+                        let geodes = Lanes::single(Self::GEODES_SECURED, state.minutes_remaining());
+                        Self(state.0.wrapping_add(geodes))
                     })
             }),
         ]
@@ -171,13 +591,13 @@ impl State {
 
     /// Determine how many geodes can be produced if *only* geode robots are
     /// produced until time is up.
-    fn possible_geodes(&self, blueprint: &Blueprint) -> u8 {
+    fn possible_geodes(self, blueprint: &Blueprint) -> u8 {
         let geode_robot_cost = blueprint.geode_robot_cost.obsidian;
-        let (_, _, geodes) = (0..self.minutes_remaining).rev().fold(
+        let (_, _, geodes) = (0..self.minutes_remaining()).rev().fold(
             (
-                self.resources.obsidian,
-                self.resources_rate.obsidian,
-                self.geodes_secured,
+                self.0.lane(Self::OBSIDIAN),
+                self.rates().lane(Self::OBSIDIAN),
+                self.geodes_secured(),
             ),
             |(obsidian, rate, geodes), minutes_remaining| {
                 if obsidian >= geode_robot_cost {
@@ -196,11 +616,124 @@ impl State {
 }
 
 super::challenge_solver_test_boilerplate! {
-    Solver19;
-        "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.\n\
-         Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian."
-     => {
-        a as usize: 33,
-        b as usize: 56 * 62,
-     }
+    Solver19::default();
+    19 => {
+        a as usize,
+        b as usize,
+    }
+
+    #[test]
+    fn resumes_from_checkpoint() -> Result<(), SolverError> {
+        let path = std::env::temp_dir().join(format!("aoc2022-19b-{}.bin", std::process::id()));
+        let mut solver = Solver19 {
+            checkpoint: crate::solver::Checkpointer::new(&path, true),
+            ..Default::default()
+        };
+
+        solver.cancel.cancel();
+        let err = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT)).unwrap_err();
+        assert!(matches!(err, SolverError::Cancelled(Cancelled)));
+        assert!(path.exists(), "cancelling should save a checkpoint");
+
+        solver.cancel = CancellationToken::new();
+        let answer = solver.solve_b(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&(56 * 62)));
+        assert!(!path.exists(), "finishing should clear the checkpoint");
+
+        Ok(())
+    }
+    #[test]
+    fn schedules_reach_the_best_count() -> Result<(), SolverError> {
+        let blueprints = parse::parse_input(SAMPLE_INPUT)?;
+
+        // Play each schedule out a minute at a time, like the puzzle description does.
+        for (blueprint, geodes) in blueprints.iter().zip([9, 12]) {
+            let builds = build_schedule(blueprint, PART_A_MINUTES, geodes);
+            let (mut stock, mut robots) = ([0_u8; 4], [1_u8, 0, 0, 0]);
+            for minute in 1..=PART_A_MINUTES {
+                let build = builds.iter().find(|build| build.minute == minute);
+                if let Some(build) = build {
+                    let cost = match build.robot {
+                        Robot::Ore => blueprint.ore_robot_cost,
+                        Robot::Clay => blueprint.clay_robot_cost,
+                        Robot::Obsidian => blueprint.obsidian_robot_cost,
+                        Robot::Geode => blueprint.geode_robot_cost,
+                    };
+                    for (have, cost) in stock.iter_mut().zip([cost.ore, cost.clay, cost.obsidian]) {
+                        *have = have.checked_sub(cost).expect("can't afford the robot");
+                    }
+                }
+                for (have, robots) in stock.iter_mut().zip(robots) {
+                    *have += robots;
+                }
+                if let Some(build) = build {
+                    robots[build.robot as usize] += 1;
+                }
+            }
+            assert_eq!(stock[3], geodes, "blueprint {}", blueprint.id);
+        }
+
+        // The puzzle's own schedule for the first blueprint starts with a clay robot in minute 3.
+        let mut solver = Solver19::default();
+        let mut config = crate::solver::SolverConfig::default();
+        config.params.insert("schedules", "true");
+        solver.configure(&config).unwrap();
+        let answer = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+        let schedules = answer.metadata()["schedules"].as_array().unwrap();
+        assert_eq!(schedules.len(), 2);
+        assert_eq!(schedules[0]["geodes"], 9);
+        assert_eq!(
+            schedules[0]["builds"][0],
+            json!({ "minute": 3, "robot": "clay" })
+        );
+
+        Ok(())
+    }
+    #[test]
+    fn sweeps_minutes_and_head_starts() -> Result<(), SolverError> {
+        let mut solver = Solver19::default();
+        let mut config = crate::solver::SolverConfig::default();
+        config.params.insert("whatif", "true");
+        config.params.insert("whatif_minutes", "1");
+        config.params.insert("threads", "2");
+        solver.configure(&config).unwrap();
+        let answer = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+
+        let whatif = answer.metadata()["whatif"].as_array().unwrap();
+        assert_eq!(whatif.len(), 2);
+        for (row, geodes) in whatif.iter().zip([9, 12]) {
+            assert_eq!(row["geodes"], geodes);
+            let scenario = |label: &str| row["scenarios"][label].as_u64().unwrap();
+            assert!(scenario("-1m") <= geodes && geodes <= scenario("+1m"));
+            for bonus in ["+1 ore", "+1 clay", "+1 obsidian"] {
+                assert!(scenario(bonus) >= geodes, "{bonus} can't hurt");
+            }
+        }
+
+        config.params.insert("whatif_minutes", "9");
+        assert!(solver.configure(&config).is_err());
+
+        Ok(())
+    }
+    #[test]
+    fn drops_blueprints() -> Result<(), SolverError> {
+        let mut solver = Solver19::default();
+        let mut config = crate::solver::SolverConfig {
+            transforms: vec!["drop blueprint 2".parse().unwrap()],
+            ..Default::default()
+        };
+        solver.configure(&config).unwrap();
+        let answer = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT))?;
+        assert_eq!(answer.downcast_ref::<usize>(), Some(&9));
+
+        config.transforms = vec!["drop blueprint 7".parse().unwrap()];
+        solver.configure(&config).unwrap();
+        let err = solver.solve_a(&mut Cursor::new(SAMPLE_INPUT)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Could not `drop blueprint 7`: there's no blueprint 7"
+        );
+
+        Ok(())
+    }
 }
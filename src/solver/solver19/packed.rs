@@ -0,0 +1,192 @@
+//! Eight `u8` counters packed into a single `u64`, so that a whole search state fits in one
+//! register and hashes as one word.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::parse::Resources;
+
+/// The top bit of every lane.
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Eight independent `u8` lanes packed into a `u64`, least significant byte first.
+///
+/// Arithmetic wraps within each lane, and never carries or borrows into a neighbouring one.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Lanes(u64);
+
+impl Lanes {
+    pub const fn from_bytes(bytes: [u8; 8]) -> Self {
+        Self(u64::from_le_bytes(bytes))
+    }
+
+    pub const fn to_bytes(self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+
+    /// `value` in lane `index`, and zero everywhere else.
+    pub const fn single(index: usize, value: u8) -> Self {
+        Self((value as u64) << (8 * index))
+    }
+
+    pub const fn lane(self, index: usize) -> u8 {
+        self.to_bytes()[index]
+    }
+
+    /// Keep only the lanes selected by `mask`, zeroing the rest.
+    pub const fn masked(self, mask: Mask) -> Self {
+        Self(self.0 & mask.0)
+    }
+
+    /// Move every lane `by` lanes towards the least significant end, shifting in zeros.
+    pub const fn shift_down(self, by: usize) -> Self {
+        Self(self.0 >> (8 * by))
+    }
+
+    /// Lane-wise wrapping addition.
+    pub const fn wrapping_add(self, rhs: Self) -> Self {
+        // Add the low seven bits of each lane, where a carry can only reach the lane's own top
+        // bit, then fix up the top bits without carrying out of them.
+        let sum = (self.0 & !HIGH_BITS) + (rhs.0 & !HIGH_BITS);
+        Self(sum ^ ((self.0 ^ rhs.0) & HIGH_BITS))
+    }
+
+    /// Lane-wise wrapping subtraction.
+    pub const fn wrapping_sub(self, rhs: Self) -> Self {
+        // Setting every top bit first gives each lane something to borrow from, so no borrow can
+        // escape into the next lane.
+        let diff = (self.0 | HIGH_BITS) - (rhs.0 & !HIGH_BITS);
+        Self(diff ^ ((self.0 ^ !rhs.0) & HIGH_BITS))
+    }
+
+    /// `true` if every lane of `self` is at least as large as the same lane of `rhs`.
+    pub const fn covers(self, rhs: Self) -> bool {
+        // The top bit of each lane of the difference, combined with the top bits of both sides,
+        // says whether that lane had to borrow.
+        let diff = self.wrapping_sub(rhs).0;
+        let borrows = (!self.0 & rhs.0) | (!(self.0 ^ rhs.0) & diff);
+        borrows & HIGH_BITS == 0
+    }
+
+    /// Lane-wise subtraction, or `None` if any lane of `rhs` is larger than the same lane of
+    /// `self`.
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        if self.covers(rhs) {
+            Some(self.wrapping_sub(rhs))
+        } else {
+            None
+        }
+    }
+}
+
+impl From<Resources> for Lanes {
+    /// Puts ore, clay and obsidian in lanes 0, 1 and 2.
+    fn from(resources: Resources) -> Self {
+        Self::from_bytes([
+            resources.ore,
+            resources.clay,
+            resources.obsidian,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ])
+    }
+}
+
+impl fmt::Debug for Lanes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Lanes").field(&self.to_bytes()).finish()
+    }
+}
+
+/// A selection of lanes, for [`Lanes::masked`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mask(u64);
+
+impl Mask {
+    /// Select the lanes in `range`.
+    pub const fn lanes(range: std::ops::Range<usize>) -> Self {
+        let width = 8 * (range.end - range.start);
+        let ones = if width == 64 {
+            u64::MAX
+        } else {
+            (1 << width) - 1
+        };
+        Self(ones << (8 * range.start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lanes_wrap_independently() {
+        let a = Lanes::from_bytes([255, 0, 128, 127, 1, 200, 0, 7]);
+        let b = Lanes::from_bytes([1, 1, 128, 1, 1, 100, 0, 8]);
+
+        assert_eq!(
+            a.wrapping_add(b),
+            Lanes::from_bytes([0, 1, 0, 128, 2, 44, 0, 15])
+        );
+        assert_eq!(
+            a.wrapping_sub(b),
+            Lanes::from_bytes([254, 255, 0, 126, 0, 100, 0, 255])
+        );
+    }
+
+    #[test]
+    fn checked_sub_fails_if_any_lane_borrows() {
+        let a = Lanes::from_bytes([4, 14, 200, 0, 0, 0, 0, 0]);
+
+        assert_eq!(
+            a.checked_sub(Lanes::from_bytes([4, 14, 129, 0, 0, 0, 0, 0])),
+            Some(Lanes::from_bytes([0, 0, 71, 0, 0, 0, 0, 0]))
+        );
+        assert_eq!(
+            a.checked_sub(Lanes::from_bytes([4, 15, 0, 0, 0, 0, 0, 0])),
+            None
+        );
+        assert_eq!(
+            a.checked_sub(Lanes::from_bytes([0, 0, 201, 0, 0, 0, 0, 0])),
+            None
+        );
+        assert_eq!(
+            a.checked_sub(Lanes::from_bytes([0, 0, 0, 1, 0, 0, 0, 0])),
+            None
+        );
+
+        // Every lane against every value, to catch borrows leaking between lanes.
+        for x in 0..=255 {
+            for y in 0..=255 {
+                let a = Lanes::from_bytes([x, y, x, y, y, x, x, y]);
+                let b = Lanes::from_bytes([y, x, x, 0, y, y, y, x]);
+                let expected = (0..8)
+                    .map(|i| a.lane(i).checked_sub(b.lane(i)))
+                    .collect::<Option<Vec<_>>>();
+                assert_eq!(
+                    a.checked_sub(b).map(|d| d.to_bytes().to_vec()),
+                    expected,
+                    "{a:?} - {b:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn masks_select_lanes() {
+        let a = Lanes::from_bytes([1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            a.masked(Mask::lanes(0..3)),
+            Lanes::from_bytes([1, 2, 3, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            a.masked(Mask::lanes(4..7)).shift_down(4),
+            Lanes::from_bytes([5, 6, 7, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(a.masked(Mask::lanes(0..8)), a);
+    }
+}
@@ -1,81 +1,28 @@
 use std::ops;
 
-use miette::GraphicalReportHandler;
 use nom::{
     character::complete as nom_cc,
     error::ParseError,
     sequence::{delimited, separated_pair, tuple},
     IResult, Parser,
 };
-use nom_locate::LocatedSpan;
 use nom_supreme::{
-    error::{BaseErrorKind, ErrorTree, GenericErrorTree},
-    final_parser::final_parser,
+    error::ErrorTree,
     multi::collect_separated_terminated,
     tag::{complete::tag, TagError},
     ParserExt,
 };
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+use crate::parse::{BadInputError, Span};
 
 /// Parse the challenge input into a vector of [`Blueprint`]s.
 ///
 /// Any parsing errors will be printed out to `stderr` with fancy formatting.
-pub fn parse_input(input: &str) -> Result<Vec<Blueprint>, ParseInputError> {
-    let input_span = Span::new(input);
-
-    let valves_res: Result<_, ErrorTree<Span>> =
-        final_parser(Blueprint::parse_all::<ErrorTree<Span>>)(input_span);
-
-    match valves_res {
-        Ok(records) => Ok(records),
-
-        Err(e) => match e {
-            GenericErrorTree::Base { location, kind } => {
-                let offset = location.location_offset().into();
-                let err = BadInputError {
-                    src: input.to_string(),
-                    bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                    kind,
-                };
-
-                let mut s = String::new();
-                GraphicalReportHandler::new()
-                    .render_report(&mut s, &err)
-                    .unwrap();
-                eprintln!("{s}");
-
-                Err(err.into())
-            }
-
-            GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-            GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-        },
-    }
-}
-
-#[derive(thiserror::Error, Debug, miette::Diagnostic)]
-#[error("Error parsing input")]
-pub struct BadInputError {
-    #[source_code]
-    src: String,
-
-    #[label("{kind}")]
-    bad_bit: miette::SourceSpan,
-
-    kind: BaseErrorKind<&'static str, Box<dyn std::error::Error + Send + Sync>>,
+pub fn parse_input(input: &str) -> Result<Vec<Blueprint>, BadInputError> {
+    crate::parse::parse_input(input, Blueprint::parse_all::<ErrorTree<Span>>)
 }
 
-#[derive(thiserror::Error, Debug)]
-pub enum ParseInputError {
-    #[error("Failed to parse input due to bad input")]
-    BadInputError {
-        #[from]
-        source: BadInputError,
-    },
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Hash)]
 pub struct Blueprint {
     pub id: u8,
     pub ore_robot_cost: Resources,
@@ -142,7 +89,7 @@ impl Blueprint {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Hash)]
 pub struct Resources {
     pub ore: u8,
     pub clay: u8,
@@ -165,14 +112,6 @@ impl Resources {
         clay: 0,
         obsidian: 1,
     };
-
-    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
-        Some(Self {
-            ore: self.ore.checked_sub(rhs.ore)?,
-            clay: self.clay.checked_sub(rhs.clay)?,
-            obsidian: self.obsidian.checked_sub(rhs.obsidian)?,
-        })
-    }
 }
 
 impl ops::Add for Resources {
@@ -0,0 +1,232 @@
+//! Seeing how much each blueprint's best count hangs on the time there is and what's in stock at
+//! the start, for `--param whatif=true`.
+
+use std::fmt;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde_json::{json, Value};
+
+use crate::{
+    error::SolverError,
+    sync::{CancellationToken, Cancelled},
+};
+
+use super::{
+    parse::{Blueprint, Resources},
+    search_from, State,
+};
+
+/// The most minutes that a what-if sweep can add or take away. The search gets slower by the
+/// minute, and much past this, a geode count no longer fits in a state's `u8`s.
+pub(super) const MAX_EXTRA_MINUTES: u8 = 8;
+
+/// What a what-if sweep tries, from the `whatif_minutes`, `whatif_bonus` and `threads`
+/// parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Sweep {
+    /// Try every number of minutes up to this many more or less than usual.
+    pub(super) minutes: u8,
+    /// Try starting out with this much more ore, clay or obsidian.
+    pub(super) bonus: u8,
+    /// How many threads to search on, or `None` for as many as the rest of the search uses (one
+    /// per core, unless `threads` is set in `aoc2022.toml`).
+    pub(super) threads: Option<usize>,
+}
+
+/// A starting point for the search that's a bit different from the usual one.
+#[derive(Debug, Clone)]
+struct Scenario {
+    /// The column heading for the scenario, like `-2m` or `+1 clay`.
+    label: String,
+    minutes: u8,
+    stock: Resources,
+}
+
+/// Each blueprint's best count for each scenario of a [`Sweep`].
+#[derive(Debug)]
+pub(super) struct WhatIf {
+    /// How many minutes there usually are.
+    minutes: u8,
+    scenarios: Vec<Scenario>,
+    /// Each blueprint's ID, its usual best count, and its best count in each scenario.
+    rows: Vec<(u8, u8, Vec<u8>)>,
+}
+
+impl Sweep {
+    /// The scenarios to try when there are usually `minutes` minutes, leaving out any with no
+    /// time at all.
+    fn scenarios(&self, minutes: u8) -> Vec<Scenario> {
+        let shorter = (1..=self.minutes).rev().filter(|&less| less < minutes);
+        let mut scenarios = shorter
+            .map(|less| Scenario {
+                label: format!("-{less}m"),
+                minutes: minutes - less,
+                stock: Resources::default(),
+            })
+            .chain((1..=self.minutes).map(|more| Scenario {
+                label: format!("+{more}m"),
+                minutes: minutes + more,
+                stock: Resources::default(),
+            }))
+            .collect::<Vec<_>>();
+
+        if self.bonus > 0 {
+            for (name, resource) in [
+                ("ore", Resources::ONE_ORE),
+                ("clay", Resources::ONE_CLAY),
+                ("obsidian", Resources::ONE_OBSIDIAN),
+            ] {
+                scenarios.push(Scenario {
+                    label: format!("+{} {name}", self.bonus),
+                    minutes,
+                    stock: resource * self.bonus,
+                });
+            }
+        }
+        scenarios
+    }
+
+    /// Search every blueprint in every scenario, all at once.
+    ///
+    /// `best` is each blueprint's usual best count with `minutes`, which has already been found.
+    pub(super) fn run(
+        &self,
+        blueprints: &[Blueprint],
+        minutes: u8,
+        best: &[u8],
+        cancel: &CancellationToken,
+    ) -> Result<WhatIf, SolverError> {
+        let scenarios = self.scenarios(minutes);
+        let jobs = blueprints
+            .iter()
+            .flat_map(|blueprint| scenarios.iter().map(move |scenario| (blueprint, scenario)))
+            .collect::<Vec<_>>();
+        let search = |(blueprint, scenario): &(&Blueprint, &Scenario)| {
+            search_from(
+                blueprint,
+                State::stocked(scenario.minutes, scenario.stock),
+                cancel,
+            )
+        };
+
+        #[cfg(feature = "parallel")]
+        let geodes = {
+            // Without a `threads` param, use as many as the rest of the search does.
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(self.threads.unwrap_or_else(rayon::current_num_threads))
+                .build()
+                .map_err(|e| {
+                    SolverError::Unsupported(format!("Could not start the search's threads: {e}"))
+                })?;
+            pool.install(|| {
+                jobs.par_iter()
+                    .map(search)
+                    .collect::<Result<Vec<_>, Cancelled>>()
+            })?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let geodes = jobs
+            .iter()
+            .map(search)
+            .collect::<Result<Vec<_>, Cancelled>>()?;
+
+        let rows = blueprints
+            .iter()
+            .zip(best)
+            .zip(geodes.chunks(scenarios.len().max(1)))
+            .map(|((blueprint, &best), geodes)| (blueprint.id, best, geodes.to_vec()))
+            .collect();
+        Ok(WhatIf {
+            minutes,
+            scenarios,
+            rows,
+        })
+    }
+}
+
+impl WhatIf {
+    /// Every blueprint's best count in every scenario, keyed by the scenarios' column headings.
+    pub(super) fn to_json(&self) -> Value {
+        self.rows
+            .iter()
+            .map(|(id, best, geodes)| {
+                let scenarios = self
+                    .scenarios
+                    .iter()
+                    .zip(geodes)
+                    .map(|(scenario, &geodes)| (scenario.label.clone(), json!(geodes)))
+                    .collect::<serde_json::Map<_, _>>();
+                json!({ "blueprint": id, "geodes": best, "scenarios": scenarios })
+            })
+            .collect()
+    }
+}
+
+/// A table with a row for each blueprint, with its usual best count and then how much more or
+/// less it would get in each scenario.
+impl fmt::Display for WhatIf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let usual = format!("{}m", self.minutes);
+        let headings = std::iter::once(&usual)
+            .chain(self.scenarios.iter().map(|scenario| &scenario.label))
+            .collect::<Vec<_>>();
+        let widths = headings
+            .iter()
+            .map(|heading| heading.len().max(3) + 2)
+            .collect::<Vec<_>>();
+
+        let mut line = format!("{:<11}", "blueprint");
+        for (heading, width) in headings.iter().zip(&widths) {
+            line += &format!("{heading:<width$}");
+        }
+        writeln!(f, "{}", line.trim_end())?;
+
+        for (id, best, geodes) in &self.rows {
+            let mut line = format!("{id:<11}{best:<width$}", width = widths[0]);
+            for (&geodes, width) in geodes.iter().zip(&widths[1..]) {
+                let change = match i16::from(geodes) - i16::from(*best) {
+                    0 => "0".to_string(),
+                    change => format!("{change:+}"),
+                };
+                line += &format!("{change:<width$}");
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tries_more_and_less_time_and_a_head_start() {
+        let sweep = Sweep {
+            minutes: 2,
+            bonus: 1,
+            threads: Some(2),
+        };
+        let labels = sweep
+            .scenarios(24)
+            .into_iter()
+            .map(|scenario| scenario.label)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                "-2m",
+                "-1m",
+                "+1m",
+                "+2m",
+                "+1 ore",
+                "+1 clay",
+                "+1 obsidian"
+            ]
+        );
+
+        // There's no such thing as no time at all.
+        assert_eq!(sweep.scenarios(2)[0].label, "-1m");
+    }
+}
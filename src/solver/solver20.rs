@@ -1,130 +1,314 @@
 use std::io::BufRead;
 
-use color_eyre::eyre::Context;
+use crate::{
+    error::{Context, SolverError},
+    input_iter::for_each_line,
+    math::MathMode,
+    parse::LineParser,
+};
+
+use super::Scrambler;
 
 const PART_B_DECRYPTION_KEY: i64 = 811589153;
 
+/// How big the numbers in a made-up file can get either way, which is about as big as the real
+/// ones do.
+const MAX_GENERATED_NUMBER: u64 = 10_000;
+
 #[derive(Debug, Default)]
-pub struct Solver20;
+pub struct Solver20 {
+    math: MathMode,
+    /// Leave out numbers that don't parse, instead of failing.
+    skip_bad_lines: bool,
+}
 
 impl super::ChallengeSolver for Solver20 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         20
     }
 
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &super::SolverConfig) -> Result<(), super::ParamError> {
+        self.math = config.math;
+        self.skip_bad_lines = config.skip_bad_lines;
+        Ok(())
+    }
+
+    fn generate(&self, size: usize, rng: &mut Scrambler) -> Option<Result<String, SolverError>> {
+        Some(generate(size, rng))
+    }
+
     fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let nums = parse(input).wrap_err("Failed to parse challenge input")?;
+        let (nums, skipped) =
+            parse(input, self.skip_bad_lines).context("Failed to parse challenge input")?;
 
-        let res = solve(nums, 1, 1);
+        let res = solve(nums, 1, 1, self.math)?;
         println!("grove coordinate sum = {res}");
 
-        Ok(Box::new(res))
+        Ok(super::Answer::new(res).with_skipped_lines(skipped))
     }
 
     fn solve_b(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
-        let nums = parse(input).wrap_err("Failed to parse challenge input")?;
+        let (nums, skipped) =
+            parse(input, self.skip_bad_lines).context("Failed to parse challenge input")?;
 
-        let res = solve(nums, PART_B_DECRYPTION_KEY, 10);
+        let res = solve(nums, PART_B_DECRYPTION_KEY, 10, self.math)?;
         println!("grove coordinate sum = {res}");
 
-        Ok(Box::new(res))
+        Ok(super::Answer::new(res).with_skipped_lines(skipped))
     }
 }
 
-fn parse(input: &mut dyn BufRead) -> color_eyre::Result<Vec<i64>> {
-    let mut nums = Vec::new();
-
-    for line in input.lines() {
-        let line = line.wrap_err("Could not read line from challenge input file")?;
-        let num = line
-            .parse::<i64>()
-            .wrap_err_with(|| format!("Could not parse `{line}` as a 64-bit signed integer"))?;
-        nums.push(num);
-    }
+/// Parse the encrypted file, one number per line, along with how many lines were skipped for not
+/// parsing.
+fn parse(input: &mut dyn BufRead, skip_bad_lines: bool) -> Result<(Vec<i64>, usize), SolverError> {
+    let mut lines = LineParser::new(skip_bad_lines);
+    let mut numbers = Vec::new();
+    for_each_line(input, |line| {
+        numbers.extend(lines.parse(line, nom::character::complete::i64));
+        Ok::<_, std::io::Error>(())
+    })
+    .context("Could not read challenge input file")?;
 
-    Ok(nums)
+    let skipped = lines.finish().context("Could not parse numbers")?;
+    Ok((numbers, skipped))
 }
 
-fn solve(numbers: Vec<i64>, decryption_key: i64, mixer_iterations: usize) -> i64 {
-    let next_jump_size = (numbers.len() as f64 / 2.0).sqrt().floor() as usize;
+/// Make up an encrypted file of `len` numbers, all of them random except for a single zero.
+fn generate(len: usize, rng: &mut Scrambler) -> Result<String, SolverError> {
+    if len < 2 {
+        return Err(SolverError::invalid_input(format!(
+            "A file needs at least 2 numbers to be mixed, not {len}"
+        )));
+    }
+    let mut numbers = (1..len)
+        .map(|_| {
+            let n = 1 + rng.below(MAX_GENERATED_NUMBER) as i64;
+            if rng.chance() {
+                -n
+            } else {
+                n
+            }
+        })
+        .collect::<Vec<_>>();
+    numbers.insert(rng.below(len as u64) as usize, 0);
+    Ok(numbers.iter().map(|n| format!("{n}\n")).collect())
+}
 
+fn solve(
+    numbers: Vec<i64>,
+    decryption_key: i64,
+    mixer_iterations: usize,
+    math: MathMode,
+) -> Result<i64, SolverError> {
+    // A skipped line could have been the zero.
+    let zero_index = numbers.iter().position(|&x| x == 0).ok_or_else(|| {
+        SolverError::invalid_input("There's no 0 to find the grove coordinates from")
+    })?;
     let numbers = numbers
         .into_iter()
-        .map(|x| x * decryption_key)
-        .collect::<Vec<_>>();
+        .map(|x| math.mul(x, decryption_key))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let mut prev = (0..numbers.len() as u16).collect::<Vec<_>>();
-    let mut next = prev.clone();
-
-    prev.rotate_right(1);
-    next.rotate_left(next_jump_size % numbers.len());
+    let mut list = IndexList::new(numbers.len())?;
 
     for _ in 0..mixer_iterations {
         for (cur, &n) in numbers.iter().enumerate() {
-            // remove cur from the list
-            fix_pairs_backwards(prev[cur], next[cur], &mut prev, &mut next, cur as _);
-
-            // find the node to insert cur after
             let amount_to_move = n.rem_euclid(numbers.len() as i64 - 1) as usize;
-            let target = find_target(prev[cur], amount_to_move, next_jump_size, &prev, &next);
-
-            // insert cur after the target
-            prev[cur] = target;
-            fix_pairs_backwards(
-                cur as u16,
-                next[target as usize],
-                &mut prev,
-                &mut next,
-                target,
-            );
+            list.move_forward(cur as Index, amount_to_move);
         }
     }
 
-    let zero_index = numbers
-        .iter()
-        .position(|&x| x == 0)
-        .expect("challenge input does not contain an element with value 0");
-
-    itertools::iterate(zero_index as u16, |&cur| {
-        find_target(cur, 1000, next_jump_size, &prev, &next)
-    })
-    .skip(1)
-    .take(3)
-    .map(|i| numbers[i as usize])
-    .sum()
+    Ok(
+        itertools::iterate(zero_index as Index, |&cur| list.find_target(cur, 1000))
+            .skip(1)
+            .take(3)
+            .try_fold(0, |sum, i| math.add(sum, numbers[i as usize]))?,
+    )
 }
 
-fn fix_pairs_backwards(left: u16, right: u16, prev: &mut [u16], next: &mut [u16], stop: u16) {
-    let (far_prev, immediate_next) = itertools::iterate(left, |&i| prev[i as usize])
-        .zip(itertools::iterate(right, |&i| prev[i as usize]))
-        .inspect(|&(before, after)| {
-            next[before as usize] = after;
-        })
-        .find(|&(_, after)| prev[after as usize] == stop)
-        .unwrap();
-    prev[immediate_next as usize] = left;
-    next[prev[far_prev as usize] as usize] = left;
+/// The position of a number in the encrypted file.
+type Index = u32;
+
+/// The mixed numbers, as a circular linked list of their positions in the encrypted file.
+///
+/// `prev` links each number to the one right before it, but `next` skips ahead by `jump` numbers
+/// at once, so that moving a long way along the list only takes a few steps.
+struct IndexList {
+    prev: Vec<Index>,
+    next: Vec<Index>,
+    jump: usize,
 }
 
-fn find_target(
-    from: u16,
-    amount_to_move: usize,
-    next_jump_size: usize,
-    prev: &[u16],
-    next: &[u16],
-) -> u16 {
-    let overshot_target = itertools::iterate(from, |&cur| next[cur as usize])
-        .nth((next_jump_size + amount_to_move) / next_jump_size)
-        .unwrap();
-    itertools::iterate(overshot_target, |&cur| prev[cur as usize])
-        .nth(next_jump_size - amount_to_move % next_jump_size)
-        .unwrap()
+impl IndexList {
+    /// Link up `len` numbers in their original order.
+    ///
+    /// Fails if there are fewer than 2 numbers, since a number can't move anywhere on its own,
+    /// or too many for every one of them to get its own [`Index`].
+    fn new(len: usize) -> Result<Self, SolverError> {
+        if len < 2 {
+            return Err(SolverError::invalid_input(format!(
+                "A file needs at least 2 numbers to be mixed, not {len}"
+            )));
+        }
+        let Ok(end) = Index::try_from(len) else {
+            return Err(SolverError::invalid_input(format!(
+                "The file has {len} numbers, but at most {} can be mixed",
+                Index::MAX
+            )));
+        };
+        let jump = (len as f64 / 2.0).sqrt().floor() as usize;
+
+        let mut prev = (0..end).collect::<Vec<_>>();
+        let mut next = prev.clone();
+        prev.rotate_right(1);
+        next.rotate_left(jump % len);
+
+        Ok(Self { prev, next, jump })
+    }
+
+    /// Take `cur` out of the list, and put it back in `amount_to_move` numbers further along.
+    fn move_forward(&mut self, cur: Index, amount_to_move: usize) {
+        let i = cur as usize;
+
+        // remove cur from the list
+        self.fix_pairs_backwards(self.prev[i], self.next[i], cur);
+
+        // find the node to insert cur after
+        let target = self.find_target(self.prev[i], amount_to_move);
+
+        // insert cur after the target
+        self.prev[i] = target;
+        self.fix_pairs_backwards(cur, self.next[target as usize], target);
+    }
+
+    fn fix_pairs_backwards(&mut self, left: Index, right: Index, stop: Index) {
+        let Self { prev, next, .. } = self;
+        let (far_prev, immediate_next) = itertools::iterate(left, |&i| prev[i as usize])
+            .zip(itertools::iterate(right, |&i| prev[i as usize]))
+            .inspect(|&(before, after)| {
+                next[before as usize] = after;
+            })
+            .find(|&(_, after)| prev[after as usize] == stop)
+            .unwrap();
+        prev[immediate_next as usize] = left;
+        next[prev[far_prev as usize] as usize] = left;
+    }
+
+    /// The number that's `amount_to_move` numbers after `from`.
+    fn find_target(&self, from: Index, amount_to_move: usize) -> Index {
+        let overshot_target = itertools::iterate(from, |&cur| self.next[cur as usize])
+            .nth((self.jump + amount_to_move) / self.jump)
+            .unwrap();
+        itertools::iterate(overshot_target, |&cur| self.prev[cur as usize])
+            .nth(self.jump - amount_to_move % self.jump)
+            .unwrap()
+    }
 }
 
 super::challenge_solver_test_boilerplate! {
-    Solver20;
-    "1\n2\n-3\n3\n-2\n0\n4" => {
-        a as i64: 3,
-        b as i64: 1623178306,
+    Solver20::default();
+    20 => {
+        a as i64,
+        b as i64,
+    }
+
+    #[test]
+    fn mixes_more_numbers_than_fit_in_a_u16() {
+        // Most numbers are a multiple of how far they could move, so they stay put. The two that
+        // don't are past index 65535, and wrap around to land in between the grove coordinates.
+        let len = u16::MAX as usize + 1000;
+        let movers = [(65_600, 2_000), (66_000, -65_000)];
+        let mut numbers = (0..len as i64).map(|i| i * (len as i64 - 1)).collect::<Vec<_>>();
+        for (i, n) in movers {
+            numbers[i] = n;
+        }
+
+        // Move the two numbers the slow way, since nothing else moves.
+        let mut list = (0..len).collect::<Vec<_>>();
+        for (i, n) in movers {
+            let from = list.iter().position(|&j| j == i).unwrap();
+            list.remove(from);
+            let to = (from as i64 + n).rem_euclid(list.len() as i64) as usize;
+            list.insert(to, i);
+        }
+        assert_ne!(list, (0..len).collect::<Vec<_>>());
+        let zero = list.iter().position(|&i| i == 0).unwrap();
+        let expected = [1000, 2000, 3000]
+            .iter()
+            .map(|offset| numbers[list[(zero + offset) % len]])
+            .sum::<i64>();
+
+        let mixed = solve(numbers, 1, 1, MathMode::Checked).unwrap();
+        assert_eq!(mixed, expected);
+        assert_ne!(mixed, 6000 * (len as i64 - 1), "nothing moved");
+    }
+
+    #[test]
+    fn refuses_fewer_than_2_numbers() {
+        assert!(solve(vec![0], 1, 1, MathMode::Checked).is_err());
+        assert!(solve(vec![], 1, 1, MathMode::Checked).is_err());
+    }
+
+    #[test]
+    fn mixes_generated_files_like_a_plain_list_would() {
+        let input = generate(2000, &mut Scrambler::new(20)).unwrap();
+        let (numbers, _) = parse(&mut Cursor::new(&input), false).unwrap();
+        assert_eq!(numbers.len(), 2000);
+        assert_eq!(numbers.iter().filter(|&&n| n == 0).count(), 1);
+
+        // Mix by taking each number out of a list and putting it back in, one by one.
+        let mix = |key: i64, times: usize| {
+            let numbers = numbers.iter().map(|n| n * key).collect::<Vec<_>>();
+            let mut list = (0..numbers.len()).collect::<Vec<_>>();
+            for _ in 0..times {
+                for (i, &n) in numbers.iter().enumerate() {
+                    let from = list.iter().position(|&j| j == i).unwrap();
+                    list.remove(from);
+                    let to = (from as i64 + n).rem_euclid(list.len() as i64) as usize;
+                    list.insert(to, i);
+                }
+            }
+            let zero = list.iter().position(|&i| numbers[i] == 0).unwrap();
+            [1000, 2000, 3000]
+                .iter()
+                .map(|offset| numbers[list[(zero + offset) % list.len()]])
+                .sum::<i64>()
+        };
+
+        assert_eq!(
+            solve(numbers.clone(), 1, 1, MathMode::Checked).unwrap(),
+            mix(1, 1)
+        );
+        assert_eq!(
+            solve(numbers.clone(), PART_B_DECRYPTION_KEY, 10, MathMode::Checked).unwrap(),
+            mix(PART_B_DECRYPTION_KEY, 10)
+        );
+        assert!(generate(1, &mut Scrambler::new(20)).is_err());
+    }
+
+    #[test]
+    fn skips_corrupted_numbers_when_asked_to() -> Result<(), SolverError> {
+        let corrupted = SAMPLE_INPUT.replace("-3\n", "-3x\n");
+        let mut solver = Solver20::default();
+        assert!(solver.solve_a(&mut Cursor::new(&corrupted)).is_err());
+
+        solver.configure(&crate::solver::SolverConfig {
+            skip_bad_lines: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let answer = solver.solve_a(&mut Cursor::new(&corrupted))?;
+        let without = SAMPLE_INPUT.replace("-3\n", "");
+        let expected = solver.solve_a(&mut Cursor::new(without))?;
+        assert_eq!(answer.downcast_ref::<i64>(), expected.downcast_ref::<i64>());
+        assert_eq!(answer.metadata()["skipped_lines"], 1);
+
+        let err = solver
+            .solve_a(&mut Cursor::new(SAMPLE_INPUT.replace("\n0\n", "\nzero\n")))
+            .unwrap_err();
+        assert!(err.to_string().contains("no 0"), "{err}");
+        Ok(())
     }
 }
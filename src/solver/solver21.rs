@@ -1,88 +1,146 @@
+mod dot;
 mod parse;
 
-use std::{
-    collections::{HashMap, VecDeque},
-    fmt,
-};
+use std::{fmt, fs, path::PathBuf};
 
-use color_eyre::eyre::Context;
 use itertools::Itertools;
 
+use crate::{
+    error::{bail, Context, SolverError},
+    explain::{self, explain},
+    intern::Interner,
+    math::MathMode,
+    toposort::toposort,
+};
+
 #[derive(Debug, Default)]
-pub struct Solver21;
+pub struct Solver21 {
+    /// Where to save the monkeys' dependency graph as Graphviz DOT, if anywhere.
+    dot: Option<PathBuf>,
+    math: MathMode,
+}
 
 impl super::ChallengeSolver for Solver21 {
     fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
         21
     }
 
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &super::SolverConfig) -> Result<(), super::ParamError> {
+        self.dot = config.params.get("dot")?;
+        self.math = config.math;
+        Ok(())
+    }
+
+    fn validate(&self, input: &str) -> Option<Result<super::ParseStats, SolverError>> {
+        Some(
+            parse::parse_input(input)
+                .map_err(Into::into)
+                .map(|monkeys| {
+                    let yelling = monkeys
+                        .iter()
+                        .filter(|m| matches!(m.op, Op::Const(_)))
+                        .count();
+                    super::ParseStats::default()
+                        .entity("monkeys", monkeys.len())
+                        .entity("number monkeys", yelling)
+                        .entity("operation monkeys", monkeys.len() - yelling)
+                }),
+        )
+    }
+
+    fn scramble(
+        &self,
+        input: &str,
+        scrambler: &mut super::Scrambler,
+    ) -> Option<Result<String, SolverError>> {
+        Some(
+            parse::parse_input(input)
+                .map_err(Into::into)
+                .map(|monkeys| scramble(monkeys, scrambler)),
+        )
+    }
+
     fn solve_a(&mut self, input: &mut dyn std::io::BufRead) -> super::ChallengeSolverResult {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read challenge input to string")?;
+            .context("Could not read challenge input to string")?;
 
         let mut monkeys = parse::parse_input(&input_buf)
-            .wrap_err("Failed to parse challenge input as a list of monkeys")?;
+            .context("Failed to parse challenge input as a list of monkeys")?;
 
-        let (root_idx, _humn_idx) = resolve_monkeys(&mut monkeys);
-        let root_idx = root_idx
-            .ok_or_else(|| color_eyre::eyre::eyre!("Challenge input is missing a `root` monkey"))?;
-
-        let root_res = monkeys[root_idx].get_value(&monkeys)?;
+        let (root_idx, humn_idx) = resolve_monkeys(&mut monkeys);
+        let root_idx = root_idx.ok_or_else(|| {
+            SolverError::invalid_input("Challenge input is missing a `root` monkey")
+        })?;
+        let values = evaluate(&monkeys, self.math)?;
+        self.save_dot(&monkeys, &values, root_idx, humn_idx)?;
+
+        let root_res = values[root_idx];
+        if explain::enabled() {
+            explain_tree(&monkeys, &values, root_idx, 0);
+        }
 
         dbg!(root_idx);
         dbg!(root_res);
 
-        Ok(Box::new(root_res))
+        Ok(super::Answer::new(root_res))
     }
 
     fn solve_b(&mut self, input: &mut dyn std::io::BufRead) -> super::ChallengeSolverResult {
         let mut input_buf = String::new();
         input
             .read_to_string(&mut input_buf)
-            .wrap_err("Could not read challenge input to string")?;
+            .context("Could not read challenge input to string")?;
 
         let mut monkeys = parse::parse_input(&input_buf)
-            .wrap_err("Failed to parse challenge input as a list of monkeys")?;
+            .context("Failed to parse challenge input as a list of monkeys")?;
 
         let (root_idx, humn_idx) = resolve_monkeys(&mut monkeys);
-        let root_idx = root_idx
-            .ok_or_else(|| color_eyre::eyre::eyre!("Challenge input is missing a `root` monkey"))?;
-        let humn_idx = humn_idx
-            .ok_or_else(|| color_eyre::eyre::eyre!("Challenge input is missing a `humn` monkey"))?;
+        let root_idx = root_idx.ok_or_else(|| {
+            SolverError::invalid_input("Challenge input is missing a `root` monkey")
+        })?;
+        let humn_idx = humn_idx.ok_or_else(|| {
+            SolverError::invalid_input("Challenge input is missing a `humn` monkey")
+        })?;
+        let values = evaluate(&monkeys, self.math)?;
+        self.save_dot(&monkeys, &values, root_idx, Some(humn_idx))?;
 
         println!("root = {} ({})", root_idx, &monkeys[root_idx]);
         println!("humn = {} ({})", humn_idx, &monkeys[humn_idx]);
 
-        let mut queue: VecDeque<(usize, i64)> = VecDeque::new(); // (index, expected value)
-
-        if let Some((lhs_ref, rhs_ref)) = monkeys[root_idx].op.monkey_refs() {
-            if let (Some(lhs_idx), Some(rhs_idx)) = (lhs_ref.resolved_idx(), rhs_ref.resolved_idx())
-            {
-                queue.push_back((rhs_idx, monkeys.get_value(lhs_idx)?));
-                queue.push_back((lhs_idx, monkeys.get_value(rhs_idx)?));
-            }
-        }
-
-        while let Some((i, expected)) = queue.pop_front() {
-            if i == humn_idx {
+        match back_solve(&monkeys, &values, root_idx, humn_idx, self.math)? {
+            Some(expected) => {
                 println!("expected = {expected}");
-                return Ok(Box::new(expected));
+                Ok(super::Answer::new(expected))
             }
-
-            if let Some((lhs_ref, rhs_ref)) = monkeys[i].op.monkey_refs() {
-                if let (Some(lhs_idx), Some(rhs_idx)) =
-                    (lhs_ref.resolved_idx(), rhs_ref.resolved_idx())
-                {
-                    queue.push_back((lhs_idx, monkeys.get_expected_lhs(i, expected)?));
-                    queue.push_back((rhs_idx, monkeys.get_expected_rhs(i, expected)?));
-                }
+            None => {
+                eprintln!("Ran out of monkeys to search through!");
+                Ok(super::Answer::new(-1_i64))
             }
         }
+    }
+}
 
-        eprintln!("Ran out of monkeys to search through!");
-        Ok(Box::new(-1_i64))
+impl Solver21 {
+    /// Save the dependency graph, if that was asked for.
+    fn save_dot(
+        &self,
+        monkeys: &[Monkey],
+        values: &[i64],
+        root_idx: usize,
+        humn_idx: Option<usize>,
+    ) -> Result<(), SolverError> {
+        let Some(path) = &self.dot else {
+            return Ok(());
+        };
+
+        fs::write(path, dot::to_dot(monkeys, values, root_idx, humn_idx))
+            .with_context(|| format!("Could not save graph to {}", path.display()))?;
+        println!("Saved graph to {}", path.display());
+        Ok(())
     }
 }
 
@@ -90,38 +148,216 @@ impl super::ChallengeSolver for Solver21 {
 /// return the index of the `root` monkey and the index of the human (`humn`) in
 /// the passed-in slice.
 fn resolve_monkeys(monkeys: &mut [Monkey]) -> (Option<usize>, Option<usize>) {
-    let name_to_index: HashMap<String, usize> = monkeys
-        .iter()
-        .enumerate()
-        .map(|(i, monkey)| (monkey.name.clone(), i))
-        .collect();
+    // Interning the names, instead of copying each one into a map, keeps them all in one buffer.
+    // If a name comes up twice, the last monkey with it wins.
+    let mut names = Interner::new();
+    let mut indices = Vec::with_capacity(monkeys.len());
+    for (i, monkey) in monkeys.iter().enumerate() {
+        let symbol = names.intern(&monkey.name);
+        match indices.get_mut(symbol.index()) {
+            Some(index) => *index = i,
+            None => indices.push(i),
+        }
+    }
+    let name_to_index = |name: &str| names.get(name).map(|symbol| indices[symbol.index()]);
 
     for monkey in monkeys.iter_mut() {
         if monkey.op.lhs_ref_unresolved() {
-            let (lhs, _) = monkey.op.monkey_refs().unwrap();
-            let lhs_name = lhs.unresolved_name().unwrap();
-            let lhs_idx = name_to_index.get(lhs_name);
+            let lhs_name = monkey.op.lhs().unwrap().unresolved_name().unwrap();
+            let lhs_idx = name_to_index(lhs_name);
 
             if let Some(lhs_idx) = lhs_idx {
-                monkey.op.set_lhs(MonkeyRef::Resolved(*lhs_idx));
+                monkey.op.set_lhs(MonkeyRef::Resolved(lhs_idx));
             }
         }
 
         if monkey.op.rhs_ref_unresolved() {
-            let (_, rhs) = monkey.op.monkey_refs().unwrap();
-            let rhs_name = rhs.unresolved_name().unwrap();
-            let rhs_idx = name_to_index.get(rhs_name);
+            let rhs_name = monkey.op.rhs().unwrap().unresolved_name().unwrap();
+            let rhs_idx = name_to_index(rhs_name);
 
             if let Some(rhs_idx) = rhs_idx {
-                monkey.op.set_rhs(MonkeyRef::Resolved(*rhs_idx));
+                monkey.op.set_rhs(MonkeyRef::Resolved(rhs_idx));
+            }
+        }
+    }
+
+    (name_to_index("root"), name_to_index("humn"))
+}
+
+/// Work out every monkey's number in one pass, starting from the monkeys that yell a number of
+/// their own, so that each monkey's operands are ready by the time it gets its turn.
+fn evaluate(monkeys: &[Monkey], math: MathMode) -> Result<Vec<i64>, SolverError> {
+    let listens_to = monkeys.iter().enumerate().flat_map(|(idx, monkey)| {
+        monkey
+            .op
+            .operands()
+            .filter_map(MonkeyRef::resolved_idx)
+            .map(move |operand| (operand, idx))
+    });
+    let order = toposort(monkeys.len(), listens_to).map_err(|cycle| {
+        SolverError::invalid_input(format!(
+            "Monkeys are waiting on each other's numbers in a loop: {}",
+            cycle
+                .nodes()
+                .iter()
+                .map(|&idx| &monkeys[idx].name)
+                .join(", ")
+        ))
+    })?;
+
+    let mut values = vec![0; monkeys.len()];
+    for idx in order {
+        values[idx] = monkeys[idx].get_value(&values, math)?;
+    }
+    Ok(values)
+}
+
+/// Explain how the monkey at `idx` works out its number, and then how every monkey it listens to
+/// works out theirs, indented by `depth`.
+fn explain_tree(monkeys: &[Monkey], values: &[i64], idx: usize, depth: usize) {
+    let monkey = &monkeys[idx];
+    let value = values[idx];
+    let indent = 2 * depth;
+
+    if let Op::Const(_) = monkey.op {
+        explain!("{:indent$}{}: {value}", "", monkey.name);
+        return;
+    }
+
+    explain!("{:indent$}{} = {value}", "", monkey.describe(monkeys));
+    for child in monkey.op.operands().filter_map(MonkeyRef::resolved_idx) {
+        explain_tree(monkeys, values, child, depth + 1);
+    }
+}
+
+/// Work out what the human has to yell for `root`'s two numbers to be equal, going backwards one
+/// monkey at a time along the path from `root` to `humn`, and explaining each step.
+///
+/// Only the monkeys on that path are undone, so operations that can't be undone, like `%`, are
+/// fine anywhere else. Returns `None` if `root` doesn't listen to `humn` at all.
+fn back_solve(
+    monkeys: &[Monkey],
+    values: &[i64],
+    root_idx: usize,
+    humn_idx: usize,
+    math: MathMode,
+) -> Result<Option<i64>, SolverError> {
+    let Some(path) = path_to(monkeys, root_idx, humn_idx) else {
+        explain!("humn isn't listened to by root, so nothing it yells can matter");
+        return Ok(None);
+    };
+
+    let mut expected = None;
+    for pair in path.windows(2) {
+        let [idx, next] = [pair[0], pair[1]];
+        let monkey = &monkeys[idx];
+        let on_left = monkey.op.lhs().and_then(MonkeyRef::resolved_idx) == Some(next);
+        let other = if on_left {
+            monkey.op.rhs()
+        } else {
+            monkey.op.lhs()
+        };
+        let next_name = &monkeys[next].name;
+
+        let next_expected = match (expected, other) {
+            // The root only checks that both of its numbers are equal.
+            (None, Some(other)) => {
+                let other_value = values[other.resolved_idx().unwrap()];
+                explain!(
+                    "{}: {lhs_name} and {rhs_name} must be equal, and {other_name} = \
+                     {other_value}, so {next_name} must be {other_value}",
+                    monkey.name,
+                    lhs_name = monkey.op.lhs().unwrap().name(monkeys),
+                    rhs_name = monkey.op.rhs().unwrap().name(monkeys),
+                    other_name = other.name(monkeys),
+                );
+                other_value
             }
+            (None, None) => bail!(
+                "`{}` has to compare two numbers, not just {next_name}'s",
+                monkey.name
+            ),
+            (Some(expected), other) => {
+                let next_expected = if on_left {
+                    monkey.get_expected_lhs(expected, values, math)?
+                } else {
+                    monkey.get_expected_rhs(expected, values, math)?
+                };
+                match other {
+                    Some(other) => explain!(
+                        "{} must be {expected}, and {other_name} = {other_value}, so {next_name} \
+                         must be {next_expected}",
+                        monkey.describe(monkeys),
+                        other_name = other.name(monkeys),
+                        other_value = values[other.resolved_idx().unwrap()],
+                    ),
+                    None => explain!(
+                        "{} must be {expected}, so {next_name} must be {next_expected}",
+                        monkey.describe(monkeys),
+                    ),
+                }
+                next_expected
+            }
+        };
+        expected = Some(next_expected);
+    }
+    Ok(expected)
+}
+
+/// The monkeys that `from` listens to, and that they listen to, and so on, until `to` is reached.
+///
+/// Starts with `from` and ends with `to`, or is `None` if `from` doesn't depend on `to` at all.
+fn path_to(monkeys: &[Monkey], from: usize, to: usize) -> Option<Vec<usize>> {
+    if from == to {
+        return Some(vec![to]);
+    }
+
+    monkeys[from]
+        .op
+        .operands()
+        .filter_map(MonkeyRef::resolved_idx)
+        .find_map(|child| path_to(monkeys, child, to))
+        .map(|mut path| {
+            path.insert(0, from);
+            path
+        })
+}
+
+/// Rename every monkey except for `root` and `humn`, shuffle them, and swap the operands of
+/// additions and multiplications at random.
+///
+/// The numbers are left alone, since changing any of them could make a division uneven.
+fn scramble(mut monkeys: Vec<Monkey>, scrambler: &mut super::Scrambler) -> String {
+    let renames = scrambler.rename(
+        monkeys.iter().map(|monkey| monkey.name.clone()),
+        &["root".to_string(), "humn".to_string()],
+        |s| s.word(b"abcdefghijklmnopqrstuvwxyz", 4),
+    );
+    let rename = |monkey_ref: &MonkeyRef| match monkey_ref {
+        MonkeyRef::Unresolved(name) => {
+            MonkeyRef::Unresolved(renames.get(name).unwrap_or(name).clone())
         }
+        MonkeyRef::Resolved(idx) => MonkeyRef::Resolved(*idx),
+    };
+
+    for monkey in &mut monkeys {
+        monkey.name = renames[&monkey.name].clone();
+        monkey.op = match &monkey.op {
+            Op::Const(n) => Op::Const(*n),
+            Op::Add(lhs, rhs) if scrambler.chance() => Op::Add(rename(rhs), rename(lhs)),
+            Op::Add(lhs, rhs) => Op::Add(rename(lhs), rename(rhs)),
+            Op::Sub(lhs, rhs) => Op::Sub(rename(lhs), rename(rhs)),
+            Op::Mul(lhs, rhs) if scrambler.chance() => Op::Mul(rename(rhs), rename(lhs)),
+            Op::Mul(lhs, rhs) => Op::Mul(rename(lhs), rename(rhs)),
+            Op::Div(lhs, rhs) => Op::Div(rename(lhs), rename(rhs)),
+            Op::Rem(lhs, rhs) => Op::Rem(rename(lhs), rename(rhs)),
+            Op::Pow(lhs, rhs) => Op::Pow(rename(lhs), rename(rhs)),
+            Op::Neg(operand) => Op::Neg(rename(operand)),
+        };
     }
+    scrambler.shuffle(&mut monkeys);
 
-    (
-        name_to_index.get("root").copied(),
-        name_to_index.get("humn").copied(),
-    )
+    monkeys.iter().map(|monkey| format!("{monkey}\n")).collect()
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -131,39 +367,27 @@ struct Monkey {
 }
 
 impl Monkey {
+    /// The monkey's number, given the numbers of every monkey it listens to in `values`.
     #[inline]
-    fn get_value(&self, monkeys: &[Monkey]) -> color_eyre::Result<i64> {
-        self.op.get_value(monkeys).wrap_err_with(|| {
-            format!(
-                "Could not get value for monkey {} ({})",
-                monkeys
-                    .iter()
-                    .find_position(|other| *other == self)
-                    .unwrap()
-                    .0,
-                self,
-            )
-        })
+    fn get_value(&self, values: &[i64], math: MathMode) -> Result<i64, SolverError> {
+        self.op
+            .get_value(values, math)
+            .with_context(|| format!("Could not get value for monkey {self}"))
     }
 
     #[inline]
     fn get_expected_lhs(
         &self,
         expected_result: i64,
-        monkeys: &[Monkey],
-    ) -> color_eyre::Result<i64> {
+        values: &[i64],
+        math: MathMode,
+    ) -> Result<i64, SolverError> {
         self.op
-            .get_expected_lhs(expected_result, monkeys)
-            .wrap_err_with(|| {
+            .get_expected_lhs(expected_result, values, math)
+            .with_context(|| {
                 format!(
-                    "Could not get lhs value for monkey {} ({}) given expected result {}",
-                    monkeys
-                        .iter()
-                        .find_position(|other| *other == self)
-                        .unwrap()
-                        .0,
-                    self,
-                    expected_result,
+                    "Could not get lhs value for monkey {self} given expected result \
+                     {expected_result}"
                 )
             })
     }
@@ -172,62 +396,52 @@ impl Monkey {
     fn get_expected_rhs(
         &self,
         expected_result: i64,
-        monkeys: &[Monkey],
-    ) -> color_eyre::Result<i64> {
+        values: &[i64],
+        math: MathMode,
+    ) -> Result<i64, SolverError> {
         self.op
-            .get_expected_rhs(expected_result, monkeys)
-            .wrap_err_with(|| {
+            .get_expected_rhs(expected_result, values, math)
+            .with_context(|| {
                 format!(
-                    "Could not get rhs value for monkey {} ({}) given expected result {}",
-                    monkeys
-                        .iter()
-                        .find_position(|other| *other == self)
-                        .unwrap()
-                        .0,
-                    self,
-                    expected_result,
+                    "Could not get rhs value for monkey {self} given expected result \
+                     {expected_result}"
                 )
             })
     }
 }
 
+impl Monkey {
+    /// Like the monkey's line in the input, with names instead of indices, like
+    /// `pppw: cczh / lfqf`.
+    fn describe(&self, monkeys: &[Monkey]) -> String {
+        match (self.op.lhs(), self.op.rhs()) {
+            (Some(operand), None) => format!(
+                "{}: {}{}",
+                self.name,
+                self.op.symbol(),
+                operand.name(monkeys)
+            ),
+            (Some(lhs), Some(rhs)) => format!(
+                "{}: {} {} {}",
+                self.name,
+                lhs.name(monkeys),
+                self.op.symbol(),
+                rhs.name(monkeys),
+            ),
+            _ => self.to_string(),
+        }
+    }
+}
+
 impl fmt::Display for Monkey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}: {}", self.name, self.op)
     }
 }
 
-trait MonkeyCollection<Idx>
-where
-    Idx: ?Sized,
-{
-    fn get_value(&self, index: Idx) -> color_eyre::Result<i64>;
-    fn get_expected_lhs(&self, index: usize, expected_result: i64) -> color_eyre::Result<i64>;
-    fn get_expected_rhs(&self, index: usize, expected_result: i64) -> color_eyre::Result<i64>;
-}
-
-impl<T> MonkeyCollection<usize> for T
-where
-    T: AsRef<[Monkey]>,
-{
-    #[inline]
-    fn get_value(&self, index: usize) -> color_eyre::Result<i64> {
-        let self_ref = self.as_ref();
-        self_ref[index].get_value(self_ref)
-    }
-
-    #[inline]
-    fn get_expected_lhs(&self, index: usize, expected_result: i64) -> color_eyre::Result<i64> {
-        let self_ref = self.as_ref();
-        self_ref[index].get_expected_lhs(expected_result, self_ref)
-    }
-
-    #[inline]
-    fn get_expected_rhs(&self, index: usize, expected_result: i64) -> color_eyre::Result<i64> {
-        let self_ref = self.as_ref();
-        self_ref[index].get_expected_rhs(expected_result, self_ref)
-    }
-}
+/// The biggest exponent that `^` accepts. Anything bigger overflows an `i64` unless the base is
+/// -1, 0 or 1, which nobody needs an operator for.
+const MAX_EXPONENT: i64 = 63;
 
 #[derive(Debug, Clone, PartialEq)]
 enum Op {
@@ -236,10 +450,17 @@ enum Op {
     Sub(MonkeyRef, MonkeyRef),
     Mul(MonkeyRef, MonkeyRef),
     Div(MonkeyRef, MonkeyRef),
+    /// The remainder of dividing `lhs` by `rhs`, which has the sign of `lhs`.
+    Rem(MonkeyRef, MonkeyRef),
+    /// `lhs` to the power of `rhs`, which has to be from 0 to [`MAX_EXPONENT`].
+    Pow(MonkeyRef, MonkeyRef),
+    /// The operand's number with its sign flipped.
+    Neg(MonkeyRef),
 }
 
 impl Op {
-    fn get_value(&self, monkeys: &[Monkey]) -> color_eyre::Result<i64> {
+    /// The result of the operation, with the operands' numbers taken from `values`.
+    fn get_value(&self, values: &[i64], math: MathMode) -> Result<i64, SolverError> {
         use MonkeyRef::*;
         use Op::*;
 
@@ -247,48 +468,65 @@ impl Op {
             Const(num) => Ok(*num),
 
             Add(Resolved(lhs_idx), Resolved(rhs_idx)) => {
-                Ok(monkeys.get_value(*lhs_idx)? + monkeys.get_value(*rhs_idx)?)
+                Ok(math.add(values[*lhs_idx], values[*rhs_idx])?)
             }
             Sub(Resolved(lhs_idx), Resolved(rhs_idx)) => {
-                Ok(monkeys.get_value(*lhs_idx)? - monkeys.get_value(*rhs_idx)?)
+                Ok(math.sub(values[*lhs_idx], values[*rhs_idx])?)
             }
             Mul(Resolved(lhs_idx), Resolved(rhs_idx)) => {
-                Ok(monkeys.get_value(*lhs_idx)? * monkeys.get_value(*rhs_idx)?)
+                Ok(math.mul(values[*lhs_idx], values[*rhs_idx])?)
             }
             Div(Resolved(lhs_idx), Resolved(rhs_idx)) => {
-                Ok(monkeys.get_value(*lhs_idx)? / monkeys.get_value(*rhs_idx)?)
+                Ok(math.div(values[*lhs_idx], values[*rhs_idx])?)
             }
-
-            Add(lhs, rhs) | Sub(lhs, rhs) | Mul(lhs, rhs) | Div(lhs, rhs) => {
-                color_eyre::eyre::bail!(
-                    "Operation has unresolved references (lhs = {lhs}, rhs = {rhs})"
-                )
+            Rem(Resolved(lhs_idx), Resolved(rhs_idx)) => {
+                Ok(math.rem(values[*lhs_idx], values[*rhs_idx])?)
+            }
+            Pow(Resolved(lhs_idx), Resolved(rhs_idx)) => {
+                pow(values[*lhs_idx], values[*rhs_idx], math)
             }
+            Neg(Resolved(idx)) => Ok(math.sub(0, values[*idx])?),
+
+            Add(lhs, rhs)
+            | Sub(lhs, rhs)
+            | Mul(lhs, rhs)
+            | Div(lhs, rhs)
+            | Rem(lhs, rhs)
+            | Pow(lhs, rhs) => {
+                bail!("Operation has unresolved references (lhs = {lhs}, rhs = {rhs})")
+            }
+            Neg(operand) => bail!("Operation has an unresolved reference ({operand})"),
         }
     }
 
     fn get_expected_lhs(
         &self,
         expected_result: i64,
-        monkeys: &[Monkey],
-    ) -> color_eyre::Result<i64> {
+        values: &[i64],
+        math: MathMode,
+    ) -> Result<i64, SolverError> {
         use MonkeyRef::*;
         use Op::*;
 
         match self {
-            Const(_) => color_eyre::eyre::bail!(
-                "Cannot get expected (aka \"opposite\") lhs value for a constant operation"
-            ),
+            Const(_) => {
+                bail!("Cannot get expected (aka \"opposite\") lhs value for a constant operation")
+            }
 
-            Add(_, Resolved(rhs_idx)) => Ok(expected_result - monkeys.get_value(*rhs_idx)?),
-            Sub(_, Resolved(rhs_idx)) => Ok(expected_result + monkeys.get_value(*rhs_idx)?),
-            Mul(_, Resolved(rhs_idx)) => Ok(expected_result / monkeys.get_value(*rhs_idx)?),
-            Div(_, Resolved(rhs_idx)) => Ok(expected_result * monkeys.get_value(*rhs_idx)?),
+            Add(_, Resolved(rhs_idx)) => Ok(math.sub(expected_result, values[*rhs_idx])?),
+            Sub(_, Resolved(rhs_idx)) => Ok(math.add(expected_result, values[*rhs_idx])?),
+            Mul(_, Resolved(rhs_idx)) => Ok(math.div(expected_result, values[*rhs_idx])?),
+            Div(_, Resolved(rhs_idx)) => Ok(math.mul(expected_result, values[*rhs_idx])?),
+            Rem(..) => bail!(
+                "Cannot undo a remainder: every number that's {expected_result} more than a \
+                 multiple of the rhs has it"
+            ),
+            Pow(_, Resolved(rhs_idx)) => root(expected_result, values[*rhs_idx]),
+            // A negation's only operand is its lhs.
+            Neg(_) => Ok(math.sub(0, expected_result)?),
 
-            Add(lhs, rhs) | Sub(lhs, rhs) | Mul(lhs, rhs) | Div(lhs, rhs) => {
-                color_eyre::eyre::bail!(
-                    "Operation has unresolved references (lhs = {lhs}, rhs = {rhs})"
-                )
+            Add(lhs, rhs) | Sub(lhs, rhs) | Mul(lhs, rhs) | Div(lhs, rhs) | Pow(lhs, rhs) => {
+                bail!("Operation has unresolved references (lhs = {lhs}, rhs = {rhs})")
             }
         }
     }
@@ -296,66 +534,98 @@ impl Op {
     fn get_expected_rhs(
         &self,
         expected_result: i64,
-        monkeys: &[Monkey],
-    ) -> color_eyre::Result<i64> {
+        values: &[i64],
+        math: MathMode,
+    ) -> Result<i64, SolverError> {
         use MonkeyRef::*;
         use Op::*;
 
         match self {
-            Const(_) => color_eyre::eyre::bail!(
+            Const(_) => bail!(
                 "Cannot get expected (aka \"opposite\") rhs value for constant operation `{self:?}`"
             ),
+            Neg(_) => bail!("Cannot get expected rhs value for a negation, which only has an lhs"),
+
+            Add(Resolved(lhs_idx), _) => Ok(math.sub(expected_result, values[*lhs_idx])?),
+            Sub(Resolved(lhs_idx), _) => Ok(math.sub(values[*lhs_idx], expected_result)?),
+            Mul(Resolved(lhs_idx), _) => Ok(math.div(expected_result, values[*lhs_idx])?),
+            Div(Resolved(lhs_idx), _) => Ok(math.div(values[*lhs_idx], expected_result)?),
+            Rem(..) => {
+                bail!("Cannot undo a remainder: more than one divisor can leave {expected_result}")
+            }
+            Pow(Resolved(lhs_idx), _) => log(values[*lhs_idx], expected_result),
 
-            Add(Resolved(lhs_idx), _) => Ok(expected_result - monkeys.get_value(*lhs_idx)?),
-            Sub(Resolved(lhs_idx), _) => Ok(monkeys.get_value(*lhs_idx)? - expected_result),
-            Mul(Resolved(lhs_idx), _) => Ok(expected_result / monkeys.get_value(*lhs_idx)?),
-            Div(Resolved(lhs_idx), _) => Ok(monkeys.get_value(*lhs_idx)? / expected_result),
-
-            Add(lhs, rhs) | Sub(lhs, rhs) | Mul(lhs, rhs) | Div(lhs, rhs) => {
-                color_eyre::eyre::bail!(
-                    "Operation has unresolved references (lhs = {lhs}, rhs = {rhs})"
-                )
+            Add(lhs, rhs) | Sub(lhs, rhs) | Mul(lhs, rhs) | Div(lhs, rhs) | Pow(lhs, rhs) => {
+                bail!("Operation has unresolved references (lhs = {lhs}, rhs = {rhs})")
             }
         }
     }
 
     fn lhs_ref_unresolved(&self) -> bool {
-        matches!(
-            self,
-            Self::Add(MonkeyRef::Unresolved(_), _)
-                | Self::Sub(MonkeyRef::Unresolved(_), _)
-                | Self::Mul(MonkeyRef::Unresolved(_), _)
-                | Self::Div(MonkeyRef::Unresolved(_), _)
-        )
+        matches!(self.lhs(), Some(MonkeyRef::Unresolved(_)))
     }
 
     fn rhs_ref_unresolved(&self) -> bool {
-        matches!(
-            self,
-            Self::Add(_, MonkeyRef::Unresolved(_))
-                | Self::Sub(_, MonkeyRef::Unresolved(_))
-                | Self::Mul(_, MonkeyRef::Unresolved(_))
-                | Self::Div(_, MonkeyRef::Unresolved(_))
-        )
+        matches!(self.rhs(), Some(MonkeyRef::Unresolved(_)))
+    }
+
+    /// The operator between the two monkeys, or in front of the one for a negation, or nothing
+    /// for a constant.
+    fn symbol(&self) -> &'static str {
+        match self {
+            Self::Const(_) => "",
+            Self::Add(..) => "+",
+            Self::Sub(..) | Self::Neg(_) => "-",
+            Self::Mul(..) => "*",
+            Self::Div(..) => "/",
+            Self::Rem(..) => "%",
+            Self::Pow(..) => "^",
+        }
     }
 
-    fn monkey_refs(&self) -> Option<(&MonkeyRef, &MonkeyRef)> {
+    /// The first monkey the operation listens to, which is the only one for a negation.
+    fn lhs(&self) -> Option<&MonkeyRef> {
         match self {
-            Self::Add(lhs, rhs)
-            | Self::Sub(lhs, rhs)
-            | Self::Mul(lhs, rhs)
-            | Self::Div(lhs, rhs) => Some((lhs, rhs)),
+            Self::Add(lhs, _)
+            | Self::Sub(lhs, _)
+            | Self::Mul(lhs, _)
+            | Self::Div(lhs, _)
+            | Self::Rem(lhs, _)
+            | Self::Pow(lhs, _)
+            | Self::Neg(lhs) => Some(lhs),
 
             Self::Const(_) => None,
         }
     }
 
+    /// The second monkey the operation listens to, if it listens to two.
+    fn rhs(&self) -> Option<&MonkeyRef> {
+        match self {
+            Self::Add(_, rhs)
+            | Self::Sub(_, rhs)
+            | Self::Mul(_, rhs)
+            | Self::Div(_, rhs)
+            | Self::Rem(_, rhs)
+            | Self::Pow(_, rhs) => Some(rhs),
+
+            Self::Const(_) | Self::Neg(_) => None,
+        }
+    }
+
+    /// Every monkey the operation listens to, from left to right.
+    fn operands(&self) -> impl Iterator<Item = &MonkeyRef> {
+        self.lhs().into_iter().chain(self.rhs())
+    }
+
     fn set_lhs(&mut self, lhs: MonkeyRef) -> Option<MonkeyRef> {
         match self {
             Self::Add(old_lhs, _)
             | Self::Sub(old_lhs, _)
             | Self::Mul(old_lhs, _)
-            | Self::Div(old_lhs, _) => Some(std::mem::replace(old_lhs, lhs)),
+            | Self::Div(old_lhs, _)
+            | Self::Rem(old_lhs, _)
+            | Self::Pow(old_lhs, _)
+            | Self::Neg(old_lhs) => Some(std::mem::replace(old_lhs, lhs)),
 
             Self::Const(_) => None,
         }
@@ -366,11 +636,72 @@ impl Op {
             Self::Add(_, old_rhs)
             | Self::Sub(_, old_rhs)
             | Self::Mul(_, old_rhs)
-            | Self::Div(_, old_rhs) => Some(std::mem::replace(old_rhs, rhs)),
+            | Self::Div(_, old_rhs)
+            | Self::Rem(_, old_rhs)
+            | Self::Pow(_, old_rhs) => Some(std::mem::replace(old_rhs, rhs)),
 
-            Self::Const(_) => None,
+            Self::Const(_) | Self::Neg(_) => None,
+        }
+    }
+}
+
+/// `base` to the power of `exponent`, by multiplying it over and over so that `math` gets to
+/// decide what happens when it overflows.
+fn pow(base: i64, exponent: i64, math: MathMode) -> Result<i64, SolverError> {
+    if !(0..=MAX_EXPONENT).contains(&exponent) {
+        bail!("Exponent {exponent} isn't from 0 to {MAX_EXPONENT}");
+    }
+    (0..exponent).try_fold(1, |acc, _| Ok(math.mul(acc, base)?))
+}
+
+/// The number that gives `result` when raised to the power of `exponent`, if there's exactly one.
+fn root(result: i64, exponent: i64) -> Result<i64, SolverError> {
+    match exponent {
+        0 => bail!("Cannot undo `^ 0`: every number to the power of 0 is 1"),
+        ..=-1 => bail!("Cannot undo `^ {exponent}`: exponents can't be negative"),
+        1 => return Ok(result),
+        _ if result == 0 => return Ok(0),
+        _ => {}
+    }
+
+    // Odd powers keep the sign and even ones lose it, so only the size of the root has to be
+    // found. Floats get close enough that one of its neighbours is right if any number is.
+    let even = exponent % 2 == 0;
+    let sign = if even { 1 } else { result.signum() };
+    let guess = (result.unsigned_abs() as f64)
+        .powf(1.0 / exponent as f64)
+        .round() as i64;
+    let root = (guess - 1..=guess + 1)
+        .map(|n| n * sign)
+        .find(|&n| pow(n, exponent, MathMode::Checked).ok() == Some(result))
+        .ok_or_else(|| {
+            SolverError::invalid_input(format!(
+                "No whole number to the power of {exponent} is {result}"
+            ))
+        })?;
+    if even {
+        bail!("Cannot undo `^ {exponent}`: both {root} and -{root} give {result}");
+    }
+    Ok(root)
+}
+
+/// The exponent that raises `base` to `result`, if there's exactly one.
+fn log(base: i64, result: i64) -> Result<i64, SolverError> {
+    if matches!(base, -1..=1) {
+        bail!("Cannot undo `{base} ^`: more than one exponent can give the same number");
+    }
+
+    let mut power = 1_i64;
+    for exponent in 0..=MAX_EXPONENT {
+        if power == result {
+            return Ok(exponent);
+        }
+        match power.checked_mul(base) {
+            Some(next) => power = next,
+            None => break,
         }
     }
+    bail!("No whole exponent raises {base} to {result}")
 }
 
 impl Default for Op {
@@ -387,6 +718,9 @@ impl fmt::Display for Op {
             Self::Sub(m1, m2) => write!(f, "{m1} - {m2}"),
             Self::Mul(m1, m2) => write!(f, "{m1} * {m2}"),
             Self::Div(m1, m2) => write!(f, "{m1} / {m2}"),
+            Self::Rem(m1, m2) => write!(f, "{m1} % {m2}"),
+            Self::Pow(m1, m2) => write!(f, "{m1} ^ {m2}"),
+            Self::Neg(m) => write!(f, "-{m}"),
         }
     }
 }
@@ -411,6 +745,14 @@ impl MonkeyRef {
             MonkeyRef::Resolved(idx) => Some(*idx),
         }
     }
+
+    /// The name of the monkey being referred to.
+    fn name<'m>(&'m self, monkeys: &'m [Monkey]) -> &'m str {
+        match self {
+            MonkeyRef::Unresolved(name) => name,
+            MonkeyRef::Resolved(idx) => &monkeys[*idx].name,
+        }
+    }
 }
 
 impl Default for MonkeyRef {
@@ -429,24 +771,79 @@ impl fmt::Display for MonkeyRef {
 }
 
 super::challenge_solver_test_boilerplate! {
-    Solver21;
-    "root: pppw + sjmn\n\
-     dbpl: 5\n\
-     cczh: sllz + lgvd\n\
-     zczc: 2\n\
-     ptdq: humn - dvpt\n\
-     dvpt: 3\n\
-     lfqf: 4\n\
-     humn: 5\n\
-     ljgn: 2\n\
-     sjmn: drzm * dbpl\n\
-     sllz: 4\n\
-     pppw: cczh / lfqf\n\
-     lgvd: ljgn * ptdq\n\
-     drzm: hmdt - zczc\n\
-     hmdt: 32" => {
-        a as i64: 152,
-        b as i64: 301,
+    Solver21::default();
+    21 => {
+        a as i64,
+        b as i64,
+    }
+
+    #[test]
+    fn scrambling_keeps_the_answers() -> Result<(), SolverError> {
+        let mut scrambler = crate::solver::Scrambler::new(21);
+        let scrambled = Solver21::default().scramble(SAMPLE_INPUT, &mut scrambler).unwrap()?;
+
+        assert_ne!(scrambled.lines().next(), SAMPLE_INPUT.lines().next());
+        assert!(!scrambled.contains("pppw"));
+        assert!(scrambled.contains("root: ") && scrambled.contains("humn: 5"));
+
+        let mut input = Cursor::new(scrambled.as_str());
+        assert_eq!(Solver21::default().solve_a(&mut input)?, crate::solver::Answer::new(152_i64));
+        let mut input = Cursor::new(scrambled.as_str());
+        assert_eq!(Solver21::default().solve_b(&mut input)?, crate::solver::Answer::new(301_i64));
+
+        Ok(())
+    }
+
+    #[test]
+    fn explains_back_solving() {
+        let explained = crate::explain::capture(100, || {
+            Solver21::default().solve_b(&mut Cursor::new(SAMPLE_INPUT)).unwrap();
+        });
+
+        assert_eq!(
+            explained.lines().collect::<Vec<_>>(),
+            [
+                "root: pppw and sjmn must be equal, and sjmn = 150, so pppw must be 150",
+                "pppw: cczh / lfqf must be 150, and lfqf = 4, so cczh must be 600",
+                "cczh: sllz + lgvd must be 600, and sllz = 4, so lgvd must be 596",
+                "lgvd: ljgn * ptdq must be 596, and ljgn = 2, so ptdq must be 298",
+                "ptdq: humn - dvpt must be 298, and dvpt = 3, so humn must be 301",
+            ]
+        );
+    }
+
+    #[test]
+    fn dot_highlights_the_human() -> Result<(), SolverError> {
+        let mut monkeys = parse::parse_input(SAMPLE_INPUT)?;
+        let (root_idx, humn_idx) = resolve_monkeys(&mut monkeys);
+        let values = evaluate(&monkeys, MathMode::Checked)?;
+        let dot = dot::to_dot(&monkeys, &values, root_idx.unwrap(), humn_idx);
+
+        assert!(dot.starts_with("digraph monkeys {"));
+        assert!(dot.contains(r#""root" [label="root\n+", color=red"#));
+        assert!(dot.contains(r#""ptdq" -> "humn" [label="lhs", color=red"#));
+        // Nothing under `sjmn` depends on the human, so it's all one box.
+        assert!(dot.contains(r#""sjmn" [label="sjmn = 150\n(5 monkeys)", shape=box]"#));
+        assert!(!dot.contains("hmdt"));
+
+        // Without a human, nothing gets collapsed.
+        let dot = dot::to_dot(&monkeys, &values, root_idx.unwrap(), None);
+        assert!(dot.contains(r#""hmdt" [label="hmdt = 32", shape=box]"#));
+        assert!(!dot.contains("red"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reports_monkeys_waiting_on_each_other() {
+        let input = "root: aaaa + bbbb\naaaa: bbbb * cccc\nbbbb: cccc - aaaa\ncccc: 3\n";
+        let err = Solver21::default()
+            .solve_a(&mut Cursor::new(input))
+            .unwrap_err();
+        assert!(matches!(err, SolverError::InvalidInput(_)));
+        assert!(err
+            .to_string()
+            .contains("waiting on each other's numbers in a loop: aaaa, bbbb"));
     }
 
     const OPS_TEST_INPUT: &str = "\
@@ -470,77 +867,129 @@ super::challenge_solver_test_boilerplate! {
 
 
     #[test]
-    fn ops_get_value() -> color_eyre::Result<()> {
-        color_eyre::install()?;
+    fn ops_get_value() -> Result<(), SolverError> {
 
         let mut monkeys = parse::parse_input(OPS_TEST_INPUT)?;
         resolve_monkeys(&mut monkeys);
+        let values = evaluate(&monkeys, MathMode::Checked)?;
 
-        assert_eq!(monkeys[0].op.get_value(&monkeys)?, 3 + 2, "Op::get_value() addition failed");
-        assert_eq!(monkeys[1].op.get_value(&monkeys)?, 2 - 5, "Op::op.get_value() subtraction failed");
-        assert_eq!(monkeys[2].op.get_value(&monkeys)?, 12 * -3, "Op::op.get_value() multiplication failed");
-        assert_eq!(monkeys[3].op.get_value(&monkeys)?, 20 / -4, "Op::op.get_value() division failed");
+        assert_eq!(monkeys[0].op.get_value(&values, MathMode::Checked)?, 3 + 2, "Op::get_value() addition failed");
+        assert_eq!(monkeys[1].op.get_value(&values, MathMode::Checked)?, 2 - 5, "Op::op.get_value() subtraction failed");
+        assert_eq!(monkeys[2].op.get_value(&values, MathMode::Checked)?, 12 * -3, "Op::op.get_value() multiplication failed");
+        assert_eq!(monkeys[3].op.get_value(&values, MathMode::Checked)?, 20 / -4, "Op::op.get_value() division failed");
 
-        assert_eq!(monkeys[0].get_value(&monkeys)?, 3 + 2, "Monkey::get_value() addition failed");
-        assert_eq!(monkeys[1].get_value(&monkeys)?, 2 - 5, "Monkey::get_value() subtraction failed");
-        assert_eq!(monkeys[2].get_value(&monkeys)?, 12 * -3, "Monkey::get_value() multiplication failed");
-        assert_eq!(monkeys[3].get_value(&monkeys)?, 20 / -4, "Monkey::get_value() division failed");
+        assert_eq!(monkeys[0].get_value(&values, MathMode::Checked)?, 3 + 2, "Monkey::get_value() addition failed");
+        assert_eq!(monkeys[1].get_value(&values, MathMode::Checked)?, 2 - 5, "Monkey::get_value() subtraction failed");
+        assert_eq!(monkeys[2].get_value(&values, MathMode::Checked)?, 12 * -3, "Monkey::get_value() multiplication failed");
+        assert_eq!(monkeys[3].get_value(&values, MathMode::Checked)?, 20 / -4, "Monkey::get_value() division failed");
 
-        assert_eq!(monkeys.get_value(0)?, 3 + 2, "MonkeyCollection::get_value() addition failed");
-        assert_eq!(monkeys.get_value(1)?, 2 - 5, "MonkeyCollection::get_value() subtraction failed");
-        assert_eq!(monkeys.get_value(2)?, 12 * -3, "MonkeyCollection::get_value() multiplication failed");
-        assert_eq!(monkeys.get_value(3)?, 20 / -4, "MonkeyCollection::get_value() division failed");
+        assert_eq!(values[0], 3 + 2, "evaluate() addition failed");
+        assert_eq!(values[1], 2 - 5, "evaluate() subtraction failed");
+        assert_eq!(values[2], 12 * -3, "evaluate() multiplication failed");
+        assert_eq!(values[3], 20 / -4, "evaluate() division failed");
 
         Ok(())
     }
 
     #[test]
-    fn ops_get_expected_lhs() -> color_eyre::Result<()> {
-        color_eyre::install()?;
+    fn ops_get_expected_lhs() -> Result<(), SolverError> {
 
         let mut monkeys = parse::parse_input(OPS_TEST_INPUT)?;
         resolve_monkeys(&mut monkeys);
+        let values = evaluate(&monkeys, MathMode::Checked)?;
 
-        assert_eq!(monkeys[0].op.get_expected_lhs(7, &monkeys)?, 5, "Op::get_expected_lhs() addition failed");
-        assert_eq!(monkeys[1].op.get_expected_lhs(-42, &monkeys)?, -37, "Op::get_expected_lhs() subtraction failed");
-        assert_eq!(monkeys[2].op.get_expected_lhs(27, &monkeys)?, -9, "Op::get_expected_lhs() multiplication failed");
-        assert_eq!(monkeys[3].op.get_expected_lhs(-16, &monkeys)?, 64, "Op::get_expected_lhs() division failed");
+        assert_eq!(monkeys[0].op.get_expected_lhs(7, &values, MathMode::Checked)?, 5, "Op::get_expected_lhs() addition failed");
+        assert_eq!(monkeys[1].op.get_expected_lhs(-42, &values, MathMode::Checked)?, -37, "Op::get_expected_lhs() subtraction failed");
+        assert_eq!(monkeys[2].op.get_expected_lhs(27, &values, MathMode::Checked)?, -9, "Op::get_expected_lhs() multiplication failed");
+        assert_eq!(monkeys[3].op.get_expected_lhs(-16, &values, MathMode::Checked)?, 64, "Op::get_expected_lhs() division failed");
 
-        assert_eq!(monkeys[0].get_expected_lhs(7, &monkeys)?, 5, "Monkey::get_expected_lhs() addition failed");
-        assert_eq!(monkeys[1].get_expected_lhs(-42, &monkeys)?, -37, "Monkey::get_expected_lhs() subtraction failed");
-        assert_eq!(monkeys[2].get_expected_lhs(27, &monkeys)?, -9, "Monkey::get_expected_lhs() multiplication failed");
-        assert_eq!(monkeys[3].get_expected_lhs(-16, &monkeys)?, 64, "Monkey::get_expected_lhs() division failed");
-
-        assert_eq!(monkeys.get_expected_lhs(0, 7)?, 5, "MonkeyCollection::get_expected_lhs() addition failed");
-        assert_eq!(monkeys.get_expected_lhs(1, -42)?, -37, "MonkeyCollection::get_expected_lhs() subtraction failed");
-        assert_eq!(monkeys.get_expected_lhs(2, 27)?, -9, "MonkeyCollection::get_expected_lhs() multiplication failed");
-        assert_eq!(monkeys.get_expected_lhs(3, -16)?, 64, "MonkeyCollection::get_expected_lhs() division failed");
+        assert_eq!(monkeys[0].get_expected_lhs(7, &values, MathMode::Checked)?, 5, "Monkey::get_expected_lhs() addition failed");
+        assert_eq!(monkeys[1].get_expected_lhs(-42, &values, MathMode::Checked)?, -37, "Monkey::get_expected_lhs() subtraction failed");
+        assert_eq!(monkeys[2].get_expected_lhs(27, &values, MathMode::Checked)?, -9, "Monkey::get_expected_lhs() multiplication failed");
+        assert_eq!(monkeys[3].get_expected_lhs(-16, &values, MathMode::Checked)?, 64, "Monkey::get_expected_lhs() division failed");
 
         Ok(())
     }
 
     #[test]
-    fn ops_get_expected_rhs() -> color_eyre::Result<()> {
-        color_eyre::install()?;
+    fn ops_get_expected_rhs() -> Result<(), SolverError> {
 
         let mut monkeys = parse::parse_input(OPS_TEST_INPUT)?;
         resolve_monkeys(&mut monkeys);
+        let values = evaluate(&monkeys, MathMode::Checked)?;
+
+        assert_eq!(monkeys[0].op.get_expected_rhs(-14, &values, MathMode::Checked)?, -17, "Op::get_expected_lhs() addition failed");
+        assert_eq!(monkeys[1].op.get_expected_rhs(10, &values, MathMode::Checked)?, -8, "Op::get_expected_lhs() subtraction failed");
+        assert_eq!(monkeys[2].op.get_expected_rhs(24, &values, MathMode::Checked)?, 2, "Op::get_expected_lhs() multiplication failed");
+        assert_eq!(monkeys[3].op.get_expected_rhs(4, &values, MathMode::Checked)?, 5, "Op::get_expected_lhs() division failed");
+
+        assert_eq!(monkeys[0].get_expected_rhs(-14, &values, MathMode::Checked)?, -17, "Monkey::get_expected_lhs() addition failed");
+        assert_eq!(monkeys[1].get_expected_rhs(10, &values, MathMode::Checked)?, -8, "Monkey::get_expected_lhs() subtraction failed");
+        assert_eq!(monkeys[2].get_expected_rhs(24, &values, MathMode::Checked)?, 2, "Monkey::get_expected_lhs() multiplication failed");
+        assert_eq!(monkeys[3].get_expected_rhs(4, &values, MathMode::Checked)?, 5, "Monkey::get_expected_lhs() division failed");
 
-        assert_eq!(monkeys[0].op.get_expected_rhs(-14, &monkeys)?, -17, "Op::get_expected_lhs() addition failed");
-        assert_eq!(monkeys[1].op.get_expected_rhs(10, &monkeys)?, -8, "Op::get_expected_lhs() subtraction failed");
-        assert_eq!(monkeys[2].op.get_expected_rhs(24, &monkeys)?, 2, "Op::get_expected_lhs() multiplication failed");
-        assert_eq!(monkeys[3].op.get_expected_rhs(4, &monkeys)?, 5, "Op::get_expected_lhs() division failed");
+        Ok(())
+    }
 
-        assert_eq!(monkeys[0].get_expected_rhs(-14, &monkeys)?, -17, "Monkey::get_expected_lhs() addition failed");
-        assert_eq!(monkeys[1].get_expected_rhs(10, &monkeys)?, -8, "Monkey::get_expected_lhs() subtraction failed");
-        assert_eq!(monkeys[2].get_expected_rhs(24, &monkeys)?, 2, "Monkey::get_expected_lhs() multiplication failed");
-        assert_eq!(monkeys[3].get_expected_rhs(4, &monkeys)?, 5, "Monkey::get_expected_lhs() division failed");
+    const MORE_OPS_INPUT: &str = "\
+        root: aaaa + bbbb\n\
+        aaaa: cccc ^ three\n\
+        cccc: -humn\n\
+        humn: 5\n\
+        three: 3\n\
+        bbbb: dddd % eeee\n\
+        dddd: -1000\n\
+        eeee: 10000\n\
+    ";
 
-        assert_eq!(monkeys.get_expected_rhs(0, -14)?, -17, "MonkeyCollection::get_expected_lhs() addition failed");
-        assert_eq!(monkeys.get_expected_rhs(1, 10)?, -8, "MonkeyCollection::get_expected_lhs() subtraction failed");
-        assert_eq!(monkeys.get_expected_rhs(2, 24)?, 2, "MonkeyCollection::get_expected_lhs() multiplication failed");
-        assert_eq!(monkeys.get_expected_rhs(3, 4)?, 5, "MonkeyCollection::get_expected_lhs() division failed");
+    #[test]
+    fn solves_with_more_ops() -> Result<(), SolverError> {
+        let mut input = Cursor::new(MORE_OPS_INPUT);
+        assert_eq!(Solver21::default().solve_a(&mut input)?, crate::solver::Answer::new(-1125_i64));
+        // The remainder isn't between root and humn, so it never has to be undone.
+        let mut input = Cursor::new(MORE_OPS_INPUT);
+        assert_eq!(Solver21::default().solve_b(&mut input)?, crate::solver::Answer::new(10_i64));
+
+        let mut monkeys = parse::parse_input(MORE_OPS_INPUT)?;
+        assert_eq!(monkeys[2].to_string(), "cccc: -humn");
+        assert_eq!(monkeys[6].op, Op::Const(-1000));
+        resolve_monkeys(&mut monkeys);
+        assert_eq!(monkeys[1].describe(&monkeys), "aaaa: cccc ^ three");
+        assert_eq!(monkeys[2].describe(&monkeys), "cccc: -humn");
 
         Ok(())
     }
+
+    #[test]
+    fn undoing_ambiguous_ops_fails() {
+        assert_eq!(pow(-3, 3, MathMode::Checked).unwrap(), -27);
+        assert!(pow(2, 64, MathMode::Checked).is_err());
+        assert!(pow(2, -1, MathMode::Checked).is_err());
+
+        assert_eq!(root(-27, 3).unwrap(), -3);
+        assert_eq!(root(0, 4).unwrap(), 0);
+        assert!(root(16, 4).unwrap_err().to_string().ends_with("both 2 and -2 give 16"));
+        assert!(root(8, 2).unwrap_err().to_string().contains("No whole number"));
+        assert!(root(-8, 2).unwrap_err().to_string().contains("No whole number"));
+        assert!(root(10, 3).unwrap_err().to_string().contains("No whole number"));
+
+        assert_eq!(log(-2, -8).unwrap(), 3);
+        assert_eq!(log(10, 1).unwrap(), 0);
+        assert!(log(1, 1).unwrap_err().to_string().contains("more than one exponent"));
+        assert!(log(3, 10).is_err());
+
+        let rem = Op::Rem(MonkeyRef::Resolved(0), MonkeyRef::Resolved(1));
+        let err = rem.get_expected_lhs(1, &[7, 3], MathMode::Checked).unwrap_err();
+        assert!(matches!(err, SolverError::InvalidInput(_)));
+        assert!(err.to_string().contains("Cannot undo a remainder"));
+    }
+
+    #[test]
+    fn undoing_negative_powers_fails() {
+        for (result, exponent) in [(1, -1), (0, -2), (8, -3)] {
+            let err = root(result, exponent).unwrap_err();
+            assert!(matches!(err, SolverError::InvalidInput(_)));
+            assert!(err.to_string().contains("can't be negative"), "{err}");
+        }
+    }
 }
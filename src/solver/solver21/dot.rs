@@ -0,0 +1,104 @@
+//! Drawing the monkeys' dependency graph with Graphviz, for `--param dot=out.dot`.
+
+use std::{collections::HashSet, fmt::Write};
+
+use super::{path_to, Monkey, MonkeyRef};
+
+/// Colors the monkeys between `root` and `humn`, whose numbers part B works out backwards.
+const PATH_COLOR: &str = "red";
+
+/// The graph of which monkeys listen to which, starting from `root_idx`, in the DOT language, with
+/// every monkey's number taken from `values`.
+///
+/// Every monkey between the root and the human is drawn with its operation, along the
+/// highlighted path that part B solves its way back down. Everything else doesn't depend on the
+/// human, so each of those subtrees is collapsed into a single box with the number it works out
+/// to. If there's no human to find, nothing is collapsed.
+pub(super) fn to_dot(
+    monkeys: &[Monkey],
+    values: &[i64],
+    root_idx: usize,
+    humn_idx: Option<usize>,
+) -> String {
+    let on_path: HashSet<usize> = humn_idx
+        .and_then(|humn_idx| path_to(monkeys, root_idx, humn_idx))
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let mut dot = String::from("digraph monkeys {\n    node [fontname=monospace];\n");
+    write_subtree(&mut dot, monkeys, values, root_idx, &on_path);
+    dot.push_str("}\n");
+    dot
+}
+
+fn write_subtree(
+    dot: &mut String,
+    monkeys: &[Monkey],
+    values: &[i64],
+    idx: usize,
+    on_path: &HashSet<usize>,
+) {
+    let monkey = &monkeys[idx];
+    let name = &monkey.name;
+    let highlight = if on_path.contains(&idx) {
+        format!(", color={PATH_COLOR}, fontcolor={PATH_COLOR}, penwidth=2")
+    } else {
+        String::new()
+    };
+
+    let collapsed = !on_path.is_empty() && !on_path.contains(&idx);
+    if collapsed || monkey.op.lhs().is_none() {
+        // The human's number is what part B is looking for, so it doesn't have one to show.
+        let label = if on_path.contains(&idx) {
+            name.clone()
+        } else {
+            let value = values[idx];
+            match subtree_size(monkeys, idx) {
+                1 => format!("{name} = {value}"),
+                size => format!("{name} = {value}\\n({size} monkeys)"),
+            }
+        };
+        let _ = writeln!(
+            dot,
+            "    \"{name}\" [label=\"{label}\", shape=box{highlight}];"
+        );
+        return;
+    }
+
+    let _ = writeln!(
+        dot,
+        "    \"{name}\" [label=\"{name}\\n{}\"{highlight}];",
+        monkey.op.symbol()
+    );
+    // A negation only has an lhs.
+    for (side, child) in [("lhs", monkey.op.lhs()), ("rhs", monkey.op.rhs())] {
+        let Some(child) = child else {
+            continue;
+        };
+        let Some(child_idx) = child.resolved_idx() else {
+            continue;
+        };
+        let color = if on_path.contains(&child_idx) {
+            format!(", color={PATH_COLOR}, penwidth=2")
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            dot,
+            "    \"{name}\" -> \"{}\" [label=\"{side}\"{color}];",
+            child.name(monkeys)
+        );
+        write_subtree(dot, monkeys, values, child_idx, on_path);
+    }
+}
+
+/// How many monkeys work out the number for the monkey at `idx`, including itself.
+fn subtree_size(monkeys: &[Monkey], idx: usize) -> usize {
+    1 + monkeys[idx]
+        .op
+        .operands()
+        .filter_map(MonkeyRef::resolved_idx)
+        .map(|child| subtree_size(monkeys, child))
+        .sum::<usize>()
+}
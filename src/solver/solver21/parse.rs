@@ -1,82 +1,29 @@
-use miette::GraphicalReportHandler;
 use nom::{
     branch::alt,
     bytes::complete::take_while1,
     character::complete as nom_cc,
     combinator::map,
     error::ParseError,
-    sequence::{separated_pair, terminated, tuple},
+    sequence::{preceded, separated_pair, terminated, tuple},
     IResult, Parser,
 };
-use nom_locate::LocatedSpan;
 use nom_supreme::{
     context::ContextError,
-    error::{BaseErrorKind, ErrorTree, GenericErrorTree},
-    final_parser::final_parser,
+    error::ErrorTree,
     multi::collect_separated_terminated,
     tag::{complete::tag, TagError},
     ParserExt,
 };
 
-use super::{Monkey, MonkeyRef, Op};
+use crate::parse::{BadInputError, Span};
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+use super::{Monkey, MonkeyRef, Op};
 
 /// Parse the challenge input into a vector of [`Blueprint`]s.
 ///
 /// Any parsing errors will be printed out to `stderr` with fancy formatting.
-pub(super) fn parse_input(input: &str) -> Result<Vec<Monkey>, ParseInputError> {
-    let input_span = Span::new(input);
-
-    let valves_res: Result<_, ErrorTree<Span>> =
-        final_parser(parse_all_monkeys::<ErrorTree<Span>>)(input_span);
-
-    match valves_res {
-        Ok(records) => Ok(records),
-
-        Err(e) => match e {
-            GenericErrorTree::Base { location, kind } => {
-                let offset = location.location_offset().into();
-                let err = BadInputError {
-                    src: input.to_string(),
-                    bad_bit: miette::SourceSpan::new(offset, 0.into()),
-                    kind,
-                };
-
-                let mut s = String::new();
-                GraphicalReportHandler::new()
-                    .render_report(&mut s, &err)
-                    .unwrap();
-                eprintln!("{s}");
-
-                Err(err.into())
-            }
-
-            GenericErrorTree::Stack { .. } => todo!("generic error tree stack"),
-            GenericErrorTree::Alt(_) => todo!("generic error tree alt"),
-        },
-    }
-}
-
-#[derive(thiserror::Error, Debug, miette::Diagnostic)]
-#[error("Error parsing input")]
-pub struct BadInputError {
-    #[source_code]
-    src: String,
-
-    #[label("{kind}")]
-    bad_bit: miette::SourceSpan,
-
-    kind: BaseErrorKind<&'static str, Box<dyn std::error::Error + Send + Sync>>,
-}
-
-#[derive(thiserror::Error, Debug)]
-pub enum ParseInputError {
-    #[error("Failed to parse input due to bad input")]
-    BadInputError {
-        #[from]
-        source: BadInputError,
-    },
+pub(super) fn parse_input(input: &str) -> Result<Vec<Monkey>, BadInputError> {
+    crate::parse::parse_input(input, parse_all_monkeys::<ErrorTree<Span>>)
 }
 
 fn parse_monkey_name<'a, E>(i: Span<'a>) -> IResult<Span<'a>, String, E>
@@ -106,6 +53,7 @@ where
 {
     alt((
         map(nom_cc::i64, Op::Const).context("constant number"),
+        map(preceded(tag("-"), parse_monkey_ref), Op::Neg).context("negation operation"),
         map(
             separated_pair(parse_monkey_ref, tag(" + "), parse_monkey_ref),
             |(a, b)| Op::Add(a, b),
@@ -126,6 +74,16 @@ where
             |(a, b)| Op::Div(a, b),
         )
         .context("division operation"),
+        map(
+            separated_pair(parse_monkey_ref, tag(" % "), parse_monkey_ref),
+            |(a, b)| Op::Rem(a, b),
+        )
+        .context("remainder operation"),
+        map(
+            separated_pair(parse_monkey_ref, tag(" ^ "), parse_monkey_ref),
+            |(a, b)| Op::Pow(a, b),
+        )
+        .context("power operation"),
     ))(i)
 }
 
@@ -0,0 +1,475 @@
+#[cfg(feature = "tui")]
+use std::num::NonZeroUsize;
+use std::{collections::HashMap, fmt, io::BufRead};
+
+use serde_json::json;
+
+#[cfg(feature = "tui")]
+use crate::tui_app;
+use crate::{
+    error::{Context, SolverError},
+    grid::{Grid, GridCoord},
+    simulation::{Pacing, Simulation, StepResult},
+};
+
+#[cfg(feature = "tui")]
+use self::viewer::Viewer;
+
+use super::{Answer, ChallengeSolver, Frontend, ParamError, ParseStats, SolverConfig};
+
+mod net;
+mod parse;
+#[cfg(feature = "tui")]
+mod viewer;
+
+#[derive(Debug, Default)]
+pub struct Solver22 {
+    /// Watch the walker follow the path in a TUI, instead of just printing the password.
+    visualize: bool,
+    /// How often the viewer ticks, and how many of its own steps per tick it starts out taking.
+    pacing: Pacing,
+}
+
+impl ChallengeSolver for Solver22 {
+    fn challenge_number(&self) -> crate::challenge::ChallengeNumber {
+        22
+    }
+
+    super::sample_methods!();
+
+    fn configure(&mut self, config: &SolverConfig) -> Result<(), ParamError> {
+        self.visualize = config.visualize;
+        self.pacing = Pacing::from_params(&config.params)?;
+        Ok(())
+    }
+
+    fn frontend(&self) -> Option<Frontend> {
+        Some(Frontend::Tui)
+    }
+
+    fn validate(&self, input: &str) -> Option<Result<ParseStats, SolverError>> {
+        Some(
+            parse::parse_input(input)
+                .and_then(|(board, path)| {
+                    net::check(input, &board)?;
+                    Ok((board, path))
+                })
+                .map_err(Into::into)
+                .map(|(board, path)| {
+                    let count = |tile| board.tiles().filter(|&(_, t)| t == tile).count();
+                    let walks = path
+                        .iter()
+                        .filter(|m| matches!(m, Move::Forward(_)))
+                        .count();
+                    ParseStats::default()
+                        .entity("open tiles", count(Tile::Open))
+                        .entity("walls", count(Tile::Wall))
+                        .entity("walks", walks)
+                        .entity("turns", path.len() - walks)
+                        .bounds(
+                            board
+                                .tiles()
+                                .filter(|&(_, t)| t != Tile::Void)
+                                .map(|(c, _)| (c.x as i64, c.y as i64)),
+                        )
+                }),
+        )
+    }
+
+    fn solve_a(&mut self, input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        let mut input_buf = String::new();
+        input
+            .read_to_string(&mut input_buf)
+            .context("Could not read input file to string")?;
+        let (board, path) = parse::parse_input(&input_buf)
+            .context("Could not parse input file as a board and a path")?;
+        net::check(&input_buf, &board).context("Could not fold the board into a cube")?;
+
+        let mut walker = Walker::new(board, path)?;
+
+        #[cfg(feature = "tui")]
+        if self.visualize {
+            // The viewer counts its own steps per tick, so that they can be sped up and slowed
+            // down from the keyboard.
+            let mut viewer = Viewer::new(walker, self.pacing.steps_per_tick.get());
+            let pacing = Pacing {
+                steps_per_tick: NonZeroUsize::MIN,
+                ..self.pacing
+            };
+            tui_app::run(&mut viewer, 60.0, pacing)?;
+            walker = viewer.into_walker();
+        }
+
+        // Finish the walk, in case the visualization was closed before the end of the path.
+        walker.run(None);
+
+        let password = walker.password();
+        println!("final password: {password}");
+
+        Ok(Answer::new(password).with_metadata("metrics", walker.metrics()))
+    }
+
+    fn solve_b(&mut self, _input: &mut dyn BufRead) -> super::ChallengeSolverResult {
+        Err(SolverError::Unsupported(
+            "Folding the board into a cube isn't implemented yet".to_string(),
+        ))
+    }
+}
+
+/// A single tile of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Tile {
+    /// Not part of the board at all.
+    #[default]
+    Void,
+    Open,
+    Wall,
+}
+
+/// The map of the board from the notes, padded out to a rectangle with [`Tile::Void`].
+struct Board {
+    grid: Grid<Tile>,
+}
+
+impl Board {
+    /// Lay out rows of tiles, which may each be a different length, on a board.
+    fn new(rows: Vec<Vec<Tile>>) -> Self {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut grid = Grid::new(width, rows.len());
+
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, tile) in row.into_iter().enumerate() {
+                *grid.cell_mut((x, y).into()).unwrap() = tile;
+            }
+        }
+
+        Self { grid }
+    }
+
+    const fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    const fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    /// The tile at `coord`, which is [`Tile::Void`] if it's off of the board.
+    fn tile(&self, coord: GridCoord) -> Tile {
+        self.grid.cell(coord).copied().unwrap_or_default()
+    }
+
+    /// Every tile on the board, row by row.
+    fn tiles(&self) -> impl Iterator<Item = (GridCoord, Tile)> + '_ {
+        (0..self.height())
+            .flat_map(move |y| (0..self.width()).map(move |x| GridCoord { x, y }))
+            .map(|coord| (coord, self.tile(coord)))
+    }
+
+    /// The leftmost open tile of the top row, where the path starts.
+    fn start(&self) -> Option<GridCoord> {
+        (0..self.width())
+            .map(|x| GridCoord { x, y: 0 })
+            .find(|&coord| self.tile(coord) == Tile::Open)
+    }
+
+    /// The coordinate one step away from `coord` when facing `facing`, if it's on the grid.
+    fn neighbor(&self, coord: GridCoord, facing: Facing) -> Option<GridCoord> {
+        let (dx, dy) = facing.delta();
+        let next = GridCoord {
+            x: coord.x.checked_add_signed(dx)?,
+            y: coord.y.checked_add_signed(dy)?,
+        };
+        self.grid.in_bounds(next).then_some(next)
+    }
+
+    /// The tile one step ahead of `coord`, wrapping around to the far side of the board when
+    /// stepping off of its edge.
+    fn ahead(&self, coord: GridCoord, facing: Facing) -> GridCoord {
+        if let Some(next) = self
+            .neighbor(coord, facing)
+            .filter(|&next| self.tile(next) != Tile::Void)
+        {
+            return next;
+        }
+
+        // Back up as far as the board goes to find the tile on the other side.
+        let mut wrapped = coord;
+        while let Some(prev) = self
+            .neighbor(wrapped, facing.reversed())
+            .filter(|&prev| self.tile(prev) != Tile::Void)
+        {
+            wrapped = prev;
+        }
+        wrapped
+    }
+}
+
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Board")
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Which way the walker is facing, in the order used to score the password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Facing {
+    Right = 0,
+    Down = 1,
+    Left = 2,
+    Up = 3,
+}
+
+impl Facing {
+    const ALL: [Self; 4] = [Self::Right, Self::Down, Self::Left, Self::Up];
+
+    /// Turn 90° clockwise.
+    const fn turned_right(self) -> Self {
+        Self::ALL[(self as usize + 1) % 4]
+    }
+
+    /// Turn 90° counterclockwise.
+    const fn turned_left(self) -> Self {
+        Self::ALL[(self as usize + 3) % 4]
+    }
+
+    const fn reversed(self) -> Self {
+        Self::ALL[(self as usize + 2) % 4]
+    }
+
+    /// One step in this direction, where positive y is downwards.
+    const fn delta(self) -> (isize, isize) {
+        match self {
+            Self::Right => (1, 0),
+            Self::Down => (0, 1),
+            Self::Left => (-1, 0),
+            Self::Up => (0, -1),
+        }
+    }
+}
+
+impl fmt::Display for Facing {
+    /// The arrow the puzzle uses to draw a path facing this way.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Right => write!(f, ">"),
+            Self::Down => write!(f, "v"),
+            Self::Left => write!(f, "<"),
+            Self::Up => write!(f, "^"),
+        }
+    }
+}
+
+/// A single instruction in the path from the notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
+    /// Walk forward this many tiles, or until hitting a wall.
+    Forward(u32),
+    TurnLeft,
+    TurnRight,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Forward(tiles) => write!(f, "{tiles}"),
+            Self::TurnLeft => write!(f, "L"),
+            Self::TurnRight => write!(f, "R"),
+        }
+    }
+}
+
+/// Someone following the path around the board, one tile or turn at a time.
+#[derive(Debug)]
+struct Walker {
+    board: Board,
+    path: Vec<Move>,
+    start: GridCoord,
+    position: GridCoord,
+    facing: Facing,
+    /// The index of the move being carried out.
+    next_move: usize,
+    /// How many tiles have been walked so far in the current [`Move::Forward`].
+    walked: u32,
+    /// Every tile that's been visited, and which way the walker last faced on it.
+    trail: HashMap<GridCoord, Facing>,
+}
+
+impl Walker {
+    fn new(board: Board, path: Vec<Move>) -> Result<Self, SolverError> {
+        let start = board.start().ok_or_else(|| {
+            SolverError::invalid_input("The top row of the board doesn't have any open tiles")
+        })?;
+
+        Ok(Self {
+            board,
+            path,
+            start,
+            position: start,
+            facing: Facing::Right,
+            next_move: 0,
+            walked: 0,
+            trail: HashMap::from([(start, Facing::Right)]),
+        })
+    }
+
+    /// The move being carried out, or `None` if the path is finished.
+    fn current_move(&self) -> Option<Move> {
+        self.path.get(self.next_move).copied()
+    }
+
+    fn finish_move(&mut self) {
+        self.next_move += 1;
+        self.walked = 0;
+    }
+
+    /// The password for where the walker is now: 1000 times the row, plus 4 times the column,
+    /// plus the facing, with rows and columns counted from 1.
+    fn password(&self) -> usize {
+        1000 * (self.position.y + 1) + 4 * (self.position.x + 1) + self.facing as usize
+    }
+}
+
+impl Simulation for Walker {
+    /// Take a single step forward, or make a single turn.
+    fn step(&mut self) -> StepResult {
+        let Some(current) = self.current_move() else {
+            return StepResult::Done;
+        };
+
+        match current {
+            Move::TurnLeft => {
+                self.facing = self.facing.turned_left();
+                self.finish_move();
+            }
+
+            Move::TurnRight => {
+                self.facing = self.facing.turned_right();
+                self.finish_move();
+            }
+
+            Move::Forward(tiles) if self.walked >= tiles => self.finish_move(),
+
+            Move::Forward(tiles) => {
+                let next = self.board.ahead(self.position, self.facing);
+                if self.board.tile(next) == Tile::Wall {
+                    // Every step after this one would bump into the same wall.
+                    self.finish_move();
+                } else {
+                    self.position = next;
+                    self.walked += 1;
+                    if self.walked == tiles {
+                        self.finish_move();
+                    }
+                }
+            }
+        }
+
+        self.trail.insert(self.position, self.facing);
+
+        if self.next_move < self.path.len() {
+            StepResult::Continue
+        } else {
+            StepResult::Done
+        }
+    }
+
+    fn reset(&mut self) {
+        self.position = self.start;
+        self.facing = Facing::Right;
+        self.next_move = 0;
+        self.walked = 0;
+        self.trail = HashMap::from([(self.start, Facing::Right)]);
+    }
+
+    fn metrics(&self) -> serde_json::Value {
+        json!({
+            "row": self.position.y + 1,
+            "column": self.position.x + 1,
+            "facing": self.facing as usize,
+            "moves_left": self.path.len() - self.next_move,
+            "tiles_visited": self.trail.len(),
+        })
+    }
+}
+
+super::challenge_solver_test_boilerplate! {
+    Solver22::default();
+    22 => {
+        a as usize,
+    }
+
+    #[test]
+    fn cube_wrapping_is_unsupported() {
+        let mut input = Cursor::new(SAMPLE_INPUT);
+        let res = Solver22::default().solve_b(&mut input);
+        assert!(matches!(res, Err(SolverError::Unsupported(_))));
+    }
+
+    #[test]
+    fn wraps_around_the_board() {
+        let (board, _) = parse::parse_input(SAMPLE_INPUT).unwrap();
+        let at = |x, y| GridCoord { x, y };
+
+        // Off the left of the top section, over to its right edge.
+        assert_eq!(board.ahead(at(8, 1), Facing::Left), at(11, 1));
+        // Off the bottom of the left section, back up to its top.
+        assert_eq!(board.ahead(at(1, 7), Facing::Down), at(1, 4));
+        // Off the top of the right section, down to the bottom of the board.
+        assert_eq!(board.ahead(at(13, 8), Facing::Up), at(13, 11));
+        // Nothing special away from the edges.
+        assert_eq!(board.ahead(at(5, 5), Facing::Right), at(6, 5));
+    }
+
+    #[test]
+    fn checks_the_board_folds_into_a_cube() {
+        let check = |input: &str| {
+            let (board, _) = parse::parse_input(input).unwrap();
+            net::check(input, &board)
+        };
+        assert_eq!(check(SAMPLE_INPUT).unwrap(), 4);
+
+        // A row of the wide part of the board, cut short by a bad copy and paste.
+        let row = "........#...\n";
+        let cut = SAMPLE_INPUT.replace(row, "........#\n");
+        let err = check(&cut).unwrap_err();
+        let row_start = SAMPLE_INPUT.find(row).unwrap();
+        assert_eq!(err.offsets().collect::<Vec<_>>(), [row_start + 8]);
+        assert!(err
+            .render()
+            .contains("this face is missing 3 of its 16 tiles"));
+
+        // The bottom of the board, gone missing.
+        let short = SAMPLE_INPUT.lines().take(8).collect::<Vec<_>>().join("\n") + "\n\n10R5\n";
+        let err = check(&short).unwrap_err();
+        assert!(err.render().contains("only 4 faces of 4×4 tiles"));
+
+        // Two rows of three faces fold up into two pairs of faces on top of each other, and a face
+        // on its own isn't attached.
+        assert_eq!(check("...\n...\n\n1\n").unwrap_err().count(), 4);
+        assert_eq!(check(" . .\n...\n .\n\n1\n").unwrap_err().count(), 1);
+        assert_eq!(check(" .\n...\n .\n .\n\n1\n").unwrap(), 1);
+    }
+
+    #[test]
+    fn leaves_a_trail() {
+        let (board, path) = parse::parse_input(SAMPLE_INPUT).unwrap();
+        let mut walker = Walker::new(board, path).unwrap();
+
+        // The first move is `10`, but there's a wall three tiles in.
+        walker.run(Some(3));
+        assert_eq!(walker.position, GridCoord { x: 10, y: 0 });
+        assert_eq!(walker.current_move(), Some(Move::TurnRight));
+
+        walker.run(None);
+        assert_eq!(walker.password(), 6032);
+        assert_eq!(walker.trail[&walker.position], Facing::Right);
+
+        walker.reset();
+        assert_eq!(walker.trail.len(), 1);
+        assert_eq!(walker.password(), 1000 + 4 * 9);
+    }
+}
@@ -0,0 +1,351 @@
+//! Checking that a board folds up into a cube, so that a board cut short by a bad copy and paste
+//! gets pointed out before anything walks around it.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    ops::Range,
+};
+
+use crate::parse::BadInputError;
+
+use super::{Board, GridCoord, Tile};
+
+/// What every problem with a board that isn't a cube net gets reported under.
+const SUMMARY: &str = "The board doesn't fold up into a cube";
+
+/// The boxes, in faces across by faces down, that every net of a cube fits into exactly.
+const NET_BOXES: [(usize, usize); 4] = [(3, 4), (4, 3), (2, 5), (5, 2)];
+
+/// Check that `board`, parsed from the start of `input`, is the net of a cube: 6 square faces of
+/// the same size, all attached to each other, that don't overlap once they're folded up.
+///
+/// Returns how many tiles across each face is. Every problem is pointed out in `input`, and
+/// printed out to `stderr` with fancy formatting like a parsing error would be.
+pub(super) fn check(input: &str, board: &Board) -> Result<usize, BadInputError> {
+    check_faces(input, board).map_err(|problems| {
+        let err = BadInputError::spans(input, SUMMARY, problems);
+        eprintln!("{}", err.render());
+        err
+    })
+}
+
+type Problems = Vec<(Range<usize>, String)>;
+
+fn check_faces(input: &str, board: &Board) -> Result<usize, Problems> {
+    let lines = Lines::new(input);
+    let tiles = board.tiles().filter(|&(_, t)| t != Tile::Void).count();
+
+    let Some((size, blocks)) = face_size_candidates(board, tiles)
+        .into_iter()
+        .map(|size| (size, Blocks::new(board, size)))
+        .min_by_key(|(_, blocks)| blocks.partial.len())
+    else {
+        return Err(vec![(
+            lines.board(board.height()),
+            format!(
+                "{tiles} tiles on a {}×{} board can't be cut into 6 square faces",
+                board.width(),
+                board.height()
+            ),
+        )]);
+    };
+
+    if !blocks.partial.is_empty() {
+        return Err(blocks
+            .partial
+            .iter()
+            .map(|&(block, filled)| partial_face(&lines, board, size, block, filled))
+            .collect());
+    }
+
+    if blocks.faces.len() < 6 {
+        let end = lines.board(board.height()).end;
+        return Err(vec![(
+            end..end,
+            format!(
+                "only {} faces of {size}×{size} tiles, but a cube needs 6",
+                blocks.faces.len()
+            ),
+        )]);
+    }
+    if blocks.faces.len() > 6 {
+        return Err(blocks.faces[6..]
+            .iter()
+            .map(|&block| (lines.face(block, size), "one face too many".to_string()))
+            .collect());
+    }
+
+    fold(&blocks.faces).map_err(|problems| {
+        problems
+            .into_iter()
+            .map(|(block, label)| (lines.face(block, size), label))
+            .collect::<Problems>()
+    })?;
+
+    Ok(size)
+}
+
+/// How many tiles across a face might be, best guesses first.
+///
+/// A board's size gives the face size away, unless some of its rows are missing or cut short, in
+/// which case it only bounds it. How many tiles there are gives it away too, unless they're
+/// missing.
+fn face_size_candidates(board: &Board, tiles: usize) -> Vec<usize> {
+    let (width, height) = (board.width(), board.height());
+    let mut sizes = Vec::new();
+
+    for (across, down) in NET_BOXES {
+        let size = width / across;
+        if size > 0 && width == across * size && height == down * size {
+            sizes.push(size);
+        }
+    }
+    let area = tiles / 6;
+    let size = (1..=area).find(|size| size * size >= area).unwrap_or(0);
+    if size > 0 && 6 * size * size == tiles {
+        sizes.push(size);
+    }
+    if !sizes.is_empty() {
+        return sizes;
+    }
+
+    for (across, down) in NET_BOXES {
+        for (side, bound, count, limit) in
+            [(width, height, across, down), (height, width, down, across)]
+        {
+            let size = side / count;
+            if size > 0 && side == count * size && bound <= limit * size {
+                sizes.push(size);
+            }
+        }
+    }
+    sizes
+}
+
+/// A board cut up into blocks of `size` by `size` tiles, at block coordinates.
+struct Blocks {
+    /// Every block that's all tiles, row by row.
+    faces: Vec<GridCoord>,
+    /// Every block that's only partly tiles, and how many tiles it has.
+    partial: Vec<(GridCoord, usize)>,
+}
+
+impl Blocks {
+    fn new(board: &Board, size: usize) -> Self {
+        let mut faces = Vec::new();
+        let mut partial = Vec::new();
+
+        for y in 0..board.height().div_ceil(size) {
+            for x in 0..board.width().div_ceil(size) {
+                let block = GridCoord { x, y };
+                match block_tiles(board, size, block).count() {
+                    0 => {}
+                    filled if filled == size * size => faces.push(block),
+                    filled => partial.push((block, filled)),
+                }
+            }
+        }
+
+        Self { faces, partial }
+    }
+}
+
+/// The coordinates of every tile in `block` that's part of the board.
+fn block_tiles(
+    board: &Board,
+    size: usize,
+    block: GridCoord,
+) -> impl Iterator<Item = GridCoord> + '_ {
+    (0..size)
+        .flat_map(move |dy| (0..size).map(move |dx| (dx, dy)))
+        .map(move |(dx, dy)| GridCoord {
+            x: block.x * size + dx,
+            y: block.y * size + dy,
+        })
+        .filter(|&coord| board.tile(coord) != Tile::Void)
+}
+
+/// Point out a block that's only partly tiles.
+///
+/// A block that's mostly tiles is taken to be a face with some of its tiles missing, which points
+/// at the first of its rows that's cut short. Otherwise its tiles are taken to be strays.
+fn partial_face(
+    lines: &Lines,
+    board: &Board,
+    size: usize,
+    block: GridCoord,
+    filled: usize,
+) -> (Range<usize>, String) {
+    let columns = block.x * size..(block.x + 1) * size;
+    let row_tiles = |y| {
+        columns
+            .clone()
+            .filter(|&x| board.tile(GridCoord { x, y }) != Tile::Void)
+            .count()
+    };
+    let mut rows = block.y * size..(block.y + 1) * size;
+    // Rows past the bottom of the board aren't on any line of it, so they're pointed at from the
+    // end of the board instead.
+    let span = |y| {
+        if y < board.height() {
+            lines.columns(y, columns.clone())
+        } else {
+            let end = lines.board(board.height()).end;
+            end..end
+        }
+    };
+
+    if 2 * filled >= size * size {
+        let y = rows
+            .find(|&y| row_tiles(y) < size)
+            .unwrap_or(block.y * size);
+        let missing = size * size - filled;
+        (
+            span(y),
+            format!(
+                "this face is missing {missing} of its {} tiles",
+                size * size
+            ),
+        )
+    } else {
+        let y = rows.find(|&y| row_tiles(y) > 0).unwrap_or(block.y * size);
+        (
+            span(y),
+            format!("{filled} stray tiles that aren't part of any face"),
+        )
+    }
+}
+
+/// Which way a face of the net points once it's folded up, along with which ways its rows and
+/// columns run, as unit vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Orientation {
+    right: [i8; 3],
+    down: [i8; 3],
+    out: [i8; 3],
+}
+
+impl Orientation {
+    /// The orientation of the face next door in the net, once it's folded over the edge between
+    /// them by a right angle.
+    fn folded(self, dx: isize, dy: isize) -> Self {
+        let neg = |v: [i8; 3]| v.map(|c| -c);
+        let Self { right, down, out } = self;
+        match (dx, dy) {
+            (1, 0) => Self {
+                right: neg(out),
+                down,
+                out: right,
+            },
+            (-1, 0) => Self {
+                right: out,
+                down,
+                out: neg(right),
+            },
+            (0, 1) => Self {
+                right,
+                down: neg(out),
+                out: down,
+            },
+            (0, -1) => Self {
+                right,
+                down: out,
+                out: neg(down),
+            },
+            _ => unreachable!("faces are only folded over one of their edges"),
+        }
+    }
+}
+
+/// Fold up the net with `faces`, at block coordinates, and point out any faces that aren't
+/// attached to the rest of them or that land on the same side of the cube as another one.
+fn fold(faces: &[GridCoord]) -> Result<(), Vec<(GridCoord, String)>> {
+    let mut orientations = HashMap::from([(
+        faces[0],
+        Orientation {
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+            out: [0, 0, 1],
+        },
+    )]);
+    let mut queue = vec![faces[0]];
+
+    while let Some(face) = queue.pop() {
+        let orientation = orientations[&face];
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let Some(next) = face
+                .x
+                .checked_add_signed(dx)
+                .zip(face.y.checked_add_signed(dy))
+                .map(|(x, y)| GridCoord { x, y })
+                .filter(|next| faces.contains(next))
+            else {
+                continue;
+            };
+            if let Entry::Vacant(entry) = orientations.entry(next) {
+                entry.insert(orientation.folded(dx, dy));
+                queue.push(next);
+            }
+        }
+    }
+
+    let mut problems = Vec::new();
+    let mut sides = HashMap::new();
+    for &face in faces {
+        let Some(orientation) = orientations.get(&face) else {
+            problems.push((face, "this face isn't attached to the others".to_string()));
+            continue;
+        };
+        if let Some(&other) = sides.get(&orientation.out) {
+            problems.push((other, "this face...".to_string()));
+            problems.push((face, "...ends up on top of this one".to_string()));
+        } else {
+            sides.insert(orientation.out, face);
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Where each line of the input is, to point at the tiles on the board.
+struct Lines(Vec<Range<usize>>);
+
+impl Lines {
+    fn new(input: &str) -> Self {
+        let mut start = 0;
+        Self(
+            input
+                .split_inclusive('\n')
+                .map(|raw_line| {
+                    let line = raw_line.strip_suffix('\n').unwrap_or(raw_line);
+                    let line = line.strip_suffix('\r').unwrap_or(line);
+                    let range = start..start + line.len();
+                    start += raw_line.len();
+                    range
+                })
+                .collect(),
+        )
+    }
+
+    /// The bytes of the tiles in `columns` of row `y` of the board, or the end of the row if it
+    /// doesn't reach that far.
+    fn columns(&self, y: usize, columns: Range<usize>) -> Range<usize> {
+        let line = self.0[y].clone();
+        let clamp = |x: usize| (line.start + x).min(line.end);
+        clamp(columns.start)..clamp(columns.end)
+    }
+
+    /// The bytes of the top row of the face at `block`.
+    fn face(&self, block: GridCoord, size: usize) -> Range<usize> {
+        self.columns(block.y * size, block.x * size..(block.x + 1) * size)
+    }
+
+    /// The bytes of the whole board, which is the first `height` lines.
+    fn board(&self, height: usize) -> Range<usize> {
+        self.0[0].start..self.0[height - 1].end
+    }
+}
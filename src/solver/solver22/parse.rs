@@ -0,0 +1,66 @@
+use nom::{
+    branch::alt,
+    character::complete::{self as nom_cc, line_ending},
+    combinator::{map, value},
+    error::ParseError,
+    multi::{many1, separated_list1},
+    sequence::{pair, separated_pair, terminated},
+    IResult, Parser,
+};
+use nom_supreme::{context::ContextError, error::ErrorTree, ParserExt};
+
+use crate::parse::{BadInputError, Span};
+
+use super::{Board, Move, Tile};
+
+/// Parse the challenge input into the board and the path to follow around it.
+///
+/// Any parsing errors will be printed out to `stderr` with fancy formatting.
+pub(super) fn parse_input(input: &str) -> Result<(Board, Vec<Move>), BadInputError> {
+    crate::parse::parse_input(input, parse_notes::<ErrorTree<Span>>)
+}
+
+fn parse_tile<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Tile, E>
+where
+    E: ParseError<Span<'a>>,
+{
+    alt((
+        value(Tile::Void, nom_cc::char(' ')),
+        value(Tile::Open, nom_cc::char('.')),
+        value(Tile::Wall, nom_cc::char('#')),
+    ))(i)
+}
+
+fn parse_board<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Board, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>, &'static str>,
+{
+    map(separated_list1(line_ending, many1(parse_tile)), Board::new)
+        .context("board")
+        .parse(i)
+}
+
+fn parse_move<'a, E>(i: Span<'a>) -> IResult<Span<'a>, Move, E>
+where
+    E: ParseError<Span<'a>>,
+{
+    alt((
+        map(nom_cc::u32, Move::Forward),
+        value(Move::TurnLeft, nom_cc::char('L')),
+        value(Move::TurnRight, nom_cc::char('R')),
+    ))(i)
+}
+
+fn parse_notes<'a, E>(i: Span<'a>) -> IResult<Span<'a>, (Board, Vec<Move>), E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>, &'static str>,
+{
+    terminated(
+        separated_pair(
+            parse_board,
+            pair(line_ending, line_ending),
+            many1(parse_move).context("path"),
+        ),
+        nom_cc::multispace0,
+    )(i)
+}
@@ -0,0 +1,243 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    grid::GridCoord,
+    simulation::{Simulation, StepResult},
+    tui_app::{Control, TuiApp},
+};
+
+use super::{Move, Tile, Walker};
+
+/// The most steps that can be taken every tick. Real inputs take around a hundred thousand steps,
+/// which would be a long wait at one step per tick.
+const MAX_STEPS_PER_TICK: usize = 1024;
+
+/// Watches the walker follow the path around the board, leaving a trail of arrows behind it like
+/// the drawings in the puzzle description.
+///
+/// The board scrolls to keep the walker in view.
+pub(super) struct Viewer {
+    walker: Walker,
+    running: bool,
+    steps_per_tick: usize,
+}
+
+impl Viewer {
+    pub(super) fn new(walker: Walker, steps_per_tick: usize) -> Self {
+        Self {
+            walker,
+            running: false,
+            steps_per_tick: steps_per_tick.min(MAX_STEPS_PER_TICK),
+        }
+    }
+
+    /// The walker, wherever it got to on the path.
+    pub(super) fn into_walker(self) -> Walker {
+        self.walker
+    }
+
+    fn step(&mut self) {
+        if self.walker.step() == StepResult::Done {
+            self.running = false;
+        }
+    }
+
+    /// The first tile to draw along an axis of `visible` tiles out of `total`, keeping `focus` in
+    /// the middle where possible.
+    fn scroll_offset(focus: usize, visible: usize, total: usize) -> usize {
+        focus
+            .saturating_sub(visible / 2)
+            .min(total.saturating_sub(visible))
+    }
+
+    fn board_lines(&self, area: Rect) -> Vec<Spans<'static>> {
+        let board = &self.walker.board;
+        let (width, height) = (area.width as usize, area.height as usize);
+        let left = Self::scroll_offset(self.walker.position.x, width, board.width());
+        let top = Self::scroll_offset(self.walker.position.y, height, board.height());
+
+        (top..(top + height).min(board.height()))
+            .map(|y| {
+                (left..(left + width).min(board.width()))
+                    .map(|x| {
+                        let coord = GridCoord { x, y };
+                        if coord == self.walker.position {
+                            return Span::styled(
+                                self.walker.facing.to_string(),
+                                Style::default()
+                                    .fg(Color::Black)
+                                    .bg(Color::Green)
+                                    .add_modifier(Modifier::BOLD),
+                            );
+                        }
+                        if let Some(facing) = self.walker.trail.get(&coord) {
+                            return Span::styled(
+                                facing.to_string(),
+                                Style::default().fg(Color::Yellow),
+                            );
+                        }
+                        match board.tile(coord) {
+                            Tile::Void => Span::raw(" "),
+                            Tile::Open => Span::styled(".", Style::default().fg(Color::DarkGray)),
+                            Tile::Wall => Span::styled("#", Style::default().fg(Color::Gray)),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect()
+    }
+
+    fn status_lines(&self) -> Vec<Spans<'static>> {
+        let walker = &self.walker;
+        let label = Style::default().add_modifier(Modifier::BOLD);
+
+        let state = if walker.current_move().is_none() {
+            Span::styled("FINISHED", Style::default().fg(Color::Red))
+        } else if self.running {
+            Span::styled("RUNNING", Style::default().fg(Color::Green))
+        } else {
+            Span::styled("PAUSED", Style::default().fg(Color::Yellow))
+        };
+
+        vec![
+            Spans::from(vec![Span::styled("state:    ", label), state]),
+            Spans::from(vec![
+                Span::styled("row:      ", label),
+                Span::raw((walker.position.y + 1).to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("column:   ", label),
+                Span::raw((walker.position.x + 1).to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("facing:   ", label),
+                Span::raw(walker.facing.to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("password: ", label),
+                Span::styled(
+                    walker.password().to_string(),
+                    Style::default().fg(Color::Cyan),
+                ),
+            ]),
+            Spans::from(vec![
+                Span::styled("speed:    ", label),
+                Span::raw(format!("{} step(s)/tick", self.steps_per_tick)),
+            ]),
+        ]
+    }
+
+    fn render_path<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let items = self
+            .walker
+            .path
+            .iter()
+            .enumerate()
+            .map(|(i, m)| match m {
+                Move::Forward(tiles) if i == self.walker.next_move => {
+                    ListItem::new(format!("{i:>5}  {m} ({}/{tiles})", self.walker.walked))
+                }
+                _ => ListItem::new(format!("{i:>5}  {m}")),
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = ListState::default();
+        if self.walker.current_move().is_some() {
+            state.select(Some(self.walker.next_move));
+        }
+
+        let list = List::new(items)
+            .block(Block::default().title("Path").borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+impl TuiApp for Viewer {
+    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(32)])
+            .split(outer[0]);
+
+        let sidebar = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(8), Constraint::Min(0)])
+            .split(columns[1]);
+
+        let board_block = Block::default().title("Board").borders(Borders::ALL);
+        let board =
+            Paragraph::new(self.board_lines(board_block.inner(columns[0]))).block(board_block);
+        f.render_widget(board, columns[0]);
+
+        let status = Paragraph::new(self.status_lines())
+            .block(Block::default().title("Walker").borders(Borders::ALL));
+        f.render_widget(status, sidebar[0]);
+
+        self.render_path(f, sidebar[1]);
+
+        let help = Paragraph::new(Spans::from(
+            "[space] run/pause  [s/→] step  [+/-] speed  [r] reset  [q] quit",
+        ));
+        f.render_widget(help, outer[1]);
+    }
+
+    fn on_tick(&mut self) {
+        for _ in 0..self.steps_per_tick {
+            if !self.running {
+                break;
+            }
+            self.step();
+        }
+    }
+
+    fn status(&self) -> StepResult {
+        if self.walker.current_move().is_none() {
+            StepResult::Done
+        } else {
+            StepResult::Continue
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Control {
+        if key.kind == KeyEventKind::Release {
+            return Control::Continue;
+        }
+
+        match key.code {
+            KeyCode::Char(' ') => {
+                self.running = !self.running && self.walker.current_move().is_some();
+            }
+            KeyCode::Char('s') | KeyCode::Right => {
+                self.running = false;
+                self.step();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.steps_per_tick = (self.steps_per_tick * 2).min(MAX_STEPS_PER_TICK);
+            }
+            KeyCode::Char('-') => self.steps_per_tick = (self.steps_per_tick / 2).max(1),
+            KeyCode::Char('r') => {
+                self.walker.reset();
+                self.running = false;
+            }
+            _ => {}
+        }
+
+        Control::Continue
+    }
+}
@@ -0,0 +1,113 @@
+//! Changing a parsed input before solving it, for `--transform`.
+
+use std::{fmt, str::FromStr};
+
+use crate::error::SolverError;
+
+/// A change to make to an input after it's parsed, to see how the answer would turn out if the
+/// input were a bit different.
+///
+/// Written as a verb, the kind of thing to change, and which one of them, like `drop blueprint 3`
+/// or `set valve XX flow=0`. What kinds of things there are, and what can be set on them, is up to
+/// each solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+    /// Take something out of the input.
+    Drop { kind: String, id: String },
+    /// Change one of something's values.
+    Set {
+        kind: String,
+        id: String,
+        field: String,
+        value: String,
+    },
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        match words[..] {
+            ["drop", kind, id] => Ok(Self::Drop {
+                kind: kind.to_string(),
+                id: id.to_string(),
+            }),
+            ["set", kind, id, assignment] => {
+                let (field, value) = assignment
+                    .split_once('=')
+                    .filter(|(field, value)| !field.is_empty() && !value.is_empty())
+                    .ok_or_else(|| format!("expected `FIELD=VALUE`, not `{assignment}`"))?;
+                Ok(Self::Set {
+                    kind: kind.to_string(),
+                    id: id.to_string(),
+                    field: field.to_string(),
+                    value: value.to_string(),
+                })
+            }
+            _ => Err(format!(
+                "expected `drop KIND ID` or `set KIND ID FIELD=VALUE`, not `{s}`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Drop { kind, id } => write!(f, "drop {kind} {id}"),
+            Self::Set {
+                kind,
+                id,
+                field,
+                value,
+            } => write!(f, "set {kind} {id} {field}={value}"),
+        }
+    }
+}
+
+/// A parsed input that `--transform` can make changes to.
+pub(crate) trait TransformableInput {
+    /// Make one change, or say why it can't be made.
+    fn apply(&mut self, transform: &Transform) -> Result<(), String>;
+
+    /// Make every change in `transforms`, in order.
+    fn apply_all(&mut self, transforms: &[Transform]) -> Result<(), SolverError> {
+        for transform in transforms {
+            self.apply(transform).map_err(|reason| {
+                SolverError::invalid_input(format!("Could not `{transform}`: {reason}"))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_verbs() {
+        for s in ["drop blueprint 3", "set valve XX flow=0"] {
+            let transform: Transform = s.parse().unwrap();
+            assert_eq!(transform.to_string(), s);
+        }
+        assert_eq!(
+            "  drop   monkey 2 ".parse(),
+            Ok(Transform::Drop {
+                kind: "monkey".to_string(),
+                id: "2".to_string(),
+            })
+        );
+
+        for bad in [
+            "",
+            "drop monkey",
+            "set valve XX flow",
+            "set valve XX =0",
+            "swap a b",
+        ] {
+            assert!(bad.parse::<Transform>().is_err(), "{bad:?} shouldn't parse");
+        }
+    }
+}
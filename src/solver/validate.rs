@@ -0,0 +1,168 @@
+use std::{error::Error, fmt};
+
+use crate::error::SolverError;
+
+/// What a solver's parsing stage found in a challenge input.
+///
+/// Returned by [`ChallengeSolver::validate`][super::ChallengeSolver::validate].
+#[derive(Debug, Default)]
+pub struct ParseStats {
+    /// How many of each kind of thing were parsed, e.g. `("sensors", 26)`.
+    pub entities: Vec<(&'static str, usize)>,
+
+    /// The smallest and largest coordinates that appear in the input, if it contains any.
+    pub bounds: Option<Bounds>,
+}
+
+impl ParseStats {
+    /// Record that `count` of some kind of entity were parsed.
+    pub fn entity(mut self, name: &'static str, count: usize) -> Self {
+        self.entities.push((name, count));
+        self
+    }
+
+    /// Record the bounds of all the given coordinates.
+    pub fn bounds(mut self, points: impl IntoIterator<Item = (i64, i64)>) -> Self {
+        self.bounds = Bounds::from_points(points);
+        self
+    }
+}
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: (i64, i64),
+    pub max: (i64, i64),
+}
+
+impl Bounds {
+    /// Find the smallest bounding box containing every point, or `None` if there aren't any.
+    pub fn from_points(points: impl IntoIterator<Item = (i64, i64)>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, (x, y)| {
+            Some(match bounds {
+                None => Self {
+                    min: (x, y),
+                    max: (x, y),
+                },
+                Some(Self { min, max }) => Self {
+                    min: (min.0.min(x), min.1.min(y)),
+                    max: (max.0.max(x), max.1.max(y)),
+                },
+            })
+        })
+    }
+}
+
+/// The result of validating a challenge input.
+#[derive(Debug)]
+pub struct ValidationReport {
+    /// The number of lines in the input.
+    pub lines: usize,
+
+    /// The number of whitespace-separated tokens in the input.
+    pub tokens: usize,
+
+    /// Problems with the input that don't depend on the solver, like Windows line endings.
+    pub warnings: Vec<String>,
+
+    /// What the solver's parser made of the input.
+    ///
+    /// `None` if the solver can't be validated separately from solving.
+    pub parsed: Option<Result<ParseStats, SolverError>>,
+}
+
+impl ValidationReport {
+    /// Collect the solver-independent statistics and warnings for an input.
+    pub fn new(input: &str, parsed: Option<Result<ParseStats, SolverError>>) -> Self {
+        let mut warnings = Vec::new();
+
+        if input.trim().is_empty() {
+            warnings.push("input is empty".to_string());
+        }
+        if input.contains('\r') {
+            warnings.push("input has Windows (CRLF) line endings".to_string());
+        }
+        if !input.is_empty() && !input.ends_with('\n') {
+            warnings.push("input does not end with a newline".to_string());
+        }
+
+        let trailing_ws = input
+            .lines()
+            .filter(|l| l.trim_end_matches('\r').ends_with([' ', '\t']))
+            .count();
+        if trailing_ws > 0 {
+            warnings.push(format!("{trailing_ws} line(s) have trailing whitespace"));
+        }
+
+        Self {
+            lines: input.lines().count(),
+            tokens: input.split_whitespace().count(),
+            warnings,
+            parsed,
+        }
+    }
+
+    /// Returns `true` if the solver's parser rejected the input.
+    pub fn is_err(&self) -> bool {
+        matches!(self.parsed, Some(Err(_)))
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "lines:  {}", self.lines)?;
+        writeln!(f, "tokens: {}", self.tokens)?;
+
+        match &self.parsed {
+            None => writeln!(f, "parsed: (this solver has no separate parsing stage)")?,
+            Some(Err(e)) => {
+                writeln!(f, "parsed: FAILED")?;
+                let causes = std::iter::successors(Some(e as &dyn Error), |&e| e.source());
+                for cause in causes {
+                    writeln!(f, "  {cause}")?;
+                }
+            }
+            Some(Ok(stats)) => {
+                writeln!(f, "parsed: ok")?;
+                for (name, count) in &stats.entities {
+                    writeln!(f, "  {name}: {count}")?;
+                }
+                if let Some(Bounds { min, max }) = stats.bounds {
+                    writeln!(f, "  min: ({}, {})", min.0, min.1)?;
+                    writeln!(f, "  max: ({}, {})", max.0, max.1)?;
+                }
+            }
+        }
+
+        for warning in &self.warnings {
+            writeln!(f, "warning: {warning}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_counts_and_warnings() {
+        let report = ValidationReport::new("noop\r\naddx 3 \r\naddx -5", None);
+        assert_eq!(report.lines, 3);
+        assert_eq!(report.tokens, 5);
+        assert_eq!(report.warnings.len(), 3);
+    }
+
+    #[test]
+    fn bounds_from_points() {
+        assert_eq!(Bounds::from_points([]), None);
+        assert_eq!(
+            Bounds::from_points([(3, -1), (-2, 4), (0, 0)]),
+            Some(Bounds {
+                min: (-2, -1),
+                max: (3, 4)
+            })
+        );
+    }
+}
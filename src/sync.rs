@@ -0,0 +1,67 @@
+//! Helpers for coordinating with solvers running on other threads.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+/// A cheap-to-clone handle for asking a long-running solver to stop early.
+///
+/// Solvers with slow searches check [`CancellationToken::check`] every so often, and can
+/// [`report`](CancellationToken::report) how far they've gotten, so that a run that gets cut
+/// short still has something to show for itself.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<TokenState>);
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    progress: Mutex<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask whoever is holding a clone of this token to stop.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns an error if the token has been cancelled, so that searches can bail out with `?`.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Record some progress, replacing anything previously reported under the same `key`.
+    pub fn report(&self, key: impl Into<String>, value: impl Into<serde_json::Value>) {
+        self.0
+            .progress
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(key.into(), value.into());
+    }
+
+    /// Everything that's been reported so far.
+    pub fn progress(&self) -> serde_json::Map<String, serde_json::Value> {
+        self.0
+            .progress
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+/// The error returned by [`CancellationToken::check`] once the token has been cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Cancelled before finishing")]
+pub struct Cancelled;
@@ -0,0 +1,367 @@
+//! Plotting pixels in a terminal with Unicode block and braille characters.
+//!
+//! Draw on a [`PixelCanvas`], then render it a row of text at a time:
+//!
+//! - [`PixelCanvas::half_block_rows`] packs two pixels into each character, one above the other,
+//!   using `▀`, `▄` and `█`. Terminal characters are about twice as tall as they are wide, so
+//!   this gives roughly square pixels, each with its own color.
+//! - [`PixelCanvas::braille_rows`] packs a 2×4 block of pixels into each braille character, for
+//!   four times the detail at the cost of one color per character.
+//!
+//! Colors are left out when [`color::enabled`] says so, which keeps the same shapes. On terminals
+//! that can't show those characters, [`glyphs::glyphs`] swaps them for ASCII ones, which keeps
+//! the same layout but loses some of the detail.
+
+use std::collections::HashMap;
+
+use owo_colors::{OwoColorize, Rgb};
+
+use crate::{
+    color,
+    glyphs::{self, Glyphs},
+};
+
+/// A pixel position, as `(x, y)`. `x` grows to the right and `y` grows downwards.
+///
+/// Coordinates are signed so that shapes can hang off of the edge of the canvas; anything outside
+/// of it is clipped.
+pub type Pixel = (i64, i64);
+
+/// How a pixel is filled in when rendering with [`PixelCanvas::half_block_rows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fill {
+    /// A solid block.
+    Solid,
+    /// A block of braille dots, to make something stand out from the solid pixels around it.
+    Dotted,
+}
+
+/// The color and fill of a pixel that's been drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ink {
+    pub color: Rgb,
+    pub fill: Fill,
+}
+
+impl Ink {
+    pub const fn solid(color: Rgb) -> Self {
+        Self {
+            color,
+            fill: Fill::Solid,
+        }
+    }
+
+    pub const fn dotted(color: Rgb) -> Self {
+        Self {
+            color,
+            fill: Fill::Dotted,
+        }
+    }
+}
+
+/// A fixed-size grid of pixels that can each be blank or drawn on with some [`Ink`].
+#[derive(Debug, Clone)]
+pub struct PixelCanvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<Ink>>,
+}
+
+impl PixelCanvas {
+    /// Create a blank canvas.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, (x, y): Pixel) -> Option<usize> {
+        let x = usize::try_from(x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(y).ok().filter(|&y| y < self.height)?;
+        Some(y * self.width + x)
+    }
+
+    /// The ink at `pixel`, or `None` if it's blank or outside of the canvas.
+    pub fn get(&self, pixel: Pixel) -> Option<Ink> {
+        self.index(pixel).and_then(|i| self.pixels[i])
+    }
+
+    /// Draw on a single pixel. Does nothing if `pixel` is outside of the canvas.
+    pub fn set(&mut self, pixel: Pixel, ink: Ink) {
+        if let Some(i) = self.index(pixel) {
+            self.pixels[i] = Some(ink);
+        }
+    }
+
+    /// Blank out a single pixel.
+    pub fn clear(&mut self, pixel: Pixel) {
+        if let Some(i) = self.index(pixel) {
+            self.pixels[i] = None;
+        }
+    }
+
+    /// Draw a straight line from `from` to `to`, including both ends.
+    pub fn line(&mut self, from: Pixel, to: Pixel, ink: Ink) {
+        // Bresenham's line algorithm, in its all-octants form.
+        let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+        let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+        let mut error = dx + dy;
+        let (mut x, mut y) = from;
+
+        loop {
+            self.set((x, y), ink);
+            if (x, y) == to {
+                break;
+            }
+
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Draw the outline of the rectangle with opposite corners at `a` and `b`.
+    pub fn rect(&mut self, a: Pixel, b: Pixel, ink: Ink) {
+        self.line(a, (b.0, a.1), ink);
+        self.line((b.0, a.1), b, ink);
+        self.line(b, (a.0, b.1), ink);
+        self.line((a.0, b.1), a, ink);
+    }
+
+    /// Fill in the rectangle with opposite corners at `a` and `b`.
+    pub fn fill_rect(&mut self, a: Pixel, b: Pixel, ink: Ink) {
+        for y in a.1.min(b.1)..=a.1.max(b.1) {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                self.set((x, y), ink);
+            }
+        }
+    }
+
+    /// Render the canvas with one character for every two rows of pixels, top to bottom.
+    ///
+    /// If the canvas has an odd height, the bottom row is drawn as if there were a blank row
+    /// below it.
+    pub fn half_block_rows(&self) -> Vec<String> {
+        let colored = color::enabled();
+        let glyphs = glyphs::glyphs();
+        (0..self.height as i64)
+            .step_by(2)
+            .map(|y| {
+                (0..self.width as i64)
+                    .map(
+                        |x| match half_block(glyphs, (self.get((x, y)), self.get((x, y + 1)))) {
+                            None => " ".to_string(),
+                            Some((glyph, _, _)) if !colored => glyph.to_string(),
+                            Some((glyph, fg, None)) => glyph.color(fg).to_string(),
+                            Some((glyph, fg, Some(bg))) => glyph.color(fg).on_color(bg).to_string(),
+                        },
+                    )
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Render the canvas with one braille character for every 2×4 block of pixels, top to
+    /// bottom.
+    ///
+    /// Each character takes the most common color among its pixels, and their fill is ignored.
+    pub fn braille_rows(&self) -> Vec<String> {
+        self.braille_rows_with(glyphs::glyphs(), color::enabled())
+    }
+
+    fn braille_rows_with(&self, glyphs: &Glyphs, colored: bool) -> Vec<String> {
+        (0..self.height as i64)
+            .step_by(4)
+            .map(|y| {
+                (0..self.width as i64)
+                    .step_by(2)
+                    .map(|x| {
+                        let mut dots = 0;
+                        let mut colors = HashMap::<_, usize>::new();
+                        for (bit, (dx, dy)) in BRAILLE_DOTS.into_iter().enumerate() {
+                            if let Some(ink) = self.get((x + dx, y + dy)) {
+                                dots |= 1 << bit;
+                                *colors
+                                    .entry((ink.color.0, ink.color.1, ink.color.2))
+                                    .or_default() += 1;
+                            }
+                        }
+
+                        match colors
+                            .into_iter()
+                            .max_by_key(|&(color, count)| (count, color))
+                        {
+                            None => " ".to_string(),
+                            Some(_) if !colored => glyphs.dots(dots).to_string(),
+                            Some(((r, g, b), _)) => {
+                                glyphs.dots(dots).color(Rgb(r, g, b)).to_string()
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Where each dot of a braille character is within its 2×4 cell, in the order of the bits of
+/// its code point.
+const BRAILLE_DOTS: [Pixel; 8] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (0, 3),
+    (1, 3),
+];
+
+/// Pick a character from `glyphs` and its foreground and background colors to show a pixel above
+/// another.
+///
+/// Returns `None` if both pixels are blank.
+fn half_block(
+    glyphs: &Glyphs,
+    (upper, lower): (Option<Ink>, Option<Ink>),
+) -> Option<(char, Rgb, Option<Rgb>)> {
+    let glyph = |ink: Ink, solid, dotted| match ink.fill {
+        Fill::Solid => solid,
+        Fill::Dotted => dotted,
+    };
+    let upper_glyph = |ink| glyph(ink, glyphs.upper, glyphs.dotted_upper);
+    let lower_glyph = |ink| glyph(ink, glyphs.lower, glyphs.dotted_lower);
+
+    Some(match (upper, lower) {
+        (None, None) => return None,
+        (Some(upper), None) => (upper_glyph(upper), upper.color, None),
+        (None, Some(lower)) => (lower_glyph(lower), lower.color, None),
+        (Some(upper), Some(lower)) if upper == lower => (
+            glyph(upper, glyphs.full, glyphs.dotted_full),
+            upper.color,
+            None,
+        ),
+        // Only the foreground can be dotted, so a dotted upper pixel goes in front of a solid
+        // lower one. Otherwise, the lower half goes in front.
+        (Some(upper), Some(lower)) if upper.fill == Fill::Dotted && lower.fill == Fill::Solid => {
+            (upper_glyph(upper), upper.color, Some(lower.color))
+        }
+        (Some(upper), Some(lower)) => (lower_glyph(lower), lower.color, Some(upper.color)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = Rgb(255, 0, 0);
+    const BLUE: Rgb = Rgb(0, 0, 255);
+
+    /// Which pixels are drawn on, as rows of `#` and `.`.
+    fn shape(canvas: &PixelCanvas) -> Vec<String> {
+        (0..canvas.height() as i64)
+            .map(|y| {
+                (0..canvas.width() as i64)
+                    .map(|x| {
+                        if canvas.get((x, y)).is_some() {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_lines_and_rects() {
+        let mut canvas = PixelCanvas::new(6, 5);
+        canvas.line((0, 0), (5, 2), Ink::solid(RED));
+        canvas.rect((1, 4), (3, 3), Ink::solid(BLUE));
+        canvas.set((9, 9), Ink::solid(RED));
+
+        assert_eq!(
+            shape(&canvas),
+            ["##....", "..##..", "....##", ".###..", ".###.."]
+        );
+
+        canvas.fill_rect((-2, -2), (1, 1), Ink::solid(BLUE));
+        canvas.clear((1, 1));
+        assert_eq!(shape(&canvas)[..2], ["##....", "#.##.."]);
+    }
+
+    #[test]
+    fn half_blocks() {
+        let red = Some(Ink::solid(RED));
+        let blue = Some(Ink::solid(BLUE));
+        let dotted = Some(Ink::dotted(RED));
+
+        let half_block = |pixels| half_block(&glyphs::UNICODE, pixels);
+        assert_eq!(half_block((None, None)), None);
+        assert_eq!(half_block((red, None)), Some(('▀', RED, None)));
+        assert_eq!(half_block((None, blue)), Some(('▄', BLUE, None)));
+        assert_eq!(half_block((red, red)), Some(('█', RED, None)));
+        assert_eq!(half_block((red, blue)), Some(('▄', BLUE, Some(RED))));
+        assert_eq!(half_block((dotted, None)), Some(('⠛', RED, None)));
+        assert_eq!(half_block((None, dotted)), Some(('⣤', RED, None)));
+        assert_eq!(half_block((dotted, dotted)), Some(('⣿', RED, None)));
+        assert_eq!(half_block((dotted, blue)), Some(('⠛', RED, Some(BLUE))));
+        assert_eq!(half_block((blue, dotted)), Some(('⣤', RED, Some(BLUE))));
+    }
+
+    #[test]
+    fn ascii_half_blocks() {
+        let red = Some(Ink::solid(RED));
+        let blue = Some(Ink::solid(BLUE));
+        let dotted = Some(Ink::dotted(RED));
+
+        let half_block = |pixels| half_block(&glyphs::ASCII, pixels).map(|(glyph, ..)| glyph);
+        assert_eq!(half_block((red, None)), Some('"'));
+        assert_eq!(half_block((None, blue)), Some(','));
+        assert_eq!(half_block((red, red)), Some('#'));
+        assert_eq!(half_block((dotted, dotted)), Some('@'));
+        assert_eq!(half_block((dotted, blue)), Some('^'));
+        assert_eq!(half_block((blue, dotted)), Some('o'));
+    }
+
+    #[test]
+    fn braille_cells() {
+        let mut canvas = PixelCanvas::new(3, 5);
+        canvas.line((0, 0), (0, 3), Ink::solid(RED));
+        canvas.set((1, 3), Ink::solid(RED));
+        canvas.set((2, 4), Ink::solid(BLUE));
+
+        assert_eq!(
+            canvas.braille_rows_with(&glyphs::UNICODE, true),
+            [
+                format!("{}{}", '⣇'.color(RED), ' '),
+                format!("{}{}", ' ', '⠁'.color(BLUE)),
+            ]
+        );
+        assert_eq!(
+            canvas.braille_rows_with(&glyphs::UNICODE, false),
+            ["⣇ ", " ⠁"]
+        );
+        assert_eq!(
+            canvas.braille_rows_with(&glyphs::ASCII, false),
+            ["+ ", " ."]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Topological sorting, for working through things that depend on each other in order.
+//!
+//! Nodes are numbered `0..len`, like the elements of [`DisjointSets`](crate::dsu::DisjointSets),
+//! and each edge `(before, after)` says that `before` has to come before `after`. Sorting uses
+//! Kahn's algorithm: nodes with nothing left to wait on are taken one at a time, in the order they
+//! became ready, so it takes time proportional to the number of nodes and edges.
+//!
+//! Built for day 21's monkeys, and meant for any other puzzle where things wait on each other.
+
+use std::collections::VecDeque;
+
+/// Some nodes that can't be put in order, because they end up waiting on themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Cycle {
+    nodes: Vec<usize>,
+}
+
+impl Cycle {
+    /// Every node on a cycle, in increasing order, along with any on a path from one cycle to
+    /// another. Nodes that only wait on a cycle, or that a cycle only waits on, aren't included.
+    pub(crate) fn nodes(&self) -> &[usize] {
+        &self.nodes
+    }
+}
+
+/// Put the nodes `0..len` in an order where, for every edge `(before, after)`, `before` comes
+/// first. Nodes that are ready at the same time keep their numeric order.
+///
+/// Returns the nodes that make that impossible if there's a cycle.
+pub(crate) fn toposort(
+    len: usize,
+    edges: impl IntoIterator<Item = (usize, usize)>,
+) -> Result<Vec<usize>, Cycle> {
+    let mut successors = vec![Vec::new(); len];
+    let mut predecessors = vec![Vec::new(); len];
+    for (before, after) in edges {
+        successors[before].push(after);
+        predecessors[after].push(before);
+    }
+
+    let mut waiting_on: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+    let order = drain_ready(&successors, &mut waiting_on, |_| true);
+    if order.len() == len {
+        return Ok(order);
+    }
+
+    // Whatever's left either is on a cycle or waits on one. Doing the same thing backwards, from
+    // the nodes that nothing left waits on, peels off the ones that only wait.
+    let left = |node: usize| waiting_on[node] > 0;
+    let mut waited_on_by: Vec<usize> = successors
+        .iter()
+        .enumerate()
+        .map(|(node, after)| {
+            if left(node) {
+                after.iter().filter(|&&next| left(next)).count()
+            } else {
+                0
+            }
+        })
+        .collect();
+    let mut on_cycle: Vec<bool> = (0..len).map(left).collect();
+    for node in drain_ready(&predecessors, &mut waited_on_by, left) {
+        on_cycle[node] = false;
+    }
+
+    Err(Cycle {
+        nodes: (0..len).filter(|&node| on_cycle[node]).collect(),
+    })
+}
+
+/// Take every node in `within` whose count is zero, in order, and count down the nodes in `within`
+/// that they lead to, taking those as well once they reach zero.
+fn drain_ready(
+    leads_to: &[Vec<usize>],
+    counts: &mut [usize],
+    within: impl Fn(usize) -> bool,
+) -> Vec<usize> {
+    let mut ready: VecDeque<usize> = (0..counts.len())
+        .filter(|&node| within(node) && counts[node] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(counts.len());
+    while let Some(node) = ready.pop_front() {
+        order.push(node);
+        for &next in leads_to[node].iter().filter(|&&next| within(next)) {
+            counts[next] -= 1;
+            if counts[next] == 0 {
+                ready.push_back(next);
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_dependencies_first() {
+        // 3 waits on 1 and 2, which both wait on 0, and 4 waits on nothing.
+        let order = toposort(5, [(1, 3), (0, 1), (2, 3), (0, 2)]);
+        assert_eq!(order, Ok(vec![0, 4, 1, 2, 3]));
+        assert_eq!(toposort(0, []), Ok(vec![]));
+    }
+
+    #[test]
+    fn reports_only_the_cycle() {
+        // 1 -> 2 -> 3 -> 1 is a cycle, which 0 leads into and 4 waits on. 5 waits on itself.
+        let cycle = toposort(6, [(0, 1), (1, 2), (2, 3), (3, 1), (3, 4), (5, 5)]).unwrap_err();
+        assert_eq!(cycle.nodes(), [1, 2, 3, 5]);
+    }
+}
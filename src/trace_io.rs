@@ -0,0 +1,213 @@
+//! Keeping track of how much of its input a solver reads, for `--trace-io`.
+//!
+//! A parser that stops early, like one that only ever reads the first line, quietly ignores the
+//! rest of the input and can come up with a wrong answer without any error. [`trace`] wraps an
+//! input so that once the solver is done with it, whatever it didn't read gets looked at, and
+//! anything in there besides whitespace is pointed out.
+
+use std::{
+    fmt,
+    io::{self, BufRead, Read},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::challenge::ChallengeInput;
+
+/// The most of an unread line to show in a warning.
+const MAX_LINE_PREVIEW: usize = 40;
+
+/// Wrap `input` to keep track of how much of it gets read.
+///
+/// The [`IoTrace`] has a report once the wrapped input has been dropped, which
+/// [`Solver::solve`](crate::solver::Solver::solve) does as soon as the solver is done with it.
+pub fn trace(input: ChallengeInput) -> (ChallengeInput, IoTrace) {
+    let trace = IoTrace::default();
+    let traced = Traced {
+        inner: input,
+        trace: trace.clone(),
+    };
+    (Box::new(traced), trace)
+}
+
+/// A handle on what a [`trace`]d input has been through.
+#[derive(Debug, Clone, Default)]
+pub struct IoTrace(Arc<Mutex<Progress>>);
+
+#[derive(Debug, Default)]
+struct Progress {
+    read: u64,
+    /// Filled in once the input is dropped.
+    unread: Option<Unread>,
+}
+
+#[derive(Debug)]
+struct Unread {
+    bytes: u64,
+    /// Lines with something other than whitespace on them.
+    lines: usize,
+    first_line: Option<String>,
+}
+
+impl IoTrace {
+    /// How much of the input was read, or `None` if something is still holding on to it, like a
+    /// solver that timed out and is still running.
+    pub fn report(&self) -> Option<IoReport> {
+        let progress = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let unread = progress.unread.as_ref()?;
+        Some(IoReport {
+            read: progress.read,
+            unread: unread.bytes,
+            unread_lines: unread.lines,
+            first_unread_line: unread.first_line.clone(),
+        })
+    }
+}
+
+/// How much of an input a solver read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoReport {
+    /// How many bytes the solver read.
+    pub read: u64,
+    /// How many bytes were left over after the solver was done.
+    pub unread: u64,
+    /// How many of the left over lines had anything other than whitespace on them.
+    pub unread_lines: usize,
+    /// The first of those lines, cut short if it's long.
+    pub first_unread_line: Option<String>,
+}
+
+impl IoReport {
+    /// Whether the solver left anything besides whitespace unread, which usually means that its
+    /// parser stopped too early.
+    pub fn has_leftovers(&self) -> bool {
+        self.unread_lines > 0
+    }
+}
+
+impl fmt::Display for IoReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.read + self.unread;
+        write!(f, "Read {} of {total} bytes of input", self.read)?;
+        if let Some(line) = &self.first_unread_line {
+            write!(
+                f,
+                "\nWarning: {} line(s) were never read, starting with `{line}`. Parsers that stop \
+                 early can give wrong answers without any errors.",
+                self.unread_lines
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The input as handed to the solver.
+struct Traced<R: BufRead> {
+    inner: R,
+    trace: IoTrace,
+}
+
+impl<R: BufRead> Traced<R> {
+    fn count(&self, bytes: usize) {
+        let mut progress = self.trace.0.lock().unwrap_or_else(PoisonError::into_inner);
+        progress.read += bytes as u64;
+    }
+}
+
+impl<R: BufRead> Read for Traced<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Traced<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.count(amt);
+    }
+}
+
+impl<R: BufRead> Drop for Traced<R> {
+    fn drop(&mut self) {
+        // Anything that can't be read now couldn't have been read by the solver either, so it
+        // doesn't count as left over.
+        let mut rest = Vec::new();
+        let _ = self.inner.read_to_end(&mut rest);
+
+        let rest = String::from_utf8_lossy(&rest);
+        let mut lines = rest.lines().map(str::trim).filter(|line| !line.is_empty());
+        let first_line = lines
+            .next()
+            .map(|line| match line.char_indices().nth(MAX_LINE_PREVIEW) {
+                Some((end, _)) => format!("{}…", &line[..end]),
+                None => line.to_string(),
+            });
+
+        let mut progress = self.trace.0.lock().unwrap_or_else(PoisonError::into_inner);
+        progress.unread = Some(Unread {
+            bytes: rest.len() as u64,
+            lines: usize::from(first_line.is_some()) + lines.count(),
+            first_line,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        challenge::Subchallenge,
+        solver::{Solver, SolverConfig},
+    };
+
+    use super::*;
+
+    fn traced(input: &'static str) -> (ChallengeInput, IoTrace) {
+        trace(Box::new(Cursor::new(input)))
+    }
+
+    #[test]
+    fn reports_what_was_left_unread() {
+        let (mut input, trace) = traced("first\nsecond\n\n  third  \n");
+        let mut line = String::new();
+        input.read_line(&mut line).unwrap();
+        assert_eq!(trace.report(), None, "the input hasn't been dropped yet");
+
+        drop(input);
+        let report = trace.report().unwrap();
+        assert_eq!(report.read, 6);
+        assert_eq!(report.unread, 18);
+        assert_eq!(report.unread_lines, 2);
+        assert_eq!(report.first_unread_line.as_deref(), Some("second"));
+
+        // Trailing whitespace isn't worth a warning.
+        let (mut input, trace) = traced("all of it\n\n");
+        input.read_to_string(&mut String::new()).unwrap();
+        drop(input);
+        let report = trace.report().unwrap();
+        assert_eq!((report.read, report.unread), (11, 0));
+        assert!(!report.has_leftovers());
+    }
+
+    #[test]
+    fn catches_solvers_that_stop_early() {
+        // Day 17 only ever reads the first line, since that's all the jet pattern should be.
+        let jets = crate::samples::input(17).unwrap().trim();
+        let input = format!("{jets}\n{jets}\n");
+        let (input, trace) = trace(Box::new(Cursor::new(input)));
+
+        Solver::new()
+            .solve(17, Subchallenge::A, input, &SolverConfig::default())
+            .unwrap();
+        let report = trace.report().unwrap();
+        assert!(report.has_leftovers());
+        assert_eq!(report.unread_lines, 1);
+        assert!(report.to_string().contains("1 line(s) were never read"));
+    }
+}
@@ -0,0 +1,482 @@
+//! Shared plumbing for the terminal UI visualizations.
+//!
+//! Every TUI solver used to set up the terminal, run an event loop, and restore the terminal by
+//! hand. Implement [`TuiApp`] and hand the app to [`run`] instead.
+
+use std::{
+    io::{self, IsTerminal},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        MouseEvent,
+    },
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::Rect,
+    style::{Color, Style},
+    text::{Span, Spans},
+    Frame, Terminal,
+};
+
+use crate::{
+    clock::SharedClock,
+    error::{Context, SolverError},
+    raster::Raster,
+    simulation::{Pacing, StepResult},
+};
+
+/// What the event loop should do after an app has handled an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Control {
+    /// Keep running.
+    Continue,
+    /// Exit the event loop and restore the terminal.
+    Quit,
+}
+
+/// An interactive terminal app that can be driven by [`run`].
+pub(crate) trait TuiApp {
+    /// Render the app UI to a tui frame.
+    fn ui<B: Backend>(&self, f: &mut Frame<B>);
+
+    /// Update the app's simulation. Called once per tick.
+    fn on_tick(&mut self);
+
+    /// Whether the app's simulation has finished, so that [`run`] can exit by itself when
+    /// `pacing.auto_exit` is set. Apps that don't step through anything never finish.
+    fn status(&self) -> StepResult {
+        StepResult::Continue
+    }
+
+    /// Handle a key event.
+    ///
+    /// Pressing `q` always quits and `t` always switches turbo mode on and off, so apps never see
+    /// them.
+    fn on_key(&mut self, _key: KeyEvent) -> Control {
+        Control::Continue
+    }
+
+    /// Handle a mouse event.
+    fn on_mouse(&mut self, _ev: MouseEvent) {}
+}
+
+/// The key hint for turbo mode, for apps that step through a simulation.
+pub(crate) const TURBO_HELP: &str = "[t] turbo";
+
+/// Take over the terminal, run `app` until the user quits, and then restore the terminal.
+///
+/// The app is ticked `default_tick_hz` times a second, unless `pacing` says otherwise, and
+/// stepped `pacing.steps_per_tick` times on each tick. In turbo mode, it's instead stepped as fast
+/// as it can be, and only drawn every `pacing.turbo_steps` steps. With `pacing.auto_exit` set,
+/// the app also quits by itself once its simulation has been finished for that long.
+///
+/// The terminal is restored even if the app returns an error. Returns
+/// [`SolverError::Unsupported`] if `stdout` isn't a terminal, since there would be nothing to draw
+/// on.
+pub(crate) fn run<A: TuiApp>(
+    app: &mut A,
+    default_tick_hz: f64,
+    pacing: Pacing,
+) -> Result<(), SolverError> {
+    if !io::stdout().is_terminal() {
+        return Err(SolverError::Unsupported(
+            "The terminal UI can only run when stdout is a terminal".to_string(),
+        ));
+    }
+
+    // setup terminal
+    enable_raw_mode().context("Could not initialize terminal UI")?;
+    let mut stdout = io::stdout();
+    crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
+        .context("Could not initialize terminal UI")?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).context("Could not initialize terminal UI")?;
+
+    // Run the app
+    let res = event_loop(app, &mut terminal, default_tick_hz, pacing);
+
+    // Restore terminal
+    disable_raw_mode().context("Could not deinitialize terminal UI")?;
+    crossterm::execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )
+    .context("Could not deinitialize terminal UI")?;
+    terminal
+        .show_cursor()
+        .context("Could not deinitialize terminal UI")?;
+
+    // Remember to unwrap the result of running the app AFTER restoring the terminal
+    res
+}
+
+fn event_loop<A: TuiApp, B: Backend>(
+    app: &mut A,
+    terminal: &mut Terminal<B>,
+    default_tick_hz: f64,
+    pacing: Pacing,
+) -> Result<(), SolverError> {
+    let mut ticker = Ticker::new(SharedClock::default(), default_tick_hz, pacing);
+    loop {
+        terminal
+            .draw(|f| app.ui(f))
+            .context("Error while drawing UI frame.")?;
+
+        if event::poll(ticker.timeout()).context("Could not poll terminal for new I/O events")? {
+            let control = match event::read().context("Could not read terminal I/O event")? {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    ..
+                }) => Control::Quit,
+
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('t'),
+                    kind,
+                    ..
+                }) => {
+                    if kind == KeyEventKind::Press {
+                        ticker.turbo = !ticker.turbo;
+                    }
+                    Control::Continue
+                }
+
+                Event::Key(key) => app.on_key(key),
+
+                Event::Mouse(ev) => {
+                    app.on_mouse(ev);
+                    Control::Continue
+                }
+
+                // Drawing picks up the new size by itself, but whatever was on the screen before
+                // is left in the wrong place.
+                Event::Resize(..) => {
+                    terminal
+                        .autoresize()
+                        .context("Could not resize terminal UI")?;
+                    terminal.clear().context("Could not resize terminal UI")?;
+                    Control::Continue
+                }
+
+                _ => Control::Continue,
+            };
+
+            if control == Control::Quit {
+                return Ok(());
+            }
+        }
+
+        ticker.tick(app);
+        if ticker.should_exit(app) {
+            return Ok(());
+        }
+    }
+}
+
+/// Decides when [`event_loop`] ticks the app, and when it quits by itself.
+///
+/// Only looks at the time through its clock, so tests can run through ticks and linger timeouts
+/// with a [`FakeClock`](crate::clock::FakeClock) instead of waiting for them.
+struct Ticker {
+    clock: SharedClock,
+    tick_rate: Duration,
+    pacing: Pacing,
+    /// Whether to step as fast as possible instead of once a tick.
+    turbo: bool,
+    last_tick: Instant,
+    /// When the app's simulation was first seen to be finished, for `pacing.auto_exit`.
+    finished_at: Option<Instant>,
+}
+
+impl Ticker {
+    fn new(clock: SharedClock, default_tick_hz: f64, pacing: Pacing) -> Self {
+        Self {
+            tick_rate: pacing.tick_rate(default_tick_hz),
+            pacing,
+            turbo: false,
+            last_tick: clock.now(),
+            finished_at: None,
+            clock,
+        }
+    }
+
+    /// How long to wait for input before the next tick is due.
+    fn timeout(&self) -> Duration {
+        if self.turbo {
+            Duration::ZERO
+        } else {
+            self.tick_rate
+                .saturating_sub(self.clock.since(self.last_tick))
+        }
+    }
+
+    /// Step `app` if a tick is due, or as many times as turbo mode takes between frames.
+    fn tick<A: TuiApp>(&mut self, app: &mut A) {
+        let steps = if self.turbo {
+            self.pacing.turbo_steps
+        } else if self.clock.since(self.last_tick) >= self.tick_rate {
+            self.pacing.steps_per_tick
+        } else {
+            return;
+        };
+        for _ in 0..steps.get() {
+            app.on_tick();
+        }
+        self.last_tick = self.clock.now();
+    }
+
+    /// Whether `app`'s simulation has been finished for as long as `pacing.auto_exit` says to
+    /// keep showing it.
+    fn should_exit<A: TuiApp>(&mut self, app: &A) -> bool {
+        let Some(linger) = self.pacing.auto_exit else {
+            return false;
+        };
+        // Resetting the simulation starts the wait over.
+        self.finished_at = match app.status() {
+            StepResult::Continue => None,
+            StepResult::Done => Some(self.finished_at.unwrap_or_else(|| self.clock.now())),
+        };
+        self.finished_at
+            .is_some_and(|at| self.clock.since(at) >= linger)
+    }
+}
+
+/// How much empty space to leave around a simulation, as a fraction of its size.
+const VIEWPORT_PADDING: f64 = 0.05;
+/// The least empty space to leave around a simulation, in its own units.
+const VIEWPORT_MIN_PADDING: f64 = 2.0;
+/// How far each press of a pan key moves the view, as a fraction of what's visible.
+const VIEWPORT_PAN_STEP: f64 = 0.1;
+/// How much each press of a zoom key zooms in or out.
+const VIEWPORT_ZOOM_STEP: f64 = 1.25;
+
+/// The part of a simulation that a canvas shows.
+///
+/// By default, the whole simulation is fit into the canvas, however big the terminal is. The
+/// user can then pan around with `w`, `a`, `s` and `d`, zoom with `+` and `-`, and go back to
+/// fitting everything with `0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Viewport {
+    /// How far the view has been moved from the middle of the simulation, as a fraction of the
+    /// size of the fitted view.
+    pan: (f64, f64),
+    /// How many times bigger things are than when fitted.
+    zoom: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            pan: (0.0, 0.0),
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    /// The key hint to show alongside a canvas.
+    pub(crate) const HELP: &'static str = "[wasd] pan  [+-] zoom  [0] fit";
+
+    /// The `[x_bounds, y_bounds]` for a canvas drawn in `area`, given the smallest and largest
+    /// coordinates in the simulation as `[[min_x, max_x], [min_y, max_y]]`.
+    ///
+    /// One unit comes out the same size across as it is up and down, taking into account that
+    /// terminal characters are about twice as tall as they are wide.
+    pub(crate) fn bounds(&self, extents: [[f64; 2]; 2], area: Rect) -> [[f64; 2]; 2] {
+        let [[min_x, max_x], [min_y, max_y]] = extents;
+        let padding =
+            |min: f64, max: f64| ((max - min) * VIEWPORT_PADDING).max(VIEWPORT_MIN_PADDING);
+        let mut width = max_x - min_x + 2.0 * padding(min_x, max_x);
+        let mut height = max_y - min_y + 2.0 * padding(min_y, max_y);
+
+        let aspect = f64::from(area.width.max(1)) / (2.0 * f64::from(area.height.max(1)));
+        if width / height < aspect {
+            width = height * aspect;
+        } else {
+            height = width / aspect;
+        }
+
+        let center_x = (min_x + max_x) / 2.0 + self.pan.0 * width;
+        let center_y = (min_y + max_y) / 2.0 + self.pan.1 * height;
+        let (half_width, half_height) = (width / self.zoom / 2.0, height / self.zoom / 2.0);
+        [
+            [center_x - half_width, center_x + half_width],
+            [center_y - half_height, center_y + half_height],
+        ]
+    }
+
+    /// Pan or zoom if `key` is one of the viewport's keys. Returns whether it was.
+    pub(crate) fn on_key(&mut self, key: KeyEvent) -> bool {
+        if !matches!(key.kind, KeyEventKind::Press | KeyEventKind::Repeat) {
+            return false;
+        }
+
+        let step = VIEWPORT_PAN_STEP / self.zoom;
+        match key.code {
+            KeyCode::Char('w') => self.pan.1 += step,
+            KeyCode::Char('s') => self.pan.1 -= step,
+            KeyCode::Char('a') => self.pan.0 -= step,
+            KeyCode::Char('d') => self.pan.0 += step,
+            KeyCode::Char('+' | '=') => self.zoom = (self.zoom * VIEWPORT_ZOOM_STEP).min(256.0),
+            KeyCode::Char('-') => self.zoom = (self.zoom / VIEWPORT_ZOOM_STEP).max(0.25),
+            KeyCode::Char('0') => *self = Self::default(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Draw as much of `raster` as fits in `area`, starting from its top-left corner, with two pixels
+/// stacked in each character.
+pub(crate) fn raster_lines(raster: &Raster, area: Rect) -> Vec<Spans<'static>> {
+    let color = |x, y| {
+        raster
+            .get(x, y)
+            .map(|owo_colors::Rgb(r, g, b)| Color::Rgb(r, g, b))
+    };
+    let width = raster.width().min(area.width as usize);
+    let height = raster.height().min(2 * area.height as usize);
+
+    (0..height)
+        .step_by(2)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let style = Style::default().fg(color(x, y).unwrap_or(Color::Reset));
+                    let style = style.bg(color(x, y + 1).unwrap_or(Color::Reset));
+                    Span::styled("▀", style)
+                })
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    fn press(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    /// Counts its ticks, and finishes after `done_after` of them.
+    struct Counter {
+        ticks: usize,
+        done_after: usize,
+    }
+
+    impl TuiApp for Counter {
+        fn ui<B: Backend>(&self, _f: &mut Frame<B>) {}
+
+        fn on_tick(&mut self) {
+            self.ticks += 1;
+        }
+
+        fn status(&self) -> StepResult {
+            if self.ticks >= self.done_after {
+                StepResult::Done
+            } else {
+                StepResult::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn ticks_on_time_and_lingers_before_exiting() {
+        let clock = crate::clock::FakeClock::new();
+        let pacing = Pacing {
+            steps_per_tick: NonZeroUsize::new(2).unwrap(),
+            turbo_steps: NonZeroUsize::new(50).unwrap(),
+            auto_exit: Some(Duration::from_secs(3)),
+            ..Pacing::default()
+        };
+        let mut ticker = Ticker::new(SharedClock::new(clock.clone()), 10.0, pacing);
+        let mut app = Counter {
+            ticks: 0,
+            done_after: 60,
+        };
+
+        // Nothing happens until a tick is due.
+        clock.advance(Duration::from_millis(40));
+        assert_eq!(ticker.timeout(), Duration::from_millis(60));
+        ticker.tick(&mut app);
+        assert_eq!(app.ticks, 0);
+
+        clock.advance(Duration::from_millis(60));
+        assert_eq!(ticker.timeout(), Duration::ZERO);
+        ticker.tick(&mut app);
+        assert_eq!(app.ticks, 2);
+        assert_eq!(ticker.timeout(), Duration::from_millis(100));
+
+        ticker.turbo = true;
+        assert_eq!(ticker.timeout(), Duration::ZERO);
+        ticker.tick(&mut app);
+        assert_eq!(app.ticks, 52);
+        ticker.tick(&mut app);
+        assert_eq!(app.ticks, 102);
+
+        // The finished simulation stays up for the linger time, which starts over if it's reset.
+        assert!(!ticker.should_exit(&app));
+        clock.advance(Duration::from_secs(2));
+        assert!(!ticker.should_exit(&app));
+        app.ticks = 0;
+        assert!(!ticker.should_exit(&app));
+        app.ticks = 60;
+        assert!(!ticker.should_exit(&app));
+        clock.advance(Duration::from_secs(2));
+        assert!(!ticker.should_exit(&app));
+        clock.advance(Duration::from_secs(1));
+        assert!(ticker.should_exit(&app));
+    }
+
+    fn assert_bounds(got: [[f64; 2]; 2], expected: [[f64; 2]; 2]) {
+        let close = got
+            .iter()
+            .flatten()
+            .zip(expected.iter().flatten())
+            .all(|(got, expected)| (got - expected).abs() < 1e-9);
+        assert!(close, "expected {expected:?}, got {got:?}");
+    }
+
+    #[test]
+    fn viewport_fits_the_terminal() {
+        let mut viewport = Viewport::default();
+        let extents = [[-100.0, 100.0], [0.0, 10.0]];
+
+        // A wide simulation in a square-ish terminal gets extra room above and below, so that
+        // its units stay square.
+        let square = Rect::new(0, 0, 80, 40);
+        assert_bounds(
+            viewport.bounds(extents, square),
+            [[-110.0, 110.0], [-105.0, 115.0]],
+        );
+
+        // After resizing to something even wider, the extra room goes on the sides instead.
+        let wide = Rect::new(0, 0, 400, 10);
+        assert_bounds(
+            viewport.bounds(extents, wide),
+            [[-140.0, 140.0], [-2.0, 12.0]],
+        );
+
+        assert!(viewport.on_key(press('d')));
+        assert!(viewport.on_key(press('+')));
+        assert!(!viewport.on_key(press('q')));
+        assert_bounds(
+            viewport.bounds(extents, wide),
+            [[28.0 - 112.0, 28.0 + 112.0], [5.0 - 5.6, 5.0 + 5.6]],
+        );
+
+        assert!(viewport.on_key(press('0')));
+        assert_eq!(viewport, Viewport::default());
+    }
+}
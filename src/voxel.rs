@@ -0,0 +1,589 @@
+//! Sparse or dense worlds of 3D voxels, with flood fills over them.
+//!
+//! A [`VoxelWorld`] keeps track of the smallest [`Bounds`] around everything in it, so solvers
+//! don't have to follow the extremes along while parsing. Small, crowded worlds are best stored
+//! densely in a flat array, while big, mostly empty ones are best stored sparsely in a map;
+//! [`VoxelWorld::from_cells`] picks whichever suits the cells it's given.
+//!
+//! Built for day 18's lava droplet, and meant for day 22's cube as well.
+
+// Groundwork for day 22: only the tests switch a world's storage so far.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+
+use itertools::{Either, Itertools};
+use nalgebra_glm::IVec3;
+
+use crate::{dsu::DisjointSets, grid::Grid};
+
+/// The offsets to the six voxels that share a face with a voxel.
+#[rustfmt::skip]
+pub(crate) const FACE_NEIGHBORS: [IVec3; 6] = [
+    IVec3::new(1, 0, 0), IVec3::new(-1, 0, 0),
+    IVec3::new(0, 1, 0), IVec3::new(0, -1, 0),
+    IVec3::new(0, 0, 1), IVec3::new(0, 0, -1),
+];
+
+/// The six voxels that share a face with `position`.
+pub(crate) fn neighbors(position: IVec3) -> impl Iterator<Item = IVec3> {
+    FACE_NEIGHBORS
+        .into_iter()
+        .map(move |delta| position + delta)
+}
+
+/// One side of a voxel, facing towards `voxel + normal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Face {
+    pub(crate) voxel: IVec3,
+    /// One of [`FACE_NEIGHBORS`].
+    pub(crate) normal: IVec3,
+}
+
+impl Face {
+    /// The face's four corners, counter-clockwise as seen from the side it faces.
+    ///
+    /// A voxel at `(x, y, z)` spans from `(x, y, z)` to `(x + 1, y + 1, z + 1)`.
+    pub(crate) fn corners(&self) -> [IVec3; 4] {
+        let axis = self.normal.iamax();
+        let (across, up) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        let mut base = self.voxel;
+        if self.normal[axis] > 0 {
+            base[axis] += 1;
+        }
+        let mut step_across = IVec3::zeros();
+        step_across[across] = 1;
+        let mut step_up = IVec3::zeros();
+        step_up[up] = 1;
+
+        let corners = [
+            base,
+            base + step_across,
+            base + step_across + step_up,
+            base + step_up,
+        ];
+        if self.normal[axis] > 0 {
+            corners
+        } else {
+            let [a, b, c, d] = corners;
+            [a, d, c, b]
+        }
+    }
+}
+
+/// An axis-aligned box of voxels, including both corners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Bounds {
+    pub(crate) min: IVec3,
+    pub(crate) max: IVec3,
+}
+
+impl Bounds {
+    /// A box holding only `position`.
+    pub(crate) const fn point(position: IVec3) -> Self {
+        Self {
+            min: position,
+            max: position,
+        }
+    }
+
+    /// The smallest box holding both this box and `position`.
+    pub(crate) fn including(self, position: IVec3) -> Self {
+        Self {
+            min: self.min.inf(&position),
+            max: self.max.sup(&position),
+        }
+    }
+
+    /// The box pushed out by `by` voxels in every direction.
+    pub(crate) fn grown(self, by: i32) -> Self {
+        Self {
+            min: self.min.add_scalar(-by),
+            max: self.max.add_scalar(by),
+        }
+    }
+
+    pub(crate) fn contains(&self, position: IVec3) -> bool {
+        (0..3).all(|axis| (self.min[axis]..=self.max[axis]).contains(&position[axis]))
+    }
+
+    /// How many voxels the box spans along each axis.
+    pub(crate) fn size(&self) -> IVec3 {
+        self.max - self.min + IVec3::repeat(1)
+    }
+
+    /// How many voxels the box holds.
+    pub(crate) fn volume(&self) -> usize {
+        self.size().iter().map(|&len| len as usize).product()
+    }
+
+    /// Every voxel in the box, with `x` changing fastest and `z` slowest.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = IVec3> {
+        let Self { min, max } = *self;
+        (min.z..=max.z)
+            .cartesian_product(min.y..=max.y)
+            .cartesian_product(min.x..=max.x)
+            .map(|((z, y), x)| IVec3::new(x, y, z))
+    }
+
+    /// Where `position` goes in a flat array laid out in [`Bounds::iter`] order.
+    fn index(&self, position: IVec3) -> Option<usize> {
+        if !self.contains(position) {
+            return None;
+        }
+        let offset = position - self.min;
+        let size = self.size();
+        Some((offset.x + size.x * (offset.y + size.y * offset.z)) as usize)
+    }
+}
+
+/// One of the three axes, for [`VoxelWorld::slice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    const fn index(self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+        }
+    }
+
+    /// The two other axes, which become a slice's `x` and `y` in that order.
+    const fn across(self) -> (usize, usize) {
+        match self {
+            Self::X => (1, 2),
+            Self::Y => (0, 2),
+            Self::Z => (0, 1),
+        }
+    }
+}
+
+/// How a [`VoxelWorld`] stores its voxels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Storage {
+    /// In a map, only taking up room for the voxels that are there.
+    Sparse,
+    /// In a flat array covering the whole bounding box, which is quicker to look things up in.
+    Dense,
+}
+
+impl Storage {
+    /// The fewest voxels out of every one in the bounding box that are worth storing densely.
+    const DENSE_OCCUPANCY: f64 = 0.05;
+
+    /// The best way to store `count` voxels spread across `bounds`.
+    pub(crate) fn for_occupancy(count: usize, bounds: Bounds) -> Self {
+        if count as f64 >= bounds.volume() as f64 * Self::DENSE_OCCUPANCY {
+            Self::Dense
+        } else {
+            Self::Sparse
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Backing<T> {
+    Sparse(HashMap<IVec3, T>),
+    /// Covers `region`, which always holds the world's bounds, but might not have been allocated
+    /// yet.
+    Dense {
+        region: Option<Bounds>,
+        cells: Vec<Option<T>>,
+    },
+}
+
+/// A 3D world of voxels holding `T`s, which can be stored sparsely or densely.
+#[derive(Debug, Clone)]
+pub(crate) struct VoxelWorld<T> {
+    backing: Backing<T>,
+    bounds: Option<Bounds>,
+    len: usize,
+}
+
+impl<T> VoxelWorld<T> {
+    /// An empty world, stored with `storage`.
+    pub(crate) fn new(storage: Storage) -> Self {
+        let backing = match storage {
+            Storage::Sparse => Backing::Sparse(HashMap::new()),
+            Storage::Dense => Backing::Dense {
+                region: None,
+                cells: Vec::new(),
+            },
+        };
+
+        Self {
+            backing,
+            bounds: None,
+            len: 0,
+        }
+    }
+
+    /// A world holding `cells`, stored however suits them best.
+    ///
+    /// If a position comes up more than once, the last value for it wins.
+    pub(crate) fn from_cells(cells: impl IntoIterator<Item = (IVec3, T)>) -> Self {
+        let cells = cells.into_iter().collect::<Vec<_>>();
+        let Some(bounds) = cells
+            .iter()
+            .map(|&(position, _)| Bounds::point(position))
+            .reduce(|bounds, point| bounds.including(point.min))
+        else {
+            return Self::new(Storage::Sparse);
+        };
+
+        let mut world = Self::new(Storage::for_occupancy(cells.len(), bounds));
+        if let Backing::Dense {
+            region,
+            cells: dense,
+        } = &mut world.backing
+        {
+            // Allocate everything at once, instead of growing the array for every new extreme.
+            *region = Some(bounds);
+            dense.resize_with(bounds.volume(), || None);
+        }
+        for (position, value) in cells {
+            world.insert(position, value);
+        }
+        world
+    }
+
+    pub(crate) const fn storage(&self) -> Storage {
+        match self.backing {
+            Backing::Sparse(_) => Storage::Sparse,
+            Backing::Dense { .. } => Storage::Dense,
+        }
+    }
+
+    /// The same world, stored with `storage` instead.
+    pub(crate) fn with_storage(self, storage: Storage) -> Self {
+        if storage == self.storage() {
+            return self;
+        }
+
+        let mut world = Self::new(storage);
+        for (position, value) in self.into_cells() {
+            world.insert(position, value);
+        }
+        world
+    }
+
+    pub(crate) const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The smallest box around every voxel in the world, or `None` if the world is empty.
+    pub(crate) const fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    pub(crate) fn get(&self, position: IVec3) -> Option<&T> {
+        match &self.backing {
+            Backing::Sparse(map) => map.get(&position),
+            Backing::Dense { region, cells } => {
+                let index = region.as_ref()?.index(position)?;
+                cells[index].as_ref()
+            }
+        }
+    }
+
+    /// Put `value` at `position`, returning whatever was there before.
+    ///
+    /// A dense world has to be copied into a bigger array if `position` is outside of its
+    /// bounds, so it's quickest to build those with [`VoxelWorld::from_cells`].
+    pub(crate) fn insert(&mut self, position: IVec3, value: T) -> Option<T> {
+        self.bounds = Some(match self.bounds {
+            Some(bounds) => bounds.including(position),
+            None => Bounds::point(position),
+        });
+
+        let previous = match &mut self.backing {
+            Backing::Sparse(map) => map.insert(position, value),
+            Backing::Dense { region, cells } => {
+                let grown = match *region {
+                    Some(old) if old.contains(position) => old,
+                    Some(old) => old.including(position),
+                    None => Bounds::point(position),
+                };
+                if Some(grown) != *region {
+                    let mut moved = Vec::new();
+                    moved.resize_with(grown.volume(), || None);
+                    if let Some(old) = *region {
+                        for (from, cell) in old.iter().zip(cells.drain(..)) {
+                            moved[grown.index(from).unwrap()] = cell;
+                        }
+                    }
+                    *region = Some(grown);
+                    *cells = moved;
+                }
+                cells[grown.index(position).unwrap()].replace(value)
+            }
+        };
+
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Every voxel in the world, in no particular order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (IVec3, &T)> {
+        match &self.backing {
+            Backing::Sparse(map) => {
+                Either::Left(map.iter().map(|(&position, value)| (position, value)))
+            }
+            Backing::Dense { region, cells } => Either::Right(
+                region
+                    .iter()
+                    .flat_map(Bounds::iter)
+                    .zip(cells)
+                    .filter_map(|(position, cell)| Some((position, cell.as_ref()?))),
+            ),
+        }
+    }
+
+    fn into_cells(self) -> impl Iterator<Item = (IVec3, T)> {
+        match self.backing {
+            Backing::Sparse(map) => Either::Left(map.into_iter()),
+            Backing::Dense { region, cells } => Either::Right(
+                region
+                    .into_iter()
+                    .flat_map(|region| region.iter())
+                    .zip(cells)
+                    .filter_map(|(position, cell)| Some((position, cell?))),
+            ),
+        }
+    }
+
+    /// The six voxels that share a face with `position`, along with what's in them.
+    pub(crate) fn neighbors(&self, position: IVec3) -> impl Iterator<Item = (IVec3, Option<&T>)> {
+        neighbors(position).map(|neighbor| (neighbor, self.get(neighbor)))
+    }
+
+    /// Every face of a voxel in the world that touches one of the empty `cells`, facing towards
+    /// that cell.
+    pub(crate) fn faces_touching<'a>(
+        &'a self,
+        cells: &'a HashSet<IVec3>,
+    ) -> impl Iterator<Item = Face> + 'a {
+        cells.iter().flat_map(move |&cell| {
+            self.neighbors(cell)
+                .filter(|(_, voxel)| voxel.is_some())
+                .map(move |(voxel, _)| Face {
+                    voxel,
+                    normal: cell - voxel,
+                })
+        })
+    }
+
+    /// Every voxel that can be reached from `start` by stepping between neighbors, without
+    /// leaving `region` or stepping into a voxel that isn't `passable`.
+    ///
+    /// Empty voxels are passed to `passable` as `None`. The fill is empty if `start` itself
+    /// can't be passed through.
+    pub(crate) fn flood_fill(
+        &self,
+        start: IVec3,
+        region: Bounds,
+        passable: impl Fn(Option<&T>) -> bool,
+    ) -> HashSet<IVec3> {
+        let mut filled = HashSet::new();
+        if !region.contains(start) || !passable(self.get(start)) {
+            return filled;
+        }
+
+        filled.insert(start);
+        let mut stack = vec![start];
+        while let Some(position) = stack.pop() {
+            for (neighbor, voxel) in self.neighbors(position) {
+                if region.contains(neighbor) && passable(voxel) && filled.insert(neighbor) {
+                    stack.push(neighbor);
+                }
+            }
+        }
+        filled
+    }
+
+    /// Split every `passable` voxel in `region` into groups that are connected to each other,
+    /// as with [`VoxelWorld::flood_fill`].
+    ///
+    /// The groups are ordered by where their first voxel comes up in [`Bounds::iter`], so the
+    /// group holding `region.min` (if any) always comes first.
+    pub(crate) fn components(
+        &self,
+        region: Bounds,
+        passable: impl Fn(Option<&T>) -> bool,
+    ) -> Vec<HashSet<IVec3>> {
+        let positions = region.iter().collect::<Vec<_>>();
+        let open = positions
+            .iter()
+            .map(|&position| passable(self.get(position)))
+            .collect::<Vec<_>>();
+
+        // Every voxel only needs joining to its neighbors further along each axis, since the ones
+        // behind it have already been joined to it.
+        let mut sets = DisjointSets::new(positions.len());
+        for (index, &position) in positions.iter().enumerate() {
+            if !open[index] {
+                continue;
+            }
+            for offset in [IVec3::x(), IVec3::y(), IVec3::z()] {
+                match region.index(position + offset) {
+                    Some(neighbor) if open[neighbor] => {
+                        sets.union(index, neighbor);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        sets.components()
+            .filter(|component| open[component[0]])
+            .map(|component| component.into_iter().map(|i| positions[i]).collect())
+            .collect()
+    }
+
+    /// A cross-section of the world where the coordinate along `axis` is `level`, for drawing.
+    ///
+    /// The slice covers the world's bounds, with the cell at `(0, 0)` being the corner closest
+    /// to [`Bounds::min`]. Slicing along [`Axis::Z`] keeps `x` and `y` as they are, while slicing
+    /// along the others uses the remaining two axes in order. Returns `None` if the world is
+    /// empty.
+    pub(crate) fn slice(&self, axis: Axis, level: i32) -> Option<Grid<Option<T>>>
+    where
+        T: Clone,
+    {
+        let bounds = self.bounds?;
+        let (across, down) = axis.across();
+        let size = bounds.size();
+        let mut grid = Grid::new(size[across] as usize, size[down] as usize);
+
+        for (x, y) in (0..grid.width).cartesian_product(0..grid.height) {
+            let mut position = bounds.min;
+            position[axis.index()] = level;
+            position[across] += x as i32;
+            position[down] += y as i32;
+            *grid.cell_mut((x, y).into()).unwrap() = self.get(position).cloned();
+        }
+        Some(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells() -> Vec<(IVec3, char)> {
+        vec![
+            (IVec3::new(0, 0, 0), 'a'),
+            (IVec3::new(2, -1, 3), 'b'),
+            (IVec3::new(-4, 5, 1), 'c'),
+        ]
+    }
+
+    #[test]
+    fn storages_agree() {
+        for storage in [Storage::Sparse, Storage::Dense] {
+            let mut world = VoxelWorld::new(storage);
+            for (position, value) in cells() {
+                assert_eq!(world.insert(position, value), None);
+            }
+            assert_eq!(world.insert(IVec3::new(0, 0, 0), 'd'), Some('a'));
+
+            assert_eq!(world.len(), 3);
+            assert_eq!(world.get(IVec3::new(2, -1, 3)), Some(&'b'));
+            assert_eq!(world.get(IVec3::new(0, 0, 0)), Some(&'d'));
+            assert_eq!(world.get(IVec3::new(1, 1, 1)), None);
+            assert_eq!(
+                world.bounds(),
+                Some(Bounds {
+                    min: IVec3::new(-4, -1, 0),
+                    max: IVec3::new(2, 5, 3),
+                })
+            );
+
+            let converted = world.clone().with_storage(Storage::Dense);
+            let mut iterated = converted.iter().map(|(p, &v)| (p, v)).collect::<Vec<_>>();
+            let mut expected = world.iter().map(|(p, &v)| (p, v)).collect::<Vec<_>>();
+            iterated.sort_by_key(|(p, _)| (p.x, p.y, p.z));
+            expected.sort_by_key(|(p, _)| (p.x, p.y, p.z));
+            assert_eq!(iterated, expected);
+        }
+
+        assert_eq!(VoxelWorld::from_cells(cells()).storage(), Storage::Sparse);
+        let cube = Bounds::point(IVec3::zeros()).grown(1);
+        assert_eq!(
+            VoxelWorld::from_cells(cube.iter().map(|p| (p, ()))).storage(),
+            Storage::Dense
+        );
+    }
+
+    #[test]
+    fn fills_around_a_hollow_cube() {
+        // A 3x3x3 shell with an empty middle.
+        let shell = Bounds::point(IVec3::zeros()).grown(1);
+        let world = VoxelWorld::from_cells(
+            shell
+                .iter()
+                .filter(|&p| p != IVec3::zeros())
+                .map(|p| (p, ())),
+        );
+
+        let region = shell.grown(1);
+        let outside = world.flood_fill(region.min, region, |voxel| voxel.is_none());
+        assert_eq!(outside.len(), region.volume() - shell.volume());
+        assert!(world
+            .flood_fill(IVec3::zeros(), shell, |v| v.is_some())
+            .is_empty());
+
+        let components = world.components(region, |voxel| voxel.is_none());
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0], outside);
+        assert_eq!(components[1], HashSet::from([IVec3::zeros()]));
+    }
+
+    #[test]
+    fn faces_wind_outwards() {
+        for normal in FACE_NEIGHBORS {
+            let face = Face {
+                voxel: IVec3::new(3, -2, 5),
+                normal,
+            };
+            let [a, b, c, _] = face.corners();
+            assert_eq!((b - a).cross(&(c - a)), normal, "{face:?}");
+            assert_eq!(
+                face.corners().iter().sum::<IVec3>(),
+                face.voxel * 4 + IVec3::repeat(2) + normal * 2,
+                "{face:?} should be on the side of its voxel it faces"
+            );
+        }
+    }
+
+    #[test]
+    fn slices_along_each_axis() {
+        let world = VoxelWorld::from_cells(cells());
+
+        let flat = world.slice(Axis::Z, 3).unwrap();
+        assert_eq!((flat.width, flat.height), (7, 7));
+        assert_eq!(flat.cell((6, 0).into()), Some(&Some('b')));
+
+        let side = world.slice(Axis::X, -4).unwrap();
+        assert_eq!((side.width, side.height), (7, 4));
+        assert_eq!(side.cell((6, 1).into()), Some(&Some('c')));
+        assert_eq!(side.data.iter().flatten().count(), 1);
+
+        assert!(VoxelWorld::<()>::new(Storage::Dense)
+            .slice(Axis::Y, 0)
+            .is_none());
+    }
+}